@@ -0,0 +1,18 @@
+// the compiler as a library, for the `wu` binary and the tests
+extern crate colored;
+extern crate dirs;
+extern crate fs_extra;
+extern crate git2;
+extern crate rustyline;
+extern crate toml;
+#[macro_use]
+extern crate wu_syntax;
+
+pub use wu_syntax::{log, response};
+
+pub mod wu;
+
+pub use wu::format::{format_range, format_source};
+
+#[cfg(feature = "mlua")]
+pub use wu::embed::load_into_lua;
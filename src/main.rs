@@ -1,32 +1,35 @@
 #![deny(mutable_borrow_reservation_conflict)]
 extern crate colored;
-extern crate dirs;
-extern crate fs_extra;
-extern crate git2;
-extern crate rustyline;
-extern crate toml;
 
-use self::colored::Colorize;
+use ::wu::wu;
 
-mod wu;
-
-use self::wu::compiler::*;
-use self::wu::error::*;
+use self::wu::api;
+use self::wu::build::{self, Build, Failure};
+use self::wu::codemods;
+use self::wu::daemon;
+use self::wu::error::ColorChoice;
+use self::wu::fix;
+use self::wu::fmt;
 use self::wu::handler;
-use self::wu::lexer::*;
-use self::wu::parser::*;
-use self::wu::source::*;
-use self::wu::visitor::*;
-
-use std::fs;
-use std::fs::metadata;
-use std::fs::File;
+use self::wu::highlight;
+use self::wu::ice;
+use self::wu::lint;
+use self::wu::lsp;
+use self::wu::parser::STACK;
+use self::wu::profile;
+use self::wu::query;
+use self::wu::references;
+use self::wu::repl;
+use self::wu::run;
+use self::wu::tags;
+use self::wu::test;
+use self::wu::triage;
+use self::wu::wumap;
 
 use std::env;
-
-use std::io::prelude::*;
-use std::path::Path;
-use std::time::Instant;
+use std::panic;
+use std::process;
+use std::thread;
 
 const HELP: &'static str = "\
 The Wu Compiler
@@ -35,283 +38,242 @@ Usage:
     wu                # Show this message
     wu <file>         # Compile .wu file to corresponding .lua file
     wu <folder>       # Compile all .wu files in given folder
+    wu -              # Compile stdin, printing the .lua to stdout
     wu clean <folder> # Removes all compiled .lua files from given folder
+    wu tags [folder]  # Writes a ctags `tags` file of all public definitions
+    wu grammar        # Prints a TextMate grammar for Wu
+    wu repl           # Reads and runs Wu with `lua` (or $WU_LUA); `:type`, `:ast` and `:lua`
+                      # show how the checker and compiler see an expression
+    wu type-at <file>:<line>:<col>
+                      # Prints the type inferred at the position
+    wu complete <file>:<line>:<col>
+                      # Prints the required members the `new` around the position leaves out
+    wu signature <file>:<line>:<col>
+                      # Prints the parameters of the call around the position, the one the
+                      # argument there goes to marked with `>`
+    wu daemon         # Builds as asked by `wu build --use-daemon` in this folder, keeping the
+                      # modules it checked in memory for later builds; with the flags given here
+    wu lsp            # Runs a language server for editors over stdin and stdout, with diagnostics,
+                      # hover, go to definition, and completion and signature help as above;
+                      # under a wu.toml, diagnostics for every file of the project
+    wu api <file>     # Prints the names the module exports with their types; with `--json`
+                      # as JSON, with `--diff <old.json>` the breaking changes since then
+    wu test [folder]  # Compiles, then runs every *_test.wu with `lua` (or $WU_LUA)
+    wu run <file>     # Compiles the file's folder, then runs the file with `lua` (or $WU_LUA)
+    wu profile <file> # Runs the file like `wu run`, sampling where it spends its time, and
+                      # prints that by Wu function, those taking a tenth or more marked `@hot`;
+                      # `wu.profile.out` also lists the Wu lines with their samples
+    wu map [<file>:<line>]
+                      # Maps a .lua line to its Wu line, or a .wu line to its .lua lines;
+                      # without a position, rewrites a Lua traceback read from stdin
+    wu fix <file>     # Applies the fixes suggested by diagnostics, e.g. missing imports
+    wu lint [folder]  # Checks every module without writing anything; with `--baseline <file>`,
+                      # first records the warnings found in <file>, then leaves those out
+    wu rename <file>:<line>:<col> <name>
+                      # Renames the definition at the position and all its uses
+    wu codemod <name> [folder]
+                      # Rewrites every module with a migration, e.g. `typed-splats`,
+                      # changing nothing else; without a known <name>, lists them
+    wu triage <file>  # Finds the phase the file crashes the compiler in, and prints as few of
+                      # its statements and lines as still crash it the same way
+    wu fmt [folder]   # Rewrites every module in the one canonical layout, keeping comments;
+                      # with `--check`, only lists the modules that aren't laid out so
 
 Project usage:
     wu new <name>     # Create a new Wu project
     wu sync           # Installs/synchronizes dependencies
     wu build          # Installs dependencies and builds current project
+                      # warning about `pub` definitions no module imports, when `main.wu`
+                      # is the entry point; with `--use-daemon`, by the `wu daemon` running here
+
+Flags:
+    --out-dir <dir>   # Write compiled .lua files into <dir>, mirroring the source tree
+                      # (also read from `outdir` in wu.toml), with a `package_path.lua` that
+                      # puts them on `package.path` for `require`
+    --emit-on-error   # Still write the modules that compiled when others failed
+    --max-errors <n>  # Stop after <n> errors, showing nothing past them
+    --fail-fast       # Stop at the first module that fails to compile, or test that fails
+    --baseline <file> # Leave out the warnings `wu lint --baseline <file>` recorded, by file
+                      # and message, so a new lint only reports what's new
+    --module-cache <dir> # Keep what checking each imported module found in <dir>, and reuse
+                      # it in later runs for as long as the module and all it imports are
+                      # unchanged
+    --wumap           # Also write a .wumap next to each .lua, mapping its lines to Wu lines
+    --coverage        # Make `wu test` run under luacov and report coverage of the Wu lines
+    --stdout          # Print compiled .lua to stdout instead of writing files
+    --inline          # Replace calls of one-expression functions with their bodies
+    --hermetic        # Build only from the project (the folder with wu.toml, or else this one),
+                      # never from `$WU_HOME`, referring to modules by their path from the
+                      # project root, so the output is the same on any machine
+    --hot             # Compile modules to be reloadable, keeping their state; with `wu run`,
+                      # recompile changed modules and reload them into the running program
+    --pretty-errors   # Make the program print an error nothing caught like a compiler error,
+                      # at the Wu file and line it came from, with the Wu lines calling it
+    --love            # Make `main.wu` set the LÖVE callbacks it exports, e.g. `love.update`
+                      # (also read from `framework = \"love\"` in wu.toml)
+    --cfg <name>      # Turn on the imports marked `if cfg(name)`, e.g. `import profiler if
+                      # cfg(debug)`; those that are off aren't compiled or required
+    --target <lua>    # Lua to compile for: `5.1` (default), `5.2`, `5.3`, `5.4` or `jit`;
+                      # from 5.2 on and in LuaJIT, `skip` compiles to `goto`;
+                      # from 5.3 on, an `int` is 64 bit rather than a float exact up to 2^53
+    --edition <n>     # Language edition to read code as, `1` (default) or `2` (also read from
+                      # `edition` in wu.toml); older code warns what later editions change
+    --max-depth <n>   # How deeply expressions, types and imports may nest (default 256)
+    --max-lines <n>   # Warn about functions longer than <n> lines,
+    --max-nesting <n> # with `if`, `while` and `for` nested more than <n> deep,
+    --max-params <n>  # or taking more than <n> parameters
+    --emit <what>     # `lua` (default), or `highlights` for .highlights.json token classes;
+                      # `callgraph` or `modgraph` for the build's function calls or module
+                      # imports as one `callgraph.dot` or `modgraph.dot` (.json with `--json`)
+    -e, --etags       # Make `wu tags` write an emacs `TAGS` file instead
+    --check           # Make `wu fmt` fail on unformatted modules instead of rewriting them
+    -v, -vv           # Explain module resolution, and with -vv each compiler phase
+    -q, --quiet       # Only print diagnostics
+    --verbose-types   # Spell out deeply nested types in diagnostics, instead of `...`
+                      # (diagnostics wrap at $COLUMNS, or 100 columns)
+    --warn <lint>     # Report <lint> as a warning, with `--deny` as an error, with `--allow`
+    --deny <lint>     # not at all (also read from `[lints]` in wu.toml, e.g.
+    --allow <lint>    # `unused_exports = \"deny\"`); the lints are `implicit_any` and
+                      # `conversions` (allowed unless asked for), `inexact_ints`, `complexity`,
+                      # `unused_exports`, `private_callbacks` and `edition`
+    --warn-conversions # Warn where `++` turns an `int`, `float` or `bool` into a `str`
+                      # (a module heading with `#!strict` makes these and untyped
+                      # parameters errors, which `#!allow(conversions, implicit_any)` lifts)
+    --internal-checks # Check the compiler's own bookkeeping while checking, e.g. that every
+                      # scope entered is left, failing with an internal compiler error where not
+    --color <when>    # Color output: `auto` (default), `always` or `never`;
+                      # `auto` honors NO_COLOR and only colors terminals
+
+Exit codes:
+    0                 # Success
+    1                 # Type errors
+    2                 # Syntax errors
+    3                 # I/O errors, e.g. a missing file
+    4                 # Bad command line
+    101               # Internal compiler error
 ";
 
-fn compile_path(path: &str, root: &String) {
-    let meta = match metadata(path) {
-        Ok(m) => m,
-        Err(why) => panic!("{}", why),
-    };
-
-    if meta.is_file() {
-        let split: Vec<&str> = path.split('.').collect();
-
-        println!(
-            "{} {}",
-            "Compiling".green().bold(),
-            path.to_string().replace("./", "")
-        );
-
-        if *split.last().unwrap() == "wu" {
-            let meta = match metadata(root) {
-                Ok(m) => Some(m),
-                Err(_) => None,
-            };
-
-            let mut root = root.to_string();
-
-            if let Some(meta) = meta {
-                if !meta.is_dir() {
-                    root = Path::new(&root).parent().unwrap().display().to_string();
-                }
-            }
-
-            if let Some(n) = file_content(path, &root) {
-                write(path, &n);
-            }
-        }
-    } else {
-        let paths = fs::read_dir(path).unwrap();
+fn main() {
+    ice::install();
 
-        for folder_path in paths {
-            let folder_path = format!("{}", folder_path.unwrap().path().display());
-            let split: Vec<&str> = folder_path.split('.').collect();
+    let caught = || match panic::catch_unwind(start) {
+        Ok(failure) => failure,
+        Err(_) => {
+            ice::report();
 
-            if Path::new(&folder_path).is_dir() || *split.last().unwrap() == "wu" {
-                compile_path(&folder_path, root)
-            }
+            Some(Failure::Internal)
         }
-    }
-}
-
-fn file_content(path: &str, root: &String) -> Option<String> {
-    let display = Path::new(path).display();
-
-    let mut file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
     };
 
-    let mut s = String::new();
+    let failure = match thread::Builder::new().stack_size(STACK).spawn(caught) {
+        Ok(compiler) => compiler.join().unwrap_or(Some(Failure::Internal)),
+        Err(_) => caught(),
+    };
 
-    match file.read_to_string(&mut s) {
-        Err(why) => panic!("failed to read {}: {}", display, why),
-        Ok(_) => run(&s, path, root),
-    }
+    process::exit(failure.map_or(0, |failure| failure as i32))
 }
 
-fn write(path: &str, data: &str) {
-    let path = Path::new(path);
+fn start() -> Option<Failure> {
+    ColorChoice::Auto.apply();
 
-    let split_name = path.file_name().unwrap().to_str().unwrap().split('.');
-    let split: Vec<&str> = split_name.collect();
+    let mut args = env::args().collect::<Vec<String>>();
+    let given = args.clone();
 
-    let path_split = path.to_str().unwrap().split('/').collect::<Vec<&str>>();
-
-    let path_real = if path_split.len() > 1 {
-        format!(
-            "{}/{}.lua",
-            path_split[0..path_split.len() - 1].join("/"),
-            split[0]
-        )
-    } else {
-        format!("{}.lua", split[0])
+    let mut build = match Build::from_args(&mut args) {
+        Ok(build) => build,
+        Err(failure) => return Some(failure),
     };
 
-    let mut output_file = File::create(&path_real).unwrap();
-    match output_file.write_all(data.as_bytes()) {
-        Ok(_) => (),
-        Err(why) => println!("{}", why),
+    if let Err(failure) = build.setup(args.get(1).map(String::as_str)) {
+        return Some(failure);
     }
-}
 
-pub fn run(content: &str, file: &str, root: &String) -> Option<String> {
-    let source = Source::from(
-        file,
-        content.lines().map(|x| x.into()).collect::<Vec<String>>(),
-    );
-    let lexer = Lexer::default(content.chars().collect(), &source);
-
-    let mut tokens = Vec::new();
-
-    for token_result in lexer {
-        if let Ok(token) = token_result {
-            tokens.push(token)
-        } else {
-            return None;
-        }
-    }
-
-    let mut parser = Parser::new(tokens, &source);
-
-    match parser.parse() {
-        Ok(ref ast) => {
-            let mut symtab = SymTab::new();
-
-            let splat_any = Type::new(TypeNode::Any, TypeMode::Splat(None));
+    if args.len() > 1 {
+        match args[1].as_str() {
+            "clean" => {
+                if args.len() > 2 {
+                    build::clean_path(&args[2])
+                }
+            }
 
-            symtab.assign_str(
-                "print",
-                Type::function(vec![splat_any.clone()], Type::from(TypeNode::Nil), false),
-            );
+            "new" => {
+                if args.len() > 2 {
+                    handler::new(Some(&args[2]))
+                } else {
+                    handler::new(None)
+                }
+            }
 
-            symtab.assign_str(
-                "ipairs",
-                Type::function(vec![splat_any.clone()], splat_any.clone(), false),
-            );
+            "build" => {
+                if build.use_daemon {
+                    if let Some(code) = daemon::build_by_daemon(&given) {
+                        return Failure::from_code(code);
+                    }
+                }
 
-            symtab.assign_str(
-                "pairs",
-                Type::function(vec![splat_any.clone()], splat_any, false),
-            );
+                build::build_project(args.get(2).map_or(".", String::as_str), &mut build)
+            }
 
-            let mut visitor = Visitor::from_symtab(ast, &source, symtab, root.clone());
+            "daemon" => daemon::serve_builds(&daemon::flags(&given, &args), &mut build),
 
-            match visitor.visit() {
-                Ok(_) => (),
-                _ => return None,
-            }
+            "sync" => handler::get(),
 
-            let mut generator = Generator::new(&source, &visitor.method_calls, &visitor.import_map);
+            "grammar" => print!("{}", highlight::grammar()),
 
-            Some(generator.generate(&ast))
-        }
+            "repl" => repl::repl(),
 
-        _ => None,
-    }
-}
+            "type-at" => query::type_at_path(args.get(2).map_or("", String::as_str), &mut build),
 
-fn clean_path(path: &str) {
-    let meta = match metadata(path) {
-        Ok(m) => m,
-        Err(why) => panic!("{}", why),
-    };
+            "complete" => query::complete_path(args.get(2).map_or("", String::as_str), &mut build),
 
-    if meta.is_dir() {
-        let paths = fs::read_dir(path).unwrap();
-
-        for path in paths {
-            let path = path.unwrap().path();
-            if path.is_dir() {
-                clean_path(&path.display().to_string())
-            } else {
-                let path = format!("{}", path.display());
-                let split: Vec<&str> = path.split('.').collect();
-
-                // removes lua file if wu source exists
-                match split.last() {
-                    Some(n) if *n == "wu" => {
-                        let path = format!("{}.lua", split[0..split.len() - 1].to_vec().join("."));
-
-                        if Path::new(&path).is_file() {
-                            println!("{} {}", "Removing".red().bold(), path.replace("./", ""));
-
-                            match fs::remove_file(&path) {
-                                Ok(_) => (),
-                                Err(why) => panic!("{}", why),
-                            }
-                        }
-                    }
-                    _ => continue,
+            "lsp" => {
+                // LSP has the server exit with 1 when told to `exit` without a `shutdown` first
+                if lsp::serve().is_err() {
+                    build.fail(Failure::Type)
                 }
             }
-        }
-    } else {
-        let split: Vec<&str> = path.split('.').collect();
-
-        let path = format!("{}.lua", split[0..split.len() - 1].to_vec().join("."));
 
-        if Path::new(&path).is_file() {
-            match fs::remove_file(&path) {
-                Ok(_) => println!("{} {}", "removed".red().bold(), path.replace("./", "")),
-                Err(why) => panic!("{}", why),
+            "signature" => {
+                query::signature_path(args.get(2).map_or("", String::as_str), &mut build)
             }
-        }
-    }
-}
 
-fn confirm_home() {
-    if env::var("WU_HOME").is_err() {
-        let dir = if let Some(dir) = dirs::home_dir() {
-            format!("{}/.wu/libs/", dir.display())
-        } else {
-            return response!(
-                Response::Weird(format!("missing environment variable `WU_HOME`")),
-                Response::Note("failed to find home directory, you can set the variable yourself")
-            );
-        };
-
-        if !Path::new(&dir).exists() {
-            if fs::create_dir_all(dir).is_err() {
-                response!(
-                    Response::Weird(format!("missing environment variable `WU_HOME`")),
-                    Response::Note("run again as super-user to fix this automatically")
-                )
-            }
-        } else {
-            env::set_var("WU_HOME", dir)
-        }
-    }
-}
+            "api" => api::api_path(args.get(2).map_or("", String::as_str), &mut build),
 
-fn main() {
-    confirm_home();
+            "test" => test::test_path(args.get(2).map_or(".", String::as_str), &mut build),
 
-    let args = env::args().collect::<Vec<String>>();
+            "run" => run::run_path(args.get(2).map_or("", String::as_str), &mut build),
 
-    let root = Path::new(&args[0].to_string())
-        .parent()
-        .unwrap()
-        .display()
-        .to_string();
+            "profile" => profile::profile_path(args.get(2).map_or("", String::as_str), &mut build),
 
-    if args.len() > 1 {
-        match args[1].as_str() {
-            "clean" => {
-                if args.len() > 2 {
-                    clean_path(&args[2])
-                }
-            }
+            "map" => wumap::map_lines(args.get(2), &mut build),
 
-            "new" => {
-                if args.len() > 2 {
-                    handler::new(Some(&args[2]))
-                } else {
-                    handler::new(None)
-                }
-            }
+            "lint" => lint::lint_path(args.get(2).map_or(".", String::as_str), &mut build),
 
-            "build" => {
-                handler::get();
+            "fix" => fix::fix_path(args.get(2).map_or(".", String::as_str), &mut build),
 
-                if args.len() > 2 {
-                    compile_path(&args[2], &root)
-                } else {
-                    compile_path(".", &root)
-                }
-            }
+            "rename" => references::rename_path(
+                args.get(2).map_or("", String::as_str),
+                args.get(3),
+                &mut build,
+            ),
 
-            "sync" => handler::get(),
+            "codemod" => codemods::codemod_path(
+                args.get(2),
+                args.get(3).map_or(".", String::as_str),
+                &mut build,
+            ),
 
-            file => {
-                let now = Instant::now();
+            "fmt" => fmt::fmt_path(args.get(2).map_or(".", String::as_str), &mut build),
 
-                compile_path(&file, &file.to_string());
+            "tags" => tags::tag_path(args.get(2).map_or(".", String::as_str), &mut build),
 
-                println!(
-                    "{} things in {}ms",
-                    "  Finished".green().bold(),
-                    now.elapsed().as_millis()
-                );
-            }
+            "triage" => triage::triage_path(args.get(2).map_or("", String::as_str), &mut build),
+
+            file => build::build_path(file, &mut build),
         }
     } else {
         println!("{}", HELP)
     }
+
+    build.failure
 }
@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::Path;
+
+use super::*;
+
+// the public surface of a module, as `wu api` reports it: every name it exports with its type,
+// and the members of the structs and traits among them as `Name member`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Api {
+    pub module: String,
+    pub entries: Vec<(String, String)>, // by name
+}
+
+// how a module's surface changed since an earlier `wu api --json`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Removed(String, String),         // (name, type)
+    Changed(String, String, String), // (name, old type, new type)
+    Gained(String, String, String),  // (struct or trait, member, type), which users have to provide
+    Added(String, String),
+}
+
+impl Change {
+    // whether code using the old surface may no longer compile against the new one
+    pub fn is_breaking(&self) -> bool {
+        !matches!(self, Change::Added(..))
+    }
+}
+
+impl Api {
+    // what a checked module exports, from its `module_content`
+    pub fn of(visitor: &mut Visitor) -> Self {
+        let mut entries = Vec::new();
+
+        for (name, t) in visitor.module_content.clone() {
+            let t = silently(|| visitor.deid(t.clone())).unwrap_or(t);
+
+            let members = match t.node {
+                TypeNode::Struct(_, ref content, _) => Some(("struct", content)),
+                TypeNode::Trait(_, ref content) => Some(("trait", content)),
+                _ => None,
+            };
+
+            match members {
+                Some((kind, content)) => {
+                    entries.push((name.clone(), kind.to_string()));
+
+                    for (member, member_t) in content.iter() {
+                        let member_t = silently(|| visitor.deid(member_t.clone()))
+                            .unwrap_or_else(|_| member_t.clone());
+
+                        entries.push((format!("{} {}", name, member), member_t.to_string()))
+                    }
+                }
+
+                None => entries.push((name, t.to_string())),
+            }
+        }
+
+        entries.sort();
+
+        Api {
+            module: visitor.source.file.0.clone(),
+            entries,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(name, t)| format!("{}: {}\n", name, t))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|(name, t)| format!("    \"{}\": \"{}\"", escape(name), escape(t)))
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\n  \"version\": 1,\n  \"module\": \"{}\",\n  \"api\": {{\n{}\n  }}\n}}\n",
+            escape(&self.module),
+            entries.join(",\n")
+        )
+    }
+
+    // reads what `to_json` writes
+    pub fn from_json(json: &str) -> Option<Self> {
+        let field = |name: &str| {
+            let start = json.find(&format!("\"{}\":", name))? + name.len() + 3;
+
+            Some(json[start..].trim_start())
+        };
+
+        let (module, _) = string(field("module")?)?;
+
+        let mut rest = field("api")?.strip_prefix('{')?.trim_start();
+        let mut entries = Vec::new();
+
+        while !rest.starts_with('}') {
+            let (name, after) = string(rest)?;
+            let (t, after) = string(after.trim_start().strip_prefix(':')?.trim_start())?;
+
+            entries.push((name, t));
+
+            let after = after.trim_start();
+            rest = after.strip_prefix(',').unwrap_or(after).trim_start();
+        }
+
+        Some(Api { module, entries })
+    }
+
+    // what changed from `old` to this, removals and changes first
+    pub fn changes_since(&self, old: &Api) -> Vec<Change> {
+        let mut changes = Vec::new();
+
+        for (name, old_t) in old.entries.iter() {
+            match self.entries.iter().find(|(new_name, _)| new_name == name) {
+                None => changes.push(Change::Removed(name.clone(), old_t.clone())),
+                Some((_, t)) if t != old_t => {
+                    changes.push(Change::Changed(name.clone(), old_t.clone(), t.clone()))
+                }
+                Some(_) => (),
+            }
+        }
+
+        let existed = |name: &str| old.entries.iter().any(|(old_name, _)| old_name == name);
+
+        for (name, t) in self.entries.iter() {
+            if existed(name) {
+                continue;
+            }
+
+            match name.split_once(' ') {
+                Some((owner, member)) if existed(owner) => changes.push(Change::Gained(
+                    owner.to_string(),
+                    member.to_string(),
+                    t.clone(),
+                )),
+
+                _ => changes.push(Change::Added(name.clone(), t.clone())),
+            }
+        }
+
+        changes
+    }
+}
+
+// the JSON string `json` starts with, unescaped, and what follows it
+pub fn string(json: &str) -> Option<(String, &str)> {
+    let mut chars = json.strip_prefix('"')?.char_indices();
+    let mut unescaped = String::new();
+
+    loop {
+        match chars.next()? {
+            (i, '"') => return Some((unescaped, &json[i + 2..])),
+            (_, '\\') => match chars.next()?.1 {
+                'n' => unescaped.push('\n'),
+                't' => unescaped.push('\t'),
+                c => unescaped.push(c),
+            },
+            (_, c) => unescaped.push(c),
+        }
+    }
+}
+
+// `wu api`, what a module exports, or with `--diff` how that changed in ways that break its users
+pub fn api_path(file: &str, build: &mut Build) {
+    if !file.ends_with(".wu") {
+        response!(Response::Wrong("expected a `.wu` file after `api`"));
+
+        return build.fail(Failure::Usage);
+    }
+
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(why) => {
+            response!(Response::Wrong(format!("can't read `{}`: {}", file, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let ast = match parse(&content, &source) {
+        Ok(ast) => ast,
+        Err(failure) => return build.fail(failure),
+    };
+
+    let root = Path::new(file).parent().map_or(".".to_string(), |root| root.display().to_string());
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root);
+
+    if visitor.visit().is_err() {
+        return build.fail(Failure::Type);
+    }
+
+    let api = Api::of(&mut visitor);
+
+    let old = match build.diff {
+        Some(ref old) => old,
+        None if build.json => return print!("{}", api.to_json()),
+        None => return print!("{}", api.to_text()),
+    };
+
+    let old = match fs::read_to_string(old).ok().and_then(|json| Api::from_json(&json)) {
+        Some(old) => old,
+        None => {
+            response!(Response::Wrong(format!(
+                "can't read `{}` as a `wu api --json` report",
+                old.display()
+            )));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let changes = api.changes_since(&old);
+
+    for change in changes.iter() {
+        match *change {
+            Change::Removed(ref name, ref t) => {
+                response!(Response::Wrong(format!("breaking change, `{}: {}` is gone", name, t)))
+            }
+
+            Change::Changed(ref name, ref old, ref new) => response!(Response::Wrong(format!(
+                "breaking change, `{}` went from `{}` to `{}`",
+                name, old, new
+            ))),
+
+            Change::Gained(ref owner, ref member, ref t) => response!(Response::Wrong(format!(
+                "breaking change, `{}` gained `{}: {}`, which its users have to provide",
+                owner, member, t
+            ))),
+
+            Change::Added(ref name, ref t) => log!(Normal, "Added", "`{}: {}`", name, t),
+        }
+    }
+
+    if changes.iter().any(Change::is_breaking) {
+        build.fail(Failure::Type)
+    }
+}
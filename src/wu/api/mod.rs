@@ -0,0 +1,9 @@
+pub mod api;
+
+use super::build::*;
+use super::error::{silently, Response};
+use super::highlight::escape;
+use super::source::Source;
+use super::visitor::*;
+
+pub use self::api::*;
@@ -0,0 +1,90 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::*;
+
+// the warnings a codebase had when it took up a lint, which later runs leave out; by file and
+// message rather than line, so editing around them doesn't bring them back
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Baseline {
+    pub warnings: BTreeMap<(String, String), usize>, // (file, message) to how often
+}
+
+impl Baseline {
+    pub fn of(warnings: Vec<(String, String)>) -> Self {
+        let mut baseline = Baseline::default();
+
+        for warning in warnings {
+            *baseline.warnings.entry(warning).or_default() += 1
+        }
+
+        baseline
+    }
+
+    pub fn len(&self) -> usize {
+        self.warnings.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    // what `error::set_baseline` takes
+    pub fn known(&self) -> HashMap<(String, String), usize> {
+        self.warnings.clone().into_iter().collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let warnings = self
+            .warnings
+            .iter()
+            .map(|((file, message), count)| {
+                format!(
+                    "    {{ \"file\": \"{}\", \"message\": \"{}\", \"count\": {} }}",
+                    escape(file),
+                    escape(message),
+                    count
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\n  \"version\": 1,\n  \"warnings\": [\n{}\n  ]\n}}\n",
+            warnings.join(",\n")
+        )
+    }
+
+    // reads what `to_json` writes
+    pub fn from_json(json: &str) -> Option<Self> {
+        let start = json.find("\"warnings\":")? + "\"warnings\":".len();
+
+        let mut rest = json[start..].trim_start().strip_prefix('[')?.trim_start();
+        let mut warnings = BTreeMap::new();
+
+        while !rest.starts_with(']') {
+            rest = rest.strip_prefix('{')?.trim_start();
+
+            let (file, after) = field(rest, "file")?;
+            let (message, after) = field(after, "message")?;
+
+            let after = after.strip_prefix("\"count\":")?.trim_start();
+            let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+            let count = after[..digits].parse().ok()?;
+
+            warnings.insert((file, message), count);
+
+            let after = after[digits..].trim_start().strip_prefix('}')?.trim_start();
+            rest = after.strip_prefix(',').unwrap_or(after).trim_start();
+        }
+
+        Some(Baseline { warnings })
+    }
+}
+
+// the string of `"name": "..."`, and what follows the comma after it
+fn field<'a>(json: &'a str, name: &str) -> Option<(String, &'a str)> {
+    let json = json.strip_prefix(&format!("\"{}\":", name))?.trim_start();
+    let (value, after) = string(json)?;
+
+    Some((value, after.trim_start().strip_prefix(',')?.trim_start()))
+}
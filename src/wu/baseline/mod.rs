@@ -0,0 +1,6 @@
+pub mod baseline;
+
+use super::api::string;
+use super::highlight::escape;
+
+pub use self::baseline::*;
@@ -0,0 +1,1193 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::{self, metadata, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::time::Instant;
+
+use colored::Colorize;
+
+use super::*;
+
+// e.g. "1 module", "7 modules"
+pub fn plural(n: usize, what: &str) -> String {
+    format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+}
+
+// why a run failed, ordered by severity; the discriminant is the exit code
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Failure {
+    Type = 1,
+    Parse = 2,
+    Io = 3,
+    Usage = 4,
+    Internal = 101,
+}
+
+impl Failure {
+    // the failure exiting with `code`, e.g. a build done by `wu daemon`
+    pub fn from_code(code: i32) -> Option<Failure> {
+        match code {
+            0 => None,
+            1 => Some(Failure::Type),
+            2 => Some(Failure::Parse),
+            3 => Some(Failure::Io),
+            4 => Some(Failure::Usage),
+            _ => Some(Failure::Internal),
+        }
+    }
+}
+
+pub struct Build {
+    pub out_dir: Option<PathBuf>,
+    pub emit_on_error: bool,
+    pub wumap: bool,
+    pub coverage: bool,
+    pub stdout: bool,
+    pub etags: bool,
+    pub check: bool,
+    pub json: bool,
+    pub diff: Option<PathBuf>,
+    pub baseline: Option<PathBuf>, // warnings to leave out, see `wu lint`
+    pub module_cache: Option<PathBuf>, // where visited modules are kept between runs
+    pub emit: Emit,
+    pub color: ColorChoice,
+    pub verbosity: Verbosity,
+    pub verbose_types: bool,
+    pub lints: Vec<(Lint, LintLevel)>, // `--warn`, `--deny` and `--allow`, in order
+    pub internal_checks: bool,
+    pub inline: bool,
+    pub hermetic: bool,
+    pub hot: bool,
+    pub pretty_errors: bool,
+    pub love: bool,
+    pub cfg: Vec<String>,
+    pub target: Target,
+    pub edition: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub limits: Vec<(Limit, usize)>, // `--max-lines` and friends
+    pub max_errors: Option<usize>,
+    pub fail_fast: bool,
+    pub use_daemon: bool,
+    pub skipped: usize, // modules left unchecked once the build stopped early
+    pub emitted: HashSet<PathBuf>, // `$WU_HOME` modules already written to `out_dir`
+    pub pending: Vec<(PathBuf, String)>,
+    pub graph: Graph, // what `--emit callgraph` or `--emit modgraph` found so far
+    pub failed: Vec<String>,
+    pub modules: usize,
+    pub failure: Option<Failure>,
+}
+
+impl Build {
+    // pulls the flags out of `args`, leaving the positional arguments
+    pub fn from_args(args: &mut Vec<String>) -> Result<Self, Failure> {
+        let mut build = Build {
+            out_dir: None,
+            emit_on_error: false,
+            wumap: false,
+            coverage: false,
+            stdout: false,
+            etags: false,
+            check: false,
+            json: false,
+            diff: None,
+            baseline: None,
+            module_cache: None,
+            emit: Emit::Lua,
+            color: ColorChoice::Auto,
+            verbosity: Verbosity::Normal,
+            verbose_types: false,
+            lints: Vec::new(),
+            internal_checks: false,
+            inline: false,
+            hermetic: false,
+            hot: false,
+            pretty_errors: false,
+            love: false,
+            cfg: Vec::new(),
+            target: Target::Lua51,
+            edition: None,
+            max_depth: None,
+            limits: Vec::new(),
+            max_errors: None,
+            fail_fast: false,
+            use_daemon: false,
+            skipped: 0,
+            emitted: HashSet::new(),
+            pending: Vec::new(),
+            graph: Graph::default(),
+            failed: Vec::new(),
+            modules: 0,
+            failure: None,
+        };
+
+        let mut rest = Vec::new();
+        let mut iter = args.drain(..);
+
+        while let Some(arg) = iter.next() {
+            if arg == "--out-dir" {
+                if let Some(dir) = iter.next() {
+                    build.out_dir = Some(PathBuf::from(dir))
+                } else {
+                    response!(Response::Wrong("expected a directory after `--out-dir`"));
+
+                    return Err(Failure::Usage);
+                }
+            } else if let Some(dir) = arg.strip_prefix("--out-dir=") {
+                build.out_dir = Some(PathBuf::from(dir))
+            } else if arg == "--color" || arg.starts_with("--color=") {
+                let choice = match arg.strip_prefix("--color=") {
+                    Some(choice) => Some(choice.to_string()),
+                    None => iter.next(),
+                };
+
+                match choice.as_deref().and_then(ColorChoice::from) {
+                    Some(color) => build.color = color,
+                    None => {
+                        response!(Response::Wrong(
+                            "expected `always`, `auto` or `never` after `--color`"
+                        ));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "-v" || arg == "--verbose" {
+                build.verbosity = build.verbosity.max(Verbosity::Verbose)
+            } else if arg == "-vv" {
+                build.verbosity = Verbosity::Trace
+            } else if arg == "-q" || arg == "--quiet" {
+                build.verbosity = Verbosity::Quiet
+            } else if arg == "--verbose-types" {
+                build.verbose_types = true
+            } else if arg == "--warn-conversions" {
+                build.lints.push((Lint::Conversions, LintLevel::Warn))
+            } else if let Some((flag, level)) = [
+                ("--allow", LintLevel::Allow),
+                ("--warn", LintLevel::Warn),
+                ("--deny", LintLevel::Deny),
+            ]
+            .into_iter()
+            .find(|(flag, _)| arg == *flag || arg.starts_with(&format!("{}=", flag)))
+            {
+                let name = match arg.split_once('=') {
+                    Some((_, name)) => Some(name.to_string()),
+                    None => iter.next(),
+                };
+
+                match name.as_deref().map(|name| (name, Lint::named(name))) {
+                    Some((_, Some(lint))) => build.lints.push((lint, level)),
+                    Some((name, None)) => {
+                        response!(
+                            Response::Wrong(format!("no such lint `{}`", name)),
+                            Response::Note(format!("the lints are {}", Lint::names()))
+                        );
+
+                        return Err(Failure::Usage);
+                    }
+                    None => {
+                        response!(Response::Wrong(format!("expected a lint after `{}`", flag)));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--internal-checks" {
+                build.internal_checks = true
+            } else if arg == "--inline" {
+                build.inline = true
+            } else if arg == "--hermetic" {
+                build.hermetic = true
+            } else if arg == "--hot" {
+                build.hot = true
+            } else if arg == "--pretty-errors" {
+                build.pretty_errors = true
+            } else if arg == "--love" {
+                build.love = true
+            } else if arg == "--cfg" || arg.starts_with("--cfg=") {
+                let name = match arg.strip_prefix("--cfg=") {
+                    Some(name) => Some(name.to_string()),
+                    None => iter.next(),
+                };
+
+                match name {
+                    Some(name) => build.cfg.push(name),
+                    None => {
+                        response!(Response::Wrong("expected a name after `--cfg`"));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--target" || arg.starts_with("--target=") {
+                let target = match arg.strip_prefix("--target=") {
+                    Some(target) => Some(target.to_string()),
+                    None => iter.next(),
+                };
+
+                match target.as_deref().and_then(Target::from) {
+                    Some(target) => build.target = target,
+                    None => {
+                        response!(Response::Wrong(
+                            "expected `5.1`, `5.2`, `5.3`, `5.4` or `jit` after `--target`"
+                        ));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--edition" || arg.starts_with("--edition=") {
+                let edition = match arg.strip_prefix("--edition=") {
+                    Some(edition) => Some(edition.to_string()),
+                    None => iter.next(),
+                };
+
+                match edition.and_then(|edition| edition.parse().ok()) {
+                    Some(edition) => build.edition = Some(edition),
+                    None => {
+                        response!(Response::Wrong("expected an edition after `--edition`"));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--max-depth" || arg.starts_with("--max-depth=") {
+                let depth = match arg.strip_prefix("--max-depth=") {
+                    Some(depth) => Some(depth.to_string()),
+                    None => iter.next(),
+                };
+
+                match depth.and_then(|depth| depth.parse().ok()) {
+                    Some(depth) if depth > 0 => build.max_depth = Some(depth),
+                    _ => {
+                        response!(Response::Wrong("expected a positive number after `--max-depth`"));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if let Some(limit) = Limit::ALL.iter().find(|limit| {
+                arg == limit.flag() || arg.starts_with(&format!("{}=", limit.flag()))
+            }) {
+                let max = match arg.split_once('=') {
+                    Some((_, max)) => Some(max.to_string()),
+                    None => iter.next(),
+                };
+
+                match max.and_then(|max| max.parse().ok()) {
+                    Some(max) if max > 0 => build.limits.push((*limit, max)),
+                    _ => {
+                        response!(Response::Wrong(format!(
+                            "expected a positive number after `{}`",
+                            limit.flag()
+                        )));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--max-errors" || arg.starts_with("--max-errors=") {
+                let max = match arg.strip_prefix("--max-errors=") {
+                    Some(max) => Some(max.to_string()),
+                    None => iter.next(),
+                };
+
+                match max.and_then(|max| max.parse().ok()) {
+                    Some(max) if max > 0 => build.max_errors = Some(max),
+                    _ => {
+                        response!(Response::Wrong("expected a positive number after `--max-errors`"));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--fail-fast" {
+                build.fail_fast = true
+            } else if arg == "--emit" || arg.starts_with("--emit=") {
+                let what = match arg.strip_prefix("--emit=") {
+                    Some(what) => Some(what.to_string()),
+                    None => iter.next(),
+                };
+
+                build.emit = match what.as_deref() {
+                    Some("lua") => Emit::Lua,
+                    Some("highlights") => Emit::Highlights,
+                    Some("callgraph") => Emit::CallGraph,
+                    Some("modgraph") => Emit::ModGraph,
+                    _ => {
+                        response!(Response::Wrong(
+                            "expected `lua`, `highlights`, `callgraph` or `modgraph` after `--emit`"
+                        ));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--stdout" {
+                build.stdout = true
+            } else if arg == "-e" || arg == "--etags" {
+                build.etags = true
+            } else if arg == "--check" {
+                build.check = true
+            } else if arg == "--json" {
+                build.json = true
+            } else if arg == "--diff" || arg.starts_with("--diff=") {
+                let old = match arg.strip_prefix("--diff=") {
+                    Some(old) => Some(old.to_string()),
+                    None => iter.next(),
+                };
+
+                match old {
+                    Some(old) => build.diff = Some(PathBuf::from(old)),
+                    None => {
+                        response!(Response::Wrong(
+                            "expected a `wu api --json` report after `--diff`"
+                        ));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--baseline" || arg.starts_with("--baseline=") {
+                let file = match arg.strip_prefix("--baseline=") {
+                    Some(file) => Some(file.to_string()),
+                    None => iter.next(),
+                };
+
+                match file {
+                    Some(file) => build.baseline = Some(PathBuf::from(file)),
+                    None => {
+                        response!(Response::Wrong("expected a file after `--baseline`"));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--module-cache" || arg.starts_with("--module-cache=") {
+                let dir = match arg.strip_prefix("--module-cache=") {
+                    Some(dir) => Some(dir.to_string()),
+                    None => iter.next(),
+                };
+
+                match dir {
+                    Some(dir) => build.module_cache = Some(PathBuf::from(dir)),
+                    None => {
+                        response!(Response::Wrong("expected a directory after `--module-cache`"));
+
+                        return Err(Failure::Usage);
+                    }
+                }
+            } else if arg == "--emit-on-error" {
+                build.emit_on_error = true
+            } else if arg == "--wumap" {
+                build.wumap = true
+            } else if arg == "--coverage" {
+                build.coverage = true
+            } else if arg == "--use-daemon" {
+                build.use_daemon = true
+            } else if arg.starts_with("--") {
+                response!(Response::Wrong(format!("unknown flag `{}`", arg)));
+
+                return Err(Failure::Usage);
+            } else {
+                rest.push(arg)
+            }
+        }
+
+        drop(iter);
+        *args = rest;
+
+        Ok(build)
+    }
+
+    // sets up the whole process as the flags say, for the `command` given, if any
+    pub fn setup(&mut self, command: Option<&str>) -> Result<(), Failure> {
+        if self.stdout || command == Some("-") {
+            self.stdout = true;
+            take_stdout()
+        }
+
+        self.color.apply();
+        self.verbosity.apply();
+        self.target.apply();
+
+        if self.verbose_types {
+            show_full_types()
+        }
+
+        // the flags go over what wu.toml says
+        for (lint, level) in handler::lints().into_iter().chain(self.lints.iter().copied()) {
+            set_level(lint, level)
+        }
+
+        if self.internal_checks {
+            enable_internal_checks()
+        }
+
+        if self.inline {
+            inline_small_functions()
+        }
+
+        if self.hermetic {
+            hermetic()
+        }
+
+        if let Some(ref dir) = self.module_cache {
+            cache_modules(dir)
+        }
+
+        if self.hot {
+            hot_reload()
+        }
+
+        if self.pretty_errors {
+            pretty_errors()
+        }
+
+        if self.love {
+            wire_love()
+        }
+
+        for name in self.cfg.iter() {
+            enable_cfg(name)
+        }
+
+        if let Some(depth) = self.max_depth {
+            set_max_depth(depth)
+        }
+
+        match self.edition.or_else(handler::edition) {
+            Some(edition) if (edition::FIRST..=edition::LATEST).contains(&edition) => {
+                set_edition(edition)
+            }
+
+            Some(edition) => {
+                response!(
+                    Response::Wrong(format!("there's no edition {}", edition)),
+                    Response::Note(format!(
+                        "the editions are {} to {}",
+                        edition::FIRST,
+                        edition::LATEST
+                    ))
+                );
+
+                return Err(Failure::Usage);
+            }
+
+            None => (),
+        }
+
+        for (limit, max) in self.limits.iter() {
+            set_limit(*limit, *max)
+        }
+
+        if let Some(max) = self.max_errors {
+            set_max_errors(max)
+        }
+
+        if let Some(ref file) = self.baseline {
+            load_baseline(file, command == Some("lint"))?
+        }
+
+        confirm_home();
+
+        Ok(())
+    }
+
+    pub fn fail(&mut self, failure: Failure) {
+        self.failure = self.failure.max(Some(failure))
+    }
+
+    // prints e.g. "2 errors, 5 warnings in 7 modules"
+    pub fn summary(&self, now: Instant) {
+        self.note_skipped();
+
+        if !Verbosity::Normal.enabled() {
+            return;
+        }
+
+        let (errors, warnings) = tallies();
+
+        let warnings = match baselined() {
+            0 => plural(warnings, "warning"),
+            known => format!("{} ({} baselined)", plural(warnings, "warning"), known),
+        };
+
+        let label = if errors > 0 || self.failure.is_some() {
+            "    Failed".red().bold()
+        } else {
+            "  Finished".green().bold()
+        };
+
+        emit(&format!(
+            "{} {}, {} in {} ({}ms)",
+            label,
+            plural(errors, "error"),
+            warnings,
+            plural(self.modules, "module"),
+            now.elapsed().as_millis()
+        ));
+    }
+
+    // the `package_path.lua` of the output folder, which puts the modules there on `package.path`
+    pub fn package_path(&self) -> Option<PathBuf> {
+        self.out_dir.as_ref().map(|dir| dir.join("package_path.lua"))
+    }
+
+    // makes `command` run the `package_path.lua` first, so the modules in the output folder resolve
+    pub fn resolve_modules(&self, command: &mut process::Command) {
+        if let Some(snippet) = self.package_path() {
+            command.arg("-e").arg(format!("dofile({:?})", snippet.display().to_string()));
+        }
+    }
+
+    // what a module compiled to, written once the build is done; for the graphs, a part of them
+    pub fn queue(&mut self, destination: PathBuf, compiled: Compiled) {
+        if self.emit.graph().is_some() {
+            self.graph.extend(compiled.graph)
+        } else {
+            self.pending.push((destination, compiled.output))
+        }
+    }
+
+    // whether to leave the modules not compiled yet be, after `--fail-fast` or `--max-errors`
+    pub fn stopped(&self) -> bool {
+        (self.fail_fast && self.failure.is_some()) || capped()
+    }
+
+    // says how much a build that stopped early left unchecked, even with the errors capped
+    pub fn note_skipped(&self) {
+        if self.skipped == 0 {
+            return;
+        }
+
+        let why = match self.max_errors {
+            Some(max) if capped() => format!("`--max-errors {}`", max),
+            _ => "`--fail-fast`".to_string(),
+        };
+
+        emit(&format!(
+            "{}",
+            Response::Note(format!(
+                "stopped early for {}, {} left unchecked",
+                why,
+                plural(self.skipped, "module")
+            ))
+        ))
+    }
+
+    // writes everything compiled so far, unless a failing module would leave the output half-stale
+    pub fn finish(&mut self) {
+        if !self.failed.is_empty() && !self.emit_on_error {
+            if !self.pending.is_empty() {
+                response!(
+                    Response::Weird(format!(
+                        "nothing written, {} of {} modules failed to compile",
+                        self.failed.len(),
+                        self.failed.len() + self.pending.len()
+                    )),
+                    Response::Note("pass `--emit-on-error` to write the modules that compiled")
+                )
+            }
+
+            self.pending.clear();
+
+            return;
+        }
+
+        if let Some(name) = self.emit.graph() {
+            let (text, extension) = if self.json {
+                (self.graph.to_json(), "json")
+            } else {
+                (self.graph.to_dot(name), "dot")
+            };
+
+            let folder = self.out_dir.clone().unwrap_or_default();
+
+            self.pending.push((folder.join(name).with_extension(extension), text))
+        }
+
+        if let Some(snippet) = self.package_path() {
+            if !self.pending.is_empty() && self.emit == Emit::Lua && !self.stdout {
+                self.pending.push((snippet, package_path()))
+            }
+        }
+
+        let several = self.pending.len() > 1;
+
+        for (destination, lua) in std::mem::take(&mut self.pending) {
+            if self.stdout {
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+
+                if several {
+                    let _ = writeln!(out, "-- {}", destination.display());
+                }
+
+                if writeln!(out, "{}", lua).is_err() {
+                    self.fail(Failure::Io)
+                }
+            } else if write(&destination, &lua).is_err() {
+                self.fail(Failure::Io)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Emit {
+    Lua,
+    Highlights,
+    CallGraph,
+    ModGraph,
+}
+
+impl Emit {
+    // the name of the graph the whole build makes, instead of a file per module
+    pub fn graph(&self) -> Option<&'static str> {
+        match *self {
+            Emit::CallGraph => Some("callgraph"),
+            Emit::ModGraph => Some("modgraph"),
+            _ => None,
+        }
+    }
+}
+
+pub struct Compiled {
+    pub output: String,
+    pub foreign: Vec<(String, String)>, // imported `$WU_HOME` modules, (file, home)
+    pub lines: Vec<usize>,              // the Wu line of every output line, for `.wumap`s
+    pub graph: Graph,                   // for `--emit callgraph` and `--emit modgraph`
+}
+
+pub fn compile_path(path: &str, root: &String, build: &mut Build) {
+    let meta = match metadata(path) {
+        Ok(m) => m,
+        Err(why) => {
+            response!(Response::Wrong(format!("can't read `{}`: {}", path, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    if meta.is_file() {
+        let split: Vec<&str> = path.split('.').collect();
+
+        if *split.last().unwrap() == "wu" && build.stopped() {
+            build.skipped += 1;
+
+            return;
+        }
+
+        log!(Normal, "Compiling", "{}", path.replace("./", ""));
+
+        if *split.last().unwrap() == "wu" {
+            let meta = match metadata(root) {
+                Ok(m) => Some(m),
+                Err(_) => None,
+            };
+
+            let mut root = root.to_string();
+
+            if let Some(meta) = meta {
+                if !meta.is_dir() {
+                    root = Path::new(&root).parent().unwrap().display().to_string();
+                }
+            }
+
+            build.modules += 1;
+
+            match file_content(path, &root, build.emit) {
+                Ok(mut compiled) => {
+                    let destination = output_path(path, build);
+
+                    if build.wumap && build.emit == Emit::Lua && !build.stdout {
+                        let map = LineMap {
+                            source: wumap::source_name(path),
+                            lines: std::mem::take(&mut compiled.lines),
+                        };
+
+                        build.pending.push((wumap::map_path(&destination), map.to_json()))
+                    }
+
+                    let foreign = std::mem::take(&mut compiled.foreign);
+
+                    build.queue(destination, compiled);
+                    emit_foreign(foreign, build)
+                }
+
+                Err(failure) => {
+                    build.failed.push(path.to_string());
+                    build.fail(failure)
+                }
+            }
+        }
+    } else {
+        let paths = match fs::read_dir(path) {
+            Ok(paths) => paths,
+            Err(why) => {
+                response!(Response::Wrong(format!("can't read `{}`: {}", path, why)));
+
+                return build.fail(Failure::Io);
+            }
+        };
+
+        for folder_path in paths.flatten() {
+            let folder_path = format!("{}", folder_path.path().display());
+            let split: Vec<&str> = folder_path.split('.').collect();
+
+            if Path::new(&folder_path).is_dir() || *split.last().unwrap() == "wu" {
+                compile_path(&folder_path, root, build)
+            }
+        }
+    }
+}
+
+// `wu -`, imports resolve from the working directory
+fn compile_stdin(build: &mut Build) {
+    let mut content = String::new();
+
+    if let Err(why) = io::stdin().read_to_string(&mut content) {
+        response!(Response::Wrong(format!("failed to read stdin: {}", why)));
+
+        return build.fail(Failure::Io);
+    }
+
+    build.modules += 1;
+
+    match compile(&content, "<stdin>", ".", build.emit) {
+        Ok(compiled) => build.queue(PathBuf::from("<stdin>"), compiled),
+
+        Err(failure) => {
+            build.failed.push("<stdin>".to_string());
+            build.fail(failure)
+        }
+    }
+}
+
+// compiles the `$WU_HOME` modules and those wu comes with that a file imports into the output
+// directory, so it can run on its own
+fn emit_foreign(foreign: Vec<(String, String)>, build: &mut Build) {
+    let out_dir = match build.out_dir {
+        Some(ref dir) => dir.clone(),
+        None => return,
+    };
+
+    for (module, home) in foreign {
+        let destination = match Path::new(&module).strip_prefix(&home) {
+            Ok(relative) => out_dir.join(relative).with_extension("lua"),
+            Err(_) => continue,
+        };
+
+        if !build.emitted.insert(destination.clone()) {
+            continue;
+        }
+
+        log!(Normal, "Compiling", "{}", module);
+
+        let root = Path::new(&module).parent().unwrap().display().to_string();
+
+        build.modules += 1;
+
+        match file_content(&module, &root, build.emit) {
+            Ok(mut compiled) => {
+                let foreign = std::mem::take(&mut compiled.foreign);
+
+                build.queue(destination, compiled);
+                emit_foreign(foreign, build)
+            }
+
+            Err(failure) => {
+                build.failed.push(module);
+                build.fail(failure)
+            }
+        }
+    }
+}
+
+// where the compiled lua of the source at `path` goes
+pub fn output_path(path: &str, build: &Build) -> PathBuf {
+    let path = Path::new(path);
+
+    let stem = path.file_name().unwrap().to_str().unwrap().split('.').next().unwrap();
+    let name = format!("{}.lua", stem);
+
+    let destination = if let Some(ref out_dir) = build.out_dir {
+        let mut relative = normalize_path(path);
+
+        if relative.is_absolute() {
+            let cwd = env::current_dir().unwrap_or_default();
+
+            relative = match relative.strip_prefix(&cwd) {
+                Ok(inner) => inner.to_path_buf(),
+                Err(_) => PathBuf::from(path.file_name().unwrap()),
+            }
+        }
+
+        // mirror the tree below the working directory, anything above it is flattened
+        let folder = relative
+            .parent()
+            .map(|parent| {
+                parent
+                    .components()
+                    .filter(|c| matches!(c, Component::Normal(_)))
+                    .collect::<PathBuf>()
+            })
+            .unwrap_or_default();
+
+        out_dir.join(folder).join(name)
+    } else {
+        path.with_file_name(name)
+    };
+
+    match build.emit {
+        Emit::Lua => destination,
+        Emit::Highlights => destination.with_extension("highlights.json"),
+        Emit::CallGraph | Emit::ModGraph => destination,
+    }
+}
+
+fn file_content(path: &str, root: &str, emit: Emit) -> Result<Compiled, Failure> {
+    let display = Path::new(path).display();
+
+    // read as the visitor reads imports, as those wu comes with aren't files
+    match read_module(Path::new(path)) {
+        Err(why) => {
+            response!(Response::Wrong(format!("failed to read `{}`: {}", display, why)));
+
+            Err(Failure::Io)
+        }
+
+        Ok(s) => compile(&s, path, root, emit),
+    }
+}
+
+// writes `data` to `path_real`, making the folders it's in
+pub fn write(path_real: &Path, data: &str) -> Result<(), Failure> {
+    if let Some(folder) = path_real.parent() {
+        if !folder.as_os_str().is_empty() && fs::create_dir_all(folder).is_err() {
+            response!(Response::Wrong(format!(
+                "failed to create directory `{}`",
+                folder.display()
+            )));
+
+            return Err(Failure::Io);
+        }
+    }
+
+    match File::create(path_real).and_then(|mut file| file.write_all(data.as_bytes())) {
+        Ok(_) => Ok(()),
+        Err(why) => {
+            response!(Response::Wrong(format!(
+                "failed to write `{}`: {}",
+                path_real.display(),
+                why
+            )));
+
+            Err(Failure::Io)
+        }
+    }
+}
+
+pub fn lex(content: &str, source: &Source) -> Result<Vec<Token>, Failure> {
+    let lexer = Lexer::default(content.chars().collect(), source);
+
+    let now = Instant::now();
+
+    let mut tokens = Vec::new();
+
+    for token_result in lexer {
+        if let Ok(token) = token_result {
+            tokens.push(token)
+        } else {
+            return Err(Failure::Parse);
+        }
+    }
+
+    log!(Trace, "Lexed", "{} tokens in {}ms", tokens.len(), now.elapsed().as_millis());
+
+    Ok(tokens)
+}
+
+pub fn parse(content: &str, source: &Source) -> Result<Vec<Statement>, Failure> {
+    let tokens = lex(content, source)?;
+
+    let now = Instant::now();
+
+    let mut parser = Parser::new(tokens, source);
+
+    match parser.parse() {
+        Ok(ast) => {
+            log!(Trace, "Parsed", "{} statements in {}ms", ast.len(), now.elapsed().as_millis());
+
+            Ok(ast)
+        }
+
+        _ => Err(Failure::Parse),
+    }
+}
+
+fn compile(content: &str, file: &str, root: &str, emit: Emit) -> Result<Compiled, Failure> {
+    match emit {
+        Emit::Lua => run(content, file, root),
+        Emit::Highlights => {
+            let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+            let tokens = lex(content, &source)?;
+            let ast = parse(content, &source)?;
+
+            Ok(Compiled {
+                output: highlight::to_json(file, &highlight::highlights(&tokens, &ast)),
+                foreign: Vec::new(),
+                lines: Vec::new(),
+                graph: Graph::default(),
+            })
+        }
+
+        Emit::CallGraph | Emit::ModGraph => {
+            let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+            let ast = parse(content, &source)?;
+
+            let mut visitor =
+                Visitor::from_symtab(&ast, &source, SymTab::prelude(), root.to_string());
+
+            // the types of receivers tell which struct's method a call is of
+            visitor.types = Some(HashMap::new());
+
+            if visitor.visit().is_err() {
+                return Err(Failure::Type);
+            }
+
+            let graph = match emit {
+                Emit::CallGraph => graph::callgraph(&visitor),
+                _ => graph::modgraph(&visitor),
+            };
+
+            Ok(Compiled {
+                output: String::new(),
+                foreign: foreign(&visitor),
+                lines: Vec::new(),
+                graph,
+            })
+        }
+    }
+}
+
+fn run(content: &str, file: &str, root: &str) -> Result<Compiled, Failure> {
+    let source = Source::from(
+        file,
+        content.lines().map(|x| x.into()).collect::<Vec<String>>(),
+    );
+
+    let ast = parse(content, &source)?;
+
+    // breaking changes of later editions, checked against the lossless tokens their fixes patch
+    let tokens = SyntaxTokens::lex(content.chars().collect(), &source).map_err(|_| Failure::Parse)?;
+
+    check_edition(&tokens, &source).map_err(|_| Failure::Parse)?;
+
+    let now = Instant::now();
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root.to_string());
+
+    match visitor.visit() {
+        Ok(_) => (),
+        _ => return Err(Failure::Type),
+    }
+
+    log!(Trace, "Checked", "{} in {}ms", file, now.elapsed().as_millis());
+
+    let now = Instant::now();
+
+    let mut generator = Generator::new(&source, &visitor);
+
+    let mut lua = generator.generate(&ast);
+
+    if is_love_main(file) {
+        let callbacks = wired(&visitor).map_err(|_| Failure::Type)?;
+
+        lua = wire(&lua, &callbacks)
+    }
+
+    log!(Trace, "Generated", "{} bytes in {}ms", lua.len(), now.elapsed().as_millis());
+
+    Ok(Compiled {
+        output: lua,
+        foreign: foreign(&visitor),
+        lines: generator.lines,
+        graph: Graph::default(),
+    })
+}
+
+// every `$WU_HOME` module loaded on the way, and those wu comes with, so those they import
+// themselves are emitted too
+fn foreign(visitor: &Visitor) -> Vec<(String, String)> {
+    let home = env::var("WU_HOME").ok().and_then(|home| fs::canonicalize(home).ok());
+    let homes = home.into_iter().chain(Some(PathBuf::from(stdlib::ROOT))).collect::<Vec<_>>();
+
+    let mut foreign = visitor
+        .files
+        .iter()
+        .filter_map(|(module, _)| {
+            let home = homes.iter().find(|home| module.starts_with(home))?;
+
+            Some((module.display().to_string(), home.display().to_string()))
+        })
+        .collect::<Vec<_>>();
+
+    foreign.sort();
+    foreign.dedup();
+
+    foreign
+}
+
+// leaves out the warnings `file` knows from here on; `wu lint` records them when it's not there
+pub fn load_baseline(file: &Path, lint: bool) -> Result<(), Failure> {
+    if !file.exists() && lint {
+        record_warnings();
+
+        return Ok(());
+    }
+
+    match fs::read_to_string(file).ok().and_then(|json| Baseline::from_json(&json)) {
+        Some(baseline) => {
+            set_baseline(baseline.known());
+
+            Ok(())
+        }
+
+        None if !file.exists() => {
+            response!(
+                Response::Wrong(format!("there's no baseline `{}`", file.display())),
+                Response::Note(format!("`wu lint --baseline {}` records one", file.display()))
+            );
+
+            Err(Failure::Io)
+        }
+
+        None => {
+            response!(Response::Wrong(format!(
+                "can't read `{}` as a `wu lint --baseline` file",
+                file.display()
+            )));
+
+            Err(Failure::Io)
+        }
+    }
+}
+
+// `file.wu:line:col`
+pub fn position(location: &str) -> Option<(&str, usize, usize)> {
+    let mut parts = location.rsplitn(3, ':');
+
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+
+    Some((parts.next()?, line, column))
+}
+
+// `wu build`, installing the dependencies first
+pub fn build_project(root: &str, build: &mut Build) {
+    handler::get();
+
+    if build.out_dir.is_none() {
+        build.out_dir = handler::out_dir().map(PathBuf::from)
+    }
+
+    if handler::framework().as_deref() == Some("love") {
+        wire_love()
+    }
+
+    let now = Instant::now();
+
+    compile_path(root, &root.to_string(), build);
+
+    // a program's modules exist for its entry point, so their exports should be used
+    if build.failed.is_empty()
+        && Path::new(root).join("main.wu").is_file()
+        && references::warn_unused_exports(Path::new(root)).is_err()
+    {
+        build.fail(Failure::Type)
+    }
+
+    build.finish();
+    build.summary(now)
+}
+
+pub fn clean_path(path: &str) {
+    let meta = match metadata(path) {
+        Ok(m) => m,
+        Err(why) => panic!("{}", why),
+    };
+
+    if meta.is_dir() {
+        let paths = fs::read_dir(path).unwrap();
+
+        for path in paths {
+            let path = path.unwrap().path();
+            if path.is_dir() {
+                clean_path(&path.display().to_string())
+            } else {
+                let path = format!("{}", path.display());
+                let split: Vec<&str> = path.split('.').collect();
+
+                // removes lua file if wu source exists
+                match split.last() {
+                    Some(n) if *n == "wu" => {
+                        let path = format!("{}.lua", split[0..split.len() - 1].to_vec().join("."));
+
+                        if Path::new(&path).is_file() {
+                            log!(Normal, "Removing", "{}", path.replace("./", ""));
+
+                            match fs::remove_file(&path) {
+                                Ok(_) => (),
+                                Err(why) => panic!("{}", why),
+                            }
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+        }
+    } else {
+        let split: Vec<&str> = path.split('.').collect();
+
+        let path = format!("{}.lua", split[0..split.len() - 1].to_vec().join("."));
+
+        if Path::new(&path).is_file() {
+            match fs::remove_file(&path) {
+                Ok(_) => log!(Normal, "Removed", "{}", path.replace("./", "")),
+                Err(why) => panic!("{}", why),
+            }
+        }
+    }
+}
+
+fn confirm_home() {
+    if env::var("WU_HOME").is_err() {
+        let dir = if let Some(dir) = dirs::home_dir() {
+            format!("{}/.wu/libs/", dir.display())
+        } else {
+            return response!(
+                Response::Weird(format!("missing environment variable `WU_HOME`")),
+                Response::Note("failed to find home directory, you can set the variable yourself")
+            );
+        };
+
+        if !Path::new(&dir).exists() {
+            if fs::create_dir_all(dir).is_err() {
+                response!(
+                    Response::Weird(format!("missing environment variable `WU_HOME`")),
+                    Response::Note("run again as super-user to fix this automatically")
+                )
+            }
+        } else {
+            env::set_var("WU_HOME", dir)
+        }
+    }
+}
+
+// `wu <file>` and `wu <folder>`, or `wu -` for the module on stdin
+pub fn build_path(path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    if path == "-" {
+        compile_stdin(build)
+    } else {
+        compile_path(path, &path.to_string(), build)
+    }
+
+    build.finish();
+    build.summary(now)
+}
@@ -0,0 +1,19 @@
+pub mod build;
+
+use super::baseline::Baseline;
+use super::compiler::*;
+use super::edition::{self, check_edition, set_edition};
+use super::error::*;
+use super::graph::{self, Graph};
+use super::handler;
+use super::highlight;
+use super::lexer::*;
+use super::love::*;
+use super::references;
+use super::parser::*;
+use super::source::*;
+use super::stdlib;
+use super::visitor::*;
+use super::wumap::{self, LineMap};
+
+pub use self::build::*;
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use super::*;
+
+// the migrations `wu codemod <name>` can run; a new one implements `Codemod` and is listed here
+pub fn codemods() -> Vec<Box<dyn Codemod>> {
+    vec![Box::new(TypedSplats)]
+}
+
+pub fn by_name(name: &str) -> Option<Box<dyn Codemod>> {
+    codemods()
+        .into_iter()
+        .find(|codemod| codemod.name() == name)
+}
+
+// `extern fun(...)` to `extern fun(...any)`, spelling out what a bare splat takes
+pub struct TypedSplats;
+
+impl Codemod for TypedSplats {
+    fn name(&self) -> &str {
+        "typed-splats"
+    }
+
+    fn description(&self) -> &str {
+        "spells out bare `...` splat types as `...any`"
+    }
+
+    fn visit(&mut self, tokens: &SyntaxTokens, at: usize) -> Vec<Patch> {
+        let token = &tokens.tokens[at].token;
+
+        if token.token_type != TokenType::Symbol || token.lexeme != "..." {
+            return Vec::new();
+        }
+
+        // what parsing a type takes as the end of a bare splat
+        let bare = match tokens.tokens.get(at + 1).map(|next| &next.token) {
+            None => true,
+            Some(next) => match next.token_type {
+                TokenType::EOL => true,
+                TokenType::Symbol => [")", "=", "{"].contains(&next.lexeme.as_str()),
+                _ => false,
+            },
+        };
+
+        if bare {
+            vec![Patch::replace(at, "...any")]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+pub fn codemod_path(name: Option<&String>, path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    let mut codemod = match name.and_then(|name| codemods::by_name(name)) {
+        Some(codemod) => codemod,
+        None => {
+            let known = codemods::codemods()
+                .iter()
+                .map(|codemod| format!("`{}` ({})", codemod.name(), codemod.description()))
+                .collect::<Vec<_>>();
+
+            let wrong = match name {
+                Some(name) => format!("no codemod called `{}`", name),
+                None => "expected `wu codemod <name> [folder]`".to_string(),
+            };
+
+            response!(
+                Response::Wrong(wrong),
+                Response::Note(format!("known codemods: {}", known.join(", ")))
+            );
+
+            return build.fail(Failure::Usage);
+        }
+    };
+
+    if !Path::new(path).exists() {
+        response!(Response::Wrong(format!("can't read `{}`: no such file or directory", path)));
+
+        return build.fail(Failure::Io);
+    }
+
+    let mut files = Vec::new();
+    sources(path, &mut files);
+
+    let mut count = 0;
+    let mut rewritten = 0;
+
+    for file in files {
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(why) => {
+                response!(Response::Wrong(format!("failed to read `{}`: {}", file, why)));
+
+                build.fail(Failure::Io);
+                continue;
+            }
+        };
+
+        let source = Source::from(&file, content.lines().map(|x| x.into()).collect());
+
+        let tokens = match SyntaxTokens::lex(content.chars().collect(), &source) {
+            Ok(tokens) => tokens,
+            Err(()) => {
+                build.fail(Failure::Parse);
+                continue;
+            }
+        };
+
+        let (text, patches) = codemod::rewrite(&tokens, codemod.as_mut());
+
+        if patches == 0 {
+            continue;
+        }
+
+        if write(Path::new(&file), &text).is_err() {
+            build.fail(Failure::Io);
+            continue;
+        }
+
+        log!(Verbose, "Rewrote", "{} ({})", file, plural(patches, "edit"));
+
+        count += patches;
+        rewritten += 1
+    }
+
+    log!(
+        Normal,
+        "Migrated",
+        "with `{}`: {} in {} ({}ms)",
+        codemod.name(),
+        plural(count, "edit"),
+        plural(rewritten, "module"),
+        now.elapsed().as_millis()
+    )
+}
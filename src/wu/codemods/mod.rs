@@ -0,0 +1,9 @@
+pub mod codemods;
+
+use super::build::*;
+use super::codemod::{self, *};
+use super::error::Response;
+use super::lexer::*;
+use super::source::{sources, Source};
+
+pub use self::codemods::*;
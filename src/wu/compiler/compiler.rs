@@ -1,8 +1,80 @@
 use super::*;
 
-use std::collections::HashMap;
-use std::path::Path;
-use std::ffi::OsStr;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::env;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// whether calls of tiny pure functions are replaced by their bodies, see `Inliner`
+static INLINE: AtomicBool = AtomicBool::new(false);
+
+// lua allows 200 active locals in a function, this leaves room for loops and temporaries
+pub const LOCALS: usize = 180;
+
+// chains of this many strings are joined with `table.concat` rather than `..`
+const CONCAT_CHAIN: usize = 5;
+
+// switches over this many integers or more look up their case in a table, see `dispatch`
+const SWITCH_TABLE: usize = 4;
+
+pub fn inline_small_functions() {
+    INLINE.store(true, Ordering::Relaxed)
+}
+
+// whether modules export `__state` and `__reload`, for `wu run --hot`
+static HOT: AtomicBool = AtomicBool::new(false);
+
+pub fn hot_reload() {
+    HOT.store(true, Ordering::Relaxed)
+}
+
+// whether modules report errors nothing caught at their Wu lines, see `--pretty-errors`
+static PRETTY_ERRORS: AtomicBool = AtomicBool::new(false);
+
+pub fn pretty_errors() {
+    PRETTY_ERRORS.store(true, Ordering::Relaxed)
+}
+
+// whether `skip` jumps to a label at the end of the loop body, rather than breaking out of a
+// `repeat ... until true` wrapped around it
+static GOTO: AtomicBool = AtomicBool::new(false);
+
+// the lua the output runs on, see `--target`; `|` and `&` are written for it, see `bitwise`
+static TARGET: Mutex<Target> = Mutex::new(Target::Lua51);
+
+// the lua the output runs on, see `--target`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Lua51,
+    Lua52,
+    Lua53,
+    Lua54,
+    LuaJit,
+}
+
+impl Target {
+    pub fn from(target: &str) -> Option<Self> {
+        match target {
+            "5.1" => Some(Target::Lua51),
+            "5.2" => Some(Target::Lua52),
+            "5.3" => Some(Target::Lua53),
+            "5.4" => Some(Target::Lua54),
+            "jit" | "luajit" => Some(Target::LuaJit),
+            _ => None,
+        }
+    }
+
+    // `goto` came with lua 5.2, and luajit has it too; integers with lua 5.3
+    pub fn apply(self) {
+        GOTO.store(self != Target::Lua51, Ordering::Relaxed);
+        *TARGET.lock().unwrap() = self;
+
+        wide_ints(matches!(self, Target::Lua53 | Target::Lua54))
+    }
+}
 
 #[derive(Clone, PartialEq)]
 pub enum FlagImplicit {
@@ -18,6 +90,15 @@ pub enum Inside {
     Then,
 }
 
+// what a switch table made at the start of a function needs to know about the function
+struct Start {
+    params: HashSet<String>, // in scope from the start
+    locals: HashSet<String>, // declared in the body, so not in scope yet
+    hoisted: usize,          // the `hoisted` lookups in scope at the start
+    loops: usize,            // loops around what is being generated
+    tables: String,
+}
+
 pub struct Generator<'g> {
     source: &'g Source,
 
@@ -26,17 +107,46 @@ pub struct Generator<'g> {
 
     loop_depth: usize,
     special_break: bool,
+    labels: usize,                  // loops given a `skip` label so far, to keep the labels apart
+    continues: Vec<(String, bool)>, // the label `skip` jumps to in each loop, and if it is used
+    discarded: bool, // the call being generated is a statement of its own
+
+    hoisted: Vec<HashMap<Pos, String>>, // module lookups made once per function, by function
+    spilled: HashSet<String>,           // module level names kept in `__locals`, past `LOCALS`
+    forward: Vec<HashSet<String>>,      // locals declared ahead of their statement, by block
+    appendable: Vec<HashSet<String>>,   // locals only the function itself can see, by function
+    buffers: HashMap<String, String>,   // the table collecting the appends to a name in a loop
+    externs: HashSet<String>,           // names from lua, whose structs have no constructor
+    globals: HashSet<String>,           // names marked `@global`, the only ones put in `_G`
+    implementing: Option<Expression>,   // what `Self` is in an implementation
+    starts: Vec<Start>,                 // every function being generated, innermost last
+    switches: usize,                    // switch tables made so far, to keep their names apart
+    module_tables: String,              // switch tables for ahead of the current module statement
+    unpacks: bool,                      // whether `__unpack` is used, see `unpack`
 
     method_calls: &'g HashMap<Pos, bool>,
+    builtins: &'g HashMap<Pos, Builtin>,
+    conversions: &'g HashSet<Pos>,
+    floats: &'g HashSet<Pos>,
+    strings: &'g HashSet<Pos>,
+    inlines: &'g HashMap<Pos, Expression>,
+    lookups: &'g HashSet<Pos>,
+    pure: &'g HashSet<Pos>,
+    packed: &'g HashSet<Pos>,
+    unpacked: &'g HashSet<Pos>,
+    defaults: &'g HashMap<Pos, Vec<String>>,
+    componentwise: &'g HashMap<Pos, Componentwise>,
+    nil_checks: &'g HashSet<Pos>,
     import_map: &'g HashMap<Pos, (String, String)>,
+    import_expansions: &'g HashMap<Pos, Vec<String>>,
+    import_paths: &'g HashMap<Pos, String>,
+
+    pub lines: Vec<usize>, // the Wu line of every generated line, see `wumap`
 }
 
 impl<'g> Generator<'g> {
-    pub fn new(
-        source: &'g Source,
-        method_calls: &'g HashMap<Pos, bool>,
-        import_map: &'g HashMap<Pos, (String, String)>,
-    ) -> Self {
+    // takes what the visitor noted about the source, all the generator knows besides the ast
+    pub fn new(source: &'g Source, visitor: &'g Visitor) -> Self {
         Generator {
             source,
 
@@ -45,19 +155,51 @@ impl<'g> Generator<'g> {
 
             loop_depth: 0,
             special_break: false,
-
-            method_calls,
-            import_map,
+            labels: 0,
+            continues: Vec::new(),
+            discarded: false,
+
+            hoisted: Vec::new(),
+            spilled: HashSet::new(),
+            forward: Vec::new(),
+            appendable: Vec::new(),
+            buffers: HashMap::new(),
+            externs: HashSet::new(),
+            globals: HashSet::new(),
+            implementing: None,
+            starts: Vec::new(),
+            switches: 0,
+            module_tables: String::new(),
+            unpacks: false,
+
+            method_calls: &visitor.method_calls,
+            builtins: &visitor.builtins,
+            conversions: &visitor.conversions,
+            floats: &visitor.floats,
+            strings: &visitor.strings,
+            inlines: &visitor.inliner.calls,
+            lookups: &visitor.lookups,
+            pure: &visitor.purity.calls,
+            packed: &visitor.escapes.packed,
+            unpacked: &visitor.escapes.unpacked,
+            defaults: &visitor.defaults,
+            componentwise: &visitor.componentwise,
+            nil_checks: &visitor.nil_checks,
+            import_map: &visitor.import_map,
+            import_expansions: &visitor.import_expansions,
+            import_paths: &visitor.import_paths,
+
+            lines: Vec::new(),
         }
     }
 
-    fn get_names(statements: &Vec<Statement>) -> Vec<String> {
+    fn get_names(&self, statements: &Vec<Statement>) -> Vec<String> {
         use self::StatementNode::*;
 
         let mut names = Vec::new();
 
         for statement in statements {
-            let mut statement = statement.clone();
+            let mut statement = statement.binding().clone();
             if let ExternBlock(ref s) = statement.node {
                 if let Variable(..) = s.node {
                     statement.node = s.node.clone()
@@ -66,11 +208,11 @@ impl<'g> Generator<'g> {
 
             match statement.node {
                 Variable(_, ref name, ..) => names.push(name.to_owned()),
-                Import(ref name, ref imports, _) => {
+                Import(ref path, ref imports, ref cfg, _) if enabled(cfg) => {
                     if imports.len() == 0 {
-                        names.push(name.to_owned())
+                        names.push(Self::module_name(path))
                     } else {
-                        names.append(&mut imports.to_owned())
+                        names.append(&mut self.import_locals(&statement.pos, imports))
                     }
                 }
                 _ => (),
@@ -82,10 +224,138 @@ impl<'g> Generator<'g> {
         names
     }
 
+    // `__state`, reading the module's variables, and `__reload`, which a freshly loaded version
+    // of the module calls with the exports of the running one: it takes over the old variables
+    // and struct tables, so existing instances get the new methods, then rebinds the old exports
+    // to the new functions
+    fn reloader(&self, ast: &Vec<Statement>) -> String {
+        use self::ExpressionNode::*;
+
+        let mut state = Vec::new();
+        let mut structs = Vec::new();
+
+        for statement in ast {
+            if let StatementNode::Variable(_, ref name, ref right, _) = statement.node {
+                if self.externs.contains(name) {
+                    continue;
+                }
+
+                match right.as_ref().map(|right| &right.node) {
+                    Some(Struct(..)) => structs.push(name),
+                    Some(Function(..) | Trait(..) | Module(..) | Extern(..) | ExternExpression(_)) => (),
+                    _ => state.push(name),
+                }
+            }
+        }
+
+        let mut saved = String::new();
+        let mut restored = String::new();
+        let mut rebound = String::new();
+
+        for name in state {
+            let (key, local) = (Self::make_valid(name), self.local(name));
+
+            saved.push_str(&format!("    {} = {},\n", key, local));
+            restored.push_str(&format!(
+                "  if state.{0} ~= nil then\n    {1} = state.{0}\n  end\n",
+                key, local
+            ));
+            rebound.push_str(&format!("  __exports.{} = {}\n", key, local))
+        }
+
+        for name in structs {
+            let (key, local) = (Self::make_valid(name), self.local(name));
+
+            restored.push_str(&format!("  if old.{} then\n", key));
+            restored.push_str(&format!("    for key, value in pairs({}) do\n", local));
+            restored.push_str("      if key ~= \"__meta\" then\n");
+            restored.push_str(&format!("        old.{}[key] = value\n", key));
+            restored.push_str("      end\n    end\n");
+            restored.push_str(&format!("    {} = old.{}\n  end\n", local, key));
+            rebound.push_str(&format!("  __exports.{} = {}\n", key, local))
+        }
+
+        let mut reloader = format!("\nfunction __exports.__state()\n  return {{\n{}  }}\nend\n", saved);
+
+        reloader.push_str("\nfunction __exports.__reload(old)\n");
+        reloader.push_str("  local state = old.__state and old.__state() or {}\n");
+        reloader.push_str(&restored);
+        reloader.push_str(&rebound);
+        reloader.push_str("  for key, value in pairs(__exports) do\n    old[key] = value\n  end\nend\n");
+
+        let rebind = self.rebinder(ast);
+
+        if !rebind.is_empty() {
+            let source = wumap::source_name(&self.source.file.0);
+
+            reloader.push_str("\npackage.wu_rebinds = package.wu_rebinds or {}\n");
+            reloader.push_str(&format!("package.wu_rebinds[{:?}] = function()\n", source));
+            reloader.push_str(&rebind);
+            reloader.push_str("end\n");
+        }
+
+        reloader
+    }
+
+    // named imports are locals holding what the imported module exported when it was loaded, so
+    // once the driver has reloaded a module, it calls every module's rebinder to look them up again
+    fn rebinder(&self, ast: &Vec<Statement>) -> String {
+        let mut rebind = String::new();
+
+        for statement in ast {
+            if let StatementNode::Import(ref path, ref specifics, ref cfg, _) = statement.node {
+                if !enabled(cfg) || specifics.is_empty() {
+                    continue;
+                }
+
+                let name = Self::module_name(path);
+
+                for local in self.import_locals(&statement.pos, specifics) {
+                    let specific = specifics
+                        .iter()
+                        .find(|(_, alias)| alias.as_ref() == Some(&local))
+                        .map_or(&local, |(specific, _)| specific);
+
+                    rebind.push_str(&format!(
+                        "  {0} = {1}['{2}']\n  __exports.{0} = {0}\n",
+                        local, name, specific
+                    ))
+                }
+            }
+        }
+
+        rebind
+    }
+
+    // local names bound by an import list, with wildcards expanded
+    fn import_locals(&self, pos: &Pos, imports: &[(String, Option<String>)]) -> Vec<String> {
+        let mut names = Vec::new();
+
+        for &(ref name, ref alias) in imports.iter() {
+            if name == "*" {
+                if let Some(expansion) = self.import_expansions.get(pos) {
+                    names.extend(expansion.iter().cloned())
+                }
+            } else {
+                names.push(alias.as_ref().unwrap_or(name).clone())
+            }
+        }
+
+        names
+    }
+
     pub fn generate(&mut self, ast: &'g Vec<Statement>) -> String {
         let mut result = "return (function()\n".to_string();
         let mut output = String::new();
 
+        self.spill(ast);
+
+        if !self.spilled.is_empty() {
+            output.push_str("local __locals = {}\n\n")
+        }
+
+        output.push_str(&self.declare_forward(ast));
+
         for statement in ast.iter() {
             let line = self.generate_statement(&statement);
             output.push_str(&line);
@@ -95,32 +365,101 @@ impl<'g> Generator<'g> {
             }
         }
 
+        if self.unpacks {
+            output.insert_str(0, "local __unpack = table.unpack or unpack\n\n")
+        }
+
         self.push_line(&mut result, &output);
 
-        result.push_str("  return {\n");
+        let hot = HOT.load(Ordering::Relaxed);
+
+        // the exports come from no line in particular
+        if hot {
+            result.push_str(&format!("{}  local __exports = {{\n", wumap::marker(0)));
+        } else {
+            result.push_str(&format!("{}  return {{\n", wumap::marker(0)));
+        }
 
         let mut assignments = String::new();
 
-        for name in Self::get_names(ast) {
-            assignments.push_str(&format!("  {0} = {0},\n", Self::make_valid(&name)))
+        for name in self.get_names(ast) {
+            assignments.push_str(&format!(
+                "  {} = {},\n",
+                Self::make_valid(&name),
+                self.local(&name)
+            ))
         }
 
         self.push_line(&mut result, &assignments);
 
         result.push_str("  }");
 
+        if hot {
+            let reloader = self.reloader(ast);
+
+            result.push('\n');
+            self.push_line(&mut result, &reloader);
+            result.push_str("  \n  return __exports");
+        }
+
         result.push_str("\nend)()");
 
-        result
+        let (mut lua, mut lines) = wumap::strip(&result);
+
+        if PRETTY_ERRORS.load(Ordering::Relaxed) {
+            let source = wumap::source_name(&self.source.file.0);
+
+            (lua, lines) = wumap::with_pretty_errors(&source, &lua, &lines)
+        }
+
+        self.lines = lines;
+
+        // anything else lua would put in `_G` is a mistake of the generator
+        if cfg!(debug_assertions) {
+            for (line, name) in globals::assigned(&lua) {
+                assert!(
+                    self.globals.contains(&name),
+                    "accidental global `{}` on line {} of the output",
+                    name,
+                    line
+                )
+            }
+        }
+
+        lua
     }
 
     fn generate_statement(&mut self, statement: &Statement) -> String {
         use self::StatementNode::*;
 
+        let _processing = ice::processing(&self.source.file, &statement.pos);
+
         let result = match statement.node {
-            Expression(ref expression) => self.generate_expression(expression),
+            Expression(ref expression) => {
+                self.discarded = matches!(expression.node, ExpressionNode::Call(..));
+
+                match expression.node {
+                    ExpressionNode::While(..) | ExpressionNode::For(..) => self.buffer(expression),
+                    _ => self.generate_expression(expression),
+                }
+            }
             Variable(_, ref left, ref right, _) => self.generate_local(left, right),
-            Assignment(ref left, ref right) => self.generate_assignment(left, right),
+            Global(ref binding) => {
+                if let Variable(_, ref name, ..) = binding.node {
+                    self.globals.insert(self.local(name));
+                }
+
+                let flag_backup = self.flag.replace(FlagImplicit::Global);
+                let result = self.generate_statement(binding);
+
+                self.flag = flag_backup;
+
+                result
+            }
+            Assignment(ref left, ref right) => match self.append(statement) {
+                Some(line) => line,
+                None => self.generate_assignment(left, right),
+            },
             SplatVariable(_, ref splats, ref right, _) => {
                 let mut left = String::new();
 
@@ -159,7 +498,13 @@ impl<'g> Generator<'g> {
                 result
             }
 
-            ExternBlock(..) => String::new(),
+            ExternBlock(ref statement) => {
+                if let Variable(_, ref name, ..) = statement.node {
+                    self.externs.insert(name.clone());
+                }
+
+                String::new()
+            }
 
             Return(ref expr) => {
                 if let Some(ref expr) = *expr {
@@ -170,7 +515,9 @@ impl<'g> Generator<'g> {
                     self.flag = Some(FlagImplicit::Return);
 
                     let line = match expr.node {
-                        Block(..) | If(..) | While(..) => self.generate_expression(expr),
+                        Block(..) | If(..) | Match(..) | While(..) => {
+                            self.generate_expression(expr)
+                        }
                         _ => format!("return {}", self.generate_expression(expr)),
                     };
 
@@ -182,47 +529,28 @@ impl<'g> Generator<'g> {
                 }
             }
 
-            Import(ref name, ref specifics, _) => {
-                let mut file_path = if let Some(new_path) = self.import_map.get(&statement.pos) {
-                    format!(
-                        "{}",
-                        new_path
-                            .1
-                            .clone()
-                            .split(&format!("/{}", name))
-                            .collect::<Vec<&str>>()[0]
-                            .to_string()
-                    )
-                } else {
-                    let my_folder = Path::new(&self.source.file.0).parent().unwrap();
-                    format!("{}/{}", my_folder.to_str().unwrap(), name)
-                };
+            Import(_, _, ref cfg, _) if !enabled(cfg) => String::new(),
 
-                if file_path.starts_with("./") {
-                    file_path = file_path[2..].to_string()
-                }
+            Import(ref path, ref specifics, ..) => {
+                let name = &Self::module_name(path);
 
-                let real_path = &Path::new(&file_path)
-                    .iter()
-                    .map(|x: &OsStr| format!("{}", x.to_str().unwrap()))
-                    .collect::<Vec<String>>()
-                    .join(".");
+                let path = self.import_paths.get(&statement.pos).unwrap_or(path);
+                let module = imported_name(self.source, self.import_map, statement, path);
 
-                let mut result:String;
+                let mut result = format!("local {} = require('{}')\n", name, module);
 
-                if self.import_map.get(&statement.pos).is_some() {
-                    let path = file_path[..file_path.len() - 1].to_string();
-                    result = format!(
-                        "package.path = package.path .. ';{0}?.lua;{0}?/init.lua'\n",
-                        path
-                    );
-                    result.push_str(&format!("local {0} = require('{0}')\n", name))
-                } else {
-                    result = format!("local {} = require('{}')\n", name, real_path)
-                }
+                for &(ref specific, ref alias) in specifics.iter() {
+                    if specific == "*" {
+                        if let Some(expansion) = self.import_expansions.get(&statement.pos) {
+                            for member in expansion.iter() {
+                                result.push_str(&format!("local {0} = {1}['{0}']\n", member, name))
+                            }
+                        }
+                    } else {
+                        let local = alias.as_ref().unwrap_or(specific);
 
-                for specific in specifics {
-                    result.push_str(&format!("local {0} = {1}['{0}']\n", specific, name))
+                        result.push_str(&format!("local {} = {}['{}']\n", local, name, specific))
+                    }
                 }
 
                 result.push('\n');
@@ -238,13 +566,22 @@ impl<'g> Generator<'g> {
                 }
             }
 
-            Skip => String::from("break"),
+            Skip => match self.continues.last_mut() {
+                Some((label, used)) => {
+                    *used = true;
 
-            Implement(ref name, ref body, _) => {
+                    format!("goto {}", label)
+                }
+
+                None => String::from("break"),
+            },
+
+            Implement(ref name, ref body, ref parent) => {
                 if let ExpressionNode::Block(ref content) = body.node {
                     let assign = self.generate_expression(name);
 
                     let flag_backup = self.flag.clone();
+                    let implementing = self.implementing.replace(name.clone());
 
                     let mut result = String::new();
 
@@ -265,12 +602,35 @@ impl<'g> Generator<'g> {
 
                                 let right = self.generate_expression(&right.clone().unwrap());
 
-                                result.push_str(&format!("{} = {}\n\n", assign, right))
+                                // each method at its own line, rather than the `implement`'s
+                                result.push_str(&format!(
+                                    "{}{} = {}\n\n",
+                                    wumap::marker((element.pos.0).0),
+                                    assign,
+                                    right
+                                ))
                             }
                         }
                     }
 
+                    // what the struct doesn't implement itself, the trait does
+                    let defaults = self.defaults.get(&statement.pos);
+
+                    if let (Some(parent), Some(defaults)) = (parent, defaults) {
+                        let parent = self.generate_expression(parent);
+
+                        for method in defaults.iter() {
+                            let method = Self::make_valid(method);
+
+                            result.push_str(&format!(
+                                "{}['{}'] = {}['{}']\n\n",
+                                assign, method, parent, method
+                            ))
+                        }
+                    }
+
                     self.flag = flag_backup;
+                    self.implementing = implementing;
 
                     result
                 } else {
@@ -279,7 +639,18 @@ impl<'g> Generator<'g> {
             }
         };
 
-        result
+        let result = if result.trim().is_empty() {
+            result
+        } else {
+            format!("{}{}", wumap::marker((statement.pos.0).0), result)
+        };
+
+        // outside functions, the tables of the switches in the functions this statement makes
+        if self.starts.is_empty() && !self.module_tables.is_empty() {
+            format!("{}{}", mem::take(&mut self.module_tables), result)
+        } else {
+            result
+        }
     }
 
     fn generate_expression(&mut self, expression: &Expression) -> String {
@@ -321,19 +692,57 @@ impl<'g> Generator<'g> {
                         )
                     }
 
+                    Operator::BitOr | Operator::BitAnd => {
+                        let value = self.generate_expression(left);
+
+                        return bitwise(op, &value, &self.generate_expression(right));
+                    }
+
                     _ => (),
                 }
 
+                if self.componentwise.contains_key(&expression.pos) {
+                    return self.generate_componentwise(expression);
+                }
+
+                // `false` is a value there, so it's `nil` that's checked for
+                if self.nil_checks.contains(&expression.pos) {
+                    let value = self.generate_expression(left);
+                    let other = self.generate_expression(right);
+
+                    return match op {
+                        Operator::Or => Self::nil_check(&value, &other, "__maybe"),
+                        _ => Self::nil_check(&value, "nil", &other),
+                    };
+                }
+
                 let folded = Parser::fold_expression(&expression);
 
                 if &folded != expression {
                     result = self.generate_expression(&folded)
+                } else if *op == Operator::Concat {
+                    let mut parts = Vec::new();
+
+                    self.concat_parts(expression, &mut parts);
+
+                    // `..` joins a chain at once already, but it holds every part in a register
+                    if parts.len() >= CONCAT_CHAIN {
+                        result = format!("table.concat({{{}}})", parts.join(", "))
+                    } else {
+                        result = format!("({})", parts.join(" .. "))
+                    }
                 } else {
+                    let mut right_side = self.generate_expression(right);
+
+                    if self.conversions.contains(&right.pos) {
+                        right_side = self.stringify(right, right_side)
+                    }
+
                     result.push_str(&format!(
                         "({} {} {})",
                         self.generate_expression(&left),
                         self.generate_operator(&op),
-                        self.generate_expression(&right),
+                        right_side,
                     ));
                 }
 
@@ -341,14 +750,64 @@ impl<'g> Generator<'g> {
             }
 
             Call(ref called, ref args) => {
+                // lua has no expression statements, so a call whose value is unused stays a call
+                let discarded = mem::replace(&mut self.discarded, false);
+
+                // and when it can't do anything either, it can go
+                if discarded && effect_free(expression, self.pure) {
+                    return String::new();
+                }
+
+                if !discarded && INLINE.load(Ordering::Relaxed) {
+                    if let Some(body) = self.inlines.get(&expression.pos) {
+                        return self.generate_expression(body);
+                    }
+                }
+
                 let flag_backup = self.flag.clone();
 
                 self.flag = Some(FlagImplicit::Assign("none".to_string()));
-                let mut caller = self.generate_expression(called);
-                let mut result = format!("{}(", caller);
+
+                if let Some(&builtin) = self.builtins.get(&called.pos) {
+                    let result = self.generate_builtin(builtin, called, args);
+
+                    self.flag = flag_backup;
+
+                    // a value on its own isn't a statement in lua
+                    return if discarded && !builtin.changes() {
+                        format!("local _ = {}", result)
+                    } else {
+                        result
+                    };
+                }
 
                 let prefix = self.method_calls.get(&called.pos).is_some();
 
+                // a receiver that isn't just a name is evaluated once, through lua's `:`
+                match called.node {
+                    Index(ref left, ref index, _)
+                        if prefix && !matches!(scope::unwrapped(left).node, Identifier(_)) =>
+                    {
+                        if let Identifier(ref method) = index.node {
+                            let receiver = self.generate_receiver(left);
+
+                            let args = args
+                                .iter()
+                                .map(|arg| self.generate_expression(arg))
+                                .collect::<Vec<_>>();
+
+                            self.flag = flag_backup;
+
+                            return format!("{}:{}({})", receiver, method, args.join(", "));
+                        }
+                    }
+
+                    _ => (),
+                }
+
+                let mut caller = self.generate_expression(called);
+                let mut result = format!("{}(", caller);
+
                 if let Index(ref left, ..) = called.node {
                     caller = self.generate_expression(left)
                 }
@@ -376,7 +835,7 @@ impl<'g> Generator<'g> {
                 result
             }
 
-            Module(ref content) => {
+            Module(ref content, ref exports) => {
                 if let Block(ref elements) = content.node {
                     let mut result = "(function()\n".to_string();
 
@@ -390,7 +849,13 @@ impl<'g> Generator<'g> {
 
                     let mut assignments = String::new();
 
-                    for name in Self::get_names(elements) {
+                    let names = if let Some(ref exports) = *exports {
+                        exports.clone()
+                    } else {
+                        self.get_names(elements)
+                    };
+
+                    for name in names {
                         assignments.push_str(&format!("{0} = {0},\n", name))
                     }
 
@@ -409,6 +874,17 @@ impl<'g> Generator<'g> {
             }
 
             Block(ref content) => {
+                if matches!(self.flag, None | Some(FlagImplicit::Return)) {
+                    // nothing the block declares is declared ahead in a dispatch
+                    self.forward.push(HashSet::new());
+                    let dispatch = self.dispatch(content);
+                    self.forward.pop();
+
+                    if let Some(dispatch) = dispatch {
+                        return dispatch;
+                    }
+                }
+
                 let flag_backup = self.flag.clone();
                 let flag = self.flag.clone();
 
@@ -446,7 +922,16 @@ impl<'g> Generator<'g> {
                 }
                 .to_string();
 
+                let chains = self.common_chains(content);
+
+                let forward = self.declare_forward(content);
+                result.push_str(&self.make_line(&forward));
+
                 for (i, element) in content.iter().enumerate() {
+                    if let Some(lines) = chains.get(&i) {
+                        result.push_str(&self.make_line(lines))
+                    }
+
                     if i == content.len() - 1 {
                         if in_return {
                             self.flag = flag.clone();
@@ -459,7 +944,7 @@ impl<'g> Generator<'g> {
                                     _ => match &self.flag.clone().unwrap() {
                                         &FlagImplicit::Return => {
                                             let line = match expression.node {
-                                                Block(..) | If(..) | While(..) => {
+                                                Block(..) | If(..) | Match(..) | While(..) => {
                                                     self.generate_expression(expression)
                                                 }
 
@@ -473,6 +958,12 @@ impl<'g> Generator<'g> {
                                                 ),
                                             };
 
+                                            let line = if line.is_empty() {
+                                                line
+                                            } else {
+                                                format!("{}{}", wumap::marker((element.pos.0).0), line)
+                                            };
+
                                             result.push_str(&self.make_line(&line));
 
                                             break;
@@ -489,6 +980,8 @@ impl<'g> Generator<'g> {
                     result.push_str(&self.make_line(&line));
                 }
 
+                self.hoisted.pop();
+                self.forward.pop();
                 self.flag = flag_backup;
 
                 if let Some(ref f) = flag {
@@ -551,15 +1044,48 @@ impl<'g> Generator<'g> {
                     result.push_str(&format!("  local {} = {{...}}\n", name))
                 }
 
+                let hoisted = self.hoist(params, body);
+                result.push_str(&hoisted);
+
+                let mut appendable = params.iter().map(|param| param.0.clone()).collect();
+                let mut captured = HashSet::new();
+
+                scope::locals_in(body, &mut appendable);
+                scope::captured_in(body, &mut captured);
+
+                self.appendable
+                    .push(appendable.difference(&captured).cloned().collect());
+
+                let mut start = Start {
+                    params: params.iter().map(|param| param.0.clone()).collect(),
+                    locals: HashSet::new(),
+                    hoisted: self.hoisted.len(),
+                    loops: 0,
+                    tables: String::new(),
+                };
+
+                if is_method {
+                    start.params.insert("self".to_string());
+                }
+
+                scope::locals_in(body, &mut start.locals);
+
+                self.starts.push(start);
+
                 let flag_backup = self.flag.clone();
                 self.flag = Some(FlagImplicit::Return);
 
                 let line = match body.node {
-                    Block(..) | If(..) | While(..) => self.generate_expression(body),
+                    Block(..) | If(..) | Match(..) | While(..) => self.generate_expression(body),
                     _ => format!("return {}", self.generate_expression(body)),
                 };
 
                 self.flag = flag_backup;
+                self.hoisted.pop();
+                self.appendable.pop();
+
+                let tables = self.starts.pop().unwrap().tables;
+                result.push_str(&self.make_line(&tables));
 
                 result.push_str(&&line);
                 result.push_str("end\n");
@@ -607,6 +1133,12 @@ impl<'g> Generator<'g> {
             }
 
             Index(ref source, ref index, is_braces) => {
+                let hoisted = self.hoisted.iter().rev().find_map(|h| h.get(&expression.pos));
+
+                if let Some(local) = hoisted {
+                    return local.clone();
+                }
+
                 let source = self.generate_expression(source);
 
                 let index = if let Identifier(ref name) = index.node {
@@ -736,9 +1268,15 @@ impl<'g> Generator<'g> {
             }
 
             For(ref iterator, ref body) => {
+                let goto = GOTO.load(Ordering::Relaxed);
+
                 let flag_backup = self.flag.clone();
                 self.inside.push(Inside::Then);
-                self.special_break = true;
+                self.special_break = !goto;
+
+                if let Some(start) = self.starts.last_mut() {
+                    start.loops += 1
+                }
 
                 if self.inside.contains(&Inside::Loop) {
                     self.loop_depth += 1
@@ -773,34 +1311,44 @@ impl<'g> Generator<'g> {
                     format!("for __iterator_{} = 1, {} do\n", self.loop_depth, iterator)
                 };
 
-                let mut body_string = format!("\nlocal __brk_{} = false\n", self.loop_depth); // doing this to remove redundant 'do' and 'end'
+                if goto {
+                    if !whole.ends_with('\n') {
+                        whole.push('\n')
+                    }
+
+                    let body_string = self.goto_body(body);
 
-                body_string.push_str("repeat\n");
+                    self.push_line(&mut whole, &body_string);
+                } else {
+                    let mut body_string = format!("\nlocal __brk_{} = false\n", self.loop_depth); // doing this to remove redundant 'do' and 'end'
 
-                if let Block(ref content) = body.node {
-                    for (i, element) in content.iter().enumerate() {
-                        if i == content.len() - 1 {
-                            if StatementNode::Skip == element.node {
-                                break;
-                            } else {
-                                if let StatementNode::Expression(ref expression) = element.node {
-                                    if Empty == expression.node {
-                                        break;
+                    body_string.push_str("repeat\n");
+
+                    if let Block(ref content) = body.node {
+                        for (i, element) in content.iter().enumerate() {
+                            if i == content.len() - 1 {
+                                if StatementNode::Skip == element.node {
+                                    break;
+                                } else {
+                                    if let StatementNode::Expression(ref expression) = element.node {
+                                        if Empty == expression.node {
+                                            break;
+                                        }
                                     }
                                 }
                             }
-                        }
 
-                        body_string.push_str(&self.generate_statement(&element));
-                        body_string.push('\n')
+                            body_string.push_str(&self.generate_statement(element));
+                            body_string.push('\n')
+                        }
                     }
-                }
 
-                // body_string.push_str(&format!("::__while_{}::\n", self.loop_depth));
-                body_string.push_str("until true\n");
-                body_string.push_str(&format!("if __brk_{} then break end", self.loop_depth));
+                    // body_string.push_str(&format!("::__while_{}::\n", self.loop_depth));
+                    body_string.push_str("until true\n");
+                    body_string.push_str(&format!("if __brk_{} then break end", self.loop_depth));
 
-                self.push_line(&mut whole, &body_string);
+                    self.push_line(&mut whole, &body_string);
+                }
 
                 whole.push_str("end\n");
 
@@ -812,6 +1360,10 @@ impl<'g> Generator<'g> {
                     result.push_str(&whole)
                 }
 
+                if let Some(start) = self.starts.last_mut() {
+                    start.loops -= 1
+                }
+
                 self.flag = flag_backup;
                 self.inside.pop();
                 self.inside.pop();
@@ -827,6 +1379,10 @@ impl<'g> Generator<'g> {
                 let flag_backup = self.flag.clone();
                 self.inside.push(Inside::Then);
 
+                if let Some(start) = self.starts.last_mut() {
+                    start.loops += 1
+                }
+
                 if self.inside.contains(&Inside::Loop) {
                     self.loop_depth += 1
                 } else {
@@ -851,31 +1407,37 @@ impl<'g> Generator<'g> {
 
                 let mut whole = format!("while {} do\n", condition);
 
-                let mut body_string = "repeat\n".to_string(); // doing this to remove redundant 'do' and 'end'
+                if GOTO.load(Ordering::Relaxed) {
+                    let body_string = self.goto_body(body);
 
-                if let Block(ref content) = body.node {
-                    for (i, element) in content.iter().enumerate() {
-                        if i == content.len() - 1 {
-                            if StatementNode::Skip == element.node {
-                                break;
-                            } else {
-                                if let StatementNode::Expression(ref expression) = element.node {
-                                    if Empty == expression.node {
-                                        break;
+                    self.push_line(&mut whole, &body_string);
+                } else {
+                    let mut body_string = "repeat\n".to_string(); // doing this to remove redundant 'do' and 'end'
+
+                    if let Block(ref content) = body.node {
+                        for (i, element) in content.iter().enumerate() {
+                            if i == content.len() - 1 {
+                                if StatementNode::Skip == element.node {
+                                    break;
+                                } else {
+                                    if let StatementNode::Expression(ref expression) = element.node {
+                                        if Empty == expression.node {
+                                            break;
+                                        }
                                     }
                                 }
                             }
-                        }
 
-                        body_string.push_str(&self.generate_statement(&element));
-                        body_string.push('\n')
+                            body_string.push_str(&self.generate_statement(element));
+                            body_string.push('\n')
+                        }
                     }
-                }
 
-                // body_string.push_str(&format!("::__while_{}::\n", self.loop_depth));
-                body_string.push_str("until true\n");
+                    // body_string.push_str(&format!("::__while_{}::\n", self.loop_depth));
+                    body_string.push_str("until true\n");
 
-                self.push_line(&mut whole, &body_string);
+                    self.push_line(&mut whole, &body_string);
+                }
 
                 whole.push_str("end\n");
 
@@ -885,6 +1447,10 @@ impl<'g> Generator<'g> {
                     result.push_str(&whole)
                 }
 
+                if let Some(start) = self.starts.last_mut() {
+                    start.loops -= 1
+                }
+
                 self.flag = flag_backup;
                 self.inside.pop();
                 self.inside.pop();
@@ -896,6 +1462,50 @@ impl<'g> Generator<'g> {
                 result
             }
 
+            // the variants of a `@flags` enum are bits, 1, 2, 4 and on
+            Enum(_, ref variants, _, true) => {
+                let mut inner = String::new();
+
+                for (bit, (variant, _)) in variants.iter().enumerate() {
+                    let variant = Self::make_valid(variant);
+
+                    inner.push_str(&format!("['{}'] = {},\n", variant, 1u64 << bit))
+                }
+
+                format!("{{\n{}}}", self.make_line(&inner))
+            }
+
+            // each variant a table tagged with its name, holding what it carries from 1 on
+            Enum(_, ref variants, ..) => {
+                let mut inner = String::new();
+
+                for (variant, carried) in variants.iter() {
+                    let variant = Self::make_valid(variant);
+
+                    if carried.is_empty() {
+                        inner.push_str(&format!("['{0}'] = {{tag = \"{0}\"}},\n", variant))
+                    } else {
+                        let values = (1..=carried.len())
+                            .map(|n| format!("__{}", n))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+
+                        inner.push_str(&format!(
+                            "['{0}'] = function({1}) return {{tag = \"{0}\", {1}}} end,\n",
+                            variant, values
+                        ))
+                    }
+                }
+
+                format!("{{\n{}}}", self.make_line(&inner))
+            }
+
+            Match(ref subject, ref arms) => {
+                let lowered = self.lower_match(expression, subject, arms);
+
+                self.generate_expression(&lowered)
+            }
+
             Initialization(ref name, ref body) => {
                 let mut inner = String::new();
 
@@ -907,11 +1517,32 @@ impl<'g> Generator<'g> {
                     ))
                 }
 
-                format!(
-                    "setmetatable({{\n{}}}, {{__index={}}})",
-                    self.make_line(&inner),
-                    self.generate_expression(name)
-                )
+                let fields = if inner.is_empty() {
+                    "{}".to_string()
+                } else {
+                    format!("{{\n{}}}", self.make_line(&inner))
+                };
+
+                let name = match (&name.node, &self.implementing) {
+                    (Identifier(ref name), Some(ref implementing)) if name == "Self" => {
+                        implementing.clone()
+                    }
+
+                    _ => (**name).clone(),
+                };
+
+                let is_extern = scope::lookup_path(&name)
+                    .is_some_and(|path| self.externs.contains(&path[0]));
+
+                if is_extern {
+                    format!(
+                        "setmetatable({}, {{__index={}}})",
+                        fields,
+                        self.generate_expression(&name)
+                    )
+                } else {
+                    format!("{}.__new({})", self.generate_expression(&name), fields)
+                }
             }
 
             Extern(_, ref lua) => {
@@ -927,14 +1558,28 @@ impl<'g> Generator<'g> {
             Bool(ref n) => format!("{}", n),
             Str(ref n) => format!("\"{}\"", n.replace("\\n", "\\\\n").replace('\n', "\\n")),
             Char(ref n) => format!("\"{}\"", n),
-            Identifier(ref n) => Self::make_valid(n),
+            Identifier(ref n) if n == "Self" && self.implementing.is_some() => {
+                let implementing = self.implementing.clone().unwrap();
+                self.generate_expression(&implementing)
+            }
+
+            Identifier(ref n) => self.local(n),
+
+            Cast(ref a, ref t) if t.node.strong_cmp(&TypeNode::Str) => {
+                let a_string = self.generate_expression(a);
+
+                if self.strings.contains(&a.pos) {
+                    a_string
+                } else {
+                    self.stringify(a, a_string)
+                }
+            }
 
             Cast(ref a, ref t) => {
                 use self::TypeNode::*;
 
                 let result = match t.node {
                     Float => "tonumber(",
-                    Str => "tostring(",
                     Int => "math.floor(tonumber(",
                     _ => "(",
                 };
@@ -947,7 +1592,9 @@ impl<'g> Generator<'g> {
                 )
             }
             UnwrapSplat(ref expression) => {
-                format!("table.unpack({})", self.generate_expression(expression))
+                let value = self.generate_expression(expression);
+
+                self.unpack(&value)
             }
             Unwrap(ref expression) => {
                 self.generate_expression(expression)
@@ -959,9 +1606,596 @@ impl<'g> Generator<'g> {
             _ => String::new(),
         };
 
+        // tuples kept as one value are tables, see `Escapes`
+        if made(&expression.node) && self.packed.contains(&expression.pos) {
+            format!("{{ {} }}", result)
+        } else if kept(&expression.node) && self.unpacked.contains(&expression.pos) {
+            self.unpack(&result)
+        } else {
+            result
+        }
+    }
+
+    // the values of the table `value`: lua 5.1 and luajit only have the global `unpack`, which
+    // later versions moved to `table.unpack`, so for them it's whichever the lua running it has
+    fn unpack(&mut self, value: &str) -> String {
+        match *TARGET.lock().unwrap() {
+            Target::Lua51 | Target::LuaJit => {
+                self.unpacks = true;
+
+                format!("__unpack({})", value)
+            }
+
+            _ => format!("table.unpack({})", value),
+        }
+    }
+
+    // moves module level names past `LOCALS` into a `__locals` table, when nothing else in the
+    // module binds the same name, so every use of it can be rewritten
+    fn spill(&mut self, ast: &Vec<Statement>) {
+        let mut locals = HashSet::new();
+        let mut variables = Vec::new();
+        let mut nested = HashSet::new();
+
+        for statement in ast {
+            match statement.node {
+                StatementNode::Variable(_, _, Some(ref right), _)
+                    if scope::undeclared(&right.node) =>
+                {
+                    scope::bound_in_expression(right, &mut nested)
+                }
+
+                StatementNode::Variable(_, ref name, ref right, _) => {
+                    if let Some(ref right) = *right {
+                        scope::bound_in_expression(right, &mut nested)
+                    }
+
+                    if locals.insert(name.clone()) {
+                        variables.push(name.clone())
+                    }
+                }
+
+                _ => {
+                    let mut bound = HashSet::new();
+
+                    scope::bound_in_statement(statement, &mut bound);
+
+                    if let StatementNode::Import(..) | StatementNode::SplatVariable(..) =
+                        statement.node
+                    {
+                        locals.extend(bound.iter().cloned())
+                    }
+
+                    nested.extend(bound)
+                }
+            }
+        }
+
+        let mut count = locals.len();
+
+        for name in variables.iter().rev() {
+            if count <= LOCALS {
+                break;
+            }
+
+            if !nested.contains(name) {
+                self.spilled.insert(name.clone());
+
+                count -= 1
+            }
+        }
+    }
+
+    // `local __a_b_c = a['b']['c']` ahead of the statement that first looks up a chain the block
+    // looks up again, when nothing in the block can change what it finds; by statement
+    fn common_chains(&mut self, content: &[Statement]) -> HashMap<usize, String> {
+        let mut lines = HashMap::<usize, String>::new();
+        let mut hoisted = HashMap::new();
+
+        let mut changed = HashSet::new();
+
+        // module level names are kept for declarations, see `spill`
+        if self.appendable.is_empty() || !scope::settled_block(content, self.pure, &mut changed) {
+            self.hoisted.push(hoisted);
+
+            return lines;
+        }
+
+        let mut found = Vec::new();
+
+        for (i, statement) in content.iter().enumerate() {
+            let mut chains = Vec::new();
+
+            scope::chains_in(statement, &mut chains);
+
+            found.extend(chains.into_iter().map(|prefixes| (i, prefixes)));
+        }
+
+        let is_hoisted = |pos: &Pos| self.hoisted.iter().any(|h| h.contains_key(pos));
+
+        found.retain(|(_, prefixes)| !prefixes.iter().any(|(node, _)| is_hoisted(&node.pos)));
+
+        let mut counts = HashMap::new();
+
+        for (_, prefixes) in found.iter() {
+            for (_, path) in prefixes {
+                *counts.entry(path).or_insert(0) += 1
+            }
+        }
+
+        // each chain is looked up through the longest chain it starts with that is repeated
+        let mut uses = Vec::<(&Vec<String>, Vec<(usize, &Expression)>)>::new();
+
+        for (i, prefixes) in found.iter() {
+            let chosen = prefixes
+                .iter()
+                .find(|(_, path)| counts[path] > 1 && !changed.contains(&path[0]));
+
+            if let Some((node, path)) = chosen {
+                match uses.iter_mut().find(|(other, _)| *other == path) {
+                    Some((_, nodes)) => nodes.push((*i, node)),
+                    None => uses.push((path, vec![(*i, node)])),
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+
+        for (path, nodes) in uses {
+            // from the first statement sure to look it up anyway, so hoisting it can't fail where
+            // the lookup wouldn't have happened
+            let start = nodes
+                .iter()
+                .find(|(i, node)| scope::evaluates(&content[*i], &node.pos));
+
+            let (first, node) = match start {
+                Some(&(first, node)) => (first, node),
+                None => continue,
+            };
+
+            let nodes = nodes
+                .into_iter()
+                .filter(|(i, _)| *i >= first)
+                .collect::<Vec<_>>();
+
+            if nodes.len() < 2 {
+                continue;
+            }
+
+            let mut local = format!("__{}", path.join("_"));
+
+            while !names.insert(local.clone()) {
+                local.push('_')
+            }
+
+            let lookup = self.generate_expression(node);
+
+            lines
+                .entry(first)
+                .or_default()
+                .push_str(&format!("local {} = {}\n", local, lookup));
+
+            for (_, node) in nodes {
+                hoisted.insert(node.pos.clone(), local.clone());
+            }
+        }
+
+        self.hoisted.push(hoisted);
+
+        lines
+    }
+
+    // a `match` as the block it runs as, an `if` on the tag of the value matched
+    fn lower_match(
+        &self,
+        expression: &Expression,
+        subject: &Expression,
+        arms: &[(Option<String>, Vec<String>, Expression, Pos)],
+    ) -> Expression {
+        use self::ExpressionNode::*;
+
+        let pos = &expression.pos;
+        let name = format!("__match_{}_{}", (pos.0).0, (pos.1).0);
+
+        let matched = Expression::new(Identifier(name.clone()), pos.clone());
+
+        let declaration = Statement::new(
+            StatementNode::Variable(
+                Type::from(TypeNode::Nil),
+                name,
+                Some(subject.clone()),
+                false,
+            ),
+            pos.clone(),
+        );
+
+        let mut branches = Vec::new();
+
+        for (n, (variant, names, body, pos)) in arms.iter().enumerate() {
+            let mut content = Vec::new();
+
+            for (i, name) in names.iter().enumerate() {
+                if name == "_" {
+                    continue;
+                }
+
+                let value = Expression::new(
+                    Index(
+                        Rc::new(matched.clone()),
+                        Rc::new(Expression::new(Int(i as i64 + 1), pos.clone())),
+                        true,
+                    ),
+                    pos.clone(),
+                );
+
+                content.push(Statement::new(
+                    StatementNode::Variable(
+                        Type::from(TypeNode::Nil),
+                        name.clone(),
+                        Some(value),
+                        false,
+                    ),
+                    pos.clone(),
+                ))
+            }
+
+            match body.node {
+                Block(ref statements) => content.extend(statements.iter().cloned()),
+                _ => content.push(Statement::new(
+                    StatementNode::Expression(body.clone()),
+                    body.pos.clone(),
+                )),
+            }
+
+            let condition = match variant {
+                Some(variant) if n == 0 || n + 1 < arms.len() => {
+                    let tag = Expression::new(
+                        Index(
+                            Rc::new(matched.clone()),
+                            Rc::new(Expression::new(Identifier("tag".to_string()), pos.clone())),
+                            false,
+                        ),
+                        pos.clone(),
+                    );
+
+                    Some(Expression::new(
+                        Binary(
+                            Rc::new(tag),
+                            Operator::Eq,
+                            Rc::new(Expression::new(Str(variant.clone()), pos.clone())),
+                        ),
+                        pos.clone(),
+                    ))
+                }
+
+                _ => None,
+            };
+
+            let body = Expression::new(Block(content), body.pos.clone());
+
+            branches.push((condition, body, pos.clone()))
+        }
+
+        let mut content = vec![declaration];
+
+        if !branches.is_empty() {
+            let (condition, body, pos) = branches.remove(0);
+
+            let condition = condition.unwrap_or_else(|| Expression::new(Bool(true), pos.clone()));
+            let elses = if branches.is_empty() { None } else { Some(branches) };
+
+            let switch = Expression::new(If(Rc::new(condition), Rc::new(body), elses), pos.clone());
+
+            content.push(Statement::new(StatementNode::Expression(switch), pos))
+        }
+
+        Expression::new(Block(content), expression.pos.clone())
+    }
+
+    // a switch statement over `SWITCH_TABLE` integers or more that are close together, as a look up
+    // of its case in a table of functions, made where everything the cases use is in scope: at
+    // the start of the innermost function with parameters they use, or else once, ahead of the
+    // outermost function
+    fn dispatch(&mut self, content: &[Statement]) -> Option<String> {
+        use self::ExpressionNode::*;
+
+        let (name, right, switch) = match content {
+            [declaration, switch] => match (&declaration.node, &switch.node) {
+                (
+                    StatementNode::Variable(_, ref name, Some(ref right), _),
+                    StatementNode::Expression(ref switch),
+                ) if name.starts_with("__switch_tmp_") => (name, right, switch),
+
+                _ => return None,
+            },
+
+            _ => return None,
+        };
+
+        let mut branches = Vec::new();
+
+        if let If(ref condition, ref body, ref elses) = switch.node {
+            branches.push((Some(&**condition), &**body));
+
+            for (condition, body, _) in elses.iter().flatten() {
+                branches.push((condition.as_ref(), body))
+            }
+        }
+
+        let mut cases = Vec::new();
+
+        for (condition, body) in branches {
+            match condition?.node {
+                Binary(ref left, Operator::Eq, ref right) => match (&left.node, &right.node) {
+                    (Int(case), Identifier(ref switched)) if switched == name => {
+                        cases.push((*case, body))
+                    }
+
+                    _ => return None,
+                },
+
+                _ => return None,
+            }
+        }
+
+        let lowest = cases.iter().map(|case| case.0).min()?;
+        let highest = cases.iter().map(|case| case.0).max()?;
+
+        let distinct = cases.iter().map(|case| case.0).collect::<HashSet<_>>();
+
+        let dense = ((highest - lowest) as usize) < cases.len() * 2;
+
+        if cases.len() < SWITCH_TABLE || distinct.len() != cases.len() || !dense {
+            return None;
+        }
+
+        let mut free = HashSet::new();
+
+        for (_, body) in cases.iter() {
+            if scope::leaves(body) {
+                return None;
+            }
+
+            free.extend(scope::free_in(body));
+        }
+
+        if self.starts.is_empty() || self.starts.iter().any(|start| !start.locals.is_disjoint(&free)) {
+            return None;
+        }
+
+        let at = self.starts.iter().rposition(|start| !start.params.is_disjoint(&free));
+
+        // a table made every time the function is called only pays off when a loop reuses it
+        if at.is_some_and(|at| self.starts[at..].iter().all(|start| start.loops == 0)) {
+            return None;
+        }
+
+        // the cases are made where the table is, so only the lookups hoisted there are in scope
+        let depth = at.map_or(0, |at| self.starts[at].hoisted);
+
+        let hoisted = self.hoisted.split_off(depth);
+        let buffers = mem::take(&mut self.buffers);
+        let flag = self.flag.take();
+
+        self.switches += 1;
+
+        let table = format!("__switch_{}", self.switches);
+        let mut functions = String::new();
+
+        for (case, body) in cases {
+            let body = match body.node {
+                Block(_) => body.clone(),
+                _ => Expression::new(
+                    Block(vec![Statement::new(
+                        StatementNode::Expression(body.clone()),
+                        body.pos.clone(),
+                    )]),
+                    body.pos.clone(),
+                ),
+            };
+
+            let function = Expression::new(
+                Function(Vec::new(), Type::from(TypeNode::Nil), Rc::new(body), false),
+                switch.pos.clone(),
+            );
+
+            let function = self.generate_expression(&function);
+
+            functions.push_str(&format!("[{}] = {},\n", case, function.trim_end()))
+        }
+
+        self.hoisted.extend(hoisted);
+        self.buffers = buffers;
+        self.flag = flag.clone();
+
+        let made = format!("local {} = {{\n{}}}\n", table, self.make_line(&functions));
+
+        match at {
+            Some(at) => self.starts[at].tables.push_str(&made),
+            None => self.module_tables.push_str(&made),
+        }
+
+        // a switch a function ends with gives what it returns
+        let call = if flag == Some(FlagImplicit::Return) {
+            "return __case()"
+        } else {
+            "__case()"
+        };
+
+        Some(format!(
+            "do\n  local __case = {}[{}]\n  if __case then\n    {}\n  end\nend\n",
+            table,
+            self.generate_expression(right),
+            call
+        ))
+    }
+
+    // `receiver` to call or index into, in parentheses when lua wants them there
+    fn generate_receiver(&mut self, receiver: &Expression) -> String {
+        use self::ExpressionNode::*;
+
+        let generated = self.generate_expression(receiver);
+
+        match scope::unwrapped(receiver).node {
+            Identifier(_) | Call(..) | Index(..) | Initialization(..) => generated,
+            _ => format!("({})", generated),
+        }
+    }
+
+    // `if_nil` where `value` is `nil`, else `otherwise`, which sees `value` as `__maybe`; unlike
+    // `or` and `and`, telling `false` from `nil`. Either side is only worked out when it's taken
+    fn nil_check(value: &str, if_nil: &str, otherwise: &str) -> String {
+        format!(
+            "(function(__maybe) if __maybe == nil then return {} end return {} end)({})",
+            if_nil, otherwise, value
+        )
+    }
+
+    // a call of an array or optional method, see `visitor::Builtin`
+    fn generate_builtin(
+        &mut self,
+        builtin: Builtin,
+        called: &Expression,
+        args: &[Expression],
+    ) -> String {
+        let receiver = match called.node {
+            ExpressionNode::Index(ref receiver, ..) => receiver,
+            _ => unreachable!(),
+        };
+
+        // a known length is just that, unless getting at what has it does something
+        if let Builtin::Len(Some(len)) | Builtin::Count(len) = builtin {
+            if let ExpressionNode::Identifier(_) = scope::unwrapped(receiver).node {
+                return len.to_string();
+            }
+        }
+
+        let receiver = self.generate_receiver(receiver);
+
+        let args = args
+            .iter()
+            .map(|arg| self.generate_expression(arg))
+            .collect::<Vec<_>>();
+
+        match builtin {
+            Builtin::Len(_) => format!("#{}", receiver),
+            Builtin::Count(_) => format!("select(\"#\", {})", receiver),
+            Builtin::Push | Builtin::Insert => {
+                format!("table.insert({}, {})", receiver, args.join(", "))
+            }
+            Builtin::Pop | Builtin::Remove => {
+                let args = Some(receiver).into_iter().chain(args).collect::<Vec<_>>();
+
+                format!("table.remove({})", args.join(", "))
+            }
+            // like `or`, unless `false` is a value
+            Builtin::UnwrapOr(false) => format!("({} or {})", receiver, args[0]),
+            Builtin::UnwrapOr(true) => Self::nil_check(&receiver, &args[0], "__maybe"),
+            Builtin::IsSome => format!("({} ~= nil)", receiver),
+            Builtin::IsNone => format!("({} == nil)", receiver),
+            // in base 10 only digits are a number, so a fraction is nothing
+            Builtin::ParseInt => format!("tonumber({}, 10)", args[0]),
+            Builtin::ParseFloat => format!("tonumber({})", args[0]),
+            Builtin::CheckedAdd | Builtin::CheckedSub | Builtin::CheckedMul => {
+                format!("{}({}, {})", checked(builtin), receiver, args[0])
+            }
+            // every flag of `b` is in `a`
+            Builtin::Contains => format!(
+                "(function(a, b) return {} == b end)({}, {})",
+                bitwise(&Operator::BitAnd, "a", "b"),
+                receiver,
+                args[0]
+            ),
+            Builtin::ToStr => match args[0].parse::<usize>() {
+                Ok(precision) => format!("string.format(\"%.{}f\", {})", precision, receiver),
+                _ => format!("string.format(\"%.\" .. {} .. \"f\", {})", args[0], receiver),
+            },
+        }
+    }
+
+    // `local a, b` for the locals of a block that functions ahead of them use, see
+    // `scope::forward_declared`; their declarations then leave out the `local`
+    fn declare_forward(&mut self, content: &[Statement]) -> String {
+        let names = scope::forward_declared(content)
+            .into_iter()
+            .filter(|name| !self.spilled.contains(name))
+            .collect::<Vec<_>>();
+
+        self.forward.push(names.iter().cloned().collect());
+
+        if names.is_empty() {
+            String::new()
+        } else {
+            format!("local {}\n", names.join(", "))
+        }
+    }
+
+    // how a name is referred to, `__locals.name` when it was spilled
+    fn local(&self, name: &String) -> String {
+        if self.spilled.contains(name) {
+            format!("__locals.{}", Self::make_valid(name))
+        } else {
+            Self::make_valid(name)
+        }
+    }
+
+    // `local __love_graphics_draw = love['graphics']['draw']` for every module function looked
+    // up more than once in a function body
+    fn hoist(&mut self, params: &[(String, Type)], body: &Expression) -> String {
+        let mut found = Vec::new();
+        let mut assigned = HashSet::new();
+
+        scope::lookups_in(body, self.lookups, &mut found, &mut assigned);
+
+        let mut bound = params.iter().map(|param| param.0.clone()).collect::<HashSet<_>>();
+
+        scope::bound_in_expression(body, &mut bound);
+
+        let mut result = String::new();
+        let mut hoisted = HashMap::new();
+        let mut locals = HashMap::new();
+
+        for (pos, path) in found.iter() {
+            let repeated = found.iter().filter(|(_, other)| other == path).count() > 1;
+
+            if !repeated || bound.contains(&path[0]) || assigned.contains(path) {
+                continue;
+            }
+
+            if bound.len() + locals.len() >= LOCALS {
+                break;
+            }
+
+            if !locals.contains_key(path) {
+                let mut local = format!("__{}", path.join("_"));
+
+                while locals.values().any(|other| *other == local) {
+                    local.push('_')
+                }
+
+                let mut lookup = self.local(&path[0]);
+
+                for member in path[1..].iter() {
+                    lookup.push_str(&format!("['{}']", Self::make_valid(member)))
+                }
+
+                result.push_str(&format!("  local {} = {}\n", local, lookup));
+
+                locals.insert(path.clone(), local);
+            }
+
+            hoisted.insert(pos.clone(), locals[path].clone());
+        }
+
+        self.hoisted.push(hoisted);
+
         result
     }
 
+    pub fn module_name(path: &str) -> String {
+        path.rsplit('/').next().unwrap().to_string()
+    }
+
     fn make_valid(n: &String) -> String {
         let mut result = String::new();
 
@@ -981,13 +2215,19 @@ impl<'g> Generator<'g> {
     fn generate_local(&mut self, name: &str, right: &Option<Expression>) -> String {
         let flag_backup = self.flag.clone();
 
-        let name = Self::make_valid(&name.to_string());
+        let spilled = self.spilled.contains(name);
+        let forward = self.forward.last().is_some_and(|names| names.contains(name));
+        let declared = name;
+        let name = self.local(&name.to_string());
+
+        // only `@global` bindings are left for lua to put in `_G`
+        let local = self.flag != Some(FlagImplicit::Global) && !spilled && !forward;
 
         let mut result = {
-            let output = if self.flag == Some(FlagImplicit::Global) {
-                name.to_owned()
-            } else {
+            let output = if local {
                 format!("local {}", name)
+            } else {
+                name.to_owned()
             };
 
             self.flag = Some(FlagImplicit::Assign(name.to_string()));
@@ -997,18 +2237,53 @@ impl<'g> Generator<'g> {
 
         if let &Some(ref right) = right {
             if let ExpressionNode::Function(..) = right.node {
+                let prefix = if local { "local " } else { "" };
+
                 result = self.generate_expression(right);
-                result = result.replacen("function", &format!("function {}", name), 1);
+                result = result.replacen("function", &format!("{}function {}", prefix, name), 1);
             } else {
+                if let ExpressionNode::Extern(..) | ExpressionNode::ExternExpression(..) = right.node {
+                    self.externs.insert(declared.to_string());
+                }
+
+                if scope::undeclared(&right.node) {
+                    return String::new();
+                }
+
                 let right_str = match right.node {
-                    ExpressionNode::Struct(..) => "{}".to_string(),
-                    ExpressionNode::Extern(_, ref lua) if lua.is_none() => return String::new(),
-                    ExpressionNode::Trait(..) | ExpressionNode::ExternExpression(..) => return String::new(),
-                    
+                    ExpressionNode::Struct(..) | ExpressionNode::Trait(..) => "{}".to_string(),
                     _ => self.generate_expression(right),
                 };
 
-                result.push_str(&format!(" = {}\n", right_str))
+                result.push_str(&format!(" = {}\n", right_str));
+
+                // a trait's default methods, which implementations without their own take
+                if let ExpressionNode::Trait(_, _, ref defaults) = right.node {
+                    for (method, function) in defaults.iter() {
+                        let assign = format!("{}['{}']", name, Self::make_valid(method));
+
+                        self.flag = Some(FlagImplicit::Assign(assign.clone()));
+
+                        let line = (function.pos.0).0;
+                        let function = self.generate_expression(function);
+
+                        result.push_str(&format!(
+                            "{}{} = {}\n",
+                            wumap::marker(line),
+                            assign,
+                            function
+                        ))
+                    }
+                }
+
+                // one metatable per struct, shared by its instances and set by its constructor
+                if let ExpressionNode::Struct(..) = right.node {
+                    result.push_str(&format!(
+                        "{0}.__meta = {{__index = {0}}}\n\
+                         function {0}.__new(fields)\n  return setmetatable(fields, {0}.__meta)\nend\n",
+                        name
+                    ))
+                }
             }
         }
 
@@ -1017,6 +2292,222 @@ impl<'g> Generator<'g> {
         format!("{}", result)
     }
 
+    // a loop body where `skip` is `goto __continue_1` and `break` breaks out of the loop itself,
+    // with `::__continue_1::` closing the body when something skips
+    fn goto_body(&mut self, body: &Expression) -> String {
+        self.labels += 1;
+        self.continues
+            .push((format!("__continue_{}", self.labels), false));
+
+        let mut result = String::new();
+
+        if let ExpressionNode::Block(ref content) = body.node {
+            for (i, element) in content.iter().enumerate() {
+                if i == content.len() - 1 {
+                    match element.node {
+                        StatementNode::Skip => break,
+                        StatementNode::Expression(ref expression)
+                            if expression.node == ExpressionNode::Empty =>
+                        {
+                            break
+                        }
+                        _ => (),
+                    }
+                }
+
+                result.push_str(&self.generate_statement(element));
+                result.push('\n')
+            }
+        }
+
+        if let Some((label, true)) = self.continues.pop() {
+            result.push_str(&format!("::{}::\n", label))
+        }
+
+        result
+    }
+
+    // arithmetic on value structs written out member by member, `a + b * 2` making
+    // `{x = (a.x + (b.x * 2)), y = ...}` with the metatable of `a`, rather than calling anything.
+    // Operands that aren't just names or numbers are worked out once, as function arguments
+    fn generate_componentwise(&mut self, expression: &Expression) -> String {
+        let members = self.componentwise[&expression.pos].members.clone();
+
+        let mut operands = Vec::new();
+        let mut made = None;
+
+        let fields = members
+            .iter()
+            .map(|member| {
+                let value = self.member_of(expression, member, &mut operands, &mut made);
+
+                format!("{} = {}", Self::make_valid(member), value)
+            })
+            .collect::<Vec<_>>();
+
+        let result = format!(
+            "setmetatable({{{}}}, getmetatable({}))",
+            fields.join(", "),
+            made.unwrap_or_default()
+        );
+
+        if operands.is_empty() {
+            return result;
+        }
+
+        let (names, values): (Vec<_>, Vec<_>) =
+            operands.into_iter().map(|(_, name, value)| (name, value)).unzip();
+
+        format!(
+            "(function({}) return {} end)({})",
+            names.join(", "),
+            result,
+            values.join(", ")
+        )
+    }
+
+    // `member` of the value struct `expression` gives, or `expression` itself when it's a number
+    fn member_of(
+        &mut self,
+        expression: &Expression,
+        member: &str,
+        operands: &mut Vec<(Pos, String, String)>,
+        made: &mut Option<String>,
+    ) -> String {
+        if let ExpressionNode::Binary(ref left, ref op, ref right) = expression.node {
+            if let Some(componentwise) = self.componentwise.get(&expression.pos) {
+                let (left_struct, right_struct) = (componentwise.left, componentwise.right);
+
+                let left = if left_struct {
+                    self.member_of(left, member, operands, made)
+                } else {
+                    self.operand(left, operands)
+                };
+
+                let right = if right_struct {
+                    self.member_of(right, member, operands, made)
+                } else {
+                    self.operand(right, operands)
+                };
+
+                return format!("({} {} {})", left, self.generate_operator(op), right);
+            }
+        }
+
+        let operand = self.operand(expression, operands);
+
+        if made.is_none() {
+            *made = Some(operand.clone())
+        }
+
+        format!("{}['{}']", operand, member)
+    }
+
+    // an operand as read once per member: names and numbers as they are, the rest by the argument
+    // it's worked out into
+    fn operand(
+        &mut self,
+        expression: &Expression,
+        operands: &mut Vec<(Pos, String, String)>,
+    ) -> String {
+        use self::ExpressionNode::*;
+
+        if matches!(expression.node, Identifier(_) | Int(_) | Float(_)) {
+            return self.generate_expression(expression);
+        }
+
+        if let Some((_, name, _)) = operands.iter().find(|(pos, ..)| pos == &expression.pos) {
+            return name.clone();
+        }
+
+        let name = format!("__operand{}", operands.len() + 1);
+        let value = self.generate_expression(expression);
+
+        operands.push((expression.pos.clone(), name.clone(), value));
+
+        name
+    }
+
+    // `value`, already generated as `code`, as a string; floats are written the same way whatever
+    // the lua, where `tostring` gives `2.0` on some and `2` on others
+    fn stringify(&self, value: &Expression, code: String) -> String {
+        if self.floats.contains(&value.pos) {
+            format!("string.format(\"%.14g\", {})", code)
+        } else {
+            format!("tostring({})", code)
+        }
+    }
+
+    // `a ++ b ++ c` as the parts `a`, `b` and `c`
+    fn concat_parts(&mut self, expression: &Expression, parts: &mut Vec<String>) {
+        if let ExpressionNode::Binary(ref left, Operator::Concat, ref right) = expression.node {
+            self.concat_parts(left, parts);
+
+            if self.conversions.contains(&right.pos) {
+                let right_side = self.generate_expression(right);
+
+                parts.push(self.stringify(right, right_side))
+            } else {
+                self.concat_parts(right, parts)
+            }
+        } else {
+            parts.push(self.generate_expression(expression))
+        }
+    }
+
+    // a loop that only appends to a local copies the whole string on every `++=`, so the
+    // pieces go into a table instead and are joined once after the loop
+    fn buffer(&mut self, looped: &Expression) -> String {
+        let mut names = match self.appendable.last() {
+            Some(appendable) if !matches!(self.flag, Some(FlagImplicit::Assign(_))) => appendable
+                .iter()
+                .filter(|name| !self.buffers.contains_key(*name))
+                .filter(|name| scope::only_appends(looped, name))
+                .cloned()
+                .collect(),
+
+            _ => Vec::new(),
+        };
+
+        names.sort();
+
+        let mut result = String::new();
+
+        for name in &names {
+            let buffer = format!("__buf_{}", Self::make_valid(name));
+
+            result.push_str(&format!("local {} = {{{}}}\n", buffer, self.local(name)));
+
+            self.buffers.insert(name.clone(), buffer);
+        }
+
+        result.push_str(&self.generate_expression(looped));
+
+        for name in &names {
+            let buffer = self.buffers.remove(name).unwrap();
+
+            result.push_str(&format!("{} = table.concat({})\n", self.local(name), buffer))
+        }
+
+        result
+    }
+
+    // `s ++= x` into the buffer of `s`, while there is one
+    fn append(&mut self, statement: &Statement) -> Option<String> {
+        let (buffer, value) = self
+            .buffers
+            .iter()
+            .find_map(|(name, buffer)| Some((buffer.clone(), scope::appended(statement, name)?)))?;
+
+        let mut value_string = self.generate_expression(value);
+
+        if self.conversions.contains(&value.pos) {
+            value_string = self.stringify(value, value_string)
+        }
+
+        Some(format!("{0}[#{0} + 1] = {1}", buffer, value_string))
+    }
+
     fn generate_assignment<'b>(&mut self, left: &'b Expression, right: &'b Expression) -> String {
         let left_string = self.generate_expression(left);
 
@@ -1060,3 +2551,103 @@ impl<'g> Generator<'g> {
         target.push_str(&self.make_line(&value))
     }
 }
+
+// a lua function for a checked `int` operation, giving `nil` where the result doesn't fit; 64 bit
+// integers wrap around, floats stop being exact past 2^53
+fn checked(builtin: Builtin) -> String {
+    let (op, fits) = match builtin {
+        Builtin::CheckedAdd => ("+", "(b >= 0) == (r >= a)"),
+        Builtin::CheckedSub => ("-", "(b >= 0) == (r <= a)"),
+        _ => ("*", "a == 0 or (r // a == b and not (a == -1 and b == math.mininteger))"),
+    };
+
+    let fits = if has_wide_ints() {
+        fits.to_string()
+    } else {
+        format!("r > -{0} and r < {0}", EXACT_INT)
+    };
+
+    format!(
+        "(function(a, b) local r = a {} b return ({}) and r or nil end)",
+        op, fits
+    )
+}
+
+// `a | b` or `a & b` on flags, however the target has it: lua 5.3 on has the operators, luajit
+// the `bit` library and lua 5.2 `bit32`. Lua 5.1 has none, so the bits are gone through in turn
+fn bitwise(op: &Operator, a: &str, b: &str) -> String {
+    let library = if *op == Operator::BitOr { "bor" } else { "band" };
+
+    match *TARGET.lock().unwrap() {
+        Target::Lua53 | Target::Lua54 => format!("({} {} {})", a, op, b),
+        Target::LuaJit => format!("bit.{}({}, {})", library, a, b),
+        Target::Lua52 => format!("bit32.{}({}, {})", library, a, b),
+
+        Target::Lua51 => {
+            let either = if *op == Operator::BitOr { "or" } else { "and" };
+
+            format!(
+                "(function(a, b) local r, bit = 0, 1 while a > 0 or b > 0 do \
+                 if a % 2 == 1 {0} b % 2 == 1 then r = r + bit end \
+                 a, b, bit = math.floor(a / 2), math.floor(b / 2), bit * 2 end \
+                 return r end)({1}, {2})",
+                either, a, b
+            )
+        }
+    }
+}
+
+// what `require` finds the module at `path` by: its path from the project root, or from
+// `$WU_HOME` for the modules there, with dots for slashes. The `package.path` of
+// `package_path` resolves these, wherever the output folder is. In a hermetic build, it's
+// always from the project root, even if the build was given the absolute path
+pub fn require_name(path: &Path) -> String {
+    let mut module = normalize_path(path);
+
+    if is_hermetic() {
+        module = from_root(&module)
+    } else if let Ok(home) = env::var("WU_HOME") {
+        if let Ok(inner) = module.strip_prefix(&home) {
+            module = inner.to_path_buf()
+        }
+    }
+
+    module
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+// the `require_name` of the module an import in `source` refers to. `$WU_HOME` modules are
+// found from there, the others from the importing file
+pub fn imported_name(
+    source: &Source,
+    import_map: &HashMap<Pos, (String, String)>,
+    statement: &Statement,
+    path: &str,
+) -> String {
+    let module = if import_map.contains_key(&statement.pos) {
+        PathBuf::from(path)
+    } else {
+        Path::new(&source.file.0).parent().unwrap().join(path)
+    };
+
+    require_name(&module)
+}
+
+// the lua that puts the modules of an output folder on `package.path`, written to it as
+// `package_path.lua`. It finds the folder it is in, so a host only needs to run it,
+// e.g. `dofile('out/package_path.lua')`, before it requires them
+pub fn package_path() -> String {
+    "\
+-- puts the modules compiled into this folder on `package.path`
+local folder = debug.getinfo(1, 'S').source:match('^@(.*[/\\\\])') or ''
+
+package.path = folder .. '?.lua;' .. folder .. '?/init.lua;' .. package.path
+"
+    .to_string()
+}
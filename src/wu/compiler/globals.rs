@@ -0,0 +1,208 @@
+// finds the globals generated lua assigns, to check that the only ones are those marked `@global`.
+// It follows lua's blocks and `local`s well enough for what the generator writes, not any lua
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Symbol(String),
+    Other, // strings and numbers
+}
+
+// every name assigned or declared as a function without being a local there, with its line
+pub fn assigned(lua: &str) -> Vec<(usize, String)> {
+    let tokens = tokenize(lua);
+
+    let mut found = Vec::new();
+
+    // the locals of every block, and whether it is a function's
+    let mut scopes: Vec<(HashSet<String>, bool)> = vec![(HashSet::new(), true)];
+    let mut braces = vec![0usize]; // open table constructors, by function
+    let mut pending = Vec::new(); // parameters and loop names, for the block about to open
+
+    let declared = |scopes: &Vec<(HashSet<String>, bool)>, name: &str| {
+        scopes.iter().any(|(scope, _)| scope.contains(name))
+    };
+
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let (ref token, line) = tokens[i];
+
+        let previous = if i > 0 { Some(&tokens[i - 1].0) } else { None };
+        let next = tokens.get(i + 1).map(|(token, _)| token);
+
+        match *token {
+            Token::Name(ref name) => match name.as_str() {
+                "local" => {
+                    i += 1;
+
+                    if let Some((Token::Name(ref word), _)) = tokens.get(i) {
+                        if word == "function" {
+                            continue;
+                        }
+                    }
+
+                    // `local a, b`, visible from the next statement on, which is near enough
+                    while let Some((Token::Name(ref name), _)) = tokens.get(i) {
+                        scopes.last_mut().unwrap().0.insert(name.clone());
+
+                        match tokens.get(i + 1) {
+                            Some((Token::Symbol(ref comma), _)) if comma == "," => i += 2,
+                            _ => break,
+                        }
+                    }
+
+                    continue;
+                }
+
+                "function" => {
+                    let local = previous == Some(&Token::Name("local".into()));
+
+                    // `function a.b(` and `function a:b(` set fields, only `function a(` a name
+                    if let (Some((Token::Name(ref name), _)), Some((Token::Symbol(ref open), _))) =
+                        (tokens.get(i + 1), tokens.get(i + 2))
+                    {
+                        if local {
+                            scopes.last_mut().unwrap().0.insert(name.clone());
+                        } else if open == "(" && !declared(&scopes, name) {
+                            found.push((line, name.clone()))
+                        }
+                    }
+
+                    while i < tokens.len() && tokens[i].0 != Token::Symbol("(".into()) {
+                        i += 1
+                    }
+
+                    while i < tokens.len() && tokens[i].0 != Token::Symbol(")".into()) {
+                        if let Token::Name(ref param) = tokens[i].0 {
+                            pending.push(param.clone())
+                        }
+
+                        i += 1
+                    }
+
+                    scopes.push((pending.drain(..).collect(), true));
+                    braces.push(0);
+                }
+
+                "for" => {
+                    i += 1;
+
+                    while let Some((Token::Name(ref name), _)) = tokens.get(i) {
+                        pending.push(name.clone());
+
+                        match tokens.get(i + 1) {
+                            Some((Token::Symbol(ref comma), _)) if comma == "," => i += 2,
+                            _ => break,
+                        }
+                    }
+                }
+
+                "do" | "then" | "repeat" => scopes.push((pending.drain(..).collect(), false)),
+
+                "else" | "elseif" => {
+                    scopes.pop();
+
+                    if name == "else" {
+                        scopes.push((HashSet::new(), false))
+                    }
+                }
+
+                "end" | "until" => {
+                    if let Some((_, true)) = scopes.pop() {
+                        braces.pop();
+                    }
+                }
+
+                _ if *braces.last().unwrap() == 0 => {
+                    let assigned = matches!(next, Some(Token::Symbol(ref s)) if s == "=");
+                    let field =
+                        matches!(previous, Some(Token::Symbol(ref s)) if s == "." || s == ":");
+
+                    if assigned && !field && !declared(&scopes, name) {
+                        found.push((line, name.clone()))
+                    }
+                }
+
+                _ => (),
+            },
+
+            Token::Symbol(ref symbol) => match symbol.as_str() {
+                "{" => *braces.last_mut().unwrap() += 1,
+                "}" => *braces.last_mut().unwrap() -= 1,
+                _ => (),
+            },
+
+            Token::Other => (),
+        }
+
+        i += 1
+    }
+
+    found
+}
+
+fn tokenize(lua: &str) -> Vec<(Token, usize)> {
+    let chars = lua.chars().collect::<Vec<_>>();
+
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' {
+            line += 1;
+            i += 1
+        } else if c.is_whitespace() {
+            i += 1
+        } else if c == '-' && chars.get(i + 1) == Some(&'-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1
+            }
+        } else if c == '"' || c == '\'' {
+            i += 1;
+
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1
+                }
+
+                i += 1
+            }
+
+            i += 1;
+            tokens.push((Token::Other, line))
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1
+            }
+
+            tokens.push((Token::Name(chars[start..i].iter().collect()), line))
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '.') {
+                i += 1
+            }
+
+            tokens.push((Token::Other, line))
+        } else {
+            // `==`, `~=`, `<=` and `>=` aren't assignments
+            let symbol = match (c, chars.get(i + 1)) {
+                ('=' | '~' | '<' | '>', Some('=')) => {
+                    i += 1;
+                    format!("{}=", c)
+                }
+                _ => c.to_string(),
+            };
+
+            i += 1;
+            tokens.push((Token::Symbol(symbol), line))
+        }
+    }
+
+    tokens
+}
@@ -1,8 +1,14 @@
 pub mod compiler;
+pub mod globals;
+pub mod scope;
 
+use super::ice;
 use super::lexer::*;
 use super::parser::*;
 use super::source::*;
 use super::visitor::*;
+use super::wumap;
 
 pub use self::compiler::*;
+// the generator's own, not the visitor's
+pub use self::compiler::Inside;
@@ -0,0 +1,814 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use super::*;
+
+// every name bound by `statement`, in nested blocks and functions too
+pub fn bound_in_statement(statement: &Statement, names: &mut HashSet<String>) {
+    use self::StatementNode::*;
+
+    match statement.node {
+        Expression(ref expression) => bound_in_expression(expression, names),
+
+        Variable(_, ref name, ref right, _) => {
+            names.insert(name.clone());
+
+            if let Some(ref right) = *right {
+                bound_in_expression(right, names)
+            }
+        }
+
+        SplatVariable(_, ref splats, ref right, _) => {
+            names.extend(splats.iter().cloned());
+
+            if let Some(ref right) = *right {
+                bound_in_expression(right, names)
+            }
+        }
+
+        Assignment(ref left, ref right) => {
+            bound_in_expression(left, names);
+            bound_in_expression(right, names)
+        }
+
+        SplatAssignment(ref splats, ref right) => {
+            for splat in splats {
+                bound_in_expression(splat, names)
+            }
+
+            bound_in_expression(right, names)
+        }
+
+        Return(ref value) => {
+            if let Some(ref value) = *value {
+                bound_in_expression(value, names)
+            }
+        }
+
+        Implement(ref name, ref body, ref parent) => {
+            bound_in_expression(name, names);
+            bound_in_expression(body, names);
+
+            if let Some(ref parent) = *parent {
+                bound_in_expression(parent, names)
+            }
+        }
+
+        Import(ref path, ref imports, ref cfg, _) if enabled(cfg) => {
+            names.insert(Generator::module_name(path));
+
+            for (name, alias) in imports {
+                names.insert(alias.as_ref().unwrap_or(name).clone());
+            }
+        }
+
+        ExternBlock(ref statement) | Global(ref statement) => bound_in_statement(statement, names),
+
+        Import(..) | Skip | Break => (),
+    }
+}
+
+pub fn bound_in_expression(expression: &Expression, names: &mut HashSet<String>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(ref params, _, ref body, _) => {
+            names.extend(params.iter().map(|param| param.0.clone()));
+
+            bound_in_expression(body, names)
+        }
+
+        For((ref iterator, ref second), ref body) => {
+            names.extend(loop_names(iterator, second));
+
+            bound_in_expression(body, names)
+        }
+
+        Match(ref subject, ref arms) => {
+            bound_in_expression(subject, names);
+
+            for (_, bound, body, _) in arms.iter() {
+                names.extend(bound.iter().cloned());
+
+                bound_in_expression(body, names)
+            }
+        }
+
+        Block(ref content) => {
+            for statement in content {
+                bound_in_statement(statement, names)
+            }
+        }
+
+        _ => {
+            for child in children(expression) {
+                bound_in_expression(child, names)
+            }
+        }
+    }
+}
+
+// the names a loop declares, `x` of `for x in xs` or `i` and `x` of `for (i, x) in ipairs(xs)`
+fn loop_names(iterator: &Expression, second: &Option<Rc<Expression>>) -> Vec<String> {
+    let names = match iterator.node {
+        _ if second.is_none() => return Vec::new(),
+
+        ExpressionNode::Tuple(ref content) | ExpressionNode::Splat(ref content) => {
+            content.iter().collect()
+        }
+
+        _ => vec![iterator],
+    };
+
+    names
+        .into_iter()
+        .filter_map(|name| match name.node {
+            ExpressionNode::Identifier(ref name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// the expressions directly inside `expression`, except the bodies of blocks
+fn children(expression: &Expression) -> Vec<&Expression> {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        UnwrapSplat(ref inner)
+        | Unwrap(ref inner)
+        | Neg(ref inner)
+        | Not(ref inner)
+        | Cast(ref inner, _)
+        | Module(ref inner, _)
+        | ExternExpression(ref inner) => vec![&**inner],
+
+        Binary(ref left, _, ref right) | Index(ref left, ref right, _) => vec![&**left, &**right],
+        While(ref condition, ref body) => vec![&**condition, &**body],
+
+        Tuple(ref content) | Array(ref content) | Splat(ref content) => content.iter().collect(),
+
+        Call(ref called, ref args) => Some(&**called).into_iter().chain(args).collect(),
+
+        Initialization(ref name, ref fields) => Some(&**name)
+            .into_iter()
+            .chain(fields.iter().map(|field| &field.1))
+            .collect(),
+
+        If(ref condition, ref body, ref elses) => {
+            let mut found = vec![&**condition, &**body];
+
+            for (condition, body, _) in elses.iter().flatten() {
+                found.extend(condition.as_ref());
+                found.push(body)
+            }
+
+            found
+        }
+
+        Match(ref subject, ref arms) => Some(&**subject)
+            .into_iter()
+            .chain(arms.iter().map(|arm| &arm.2))
+            .collect(),
+
+        Function(_, _, ref body, _) => vec![&**body],
+        Trait(_, _, ref defaults) => defaults.iter().map(|default| &default.1).collect(),
+        For((ref iterator, ref second), ref body) => Some(&**iterator)
+            .into_iter()
+            .chain(second.as_deref())
+            .chain(Some(&**body))
+            .collect(),
+
+        _ => Vec::new(),
+    }
+}
+
+fn statement_children(statement: &Statement) -> Vec<&Expression> {
+    use self::StatementNode::*;
+
+    match statement.node {
+        Expression(ref expression) => vec![expression],
+        Variable(_, _, ref right, _) | SplatVariable(_, _, ref right, _) => right.iter().collect(),
+        Assignment(ref left, ref right) => vec![left, right],
+        SplatAssignment(ref splats, ref right) => splats.iter().chain(Some(right)).collect(),
+        Return(ref value) => value.iter().map(|value| &**value).collect(),
+        Implement(ref name, ref body, ref parent) => vec![name, body]
+            .into_iter()
+            .chain(parent.as_ref())
+            .collect(),
+        ExternBlock(ref statement) | Global(ref statement) => statement_children(statement),
+        Import(..) | Skip | Break => Vec::new(),
+    }
+}
+
+// the locals declared in `content` that functions written before them use, other functions
+// included. Those have to be declared ahead, or the functions would read globals of the same name
+// some externs and traits are never declared in lua; a trait is a table of its default methods
+pub fn undeclared(node: &ExpressionNode) -> bool {
+    match *node {
+        ExpressionNode::Trait(_, _, ref defaults) => defaults.is_empty(),
+        ExpressionNode::ExternExpression(..) | ExpressionNode::Extern(_, None) => true,
+        _ => false,
+    }
+}
+
+pub fn forward_declared(content: &[Statement]) -> Vec<String> {
+    let mut captured = HashSet::new();
+    let mut forward = Vec::new();
+
+    for statement in content {
+        if let StatementNode::Variable(_, ref name, ref right, _) = statement.node {
+            let local = !right.as_ref().is_some_and(|right| undeclared(&right.node));
+
+            if local && captured.contains(name) && !forward.contains(name) {
+                forward.push(name.clone())
+            }
+        }
+
+        for child in statement_children(statement) {
+            closed_over(child, &mut captured)
+        }
+    }
+
+    forward
+}
+
+// the names the functions nested in `expression` read from around them, leaving out their
+// parameters and whatever they declare themselves
+fn closed_over(expression: &Expression, names: &mut HashSet<String>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(..) | Module(..) => {
+            let mut used = HashSet::new();
+            let mut bound = HashSet::new();
+
+            used_in(expression, &mut used);
+            bound_in_expression(expression, &mut bound);
+
+            names.extend(used.difference(&bound).cloned())
+        }
+
+        Block(ref content) => {
+            for child in content.iter().flat_map(statement_children) {
+                closed_over(child, names)
+            }
+        }
+
+        _ => {
+            for child in children(expression) {
+                closed_over(child, names)
+            }
+        }
+    }
+}
+
+// `expression` without the `!`s after it, which are gone in lua
+pub fn unwrapped(expression: &Expression) -> &Expression {
+    match expression.node {
+        ExpressionNode::Unwrap(ref inner) => unwrapped(inner),
+        _ => expression,
+    }
+}
+
+// the names `a b c` goes through, when it only looks up members by name
+pub fn lookup_path(expression: &Expression) -> Option<Vec<String>> {
+    match expression.node {
+        ExpressionNode::Identifier(ref name) => Some(vec![name.clone()]),
+
+        ExpressionNode::Index(ref source, ref member, false) => {
+            if let ExpressionNode::Identifier(ref member) = member.node {
+                let mut path = lookup_path(source)?;
+
+                path.push(member.clone());
+
+                Some(path)
+            } else {
+                None
+            }
+        }
+
+        _ => None,
+    }
+}
+
+// the module function lookups in a function body, in order, and the paths assigned to in it,
+// leaving out nested functions, which hoist their own
+pub fn lookups_in(
+    expression: &Expression,
+    lookups: &HashSet<Pos>,
+    found: &mut Vec<(Pos, Vec<String>)>,
+    assigned: &mut HashSet<Vec<String>>,
+) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(..) | Module(..) => return,
+
+        Block(ref content) => {
+            for statement in content {
+                if let StatementNode::Assignment(ref left, _) = statement.node {
+                    assigned.extend(lookup_path(left));
+                }
+
+                for child in statement_children(statement) {
+                    lookups_in(child, lookups, found, assigned)
+                }
+            }
+
+            return;
+        }
+
+        _ => (),
+    }
+
+    if lookups.contains(&expression.pos) {
+        if let Some(path) = lookup_path(expression) {
+            found.push((expression.pos.clone(), path));
+
+            return;
+        }
+    }
+
+    for child in children(expression) {
+        lookups_in(child, lookups, found, assigned)
+    }
+}
+
+// the names declared in a function body, leaving out the functions nested in it
+pub fn locals_in(expression: &Expression, names: &mut HashSet<String>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(..) | Module(..) => (),
+
+        Block(ref content) => {
+            for statement in content {
+                match statement.node {
+                    StatementNode::Variable(_, ref name, ..) => {
+                        names.insert(name.clone());
+                    }
+
+                    StatementNode::SplatVariable(_, ref splats, ..) => {
+                        names.extend(splats.iter().cloned())
+                    }
+
+                    _ => (),
+                }
+
+                for child in statement_children(statement) {
+                    locals_in(child, names)
+                }
+            }
+        }
+
+        For((ref iterator, ref second), ref body) => {
+            names.extend(loop_names(iterator, second));
+
+            locals_in(body, names)
+        }
+
+        Match(ref subject, ref arms) => {
+            locals_in(subject, names);
+
+            for (_, bound, body, _) in arms.iter() {
+                names.extend(bound.iter().cloned());
+
+                locals_in(body, names)
+            }
+        }
+
+        _ => {
+            for child in children(expression) {
+                locals_in(child, names)
+            }
+        }
+    }
+}
+
+// the most locals a function body has at once: those of each block on top of those of the
+// blocks around it, and the three a `for` keeps to itself, leaving out the functions nested in it
+pub fn active_locals(expression: &Expression) -> usize {
+    most_locals(expression, 0)
+}
+
+fn most_locals(expression: &Expression, around: usize) -> usize {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(..) | Module(..) => around,
+
+        Block(ref content) => {
+            let mut active = around;
+            let mut most = around;
+
+            for statement in content {
+                for child in statement_children(statement) {
+                    most = most.max(most_locals(child, active))
+                }
+
+                active += match statement.node {
+                    StatementNode::Variable(_, _, Some(ref right), _)
+                        if undeclared(&right.node) =>
+                    {
+                        0
+                    }
+
+                    StatementNode::Variable(..) => 1,
+                    StatementNode::SplatVariable(_, ref splats, ..) => splats.len(),
+
+                    StatementNode::Import(..) => {
+                        let mut bound = HashSet::new();
+
+                        bound_in_statement(statement, &mut bound);
+
+                        bound.len()
+                    }
+
+                    _ => 0,
+                };
+
+                most = most.max(active)
+            }
+
+            most
+        }
+
+        For((ref iterator, ref second), ref body) => {
+            let looped = most_locals(second.as_deref().unwrap_or(iterator), around);
+            let names = loop_names(iterator, second).len().max(1);
+
+            looped.max(most_locals(body, around + 3 + names))
+        }
+
+        Match(ref subject, ref arms) => arms.iter().fold(
+            most_locals(subject, around),
+            |most, (_, bound, body, _)| most.max(most_locals(body, around + bound.len())),
+        ),
+
+        _ => children(expression)
+            .into_iter()
+            .fold(around, |most, child| most.max(most_locals(child, around))),
+    }
+}
+
+// every name used in `expression`
+fn used_in(expression: &Expression, names: &mut HashSet<String>) {
+    match expression.node {
+        ExpressionNode::Identifier(ref name) => {
+            names.insert(name.clone());
+        }
+
+        ExpressionNode::Block(ref content) => {
+            for child in content.iter().flat_map(statement_children) {
+                used_in(child, names)
+            }
+        }
+
+        _ => {
+            for child in children(expression) {
+                used_in(child, names)
+            }
+        }
+    }
+}
+
+// every name used in `statement`
+pub fn used_by(statement: &Statement) -> HashSet<String> {
+    let mut names = HashSet::new();
+
+    for child in statement_children(statement) {
+        used_in(child, &mut names)
+    }
+
+    names
+}
+
+// the names used by the functions nested in `expression`, which may read them at any time
+pub fn captured_in(expression: &Expression, names: &mut HashSet<String>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(..) | Module(..) => used_in(expression, names),
+
+        Block(ref content) => {
+            for child in content.iter().flat_map(statement_children) {
+                captured_in(child, names)
+            }
+        }
+
+        _ => {
+            for child in children(expression) {
+                captured_in(child, names)
+            }
+        }
+    }
+}
+
+fn uses(expression: &Expression, name: &str) -> usize {
+    match expression.node {
+        ExpressionNode::Identifier(ref n) => (n == name) as usize,
+
+        ExpressionNode::Block(ref content) => content
+            .iter()
+            .flat_map(statement_children)
+            .map(|child| uses(child, name))
+            .sum(),
+
+        _ => children(expression)
+            .into_iter()
+            .map(|child| uses(child, name))
+            .sum(),
+    }
+}
+
+// the value `name ++= value` appends, or `name = name ++ value`
+pub fn appended<'e>(statement: &'e Statement, name: &str) -> Option<&'e Expression> {
+    if let StatementNode::Assignment(ref left, ref right) = statement.node {
+        if let ExpressionNode::Binary(ref source, Operator::Concat, ref value) = right.node {
+            let is_name =
+                |e: &Expression| matches!(e.node, ExpressionNode::Identifier(ref n) if n == name);
+
+            if is_name(left) && is_name(source) && uses(value, name) == 0 {
+                return Some(value);
+            }
+        }
+    }
+
+    None
+}
+
+// counts the appends to `name` in `expression`, false when it could return halfway through
+fn appends(expression: &Expression, name: &str, count: &mut usize) -> bool {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(..) | Module(..) => true,
+
+        Block(ref content) => content.iter().all(|statement| {
+            if let StatementNode::Return(_) = statement.node {
+                return false;
+            }
+
+            if appended(statement, name).is_some() {
+                *count += 1
+            }
+
+            statement_children(statement)
+                .into_iter()
+                .all(|child| appends(child, name, count))
+        }),
+
+        _ => children(expression)
+            .into_iter()
+            .all(|child| appends(child, name, count)),
+    }
+}
+
+// whether all a loop does with `name` is append to it, so the appends can go into a buffer
+pub fn only_appends(looped: &Expression, name: &str) -> bool {
+    let mut count = 0;
+    let mut declared = HashSet::new();
+
+    locals_in(looped, &mut declared);
+
+    appends(looped, name, &mut count)
+        && count > 0
+        && uses(looped, name) == 2 * count
+        && !declared.contains(name)
+}
+
+// every `a b c` chain in `statement` looking up two members or more, each with the shorter
+// chains it starts with, longest first, leaving out nested functions
+pub fn chains_in<'e>(
+    statement: &'e Statement,
+    found: &mut Vec<Vec<(&'e Expression, Vec<String>)>>,
+) {
+    for child in statement_children(statement) {
+        expression_chains(child, found)
+    }
+}
+
+fn expression_chains<'e>(
+    expression: &'e Expression,
+    found: &mut Vec<Vec<(&'e Expression, Vec<String>)>>,
+) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(..) | Module(..) => return,
+
+        Block(ref content) => {
+            for statement in content {
+                chains_in(statement, found)
+            }
+
+            return;
+        }
+
+        Index(..) => {
+            if let Some(path) = lookup_path(expression) {
+                let mut prefixes = Vec::new();
+                let mut node = expression;
+
+                for len in (3..=path.len()).rev() {
+                    prefixes.push((node, path[..len].to_vec()));
+
+                    if let Index(ref source, ..) = node.node {
+                        node = source
+                    }
+                }
+
+                if !prefixes.is_empty() {
+                    found.push(prefixes)
+                }
+
+                return;
+            }
+        }
+
+        _ => (),
+    }
+
+    for child in children(expression) {
+        expression_chains(child, found)
+    }
+}
+
+// false when `expression` may change a table, writing to one or calling something that isn't
+// pure, and otherwise the names it assigns or declares
+pub fn settled(
+    expression: &Expression,
+    pure: &HashSet<Pos>,
+    changed: &mut HashSet<String>,
+) -> bool {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Function(..) => true,
+
+        Call(..) if !pure.contains(&expression.pos) => false,
+        Binary(_, Operator::PipeLeft, _) | Binary(_, Operator::PipeRight, _) => false,
+
+        Block(ref content) => settled_block(content, pure, changed),
+
+        For((ref iterator, ref second), _) => {
+            changed.extend(loop_names(iterator, second));
+
+            children(expression)
+                .into_iter()
+                .all(|child| settled(child, pure, changed))
+        }
+
+        Match(_, ref arms) => {
+            for (_, bound, ..) in arms.iter() {
+                changed.extend(bound.iter().cloned())
+            }
+
+            children(expression)
+                .into_iter()
+                .all(|child| settled(child, pure, changed))
+        }
+
+        _ => children(expression)
+            .into_iter()
+            .all(|child| settled(child, pure, changed)),
+    }
+}
+
+pub fn settled_block(
+    content: &[Statement],
+    pure: &HashSet<Pos>,
+    changed: &mut HashSet<String>,
+) -> bool {
+    use self::ExpressionNode::*;
+
+    content.iter().all(|statement| {
+        match statement.node {
+            StatementNode::Assignment(ref left, _) => match left.node {
+                Identifier(ref name) => {
+                    changed.insert(name.clone());
+                }
+
+                _ => return false,
+            },
+
+            StatementNode::SplatAssignment(ref lefts, _) => {
+                for left in lefts {
+                    match left.node {
+                        Identifier(ref name) => {
+                            changed.insert(name.clone());
+                        }
+
+                        _ => return false,
+                    }
+                }
+            }
+
+            StatementNode::Variable(_, ref name, ..) => {
+                changed.insert(name.clone());
+            }
+
+            StatementNode::SplatVariable(_, ref names, ..) => changed.extend(names.iter().cloned()),
+
+            StatementNode::Implement(..) | StatementNode::Import(..) => return false,
+
+            _ => (),
+        }
+
+        statement_children(statement)
+            .into_iter()
+            .all(|child| settled(child, pure, changed))
+    })
+}
+
+// whether `statement` always looks up the expression at `pos`, rather than maybe, behind an
+// `if` or an `and`
+pub fn evaluates(statement: &Statement, pos: &Pos) -> bool {
+    statement_children(statement)
+        .into_iter()
+        .any(|child| always_evaluates(child, pos))
+}
+
+fn always_evaluates(expression: &Expression, pos: &Pos) -> bool {
+    use self::ExpressionNode::*;
+
+    if expression.pos == *pos {
+        return true;
+    }
+
+    match expression.node {
+        Binary(ref left, Operator::And, _) | Binary(ref left, Operator::Or, _) => {
+            always_evaluates(left, pos)
+        }
+
+        If(ref condition, ..) | While(ref condition, _) => always_evaluates(condition, pos),
+        Match(ref subject, _) => always_evaluates(subject, pos),
+        For((ref iterator, ref second), _) => {
+            always_evaluates(second.as_deref().unwrap_or(iterator), pos)
+        }
+
+        Block(_) | Function(..) | Module(..) => false,
+
+        _ => children(expression)
+            .into_iter()
+            .any(|child| always_evaluates(child, pos)),
+    }
+}
+
+// the names `expression` uses that it doesn't declare itself
+pub fn free_in(expression: &Expression) -> HashSet<String> {
+    let mut used = HashSet::new();
+    let mut declared = HashSet::new();
+
+    used_in(expression, &mut used);
+    locals_in(expression, &mut declared);
+
+    used.difference(&declared).cloned().collect()
+}
+
+// whether `expression` returns, or breaks or skips out of a loop around it, or assigns a name it
+// doesn't declare, which it can't do once it is moved into a function of its own
+pub fn leaves(expression: &Expression) -> bool {
+    let mut declared = HashSet::new();
+
+    locals_in(expression, &mut declared);
+
+    escapes(expression, &declared, true, false)
+}
+
+// `jumps` is whether a `break` or `skip` would leave, and `return` is fine in nested functions
+fn escapes(expression: &Expression, declared: &HashSet<String>, jumps: bool, nested: bool) -> bool {
+    use self::ExpressionNode::*;
+
+    let outer = |left: &Expression| match left.node {
+        Identifier(ref name) => !declared.contains(name),
+        _ => false,
+    };
+
+    let (jumps, nested) = match expression.node {
+        While(..) | For(..) => (false, nested),
+        Function(..) => (false, true),
+        _ => (jumps, nested),
+    };
+
+    if let Block(ref content) = expression.node {
+        content.iter().any(|statement| {
+            let leaves = match statement.node {
+                StatementNode::Return(_) => !nested,
+                StatementNode::Break | StatementNode::Skip => jumps,
+                StatementNode::Assignment(ref left, _) => outer(left),
+                StatementNode::SplatAssignment(ref lefts, _) => lefts.iter().any(outer),
+                _ => false,
+            };
+
+            leaves
+                || statement_children(statement)
+                    .into_iter()
+                    .any(|child| escapes(child, declared, jumps, nested))
+        })
+    } else {
+        children(expression)
+            .into_iter()
+            .any(|child| escapes(child, declared, jumps, nested))
+    }
+}
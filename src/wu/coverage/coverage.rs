@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::*;
+
+// where luacov leaves its hit counts
+pub const STATS: &str = "luacov.stats.out";
+
+// the Wu listings with hit counts, written next to the stats
+pub const REPORT: &str = "luacov.report.wu.out";
+
+// hits per line of every lua file luacov saw, from `luacov.stats.out`:
+// a `<lines>:<file>` header, then one line of space separated counts
+pub fn read_stats(stats: &str) -> Vec<(String, Vec<usize>)> {
+    let mut files = Vec::new();
+    let mut lines = stats.lines();
+
+    while let Some(header) = lines.next() {
+        let file = match header.split_once(':') {
+            Some((_, file)) => file.trim_start_matches("./").to_string(),
+            None => continue,
+        };
+
+        let hits = lines
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|hits| hits.parse().unwrap_or(0))
+            .collect();
+
+        files.push((file, hits))
+    }
+
+    files
+}
+
+// the coverage of one Wu file
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    pub lines: BTreeMap<usize, usize>, // hits of every Wu line that generated code
+}
+
+impl Coverage {
+    pub fn covered(&self) -> usize {
+        self.lines.values().filter(|&&hits| hits > 0).count()
+    }
+
+    pub fn percent(&self) -> f64 {
+        if self.lines.is_empty() {
+            100.0
+        } else {
+            self.covered() as f64 * 100.0 / self.lines.len() as f64
+        }
+    }
+}
+
+// carries lua hits over to the Wu lines they were generated from, for the files that have a `.wumap`;
+// a Wu line counts the most any of its lua lines was hit
+pub fn map_stats(stats: &[(String, Vec<usize>)]) -> BTreeMap<String, Coverage> {
+    let mut files: BTreeMap<String, Coverage> = BTreeMap::new();
+
+    for (lua, hits) in stats.iter() {
+        let map = match LineMap::load(&wumap::map_path(Path::new(lua))) {
+            Some(map) => map,
+            None => continue,
+        };
+
+        let code = fs::read_to_string(lua).unwrap_or_default();
+        let code = code.lines().collect::<Vec<_>>();
+
+        let coverage = files.entry(map.source.clone()).or_default();
+
+        for (i, &wu_line) in map.lines.iter().enumerate() {
+            // blank lua lines and `end`s don't make a Wu line worth covering on their own
+            let is_code = code
+                .get(i)
+                .is_some_and(|line| !matches!(line.trim(), "" | "end" | "else" | "do"));
+
+            if wu_line == 0 || !is_code {
+                continue;
+            }
+
+            let line_hits = hits.get(i).copied().unwrap_or(0);
+            let entry = coverage.lines.entry(wu_line).or_insert(0);
+
+            *entry = (*entry).max(line_hits)
+        }
+    }
+
+    files
+}
+
+// the source of every file with its hit counts in the margin, unhit code marked with `*0`
+pub fn listing(files: &BTreeMap<String, Coverage>) -> String {
+    let mut result = String::new();
+
+    for (file, coverage) in files.iter() {
+        let source = fs::read_to_string(file).unwrap_or_default();
+
+        result.push_str(&format!(
+            "{}\n{} ({:.1}%)\n{}\n",
+            "=".repeat(78),
+            file,
+            coverage.percent(),
+            "=".repeat(78)
+        ));
+
+        for (i, line) in source.lines().enumerate() {
+            let margin = match coverage.lines.get(&(i + 1)) {
+                Some(0) => "*0".to_string(),
+                Some(hits) => hits.to_string(),
+                None => String::new(),
+            };
+
+            result.push_str(&format!("{:>7} {}\n", margin, line))
+        }
+
+        result.push('\n')
+    }
+
+    result
+}
@@ -0,0 +1,5 @@
+pub mod coverage;
+
+use super::wumap::{self, LineMap};
+
+pub use self::coverage::*;
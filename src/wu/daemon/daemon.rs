@@ -0,0 +1,257 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::time::Duration;
+
+use super::*;
+
+// where `wu daemon` listens, in the folder it was started in
+pub const SOCKET: &str = ".wu-daemon.sock";
+
+// the longest request read, and how long a client has to send it, so no client holds up the
+// builds of the others
+const REQUEST_BYTES: u64 = 1 << 20;
+const REQUEST_TIME: Duration = Duration::from_secs(5);
+
+// `wu daemon`, building whatever is asked over the socket at `socket`, one build at a time:
+// `build` gets the folder to build in and the command line, and answers with what the build
+// showed and its exit code. Being one process, modules visited stay so for later builds as long
+// as their files don't change
+#[cfg(unix)]
+pub fn serve(
+    socket: &Path,
+    mut build: impl FnMut(&Path, Vec<String>) -> (String, i32),
+) -> io::Result<()> {
+    // left behind by a daemon that didn't get to clean up
+    if socket.exists() && UnixStream::connect(socket).is_err() {
+        fs::remove_file(socket)?
+    }
+
+    let listener = UnixListener::bind(socket)?;
+
+    log!(Normal, "Listening", "on `{}`", socket.display());
+
+    for stream in listener.incoming().flatten() {
+        // a client gone before its answer just doesn't get it
+        let _ = answer(&stream, &mut build);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve(
+    _socket: &Path,
+    _build: impl FnMut(&Path, Vec<String>) -> (String, i32),
+) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the daemon listens on a unix socket",
+    ))
+}
+
+#[cfg(unix)]
+fn answer(
+    stream: &UnixStream,
+    build: &mut impl FnMut(&Path, Vec<String>) -> (String, i32),
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIME))?;
+
+    let mut line = String::new();
+    BufReader::new(stream.take(REQUEST_BYTES)).read_line(&mut line)?;
+
+    if !line.ends_with('\n') {
+        return Err(malformed("request"));
+    }
+
+    let request = Json::parse(&line).ok_or_else(|| malformed("request"))?;
+
+    let folder = request
+        .get("folder")
+        .and_then(Json::as_str)
+        .ok_or_else(|| malformed("request"))?;
+
+    let args = match request.get("args") {
+        Some(Json::Array(ref args)) => args
+            .iter()
+            .filter_map(Json::as_str)
+            .map(String::from)
+            .collect(),
+        _ => return Err(malformed("request")),
+    };
+
+    let (shown, code) = build(Path::new(folder), args);
+
+    let reply = Json::object(vec![
+        ("shown", Json::Str(shown)),
+        ("code", Json::Number(code as f64)),
+    ]);
+
+    let mut stream = stream;
+    writeln!(stream, "{}", reply)
+}
+
+// `wu build --use-daemon`, having the daemon listening at `socket` build in `folder` as told by
+// `args`, a command line without the program; what it showed and its exit code
+#[cfg(unix)]
+pub fn request(socket: &Path, folder: &Path, args: &[String]) -> io::Result<(String, i32)> {
+    let mut stream = UnixStream::connect(socket)?;
+
+    let request = Json::object(vec![
+        ("folder", Json::string(folder.display())),
+        ("args", Json::Array(args.iter().map(Json::string).collect())),
+    ]);
+
+    writeln!(stream, "{}", request)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+
+    let reply = Json::parse(&line).ok_or_else(|| malformed("reply"))?;
+
+    let shown = reply.get("shown").and_then(Json::as_str);
+    let code = reply.get("code").and_then(Json::as_usize);
+
+    match (shown, code) {
+        (Some(shown), Some(code)) => Ok((shown.to_string(), code as i32)),
+        _ => Err(malformed("reply")),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn request(_socket: &Path, _folder: &Path, _args: &[String]) -> io::Result<(String, i32)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the daemon listens on a unix socket",
+    ))
+}
+
+fn malformed(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("malformed {}", what))
+}
+
+// the flags of the command line `given`, i.e. all but the `positional` arguments left by
+// `Build::from_args`, and but the program
+pub fn flags(given: &[String], positional: &[String]) -> Vec<String> {
+    let mut positional = positional.iter().skip(1).peekable();
+
+    given
+        .iter()
+        .skip(1)
+        .filter(|arg| {
+            if positional.peek() == Some(arg) {
+                positional.next();
+
+                false
+            } else {
+                true
+            }
+        })
+        .filter(|arg| *arg != "--use-daemon")
+        .cloned()
+        .collect()
+}
+
+// `wu build --use-daemon`, showing what the daemon running here showed building; its exit code,
+// or none when there's no daemon to build, and the build is done here after all
+pub fn build_by_daemon(given: &[String]) -> Option<i32> {
+    let folder = env::current_dir().unwrap_or_default();
+
+    let args = given
+        .iter()
+        .skip(1)
+        .filter(|arg| *arg != "--use-daemon")
+        .cloned()
+        .collect::<Vec<_>>();
+
+    match daemon::request(Path::new(daemon::SOCKET), &folder, &args) {
+        Ok((shown, code)) => {
+            print!("{}", shown);
+
+            Some(code)
+        }
+
+        Err(why) => {
+            response!(
+                Response::Weird(format!("no `wu daemon` to build with: {}", why)),
+                Response::Note("building without it")
+            );
+
+            None
+        }
+    }
+}
+
+// a build asked of `wu daemon` in `folder`, by the command line `args`, which has to have the
+// `flags` the daemon was started with, as those are set once for the whole process
+pub fn daemon_build(folder: &Path, mut args: Vec<String>, flags: &[String]) -> (String, i32) {
+    let (failure, shown) = captured(|| {
+        reset_tallies();
+
+        if let Err(why) = env::set_current_dir(folder) {
+            response!(Response::Wrong(format!(
+                "can't build in `{}`: {}",
+                folder.display(),
+                why
+            )));
+
+            return Some(Failure::Io);
+        }
+
+        args.insert(0, "wu".to_string());
+
+        let given = args.clone();
+
+        let mut build = match Build::from_args(&mut args) {
+            Ok(build) => build,
+            Err(failure) => return Some(failure),
+        };
+
+        let asked = self::flags(&given, &args);
+
+        if asked != flags {
+            response!(
+                Response::Wrong(format!(
+                    "the daemon builds with `{}`, not `{}`",
+                    flags.join(" "),
+                    asked.join(" ")
+                )),
+                Response::Note("restart `wu daemon` with the flags to build with")
+            );
+
+            return Some(Failure::Usage);
+        }
+
+        if args.get(1).map(String::as_str) != Some("build") {
+            response!(Response::Wrong("the daemon only does `wu build`"));
+
+            return Some(Failure::Usage);
+        }
+
+        if let Some(ref file) = build.baseline {
+            if let Err(failure) = load_baseline(file, false) {
+                return Some(failure);
+            }
+        }
+
+        build_project(args.get(2).map_or(".", String::as_str), &mut build);
+
+        build.failure
+    });
+
+    (shown, failure.map_or(0, |failure| failure as i32))
+}
+
+// `wu daemon`, serving builds here until it's stopped
+pub fn serve_builds(flags: &[String], build: &mut Build) {
+    let served = serve(Path::new(SOCKET), |folder, args| daemon_build(folder, args, flags));
+
+    if let Err(why) = served {
+        response!(Response::Wrong(format!("can't listen on `{}`: {}", SOCKET, why)));
+
+        build.fail(Failure::Io)
+    }
+}
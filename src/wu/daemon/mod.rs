@@ -0,0 +1,7 @@
+pub mod daemon;
+
+use super::build::*;
+use super::error::{captured, reset_tallies, Response};
+use super::lsp::Json;
+
+pub use self::daemon::*;
@@ -0,0 +1,118 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::*;
+
+// the edition a project without an `edition` in its wu.toml is written for
+pub const FIRST: usize = 1;
+
+// what `wu new` starts projects on
+pub const LATEST: usize = 2;
+
+static EDITION: AtomicUsize = AtomicUsize::new(FIRST);
+
+// which edition of the language to read code as, see `--edition`
+pub fn set_edition(edition: usize) {
+    EDITION.store(edition, Ordering::Relaxed)
+}
+
+pub fn edition() -> usize {
+    EDITION.load(Ordering::Relaxed)
+}
+
+// a breaking change an edition made, with the codemod that finds the code it breaks and
+// migrates it
+pub struct Migration {
+    pub edition: usize,
+    pub change: &'static str, // e.g. "splat types say what they take"
+    pub codemod: fn() -> Box<dyn Codemod>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    edition: 2,
+    change: "splat types say what they take",
+    codemod: || Box::new(TypedSplats),
+}];
+
+// checks `tokens` against the changes of the editions up to the one in use, which they break,
+// and warns where the later ones will; either way suggesting the fix
+pub fn check_edition(tokens: &SyntaxTokens, source: &Source) -> Result<(), ()> {
+    let mut broken = false;
+
+    for migration in MIGRATIONS.iter() {
+        let mut codemod = (migration.codemod)();
+
+        for at in 0..tokens.tokens.len() {
+            for patch in codemod.visit(tokens, at) {
+                let first = match tokens.tokens.get(patch.range.start) {
+                    Some(first) => &first.token,
+                    None => continue,
+                };
+
+                let pos = Pos(first.line.clone(), first.slice);
+
+                if edition() >= migration.edition {
+                    broken = true;
+
+                    response!(
+                        Response::Wrong(format!(
+                            "{} since edition {}",
+                            migration.change, migration.edition
+                        )),
+                        source.file,
+                        pos
+                    )
+                } else {
+                    let level = Lint::Edition.level();
+
+                    match level.response(format!(
+                        "from edition {}, {}",
+                        migration.edition, migration.change
+                    )) {
+                        Some(reported) => response!(reported, source.file, pos),
+                        None => continue,
+                    }
+
+                    broken |= level == LintLevel::Deny
+                }
+
+                suggest(&patch, tokens, source)
+            }
+        }
+    }
+
+    if broken {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+// the fix `wu fix` applies for `patch`, when it keeps to one line
+fn suggest(patch: &Patch, tokens: &SyntaxTokens, source: &Source) {
+    let first = &tokens.tokens[patch.range.start].token;
+
+    let last = match patch.range.end.checked_sub(1) {
+        Some(end) if end >= patch.range.start => &tokens.tokens[end].token,
+        _ => first,
+    };
+
+    if first.line.0 != last.line.0 {
+        return;
+    }
+
+    let span = if patch.range.is_empty() {
+        (first.line.0, first.slice.0, first.slice.0.saturating_sub(1))
+    } else {
+        (first.line.0, first.slice.0, last.slice.1)
+    };
+
+    fix::suggest(Fix {
+        title: format!("write `{}`", patch.text),
+        file: source.file.0.clone(),
+        line: first.line.0,
+        edits: vec![Edit {
+            span,
+            text: patch.text.clone(),
+        }],
+    })
+}
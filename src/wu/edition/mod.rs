@@ -0,0 +1,12 @@
+pub mod edition;
+
+use super::codemod::*;
+use super::codemods::*;
+use super::error::*;
+use super::fix::{self, Fix};
+use super::lexer::*;
+use super::parser::{Lint, LintLevel};
+use super::references::Edit;
+use super::source::*;
+
+pub use self::edition::*;
@@ -0,0 +1,58 @@
+// compiling Wu where it runs, for Rust hosts that embed Lua through mlua, e.g. to hot-load scripts
+use std::path::Path;
+
+use mlua::{Function, Lua};
+
+use super::compiler::Generator;
+use super::error::captured;
+use super::lexer::Lexer;
+use super::parser::Parser;
+use super::source::Source;
+use super::visitor::{SymTab, Visitor};
+
+// `content` as Lua, or the diagnostics saying why it isn't; `name` is the file it is reported
+// as, and its imports resolve next to it
+pub fn compile_str(name: &str, content: &str) -> Result<String, String> {
+    let source = Source::from(name, content.lines().map(|x| x.into()).collect());
+
+    let root = match Path::new(name).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+        _ => ".".to_string(),
+    };
+
+    let (lua, diagnostics) = captured(|| {
+        let mut tokens = Vec::new();
+
+        for token in Lexer::default(content.chars().collect(), &source) {
+            tokens.push(token?)
+        }
+
+        let ast = Parser::new(tokens, &source).parse()?;
+
+        let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root);
+        visitor.visit()?;
+
+        let lua = Generator::new(&source, &visitor).generate(&ast);
+
+        Ok::<_, ()>(lua)
+    });
+
+    lua.map_err(|_| diagnostics)
+}
+
+// compiles `content` and loads it into `lua` as a chunk named after `name`, so errors and
+// tracebacks point at the script; calling the chunk runs it and returns what it exports
+pub fn load_into_lua<'lua>(
+    lua: &'lua Lua,
+    name: &str,
+    content: &str,
+) -> mlua::Result<Function<'lua>> {
+    let compiled = compile_str(name, content).map_err(|diagnostics| mlua::Error::SyntaxError {
+        message: format!("{}: failed to compile\n{}", name, diagnostics),
+        incomplete_input: false,
+    })?;
+
+    lua.load(compiled)
+        .set_name(format!("@{}", name))
+        .into_function()
+}
@@ -0,0 +1,246 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::*;
+
+// a machine-applicable suggestion for a diagnostic
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub title: String, // e.g. "import `Vec2` from `math`"
+    pub file: String,
+    pub line: usize, // of the problem it fixes, which code actions are offered on
+    pub edits: Vec<Edit>,
+}
+
+static FIXES: Mutex<Vec<Fix>> = Mutex::new(Vec::new());
+
+// records `fix` for `wu fix` and code actions, and tells the user about it
+pub fn suggest(fix: Fix) {
+    response!(Response::Note(&fix.title));
+
+    FIXES.lock().unwrap().push(fix)
+}
+
+// everything suggested so far, leaving none behind
+pub fn take() -> Vec<Fix> {
+    FIXES.lock().unwrap().drain(..).collect()
+}
+
+// inserts `text` before `line`:`column`
+pub fn insertion(line: usize, column: usize, text: String) -> Edit {
+    Edit {
+        span: (line, column, column.saturating_sub(1)),
+        text,
+    }
+}
+
+// applies fixes file by file, skipping any touching text another one already changes
+pub fn apply_all(fixes: &[Fix]) -> Result<Vec<&Fix>, ()> {
+    let mut files: HashMap<&str, Vec<&Fix>> = HashMap::new();
+
+    for fix in fixes.iter() {
+        files.entry(&fix.file).or_default().push(fix)
+    }
+
+    let mut applied = Vec::new();
+
+    for (file, fixes) in files {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(why) => {
+                return Err(response!(Response::Wrong(format!(
+                    "failed to read `{}`: {}",
+                    file, why
+                ))))
+            }
+        };
+
+        let mut edits: Vec<Edit> = Vec::new();
+
+        for fix in fixes {
+            let overlaps = fix.edits.iter().any(|edit| {
+                edits.iter().any(|other| {
+                    edit.span.0 == other.span.0
+                        && edit.span.1 <= other.span.2.max(other.span.1)
+                        && other.span.1 <= edit.span.2.max(edit.span.1)
+                })
+            });
+
+            if !overlaps {
+                edits.extend(fix.edits.iter().cloned());
+                applied.push(fix)
+            }
+        }
+
+        if let Err(why) = fs::write(file, apply(&content, &edits)) {
+            return Err(response!(Response::Wrong(format!(
+                "failed to write `{}`: {}",
+                file, why
+            ))));
+        }
+    }
+
+    Ok(applied)
+}
+
+// a literal of type `kind` to fill in for a missing struct member
+pub fn default_value(kind: &Type) -> Option<&'static str> {
+    match kind.node {
+        TypeNode::Int => Some("0"),
+        TypeNode::Float => Some("0.0"),
+        TypeNode::Bool => Some("false"),
+        TypeNode::Str => Some("\"\""),
+        TypeNode::Char => Some("' '"),
+        _ => None,
+    }
+}
+
+// the modules of a project publicly defining each name, read the first time one is looked up and
+// then shared by the visitors of a compilation, so that every unknown name doesn't reread them
+#[derive(Debug, Clone, Default)]
+pub struct Exports(Rc<RefCell<Option<Index>>>);
+
+// module paths and their import paths, by the names they define
+type Index = HashMap<String, Vec<(PathBuf, String)>>;
+
+impl Exports {
+    // import paths, relative to `root`, of the modules publicly defining `name`; `file` excluded
+    pub fn exporters(&self, name: &str, root: &str, file: &str) -> Vec<String> {
+        let mut index = self.0.borrow_mut();
+        let index = index.get_or_insert_with(|| index_exports(root));
+
+        let file = fs::canonicalize(file).ok();
+
+        index
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|(path, _)| Some(path) != file.as_ref())
+            .map(|(_, import)| import.clone())
+            .collect()
+    }
+}
+
+// what each module below `root` publicly defines
+fn index_exports(root: &str) -> Index {
+    let root = if root.is_empty() { "." } else { root };
+
+    let mut index = Index::new();
+
+    let root_path = match fs::canonicalize(root) {
+        Ok(path) => path,
+        Err(_) => return index,
+    };
+
+    let mut files = Vec::new();
+    sources(root, &mut files);
+
+    for other in files.iter() {
+        let other_path = match fs::canonicalize(other) {
+            Ok(path) => path,
+            Err(_) => continue,
+        };
+
+        let relative = match other_path.strip_prefix(&root_path) {
+            Ok(relative) => relative.with_extension(""),
+            Err(_) => continue,
+        };
+
+        let relative = match relative.file_name() {
+            Some(stem) if stem == "init" => relative.parent().unwrap_or(Path::new("")),
+            _ => &relative,
+        };
+
+        let segments = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+
+        if segments.is_empty() {
+            continue;
+        }
+
+        let names = silently(|| {
+            let content = fs::read_to_string(&other_path).ok()?;
+            let source = Source::from(other, content.lines().map(|x| x.into()).collect());
+
+            let mut tokens = Vec::new();
+
+            for token_result in Lexer::default(content.chars().collect(), &source) {
+                tokens.push(token_result.ok()?)
+            }
+
+            let ast = Parser::new(tokens, &source).parse().ok()?;
+
+            Some(
+                tags::collect(&ast, &source)
+                    .into_iter()
+                    .filter(|tag| tag.scope.is_none())
+                    .map(|tag| tag.name)
+                    .collect::<HashSet<_>>(),
+            )
+        });
+
+        for name in names.into_iter().flatten() {
+            index
+                .entry(name)
+                .or_default()
+                .push((other_path.clone(), segments.join("/")))
+        }
+    }
+
+    index
+}
+
+// each round of `wu fix` can uncover new problems, but not forever
+const FIX_ROUNDS: usize = 10;
+
+pub fn fix_path(path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    let mut count = 0;
+
+    for _ in 0..FIX_ROUNDS {
+        fix::take();
+
+        silently(|| compile_path(path, &path.to_string(), build));
+
+        build.pending.clear();
+        build.failed.clear();
+        build.failure = None;
+        build.modules = 0;
+
+        let fixes = fix::take();
+
+        let applied = match fix::apply_all(&fixes) {
+            Ok(applied) => applied,
+            Err(()) => return build.fail(Failure::Io),
+        };
+
+        if applied.is_empty() {
+            break;
+        }
+
+        for fix in applied.iter() {
+            log!(Normal, "Fixed", "{}: {}", fix.file.replace("./", ""), fix.title);
+        }
+
+        count += applied.len()
+    }
+
+    log!(Verbose, "Fixed", "{} ({}ms)", plural(count, "problem"), now.elapsed().as_millis());
+
+    // whatever is left, reported as usual
+    let now = Instant::now();
+
+    compile_path(path, &path.to_string(), build);
+    fix::take();
+
+    build.pending.clear();
+    build.summary(now)
+}
@@ -0,0 +1,12 @@
+pub mod fix;
+
+use super::build::*;
+use super::error::*;
+use super::lexer::*;
+use super::parser::*;
+use super::references::*;
+use super::source::*;
+use super::tags;
+use super::visitor::*;
+
+pub use self::fix::*;
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use super::*;
+
+// `wu fmt`, every module rewritten the way `format_file` lays it out; with `--check`, nothing
+// written, only the modules that would be
+pub fn fmt_path(path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    if !Path::new(path).exists() {
+        response!(Response::Wrong(format!("can't read `{}`: no such file or directory", path)));
+
+        return build.fail(Failure::Io);
+    }
+
+    let mut files = Vec::new();
+    sources(path, &mut files);
+
+    let mut formatted = 0;
+
+    for file in files.iter() {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(why) => {
+                response!(Response::Wrong(format!("failed to read `{}`: {}", file, why)));
+
+                build.fail(Failure::Io);
+                continue;
+            }
+        };
+
+        let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+        let text = match format::format_file(&content, &source) {
+            Ok(text) => text,
+            Err(()) => {
+                build.fail(Failure::Parse);
+                continue;
+            }
+        };
+
+        if text == content {
+            continue;
+        }
+
+        formatted += 1;
+
+        if build.check {
+            response!(
+                Response::Wrong(format!("`{}` isn't formatted", file)),
+                Response::Note("`wu fmt` lays it out")
+            );
+
+            build.fail(Failure::Type);
+            continue;
+        }
+
+        if write(Path::new(file), &text).is_err() {
+            build.fail(Failure::Io);
+            continue;
+        }
+
+        log!(Verbose, "Formatted", "{}", file)
+    }
+
+    if build.check {
+        log!(
+            Normal,
+            "Checked",
+            "{}, {} unformatted ({}ms)",
+            plural(files.len(), "module"),
+            formatted,
+            now.elapsed().as_millis()
+        )
+    } else {
+        log!(
+            Normal,
+            "Formatted",
+            "{} of {} ({}ms)",
+            plural(formatted, "module"),
+            files.len(),
+            now.elapsed().as_millis()
+        )
+    }
+}
@@ -0,0 +1,8 @@
+pub mod fmt;
+
+use super::build::*;
+use super::error::*;
+use super::format;
+use super::source::{sources, Source};
+
+pub use self::fmt::*;
@@ -0,0 +1,402 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use super::*;
+
+// who depends on whom, as `--emit callgraph` and `--emit modgraph` write it for the whole build:
+// functions by their module's `require` name, e.g. `lib.util.double` and `shapes.Point.area`,
+// or modules by it, with the module's own top level calling as the module itself
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Graph {
+    pub nodes: BTreeSet<String>,
+    pub edges: BTreeSet<(String, String)>,
+}
+
+impl Graph {
+    pub fn extend(&mut self, other: Graph) {
+        self.nodes.extend(other.nodes);
+        self.edges.extend(other.edges);
+    }
+
+    fn edge(&mut self, from: &str, to: &str) {
+        self.nodes.insert(from.to_string());
+        self.nodes.insert(to.to_string());
+        self.edges.insert((from.to_string(), to.to_string()));
+    }
+
+    // for graphviz, e.g. `dot -Tsvg callgraph.dot`
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", name);
+
+        for node in self.nodes.iter() {
+            dot.push_str(&format!("  {:?};\n", node))
+        }
+
+        for (from, to) in self.edges.iter() {
+            dot.push_str(&format!("  {:?} -> {:?};\n", from, to))
+        }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    pub fn to_json(&self) -> String {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| format!("    \"{}\"", escape(node)))
+            .collect::<Vec<_>>();
+
+        let edges = self
+            .edges
+            .iter()
+            .map(|(from, to)| format!("    [\"{}\", \"{}\"]", escape(from), escape(to)))
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ]\n}}\n",
+            nodes.join(",\n"),
+            edges.join(",\n")
+        )
+    }
+}
+
+// the `require` name of the module compiled from `file`
+pub fn module_name(file: &str) -> String {
+    require_name(&Path::new(file).with_extension(""))
+}
+
+// the modules a checked module imports, leaving out those whose `cfg` is off
+pub fn modgraph(visitor: &Visitor) -> Graph {
+    let module = module_name(&visitor.source.file.0);
+
+    let mut graph = Graph::default();
+    graph.nodes.insert(module.clone());
+
+    for statement in visitor.ast.iter() {
+        if let StatementNode::Import(ref path, _, ref cfg, _) = statement.node {
+            if enabled(cfg) {
+                let path = visitor.import_paths.get(&statement.pos).unwrap_or(path);
+                let imported = imported_name(visitor.source, &visitor.import_map, statement, path);
+
+                graph.edge(&module, &imported)
+            }
+        }
+    }
+
+    graph
+}
+
+// the functions a checked module calls, by what they resolve to: its own functions, the members
+// of its modules and of the modules it imports, and methods of the structs it knows. Calls of
+// anything else, e.g. parameters, locals or `print`, are left out. Method calls are only known
+// when the visitor recorded the `types` of expressions
+pub fn callgraph(visitor: &Visitor) -> Graph {
+    let module = module_name(&visitor.source.file.0);
+
+    let mut calls = Calls {
+        visitor,
+        graph: Graph::default(),
+        names: HashMap::new(),
+        modules: HashMap::new(),
+        types: HashMap::new(),
+    };
+
+    calls.graph.nodes.insert(module.clone());
+    calls.declare(visitor.ast, &module);
+    calls.block(visitor.ast, &module, &module);
+
+    calls.graph
+}
+
+struct Calls<'c, 'v> {
+    visitor: &'c Visitor<'v>,
+    graph: Graph,
+
+    names: HashMap<String, String>,   // callable names, qualified
+    modules: HashMap<String, String>, // imported and inner modules, qualified
+    types: HashMap<String, String>,   // structs, qualified, for their methods
+}
+
+impl<'c, 'v> Calls<'c, 'v> {
+    // what `block` declares, before its calls are looked at, as they may come first
+    fn declare(&mut self, block: &[Statement], prefix: &str) {
+        for statement in block.iter() {
+            match statement.binding().node {
+                StatementNode::Variable(_, ref name, Some(ref right), _) => {
+                    let qualified = format!("{}.{}", prefix, name);
+
+                    match right.node {
+                        ExpressionNode::Function(..) => {
+                            self.graph.nodes.insert(qualified.clone());
+                            self.names.insert(name.clone(), qualified);
+                        }
+
+                        ExpressionNode::Struct(..) => {
+                            self.types.insert(name.clone(), qualified);
+                        }
+
+                        ExpressionNode::Module(ref content, _) => {
+                            if let ExpressionNode::Block(ref content) = content.node {
+                                self.declare(content, &qualified)
+                            }
+
+                            self.modules.insert(name.clone(), qualified);
+                        }
+
+                        _ => (),
+                    }
+                }
+
+                StatementNode::Import(ref path, ref members, ref cfg, _) if enabled(cfg) => {
+                    let path = self.visitor.import_paths.get(&statement.pos).unwrap_or(path);
+                    let imported = imported_name(
+                        self.visitor.source,
+                        &self.visitor.import_map,
+                        statement,
+                        path,
+                    );
+
+                    let expanded = self.visitor.import_expansions.get(&statement.pos);
+
+                    // `{ * }` brings in what the module turned out to export
+                    let members = members
+                        .iter()
+                        .filter(|(member, _)| member != "*")
+                        .cloned()
+                        .chain(
+                            expanded
+                                .into_iter()
+                                .flatten()
+                                .map(|name| (name.clone(), None)),
+                        )
+                        .collect::<Vec<_>>();
+
+                    for (member, alias) in members.iter() {
+                        let local = alias.as_ref().unwrap_or(member).clone();
+                        let qualified = format!("{}.{}", imported, member);
+
+                        self.types.insert(local.clone(), qualified.clone());
+                        self.names.insert(local, qualified);
+                    }
+
+                    let name = Path::new(path)
+                        .file_name()
+                        .map_or(path.clone(), |name| name.to_string_lossy().to_string());
+
+                    self.modules.insert(name, imported);
+                }
+
+                _ => (),
+            }
+        }
+    }
+
+    // the calls of a module's `block`, made by `caller`, with its functions named below `prefix`
+    fn block(&mut self, block: &[Statement], prefix: &str, caller: &str) {
+        for statement in block.iter() {
+            self.statement(statement, Some(prefix), caller)
+        }
+    }
+
+    // `prefix` is there at the top level of modules, where functions are nodes of their own
+    fn statement(&mut self, statement: &Statement, prefix: Option<&str>, caller: &str) {
+        use self::StatementNode::*;
+
+        match statement.node {
+            Expression(ref expression) => self.expression(expression, caller),
+
+            Variable(_, ref name, Some(ref right), _) => match (prefix, &right.node) {
+                // a module's functions call as themselves, nested ones as the function around them
+                (Some(prefix), ExpressionNode::Function(_, _, ref body, _)) => {
+                    self.expression(body, &format!("{}.{}", prefix, name))
+                }
+
+                (Some(prefix), ExpressionNode::Module(ref content, _)) => {
+                    if let ExpressionNode::Block(ref content) = content.node {
+                        let qualified = format!("{}.{}", prefix, name);
+
+                        self.block(content, &qualified, &qualified)
+                    }
+                }
+
+                _ => self.expression(right, caller),
+            },
+
+            SplatVariable(_, _, Some(ref right), _) => self.expression(right, caller),
+
+            Return(Some(ref right)) => self.expression(right, caller),
+
+            Assignment(ref left, ref right) => {
+                self.expression(left, caller);
+                self.expression(right, caller)
+            }
+
+            SplatAssignment(ref left, ref right) => {
+                for left in left.iter() {
+                    self.expression(left, caller)
+                }
+
+                self.expression(right, caller)
+            }
+
+            Implement(ref name, ref body, _) => {
+                let owner = match name.node {
+                    ExpressionNode::Identifier(ref name) => self
+                        .types
+                        .get(name)
+                        .cloned()
+                        .unwrap_or_else(|| format!("{}.{}", prefix.unwrap_or(caller), name)),
+                    _ => return,
+                };
+
+                if let ExpressionNode::Block(ref content) = body.node {
+                    for element in content.iter() {
+                        if let Variable(_, ref name, Some(ref right), _) = element.node {
+                            let method = format!("{}.{}", owner, name);
+
+                            self.graph.nodes.insert(method.clone());
+
+                            if let ExpressionNode::Function(_, _, ref body, _) = right.node {
+                                self.expression(body, &method)
+                            }
+                        }
+                    }
+                }
+            }
+
+            ExternBlock(ref statement) | Global(ref statement) => {
+                self.statement(statement, prefix, caller)
+            }
+
+            _ => (),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression, caller: &str) {
+        use self::ExpressionNode::*;
+
+        match expression.node {
+            Call(ref called, ref args) => {
+                if let Some(callee) = self.callee(called) {
+                    self.graph.edge(caller, &callee)
+                }
+
+                self.expression(called, caller);
+
+                for arg in args.iter() {
+                    self.expression(arg, caller)
+                }
+            }
+
+            UnwrapSplat(ref inner)
+            | Unwrap(ref inner)
+            | Neg(ref inner)
+            | Not(ref inner)
+            | Cast(ref inner, _)
+            | ExternExpression(ref inner) => self.expression(inner, caller),
+
+            Binary(ref left, _, ref right) | Index(ref left, ref right, _) => {
+                self.expression(left, caller);
+                self.expression(right, caller)
+            }
+
+            While(ref condition, ref body) => {
+                self.expression(condition, caller);
+                self.expression(body, caller)
+            }
+
+            For((ref iterator, ref other), ref body) => {
+                self.expression(iterator, caller);
+
+                if let Some(ref other) = *other {
+                    self.expression(other, caller)
+                }
+
+                self.expression(body, caller)
+            }
+
+            If(ref condition, ref body, ref cases) => {
+                self.expression(condition, caller);
+                self.expression(body, caller);
+
+                for (condition, body, _) in cases.iter().flatten() {
+                    if let Some(ref condition) = *condition {
+                        self.expression(condition, caller)
+                    }
+
+                    self.expression(body, caller)
+                }
+            }
+
+            Match(ref subject, ref arms) => {
+                self.expression(subject, caller);
+
+                for (_, _, body, _) in arms.iter() {
+                    self.expression(body, caller)
+                }
+            }
+
+            Tuple(ref content) | Array(ref content) | Splat(ref content) => {
+                for element in content.iter() {
+                    self.expression(element, caller)
+                }
+            }
+
+            Initialization(ref name, ref fields) => {
+                self.expression(name, caller);
+
+                for (_, value) in fields.iter() {
+                    self.expression(value, caller)
+                }
+            }
+
+            Function(_, _, ref body, _) => self.expression(body, caller),
+
+            Block(ref content) => {
+                for statement in content.iter() {
+                    self.statement(statement, None, caller)
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    // what a call of `called` resolves to, when it's one of the functions the graph knows
+    fn callee(&self, called: &Expression) -> Option<String> {
+        use self::ExpressionNode::*;
+
+        match called.node {
+            Identifier(ref name) => self.names.get(name).cloned(),
+
+            Index(ref left, ref right, false) => {
+                let member = match right.node {
+                    Identifier(ref member) => member,
+                    _ => return None,
+                };
+
+                // `util double`, `Point new`, and methods, `p area`
+                let owner = match left.node {
+                    Identifier(ref name) if self.modules.contains_key(name) => {
+                        self.modules.get(name).cloned()
+                    }
+
+                    Identifier(ref name) if self.types.contains_key(name) => {
+                        self.types.get(name).cloned()
+                    }
+
+                    _ => match self.visitor.types.as_ref()?.get(&left.pos)?.node {
+                        TypeNode::Struct(ref name, ..) => self.types.get(name).cloned(),
+                        _ => None,
+                    },
+                }?;
+
+                Some(format!("{}.{}", owner, member))
+            }
+
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod graph;
+
+use super::compiler::{imported_name, require_name};
+use super::highlight::escape;
+use super::parser::*;
+use super::visitor::*;
+
+pub use self::graph::*;
@@ -10,7 +10,9 @@ use std::io::Write;
 
 use std::path::Path;
 
-use colored::Colorize;
+use super::super::edition::LATEST;
+use super::super::error::Response;
+use super::super::parser::{Lint, LintLevel};
 
 pub fn new(name: Option<&str>) {
     if let Some(name) = name {
@@ -23,13 +25,17 @@ pub fn new(name: Option<&str>) {
             init.write_all(b"import src\n").unwrap();
 
             let mut wu_toml = File::create(&format!("{}/wu.toml", name)).unwrap();
-            wu_toml.write_all(b"[dependencies]\n").unwrap();
+            wu_toml
+                .write_all(format!("edition = {}\n\n[dependencies]\n", LATEST).as_bytes())
+                .unwrap();
 
             File::create(&format!("{}/src/init.wu", name)).unwrap();
         }
     } else {
         let mut wu_toml = File::create("wu.toml").unwrap();
-        wu_toml.write_all(b"[dependencies]").unwrap();
+        wu_toml
+            .write_all(format!("edition = {}\n\n[dependencies]", LATEST).as_bytes())
+            .unwrap();
 
         File::create("src/init.wu").unwrap();
     }
@@ -71,15 +77,7 @@ pub fn get() {
                                     fs::remove_dir_all(path).unwrap()
                                 }
 
-                                println!(
-                                    "{}",
-                                    format!(
-                                        "{} {} => `{}`",
-                                        "Cloning".green().bold(),
-                                        member.0,
-                                        dep_path
-                                    )
-                                );
+                                log!(Normal, "Cloning", "{} => `{}`", member.0, dep_path);
                                 clone(&format!("https://github.com/{}", url), path);
 
                                 modules.push(format!("import {}", member.0))
@@ -101,6 +99,99 @@ pub fn get() {
     }
 }
 
+// the `outdir` key of 'wu.toml', if any
+pub fn out_dir() -> Option<String> {
+    let mut contents = String::new();
+
+    File::open("wu.toml").ok()?.read_to_string(&mut contents).ok()?;
+
+    match toml::from_str::<Value>(&contents).ok()?.get("outdir") {
+        Some(Value::String(path)) => Some(path.clone()),
+        Some(_) => {
+            wrong("Expected string `outdir` value");
+            None
+        }
+        None => None,
+    }
+}
+
+// the `framework` key of 'wu.toml', e.g. `love`, if any
+pub fn framework() -> Option<String> {
+    let mut contents = String::new();
+
+    File::open("wu.toml").ok()?.read_to_string(&mut contents).ok()?;
+
+    match toml::from_str::<Value>(&contents).ok()?.get("framework") {
+        Some(Value::String(framework)) => Some(framework.clone()),
+        Some(_) => {
+            wrong("Expected string `framework` value");
+            None
+        }
+        None => None,
+    }
+}
+
+// the `edition` key of 'wu.toml', e.g. `2`, if any
+pub fn edition() -> Option<usize> {
+    let mut contents = String::new();
+
+    File::open("wu.toml").ok()?.read_to_string(&mut contents).ok()?;
+
+    match toml::from_str::<Value>(&contents).ok()?.get("edition") {
+        Some(Value::Integer(edition)) if *edition > 0 => Some(*edition as usize),
+        Some(_) => {
+            wrong("Expected positive integer `edition` value");
+            None
+        }
+        None => None,
+    }
+}
+
+// the `[lints]` table of 'wu.toml', e.g. `unused_exports = "deny"`
+pub fn lints() -> Vec<(Lint, LintLevel)> {
+    let mut contents = String::new();
+
+    let table = File::open("wu.toml")
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .ok()
+        .and_then(|_| toml::from_str::<Value>(&contents).ok())
+        .and_then(|manifest| manifest.get("lints").cloned());
+
+    let table = match table {
+        Some(Value::Table(table)) => table,
+        Some(_) => {
+            wrong("Expected table `lints`");
+            return Vec::new();
+        }
+        None => return Vec::new(),
+    };
+
+    let mut lints = Vec::new();
+
+    for (name, level) in table.iter() {
+        let lint = match Lint::named(name) {
+            Some(lint) => lint,
+            None => {
+                response!(
+                    Response::Wrong(format!("no such lint `{}` in `[lints]`", name)),
+                    Response::Note(format!("the lints are {}", Lint::names()))
+                );
+                continue;
+            }
+        };
+
+        match level.as_str().and_then(LintLevel::from) {
+            Some(level) => lints.push((lint, level)),
+            None => wrong(&format!(
+                "Expected `allow`, `warn` or `deny` for lint `{}`",
+                name
+            )),
+        }
+    }
+
+    lints
+}
+
 fn clone(url: &str, path: &str) {
     let cb = RemoteCallbacks::new();
     let co = CheckoutBuilder::new();
@@ -121,5 +212,5 @@ fn clone(url: &str, path: &str) {
 }
 
 fn wrong(message: &str) {
-    println!("{} {}", "wrong:".red().bold(), message)
+    response!(Response::Wrong(message))
 }
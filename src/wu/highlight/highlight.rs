@@ -0,0 +1,539 @@
+use std::collections::{HashMap, HashSet};
+
+use super::super::references::declared_span;
+use super::*;
+
+pub const BUILTIN_TYPES: &[&str] = &["int", "float", "str", "char", "bool", "any"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Class {
+    Keyword,
+    Operator,
+    String,
+    Number,
+    Constant,
+    Type,
+    Function,
+    Parameter,
+    Field,
+    Module,
+    Variable,
+}
+
+impl Class {
+    pub fn name(&self) -> &'static str {
+        use self::Class::*;
+
+        match *self {
+            Keyword => "keyword",
+            Operator => "operator",
+            String => "string",
+            Number => "number",
+            Constant => "constant",
+            Type => "type",
+            Function => "function",
+            Parameter => "parameter",
+            Field => "field",
+            Module => "module",
+            Variable => "variable",
+        }
+    }
+
+    // the TextMate scope, shared by exported highlights and the generated grammar
+    pub fn scope(&self) -> &'static str {
+        use self::Class::*;
+
+        match *self {
+            Keyword => "keyword.control.wu",
+            Operator => "keyword.operator.wu",
+            String => "string.quoted.wu",
+            Number => "constant.numeric.wu",
+            Constant => "constant.language.wu",
+            Type => "entity.name.type.wu",
+            Function => "entity.name.function.wu",
+            Parameter => "variable.parameter.wu",
+            Field => "variable.other.member.wu",
+            Module => "entity.name.namespace.wu",
+            Variable => "variable.other.wu",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    pub line: usize,   // 1-based, like diagnostics
+    pub column: usize, // 0-based, in characters
+    pub length: usize,
+    pub class: Class,
+}
+
+// (line, start, end), as carried by `Pos`
+type Span = (usize, usize, usize);
+
+fn span(pos: &Pos) -> Span {
+    ((pos.0).0, (pos.1).0, (pos.1).1)
+}
+
+// what the syntax tree knows about identifiers
+#[derive(Default)]
+struct Semantics {
+    spans: HashMap<Span, Class>,
+    types: HashSet<String>,
+    functions: HashSet<String>,
+    modules: HashSet<String>,
+    params: Vec<Vec<String>>,
+}
+
+impl Semantics {
+    fn identifier(&self, name: &str) -> Class {
+        if name == "self"
+            || self
+                .params
+                .iter()
+                .any(|scope| scope.iter().any(|param| param == name))
+        {
+            Class::Parameter
+        } else if self.types.contains(name) || BUILTIN_TYPES.contains(&name) {
+            Class::Type
+        } else if self.functions.contains(name) {
+            Class::Function
+        } else if self.modules.contains(name) {
+            Class::Module
+        } else {
+            Class::Variable
+        }
+    }
+
+    fn mark(&mut self, expression: &Expression, class: Class) {
+        if let ExpressionNode::Identifier(_) = expression.node {
+            self.spans.insert(span(&expression.pos), class);
+        }
+    }
+
+    // marks the last name of e.g. `foo Moving`
+    fn mark_type(&mut self, expression: &Expression) {
+        match expression.node {
+            ExpressionNode::Identifier(_) => self.mark(expression, Class::Type),
+            ExpressionNode::Index(ref left, ref index, _) => {
+                self.expression(left);
+                self.mark(index, Class::Type)
+            }
+            _ => self.expression(expression),
+        }
+    }
+
+    fn block(&mut self, ast: &[Statement]) {
+        for statement in ast.iter() {
+            self.statement(statement)
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        use self::StatementNode::*;
+
+        match statement.node {
+            Expression(ref expression) => self.expression(expression),
+
+            Variable(_, ref name, ref right, _) => {
+                let class = match right.as_ref().map(|right| &right.node) {
+                    Some(ExpressionNode::Function(..)) => {
+                        self.functions.insert(name.clone());
+                        Class::Function
+                    }
+
+                    Some(ExpressionNode::Struct(..))
+                    | Some(ExpressionNode::Trait(..))
+                    | Some(ExpressionNode::Enum(..)) => {
+                        self.types.insert(name.clone());
+                        Class::Type
+                    }
+
+                    Some(ExpressionNode::Module(..)) => {
+                        self.modules.insert(name.clone());
+                        Class::Module
+                    }
+
+                    Some(ExpressionNode::Extern(t, _)) if matches!(t.node, TypeNode::Func(..)) => {
+                        self.functions.insert(name.clone());
+                        Class::Function
+                    }
+
+                    _ => Class::Variable,
+                };
+
+                self.spans.insert(declared_span(&statement.pos, name), class);
+
+                if let Some(ref right) = *right {
+                    self.expression(right)
+                }
+            }
+
+            SplatVariable(_, _, Some(ref right), _) => self.expression(right),
+
+            Assignment(ref left, ref right) => {
+                self.expression(left);
+                self.expression(right)
+            }
+
+            SplatAssignment(ref left, ref right) => {
+                for expression in left.iter() {
+                    self.expression(expression)
+                }
+
+                self.expression(right)
+            }
+
+            Return(Some(ref expression)) => self.expression(expression),
+
+            Implement(ref name, ref body, ref parent) => {
+                self.mark_type(name);
+
+                if let Some(ref parent) = *parent {
+                    self.mark_type(parent)
+                }
+
+                self.expression(body)
+            }
+
+            Global(ref binding) => self.statement(binding),
+
+            _ => (),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        use self::ExpressionNode::*;
+
+        match expression.node {
+            Identifier(ref name) => {
+                let class = self.identifier(name);
+                self.spans.insert(span(&expression.pos), class);
+            }
+
+            Call(ref callee, ref args) => {
+                match callee.node {
+                    Identifier(_) => self.mark(callee, Class::Function),
+                    Index(ref left, ref index, false) => {
+                        self.expression(left);
+                        self.mark(index, Class::Function)
+                    }
+                    _ => self.expression(callee),
+                }
+
+                for arg in args.iter() {
+                    self.expression(arg)
+                }
+            }
+
+            Index(ref left, ref index, is_array) => {
+                self.expression(left);
+
+                if is_array {
+                    self.expression(index)
+                } else {
+                    self.mark(index, Class::Field)
+                }
+            }
+
+            Function(ref params, _, ref body, _) => {
+                self.params
+                    .push(params.iter().map(|param| param.0.clone()).collect());
+
+                self.expression(body);
+
+                self.params.pop();
+            }
+
+            Initialization(ref name, ref fields) => {
+                self.mark_type(name);
+
+                for field in fields.iter() {
+                    self.expression(&field.1)
+                }
+            }
+
+            Block(ref content) => self.block(content),
+
+            If(ref condition, ref body, ref elses) => {
+                self.expression(condition);
+                self.expression(body);
+
+                if let Some(ref elses) = *elses {
+                    for (condition, body, _) in elses.iter() {
+                        if let Some(ref condition) = *condition {
+                            self.expression(condition)
+                        }
+
+                        self.expression(body)
+                    }
+                }
+            }
+
+            For((ref iterator, ref other), ref body) => {
+                self.expression(iterator);
+
+                if let Some(ref other) = *other {
+                    self.expression(other)
+                }
+
+                self.expression(body)
+            }
+
+            While(ref condition, ref body) => {
+                self.expression(condition);
+                self.expression(body)
+            }
+
+            Match(ref subject, ref arms) => {
+                self.expression(subject);
+
+                for (_, _, body, _) in arms.iter() {
+                    self.expression(body)
+                }
+            }
+
+            Binary(ref left, _, ref right) => {
+                self.expression(left);
+                self.expression(right)
+            }
+
+            Tuple(ref content) | Array(ref content) | Splat(ref content) => {
+                for expression in content.iter() {
+                    self.expression(expression)
+                }
+            }
+
+            Neg(ref inner)
+            | Not(ref inner)
+            | Unwrap(ref inner)
+            | UnwrapSplat(ref inner)
+            | Cast(ref inner, _)
+            | Module(ref inner, _)
+            | ExternExpression(ref inner) => self.expression(inner),
+
+            _ => (),
+        }
+    }
+}
+
+// classifies every meaningful token, using the syntax tree where it knows better than the token
+pub fn highlights(tokens: &[Token], ast: &[Statement]) -> Vec<Highlight> {
+    let mut semantics = Semantics::default();
+
+    // twice, so uses above a declaration know what it declares
+    semantics.block(ast);
+    semantics.block(ast);
+
+    // what the names declared inside each open bracket are
+    let mut brackets: Vec<Option<Class>> = Vec::new();
+    let mut after_new = false;
+
+    let mut highlights = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        let previous = tokens[..i]
+            .iter()
+            .rev()
+            .find(|token| token.token_type != TokenType::EOL)
+            .map(|token| token.lexeme.as_str());
+
+        let next = tokens.get(i + 1).map(|token| token.lexeme.as_str());
+
+        let class = match token.token_type {
+            TokenType::Keyword if token.lexeme == "nil" => Class::Constant,
+            TokenType::Keyword => {
+                if token.lexeme == "new" {
+                    after_new = true
+                }
+
+                Class::Keyword
+            }
+
+            TokenType::Bool => Class::Constant,
+            TokenType::Int | TokenType::Float => Class::Number,
+            TokenType::Str | TokenType::Interpolation | TokenType::Char => Class::String,
+            TokenType::Operator => Class::Operator,
+
+            TokenType::Identifier => match brackets.last() {
+                Some(Some(Class::Parameter)) if next == Some(":") || token.lexeme == "self" => {
+                    Class::Parameter
+                }
+
+                Some(Some(Class::Field)) if next == Some(":") => Class::Field,
+
+                _ => match semantics
+                    .spans
+                    .get(&(token.line.0, token.slice.0, token.slice.1))
+                {
+                    Some(class) => *class,
+                    None => semantics.identifier(&token.lexeme),
+                },
+            },
+
+            TokenType::Symbol => {
+                match token.lexeme.as_str() {
+                    "(" => brackets.push(if previous == Some("fun") {
+                        Some(Class::Parameter)
+                    } else {
+                        None
+                    }),
+
+                    "{" => {
+                        brackets.push(if previous == Some("struct") || after_new {
+                            Some(Class::Field)
+                        } else {
+                            None
+                        });
+
+                        after_new = false
+                    }
+
+                    "[" => brackets.push(None),
+
+                    ")" | "}" | "]" => {
+                        brackets.pop();
+                    }
+
+                    _ => (),
+                }
+
+                continue;
+            }
+
+            _ => continue,
+        };
+
+        let column = token.slice.0.saturating_sub(1);
+
+        highlights.push(Highlight {
+            line: token.line.0,
+            column,
+            length: token.slice.1.saturating_sub(column),
+            class,
+        })
+    }
+
+    highlights
+}
+
+pub fn escape(text: &str) -> String {
+    let mut result = String::new();
+
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+pub fn to_json(file: &str, highlights: &[Highlight]) -> String {
+    let mut result = format!("{{\n  \"file\": \"{}\",\n  \"highlights\": [", escape(file));
+
+    for (i, highlight) in highlights.iter().enumerate() {
+        result.push_str(&format!(
+            "{}\n    {{ \"line\": {}, \"column\": {}, \"length\": {}, \"class\": \"{}\", \"scope\": \"{}\" }}",
+            if i == 0 { "" } else { "," },
+            highlight.line,
+            highlight.column,
+            highlight.length,
+            highlight.class.name(),
+            highlight.class.scope()
+        ))
+    }
+
+    result.push_str("\n  ]\n}\n");
+
+    result
+}
+
+fn regex_escape(text: &str) -> String {
+    let mut result = String::new();
+
+    for c in text.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            result.push('\\')
+        }
+
+        result.push(c)
+    }
+
+    result
+}
+
+fn words(words: &[&str]) -> String {
+    let mut unique = Vec::new();
+
+    for word in words.iter() {
+        if word.chars().all(char::is_alphanumeric) && !unique.contains(word) {
+            unique.push(*word)
+        }
+    }
+
+    format!(r"\b({})\b", unique.join("|"))
+}
+
+// a TextMate grammar for editors without semantic highlighting, generated from the lexer's tables
+pub fn grammar() -> String {
+    let keywords = KEYWORDS
+        .iter()
+        .filter(|keyword| **keyword != "nil")
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut operators = OPERATORS
+        .iter()
+        .chain(
+            KEYWORDS
+                .iter()
+                .filter(|keyword| !keyword.chars().all(char::is_alphanumeric)),
+        )
+        .map(|operator| regex_escape(operator))
+        .collect::<Vec<_>>();
+
+    // longest first, so `==` isn't read as two `=`
+    operators.sort_by_key(|operator| std::cmp::Reverse(operator.len()));
+
+    let patterns = [
+        ("comment.line.number-sign.wu", r"#.*$".to_string()),
+        (Class::String.scope(), r#"r?"(\\.|[^"\\])*""#.to_string()),
+        (Class::String.scope(), r"'(\\.|[^'\\])'".to_string()),
+        (
+            Class::Number.scope(),
+            r"\b[0-9][0-9_]*(\.[0-9_]+)?\b".to_string(),
+        ),
+        (Class::Constant.scope(), words(&["true", "false", "nil"])),
+        (Class::Keyword.scope(), words(&keywords)),
+        (Class::Operator.scope(), words(WORD_OPERATORS)),
+        (Class::Operator.scope(), operators.join("|")),
+        (Class::Type.scope(), words(BUILTIN_TYPES)),
+        (
+            Class::Function.scope(),
+            r"\b[A-Za-z_][A-Za-z0-9_]*(?=\s*:\s*(extern\s+)?fun\b)".to_string(),
+        ),
+        (Class::Type.scope(), r"\b[A-Z][A-Za-z0-9_]*\b".to_string()),
+    ];
+
+    let mut result = String::from(
+        "{\n  \"name\": \"Wu\",\n  \"scopeName\": \"source.wu\",\n  \"fileTypes\": [\"wu\"],\n  \"patterns\": [",
+    );
+
+    for (i, (scope, pattern)) in patterns.iter().enumerate() {
+        result.push_str(&format!(
+            "{}\n    {{ \"name\": \"{}\", \"match\": \"{}\" }}",
+            if i == 0 { "" } else { "," },
+            scope,
+            escape(pattern)
+        ))
+    }
+
+    result.push_str("\n  ]\n}\n");
+
+    result
+}
@@ -0,0 +1,7 @@
+pub mod highlight;
+
+use super::lexer::*;
+use super::parser::*;
+use super::visitor::*;
+
+pub use self::highlight::*;
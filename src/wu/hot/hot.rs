@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+// where `wu run --hot` lists the recompiled modules, one `<round> <lua file>` a line, for the
+// running program to pick up
+pub const SIGNAL: &str = ".wu-hot";
+
+// run ahead of the program with `lua -e`: every thousand instructions, at most five times a
+// second, it reloads the modules listed in `SIGNAL` since it last looked, through the new
+// module's `__reload`, then lets every module look its named imports up again. Modules are
+// found in `package.loaded` by their file, `a/b.lua` is `a.b`
+pub const DRIVER: &str = r#"
+local seen, last = 0, os.clock()
+
+debug.sethook(function()
+  if os.clock() - last < 0.2 then
+    return
+  end
+
+  last = os.clock()
+
+  local signal = io.open(".wu-hot")
+
+  if not signal then
+    return
+  end
+
+  for line in signal:lines() do
+    local round, file = line:match("^(%d+) (.*)$")
+
+    if round and tonumber(round) > seen then
+      seen = tonumber(round)
+
+      local name = file:gsub("^%./", ""):gsub("%.lua$", ""):gsub("[/\\]", ".")
+      local old = package.loaded[name]
+
+      if type(old) == "table" then
+        local ok, fresh = pcall(dofile, file)
+
+        if ok and type(fresh) == "table" and fresh.__reload then
+          fresh.__reload(old)
+
+          for _, rebind in pairs(package.wu_rebinds or {}) do
+            rebind()
+          end
+        else
+          io.stderr:write("can't reload `" .. name .. "`: " .. tostring(fresh) .. "\n")
+        end
+      end
+    end
+  end
+
+  signal:close()
+end, "", 1000)
+"#;
+
+// when each of `files` was last changed
+pub fn stamps(files: &[String]) -> HashMap<String, SystemTime> {
+    files
+        .iter()
+        .filter_map(|file| {
+            let modified = fs::metadata(file).and_then(|meta| meta.modified()).ok()?;
+
+            Some((file.clone(), modified))
+        })
+        .collect()
+}
+
+// tells the running program to reload `lua` in `round`
+pub fn signal(round: usize, lua: &Path) -> io::Result<()> {
+    let mut signal = OpenOptions::new().create(true).append(true).open(SIGNAL)?;
+
+    writeln!(signal, "{} {}", round, lua.display())
+}
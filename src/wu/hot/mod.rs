@@ -0,0 +1,3 @@
+pub mod hot;
+
+pub use self::hot::*;
@@ -0,0 +1,154 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::panic;
+use std::sync::Mutex;
+
+use super::*;
+
+// how many of the compiler's own frames an ICE shows
+const FRAMES: usize = 12;
+
+thread_local! {
+    // the nodes being worked on, innermost last
+    static PROCESSING: RefCell<Vec<(String, Pos)>> = const { RefCell::new(Vec::new()) };
+}
+
+// what the panic hook saw, for `report` to show once the panic is caught
+static ICE: Mutex<Option<Ice>> = Mutex::new(None);
+
+struct Ice {
+    message: String,
+    location: String,
+    processing: Option<(String, Pos)>,
+    backtrace: Backtrace,
+}
+
+// marks a node as being worked on until the guard is dropped
+pub struct Processing;
+
+impl Drop for Processing {
+    fn drop(&mut self) {
+        let _ = PROCESSING.try_with(|processing| processing.borrow_mut().pop());
+    }
+}
+
+pub fn processing(file: &FilePath, pos: &Pos) -> Processing {
+    PROCESSING.with(|processing| processing.borrow_mut().push((file.0.clone(), pos.clone())));
+
+    Processing
+}
+
+// replaces the raw panic dump with a note of the panic, rendered by `report`
+pub fn install() {
+    panic::set_hook(Box::new(|info| {
+        let message = message(info.payload());
+
+        let location = info.location().map_or_else(String::new, |location| {
+            format!(
+                "{}:{}:{}",
+                location.file(),
+                location.line(),
+                location.column()
+            )
+        });
+
+        let processing = PROCESSING
+            .try_with(|processing| processing.borrow().last().cloned())
+            .ok()
+            .flatten();
+
+        if let Ok(mut ice) = ICE.lock() {
+            *ice = Some(Ice {
+                message,
+                location,
+                processing,
+                backtrace: Backtrace::force_capture(),
+            })
+        }
+    }))
+}
+
+// what a panic said, given what it panicked with
+pub fn message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+// the compiler's own frames of a backtrace, innermost first, without the panic machinery
+fn minimize(backtrace: &Backtrace) -> Vec<String> {
+    let mut frames: Vec<(String, Option<String>)> = Vec::new();
+
+    for line in backtrace.to_string().lines() {
+        let line = line.trim();
+
+        if let Some(at) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                let at = at.find("src/").map_or(at, |src| &at[src..]);
+
+                frame.1.get_or_insert_with(|| at.to_string());
+            }
+        } else if let Some((index, symbol)) = line.split_once(": ") {
+            if index.chars().all(|c| c.is_ascii_digit()) {
+                frames.push((symbol.to_string(), None))
+            }
+        }
+    }
+
+    frames
+        .into_iter()
+        .filter(|(symbol, _)| symbol.starts_with("wu::") && !symbol.starts_with("wu::wu::ice::"))
+        .take(FRAMES)
+        .map(|(symbol, at)| {
+            let symbol = symbol.replacen("wu::wu::", "wu::", 1);
+
+            match at {
+                Some(at) => format!("    {} ({})", symbol, at),
+                None => format!("    {}", symbol),
+            }
+        })
+        .collect()
+}
+
+// reports the caught panic as an internal compiler error
+pub fn report() {
+    let ice = ICE.lock().ok().and_then(|mut ice| ice.take());
+
+    let ice = match ice {
+        Some(ice) => ice,
+        None => {
+            response!(Wrong("internal compiler error"));
+            return;
+        }
+    };
+
+    // the payload can be a whole debug dump, the first line says what went wrong
+    let message = format!(
+        "internal compiler error: {}",
+        ice.message.lines().next().unwrap_or_default()
+    );
+
+    match ice.processing {
+        Some((file, pos)) => response!(Wrong(message), FilePath(file), pos),
+        None => response!(Wrong(message)),
+    }
+
+    response!(Note(format!(
+        "wu {} panicked at {}",
+        env!("CARGO_PKG_VERSION"),
+        ice.location
+    )));
+
+    let frames = minimize(&ice.backtrace);
+
+    if !frames.is_empty() {
+        response!(Note(format!("backtrace:\n{}", frames.join("\n"))))
+    }
+
+    response!(Note(
+        "this is a bug in wu, please report it along with the code that caused it"
+    ))
+}
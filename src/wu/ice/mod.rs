@@ -0,0 +1,7 @@
+pub mod ice;
+
+use super::error::Response::*;
+use super::lexer::*;
+use super::source::*;
+
+pub use self::ice::*;
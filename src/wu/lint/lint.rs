@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use super::*;
+
+// checks everything below `path` like a build would, writing nothing but a new baseline
+pub fn lint_path(path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    compile_path(path, &path.to_string(), build);
+    build.pending.clear();
+
+    if build.failed.is_empty()
+        && Path::new(path).join("main.wu").is_file()
+        && references::warn_unused_exports(Path::new(path)).is_err()
+    {
+        build.fail(Failure::Type)
+    }
+
+    if let Some(ref file) = build.baseline {
+        if !file.exists() {
+            let baseline = Baseline::of(recorded());
+
+            match fs::write(file, baseline.to_json()) {
+                Ok(_) => log!(
+                    Normal,
+                    "Baselined",
+                    "{} in `{}`",
+                    plural(baseline.len(), "warning"),
+                    file.display()
+                ),
+
+                Err(why) => {
+                    response!(Response::Wrong(format!(
+                        "failed to write `{}`: {}",
+                        file.display(),
+                        why
+                    )));
+
+                    build.fail(Failure::Io)
+                }
+            }
+        }
+    }
+
+    build.summary(now)
+}
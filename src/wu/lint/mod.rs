@@ -0,0 +1,8 @@
+pub mod lint;
+
+use super::baseline::Baseline;
+use super::build::*;
+use super::error::*;
+use super::references;
+
+pub use self::lint::*;
@@ -0,0 +1,120 @@
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::super::error::Response::*;
+use super::*;
+
+// whether the `main.wu` of a LÖVE game sets the callbacks it exports, see `--love`
+static LOVE: AtomicBool = AtomicBool::new(false);
+
+pub fn wire_love() {
+    LOVE.store(true, Ordering::Relaxed)
+}
+
+// the callbacks that are wired, with what LÖVE calls them with
+fn callbacks() -> Vec<(&'static str, Vec<Type>)> {
+    let str = || Type::from(TypeNode::Str);
+
+    vec![
+        ("load", vec![Type::from(TypeNode::Array(Rc::new(str()), None))]),
+        ("update", vec![Type::from(TypeNode::Float)]),
+        ("draw", vec![]),
+        ("keypressed", vec![str(), str(), Type::from(TypeNode::Bool)]),
+    ]
+}
+
+// whether `file` compiles to the `main.lua` LÖVE starts from
+pub fn is_love_main(file: &str) -> bool {
+    LOVE.load(Ordering::Relaxed) && Path::new(file).file_stem().is_some_and(|stem| stem == "main")
+}
+
+// the callbacks `main` exports, once they are checked against what LÖVE passes them
+pub fn wired(visitor: &Visitor) -> Result<Vec<&'static str>, ()> {
+    let mut wired = Vec::new();
+
+    for (name, params) in callbacks() {
+        let found = visitor
+            .ast
+            .iter()
+            .map(Statement::binding)
+            .find_map(|statement| match statement.node {
+                StatementNode::Variable(_, ref n, _, public) if n == name => {
+                    Some((statement, public))
+                }
+                _ => None,
+            });
+
+        let (declaration, public) = match found {
+            Some(found) => found,
+            None => continue,
+        };
+
+        if !public {
+            let level = visitor.pragmas.level(Lint::PrivateCallbacks);
+
+            if let Some(reported) =
+                level.response(format!("`{}` isn't `pub`, so LÖVE won't call it", name))
+            {
+                response!(reported, visitor.source.file, declaration.pos)
+            }
+
+            if level == LintLevel::Deny {
+                return Err(());
+            }
+
+            continue;
+        }
+
+        let t = match visitor.module_content.get(name) {
+            Some(t) => t,
+            None => continue,
+        };
+        let expected = Type::function(params.clone(), Type::from(TypeNode::Nil), false);
+
+        // lua drops the arguments a function doesn't take, so it may leave out those at the end
+        let fits = match t.node {
+            TypeNode::Func(ref declared, ..) => {
+                declared.len() <= params.len()
+                    && declared.iter().zip(params.iter()).all(|(a, b)| a.node == b.node)
+            }
+
+            _ => false,
+        };
+
+        if !fits {
+            return Err(response!(
+                Wrong(format!(
+                    "mismatched callback, LÖVE calls `love.{}` as `{}` but it is `{}`",
+                    name, expected, t
+                )),
+                visitor.source.file,
+                declaration.pos
+            ));
+        }
+
+        wired.push(name)
+    }
+
+    Ok(wired)
+}
+
+// the compiled `main`, setting `love.<name>` to each of `callbacks` it exports
+pub fn wire(lua: &str, callbacks: &[&str]) -> String {
+    // with `--pretty-errors`, the module returns after the helper ahead of it
+    let start = match lua.starts_with("return ") {
+        true => 0,
+        false => lua.rfind("\nreturn ").map_or(0, |at| at + 1),
+    };
+
+    let (ahead, module) = lua.split_at(start);
+    let module = module.strip_prefix("return ").unwrap_or(module);
+
+    let mut main = format!("{}local __main = {}\n", ahead, module);
+
+    for name in callbacks {
+        main.push_str(&format!("\nlove.{0} = __main.{0}", name))
+    }
+
+    main
+}
@@ -0,0 +1,6 @@
+pub mod love;
+
+use super::parser::*;
+use super::visitor::*;
+
+pub use self::love::*;
@@ -0,0 +1,295 @@
+use std::fmt;
+
+use super::super::highlight::escape;
+
+// what LSP messages are made of, just enough of JSON for the server to read and write them
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>), // in the order written
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Option<Json> {
+        let mut reader = Reader {
+            chars: text.chars().collect(),
+            at: 0,
+        };
+
+        let json = reader.value()?;
+
+        reader.skip_whitespace();
+
+        if reader.at == reader.chars.len() {
+            Some(json)
+        } else {
+            None
+        }
+    }
+
+    pub fn object(members: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(text: impl fmt::Display) -> Json {
+        Json::Str(text.to_string())
+    }
+
+    // the member `key` of an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    // the member found by following `keys` through nested objects, e.g. `["position", "line"]`
+    pub fn at(&self, keys: &[&str]) -> Option<&Json> {
+        keys.iter().try_fold(self, |json, key| json.get(key))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::Str(ref text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Json::Number(number) if number >= 0.0 && number.fract() == 0.0 => Some(number as usize),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                write!(f, "{}", number as i64)
+            }
+            Json::Number(number) => write!(f, "{}", number),
+            Json::Str(ref text) => write!(f, "\"{}\"", escape(text)),
+
+            Json::Array(ref elements) => {
+                write!(f, "[")?;
+
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?
+                    }
+
+                    write!(f, "{}", element)?
+                }
+
+                write!(f, "]")
+            }
+
+            Json::Object(ref members) => {
+                write!(f, "{{")?;
+
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?
+                    }
+
+                    write!(f, "\"{}\":{}", escape(key), value)?
+                }
+
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct Reader {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl Reader {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.at).is_some_and(|c| c.is_whitespace()) {
+            self.at += 1
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> Option<()> {
+        self.skip_whitespace();
+
+        if self.chars.get(self.at) == Some(&expected) {
+            self.at += 1;
+
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn word(&mut self, word: &str, value: Json) -> Option<Json> {
+        for c in word.chars() {
+            if self.chars.get(self.at) != Some(&c) {
+                return None;
+            }
+
+            self.at += 1
+        }
+
+        Some(value)
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+
+        match *self.chars.get(self.at)? {
+            'n' => self.word("null", Json::Null),
+            't' => self.word("true", Json::Bool(true)),
+            'f' => self.word("false", Json::Bool(false)),
+            '"' => self.string().map(Json::Str),
+
+            '[' => {
+                self.at += 1;
+
+                let mut elements = Vec::new();
+
+                if self.eat(']').is_some() {
+                    return Some(Json::Array(elements));
+                }
+
+                loop {
+                    elements.push(self.value()?);
+
+                    if self.eat(']').is_some() {
+                        return Some(Json::Array(elements));
+                    }
+
+                    self.eat(',')?
+                }
+            }
+
+            '{' => {
+                self.at += 1;
+
+                let mut members = Vec::new();
+
+                if self.eat('}').is_some() {
+                    return Some(Json::Object(members));
+                }
+
+                loop {
+                    self.skip_whitespace();
+
+                    let key = self.string()?;
+
+                    self.eat(':')?;
+                    members.push((key, self.value()?));
+
+                    if self.eat('}').is_some() {
+                        return Some(Json::Object(members));
+                    }
+
+                    self.eat(',')?
+                }
+            }
+
+            _ => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let start = self.at;
+
+        while self
+            .chars
+            .get(self.at)
+            .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+        {
+            self.at += 1
+        }
+
+        let number = self.chars[start..self.at].iter().collect::<String>();
+
+        number.parse().ok().map(Json::Number)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.chars.get(self.at) != Some(&'"') {
+            return None;
+        }
+
+        self.at += 1;
+
+        let mut text = String::new();
+
+        loop {
+            let c = *self.chars.get(self.at)?;
+            self.at += 1;
+
+            match c {
+                '"' => return Some(text),
+
+                '\\' => {
+                    let escaped = *self.chars.get(self.at)?;
+                    self.at += 1;
+
+                    match escaped {
+                        'n' => text.push('\n'),
+                        't' => text.push('\t'),
+                        'r' => text.push('\r'),
+                        'b' => text.push('\u{8}'),
+                        'f' => text.push('\u{c}'),
+                        'u' => text.push(self.unicode()?),
+                        c => text.push(c),
+                    }
+                }
+
+                c => text.push(c),
+            }
+        }
+    }
+
+    // the character of a `\u` escape, which takes two of them past the basic plane
+    fn unicode(&mut self) -> Option<char> {
+        let high = self.hex()?;
+
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high);
+        }
+
+        if self.chars.get(self.at..self.at + 2)? != ['\\', 'u'] {
+            return None;
+        }
+
+        self.at += 2;
+
+        let low = self.hex()?;
+
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low.checked_sub(0xdc00)?))
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let digits = self
+            .chars
+            .get(self.at..self.at + 4)?
+            .iter()
+            .collect::<String>();
+        self.at += 4;
+
+        u32::from_str_radix(&digits, 16).ok()
+    }
+}
@@ -0,0 +1,763 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+use super::super::error::{collected, silently, take_stdout, ColorChoice, Diagnostic, Severity};
+use super::super::format::format_range;
+use super::*;
+
+// `wu lsp`, a language server speaking LSP over stdin and stdout. Open documents are checked
+// whole as the editor has them, on every change, and so is what they import. Inside a project,
+// i.e. under a `wu.toml`, every other file of it is checked after, whenever there's no message
+// waiting. Stops on `exit`, failing unless asked to `shutdown` first
+pub fn serve() -> Result<(), ()> {
+    // anything else written to stdout would garble the messages
+    take_stdout();
+    ColorChoice::Never.apply();
+
+    let mut server = Server::default();
+
+    // checking stays on this thread, reading goes on while it does
+    let (sender, messages) = mpsc::channel();
+
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+
+        while let Some(message) = read(&mut input) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        let message = match messages.try_recv() {
+            Ok(message) => message,
+
+            Err(TryRecvError::Empty) => {
+                if let Some(file) = server.pending.pop_front() {
+                    server.publish(&server.uri_of(&file));
+
+                    continue;
+                }
+
+                match messages.recv() {
+                    Ok(message) => message,
+                    Err(_) => break,
+                }
+            }
+
+            Err(TryRecvError::Disconnected) => break,
+        };
+
+        if server.handle(&message) {
+            break;
+        }
+    }
+
+    if server.shutdown {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<String, String>, // the content of every open document, by uri
+    pending: VecDeque<PathBuf>,         // files of a project still to be checked again
+    shutdown: bool,
+}
+
+impl Server {
+    // answers a request, or takes note of a notification; true on `exit`
+    fn handle(&mut self, message: &Json) -> bool {
+        let method = message
+            .get("method")
+            .and_then(Json::as_str)
+            .unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            None => return self.notified(method, &params),
+        };
+
+        let outcome = match self.request(method, &params) {
+            Ok(result) => ("result", result),
+            Err((code, why)) => (
+                "error",
+                Json::object(vec![
+                    ("code", Json::Number(code as f64)),
+                    ("message", Json::string(why)),
+                ]),
+            ),
+        };
+
+        send(&Json::object(vec![
+            ("jsonrpc", Json::string("2.0")),
+            ("id", id),
+            outcome,
+        ]));
+
+        false
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (i64, String)> {
+        match method {
+            "initialize" => Ok(capabilities()),
+
+            "shutdown" => {
+                self.shutdown = true;
+                self.pending.clear();
+
+                Ok(Json::Null)
+            }
+
+            "textDocument/hover" => Ok(self.hover(params).unwrap_or(Json::Null)),
+            "textDocument/definition" => Ok(self.definition(params).unwrap_or(Json::Null)),
+            "textDocument/completion" => Ok(self.completion(params).unwrap_or(Json::Null)),
+            "textDocument/signatureHelp" => Ok(self.signature_help(params).unwrap_or(Json::Null)),
+            "textDocument/rangeFormatting" => {
+                Ok(self.range_formatting(params).unwrap_or(Json::Null))
+            }
+            "textDocument/codeAction" => Ok(self.code_actions(params).unwrap_or(Json::Null)),
+            "textDocument/rename" => self.rename(params),
+
+            _ => Err((-32601, format!("`{}` isn't supported", method))),
+        }
+    }
+
+    fn notified(&mut self, method: &str, params: &Json) -> bool {
+        let uri = params
+            .at(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let file = canonical(&path(&uri));
+
+        match method {
+            "textDocument/didOpen" => {
+                if let Some(text) = params.at(&["textDocument", "text"]).and_then(Json::as_str) {
+                    self.documents.insert(uri.clone(), text.to_string());
+                    edit(&file, Some(text.to_string()));
+
+                    self.publish(&uri);
+                    self.queue(&file)
+                }
+            }
+
+            // the whole document every time, see `capabilities`
+            "textDocument/didChange" => {
+                let text = match params.get("contentChanges") {
+                    Some(Json::Array(ref changes)) => {
+                        changes.last().and_then(|change| change.get("text"))
+                    }
+                    _ => None,
+                };
+
+                if let Some(text) = text.and_then(Json::as_str) {
+                    self.documents.insert(uri.clone(), text.to_string());
+                    edit(&file, Some(text.to_string()));
+
+                    self.publish(&uri);
+                    self.queue(&file)
+                }
+            }
+
+            // back to what's on disk
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                edit(&file, None);
+
+                self.publish(&uri);
+                self.queue(&file)
+            }
+
+            "exit" => return true,
+
+            _ => (),
+        }
+
+        false
+    }
+
+    // the diagnostics of checking the document, none once it's closed unless it's part of a
+    // project, which it's checked from disk as then
+    fn publish(&self, uri: &str) {
+        let file = path(uri);
+
+        let content = match self.documents.get(uri) {
+            Some(content) => Some(content.clone()),
+            None if project_of(&canonical(&file)).is_some() => fs::read_to_string(&file).ok(),
+            None => None,
+        };
+
+        let diagnostics = match content {
+            Some(content) => check(&file.display().to_string(), &content).0,
+            None => Vec::new(),
+        };
+
+        send(&Json::object(vec![
+            ("jsonrpc", Json::string("2.0")),
+            ("method", Json::string("textDocument/publishDiagnostics")),
+            (
+                "params",
+                Json::object(vec![
+                    ("uri", Json::string(uri)),
+                    ("diagnostics", Json::Array(diagnostics)),
+                ]),
+            ),
+        ]))
+    }
+
+    // every other file of the project `file` is in, to be checked once there's time, as what
+    // they import may have just changed
+    fn queue(&mut self, file: &Path) {
+        let root = match project_of(file) {
+            Some(root) => root,
+            None => return,
+        };
+
+        let mut files = Vec::new();
+        sources(&root.display().to_string(), &mut files);
+
+        for other in files.iter().map(Path::new) {
+            // leaving out hidden folders such as `.git`
+            let hidden = other
+                .strip_prefix(&root)
+                .unwrap_or(other)
+                .components()
+                .any(|part| part.as_os_str().to_string_lossy().starts_with('.'));
+
+            if hidden {
+                continue;
+            }
+
+            let other = canonical(other);
+
+            if other != file && !self.pending.contains(&other) {
+                self.pending.push_back(other)
+            }
+        }
+    }
+
+    // the uri the editor knows `file` by, if it has it open
+    fn uri_of(&self, file: &Path) -> String {
+        self.documents
+            .keys()
+            .find(|uri| canonical(&path(uri)) == file)
+            .cloned()
+            .unwrap_or_else(|| uri(file))
+    }
+
+    // checks the document a request is about, then asks `ask` of the visitor, given the
+    // position of the request as `Pos` counts lines and columns
+    fn visited<T>(
+        &self,
+        params: &Json,
+        prepare: impl FnOnce(&mut Visitor),
+        ask: impl FnOnce(&mut Visitor, &[Token], usize, usize) -> Option<T>,
+    ) -> Option<T> {
+        let uri = params.at(&["textDocument", "uri"]).and_then(Json::as_str)?;
+        let content = self.documents.get(uri)?;
+
+        let (line, column) = position(params.get("position")?, content)?;
+
+        let file = path(uri).display().to_string();
+        let source = Source::from(&file, content.lines().map(|x| x.into()).collect());
+
+        let root = Path::new(&file)
+            .parent()
+            .map_or(".".to_string(), |root| root.display().to_string());
+
+        let asked = silently(|| {
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                let tokens = Lexer::default(content.chars().collect(), &source)
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()?;
+
+                let ast = Parser::new(tokens.clone(), &source).parse().ok()?;
+
+                let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root);
+                prepare(&mut visitor);
+
+                // what's asked about may well be wrong while it's being written
+                let _ = visitor.visit();
+
+                ask(&mut visitor, &tokens, line, column)
+            }))
+        });
+
+        asked.ok().flatten()
+    }
+
+    fn hover(&self, params: &Json) -> Option<Json> {
+        let described = self.visited(
+            params,
+            |visitor| visitor.types = Some(HashMap::new()),
+            |visitor, _, line, column| {
+                let t = visitor.type_at(line, column)?;
+
+                Some(visitor.describe(&t))
+            },
+        )?;
+
+        Some(Json::object(vec![(
+            "contents",
+            Json::object(vec![
+                ("kind", Json::string("plaintext")),
+                ("value", Json::string(described)),
+            ]),
+        )]))
+    }
+
+    fn definition(&self, params: &Json) -> Option<Json> {
+        let uri = params.at(&["textDocument", "uri"]).and_then(Json::as_str)?;
+        let content = self.documents.get(uri)?;
+
+        let (line, column) = position(params.get("position")?, content)?;
+
+        let file = path(uri);
+        let unit = silently(|| Unit::parse(&file, content)).ok()?;
+
+        let (defined, span) = references::definition(&file, unit, line, column)?;
+
+        let uri = self::uri(&defined);
+        let text = match self.documents.get(&uri) {
+            Some(content) => content.clone(),
+            None => fs::read_to_string(&defined).unwrap_or_default(),
+        };
+        let text = text.lines().nth(span.0.saturating_sub(1)).unwrap_or_default();
+
+        Some(Json::object(vec![
+            ("uri", Json::string(uri)),
+            ("range", range(span.0, span.1.saturating_sub(1), span.2, text)),
+        ]))
+    }
+
+    // the members a `new` still needs
+    fn completion(&self, params: &Json) -> Option<Json> {
+        let unassigned = self.visited(
+            params,
+            |visitor| visitor.initializations = Some(Vec::new()),
+            |visitor, tokens, line, column| visitor.completions_at(tokens, line, column),
+        )?;
+
+        let items = unassigned
+            .iter()
+            .map(|(name, t)| {
+                Json::object(vec![
+                    ("label", Json::string(name)),
+                    ("kind", Json::Number(5.0)), // a field
+                    ("detail", Json::string(t)),
+                    ("insertText", Json::string(format!("{}: ", name))),
+                ])
+            })
+            .collect();
+
+        Some(Json::Array(items))
+    }
+
+    // the top-level statements the range touches, laid out the way `wu fmt` does
+    fn range_formatting(&self, params: &Json) -> Option<Json> {
+        let uri = params.at(&["textDocument", "uri"]).and_then(Json::as_str)?;
+        let content = self.documents.get(uri)?;
+
+        let offset = |at: &[&str]| {
+            let (line, column) = position(params.at(at)?, content)?;
+
+            let lines = content.split_inclusive('\n').take(line - 1);
+            let before = lines.map(str::len).sum::<usize>();
+
+            let rest = content[before..].chars().take(column - 1);
+
+            Some(before + rest.map(char::len_utf8).sum::<usize>())
+        };
+
+        let (first, until, text) =
+            format_range(content, offset(&["range", "start"])?, offset(&["range", "end"])?)?;
+
+        let lines = content.split_inclusive('\n').skip(first).take(until - first);
+
+        if lines.collect::<String>() == text {
+            return Some(Json::Array(Vec::new()));
+        }
+
+        // from the first line through to the start of the one after the last
+        let at = |line: usize| {
+            Json::object(vec![
+                ("line", Json::Number(line as f64)),
+                ("character", Json::Number(0.0)),
+            ])
+        };
+
+        let edit = Json::object(vec![
+            ("range", Json::object(vec![("start", at(first)), ("end", at(until))])),
+            ("newText", Json::Str(text)),
+        ]);
+
+        Some(Json::Array(vec![edit]))
+    }
+
+    // the fixes suggested for the problems on the lines of the range, as quick fixes
+    fn code_actions(&self, params: &Json) -> Option<Json> {
+        let uri = params.at(&["textDocument", "uri"]).and_then(Json::as_str)?;
+        let content = self.documents.get(uri)?;
+
+        let (first, _) = position(params.at(&["range", "start"])?, content)?;
+        let (last, _) = position(params.at(&["range", "end"])?, content)?;
+
+        let file = path(uri).display().to_string();
+        let (_, fixes) = check(&file, content);
+
+        let actions = fixes
+            .iter()
+            .filter(|fix| fix.file == file && (first..=last).contains(&fix.line))
+            .map(|fix| {
+                let changes = Json::object(vec![(uri, text_edits(&fix.edits, content))]);
+
+                Json::object(vec![
+                    ("title", Json::string(&fix.title)),
+                    ("kind", Json::string("quickfix")),
+                    ("edit", Json::object(vec![("changes", changes)])),
+                ])
+            })
+            .collect();
+
+        Some(Json::Array(actions))
+    }
+
+    // the edits renaming what's at the position across the project, see `wu rename`, or why it
+    // can't be renamed
+    fn rename(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let invalid = || (-32602, "expected a document, a position and a `newName`".to_string());
+
+        let uri = params.at(&["textDocument", "uri"]).and_then(Json::as_str);
+        let content = uri.and_then(|uri| self.documents.get(uri)).ok_or_else(invalid)?;
+
+        let at = params.get("position").ok_or_else(invalid)?;
+        let (line, column) = position(at, content).ok_or_else(invalid)?;
+
+        let new = params.get("newName").and_then(Json::as_str).ok_or_else(invalid)?;
+
+        let file = path(uri.unwrap_or_default());
+        let (renamed, found) = collected(|| references::rename(&file, line, column, new));
+
+        let edits = renamed.map_err(|_| {
+            let why = found.into_iter().next().map(|found| found.message);
+
+            (-32803, why.unwrap_or_else(|| "can't rename this".to_string()))
+        })?;
+
+        let mut changes = edits
+            .iter()
+            .map(|(file, edits)| {
+                let content = read_module(file).unwrap_or_default();
+
+                (self.uri_of(file), text_edits(edits, &content))
+            })
+            .collect::<Vec<_>>();
+
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(Json::object(vec![("changes", Json::Object(changes))]))
+    }
+
+    fn signature_help(&self, params: &Json) -> Option<Json> {
+        let (called, before) = self.visited(
+            params,
+            |visitor| visitor.calls = Some(Vec::new()),
+            |visitor, tokens, line, column| visitor.signature_at(tokens, line, column),
+        )?;
+
+        let returned = match called.node {
+            TypeNode::Func(_, ref returned, ..) => returned.clone(),
+            _ => return None,
+        };
+
+        let params = parameters(&called);
+
+        let labels = params
+            .iter()
+            .map(|(name, t)| match name {
+                Some(name) => format!("{}: {}", name, t),
+                None => format!("{}", t),
+            })
+            .collect::<Vec<_>>();
+
+        let mut signature = vec![
+            (
+                "label",
+                Json::string(format!("fun({}) -> {}", labels.join(", "), returned)),
+            ),
+            (
+                "parameters",
+                Json::Array(
+                    labels
+                        .into_iter()
+                        .map(|label| Json::object(vec![("label", Json::Str(label))]))
+                        .collect(),
+                ),
+            ),
+        ];
+
+        if let Some(active) = active_parameter(&params, before) {
+            signature.push(("activeParameter", Json::Number(active as f64)))
+        }
+
+        Some(Json::object(vec![(
+            "signatures",
+            Json::Array(vec![Json::object(signature)]),
+        )]))
+    }
+}
+
+fn capabilities() -> Json {
+    let triggers = Json::Array(vec![Json::string("("), Json::string(",")]);
+
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("textDocumentSync", Json::Number(1.0)), // whole documents
+                ("hoverProvider", Json::Bool(true)),
+                ("definitionProvider", Json::Bool(true)),
+                ("completionProvider", Json::object(Vec::new())),
+                (
+                    "signatureHelpProvider",
+                    Json::object(vec![("triggerCharacters", triggers)]),
+                ),
+                ("documentRangeFormattingProvider", Json::Bool(true)),
+                ("codeActionProvider", Json::Bool(true)),
+                ("renameProvider", Json::Bool(true)),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![
+                ("name", Json::string("wu")),
+                ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+            ]),
+        ),
+    ])
+}
+
+// the diagnostics of checking `content` as the file `file`, and the fixes suggested for them
+fn check(file: &str, content: &str) -> (Vec<Json>, Vec<Fix>) {
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let root = Path::new(file)
+        .parent()
+        .map_or(".".to_string(), |root| root.display().to_string());
+
+    // left over from before, as nothing else takes them here
+    fix::take();
+
+    let (_, found) = collected(|| {
+        let checked = panic::catch_unwind(AssertUnwindSafe(|| {
+            let tokens = Lexer::default(content.chars().collect(), &source)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let ast = Parser::new(tokens, &source).parse()?;
+
+            Visitor::from_symtab(&ast, &source, SymTab::prelude(), root).visit()
+        }));
+
+        if checked.is_err() {
+            ice::report()
+        }
+    });
+
+    (diagnostics(found, file), fix::take())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// the diagnostics in `found` that are about `file`, or about no file in particular
+fn diagnostics(found: Vec<Diagnostic>, file: &str) -> Vec<Json> {
+    found
+        .into_iter()
+        .filter(|found| found.file.as_ref().is_none_or(|found| found == file))
+        .map(|found| {
+            let range = match found.pos {
+                Some(Pos((line, ref text), (start, end))) => {
+                    range(line, start.saturating_sub(1), end, text)
+                }
+                None => range(1, 0, 0, ""),
+            };
+
+            let severity = match found.severity {
+                Severity::Wrong => 1,
+                Severity::Weird => 2,
+                Severity::Note => 3,
+            };
+
+            let mut message = found.message;
+
+            for note in found.notes {
+                message.push('\n');
+                message.push_str(&note)
+            }
+
+            Json::object(vec![
+                ("range", range),
+                ("severity", Json::Number(severity as f64)),
+                ("source", Json::string("wu")),
+                ("message", Json::Str(message)),
+            ])
+        })
+        .collect()
+}
+
+// `edits` of `content` as LSP's text edits
+fn text_edits(edits: &[references::Edit], content: &str) -> Json {
+    let lines = content.lines().collect::<Vec<_>>();
+
+    let edits = edits
+        .iter()
+        .map(|edit| {
+            let (line, start, end) = edit.span;
+            let text = lines.get(line.saturating_sub(1)).copied().unwrap_or_default();
+
+            Json::object(vec![
+                ("range", range(line, start.saturating_sub(1), end, text)),
+                ("newText", Json::string(&edit.text)),
+            ])
+        })
+        .collect();
+
+    Json::Array(edits)
+}
+
+// from the 1-based `line` and 0-based columns in chars of `text`, the line, to LSP's 0-based
+// lines and characters, which count UTF-16 code units
+fn range(line: usize, start: usize, end: usize, text: &str) -> Json {
+    let position = |column: usize| {
+        // spans can point past the end of their line
+        let past = column.saturating_sub(text.chars().count());
+        let character = text.chars().take(column).map(char::len_utf16).sum::<usize>() + past;
+
+        Json::object(vec![
+            ("line", Json::Number(line.saturating_sub(1) as f64)),
+            ("character", Json::Number(character as f64)),
+        ])
+    };
+
+    Json::object(vec![("start", position(start)), ("end", position(end))])
+}
+
+// the `position` of a request as `Pos` counts, the line and column both from 1, in `content`
+fn position(at: &Json, content: &str) -> Option<(usize, usize)> {
+    let line = at.get("line")?.as_usize()?;
+    let character = at.get("character")?.as_usize()?;
+
+    // back from UTF-16 code units to chars
+    let mut units = 0;
+    let column = content
+        .lines()
+        .nth(line)
+        .unwrap_or_default()
+        .chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= character
+        })
+        .count();
+
+    Some((line + 1, column + 1))
+}
+
+// the file a `file://` uri names
+fn path(uri: &str) -> PathBuf {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+
+    let mut decoded = Vec::new();
+    let mut bytes = path.bytes();
+
+    while let Some(byte) = bytes.next() {
+        let escaped = match byte {
+            b'%' => {
+                let digits = [bytes.next().unwrap_or(b'0'), bytes.next().unwrap_or(b'0')];
+
+                std::str::from_utf8(&digits)
+                    .ok()
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            }
+            _ => None,
+        };
+
+        decoded.push(escaped.unwrap_or(byte))
+    }
+
+    PathBuf::from(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+fn uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+
+    for byte in path.display().to_string().bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    uri
+}
+
+// the next message, after its `Content-Length` header; none once the input ends
+fn read(input: &mut impl BufRead) -> Option<Json> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok()
+            }
+        }
+    }
+
+    let mut content = vec![0; length?];
+    input.read_exact(&mut content).ok()?;
+
+    // a message that isn't JSON is skipped over, the next one may be fine
+    Some(Json::parse(&String::from_utf8_lossy(&content)).unwrap_or(Json::Null))
+}
+
+fn send(message: &Json) {
+    let content = message.to_string();
+
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+
+    let _ = write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    );
+    let _ = output.flush();
+}
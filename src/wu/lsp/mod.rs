@@ -0,0 +1,13 @@
+pub mod json;
+pub mod lsp;
+
+use super::fix::{self, Fix};
+use super::ice;
+use super::lexer::*;
+use super::parser::*;
+use super::references::{self, Unit};
+use super::source::*;
+use super::visitor::*;
+
+pub use self::json::*;
+pub use self::lsp::*;
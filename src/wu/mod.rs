@@ -1,8 +1,34 @@
-#[macro_use]
-pub mod error;
+pub use wu_syntax::{budget, codemod, error, format, lexer, parser, source};
+
+pub mod api;
+pub mod baseline;
+pub mod build;
+pub mod codemods;
 pub mod compiler;
+pub mod coverage;
+pub mod daemon;
+pub mod edition;
+#[cfg(feature = "mlua")]
+pub mod embed;
+pub mod fix;
+pub mod fmt;
+pub mod graph;
 pub mod handler;
-pub mod lexer;
-pub mod parser;
-pub mod source;
+pub mod highlight;
+pub mod hot;
+pub mod ice;
+pub mod lint;
+pub mod love;
+pub mod lsp;
+pub mod playground;
+pub mod profile;
+pub mod query;
+pub mod references;
+pub mod repl;
+pub mod run;
+pub mod stdlib;
+pub mod tags;
+pub mod test;
+pub mod triage;
 pub mod visitor;
+pub mod wumap;
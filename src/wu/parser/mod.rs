@@ -1,9 +0,0 @@
-pub mod ast;
-pub mod parser;
-
-use super::lexer::*;
-use super::source::*;
-use super::visitor::*;
-
-pub use self::ast::*;
-pub use self::parser::*;
@@ -0,0 +1,10 @@
+pub mod playground;
+
+use super::compiler::Generator;
+use super::error::captured;
+use super::lexer::Lexer;
+use super::parser::{Parser, STACK};
+use super::source::Source;
+use super::visitor::*;
+
+pub use self::playground::*;
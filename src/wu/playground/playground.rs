@@ -0,0 +1,87 @@
+use std::fmt;
+use std::panic;
+use std::path::Path;
+use std::thread;
+
+use super::*;
+
+// why a playground didn't get Lua back for what it sent
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejected {
+    Diagnostics(String), // the program is wrong, as the diagnostics say
+    TooLarge(Exceeded),  // compiling it went over one of the `Limits`
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rejected::Diagnostics(ref diagnostics) => write!(f, "{}", diagnostics),
+            Rejected::TooLarge(ref exceeded) => write!(f, "{}", exceeded),
+        }
+    }
+}
+
+// `content` as Lua like `compile_str`, for services compiling whatever anyone sends them, e.g.
+// a web playground: going over `limits` gives up on it rather than tying the service up. It's
+// compiled on a thread of its own, with the stack `max_depth` needs whatever the caller's is
+pub fn compile_limited(name: &str, content: &str, limits: &Limits) -> Result<String, Rejected> {
+    let compile = || compile_within(name, content, *limits);
+
+    let compiled = thread::scope(|scope| {
+        match thread::Builder::new().stack_size(STACK).spawn_scoped(scope, compile) {
+            Ok(compiler) => Some(compiler.join().unwrap_or_else(|ice| panic::resume_unwind(ice))),
+            Err(_) => None,
+        }
+    });
+
+    let (lua, diagnostics, exceeded) = compiled.unwrap_or_else(compile);
+
+    match exceeded {
+        Some(exceeded) => Err(Rejected::TooLarge(exceeded)),
+        None => lua.map_err(|_| Rejected::Diagnostics(diagnostics)),
+    }
+}
+
+// compiles for `compile_limited`, giving the Lua, the diagnostics and the limit gone over if any
+fn compile_within(
+    name: &str,
+    content: &str,
+    limits: Limits,
+) -> (Result<String, ()>, String, Option<Exceeded>) {
+    let source = Source::from(name, content.lines().map(|x| x.into()).collect());
+
+    let root = match Path::new(name).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+        _ => ".".to_string(),
+    };
+
+    let budget = Budget::new(limits);
+
+    let (lua, diagnostics) = captured(|| {
+        // checked before lexing, as everything after takes time and memory in proportion
+        budget.spend(Resource::Bytes, content.len()).map_err(|_| ())?;
+
+        let mut tokens = Vec::new();
+
+        for token in Lexer::default(content.chars().collect(), &source) {
+            tokens.push(token?)
+        }
+
+        budget.spend(Resource::Time, 0).map_err(|_| ())?;
+
+        let mut parser = Parser::new(tokens, &source);
+        parser.budget = budget.clone();
+
+        let ast = parser.parse()?;
+
+        let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root);
+        visitor.budget = budget.clone();
+        visitor.visit()?;
+
+        let lua = Generator::new(&source, &visitor).generate(&ast);
+
+        Ok::<_, ()>(lua)
+    });
+
+    (lua, diagnostics, budget.exceeded())
+}
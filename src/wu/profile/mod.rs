@@ -0,0 +1,10 @@
+pub mod profile;
+
+use super::build::*;
+use super::error::Response;
+use super::lexer::*;
+use super::parser::*;
+use super::source::*;
+use super::wumap::{self, LineMap};
+
+pub use self::profile::*;
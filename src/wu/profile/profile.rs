@@ -0,0 +1,421 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+
+use super::*;
+
+// where the program leaves its samples, one `<count>\t<frame>\t<frame>...` a line, innermost
+// frame first, each `<lua file>:<line defined>:<current line>`
+pub const STATS: &str = ".wu-profile";
+
+// the functions by the time spent in them, and the Wu listings with samples in the margin
+pub const REPORT: &str = "wu.profile.out";
+
+// the share of samples, in percent, a function takes itself to be marked `@hot`
+pub const HOT: f64 = 10.0;
+
+// runs `main` with `lua -e`, sampling the stack every thousand instructions, and writes the
+// samples to `STATS` once it ends, failed or not; only frames of lua files are kept
+pub fn driver(main: &str) -> String {
+    format!(
+        r#"
+local counts, getinfo = {{}}, debug.getinfo
+
+debug.sethook(function()
+  local frames, level = {{}}, 2
+
+  while true do
+    local info = getinfo(level, "Sl")
+
+    if not info then
+      break
+    end
+
+    if info.source:sub(1, 1) == "@" and info.currentline > 0 then
+      frames[#frames + 1] = info.source:sub(2) .. ":" .. info.linedefined .. ":" .. info.currentline
+    end
+
+    level = level + 1
+  end
+
+  if #frames > 0 then
+    local stack = table.concat(frames, "\t")
+    counts[stack] = (counts[stack] or 0) + 1
+  end
+end, "", 1000)
+
+local ok, why = xpcall(dofile, debug.traceback, {:?})
+
+debug.sethook()
+
+local stats = io.open("{}", "w")
+
+for stack, count in pairs(counts) do
+  stats:write(count, "\t", stack, "\n")
+end
+
+stats:close()
+
+if not ok then
+  io.stderr:write(tostring(why), "\n")
+  os.exit(1)
+end
+"#,
+        main, STATS
+    )
+}
+
+// one function of the stack, in the lua it was compiled to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub lua: String,
+    pub defined: usize,
+    pub line: usize,
+}
+
+// the stacks sampled and how often each was, from `STATS`
+pub fn read_stats(stats: &str) -> Vec<(usize, Vec<Frame>)> {
+    let mut stacks = Vec::new();
+
+    for line in stats.lines() {
+        let mut parts = line.split('\t');
+
+        let count = match parts.next().map(str::parse) {
+            Some(Ok(count)) => count,
+            _ => continue,
+        };
+
+        let frames = parts
+            .filter_map(|frame| {
+                let mut fields = frame.rsplitn(3, ':');
+
+                let line = fields.next()?.parse().ok()?;
+                let defined = fields.next()?.parse().ok()?;
+                let lua = fields.next()?.trim_start_matches("./").to_string();
+
+                Some(Frame { lua, defined, line })
+            })
+            .collect();
+
+        stacks.push((count, frames))
+    }
+
+    stacks
+}
+
+// the samples of one Wu function
+#[derive(Debug, Clone, Default)]
+pub struct Function {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    pub own: usize,   // samples taken in the function itself
+    pub total: usize, // samples taken in it or anything it called
+}
+
+// what a run spent its time on, in Wu terms
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub samples: usize,
+    pub functions: Vec<Function>, // the most time taken first
+    pub lines: BTreeMap<String, BTreeMap<usize, usize>>, // samples per Wu line of every file
+}
+
+impl Profile {
+    pub fn percent(&self, samples: usize) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            samples as f64 * 100.0 / self.samples as f64
+        }
+    }
+
+    pub fn is_hot(&self, function: &Function) -> bool {
+        self.percent(function.own) >= HOT
+    }
+
+    // a line a function, e.g. `  42.0%   60.0%  main.wu:3 fib @hot`
+    pub fn table(&self) -> String {
+        let mut result = String::from("   self    total  function\n");
+
+        for function in self.functions.iter() {
+            let place = match function.line {
+                0 => function.file.clone(),
+                line => format!("{}:{}", function.file, line),
+            };
+
+            result.push_str(&format!(
+                "{:>6.1}%  {:>6.1}%  {} {}{}\n",
+                self.percent(function.own),
+                self.percent(function.total),
+                place,
+                function.name,
+                if self.is_hot(function) { " @hot" } else { "" }
+            ))
+        }
+
+        result
+    }
+
+    // the table, then the source of every sampled file with the samples of each line in the
+    // margin and its hot functions marked `@hot` above them
+    pub fn report(&self) -> String {
+        let mut result = format!("{} samples\n\n{}\n", self.samples, self.table());
+
+        for (file, lines) in self.lines.iter() {
+            let source = fs::read_to_string(file).unwrap_or_default();
+
+            result.push_str(&format!(
+                "{}\n{}\n{}\n",
+                "=".repeat(78),
+                file,
+                "=".repeat(78)
+            ));
+
+            for (i, line) in source.lines().enumerate() {
+                let hot = self.functions.iter().any(|function| {
+                    &function.file == file && function.line == i + 1 && self.is_hot(function)
+                });
+
+                if hot {
+                    let indent = line.len() - line.trim_start().len();
+
+                    result.push_str(&format!("{:>7} {}@hot\n", "", &line[..indent]))
+                }
+
+                let margin = lines
+                    .get(&(i + 1))
+                    .map(usize::to_string)
+                    .unwrap_or_default();
+
+                result.push_str(&format!("{:>7} {}\n", margin, line))
+            }
+
+            result.push('\n')
+        }
+
+        result
+    }
+}
+
+// carries the sampled stacks over to the Wu functions and lines they were compiled from, for the
+// lua files that have a `.wumap`; a function is known by the Wu line it's defined on, code
+// outside of any by the module's name
+pub fn map_samples(stacks: &[(usize, Vec<Frame>)]) -> Profile {
+    let mut maps: HashMap<String, Option<LineMap>> = HashMap::new();
+    let mut names: HashMap<String, HashMap<usize, String>> = HashMap::new();
+
+    let mut functions: BTreeMap<(String, usize), Function> = BTreeMap::new();
+    let mut profile = Profile::default();
+
+    for (count, frames) in stacks.iter() {
+        let mut seen = Vec::new();
+
+        for (depth, frame) in frames.iter().enumerate() {
+            let map = maps
+                .entry(frame.lua.clone())
+                .or_insert_with(|| LineMap::load(&wumap::map_path(Path::new(&frame.lua))));
+
+            let map = match map {
+                Some(map) => map,
+                None => continue,
+            };
+
+            let line = map.wu_line(frame.defined).unwrap_or(0);
+
+            let name = names
+                .entry(map.source.clone())
+                .or_insert_with(|| definitions(&map.source))
+                .get(&line)
+                .cloned()
+                .unwrap_or_else(|| match line {
+                    0 => "(module)".to_string(),
+                    _ => "(function)".to_string(),
+                });
+
+            let key = (map.source.clone(), line);
+
+            let function = functions.entry(key.clone()).or_insert_with(|| Function {
+                name,
+                file: map.source.clone(),
+                line,
+                ..Function::default()
+            });
+
+            // a recursive function counts once a sample towards its total
+            if !seen.contains(&key) {
+                function.total += count;
+                seen.push(key)
+            }
+
+            if depth == 0 {
+                function.own += count;
+
+                if let Some(wu_line) = map.wu_line(frame.line) {
+                    *profile
+                        .lines
+                        .entry(map.source.clone())
+                        .or_default()
+                        .entry(wu_line)
+                        .or_insert(0) += count
+                }
+            }
+        }
+
+        profile.samples += count
+    }
+
+    profile.functions = functions.into_values().collect();
+    profile
+        .functions
+        .sort_by_key(|function| Reverse((function.own, function.total)));
+
+    profile
+}
+
+// the functions of a Wu file by the line they're defined on, methods as `Struct:method`
+fn definitions(file: &str) -> HashMap<usize, String> {
+    let mut names = HashMap::new();
+
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(_) => return names,
+    };
+
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let tokens = Lexer::default(content.chars().collect(), &source).collect::<Result<Vec<_>, _>>();
+
+    if let Ok(ast) = tokens.and_then(|tokens| Parser::new(tokens, &source).parse()) {
+        definitions_in(&ast, None, &mut names)
+    }
+
+    names
+}
+
+fn definitions_in(ast: &[Statement], scope: Option<&str>, names: &mut HashMap<usize, String>) {
+    let named = |name: &str| match scope {
+        Some(scope) => format!("{}:{}", scope, name),
+        None => name.to_string(),
+    };
+
+    for statement in ast.iter() {
+        match statement.binding().node {
+            StatementNode::Variable(_, ref name, Some(ref right), _) => match right.node {
+                ExpressionNode::Function(..) => {
+                    names.insert((statement.pos.0).0, named(name));
+                }
+
+                ExpressionNode::Trait(_, _, ref defaults) => {
+                    for (method, function) in defaults.iter() {
+                        names.insert((function.pos.0).0, format!("{}:{}", named(name), method));
+                    }
+                }
+
+                ExpressionNode::Module(ref body, _) => {
+                    if let ExpressionNode::Block(ref content) = body.node {
+                        definitions_in(content, Some(&named(name)), names)
+                    }
+                }
+
+                _ => (),
+            },
+
+            StatementNode::Implement(ref name, ref body, _) => {
+                let name = match name.node {
+                    ExpressionNode::Identifier(ref name) => name.clone(),
+                    ExpressionNode::Index(_, ref index, _) => match index.node {
+                        ExpressionNode::Identifier(ref name) => name.clone(),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+
+                if let ExpressionNode::Block(ref content) = body.node {
+                    definitions_in(content, Some(&named(&name)), names)
+                }
+            }
+
+            _ => (),
+        }
+    }
+}
+
+// `wu profile`, running the file under a sampling hook and mapping the samples back to Wu
+pub fn profile_path(path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    if !path.ends_with(".wu") {
+        response!(Response::Wrong("expected a `.wu` file after `profile`"));
+
+        return build.fail(Failure::Usage);
+    }
+
+    let folder = match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+        _ => ".".to_string(),
+    };
+
+    build.wumap = true;
+
+    compile_path(&folder, &folder, build);
+    build.finish();
+
+    if build.failure.is_some() {
+        return build.summary(now);
+    }
+
+    let lua = env::var("WU_LUA").unwrap_or_else(|_| "lua".to_string());
+
+    let _ = fs::remove_file(profile::STATS);
+
+    let mut command = process::Command::new(&lua);
+    build.resolve_modules(&mut command);
+
+    let main = output_path(path, build).display().to_string();
+
+    let status = match command.arg("-e").arg(profile::driver(&main)).status() {
+        Ok(status) => status,
+        Err(why) => {
+            response!(Response::Wrong(format!("failed to run `{}`: {}", lua, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    if !status.success() {
+        build.fail(Failure::Type)
+    }
+
+    let stats = match fs::read_to_string(profile::STATS) {
+        Ok(stats) => stats,
+        Err(why) => {
+            response!(Response::Wrong(format!("can't read `{}`: {}", profile::STATS, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let _ = fs::remove_file(profile::STATS);
+
+    let profile = profile::map_samples(&profile::read_stats(&stats));
+
+    print!("{}", profile.table());
+
+    if write(Path::new(profile::REPORT), &profile.report()).is_err() {
+        return build.fail(Failure::Io);
+    }
+
+    log!(
+        Normal,
+        "Profiled",
+        "{} samples, see `{}` ({}ms)",
+        profile.samples,
+        profile::REPORT,
+        now.elapsed().as_millis()
+    )
+}
@@ -0,0 +1,8 @@
+pub mod query;
+
+use super::build::*;
+use super::error::*;
+use super::source::Source;
+use super::visitor::*;
+
+pub use self::query::*;
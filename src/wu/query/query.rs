@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::*;
+
+pub fn type_at_path(location: &str, build: &mut Build) {
+    let (file, line, column) = match position(location) {
+        Some(position) => position,
+        None => {
+            response!(Response::Wrong("expected `wu type-at <file>:<line>:<col>`"));
+
+            return build.fail(Failure::Usage);
+        }
+    };
+
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(why) => {
+            response!(Response::Wrong(format!("can't read `{}`: {}", file, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let ast = match parse(&content, &source) {
+        Ok(ast) => ast,
+        Err(failure) => return build.fail(failure),
+    };
+
+    let root = Path::new(file).parent().map_or(".".to_string(), |root| root.display().to_string());
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root);
+    visitor.types = Some(HashMap::new());
+
+    // a failing check still knows the types up to the failure
+    let _ = silently(|| visitor.visit());
+
+    match visitor.type_at(line, column) {
+        Some(t) => println!("{}", visitor.describe(&t)),
+        None => {
+            response!(Response::Wrong(format!("nothing typed at `{}`", location)));
+
+            build.fail(Failure::Type)
+        }
+    }
+}
+
+// `wu complete`, the members still to assign in the `new` around a position, for editors
+pub fn complete_path(location: &str, build: &mut Build) {
+    let (file, line, column) = match position(location) {
+        Some(position) => position,
+        None => {
+            response!(Response::Wrong("expected `wu complete <file>:<line>:<col>`"));
+
+            return build.fail(Failure::Usage);
+        }
+    };
+
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(why) => {
+            response!(Response::Wrong(format!("can't read `{}`: {}", file, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let tokens = match lex(&content, &source) {
+        Ok(tokens) => tokens,
+        Err(failure) => return build.fail(failure),
+    };
+
+    let ast = match parse(&content, &source) {
+        Ok(ast) => ast,
+        Err(failure) => return build.fail(failure),
+    };
+
+    let root = Path::new(file).parent().map_or(".".to_string(), |root| root.display().to_string());
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root);
+    visitor.initializations = Some(Vec::new());
+
+    // members left out are what's being completed, so the check failing on them is expected
+    let _ = silently(|| visitor.visit());
+
+    match visitor.completions_at(&tokens, line, column) {
+        Some(unassigned) => {
+            for (name, t) in unassigned {
+                println!("{}: {}", name, t)
+            }
+        }
+
+        None => {
+            response!(Response::Wrong(format!("no `new` to complete at `{}`", location)));
+
+            build.fail(Failure::Type)
+        }
+    }
+}
+
+// `wu signature`, the parameters of the function called around a position, for editors
+pub fn signature_path(location: &str, build: &mut Build) {
+    let (file, line, column) = match position(location) {
+        Some(position) => position,
+        None => {
+            response!(Response::Wrong("expected `wu signature <file>:<line>:<col>`"));
+
+            return build.fail(Failure::Usage);
+        }
+    };
+
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(why) => {
+            response!(Response::Wrong(format!("can't read `{}`: {}", file, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let tokens = match lex(&content, &source) {
+        Ok(tokens) => tokens,
+        Err(failure) => return build.fail(failure),
+    };
+
+    let ast = match parse(&content, &source) {
+        Ok(ast) => ast,
+        Err(failure) => return build.fail(failure),
+    };
+
+    let root = Path::new(file).parent().map_or(".".to_string(), |root| root.display().to_string());
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root);
+    visitor.calls = Some(Vec::new());
+
+    // the arguments may well be wrong while they're being written
+    let _ = silently(|| visitor.visit());
+
+    match visitor.signature_at(&tokens, line, column) {
+        Some((called, before)) => print!("{}", signature(&called, before)),
+        None => {
+            response!(Response::Wrong(format!("no call around `{}`", location)));
+
+            build.fail(Failure::Type)
+        }
+    }
+}
+
+// `called` with a parameter per line below, the one `before` arguments in marked with `>`
+fn signature(called: &Type, before: usize) -> String {
+    let params = parameters(called);
+    let active = active_parameter(&params, before);
+
+    let mut rendered = format!("{}\n", called);
+
+    for (i, (name, param)) in params.iter().enumerate() {
+        let mark = if Some(i) == active { ">" } else { " " };
+
+        match name {
+            Some(name) => rendered.push_str(&format!("{} {}: {}\n", mark, name, param)),
+            None => rendered.push_str(&format!("{} {}\n", mark, param)),
+        }
+    }
+
+    rendered
+}
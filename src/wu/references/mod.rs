@@ -0,0 +1,13 @@
+pub mod rename;
+pub mod unused;
+pub mod usedef;
+
+use super::build::*;
+use super::lexer::*;
+use super::parser::*;
+use super::source::*;
+use super::visitor::*;
+
+pub use self::rename::*;
+pub use self::unused::*;
+pub use self::usedef::*;
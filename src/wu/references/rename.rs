@@ -0,0 +1,520 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::super::error::{silently, Response};
+use super::super::error::Response::*;
+use super::*;
+
+// a parsed file of the project, with its use-def table
+pub struct Unit {
+    pub source: Source,
+    pub table: UseDef,
+}
+
+impl Unit {
+    // the file at `path`, as edited if it's open in an editor, see `read_module`
+    pub fn load(path: &Path) -> Result<Self, ()> {
+        let content = match read_module(path) {
+            Ok(content) => content,
+            Err(why) => {
+                return Err(response!(Wrong(format!(
+                    "failed to read `{}`: {}",
+                    path.display(),
+                    why
+                ))))
+            }
+        };
+
+        Unit::parse(path, &content)
+    }
+
+    // `content` as the file at `path`, e.g. what an editor has open of it without saving
+    pub fn parse(path: &Path, content: &str) -> Result<Self, ()> {
+        let source = Source::from(
+            &path.display().to_string(),
+            content.lines().map(|x| x.into()).collect(),
+        );
+
+        let mut tokens = Vec::new();
+
+        for token_result in Lexer::default(content.chars().collect(), &source) {
+            tokens.push(token_result?)
+        }
+
+        let ast = Parser::new(tokens.clone(), &source).parse()?;
+        let table = UseDef::new(&ast, &tokens);
+
+        Ok(Unit { source, table })
+    }
+
+    pub fn pos(&self, span: Span) -> Pos {
+        let line = self
+            .source
+            .shared
+            .get(span.0.saturating_sub(1))
+            .cloned()
+            .unwrap_or_else(|| Arc::from(""));
+
+        Pos((span.0, line), (span.1, span.2))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub span: Span,
+    pub text: String,
+}
+
+pub fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// closest folder holding a `wu.toml`, else the working directory
+fn project_root(file: &Path) -> PathBuf {
+    for ancestor in file.ancestors().skip(1) {
+        if ancestor.join("wu.toml").is_file() {
+            return ancestor.to_path_buf();
+        }
+    }
+
+    canonical(&env::current_dir().unwrap_or_default())
+}
+
+// the file an import in `from` refers to, if it's part of the project
+pub fn resolve(from: &Path, path: &str, root: &Path) -> Option<PathBuf> {
+    // `root/a` is only looked for from the project root
+    let (path, bases) = match path.strip_prefix("root/") {
+        Some(rest) => (rest, vec![root.to_path_buf()]),
+        None => (
+            path,
+            vec![from.parent().unwrap_or(root).to_path_buf(), root.to_path_buf()],
+        ),
+    };
+
+    for base in bases.iter() {
+        for candidate in [format!("{}.wu", path), format!("{}/init.wu", path)].iter() {
+            let candidate = base.join(candidate);
+
+            if candidate.is_file() {
+                return Some(canonical(&candidate));
+            }
+        }
+    }
+
+    None
+}
+
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+        && !WORD_OPERATORS.contains(&name)
+        && name != "true"
+        && name != "false"
+}
+
+struct Renamer {
+    old: String,
+    new: String,
+    root: PathBuf,
+    units: HashMap<PathBuf, Unit>,
+    edits: HashMap<PathBuf, Vec<Edit>>,
+    renamed: HashSet<(PathBuf, usize)>,
+    exposed: Vec<PathBuf>, // files whose top-level `old` is being renamed, for importers
+}
+
+impl Renamer {
+    fn unit(&mut self, path: &Path) -> Result<&Unit, ()> {
+        if !self.units.contains_key(path) {
+            let unit = Unit::load(path)?;
+            self.units.insert(path.to_path_buf(), unit);
+        }
+
+        Ok(&self.units[path])
+    }
+
+    fn edit(&mut self, path: &Path, span: Span) {
+        let edits = self.edits.entry(path.to_path_buf()).or_default();
+
+        let edit = Edit {
+            span,
+            text: self.new.clone(),
+        };
+
+        if !edits.contains(&edit) {
+            edits.push(edit)
+        }
+    }
+
+    fn collision(&self, path: &Path, span: Span, other: usize) -> Result<(), ()> {
+        let unit = &self.units[path];
+        let other = &unit.table.bindings[other];
+
+        Err(response!(
+            Wrong(format!(
+                "can't rename `{}` to `{}`, it would clash with the `{}` declared on line {}",
+                self.old, self.new, self.new, other.span.0
+            )),
+            unit.source.file,
+            unit.pos(span)
+        ))
+    }
+
+    // `new` must not capture, or be captured at, any of the places written with the old name
+    fn check_use(&self, path: &Path, using: &Use, meant: Option<usize>) -> Result<(), ()> {
+        let table = &self.units[path].table;
+
+        if let Some(Target::Binding(other)) = table.lookup(&self.new, using.scope, using.visible) {
+            if Some(other) != meant {
+                return self.collision(path, using.span, other);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rename_binding(&mut self, path: &Path, id: usize) -> Result<(), ()> {
+        if !self.renamed.insert((path.to_path_buf(), id)) {
+            return Ok(());
+        }
+
+        self.unit(path)?;
+
+        let binding = self.units[path].table.bindings[id].clone();
+
+        if let Some(other) = self.units[path]
+            .table
+            .bindings
+            .iter()
+            .position(|other| other.scope == binding.scope && other.name == self.new)
+        {
+            return self.collision(path, binding.span, other);
+        }
+
+        self.edit(path, binding.span);
+
+        let uses = self.units[path]
+            .table
+            .uses
+            .iter()
+            .filter(|using| using.target == Some(Target::Binding(id)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for using in uses.iter() {
+            self.check_use(path, using, Some(id))?;
+            self.edit(path, using.span)
+        }
+
+        if binding.scope == 0 && binding.public {
+            self.exposed.push(path.to_path_buf())
+        }
+
+        Ok(())
+    }
+
+    // follows the exposed names into every file importing them
+    fn propagate(&mut self, files: &[PathBuf]) -> Result<(), ()> {
+        let mut done = HashSet::new();
+
+        while let Some(exposer) = self.exposed.pop() {
+            if !done.insert(exposer.clone()) {
+                continue;
+            }
+
+            for file in files.iter() {
+                self.unit(file)?;
+
+                let table = self.units[file].table.clone();
+
+                for (id, binding) in table.bindings.iter().enumerate() {
+                    let (path, member) = match binding.kind {
+                        BindingKind::Import(ref path, ref member) => (path, member),
+                        _ => continue,
+                    };
+
+                    if resolve(file, path, &self.root).as_ref() != Some(&exposer) {
+                        continue;
+                    }
+
+                    match member.as_deref() {
+                        // `module old`, or `old` through a wildcard
+                        None | Some("*") => {
+                            for using in table.uses.iter() {
+                                if using.target == Some(Target::Member(id, self.old.clone())) {
+                                    if member.is_some() {
+                                        self.check_use(file, using, None)?
+                                    }
+
+                                    self.edit(file, using.span)
+                                }
+                            }
+
+                            if member.is_some() && binding.public {
+                                self.exposed.push(file.clone())
+                            }
+                        }
+
+                        Some(member) if member == self.old => {
+                            for site in table.imports.iter() {
+                                for (_, span, local) in site.members.iter() {
+                                    if *local == id {
+                                        self.edit(file, *span)
+                                    }
+                                }
+                            }
+
+                            // unaliased, so the local name follows along
+                            if binding.name == self.old {
+                                self.rename_binding(file, id)?
+                            }
+                        }
+
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // the definition behind whatever is written at a position, following imports into other files
+    fn definition(&mut self, path: &Path, target: Target) -> Result<(PathBuf, usize), ()> {
+        let table = self.unit(path)?.table.clone();
+
+        let (module, member) = match target {
+            Target::Binding(id) => match table.bindings[id].kind {
+                BindingKind::Import(ref module, Some(ref member))
+                    if member != "*" && *member == table.bindings[id].name =>
+                {
+                    (module.clone(), member.clone())
+                }
+
+                _ => return Ok((path.to_path_buf(), id)),
+            },
+
+            Target::Member(id, ref member) => match table.bindings[id].kind {
+                BindingKind::Import(ref module, _) => (module.clone(), member.clone()),
+                _ => return Err(()),
+            },
+        };
+
+        let file = match resolve(path, &module, &self.root) {
+            Some(file) => file,
+            None => {
+                return Err(response!(
+                    Wrong(format!(
+                        "can't rename `{}`, it's defined outside of the project",
+                        member
+                    )),
+                    Note(format!(
+                        "`{}` isn't below `{}`",
+                        module,
+                        self.root.display()
+                    ))
+                ))
+            }
+        };
+
+        match self.unit(&file)?.table.top_level(&member) {
+            Some(id) => self.definition(&file, Target::Binding(id)),
+            None => Err(response!(Wrong(format!(
+                "can't find the definition of `{}` in `{}`",
+                member,
+                file.display()
+            )))),
+        }
+    }
+}
+
+// every edit renaming what's written at `line`:`column` of `file` to `new`, across the project
+pub fn rename(
+    file: &Path,
+    line: usize,
+    column: usize,
+    new: &str,
+) -> Result<HashMap<PathBuf, Vec<Edit>>, ()> {
+    if !is_identifier(new) {
+        return Err(response!(Wrong(format!("`{}` isn't a valid name", new))));
+    }
+
+    let file = canonical(file);
+    let root = project_root(&file);
+
+    let mut renamer = Renamer {
+        old: String::new(),
+        new: new.to_string(),
+        root: root.clone(),
+        units: HashMap::new(),
+        edits: HashMap::new(),
+        renamed: HashSet::new(),
+        exposed: Vec::new(),
+    };
+
+    let target = match renamer.unit(&file)?.table.at(line, column) {
+        Some(target) => target,
+        None => {
+            let unit = &renamer.units[&file];
+
+            return Err(response!(
+                Wrong("there's no name to rename here"),
+                unit.source.file,
+                unit.pos((line, column, column))
+            ));
+        }
+    };
+
+    let (path, id) = renamer.definition(&file, target)?;
+    let binding = renamer.units[&path].table.bindings[id].clone();
+
+    renamer.old = binding.name.clone();
+
+    match binding.kind {
+        BindingKind::Import(_, None) => {
+            return Err(response!(
+                Wrong(format!("can't rename the module `{}`", binding.name)),
+                Note("its name comes from the file, rename that instead")
+            ))
+        }
+
+        BindingKind::Import(_, Some(ref member)) if member == "*" => {
+            return Err(response!(Wrong("can't rename a wildcard import")))
+        }
+
+        BindingKind::Method => {
+            return Err(response!(
+                Wrong(format!("can't rename the method `{}`", binding.name)),
+                Note(
+                    "methods and fields are found through types, which renaming doesn't follow yet"
+                )
+            ))
+        }
+
+        _ => (),
+    }
+
+    if renamer.old == renamer.new {
+        return Ok(HashMap::new());
+    }
+
+    renamer.rename_binding(&path, id)?;
+
+    let mut files = Vec::new();
+    sources(&root.display().to_string(), &mut files);
+
+    let files = files
+        .iter()
+        .map(|file| canonical(Path::new(file)))
+        .collect::<Vec<_>>();
+
+    renamer.propagate(&files)?;
+
+    Ok(renamer.edits)
+}
+
+// where what's written at `line`:`column` of `unit`, the file at `file`, is defined, following
+// imports into the other files of the project
+pub fn definition(file: &Path, unit: Unit, line: usize, column: usize) -> Option<(PathBuf, Span)> {
+    let file = canonical(file);
+    let target = unit.table.at(line, column)?;
+
+    let mut finder = Renamer {
+        old: String::new(),
+        new: String::new(),
+        root: project_root(&file),
+        units: HashMap::from([(file.clone(), unit)]),
+        edits: HashMap::new(),
+        renamed: HashSet::new(),
+        exposed: Vec::new(),
+    };
+
+    let (path, id) = silently(|| finder.definition(&file, target)).ok()?;
+    let span = finder.units[&path].table.bindings[id].span;
+
+    Some((path, span))
+}
+
+// rewrites `content` with `edits`, which must not overlap
+pub fn apply(content: &str, edits: &[Edit]) -> String {
+    let mut edits = edits.to_vec();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.span));
+
+    let mut lines = content.split('\n').map(String::from).collect::<Vec<_>>();
+
+    for edit in edits.iter() {
+        if let Some(line) = lines.get_mut(edit.span.0.saturating_sub(1)) {
+            let mut chars = line.chars().collect::<Vec<_>>();
+
+            let start = edit.span.1.saturating_sub(1).min(chars.len());
+            let end = edit.span.2.min(chars.len());
+
+            chars.splice(start..end, edit.text.chars());
+
+            *line = chars.into_iter().collect()
+        }
+    }
+
+    lines.join("\n")
+}
+
+pub fn rename_path(location: &str, name: Option<&String>, build: &mut Build) {
+    let now = Instant::now();
+
+    let (file, line, column, name) = match (position(location), name) {
+        (Some((file, line, column)), Some(name)) => (file, line, column, name),
+        _ => {
+            response!(Response::Wrong("expected `wu rename <file>:<line>:<col> <name>`"));
+
+            return build.fail(Failure::Usage);
+        }
+    };
+
+    if !Path::new(file).is_file() {
+        response!(Response::Wrong(format!("can't read `{}`: no such file", file)));
+
+        return build.fail(Failure::Io);
+    }
+
+    let edits = match rename(Path::new(file), line, column, name) {
+        Ok(edits) => edits,
+        Err(()) => return build.fail(Failure::Type),
+    };
+
+    let mut count = 0;
+
+    for (path, edits) in edits.iter() {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(why) => {
+                response!(Response::Wrong(format!("failed to read `{}`: {}", path.display(), why)));
+
+                return build.fail(Failure::Io);
+            }
+        };
+
+        if write(path, &apply(&content, edits)).is_err() {
+            return build.fail(Failure::Io);
+        }
+
+        log!(Verbose, "Rewrote", "{} ({} edits)", path.display(), edits.len());
+
+        count += edits.len()
+    }
+
+    log!(
+        Normal,
+        "Renamed",
+        "to `{}`: {} in {} ({}ms)",
+        name,
+        plural(count, "edit"),
+        plural(edits.len(), "module"),
+        now.elapsed().as_millis()
+    )
+}
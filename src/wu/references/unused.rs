@@ -0,0 +1,109 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::super::error::Response::*;
+use super::*;
+
+// warns about the `pub` definitions of the project below `root` that no other module imports.
+// The entry point, `main.wu`, is left out: what it exports is for whatever runs it. Where
+// `unused_exports` is denied, any of them fail the build
+pub fn warn_unused_exports(root: &Path) -> Result<usize, ()> {
+    let root = canonical(root);
+    let entry = root.join("main.wu");
+
+    let mut files = Vec::new();
+    sources(&root.display().to_string(), &mut files);
+
+    let files = files
+        .iter()
+        .map(|file| canonical(Path::new(file)))
+        .collect::<Vec<_>>();
+
+    let mut units = HashMap::new();
+
+    for file in files.iter() {
+        units.insert(file.clone(), Unit::load(file)?);
+    }
+
+    // (file, name) for every name imported from `file`, and the files imported with `*`
+    let mut imported = HashSet::new();
+    let mut wildcards = HashSet::new();
+
+    for file in files.iter() {
+        let table = &units[file].table;
+
+        for (id, binding) in table.bindings.iter().enumerate() {
+            let (path, member) = match binding.kind {
+                BindingKind::Import(ref path, ref member) => (path, member),
+                _ => continue,
+            };
+
+            let exporter = match resolve(file, path, &root) {
+                Some(exporter) => exporter,
+                None => continue,
+            };
+
+            match member.as_deref() {
+                Some("*") => {
+                    wildcards.insert(exporter);
+                }
+
+                Some(member) => {
+                    imported.insert((exporter, member.to_string()));
+                }
+
+                // `import shapes`, then `shapes area`
+                None => {
+                    for using in table.uses.iter() {
+                        if let Some(Target::Member(module, ref member)) = using.target {
+                            if module == id {
+                                imported.insert((exporter.clone(), member.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let level = Lint::UnusedExports.level();
+    let mut unused = 0;
+
+    for file in files.iter() {
+        if *file == entry || wildcards.contains(file) {
+            continue;
+        }
+
+        let unit = &units[file];
+
+        for binding in unit.table.bindings.iter() {
+            let exported = binding.scope == 0
+                && binding.public
+                && !matches!(binding.kind, BindingKind::Import(..));
+
+            if !exported || imported.contains(&(file.clone(), binding.name.clone())) {
+                continue;
+            }
+
+            if let Some(reported) = level.response(format!(
+                "`{}` is `pub`, but no other module imports it",
+                binding.name
+            )) {
+                response!(
+                    reported,
+                    unit.source.file,
+                    unit.pos(binding.span),
+                    Note("drop the `pub`, or the definition if nothing uses it")
+                );
+            }
+
+            unused += 1
+        }
+    }
+
+    if unused > 0 && level == LintLevel::Deny {
+        Err(())
+    } else {
+        Ok(unused)
+    }
+}
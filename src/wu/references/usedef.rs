@@ -0,0 +1,743 @@
+use std::collections::HashMap;
+
+use super::*;
+
+// (line, first column, last column), with 1-based columns like `Pos`
+pub type Span = (usize, usize, usize);
+
+// the span of `name` written at the start of `pos`
+pub fn name_span(pos: &Pos, name: &str) -> Span {
+    let start = (pos.1).0;
+
+    (
+        (pos.0).0,
+        start,
+        start + name.chars().count().saturating_sub(1),
+    )
+}
+
+// the span of the name a declaration at `pos` binds, which is written before `pos` starts
+pub fn declared_span(pos: &Pos, name: &str) -> Span {
+    let line = (pos.0).1.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+
+    let found = (0..(pos.1).0.min(line.len() + 1)).rev().find(|&start| {
+        line[start..].starts_with(&name)
+            && !line.get(start + name.len()).is_some_and(is_word)
+            && !(start > 0 && is_word(&line[start - 1]))
+    });
+
+    match found {
+        Some(start) => ((pos.0).0, start + 1, start + name.len()),
+        None => name_span(pos, &name.iter().collect::<String>()),
+    }
+}
+
+// the span of `name` among the names of a `match` arm's pattern at `pos`
+fn pattern_span(pos: &Pos, name: &str) -> Span {
+    let end = Pos(pos.0.clone(), ((pos.1).1, (pos.1).1));
+
+    declared_span(&end, name)
+}
+
+fn token_span(token: &Token) -> Span {
+    (token.line.0, token.slice.0, token.slice.1)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingKind {
+    Variable,
+    Function,
+    Type,
+    Module,
+    Parameter,
+    Method, // inside `implement`, reached through values rather than names
+    Import(String, Option<String>), // (path, member), no member for the module itself and `*` for wildcards
+}
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub name: String,
+    pub span: Span,
+    pub kind: BindingKind,
+    pub scope: usize,
+    pub public: bool,
+    pub body: Option<usize>, // scope holding a module's content
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Binding(usize),
+    Member(usize, String), // a member of the imported module, or wildcard, bound at `usize`
+}
+
+#[derive(Debug, Clone)]
+pub struct Use {
+    pub span: Span,
+    pub scope: usize,
+    pub visible: usize, // only bindings before this one were declared at the time
+    pub target: Option<Target>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub parent: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportSite {
+    pub members: Vec<(String, Span, usize)>, // imported name, where it's written, and the local binding
+}
+
+// which definition every name in a file refers to
+#[derive(Debug, Clone, Default)]
+pub struct UseDef {
+    pub bindings: Vec<Binding>,
+    pub scopes: Vec<Scope>,
+    pub uses: Vec<Use>,
+    pub imports: Vec<ImportSite>,
+}
+
+impl UseDef {
+    pub fn new(ast: &[Statement], tokens: &[Token]) -> Self {
+        let mut index = HashMap::new();
+
+        for (i, token) in tokens.iter().enumerate() {
+            index.insert((token.line.0, token.slice.0), i);
+        }
+
+        let mut builder = Builder {
+            table: UseDef::default(),
+            tokens,
+            index,
+            scope: 0,
+            implementing: false,
+        };
+
+        builder.table.scopes.push(Scope { parent: None });
+        builder.block(ast);
+
+        builder.table
+    }
+
+    // what `name` means in `scope`, counting only the first `visible` bindings
+    pub fn lookup(&self, name: &str, scope: usize, visible: usize) -> Option<Target> {
+        let mut wildcard = None;
+        let mut current = Some(scope);
+
+        while let Some(scope) = current {
+            for (id, binding) in self.bindings[..visible].iter().enumerate().rev() {
+                if binding.scope != scope {
+                    continue;
+                }
+
+                if binding.name == name {
+                    return Some(Target::Binding(id));
+                }
+
+                if let BindingKind::Import(_, Some(ref member)) = binding.kind {
+                    if member == "*" && wildcard.is_none() {
+                        wildcard = Some(id)
+                    }
+                }
+            }
+
+            current = self.scopes[scope].parent
+        }
+
+        wildcard.map(|id| Target::Member(id, name.to_string()))
+    }
+
+    // the binding or use written at `line`:`column`
+    pub fn at(&self, line: usize, column: usize) -> Option<Target> {
+        let inside = |span: &Span| span.0 == line && span.1 <= column && column <= span.2;
+
+        for (id, binding) in self.bindings.iter().enumerate() {
+            if inside(&binding.span) {
+                return Some(Target::Binding(id));
+            }
+        }
+
+        self.uses
+            .iter()
+            .find(|using| inside(&using.span))
+            .and_then(|using| using.target.clone())
+    }
+
+    // the last top-level binding called `name`
+    pub fn top_level(&self, name: &str) -> Option<usize> {
+        self.bindings
+            .iter()
+            .rposition(|binding| binding.scope == 0 && binding.name == name)
+    }
+}
+
+struct Builder<'t> {
+    table: UseDef,
+    tokens: &'t [Token],
+    index: HashMap<(usize, usize), usize>,
+    scope: usize,
+    implementing: bool,
+}
+
+impl<'t> Builder<'t> {
+    fn push_scope(&mut self) {
+        self.table.scopes.push(Scope {
+            parent: Some(self.scope),
+        });
+
+        self.scope = self.table.scopes.len() - 1
+    }
+
+    fn pop_scope(&mut self) {
+        self.scope = self.table.scopes[self.scope].parent.unwrap_or(0)
+    }
+
+    fn bind(&mut self, name: &str, span: Span, kind: BindingKind, public: bool) -> usize {
+        self.table.bindings.push(Binding {
+            name: name.to_string(),
+            span,
+            kind,
+            scope: self.scope,
+            public,
+            body: None,
+        });
+
+        self.table.bindings.len() - 1
+    }
+
+    fn refer(&mut self, span: Span, target: Option<Target>) {
+        self.table.uses.push(Use {
+            span,
+            scope: self.scope,
+            visible: self.table.bindings.len(),
+            target,
+        })
+    }
+
+    fn use_name(&mut self, name: &str, span: Span) -> Option<Target> {
+        let target = self
+            .table
+            .lookup(name, self.scope, self.table.bindings.len());
+
+        self.refer(span, target.clone());
+
+        target
+    }
+
+    // the module an expression like `foo bar` names, recording the names along the way
+    fn module_of(&mut self, expression: &Expression) -> Option<usize> {
+        match expression.node {
+            ExpressionNode::Identifier(ref name) => {
+                let span = name_span(&expression.pos, name);
+
+                match self.use_name(name, span) {
+                    Some(Target::Binding(id)) => match self.table.bindings[id].kind {
+                        BindingKind::Module | BindingKind::Import(_, None) => Some(id),
+                        _ => None,
+                    },
+
+                    _ => None,
+                }
+            }
+
+            ExpressionNode::Index(ref left, ref index, false) => {
+                let module = self.module_of(left)?;
+
+                let name = match index.node {
+                    ExpressionNode::Identifier(ref name) => name,
+                    _ => return None,
+                };
+
+                let span = name_span(&index.pos, name);
+
+                if let BindingKind::Import(_, None) = self.table.bindings[module].kind {
+                    self.refer(span, Some(Target::Member(module, name.clone())));
+
+                    return None;
+                }
+
+                let body = self.table.bindings[module].body?;
+
+                let member = self
+                    .table
+                    .bindings
+                    .iter()
+                    .rposition(|binding| binding.scope == body && &binding.name == name);
+
+                self.refer(span, member.map(Target::Binding));
+
+                member.filter(|member| self.table.bindings[*member].kind == BindingKind::Module)
+            }
+
+            _ => {
+                self.expression(expression);
+
+                None
+            }
+        }
+    }
+
+    fn token_at(&self, span: Span) -> Option<usize> {
+        self.index.get(&(span.0, span.1)).cloned()
+    }
+
+    fn next_significant(&self, mut i: usize) -> Option<usize> {
+        while let Some(token) = self.tokens.get(i) {
+            if token.token_type != TokenType::EOL {
+                return Some(i);
+            }
+
+            i += 1
+        }
+
+        None
+    }
+
+    // parameter names written in the parentheses after `fun`
+    fn param_spans(&self, pos: &Pos) -> Vec<Span> {
+        let mut spans = Vec::new();
+
+        let start = match self.token_at(name_span(pos, "fun")) {
+            Some(start) => start,
+            None => return spans,
+        };
+
+        let mut depth = 0;
+
+        for i in start + 1..self.tokens.len() {
+            let token = &self.tokens[i];
+
+            match token.lexeme.as_str() {
+                "(" => depth += 1,
+                ")" => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        break;
+                    }
+                }
+
+                _ if depth == 0 && token.token_type != TokenType::EOL => break,
+
+                _ if depth == 1
+                    && token.token_type == TokenType::Identifier
+                    && self.tokens.get(i + 1).map(|next| next.lexeme.as_str()) == Some(":") =>
+                {
+                    spans.push(token_span(token))
+                }
+
+                _ => (),
+            }
+        }
+
+        spans
+    }
+
+    // names listed in a module's `export { .. }`
+    fn export_spans(&self, pos: &Pos) -> Vec<(String, Span)> {
+        let mut spans = Vec::new();
+
+        let start = match self.token_at(name_span(pos, "module")) {
+            Some(start) => start,
+            None => return spans,
+        };
+
+        match self.next_significant(start + 1) {
+            Some(i) if self.tokens[i].lexeme == "export" => {
+                for token in self.tokens[i + 1..].iter() {
+                    match token.token_type {
+                        TokenType::Identifier => {
+                            spans.push((token.lexeme.clone(), token_span(token)))
+                        }
+                        _ if token.lexeme == "}" => break,
+                        _ => (),
+                    }
+                }
+            }
+
+            _ => (),
+        }
+
+        spans
+    }
+
+    fn block(&mut self, ast: &[Statement]) {
+        for statement in ast.iter() {
+            self.statement(statement)
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        use self::StatementNode::*;
+
+        match statement.node {
+            Expression(ref expression) => self.expression(expression),
+
+            Variable(_, ref name, ref right, public) => {
+                let span = declared_span(&statement.pos, name);
+
+                let kind = if self.implementing {
+                    BindingKind::Method
+                } else {
+                    match right.as_ref().map(|right| &right.node) {
+                        Some(ExpressionNode::Function(..)) => BindingKind::Function,
+                        Some(ExpressionNode::Struct(..))
+                        | Some(ExpressionNode::Trait(..))
+                        | Some(ExpressionNode::Enum(..)) => BindingKind::Type,
+                        Some(ExpressionNode::Module(..)) => BindingKind::Module,
+                        Some(ExpressionNode::Extern(t, _))
+                            if matches!(t.node, TypeNode::Func(..)) =>
+                        {
+                            BindingKind::Function
+                        }
+                        _ => BindingKind::Variable,
+                    }
+                };
+
+                self.implementing = false;
+
+                let right = match *right {
+                    Some(ref right) => right,
+                    None => {
+                        self.bind(name, span, kind, public);
+                        return;
+                    }
+                };
+
+                match right.node {
+                    // visible to themselves
+                    ExpressionNode::Function(..)
+                    | ExpressionNode::Struct(..)
+                    | ExpressionNode::Trait(..)
+                    | ExpressionNode::Enum(..) => {
+                        self.bind(name, span, kind, public);
+                        self.expression(right)
+                    }
+
+                    ExpressionNode::Module(ref body, ref exports) => {
+                        let id = self.bind(name, span, kind, public);
+
+                        let scope = self.table.scopes.len();
+                        self.expression(body);
+
+                        self.table.bindings[id].body = Some(scope);
+
+                        if exports.is_some() {
+                            for (export, span) in self.export_spans(&right.pos) {
+                                let member = self.table.bindings.iter().rposition(|binding| {
+                                    binding.scope == scope && binding.name == export
+                                });
+
+                                self.refer(span, member.map(Target::Binding))
+                            }
+                        }
+                    }
+
+                    _ => {
+                        self.expression(right);
+                        self.bind(name, span, kind, public);
+                    }
+                }
+            }
+
+            SplatVariable(_, ref names, ref right, public) => {
+                if let Some(ref right) = *right {
+                    self.expression(right)
+                }
+
+                let mut i = self.token_at(declared_span(&statement.pos, &names[0]));
+
+                for name in names.iter() {
+                    let span = match i {
+                        Some(index) => token_span(&self.tokens[index]),
+                        None => ((statement.pos.0).0, 0, 0),
+                    };
+
+                    self.bind(name, span, BindingKind::Variable, public);
+
+                    // skip the comma
+                    i = i.map(|index| index + 2)
+                }
+            }
+
+            Assignment(ref left, ref right) => {
+                self.expression(left);
+                self.expression(right)
+            }
+
+            SplatAssignment(ref left, ref right) => {
+                for expression in left.iter() {
+                    self.expression(expression)
+                }
+
+                self.expression(right)
+            }
+
+            Return(Some(ref expression)) => self.expression(expression),
+
+            Implement(ref name, ref body, ref parent) => {
+                self.expression(name);
+
+                if let Some(ref parent) = *parent {
+                    self.expression(parent)
+                }
+
+                if let ExpressionNode::Block(ref content) = body.node {
+                    self.push_scope();
+
+                    for statement in content.iter() {
+                        self.implementing = true;
+                        self.statement(statement);
+                        self.implementing = false
+                    }
+
+                    self.pop_scope()
+                }
+            }
+
+            Import(ref path, ref specifics, _, public) => {
+                self.import(statement, path, specifics, public)
+            }
+
+            Global(ref binding) => self.statement(binding),
+
+            _ => (),
+        }
+    }
+
+    fn import(
+        &mut self,
+        statement: &Statement,
+        path: &str,
+        specifics: &[(String, Option<String>)],
+        public: bool,
+    ) {
+        let module = path.rsplit('/').next().unwrap_or(path);
+
+        let start = self
+            .index
+            .iter()
+            .filter(|(key, _)| key.0 == (statement.pos.0).0 && key.1 >= (statement.pos.1).0)
+            .map(|(_, i)| *i)
+            .min()
+            .unwrap_or(self.tokens.len());
+
+        // the path ends at the last name before `{` or the end of the line
+        let mut module_span = name_span(&statement.pos, module);
+        let mut i = start;
+
+        while let Some(token) = self.tokens.get(i) {
+            if token.token_type == TokenType::EOL || token.lexeme == "{" {
+                break;
+            }
+
+            if token.lexeme == module {
+                module_span = token_span(token)
+            }
+
+            i += 1
+        }
+
+        self.bind(
+            module,
+            module_span,
+            BindingKind::Import(path.to_string(), None),
+            public,
+        );
+
+        let mut site = ImportSite {
+            members: Vec::new(),
+        };
+
+        let mut written = Vec::new();
+
+        while let Some(token) = self.tokens.get(i) {
+            if token.lexeme == "}" {
+                break;
+            }
+
+            if token.token_type == TokenType::Identifier || token.lexeme == "*" {
+                let alias = match self.next_significant(i + 1) {
+                    Some(next) if self.tokens[next].lexeme == "as" => {
+                        self.next_significant(next + 1)
+                    }
+                    _ => None,
+                };
+
+                written.push((
+                    token_span(token),
+                    alias.map(|alias| token_span(&self.tokens[alias])),
+                ));
+
+                i = alias.unwrap_or(i)
+            }
+
+            i += 1
+        }
+
+        for (n, (member, alias)) in specifics.iter().enumerate() {
+            let (member_span, alias_span) = written
+                .get(n)
+                .cloned()
+                .unwrap_or((((statement.pos.0).0, 0, 0), None));
+
+            let name = alias.as_ref().unwrap_or(member);
+
+            let id = self.bind(
+                name,
+                alias_span.unwrap_or(member_span),
+                BindingKind::Import(path.to_string(), Some(member.clone())),
+                public,
+            );
+
+            site.members.push((member.clone(), member_span, id))
+        }
+
+        self.table.imports.push(site)
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        use self::ExpressionNode::*;
+
+        match expression.node {
+            Identifier(ref name) => {
+                self.use_name(name, name_span(&expression.pos, name));
+            }
+
+            Index(ref left, ref index, true) => {
+                self.expression(left);
+                self.expression(index)
+            }
+
+            Index(..) => {
+                self.module_of(expression);
+            }
+
+            Call(ref callee, ref args) => {
+                self.expression(callee);
+
+                for arg in args.iter() {
+                    self.expression(arg)
+                }
+            }
+
+            Function(ref params, _, ref body, _) => {
+                let spans = self.param_spans(&expression.pos);
+
+                self.push_scope();
+
+                for (n, param) in params.iter().enumerate() {
+                    let span = spans
+                        .get(n)
+                        .cloned()
+                        .unwrap_or(((expression.pos.0).0, 0, 0));
+
+                    self.bind(&param.0, span, BindingKind::Parameter, false);
+                }
+
+                self.expression(body);
+                self.pop_scope()
+            }
+
+            Block(ref content) => {
+                self.push_scope();
+                self.block(content);
+                self.pop_scope()
+            }
+
+            For((ref iterator, ref other), ref body) => match *other {
+                Some(ref other) => {
+                    self.expression(other);
+
+                    self.push_scope();
+
+                    if let Identifier(ref name) = iterator.node {
+                        self.bind(
+                            name,
+                            name_span(&iterator.pos, name),
+                            BindingKind::Variable,
+                            false,
+                        );
+                    } else {
+                        self.expression(iterator)
+                    }
+
+                    self.expression(body);
+                    self.pop_scope()
+                }
+
+                None => {
+                    self.expression(iterator);
+                    self.expression(body)
+                }
+            },
+
+            If(ref condition, ref body, ref elses) => {
+                self.expression(condition);
+                self.expression(body);
+
+                if let Some(ref elses) = *elses {
+                    for (condition, body, _) in elses.iter() {
+                        if let Some(ref condition) = *condition {
+                            self.expression(condition)
+                        }
+
+                        self.expression(body)
+                    }
+                }
+            }
+
+            While(ref condition, ref body) => {
+                self.expression(condition);
+                self.expression(body)
+            }
+
+            Match(ref subject, ref arms) => {
+                self.expression(subject);
+
+                for (_, names, body, pos) in arms.iter() {
+                    self.push_scope();
+
+                    for name in names.iter().filter(|name| *name != "_") {
+                        self.bind(name, pattern_span(pos, name), BindingKind::Variable, false);
+                    }
+
+                    self.expression(body);
+                    self.pop_scope()
+                }
+            }
+
+            Binary(ref left, _, ref right) => {
+                self.expression(left);
+                self.expression(right)
+            }
+
+            Initialization(ref name, ref fields) => {
+                self.expression(name);
+
+                for field in fields.iter() {
+                    self.expression(&field.1)
+                }
+            }
+
+            Tuple(ref content) | Array(ref content) | Splat(ref content) => {
+                for expression in content.iter() {
+                    self.expression(expression)
+                }
+            }
+
+            Neg(ref inner)
+            | Not(ref inner)
+            | Unwrap(ref inner)
+            | UnwrapSplat(ref inner)
+            | Cast(ref inner, _)
+            | Module(ref inner, _)
+            | ExternExpression(ref inner) => self.expression(inner),
+
+            _ => (),
+        }
+    }
+}
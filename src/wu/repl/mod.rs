@@ -0,0 +1,10 @@
+pub mod repl;
+
+use super::compiler::Generator;
+use super::error::*;
+use super::lexer::Lexer;
+use super::parser::*;
+use super::source::Source;
+use super::visitor::*;
+
+pub use self::repl::*;
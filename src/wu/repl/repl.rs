@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+
+use super::*;
+
+// the file diagnostics report the REPL's code as
+const FILE: &str = "repl.wu";
+
+// what the value of an expression typed in is bound to, to print it after running
+const VALUE: &str = "__value";
+
+const HELP: &str = "\
+    <code>         # Checks and runs the code; what it declares stays for what follows
+    :type <expr>   # Prints the type the checker gives the expression, without running it
+    :ast <code>    # Prints the syntax tree of the code
+    :lua <code>    # Prints the lua the code compiles to
+    :reset         # Forgets everything declared so far
+    :quit          # Leaves, as does ctrl-d";
+
+// what the REPL was given that declares something, checked and run again before each input
+#[derive(Debug, Default)]
+pub struct Session {
+    pub declared: Vec<String>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    // the line code typed in starts on, after what's declared
+    fn first_line(&self) -> usize {
+        self.declared
+            .iter()
+            .map(|code| code.lines().count())
+            .sum::<usize>()
+            + 1
+    }
+
+    // checks what's declared followed by `input`, then hands `f` all of it, with the index of
+    // the first statement of `input`
+    fn checked<T>(
+        &self,
+        input: &str,
+        f: impl FnOnce(&Vec<Statement>, usize, &mut Visitor, &Source) -> T,
+    ) -> Result<T, ()> {
+        let mut content = self.declared.join("\n");
+
+        if !content.is_empty() {
+            content.push('\n')
+        }
+
+        content.push_str(input);
+
+        let source = Source::from(FILE, content.lines().map(String::from).collect());
+        let ast = parse(&content, &source)?;
+
+        let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), ".".to_string());
+        visitor.types = Some(HashMap::new());
+        visitor.visit()?;
+
+        let first = self.first_line();
+        let start = ast
+            .iter()
+            .position(|statement| (statement.pos.0).0 >= first)
+            .unwrap_or(ast.len());
+
+        Ok(f(&ast, start, &mut visitor, &source))
+    }
+
+    // the type the checker gives the expression `input`, spelled out like `wu type-at` does
+    pub fn type_of(&self, input: &str) -> Result<String, ()> {
+        self.checked(input, |ast, start, visitor, _| match ast[start..] {
+            [Statement {
+                node: StatementNode::Expression(ref expression),
+                ..
+            }] => match typed(visitor, expression) {
+                Some(t) => Ok(visitor.describe(&t)),
+                None => Err(response!(Response::Wrong("nothing typed there"))),
+            },
+
+            _ => Err(response!(Response::Wrong(
+                "expected an expression after `:type`"
+            ))),
+        })?
+    }
+
+    // the syntax tree of `input` on its own, leaving out where everything is
+    pub fn ast(&self, input: &str) -> Result<String, ()> {
+        let source = Source::from(FILE, input.lines().map(String::from).collect());
+
+        let ast = parse(input, &source)?;
+
+        Ok(ast
+            .iter()
+            .map(|statement| without_positions(&format!("{:#?}", statement.node)))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    // the lua `input` compiles to, after what's declared
+    pub fn lua(&self, input: &str) -> Result<String, ()> {
+        let first = self.first_line();
+
+        self.checked(input, |ast, _, visitor, source| {
+            let mut generator = Generator::new(source, visitor);
+            let lua = generator.generate(ast);
+
+            let lines = lua
+                .lines()
+                .zip(generator.lines.iter())
+                .filter(|(line, &wu)| wu >= first && !line.trim().is_empty())
+                .map(|(line, _)| line)
+                .collect::<Vec<_>>();
+
+            let indent = lines
+                .iter()
+                .map(|line| line.len() - line.trim_start().len())
+                .min()
+                .unwrap_or(0);
+
+            lines
+                .iter()
+                .map(|line| &line[indent..])
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+
+    // checks and runs `input` with `lua`, after what's declared, keeping it when all it does is
+    // declare and that didn't fail running; what it printed, then the value of an expression
+    pub fn eval(&mut self, input: &str, lua: &str) -> Result<String, ()> {
+        let (value, declares) = self.checked(input, |ast, start, visitor, _| {
+            let value = match ast[start..] {
+                [Statement {
+                    node: StatementNode::Expression(ref expression),
+                    ..
+                }] => typed(visitor, expression).is_some_and(|t| t.node != TypeNode::Nil),
+
+                _ => false,
+            };
+
+            let declares = ast[start..]
+                .iter()
+                .all(|statement| !matches!(statement.node, StatementNode::Expression(_)));
+
+            (value, declares)
+        })?;
+
+        let code = if value {
+            format!("pub {} := {}", VALUE, input)
+        } else {
+            input.to_string()
+        };
+
+        let compiled = self.checked(&code, |ast, _, visitor, source| {
+            Generator::new(source, visitor).generate(ast)
+        })?;
+
+        let mut program = compiled.replacen("return ", "local __repl = ", 1);
+
+        if value {
+            program.push_str(&format!("\nprint(__repl.{})", VALUE))
+        }
+
+        let file = env::temp_dir().join(format!("wu-repl-{}.lua", process::id()));
+
+        if let Err(why) = fs::write(&file, program) {
+            return Err(response!(Response::Wrong(format!(
+                "failed to write `{}`: {}",
+                file.display(),
+                why
+            ))));
+        }
+
+        let output = Command::new(lua).arg(&file).output();
+        let _ = fs::remove_file(&file);
+
+        let output = match output {
+            Ok(output) => output,
+            Err(why) => {
+                // without lua, what's declared can still be looked at
+                if declares {
+                    self.declared.push(input.to_string())
+                }
+
+                return Err(response!(Response::Wrong(format!(
+                    "can't run `{}`: {}",
+                    lua, why
+                ))));
+            }
+        };
+
+        if declares && output.status.success() {
+            self.declared.push(input.to_string())
+        }
+
+        Ok(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .trim_end()
+        .to_string())
+    }
+}
+
+// reads code and `:` commands until ctrl-d or `:quit`, running code with `lua` (or $WU_LUA)
+pub fn repl() {
+    let lua = env::var("WU_LUA").unwrap_or_else(|_| "lua".to_string());
+
+    let mut editor = Editor::<()>::new();
+    let mut session = Session::new();
+
+    println!(
+        "Wu {}, `:help` lists the commands",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    loop {
+        let mut input = match editor.readline(">> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(_) => break,
+        };
+
+        // a bracket left open continues on the next line
+        while open_brackets(&input) > 0 {
+            match editor.readline(".. ") {
+                Ok(line) => {
+                    input.push('\n');
+                    input.push_str(&line)
+                }
+
+                Err(_) => break,
+            }
+        }
+
+        if input.trim().is_empty() {
+            continue;
+        }
+
+        editor.add_history_entry(input.as_str());
+
+        let (command, rest) = match input.trim_start().strip_prefix(':') {
+            Some(command) => command
+                .split_once(char::is_whitespace)
+                .unwrap_or((command, "")),
+            None => ("", input.as_str()),
+        };
+
+        let result = match command {
+            "" => session.eval(rest, &lua),
+            "type" => session.type_of(rest),
+            "ast" => session.ast(rest),
+            "lua" => session.lua(rest),
+            "reset" => {
+                session = Session::new();
+                Ok(String::new())
+            }
+
+            "help" => Ok(HELP.to_string()),
+            "quit" | "q" => break,
+
+            command => Err(response!(
+                Response::Wrong(format!("unknown command `:{}`", command)),
+                Response::Note("`:help` lists them")
+            )),
+        };
+
+        match result {
+            Ok(output) if !output.is_empty() => println!("{}", output),
+            _ => (),
+        }
+    }
+}
+
+fn parse(content: &str, source: &Source) -> Result<Vec<Statement>, ()> {
+    let tokens =
+        Lexer::default(content.chars().collect(), source).collect::<Result<Vec<_>, _>>()?;
+
+    Parser::new(tokens, source).parse()
+}
+
+// the type of a checked top level `expression`, which the checker only recorded the parts of
+fn typed(visitor: &mut Visitor, expression: &Expression) -> Option<Type> {
+    silently(|| visitor.type_expression(expression)).ok()
+}
+
+// how many more brackets `code` opens than it closes, outside strings and comments
+fn open_brackets(code: &str) -> isize {
+    let mut open = 0;
+
+    for line in code.lines() {
+        let mut quoted = false;
+
+        for c in line.chars() {
+            match c {
+                '"' => quoted = !quoted,
+                '#' if !quoted => break,
+                '(' | '[' | '{' if !quoted => open += 1,
+                ')' | ']' | '}' if !quoted => open -= 1,
+                _ => (),
+            }
+        }
+    }
+
+    open
+}
+
+// `debug` without the `pos: Pos(..)` fields, which spell out whole lines
+fn without_positions(debug: &str) -> String {
+    let mut kept = Vec::new();
+    let mut skipping = None; // the indentation of the `pos` left out
+
+    for line in debug.lines() {
+        let indent = line.len() - line.trim_start().len();
+
+        match skipping {
+            Some(depth) => {
+                if indent == depth && line.trim_start().starts_with(')') {
+                    skipping = None
+                }
+            }
+
+            None if line.trim_start().starts_with("pos: Pos(") => skipping = Some(indent),
+            None => kept.push(line),
+        }
+    }
+
+    kept.join("\n")
+}
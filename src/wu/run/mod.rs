@@ -0,0 +1,8 @@
+pub mod run;
+
+use super::build::*;
+use super::error::*;
+use super::hot;
+use super::source::sources;
+
+pub use self::run::*;
@@ -0,0 +1,126 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::*;
+
+// recompiles the modules in `folder` as they change, telling the running program to reload them
+fn watch(child: &mut process::Child, folder: &str, build: &mut Build) -> io::Result<process::ExitStatus> {
+    let mut files = Vec::new();
+    sources(folder, &mut files);
+
+    let mut stamps = hot::stamps(&files);
+    let mut round = 0;
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+
+        thread::sleep(Duration::from_millis(250));
+
+        let mut files = Vec::new();
+        sources(folder, &mut files);
+
+        let changed = hot::stamps(&files);
+
+        for (file, stamp) in changed.iter() {
+            if stamps.get(file) == Some(stamp) {
+                continue;
+            }
+
+            round += 1;
+
+            compile_path(file, &folder.to_string(), build);
+
+            let compiled = build.failed.is_empty();
+            build.finish();
+
+            if compiled {
+                match hot::signal(round, &output_path(file, build)) {
+                    Ok(_) => log!(Normal, "Reloading", "{}", file),
+                    Err(why) => {
+                        response!(Response::Wrong(format!("can't write `{}`: {}", hot::SIGNAL, why)))
+                    }
+                }
+            }
+
+            // a module that doesn't compile is left running as it was
+            build.failed.clear();
+            build.failure = None
+        }
+
+        stamps = changed
+    }
+}
+
+// `wu run`, with `--hot` watching the folder and reloading the modules that change until the
+// program ends
+pub fn run_path(path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    if !path.ends_with(".wu") {
+        response!(Response::Wrong("expected a `.wu` file after `run`"));
+
+        return build.fail(Failure::Usage);
+    }
+
+    let folder = match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+        _ => ".".to_string(),
+    };
+
+    compile_path(&folder, &folder, build);
+    build.finish();
+
+    if build.failure.is_some() {
+        return build.summary(now);
+    }
+
+    let lua = env::var("WU_LUA").unwrap_or_else(|_| "lua".to_string());
+
+    let mut command = process::Command::new(&lua);
+    build.resolve_modules(&mut command);
+
+    if build.hot {
+        let _ = fs::remove_file(hot::SIGNAL);
+
+        command.arg("-e").arg(hot::DRIVER);
+    }
+
+    let mut child = match command.arg(output_path(path, build)).spawn() {
+        Ok(child) => child,
+        Err(why) => {
+            response!(Response::Wrong(format!("failed to run `{}`: {}", lua, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let status = if build.hot {
+        watch(&mut child, &folder, build)
+    } else {
+        child.wait()
+    };
+
+    let status = match status {
+        Ok(status) => status,
+        Err(why) => {
+            response!(Response::Wrong(format!("failed to wait for `{}`: {}", lua, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    if build.hot {
+        let _ = fs::remove_file(hot::SIGNAL);
+    }
+
+    if !status.success() {
+        build.fail(Failure::Type)
+    }
+}
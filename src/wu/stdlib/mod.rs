@@ -0,0 +1,3 @@
+pub mod stdlib;
+
+pub use self::stdlib::*;
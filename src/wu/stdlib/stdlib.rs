@@ -0,0 +1,30 @@
+// the modules wu comes with, imported as `std/<name>` and read from the wu binary itself, so they
+// are there with or without `$WU_HOME`. A file of the same name in `$WU_HOME/std` goes over what
+// wu comes with, but not in a hermetic build
+use std::path::Path;
+
+const MODULES: &[(&str, &str)] = &[("vec", include_str!("vec.wu"))];
+
+// the folder the modules wu comes with are in, one that's never on disk
+pub const ROOT: &str = "<wu>";
+
+// the path of the module wu comes with imported as `path`, e.g. `std/vec`
+pub fn std_module(path: &str) -> Option<String> {
+    let name = path.strip_prefix("std/")?;
+
+    MODULES
+        .iter()
+        .any(|(module, _)| *module == name)
+        .then(|| format!("{}/{}.wu", ROOT, path))
+}
+
+// the source of the module wu comes with at `path`, as given by `std_module`
+pub fn embedded(path: &Path) -> Option<&'static str> {
+    let name = path.strip_prefix(ROOT).ok()?.strip_prefix("std").ok()?;
+    let name = name.to_str()?.strip_suffix(".wu")?;
+
+    MODULES
+        .iter()
+        .find(|(module, _)| *module == name)
+        .map(|(_, content)| *content)
+}
@@ -0,0 +1,9 @@
+pub mod tags;
+
+use super::build::*;
+use super::error::Response;
+use super::parser::*;
+use super::source::*;
+use super::visitor::*;
+
+pub use self::tags::*;
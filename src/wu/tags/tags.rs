@@ -0,0 +1,261 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    pub text: String,          // the whole line, for etags
+    pub offset: usize,         // byte offset of the line, for etags
+    pub kind: char,            // f: function, s: struct, t: trait, g: enum, m: module, v: variable
+    pub scope: Option<String>, // e.g. `module:bar` or `struct:Player`
+}
+
+// every definition visible from outside a file, i.e. public bindings, module members and methods
+pub fn collect(ast: &[Statement], source: &Source) -> Vec<Tag> {
+    let mut tags = Vec::new();
+
+    let mut offsets = Vec::with_capacity(source.lines.len() + 1);
+    let mut offset = 0;
+
+    for line in source.lines.iter() {
+        offsets.push(offset);
+        offset += line.len() + 1
+    }
+
+    collect_block(ast, source, &offsets, None, None, &mut tags);
+
+    tags
+}
+
+fn collect_block(
+    ast: &[Statement],
+    source: &Source,
+    offsets: &[usize],
+    scope: Option<&str>,
+    exports: Option<&Vec<String>>,
+    tags: &mut Vec<Tag>,
+) {
+    for statement in ast.iter() {
+        match statement.binding().node {
+            StatementNode::Variable(_, ref name, ref right, public) => {
+                let visible = match exports {
+                    Some(exports) => exports.contains(name),
+                    None => public,
+                };
+
+                if !visible {
+                    continue;
+                }
+
+                let kind = match right.as_ref().map(|right| &right.node) {
+                    Some(ExpressionNode::Function(..)) => 'f',
+                    Some(ExpressionNode::Struct(..)) => 's',
+                    Some(ExpressionNode::Trait(..)) => 't',
+                    Some(ExpressionNode::Enum(..)) => 'g',
+                    Some(ExpressionNode::Module(..)) => 'm',
+                    Some(ExpressionNode::Extern(t, _)) if matches!(t.node, TypeNode::Func(..)) => {
+                        'f'
+                    }
+                    _ => 'v',
+                };
+
+                tags.push(tag(
+                    name,
+                    kind,
+                    scope.map(str::to_string),
+                    statement,
+                    source,
+                    offsets,
+                ));
+
+                if let Some(Expression {
+                    node: ExpressionNode::Module(ref body, ref exports),
+                    ..
+                }) = *right
+                {
+                    if let ExpressionNode::Block(ref content) = body.node {
+                        let scope = match scope {
+                            Some(outer) => format!("{}.{}", outer, name),
+                            None => format!("module:{}", name),
+                        };
+
+                        collect_block(
+                            content,
+                            source,
+                            offsets,
+                            Some(&scope),
+                            exports.as_ref(),
+                            tags,
+                        )
+                    }
+                }
+            }
+
+            StatementNode::Implement(ref name, ref body, _) => {
+                let name = match name.node {
+                    ExpressionNode::Identifier(ref name) => name.clone(),
+                    ExpressionNode::Index(_, ref index, _) => match index.node {
+                        ExpressionNode::Identifier(ref name) => name.clone(),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+
+                if let ExpressionNode::Block(ref content) = body.node {
+                    let scope = format!("struct:{}", name);
+
+                    for method in content.iter() {
+                        if let StatementNode::Variable(_, ref method_name, ..) = method.node {
+                            tags.push(tag(
+                                method_name,
+                                'f',
+                                Some(scope.clone()),
+                                method,
+                                source,
+                                offsets,
+                            ))
+                        }
+                    }
+                }
+            }
+
+            _ => (),
+        }
+    }
+}
+
+fn tag(
+    name: &str,
+    kind: char,
+    scope: Option<String>,
+    statement: &Statement,
+    source: &Source,
+    offsets: &[usize],
+) -> Tag {
+    let line = (statement.pos.0).0;
+
+    Tag {
+        name: name.to_string(),
+        file: source.file.0.clone(),
+        line,
+        text: (statement.pos.0).1.to_string(),
+        offset: offsets.get(line.saturating_sub(1)).cloned().unwrap_or(0),
+        kind,
+        scope,
+    }
+}
+
+// a sorted `tags` file in the extended ctags format
+pub fn ctags(tags: &[Tag]) -> String {
+    let mut tags = tags.to_vec();
+    tags.sort_by(|a, b| (&a.name, &a.file, a.line).cmp(&(&b.name, &b.file, b.line)));
+
+    let mut result = String::from(
+        "!_TAG_FILE_FORMAT\t2\t/extended format/\n!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted/\n",
+    );
+
+    for tag in tags.iter() {
+        result.push_str(&format!(
+            "{}\t{}\t{};\"\t{}",
+            tag.name, tag.file, tag.line, tag.kind
+        ));
+
+        if let Some(ref scope) = tag.scope {
+            result.push_str(&format!("\t{}", scope))
+        }
+
+        result.push('\n')
+    }
+
+    result
+}
+
+// a `TAGS` file for emacs, one section per file
+pub fn etags(tags: &[Tag]) -> String {
+    let mut files: Vec<&String> = Vec::new();
+
+    for tag in tags.iter() {
+        if !files.contains(&&tag.file) {
+            files.push(&tag.file)
+        }
+    }
+
+    let mut result = String::new();
+
+    for file in files {
+        let mut section = String::new();
+
+        for tag in tags.iter().filter(|tag| &tag.file == file) {
+            section.push_str(&format!(
+                "{}\x7f{}\x01{},{}\n",
+                tag.text, tag.name, tag.line, tag.offset
+            ))
+        }
+
+        result.push_str(&format!("\x0c\n{},{}\n{}", file, section.len(), section))
+    }
+
+    result
+}
+
+pub fn tag_path(path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    if !Path::new(path).exists() {
+        response!(Response::Wrong(format!("can't read `{}`: no such file or directory", path)));
+
+        return build.fail(Failure::Io);
+    }
+
+    let mut files = Vec::new();
+    sources(path, &mut files);
+
+    let mut tags = Vec::new();
+
+    for file in files {
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(why) => {
+                response!(Response::Wrong(format!("failed to read `{}`: {}", file, why)));
+
+                build.fail(Failure::Io);
+                continue;
+            }
+        };
+
+        let source = Source::from(&file, content.lines().map(|x| x.into()).collect());
+
+        build.modules += 1;
+
+        match parse(&content, &source) {
+            Ok(ast) => tags.extend(tags::collect(&ast, &source)),
+            Err(failure) => build.fail(failure),
+        }
+    }
+
+    let (name, content) = if build.etags {
+        ("TAGS", tags::etags(&tags))
+    } else {
+        ("tags", tags::ctags(&tags))
+    };
+
+    if build.stdout {
+        print!("{}", content)
+    } else if write(Path::new(name), &content).is_err() {
+        return build.fail(Failure::Io);
+    }
+
+    log!(
+        Normal,
+        "Tagged",
+        "{} definitions in {} modules ({}ms)",
+        tags.len(),
+        build.modules,
+        now.elapsed().as_millis()
+    )
+}
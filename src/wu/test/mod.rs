@@ -0,0 +1,9 @@
+pub mod test;
+
+use super::build::*;
+use super::coverage;
+use super::error::*;
+use super::source::sources;
+use super::wumap;
+
+pub use self::test::*;
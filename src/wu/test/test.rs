@@ -0,0 +1,118 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+
+use super::*;
+
+fn is_test(file: &str) -> bool {
+    Path::new(file)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.ends_with("_test"))
+}
+
+pub fn test_path(path: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    build.wumap = true;
+
+    compile_path(path, &path.to_string(), build);
+    build.finish();
+
+    if build.failure.is_some() {
+        return build.summary(now);
+    }
+
+    let mut files = Vec::new();
+    sources(path, &mut files);
+
+    let lua = env::var("WU_LUA").unwrap_or_else(|_| "lua".to_string());
+
+    if build.coverage {
+        let _ = fs::remove_file(coverage::STATS);
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+
+    for test in files.iter().filter(|file| is_test(file)) {
+        if build.fail_fast && failed > 0 {
+            break;
+        }
+
+        log!(Normal, "Testing", "{}", test);
+
+        let mut command = process::Command::new(&lua);
+        build.resolve_modules(&mut command);
+
+        if build.coverage {
+            command.arg("-lluacov");
+        }
+
+        let output = match command.arg(output_path(test, build)).output() {
+            Ok(output) => output,
+            Err(why) => {
+                response!(Response::Wrong(format!("failed to run `{}`: {}", lua, why)));
+
+                return build.fail(Failure::Io);
+            }
+        };
+
+        let _ = io::stdout().write_all(&output.stdout);
+
+        if output.status.success() {
+            passed += 1
+        } else {
+            response!(
+                Response::Wrong(format!("test `{}` failed", test)),
+                Response::Note(wumap::rewrite(String::from_utf8_lossy(&output.stderr).trim_end()))
+            );
+
+            build.fail(Failure::Type);
+            failed += 1
+        }
+    }
+
+    log!(
+        Normal,
+        "Tested",
+        "{} passed, {} failed ({}ms)",
+        passed,
+        failed,
+        now.elapsed().as_millis()
+    );
+
+    if build.coverage {
+        let stats = match fs::read_to_string(coverage::STATS) {
+            Ok(stats) => stats,
+            Err(why) => {
+                response!(
+                    Response::Wrong(format!("can't read `{}`: {}", coverage::STATS, why)),
+                    Response::Note("is luacov installed?")
+                );
+
+                return build.fail(Failure::Io);
+            }
+        };
+
+        let files = coverage::map_stats(&coverage::read_stats(&stats));
+
+        for (file, coverage) in files.iter() {
+            println!(
+                "{:>6.1}% {:>4}/{:<4} {}",
+                coverage.percent(),
+                coverage.covered(),
+                coverage.lines.len(),
+                file
+            )
+        }
+
+        if write(Path::new(coverage::REPORT), &coverage::listing(&files)).is_err() {
+            return build.fail(Failure::Io);
+        }
+
+        log!(Normal, "Covered", "{}, see `{}`", plural(files.len(), "module"), coverage::REPORT)
+    }
+}
@@ -0,0 +1,12 @@
+pub mod triage;
+
+use super::build::*;
+use super::compiler::Generator;
+use super::error::{silently, Response};
+use super::ice;
+use super::lexer::Lexer;
+use super::parser::*;
+use super::source::Source;
+use super::visitor::*;
+
+pub use self::triage::*;
@@ -0,0 +1,244 @@
+use std::fmt;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::time::Instant;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Lexing,
+    Parsing,
+    Checking,
+    Generating,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Phase::Lexing => write!(f, "lexing"),
+            Phase::Parsing => write!(f, "parsing"),
+            Phase::Checking => write!(f, "checking"),
+            Phase::Generating => write!(f, "generating"),
+        }
+    }
+}
+
+// a panic of the compiler, and the phase it panicked in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crash {
+    pub phase: Phase,
+    pub message: String, // its first line, which says what went wrong
+}
+
+// a crash with as little of the program as still crashes the same way
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triage {
+    pub crash: Crash,
+    pub reproducer: String,
+    pub runs: usize, // how many times the compiler ran to find it
+}
+
+// the crash compiling `content` as `file` ends in, if it does, minimized: first by top level
+// statements, or lines when it doesn't parse, then by lines, halving how many go at a time
+pub fn triage(file: &str, content: &str) -> Option<Triage> {
+    // the panics looked for aren't news
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| ()));
+
+    let triaged = minimize(file, content);
+
+    panic::set_hook(hook);
+
+    triaged
+}
+
+fn minimize(file: &str, content: &str) -> Option<Triage> {
+    let found = crash(file, content)?;
+
+    let mut runs = 1;
+
+    let mut same = |content: &str| {
+        runs += 1;
+        crash(file, content).as_ref() == Some(&found)
+    };
+
+    let reduced = reduce(statements(file, content), &mut same).concat();
+    let reduced = reduce(lines(&reduced), &mut same).concat();
+
+    Some(Triage {
+        crash: found,
+        reproducer: reduced,
+        runs,
+    })
+}
+
+// compiles `content` as `file` without writing or reporting anything, catching the compiler's
+// panic, if it panics
+pub fn crash(file: &str, content: &str) -> Option<Crash> {
+    compiled(file, content).err().flatten()
+}
+
+// the Lua `content` compiles to; or why not, its crash or nothing for diagnostics
+fn compiled(file: &str, content: &str) -> Result<String, Option<Crash>> {
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let root = match Path::new(file).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+        _ => ".".to_string(),
+    };
+
+    let tokens = caught(Phase::Lexing, || {
+        Lexer::default(content.chars().collect(), &source).collect::<Result<Vec<_>, _>>()
+    })?;
+
+    let ast = caught(Phase::Parsing, || Parser::new(tokens, &source).parse())?;
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), root);
+
+    caught(Phase::Checking, || visitor.visit())?;
+
+    caught(Phase::Generating, || {
+        Ok(Generator::new(&source, &visitor).generate(&ast))
+    })
+}
+
+// runs `f` quietly, catching it panicking in `phase`
+fn caught<T>(phase: Phase, f: impl FnOnce() -> Result<T, ()>) -> Result<T, Option<Crash>> {
+    match silently(|| panic::catch_unwind(AssertUnwindSafe(f))) {
+        Ok(result) => result.map_err(|_| None),
+        Err(payload) => Err(Some(Crash {
+            phase,
+            message: ice::message(&*payload)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        })),
+    }
+}
+
+// `content` split before each top level statement, or into lines when it doesn't parse
+fn statements(file: &str, content: &str) -> Vec<String> {
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let ast = caught(Phase::Parsing, || {
+        let tokens =
+            Lexer::default(content.chars().collect(), &source).collect::<Result<Vec<_>, _>>()?;
+
+        Parser::new(tokens, &source).parse()
+    });
+
+    let ast = match ast {
+        Ok(ast) => ast,
+        Err(_) => return lines(content),
+    };
+
+    let mut units: Vec<String> = vec![String::new()];
+
+    for (number, line) in lines(content).into_iter().enumerate() {
+        let starts = ast
+            .iter()
+            .any(|statement| (statement.pos.0).0 == number + 1);
+
+        if starts && !units.last().is_some_and(|unit| unit.is_empty()) {
+            units.push(String::new())
+        }
+
+        units.last_mut().unwrap().push_str(&line)
+    }
+
+    units
+}
+
+// the lines of `content`, each with its line break
+fn lines(content: &str) -> Vec<String> {
+    content.split_inclusive('\n').map(String::from).collect()
+}
+
+// `units` without those `same` still holds without, leaving out half of them at a time, then a
+// quarter, down to one at a time
+fn reduce(mut units: Vec<String>, same: &mut impl FnMut(&str) -> bool) -> Vec<String> {
+    let mut chunk = (units.len() / 2).max(1);
+
+    loop {
+        let mut at = 0;
+
+        while at < units.len() {
+            let end = (at + chunk).min(units.len());
+
+            let without = units[..at]
+                .iter()
+                .chain(&units[end..])
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if same(&without.concat()) {
+                units = without
+            } else {
+                at = end
+            }
+        }
+
+        if chunk == 1 {
+            return units;
+        }
+
+        chunk /= 2
+    }
+}
+
+// `wu triage`, the phase a crashing file crashes the compiler in, and as little of it as still
+// crashes it that way
+pub fn triage_path(file: &str, build: &mut Build) {
+    let now = Instant::now();
+
+    if file.is_empty() {
+        response!(Response::Wrong("expected `wu triage <file>`"));
+
+        return build.fail(Failure::Usage);
+    }
+
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(why) => {
+            response!(Response::Wrong(format!("can't read `{}`: {}", file, why)));
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let triaged = match triage::triage(file, &content) {
+        Some(triaged) => triaged,
+        None => {
+            response!(Response::Weird(format!("`{}` doesn't crash the compiler", file)));
+
+            return;
+        }
+    };
+
+    log!(
+        Normal,
+        "Crashed",
+        "while {}: {}",
+        triaged.crash.phase,
+        triaged.crash.message
+    );
+
+    log!(
+        Normal,
+        "Minimized",
+        "{} to {} ({}, {}ms)",
+        plural(content.lines().count(), "line"),
+        plural(triaged.reproducer.lines().count(), "line"),
+        plural(triaged.runs, "run"),
+        now.elapsed().as_millis()
+    );
+
+    print!("{}", triaged.reproducer);
+
+    if !triaged.reproducer.ends_with('\n') {
+        println!()
+    }
+}
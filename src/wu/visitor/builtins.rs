@@ -0,0 +1,130 @@
+use std::rc::Rc;
+
+use super::*;
+
+// the methods arrays, strings, tuples, optionals and numbers have without being structs, e.g.
+// `xs push(x)` or `name unwrap_or("?")`, and those of the number types, e.g. `int parse(s)`. They
+// only exist called, and the generator writes them out as lua
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    Len(Option<usize>), // known before running for fixed size arrays
+    Count(usize),       // the values in a tuple
+    Push,
+    Pop,
+    Insert,
+    Remove,
+    UnwrapOr(bool), // whether what's wrapped can be `false`, which lua's `or` takes for `nil`
+    IsSome,
+    IsNone,
+    ParseInt, // `int parse`, nothing for what isn't a whole number
+    ParseFloat,
+    ToStr, // `x to_str(2)`, with that many decimals
+    CheckedAdd, // nothing where the result doesn't fit an `int` of the target
+    CheckedSub,
+    CheckedMul,
+    Contains, // whether a value of a `@flags` enum has every flag of another
+}
+
+impl Builtin {
+    // the builtin called `name` on values of type `receiver`
+    pub fn find(receiver: &TypeNode, name: &str) -> Option<Builtin> {
+        use self::Builtin::*;
+
+        let builtin = match (receiver, name) {
+            (TypeNode::Array(_, len), "len") => Len(*len),
+            (TypeNode::Tuple(ref content), "len") => Count(content.len()),
+            (TypeNode::Str, "len") => Len(None),
+            (TypeNode::Array(..), "push") => Push,
+            (TypeNode::Array(..), "pop") => Pop,
+            (TypeNode::Array(..), "insert") => Insert,
+            (TypeNode::Array(..), "remove") => Remove,
+            (TypeNode::Optional(ref inner), "unwrap_or") => UnwrapOr(can_be_false(inner)),
+            (TypeNode::Optional(_), "is_some") => IsSome,
+            (TypeNode::Optional(_), "is_none") => IsNone,
+            (TypeNode::Float, "to_str") => ToStr,
+            (TypeNode::Int, "checked_add") => CheckedAdd,
+            (TypeNode::Int, "checked_sub") => CheckedSub,
+            (TypeNode::Int, "checked_mul") => CheckedMul,
+            (TypeNode::Enum(.., true), "contains") => Contains,
+            _ => return None,
+        };
+
+        Some(builtin)
+    }
+
+    // the builtin called `name` on `receiver`; an enum itself has only its variants
+    pub fn of(receiver: &Type, name: &str) -> Option<Builtin> {
+        match receiver.node {
+            TypeNode::Enum(..) if receiver.mode.strong_cmp(&TypeMode::Undeclared) => None,
+            ref node => Builtin::find(node, name),
+        }
+    }
+
+    // the builtin called `name` on the type called `ty`, e.g. `int parse`
+    pub fn find_static(ty: &str, name: &str) -> Option<Builtin> {
+        match (ty, name) {
+            ("int", "parse") => Some(Builtin::ParseInt),
+            ("float", "parse") => Some(Builtin::ParseFloat),
+            _ => None,
+        }
+    }
+
+    // its type as a function, on `receiver`
+    pub fn signature(self, receiver: &TypeNode) -> Type {
+        use self::Builtin::*;
+
+        let element = match *receiver {
+            TypeNode::Array(ref t, _) => (**t).clone(),
+            TypeNode::Optional(ref t) => Type::from((**t).clone()),
+            _ => Type::from(TypeNode::Any),
+        };
+
+        let optional = Type::from(TypeNode::Optional(Rc::new(element.node.clone())));
+
+        let int = Type::from(TypeNode::Int);
+        let nil = Type::from(TypeNode::Nil);
+        let str = Type::from(TypeNode::Str);
+
+        let parsed = |number| Type::from(TypeNode::Optional(Rc::new(number)));
+
+        let (params, returns) = match self {
+            Len(_) | Count(_) => (vec![], int),
+            Push => (vec![element], nil),
+            Pop => (vec![], optional),
+            Insert => (vec![int, element], nil),
+            Remove => (vec![int], optional),
+            UnwrapOr(_) => (vec![element.clone()], element),
+            IsSome | IsNone => (vec![], Type::from(TypeNode::Bool)),
+            ParseInt => (vec![str], parsed(TypeNode::Int)),
+            ParseFloat => (vec![str], parsed(TypeNode::Float)),
+            ToStr => (vec![int], str),
+            CheckedAdd | CheckedSub | CheckedMul => (vec![int], parsed(TypeNode::Int)),
+            Contains => (vec![Type::from(receiver.clone())], Type::from(TypeNode::Bool)),
+        };
+
+        Type::function(params, returns, false)
+    }
+
+    // whether calling it does anything but give back a value
+    pub fn changes(self) -> bool {
+        matches!(self, Builtin::Push | Builtin::Pop | Builtin::Insert | Builtin::Remove)
+    }
+
+    // what it is called on, in messages
+    pub fn receiver_name(receiver: &TypeNode) -> &'static str {
+        match *receiver {
+            TypeNode::Array(..) => "array",
+            TypeNode::Tuple(_) => "tuple",
+            TypeNode::Str => "string",
+            TypeNode::Int => "int",
+            TypeNode::Float => "float",
+            TypeNode::Enum(..) => "flags",
+            _ => "optional",
+        }
+    }
+}
+
+// whether a value of type `t` can be `false`, so `or` and `and` can't tell it from `nil`
+pub fn can_be_false(t: &TypeNode) -> bool {
+    matches!(t, TypeNode::Bool | TypeNode::Any)
+}
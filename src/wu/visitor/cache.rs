@@ -0,0 +1,426 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use super::*;
+
+// the folder visited modules are kept in between runs, see `--module-cache`
+static CACHE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// bumped whenever what's written changes, so older caches are passed over
+const FORMAT: usize = 1;
+
+pub fn cache_modules(folder: &Path) {
+    *CACHE.lock().unwrap() = Some(folder.to_path_buf())
+}
+
+// a module as `store_module` wrote it for `key`, if it was, by this version of wu and with the
+// same flags. Whether its files are still the same is left to the caller
+pub fn load_module(key: &(PathBuf, String, bool)) -> Option<ImportedModule> {
+    let (file, header) = cache_file(key)?;
+    let content = fs::read_to_string(file).ok()?;
+
+    let mut reader = Reader(&content);
+
+    if reader.text()? != header {
+        return None;
+    }
+
+    let mut files = Vec::new();
+
+    for _ in 0..reader.number()? {
+        files.push((PathBuf::from(reader.text()?), reader.number()?))
+    }
+
+    let content = reader.names()?;
+
+    let mut implementations = HashMap::new();
+
+    for _ in 0..reader.number::<usize>()? {
+        implementations.insert(reader.text()?, reader.names()?);
+    }
+
+    Some(ImportedModule {
+        content,
+        implementations,
+        files,
+    })
+}
+
+// writes `imported` down for later runs, unless there's no cache or its types can't be written,
+// e.g. names of types no declaration was found for
+pub fn store_module(key: &(PathBuf, String, bool), imported: &ImportedModule) {
+    let (file, header) = match cache_file(key) {
+        Some(found) => found,
+        None => return,
+    };
+
+    let mut writer = Writer(String::new());
+
+    writer.text(&header);
+    writer.word(imported.files.len());
+
+    for (path, hashed) in imported.files.iter() {
+        writer.text(&path.display().to_string());
+        writer.word(hashed)
+    }
+
+    let written = writer.names(&imported.content).and_then(|_| {
+        writer.word(imported.implementations.len());
+
+        for (name, implemented) in imported.implementations.iter() {
+            writer.text(name);
+            writer.names(implemented)?
+        }
+
+        Some(())
+    });
+
+    if written.is_none() {
+        return;
+    }
+
+    // written whole and then moved in place, so no run reads half of it
+    let partial = file.with_extension(format!("{}.partial", process::id()));
+
+    let stored = fs::create_dir_all(file.parent().unwrap())
+        .and_then(|_| fs::write(&partial, &writer.0))
+        .and_then(|_| fs::rename(&partial, &file));
+
+    if stored.is_err() {
+        let _ = fs::remove_file(partial);
+    }
+}
+
+// where the module of `key` is kept, and what the file starts with: the key itself and all that
+// changes what visiting a module finds besides its files
+fn cache_file(key: &(PathBuf, String, bool)) -> Option<(PathBuf, String)> {
+    let folder = CACHE.lock().unwrap().clone()?;
+
+    let levels = Lint::ALL
+        .iter()
+        .map(|lint| format!("{:?}", lint.level()))
+        .collect::<Vec<_>>();
+
+    let header = format!(
+        "wu {} {} | {} {} {} | {} {} {} {} | {}",
+        env!("CARGO_PKG_VERSION"),
+        FORMAT,
+        key.0.display(),
+        key.1,
+        key.2,
+        enabled_cfgs().join(","),
+        has_wide_ints(),
+        is_hermetic(),
+        super::super::edition::edition(),
+        levels.join(","),
+    );
+
+    let header = format!("{} | {}", header, env::current_dir().ok()?.display());
+
+    let mut hasher = DefaultHasher::new();
+    header.hash(&mut hasher);
+
+    Some((folder.join(format!("{:016x}.module", hasher.finish())), header))
+}
+
+// what `store_module` writes: words split by spaces, and texts as their length and content
+struct Writer(String);
+
+impl Writer {
+    fn word(&mut self, word: impl Display) {
+        self.0.push_str(&format!("{} ", word))
+    }
+
+    fn text(&mut self, text: &str) {
+        self.0.push_str(&format!("{}:{} ", text.len(), text))
+    }
+
+    fn names(&mut self, names: &HashMap<String, Type>) -> Option<()> {
+        self.word(names.len());
+
+        for (name, t) in names.iter() {
+            self.text(name);
+            self.kind(t)?
+        }
+
+        Some(())
+    }
+
+    fn kinds(&mut self, types: &[Type]) -> Option<()> {
+        self.word(types.len());
+
+        types.iter().try_for_each(|t| self.kind(t))
+    }
+
+    fn kind(&mut self, t: &Type) -> Option<()> {
+        use self::TypeMode::*;
+
+        match t.mode {
+            Undeclared => self.word("u"),
+            Immutable => self.word("i"),
+            Optional => self.word("o"),
+            Implemented => self.word("m"),
+            Regular => self.word("r"),
+            Splat(None) => self.word("s"),
+            Splat(Some(len)) => self.word(format!("s{}", len)),
+            Unwrap(len) => self.word(format!("w{}", len)),
+        }
+
+        self.node(&t.node)
+    }
+
+    fn node(&mut self, node: &TypeNode) -> Option<()> {
+        use self::TypeNode::*;
+
+        match *node {
+            Int => self.word("int"),
+            Float => self.word("float"),
+            Bool => self.word("bool"),
+            Str => self.word("str"),
+            Any => self.word("any"),
+            Char => self.word("char"),
+            Nil => self.word("nil"),
+            This => self.word("this"),
+
+            // a name left to look up, which means nothing outside of the module
+            Id(_) => return None,
+
+            Array(ref t, len) => {
+                self.word("array");
+                self.kind(t)?;
+                self.word(len.map_or("-".to_string(), |len| len.to_string()))
+            }
+
+            // of the function itself, only its parameter names are ever looked at
+            Func(ref params, ref returns, ref function, is_method) => {
+                self.word("fun");
+                self.kinds(params)?;
+                self.kind(returns)?;
+
+                match function.as_deref() {
+                    None => self.word("-"),
+
+                    Some(ExpressionNode::Function(ref named, ..)) => {
+                        self.word(named.len());
+
+                        for (name, _) in named.iter() {
+                            self.text(name)
+                        }
+                    }
+
+                    Some(_) => return None,
+                }
+
+                self.word(is_method)
+            }
+
+            Module(ref content, is_foreign) => {
+                self.word("module");
+                self.word(is_foreign);
+                self.names(content)?
+            }
+
+            Struct(ref name, ref content, ref id) => {
+                self.word("struct");
+                self.text(name);
+                self.text(id);
+                self.names(content)?
+            }
+
+            Trait(ref name, ref content) => {
+                self.word("trait");
+                self.text(name);
+                self.names(content)?
+            }
+
+            Enum(ref name, ref variants, ref id, flags) => {
+                self.word("enum");
+                self.text(name);
+                self.text(id);
+                self.word(flags);
+                self.word(variants.len());
+
+                for (variant, carried) in variants.iter() {
+                    self.text(variant);
+                    self.kinds(carried)?
+                }
+            }
+
+            Optional(ref inner) => {
+                self.word("optional");
+                self.node(inner)?
+            }
+
+            Tuple(ref content) => {
+                self.word("tuple");
+                self.kinds(content)?
+            }
+        }
+
+        Some(())
+    }
+}
+
+// reads back what `Writer` wrote, giving nothing for anything else
+struct Reader<'r>(&'r str);
+
+impl<'r> Reader<'r> {
+    fn word(&mut self) -> Option<&'r str> {
+        let (word, rest) = self.0.split_once(' ')?;
+
+        self.0 = rest;
+
+        Some(word)
+    }
+
+    fn number<T: FromStr>(&mut self) -> Option<T> {
+        self.word()?.parse().ok()
+    }
+
+    fn text(&mut self) -> Option<String> {
+        let (len, rest) = self.0.split_once(':')?;
+        let len = len.parse::<usize>().ok()?;
+
+        let text = rest.get(..len)?;
+
+        self.0 = rest.get(len..)?.strip_prefix(' ')?;
+
+        Some(text.to_string())
+    }
+
+    fn names(&mut self) -> Option<HashMap<String, Type>> {
+        let mut names = HashMap::new();
+
+        for _ in 0..self.number::<usize>()? {
+            names.insert(self.text()?, self.kind()?);
+        }
+
+        Some(names)
+    }
+
+    fn kinds(&mut self) -> Option<Vec<Type>> {
+        (0..self.number::<usize>()?).map(|_| self.kind()).collect()
+    }
+
+    fn kind(&mut self) -> Option<Type> {
+        let mode = match self.word()? {
+            "u" => TypeMode::Undeclared,
+            "i" => TypeMode::Immutable,
+            "o" => TypeMode::Optional,
+            "m" => TypeMode::Implemented,
+            "r" => TypeMode::Regular,
+            "s" => TypeMode::Splat(None),
+            word => match word.split_at(1) {
+                ("s", len) => TypeMode::Splat(Some(len.parse().ok()?)),
+                ("w", len) => TypeMode::Unwrap(len.parse().ok()?),
+                _ => return None,
+            },
+        };
+
+        Some(Type::new(self.node()?, mode))
+    }
+
+    fn node(&mut self) -> Option<TypeNode> {
+        use self::TypeNode::*;
+
+        let node = match self.word()? {
+            "int" => Int,
+            "float" => Float,
+            "bool" => Bool,
+            "str" => Str,
+            "any" => Any,
+            "char" => Char,
+            "nil" => Nil,
+            "this" => This,
+
+            "array" => {
+                let t = self.kind()?;
+
+                let len = match self.word()? {
+                    "-" => None,
+                    len => Some(len.parse().ok()?),
+                };
+
+                Array(Rc::new(t), len)
+            }
+
+            "fun" => {
+                let params = self.kinds()?;
+                let returns = self.kind()?;
+
+                let function = match self.word()? {
+                    "-" => None,
+
+                    count => {
+                        let mut named = Vec::new();
+
+                        for i in 0..count.parse::<usize>().ok()? {
+                            let t = params.get(i).cloned().unwrap_or_else(|| Type::from(Any));
+
+                            named.push((self.text()?, t))
+                        }
+
+                        let nowhere = Pos((0, Arc::from("")), (0, 0));
+                        let body = Expression::new(ExpressionNode::Empty, nowhere);
+
+                        Some(Rc::new(ExpressionNode::Function(
+                            named,
+                            returns.clone(),
+                            Rc::new(body),
+                            false,
+                        )))
+                    }
+                };
+
+                Func(params, Rc::new(returns), function, self.word()?.parse().ok()?)
+            }
+
+            "module" => {
+                let is_foreign = self.word()?.parse().ok()?;
+
+                Module(Rc::new(self.names()?), is_foreign)
+            }
+
+            "struct" => {
+                let (name, id) = (self.text()?, self.text()?);
+
+                Struct(name, Rc::new(self.names()?), id)
+            }
+
+            "trait" => {
+                let name = self.text()?;
+
+                Trait(name, Rc::new(self.names()?))
+            }
+
+            "enum" => {
+                let (name, id) = (self.text()?, self.text()?);
+                let flags = self.word()?.parse().ok()?;
+
+                let mut variants = Vec::new();
+
+                for _ in 0..self.number::<usize>()? {
+                    variants.push((self.text()?, self.kinds()?))
+                }
+
+                Enum(name, Rc::new(variants), id, flags)
+            }
+
+            "optional" => Optional(Rc::new(self.node()?)),
+            "tuple" => Tuple(self.kinds()?),
+
+            _ => return None,
+        };
+
+        Some(node)
+    }
+}
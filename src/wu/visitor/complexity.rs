@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::*;
+
+// the sizes functions are warned about beyond, 0 for no limit
+static MAX_LINES: AtomicUsize = AtomicUsize::new(0);
+static MAX_NESTING: AtomicUsize = AtomicUsize::new(0);
+static MAX_PARAMS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Lines,   // from `fun` to the last line of the body
+    Nesting, // of `if`, `while` and `for` in each other
+    Params,
+}
+
+impl Limit {
+    pub const ALL: [Limit; 3] = [Limit::Lines, Limit::Nesting, Limit::Params];
+
+    pub fn flag(&self) -> &'static str {
+        match *self {
+            Limit::Lines => "--max-lines",
+            Limit::Nesting => "--max-nesting",
+            Limit::Params => "--max-params",
+        }
+    }
+
+    fn max(&self) -> &'static AtomicUsize {
+        match *self {
+            Limit::Lines => &MAX_LINES,
+            Limit::Nesting => &MAX_NESTING,
+            Limit::Params => &MAX_PARAMS,
+        }
+    }
+}
+
+pub fn set_limit(limit: Limit, max: usize) {
+    limit.max().store(max, Ordering::Relaxed)
+}
+
+pub fn limit(limit: Limit) -> Option<usize> {
+    match limit.max().load(Ordering::Relaxed) {
+        0 => None,
+        max => Some(max),
+    }
+}
+
+// how big a function turned out, as far as the limits go
+#[derive(Debug, Clone)]
+pub struct Measure {
+    pub first: usize, // line of `fun`
+    pub last: usize,
+    pub deepest: Option<(usize, Pos)>, // the most nested `if`, `while` or `for`, and how deep
+
+    nesting: usize,
+}
+
+impl Measure {
+    pub fn lines(&self) -> usize {
+        self.last - self.first + 1
+    }
+}
+
+// the measures of the functions being visited, innermost last; only kept when a limit is set
+#[derive(Debug, Clone, Default)]
+pub struct Complexity(Rc<RefCell<Vec<Measure>>>);
+
+// a level of nesting in the innermost function, given back when dropped
+pub struct Nested(Rc<RefCell<Vec<Measure>>>);
+
+impl Complexity {
+    pub fn enter(&self, function: &Pos) {
+        if Limit::ALL.iter().all(|kind| limit(*kind).is_none()) {
+            return;
+        }
+
+        let line = (function.0).0;
+
+        self.0.borrow_mut().push(Measure {
+            first: line,
+            last: line,
+            deepest: None,
+            nesting: 0,
+        })
+    }
+
+    pub fn leave(&self) -> Option<Measure> {
+        self.0.borrow_mut().pop()
+    }
+
+    // `pos` is part of the innermost function; with `nests`, one level deeper than what it's in
+    pub fn see(&self, pos: &Pos, nests: bool) -> Option<Nested> {
+        let mut measures = self.0.borrow_mut();
+        let measure = measures.last_mut()?;
+
+        measure.last = measure.last.max((pos.0).0);
+
+        if !nests {
+            return None;
+        }
+
+        measure.nesting += 1;
+
+        if measure
+            .deepest
+            .as_ref()
+            .is_none_or(|(deepest, _)| measure.nesting > *deepest)
+        {
+            measure.deepest = Some((measure.nesting, pos.clone()))
+        }
+
+        Some(Nested(self.0.clone()))
+    }
+}
+
+impl Drop for Nested {
+    fn drop(&mut self) {
+        if let Some(measure) = self.0.borrow_mut().last_mut() {
+            measure.nesting -= 1
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use super::*;
+
+// arithmetic on value structs, those holding only numbers like `Vec2`: `+`, `-`, `*` and `/` of
+// two of the same struct, and `*` and `/` scaling one by a number, member by member
+#[derive(Debug, Clone)]
+pub struct Componentwise {
+    pub members: Vec<String>, // what every operand is made of, in order
+    pub left: bool,           // whether the left side is the struct, rather than a number
+    pub right: bool,
+}
+
+impl Componentwise {
+    // `a op b` as done member by member, and the struct it gives, when it is that
+    pub fn of(a: &TypeNode, op: &Operator, b: &TypeNode) -> Option<(Self, TypeNode)> {
+        use self::Operator::*;
+
+        let number = |t: &TypeNode| matches!(t, TypeNode::Float | TypeNode::Int);
+
+        let (members, left, right, made) = match (members(a), members(b)) {
+            (Some(members), Some(_)) if a == b && matches!(op, Add | Sub | Mul | Div) => {
+                (members, true, true, a)
+            }
+            (Some(members), None) if number(b) && matches!(op, Mul | Div) => {
+                (members, true, false, a)
+            }
+            (None, Some(members)) if number(a) && matches!(op, Mul) => (members, false, true, b),
+            _ => return None,
+        };
+
+        Some((
+            Componentwise {
+                members,
+                left,
+                right,
+            },
+            made.clone(),
+        ))
+    }
+}
+
+// the members of a value struct, leaving out its methods and constants
+fn members(t: &TypeNode) -> Option<Vec<String>> {
+    let content = match *t {
+        TypeNode::Struct(_, ref content, _) => content,
+        _ => return None,
+    };
+
+    let mut members = Vec::new();
+
+    for (name, member) in content.iter() {
+        if member.mode.strong_cmp(&TypeMode::Implemented) {
+            continue;
+        }
+
+        match member.node {
+            TypeNode::Float | TypeNode::Int => members.push(name.clone()),
+            _ => return None,
+        }
+    }
+
+    members.sort();
+
+    if members.is_empty() {
+        None
+    } else {
+        Some(members)
+    }
+}
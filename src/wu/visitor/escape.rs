@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+
+use super::*;
+
+// which tuples escape: a tuple is as many lua values, which a variable, argument, field or
+// element can't hold, so only there it is made a table, and taken apart again where its values
+// are wanted one by one; a tuple returned and destructured right away never allocates
+#[derive(Debug, Clone, Default)]
+pub struct Escapes {
+    pub packed: HashSet<Pos>, // tuples made where they're kept as one value, made tables
+    pub unpacked: HashSet<Pos>, // kept tuples whose values are taken one by one, unpacked
+}
+
+impl Escapes {
+    // `value` of type `t` is kept as one value
+    pub fn keep(&mut self, value: &Expression, t: &Type) {
+        if is_tuple(t) && made(&value.node) {
+            self.packed.insert(value.pos.clone());
+        }
+    }
+
+    // the values of `value` of type `t` are taken one by one, returned or destructured
+    pub fn spread(&mut self, value: &Expression, t: &Type) {
+        if is_tuple(t) && kept(&value.node) {
+            self.unpacked.insert(value.pos.clone());
+        }
+    }
+
+    pub fn extend(&mut self, other: Escapes) {
+        self.packed.extend(other.packed);
+        self.unpacked.extend(other.unpacked);
+    }
+}
+
+fn is_tuple(t: &Type) -> bool {
+    matches!(
+        (&t.node, &t.mode),
+        (TypeNode::Tuple(_), TypeMode::Regular | TypeMode::Immutable)
+    )
+}
+
+// the expressions giving a tuple as lua values
+pub fn made(node: &ExpressionNode) -> bool {
+    matches!(node, ExpressionNode::Tuple(_) | ExpressionNode::Call(..))
+}
+
+// the expressions reading a tuple back from where it was kept
+pub fn kept(node: &ExpressionNode) -> bool {
+    matches!(
+        node,
+        ExpressionNode::Identifier(_) | ExpressionNode::Index(..)
+    )
+}
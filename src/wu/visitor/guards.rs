@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+use super::*;
+
+// what's being visited around where the visitor is, innermost last; shared with the guards
+// that take their entries off again, so returning early can't leave any behind
+#[derive(Debug, Clone, Default)]
+pub struct Insides(Rc<RefCell<Vec<Inside>>>);
+
+// an entry of `Insides`, taken off with any entered after it when dropped
+#[must_use]
+pub struct Entered {
+    insides: Rc<RefCell<Vec<Inside>>>,
+    depth: usize,
+}
+
+impl Insides {
+    pub fn enter(&self, inside: Inside) -> Entered {
+        let mut insides = self.0.borrow_mut();
+        let depth = insides.len();
+
+        insides.push(inside);
+
+        Entered {
+            insides: self.0.clone(),
+            depth,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    pub fn contains(&self, inside: &Inside) -> bool {
+        self.0.borrow().contains(inside)
+    }
+
+    pub fn last(&self) -> Option<Inside> {
+        self.0.borrow().last().cloned()
+    }
+
+    // what `f` finds in the innermost entry it finds anything in
+    pub fn innermost<T>(&self, f: impl FnMut(&Inside) -> Option<T>) -> Option<T> {
+        self.0.borrow().iter().rev().find_map(f)
+    }
+
+    // swaps the innermost entry for `inside`, e.g. the struct being implemented for itself with
+    // one more method
+    pub fn replace_innermost(&self, inside: Inside) {
+        if let Some(last) = self.0.borrow_mut().last_mut() {
+            *last = inside
+        }
+    }
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        self.insides.borrow_mut().truncate(self.depth)
+    }
+}
+
+// a scope of the visitor's symtab, left when dropped; what's visited in it is visited through
+// the guard
+pub struct Scope<'s, 'v> {
+    visitor: &'s mut Visitor<'v>,
+}
+
+impl<'v> Visitor<'v> {
+    pub fn scope(&mut self) -> Scope<'_, 'v> {
+        self.symtab.push();
+
+        Scope { visitor: self }
+    }
+
+    // a scope starting out with `table`, e.g. a function's parameters
+    pub fn scope_with(&mut self, table: HashMap<String, Type>) -> Scope<'_, 'v> {
+        self.symtab.push_with(table);
+
+        Scope { visitor: self }
+    }
+
+    // the scope left last, entered once more
+    pub fn reentered(&mut self) -> Scope<'_, 'v> {
+        self.symtab.reenter();
+
+        Scope { visitor: self }
+    }
+}
+
+impl<'v> Deref for Scope<'_, 'v> {
+    type Target = Visitor<'v>;
+
+    fn deref(&self) -> &Self::Target {
+        self.visitor
+    }
+}
+
+impl DerefMut for Scope<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.visitor
+    }
+}
+
+impl Drop for Scope<'_, '_> {
+    fn drop(&mut self) {
+        self.visitor.symtab.pop()
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::*;
+
+// what `--inline` needs from the visitor: calls of functions whose whole body is one pure
+// expression over their parameters, e.g. `area(2, 3)` of `area: fun(w: int, h: int) { w * h }`
+#[derive(Debug, Clone, Default)]
+pub struct Inliner {
+    pub calls: HashMap<Pos, Expression>, // the body standing in for each call, arguments filled in
+
+    candidates: HashMap<Pos, (String, Pos)>, // called name and function body of every call
+    declared: HashMap<Pos, String>,          // function bodies by the name they are declared as
+    reassigned: HashSet<String>,
+}
+
+impl Inliner {
+    pub fn declare(&mut self, name: &str, function: &ExpressionNode) {
+        if let ExpressionNode::Function(_, _, ref body, _) = *function {
+            self.declared.insert(body.pos.clone(), name.to_string());
+        }
+    }
+
+    pub fn reassign(&mut self, left: &Expression) {
+        if let ExpressionNode::Identifier(ref name) = left.node {
+            self.reassigned.insert(name.clone());
+        }
+    }
+
+    pub fn offer(
+        &mut self,
+        call: &Pos,
+        called: &Expression,
+        function: &ExpressionNode,
+        args: &[Expression],
+    ) {
+        let name = match called.node {
+            ExpressionNode::Identifier(ref name) => name,
+            _ => return,
+        };
+
+        let (params, body) = match *function {
+            ExpressionNode::Function(ref params, _, ref body, false) => (params, body),
+            _ => return,
+        };
+
+        let value = match single_expression(body) {
+            Some(value) => value,
+            None => return,
+        };
+
+        let names = params.iter().map(|p| p.0.as_str()).collect::<Vec<_>>();
+
+        let splat = params
+            .iter()
+            .any(|p| matches!(p.1.mode, TypeMode::Splat(_)));
+
+        if splat || args.len() != params.len() || !is_pure(value, &names) {
+            return;
+        }
+
+        // arguments are evaluated once and in order at a call, so only plain values may be
+        // copied around or dropped; one argument used exactly once may be anything
+        let mut involved = args
+            .iter()
+            .zip(names.iter())
+            .filter(|(arg, _)| !is_plain(arg));
+
+        if let Some((_, name)) = involved.next() {
+            if involved.next().is_some() || uses(value, name) != 1 || short_circuits(value) {
+                return;
+            }
+        }
+
+        let filled = names
+            .iter()
+            .map(|name| name.to_string())
+            .zip(args.iter().cloned())
+            .collect::<HashMap<_, _>>();
+
+        self.calls.insert(call.clone(), substitute(value, &filled));
+        self.candidates
+            .insert(call.clone(), (name.clone(), body.pos.clone()));
+    }
+
+    // drops the calls of functions that may not be what they were declared as by the time
+    // they are called
+    pub fn finish(&mut self) {
+        let declared = &self.declared;
+        let reassigned = &self.reassigned;
+
+        let keep = |(name, body): &(String, Pos)| {
+            !reassigned.contains(name)
+                && declared
+                    .get(body)
+                    .is_some_and(|declared| !reassigned.contains(declared))
+        };
+
+        let dropped = self
+            .candidates
+            .iter()
+            .filter(|(_, candidate)| !keep(candidate))
+            .map(|(call, _)| call.clone())
+            .collect::<Vec<_>>();
+
+        for call in dropped {
+            self.calls.remove(&call);
+        }
+
+        self.candidates.clear()
+    }
+
+    pub fn extend(&mut self, other: Inliner) {
+        self.calls.extend(other.calls)
+    }
+}
+
+// `x * 2` out of `{ x * 2 }` or `{ return x * 2 }`
+fn single_expression(body: &Expression) -> Option<&Expression> {
+    match body.node {
+        ExpressionNode::Block(ref content) if content.len() == 1 => match content[0].node {
+            StatementNode::Expression(ref value) => Some(value),
+            StatementNode::Return(Some(ref value)) => Some(&**value),
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+fn is_plain(arg: &Expression) -> bool {
+    use self::ExpressionNode::*;
+
+    matches!(
+        arg.node,
+        Int(_) | Float(_) | Str(_) | Char(_) | Bool(_) | Identifier(_)
+    )
+}
+
+// made of literals, the parameters and operators that call nothing
+fn is_pure(value: &Expression, params: &[&str]) -> bool {
+    use self::ExpressionNode::*;
+
+    match value.node {
+        Int(_) | Float(_) | Str(_) | Char(_) | Bool(_) => true,
+        Identifier(ref name) => params.contains(&name.as_str()),
+
+        Neg(ref inner) | Not(ref inner) | Cast(ref inner, _) => is_pure(inner, params),
+
+        Binary(ref left, ref op, ref right) => {
+            !matches!(op, Operator::PipeLeft | Operator::PipeRight)
+                && is_pure(left, params)
+                && is_pure(right, params)
+        }
+
+        _ => false,
+    }
+}
+
+fn uses(value: &Expression, name: &str) -> usize {
+    use self::ExpressionNode::*;
+
+    match value.node {
+        Identifier(ref n) => (n == name) as usize,
+        Neg(ref inner) | Not(ref inner) | Cast(ref inner, _) => uses(inner, name),
+        Binary(ref left, _, ref right) => uses(left, name) + uses(right, name),
+        _ => 0,
+    }
+}
+
+// whether some of `value` may be skipped, along with the argument inside it
+fn short_circuits(value: &Expression) -> bool {
+    use self::ExpressionNode::*;
+
+    match value.node {
+        Neg(ref inner) | Not(ref inner) | Cast(ref inner, _) => short_circuits(inner),
+        Binary(ref left, ref op, ref right) => {
+            matches!(op, Operator::And | Operator::Or)
+                || short_circuits(left)
+                || short_circuits(right)
+        }
+        _ => false,
+    }
+}
+
+// `value` with the parameters replaced by their arguments, keeping the positions of the body so
+// what the visitor noted about it still applies
+fn substitute(value: &Expression, filled: &HashMap<String, Expression>) -> Expression {
+    use self::ExpressionNode::*;
+
+    let node = match value.node {
+        Identifier(ref name) => match filled.get(name) {
+            Some(arg) => arg.node.clone(),
+            None => value.node.clone(),
+        },
+
+        Neg(ref inner) => Neg(Rc::new(substitute(inner, filled))),
+        Not(ref inner) => Not(Rc::new(substitute(inner, filled))),
+        Cast(ref inner, ref t) => Cast(Rc::new(substitute(inner, filled)), t.clone()),
+
+        Binary(ref left, ref op, ref right) => Binary(
+            Rc::new(substitute(left, filled)),
+            op.clone(),
+            Rc::new(substitute(right, filled)),
+        ),
+
+        _ => value.node.clone(),
+    };
+
+    Expression::new(node, value.pos.clone())
+}
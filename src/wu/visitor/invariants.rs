@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::*;
+
+// whether visiting checks its own bookkeeping as it goes, see `--internal-checks`
+static INTERNAL_CHECKS: AtomicBool = AtomicBool::new(false);
+
+pub fn enable_internal_checks() {
+    INTERNAL_CHECKS.store(true, Ordering::Relaxed)
+}
+
+pub fn internal_checks() -> bool {
+    INTERNAL_CHECKS.load(Ordering::Relaxed)
+}
+
+// the state visiting a node has to leave as it found it, once it visited it without errors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Balance {
+    scope: usize,  // the symtab frame being declared into
+    inside: usize, // how many `Inside` are pushed
+}
+
+impl<'v> Visitor<'v> {
+    pub fn balance(&self) -> Balance {
+        Balance {
+            scope: self.symtab.current,
+            inside: self.inside.len(),
+        }
+    }
+
+    // an internal compiler error at `pos` when visiting the `what` there left the scopes or
+    // `inside` other than `before`
+    pub fn check_balance(&self, before: Balance, pos: &Pos, what: &str) {
+        let _processing = ice::processing(&self.source.file, pos);
+
+        let after = self.balance();
+
+        if after.scope != before.scope {
+            panic!(
+                "internal check failed: visiting the {} left scope {} entered, not scope {}",
+                what, after.scope, before.scope
+            )
+        }
+
+        if after.inside != before.inside {
+            panic!(
+                "internal check failed: visiting the {} left `inside` {} deep, not {}",
+                what, after.inside, before.inside
+            )
+        }
+    }
+
+    // an internal compiler error when the module exports as undeclared what isn't a struct or
+    // enum itself, which only their definitions are
+    pub fn check_module_content(&self) {
+        let mut names = self.module_content.keys().collect::<Vec<_>>();
+        names.sort();
+
+        for name in names {
+            let t = &self.module_content[name];
+
+            let declared = matches!(t.node, TypeNode::Struct(..) | TypeNode::Enum(..));
+
+            if t.mode.strong_cmp(&TypeMode::Undeclared) && !declared {
+                panic!(
+                    "internal check failed: `{}` exported as an undeclared `{}` in `{}`",
+                    name, t, self.source.file.0
+                )
+            }
+        }
+    }
+}
@@ -1,9 +1,39 @@
+pub mod builtins;
+pub mod cache;
+pub mod complexity;
+pub mod componentwise;
+pub mod escape;
+pub mod guards;
+pub mod inline;
+pub mod invariants;
+pub mod purity;
+pub mod resolve;
 pub mod symtab;
 pub mod visitor;
 
+use super::compiler::scope::{active_locals, used_by};
+use super::compiler::LOCALS;
+use super::error::{silenced, silently};
+use super::fix::{self, Exports, Fix};
+use super::ice;
 use super::lexer::*;
 use super::parser::*;
+use super::references::declared_span;
 use super::source::*;
+use super::stdlib::{self, embedded};
 
+pub use self::builtins::*;
+pub use self::cache::*;
+pub use self::complexity::*;
+pub use self::componentwise::*;
+pub use self::escape::*;
+pub use self::guards::*;
+pub use self::inline::*;
+pub use self::invariants::*;
+pub use self::purity::*;
+pub use self::resolve::*;
 pub use self::symtab::*;
 pub use self::visitor::*;
+
+pub use super::budget::*;
+pub use wu_syntax::types::*;
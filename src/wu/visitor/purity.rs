@@ -0,0 +1,262 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::*;
+
+// which calls are of pure functions: ones that call only pure functions, assign only their own
+// locals and can't fail or loop forever, so a call whose value goes unused can be left out, and
+// two calls with the same arguments give the same value
+#[derive(Debug, Clone, Default)]
+pub struct Purity {
+    pub calls: HashSet<Pos>, // calls of pure functions
+
+    callees: HashMap<Pos, (String, Pos)>, // called name and function body of every known call
+    bodies: HashMap<Pos, (Vec<String>, Rc<Expression>)>, // parameters and body of each callee
+    reassigned: HashSet<String>,
+}
+
+impl Purity {
+    pub fn call(&mut self, call: &Pos, called: &Expression, function: &ExpressionNode) {
+        let name = match called.node {
+            ExpressionNode::Identifier(ref name) => name,
+            _ => return,
+        };
+
+        if let ExpressionNode::Function(ref params, _, ref body, _) = *function {
+            let params = params.iter().map(|param| param.0.clone()).collect();
+
+            self.callees
+                .insert(call.clone(), (name.clone(), body.pos.clone()));
+            self.bodies
+                .entry(body.pos.clone())
+                .or_insert_with(|| (params, body.clone()));
+        }
+    }
+
+    pub fn reassign(&mut self, left: &Expression) {
+        if let ExpressionNode::Identifier(ref name) = left.node {
+            self.reassigned.insert(name.clone());
+        }
+    }
+
+    // a function is pure once everything it calls is, so a function calling itself never is,
+    // and can't recurse forever either
+    pub fn finish(&mut self) {
+        let mut made = HashMap::new();
+
+        for (pos, (params, body)) in self.bodies.iter() {
+            let mut locals = params.iter().cloned().collect();
+            let mut calls = Vec::new();
+
+            if effects(body, &mut locals, &mut calls) {
+                made.insert(pos.clone(), calls);
+            }
+        }
+
+        let mut pure = HashSet::new();
+
+        loop {
+            let found = made
+                .iter()
+                .filter(|(pos, _)| !pure.contains(*pos))
+                .filter(|(_, calls)| {
+                    calls.iter().all(|call| {
+                        self.callees.get(call).is_some_and(|(name, callee)| {
+                            !self.reassigned.contains(name) && pure.contains(callee)
+                        })
+                    })
+                })
+                .map(|(pos, _)| pos.clone())
+                .collect::<Vec<_>>();
+
+            if found.is_empty() {
+                break;
+            }
+
+            pure.extend(found)
+        }
+
+        self.calls = self
+            .callees
+            .iter()
+            .filter(|(_, (name, callee))| !self.reassigned.contains(name) && pure.contains(callee))
+            .map(|(call, _)| call.clone())
+            .collect()
+    }
+
+    pub fn extend(&mut self, other: Purity) {
+        self.callees.extend(other.callees);
+        self.reassigned.extend(other.reassigned);
+
+        for (pos, body) in other.bodies {
+            self.bodies.entry(pos).or_insert(body);
+        }
+    }
+}
+
+// whether `expression` has no effects of its own, collecting the calls it makes, which are pure
+// only when what they call is
+fn effects(expression: &Expression, locals: &mut HashSet<String>, calls: &mut Vec<Pos>) -> bool {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Int(_) | Float(_) | Str(_) | Char(_) | Bool(_) | Identifier(_) | Empty | EOF => true,
+
+        // making a closure does nothing until it is called
+        Function(..) => true,
+
+        Call(ref called, ref args) => {
+            calls.push(expression.pos.clone());
+
+            effects(called, locals, calls) && args.iter().all(|arg| effects(arg, locals, calls))
+        }
+
+        Binary(ref left, ref op, ref right) => {
+            !matches!(op, Operator::PipeLeft | Operator::PipeRight)
+                && effects(left, locals, calls)
+                && effects(right, locals, calls)
+        }
+
+        Neg(ref inner) | Not(ref inner) | Cast(ref inner, _) | UnwrapSplat(ref inner) => {
+            effects(inner, locals, calls)
+        }
+
+        Index(ref source, ref member, _) => {
+            effects(source, locals, calls) && effects(member, locals, calls)
+        }
+
+        Tuple(ref content) | Array(ref content) | Splat(ref content) => content
+            .iter()
+            .all(|element| effects(element, locals, calls)),
+
+        Initialization(ref name, ref fields) => {
+            effects(name, locals, calls)
+                && fields.iter().all(|field| effects(&field.1, locals, calls))
+        }
+
+        If(ref condition, ref body, ref elses) => {
+            effects(condition, locals, calls)
+                && effects(body, locals, calls)
+                && elses.iter().flatten().all(|(condition, body, _)| {
+                    condition
+                        .as_ref()
+                        .is_none_or(|condition| effects(condition, locals, calls))
+                        && effects(body, locals, calls)
+                })
+        }
+
+        For((ref iterator, ref second), ref body) => {
+            for name in Some(iterator).into_iter().chain(second.as_ref()) {
+                if let Identifier(ref name) = name.node {
+                    locals.insert(name.clone());
+                }
+            }
+
+            effects(iterator, locals, calls)
+                && second
+                    .as_ref()
+                    .is_none_or(|second| effects(second, locals, calls))
+                && effects(body, locals, calls)
+        }
+
+        Block(ref content) => content
+            .iter()
+            .all(|statement| statement_effects(statement, locals, calls)),
+
+        Match(ref subject, ref arms) => {
+            effects(subject, locals, calls)
+                && arms.iter().all(|(_, names, body, _)| {
+                    locals.extend(names.iter().cloned());
+
+                    effects(body, locals, calls)
+                })
+        }
+
+        // `x!` fails on nil, `while` may never end, and anything from lua may do anything
+        Unwrap(_) | While(..) | Module(..) | Extern(..) | ExternExpression(_) | Struct(..)
+        | Trait(..) | Enum(..) => false,
+    }
+}
+
+// only the function's own locals may be assigned, anything else is seen by others
+fn assignable(left: &Expression, locals: &HashSet<String>) -> bool {
+    matches!(left.node, ExpressionNode::Identifier(ref name) if locals.contains(name))
+}
+
+fn statement_effects(
+    statement: &Statement,
+    locals: &mut HashSet<String>,
+    calls: &mut Vec<Pos>,
+) -> bool {
+    use self::StatementNode::*;
+
+    match statement.node {
+        Expression(ref expression) => effects(expression, locals, calls),
+
+        Variable(_, ref name, ref right, _) => {
+            locals.insert(name.clone());
+
+            right
+                .as_ref()
+                .is_none_or(|right| effects(right, locals, calls))
+        }
+
+        SplatVariable(_, ref names, ref right, _) => {
+            locals.extend(names.iter().cloned());
+
+            right
+                .as_ref()
+                .is_none_or(|right| effects(right, locals, calls))
+        }
+
+        Assignment(ref left, ref right) => {
+            assignable(left, locals) && effects(right, locals, calls)
+        }
+
+        SplatAssignment(ref lefts, ref right) => {
+            lefts.iter().all(|left| assignable(left, locals)) && effects(right, locals, calls)
+        }
+
+        Return(ref value) => value
+            .as_ref()
+            .is_none_or(|value| effects(value, locals, calls)),
+
+        Break | Skip => true,
+
+        Implement(..) | Import(..) | ExternBlock(_) | Global(_) => false,
+    }
+}
+
+// whether leaving `expression` out changes nothing, given the calls known to be pure
+pub fn effect_free(expression: &Expression, pure: &HashSet<Pos>) -> bool {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+        Int(_) | Float(_) | Str(_) | Char(_) | Bool(_) | Identifier(_) | Function(..) => true,
+
+        Call(ref called, ref args) => {
+            pure.contains(&expression.pos)
+                && effect_free(called, pure)
+                && args.iter().all(|arg| effect_free(arg, pure))
+        }
+
+        Binary(ref left, ref op, ref right) => {
+            !matches!(op, Operator::PipeLeft | Operator::PipeRight)
+                && effect_free(left, pure)
+                && effect_free(right, pure)
+        }
+
+        Neg(ref inner) | Not(ref inner) | Cast(ref inner, _) => effect_free(inner, pure),
+        Index(ref source, ref member, _) => effect_free(source, pure) && effect_free(member, pure),
+
+        Tuple(ref content) | Array(ref content) => {
+            content.iter().all(|element| effect_free(element, pure))
+        }
+
+        Initialization(ref name, ref fields) => {
+            effect_free(name, pure) && fields.iter().all(|field| effect_free(&field.1, pure))
+        }
+
+        _ => false,
+    }
+}
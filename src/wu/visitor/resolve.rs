@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use super::*;
+
+// the resolution pass: before a block is checked, the types and functions it declares are put
+// in scope, so declarations can refer to each other whatever order they're written in. Until
+// checking reaches its declaration, a name may only be used where it isn't evaluated right away,
+// in types and in function bodies, which run later
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    // what the blocks being checked declare and where, innermost last; `None` once reached
+    blocks: Vec<HashMap<String, Option<Pos>>>,
+}
+
+impl Resolution {
+    pub fn enter(&mut self, declared: &[Declaration]) {
+        let names = declared
+            .iter()
+            .map(|declaration| (declaration.name.clone(), Some(declaration.pos.clone())))
+            .collect();
+
+        self.blocks.push(names)
+    }
+
+    // checking got to the statement declaring `name`, from here on it is an ordinary binding
+    pub fn reach(&mut self, name: &str) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.insert(name.to_string(), None);
+        }
+    }
+
+    pub fn leave(&mut self) {
+        self.blocks.pop();
+    }
+
+    // where `name` is declared, when that is further down than what is being checked
+    pub fn ahead(&self, name: &str) -> Option<&Pos> {
+        self.blocks
+            .iter()
+            .rev()
+            .find_map(|block| block.get(name))
+            .and_then(|pos| pos.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Declared {
+    Type, // structs and traits
+    Function,
+}
+
+#[derive(Debug, Clone)]
+pub struct Declaration<'a> {
+    pub name: &'a String,
+    pub kind: Declared,
+    pub right: &'a Expression,
+    pub pos: &'a Pos,
+}
+
+// what `block` declares that may be referred to before it is written
+pub fn declarations(block: &[Statement]) -> Vec<Declaration<'_>> {
+    let mut declared = Vec::new();
+
+    for statement in block {
+        if let StatementNode::Variable(_, ref name, Some(ref right), _) = statement.binding().node {
+            let kind = match right.node {
+                ExpressionNode::Struct(..)
+                | ExpressionNode::Trait(..)
+                | ExpressionNode::Enum(..) => Declared::Type,
+                ExpressionNode::Function(..) => Declared::Function,
+                _ => continue,
+            };
+
+            declared.push(Declaration {
+                name,
+                kind,
+                right,
+                pos: &statement.pos,
+            })
+        }
+    }
+
+    declared
+}
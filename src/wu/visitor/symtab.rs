@@ -1,36 +1,33 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::rc::Rc;
 
-use super::visitor::*;
+use super::*;
 
+pub type Table = Rc<RefCell<HashMap<String, Rc<Type>>>>;
+
+// one scope of bindings and the scope it is nested in. Types are kept interned, so the copies
+// handed out share everything they are made of
 #[derive(Debug, Clone)]
 pub struct Frame {
-    pub table: RefCell<HashMap<String, Type>>,
+    pub parent: Option<usize>,
+    pub table: Table,
 }
 
 impl Frame {
-    pub fn new() -> Self {
-        Frame {
-            table: RefCell::new(HashMap::new()),
-        }
-    }
-
-    pub fn from(table: HashMap<String, Type>) -> Self {
+    pub fn new(parent: Option<usize>) -> Self {
         Frame {
-            table: RefCell::new(table),
+            parent,
+            table: Rc::new(RefCell::new(HashMap::new())),
         }
     }
 
     pub fn get(&self, name: &String) -> Option<Type> {
-        if let Some(v) = self.table.borrow().get(name) {
-            Some(v.clone())
-        } else {
-            None
-        }
+        self.table.borrow().get(name).map(|t| (**t).clone())
     }
 
-    pub fn assign(&mut self, name: String, t: Type) {
-        self.table.borrow_mut().insert(name, t);
+    pub fn assign(&self, name: String, t: Type) {
+        self.table.borrow_mut().insert(name, intern(t));
     }
 
     #[allow(dead_code)]
@@ -44,20 +41,30 @@ impl Frame {
     }
 }
 
+// where in the scope tree a `SymTab` was, to look names up from there later, e.g. for completions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Snapshot(usize);
+
+// frames form a tree through their parents; entering and leaving a scope only moves `current`.
+// Frames left are dropped again unless a snapshot still points into them
 #[derive(Debug, Clone)]
 pub struct SymTab {
-    pub stack: Vec<Frame>, // active frames
-    pub last: Frame,       // last frame
+    pub frames: Vec<Frame>,
+    pub current: usize,
+    pub last: Table, // bindings of the scope left last
+    pinned: Cell<usize>, // frames up to here are kept for snapshots
 
     pub implementations: HashMap<String, HashMap<String, Type>>,
-    pub foreign_imports: HashMap<String, HashMap<String, Type>>,
+    pub foreign_imports: HashMap<String, Rc<HashMap<String, Type>>>,
 }
 
 impl SymTab {
     pub fn new() -> Self {
         SymTab {
-            stack: vec![Frame::new()],
-            last: Frame::new(),
+            frames: vec![Frame::new(None)],
+            current: 0,
+            last: Table::default(),
+            pinned: Cell::new(0),
 
             implementations: HashMap::new(),
             foreign_imports: HashMap::new(),
@@ -65,53 +72,160 @@ impl SymTab {
     }
 
     pub fn from(table: HashMap<String, Type>) -> Self {
-        SymTab {
-            stack: vec![Frame::from(table)],
-            last: Frame::new(),
+        let symtab = SymTab::new();
 
-            implementations: HashMap::new(),
-            foreign_imports: HashMap::new(),
+        for (name, t) in table {
+            symtab.frames[0].assign(name, t)
         }
+
+        symtab
+    }
+
+    // the globals every module starts out with
+    pub fn prelude() -> Self {
+        let mut symtab = SymTab::new();
+
+        let splat_any = Type::new(TypeNode::Any, TypeMode::Splat(None));
+
+        symtab.assign_str(
+            "print",
+            Type::function(vec![splat_any.clone()], Type::from(TypeNode::Nil), false),
+        );
+
+        symtab.assign_str(
+            "ipairs",
+            Type::function(vec![splat_any.clone()], splat_any.clone(), false),
+        );
+
+        symtab.assign_str(
+            "pairs",
+            Type::function(vec![splat_any.clone()], splat_any, false),
+        );
+
+        symtab
     }
 
     pub fn assign(&mut self, name: String, t: Type) {
-        self.current_frame_mut().assign(name, t)
+        self.frames[self.current].assign(name, t)
     }
 
     pub fn assign_str(&mut self, name: &str, t: Type) {
-        self.current_frame_mut().assign(name.to_string(), t)
+        self.assign(name.to_string(), t)
     }
 
     pub fn fetch(&self, name: &String) -> Option<Type> {
-        let mut offset = self.stack.len() - 1;
+        self.fetch_from(self.current, name)
+    }
 
-        loop {
-            if let Some(t) = self.stack[offset].get(name) {
-                return Some(t);
-            } else {
-                if offset == 0 {
-                    return None;
-                }
+    fn fetch_from(&self, scope: usize, name: &String) -> Option<Type> {
+        let mut scope = Some(scope);
 
-                offset -= 1;
+        while let Some(at) = scope {
+            if let Some(t) = self.frames[at].get(name) {
+                return Some(t);
             }
+
+            scope = self.frames[at].parent
         }
+
+        None
     }
 
-    pub fn current_frame_mut(&mut self) -> &mut Frame {
-        self.stack.last_mut().unwrap()
+    // what `name` is at the outermost scope of the module
+    pub fn global(&self, name: &String) -> Option<Type> {
+        self.frames[0].get(name)
     }
 
-    pub fn put_frame(&mut self, frame: Frame) {
-        self.stack.push(frame)
+    pub fn at_root(&self) -> bool {
+        self.current == 0
     }
 
     pub fn push(&mut self) {
-        self.stack.push(Frame::new())
+        self.frames.push(Frame::new(Some(self.current)));
+        self.current = self.frames.len() - 1
+    }
+
+    // enters a new scope starting out with `table`
+    pub fn push_with(&mut self, table: HashMap<String, Type>) {
+        self.push();
+
+        for (name, t) in table {
+            self.assign(name, t)
+        }
+    }
+
+    // enters the bindings of the scope left last once more, without copying them
+    pub fn reenter(&mut self) {
+        self.frames.push(Frame {
+            parent: Some(self.current),
+            table: self.last.clone(),
+        });
+
+        self.current = self.frames.len() - 1
     }
 
     pub fn pop(&mut self) {
-        self.last = self.stack.pop().unwrap()
+        let left = self.current;
+
+        self.last = self.frames[left].table.clone();
+        self.current = self.frames[left].parent.expect("popped the outermost scope");
+
+        if left == self.frames.len() - 1 && left > self.pinned.get() {
+            self.frames.pop();
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        self.pinned.set(self.pinned.get().max(self.current));
+
+        Snapshot(self.current)
+    }
+
+    // `name` as seen from where `at` was taken
+    pub fn fetch_at(&self, at: Snapshot, name: &String) -> Option<Type> {
+        self.fetch_from(at.0, name)
+    }
+
+    // every binding visible from where `at` was taken, sorted by name, inner ones shadowing
+    // outer ones
+    pub fn visible_at(&self, at: Snapshot) -> Vec<(String, Type)> {
+        let mut visible = HashMap::new();
+        let mut scope = Some(at.0);
+
+        while let Some(at) = scope {
+            for (name, t) in self.frames[at].table.borrow().iter() {
+                visible.entry(name.clone()).or_insert_with(|| (**t).clone());
+            }
+
+            scope = self.frames[at].parent
+        }
+
+        let mut visible = visible.into_iter().collect::<Vec<_>>();
+        visible.sort_by(|a, b| a.0.cmp(&b.0));
+
+        visible
+    }
+
+    // the visible name that is `name` in another case, e.g. `Player` for `player`
+    pub fn fetch_other_case(&self, name: &str) -> Option<(String, Type)> {
+        let mut scope = Some(self.current);
+
+        while let Some(at) = scope {
+            let table = self.frames[at].table.borrow();
+
+            let found = table
+                .iter()
+                .filter(|(other, _)| *other != name && other.eq_ignore_ascii_case(name))
+                .min_by(|a, b| a.0.cmp(b.0));
+
+            if let Some((other, t)) = found {
+                return Some((other.clone(), (**t).clone()));
+            }
+
+            scope = self.frames[at].parent
+        }
+
+        None
     }
 
     pub fn get_implementations(&self, id: &String) -> Option<&HashMap<String, Type>> {
@@ -140,11 +254,11 @@ impl SymTab {
         self.implementations.insert(id.to_owned(), hash);
     }
 
-    pub fn get_foreign_module(&self, id: &String) -> Option<&HashMap<String, Type>> {
+    pub fn get_foreign_module(&self, id: &String) -> Option<&Rc<HashMap<String, Type>>> {
         self.foreign_imports.get(id)
     }
 
-    pub fn import(&mut self, id: String, origin: HashMap<String, Type>) {
+    pub fn import(&mut self, id: String, origin: Rc<HashMap<String, Type>>) {
         self.foreign_imports.insert(id, origin);
     }
 }
@@ -1,339 +1,361 @@
-use std::collections::HashMap;
-use std::fmt::{self, Display, Formatter};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 
+use super::super::error::{capped, collected, report, Diagnostic};
 use super::super::error::Response::*;
 
 use super::*;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use std::env;
 
-#[derive(Debug, Clone)]
-pub enum TypeNode {
-    Int,
-    Float,
-    Bool,
-    Str,
-    Any,
-    Char,
-    Nil,
-    Id(Rc<Expression>),
-    Array(Rc<Type>, Option<usize>),
-    Func(Vec<Type>, Rc<Type>, Option<Rc<ExpressionNode>>, bool),
-    Module(HashMap<String, Type>, bool), // is_foreign
-    Struct(String, HashMap<String, Type>, String),
-    Trait(String, HashMap<String, Type>),
-    Optional(Rc<TypeNode>),
-    Tuple(Vec<Type>),
-    This,
-}
+// whether a build comes out the same wherever it's made, see `--hermetic`
+static HERMETIC: AtomicBool = AtomicBool::new(false);
 
-impl TypeNode {
-    pub fn check_expression(&self, other: &ExpressionNode) -> bool {
-        use self::TypeNode::*;
+pub fn hermetic() {
+    HERMETIC.store(true, Ordering::Relaxed)
+}
 
-        match *other {
-            ExpressionNode::Int(_) => match *self {
-                Int | Float => true,
-                _ => false,
-            },
+pub fn is_hermetic() -> bool {
+    HERMETIC.load(Ordering::Relaxed)
+}
 
-            ExpressionNode::Array(ref content) => {
-                let array_content = if let &Array(ref array_content, ref len) = self {
-                    if let Some(len) = len {
-                        if *len != content.len() {
-                            return false;
-                        }
-                    }
+// what a hermetic build refers to `file` from: the project it's in, or the working directory
+pub fn hermetic_root(file: &Path) -> PathBuf {
+    let cwd = env::current_dir().unwrap_or_default();
 
-                    array_content
-                } else {
-                    return false;
-                };
+    absolute_path(file)
+        .parent()
+        .and_then(project_of)
+        .unwrap_or_else(|| fs::canonicalize(&cwd).unwrap_or(cwd))
+}
 
-                for element in content {
-                    if !array_content.node.check_expression(&element.node) {
-                        return false;
-                    }
-                }
+// `file` from its `hermetic_root`, or as it is if it's outside of that
+pub fn from_root(file: &Path) -> PathBuf {
+    absolute_path(file)
+        .strip_prefix(hermetic_root(file))
+        .map_or_else(|_| file.to_path_buf(), Path::to_path_buf)
+}
 
-                true
-            }
+// `file` from the file system root, through the folders links lead to
+fn absolute_path(file: &Path) -> PathBuf {
+    let absolute = env::current_dir().unwrap_or_default().join(file);
 
-            _ => false,
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(folder), Some(name)) => {
+            fs::canonicalize(folder).map_or_else(|_| absolute.clone(), |folder| folder.join(name))
         }
+        _ => absolute,
     }
+}
 
-    pub fn strong_cmp(&self, other: &TypeNode) -> bool {
-        use self::TypeNode::*;
+// the names given with `--cfg`, which turn on the imports marked `if cfg(name)`
+static CFG: Mutex<Vec<String>> = Mutex::new(Vec::new());
 
-        match (self, other) {
-            (&Int, &Int) => true,
-            (&Float, &Float) => true,
-            (&Bool, &Bool) => true,
-            (&Str, &Str) => true,
-            (&Any, &Any) => true,
-            (&Char, &Char) => true,
-            (&This, &This) => true,
-            (&Nil, &Nil) => true,
-            (&Tuple(ref a), &Tuple(ref b)) => a == b,
-            (&Optional(ref a), &Optional(ref b)) => a == b,
-            (&Id(ref a), &Id(ref b)) => a == b,
-            (&Array(ref a, ref la), &Array(ref b, ref lb)) => a == b && (la == &None || la == lb),
-            (&Func(ref a_params, ref a_retty, .., a), &Func(ref b_params, ref b_retty, .., b)) => {
-                a_params == b_params && a_retty == b_retty && a == b
-            }
-            (&Struct(ref name, _, ref content), &Struct(ref name_b, _, ref content_b)) => {
-                name == name_b && content == content_b
-            }
-            (&Trait(ref name, ref content), &Trait(ref name_b, ref content_b)) => {
-                name == name_b && content == content_b
-            }
-            _ => false,
-        }
-    }
+pub fn enable_cfg(name: &str) {
+    CFG.lock().unwrap().push(name.to_string())
 }
 
-impl PartialEq for TypeNode {
-    fn eq(&self, other: &Self) -> bool {
-        use self::TypeNode::*;
-
-        match (self, other) {
-            (&Any, _) => true,
-            (_, &Any) => true,
-            (&Optional(ref a), _) if **a == Any => true,
-            (_, &Optional(ref b)) if **b == Any => true,
-
-            (&Int, &Int) => true,
-            (&Str, &Str) => true,
-            (&Float, &Float) => true,
-            (&Char, &Char) => true,
-            (&Bool, &Bool) => true,
-            (&Nil, &Nil) => true,
-            (&This, &This) => true,
-            (&Tuple(ref a), &Tuple(ref b)) => a == b,
-            (&Array(ref a, ref la), &Array(ref b, ref lb)) => {
-                a == b && (la == &None || (a.node == Any && lb == &None) || la == lb)
-            }
-            (&Id(ref a), &Id(ref b)) => a == b,
-            (&Func(ref a_params, ref a_retty, .., a), &Func(ref b_params, ref b_retty, .., b)) => {
-                a_params == b_params && a_retty == b_retty && a == b
-            }
-
-            (&Struct(ref name, _, ref content), &Struct(ref name_b, _, ref content_b)) => {
-                name == name_b && content == content_b
-            }
-            (&Trait(_, ref content), &Trait(_, ref content_b)) => content == content_b,
-            (&Trait(_, ref content), &Struct(_, ref content_b, _)) => {
-                for (name, ty) in content.iter() {
-                    if let Some(ty_b) = content_b.get(name) {
-                        if ty.node != ty_b.node {
-                            return false;
-                        }
-                    } else {
-                        return false;
-                    }
-                }
+pub fn enabled_cfgs() -> Vec<String> {
+    CFG.lock().unwrap().clone()
+}
 
-                true
-            }
+pub fn cfg_enabled(name: &str) -> bool {
+    CFG.lock().unwrap().iter().any(|enabled| enabled == name)
+}
 
-            (&Optional(_), &Nil) => true,
-            (&Nil, &Optional(_)) => true,
-            (&Optional(ref a), &Optional(ref b)) => a == b,
-            (&Optional(ref a), ref b) => **a == **b,
-            (_, Optional(_)) => false,
+// whether an import with this `if cfg(name)`, if any, is imported
+pub fn enabled(cfg: &Option<String>) -> bool {
+    cfg.as_deref().is_none_or(cfg_enabled)
+}
 
-            (&Struct(..), &Trait(..)) => other == self,
+// files open in an editor, as edited and maybe not saved yet, which imports read instead of
+// what's on disk, see `wu lsp`
+static EDITED: Mutex<Vec<(PathBuf, String)>> = Mutex::new(Vec::new());
 
-            _ => false,
-        }
+// `content` stands in for the file at the canonical `path` from here on, or stops to if none
+pub fn edit(path: &Path, content: Option<String>) {
+    let mut edited = EDITED.lock().unwrap();
+
+    edited.retain(|(edited, _)| edited != path);
+
+    if let Some(content) = content {
+        edited.push((path.to_path_buf(), content))
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum TypeMode {
-    Undeclared,
-    // TODO: Not fully implemented yet
-    #[allow(dead_code)]
-    Immutable,
-    #[allow(dead_code)]
-    Optional,
-    Implemented,
-    Regular,
-    Splat(Option<usize>),
-    Unwrap(usize),
+// the content of the module at the canonical `path`, as edited if it is, or as wu comes with it
+// for those in `stdlib::ROOT`
+pub fn read_module(path: &Path) -> io::Result<String> {
+    let edited = EDITED
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(edited, _)| edited == path)
+        .map(|(_, content)| content.clone());
+
+    match (edited, embedded(path)) {
+        (Some(content), _) => Ok(content),
+        (None, Some(content)) => Ok(content.to_string()),
+        (None, None) => fs::read_to_string(path),
+    }
 }
 
-impl TypeMode {
-    pub fn strong_cmp(&self, other: &TypeMode) -> bool {
-        use self::TypeMode::*;
+// the closest folder from `folder` up that has a `wu.toml`
+pub fn project_of(folder: &Path) -> Option<PathBuf> {
+    folder
+        .ancestors()
+        .find(|ancestor| ancestor.join("wu.toml").is_file())
+        .map(Path::to_path_buf)
+}
 
-        match (self, other) {
-            (&Regular, &Regular) => true,
-            (&Immutable, &Immutable) => true,
-            (&Optional, &Optional) => true,
-            (&Implemented, &Implemented) => true,
-            (&Undeclared, &Undeclared) => true,
-            (&Splat(a), &Splat(b)) => &a == &b,
-            (&Unwrap(_), &Unwrap(_)) => true,
-            _ => false,
-        }
-    }
+// the modules visited so far by this process, e.g. by the other files of `wu .` or earlier
+// rebuilds of `--watch`, by canonical path and where their own imports resolve from
+thread_local! {
+    static MODULES: RefCell<HashMap<(PathBuf, String, bool), ImportedModule>> =
+        RefCell::new(HashMap::new());
 }
 
-impl Display for TypeNode {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        use self::TypeNode::*;
+fn hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
 
-        match *self {
-            Int => write!(f, "int"),
-            Float => write!(f, "float"),
-            Bool => write!(f, "bool"),
-            Str => write!(f, "str"),
-            Char => write!(f, "char"),
-            Nil => write!(f, "nil"),
-            This => write!(f, "self"),
-            Any => write!(f, "any"),
+    hasher.finish()
+}
 
-            Tuple(ref content) => {
-                write!(f, "(")?;
+// whether every file still reads as it did when hashed
+fn unchanged(files: &[(PathBuf, u64)]) -> bool {
+    files
+        .iter()
+        .all(|(file, hashed)| read_module(file).is_ok_and(|content| hash(&content) == *hashed))
+}
 
-                for (i, t) in content.iter().enumerate() {
-                    write!(f, "{}", t)?;
+// whether an `int` is a 64 bit integer that wraps around, as from lua 5.3 on, rather than a float
+// that is exact up to 2^53; see `Target`
+static WIDE_INTS: AtomicBool = AtomicBool::new(false);
 
-                    if i != content.len() - 1 {
-                        write!(f, ", ")?;
-                    }
-                }
+// the largest `int` a float holds exactly, 2^53
+pub const EXACT_INT: i64 = 1 << 53;
 
-                write!(f, ")")?;
-                Ok(())
-            }
+// the most variants a `@flags` enum has, one bit each, so all stay positive in luajit's `bit`
+pub const FLAGS: usize = 31;
 
-            Trait(ref name, _) => write!(f, "{}", name),
+pub fn wide_ints(wide: bool) {
+    WIDE_INTS.store(wide, Ordering::Relaxed)
+}
 
-            Array(ref n, l) => {
-                if let Some(len) = l {
-                    write!(f, "[{}; {}]", n, len)?;
-                } else {
-                    write!(f, "[{}]", n)?;
-                }
-                return Ok(())
-            }
+pub fn has_wide_ints() -> bool {
+    WIDE_INTS.load(Ordering::Relaxed)
+}
 
-            Id(ref n) => write!(f, "deid({})", n.pos.get_lexeme()),
+// whether `t` is a struct itself, rather than a value of it
+fn is_struct_itself(t: &Type) -> bool {
+    matches!(t.node, TypeNode::Struct(..)) && t.mode.strong_cmp(&TypeMode::Undeclared)
+}
 
-            Module(..) => write!(f, "module"),
-            Struct(ref name, ..) => write!(f, "{}", name),
+fn has_else(elses: &Option<Vec<(Option<Expression>, Expression, Pos)>>) -> bool {
+    elses
+        .iter()
+        .flatten()
+        .any(|(condition, ..)| condition.is_none())
+}
 
-            Func(ref params, ref return_type, ..) => {
-                write!(f, "fun(")?;
+// where the `delimiters` after `pos` open and close, as lines and columns, e.g. the braces of a
+// `new` or the parentheses of a call
+fn delimited_after(
+    tokens: &[Token],
+    pos: &Pos,
+    delimiters: (&str, &str),
+) -> Option<((usize, usize), (usize, usize))> {
+    let start = tokens.iter().position(|token| {
+        token.token_type == TokenType::Symbol
+            && token.lexeme == delimiters.0
+            && (token.line.0, token.slice.0) >= ((pos.0).0, (pos.1).1)
+    })?;
+
+    let mut nest_count = 0;
+
+    for token in tokens[start..].iter() {
+        if token.token_type != TokenType::Symbol {
+            continue;
+        }
 
-                for (index, element) in params.iter().enumerate() {
-                    if index < params.len() - 1 {
-                        write!(f, "{}, ", element)?;
-                    } else {
-                        write!(f, "{}", element)?;
-                    }
-                }
+        if token.lexeme == delimiters.0 {
+            nest_count += 1
+        } else if token.lexeme == delimiters.1 {
+            nest_count -= 1
+        } else {
+            continue;
+        }
 
-                write!(f, ") -> {}", return_type)?;
-                return Ok(())
-            }
+        if nest_count == 0 {
+            let open = &tokens[start];
 
-            Optional(ref inner) => write!(f, "{}?", inner),
+            return Some(((open.line.0, open.slice.0), (token.line.0, token.slice.0)));
         }
     }
+
+    None
 }
 
-impl PartialEq for TypeMode {
-    fn eq(&self, other: &TypeMode) -> bool {
-        use self::TypeMode::*;
-
-        match (self, other) {
-            (&Regular, &Regular) => true,
-            (&Regular, &Immutable) => true,
-            (&Immutable, &Immutable) => true,
-            (&Immutable, &Regular) => true,
-            (_, &Optional) => true,
-            (&Optional, _) => true,
-            (&Undeclared, _) => false,
-            (_, &Undeclared) => false,
-            (&Splat(a), &Splat(b)) => (a.is_none() || b.is_none()) || a <= b,
-            (&Unwrap(_), _) => true,
-            (_, &Unwrap(_)) => true,
-            _ => false,
+// the parameters of the function `called` is, with their names when its definition is known
+pub fn parameters(called: &Type) -> Vec<(Option<String>, Type)> {
+    let (params, function) = match called.node {
+        TypeNode::Func(ref params, _, ref function, _) => (params, function),
+        _ => return Vec::new(),
+    };
+
+    let names = match function.as_deref() {
+        Some(ExpressionNode::Function(ref named, ..)) => {
+            named.iter().map(|param| Some(param.0.clone())).collect()
         }
-    }
+        _ => Vec::new(),
+    };
+
+    params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| (names.get(i).cloned().flatten(), param.clone()))
+        .collect()
 }
 
-impl Display for TypeMode {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        use self::TypeMode::*;
-
-        match *self {
-            Regular => Ok(()),
-            Immutable => write!(f, "constant "),
-            Undeclared => write!(f, "undeclared "),
-            Optional => write!(f, "optional? "),
-            Implemented => Ok(()),
-            Splat(_count) => write!(f, "..."),
-            Unwrap(_) => write!(f, "*"),
+// the parameter the argument after `before` others goes to, where a splat takes all the rest
+pub fn active_parameter(params: &[(Option<String>, Type)], before: usize) -> Option<usize> {
+    match params.last() {
+        Some((_, last)) if matches!(last.mode, TypeMode::Splat(_)) => {
+            Some(before.min(params.len() - 1))
         }
+        _ if before < params.len() => Some(before),
+        _ => None,
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Type {
-    pub node: TypeNode,
-    pub mode: TypeMode,
-}
+// how many arguments come before `at` in the parentheses opening at `open`, by their commas
+fn arguments_before(tokens: &[Token], open: (usize, usize), at: (usize, usize)) -> usize {
+    let mut nest_count = 0;
+    let mut commas = 0;
 
-impl Type {
-    pub fn new(node: TypeNode, mode: TypeMode) -> Self {
-        Self { node, mode }
-    }
+    for token in tokens.iter() {
+        let from = (token.line.0, token.slice.0);
 
-    pub fn is_method(&self) -> bool {
-        if let TypeNode::Func(.., is_method) = self.node {
-            return is_method;
+        if from <= open || token.token_type != TokenType::Symbol {
+            continue;
         }
 
-        false
-    }
+        if from >= at {
+            break;
+        }
 
-    pub fn id(id: Rc<Expression>) -> Self {
-        Type::new(TypeNode::Id(id), TypeMode::Regular)
+        match token.lexeme.as_str() {
+            "(" | "[" | "{" => nest_count += 1,
+            ")" | "]" | "}" => nest_count -= 1,
+            "," if nest_count == 0 => commas += 1,
+            _ => (),
+        }
     }
 
-    pub fn from(node: TypeNode) -> Type {
-        Type::new(node, TypeMode::Regular)
-    }
+    commas
+}
 
-    pub fn tuple(t: Vec<Type>) -> Type {
-        Type::new(TypeNode::Tuple(t), TypeMode::Regular)
+// the names `statement` declares
+fn declared_by(statement: &Statement) -> Vec<String> {
+    match statement.binding().node {
+        StatementNode::Variable(_, ref name, ..) => vec![name.clone()],
+        StatementNode::SplatVariable(_, ref names, ..) => names.clone(),
+        StatementNode::Import(ref path, ref specifics, ..) => match specifics.as_slice() {
+            [] => vec![Visitor::module_name(path)],
+            _ => specifics
+                .iter()
+                .filter(|(name, _)| name != "*")
+                .map(|(name, alias)| alias.as_ref().unwrap_or(name).clone())
+                .collect(),
+        },
+        _ => Vec::new(),
     }
+}
+
+// a function's signature without the `{` of the body that its span can reach into
+fn signature_pos(function: &Pos) -> Pos {
+    let line = (function.0).1.chars().collect::<Vec<_>>();
+    let end = (function.1).1.min(line.len());
+    let start = (function.1).0.saturating_sub(1).min(end);
+
+    let end = (start..end)
+        .rev()
+        .find(|&i| !line[i].is_whitespace() && line[i] != '{')
+        .map_or(end, |i| i + 1);
+
+    Pos(function.0.clone(), ((function.1).0, end))
+}
+
+// a parameter written without a type, e.g. `x` in `fun(x, y: int)`, which makes it `any`
+fn untyped_param(function: &Pos, name: &str) -> Option<Pos> {
+    let pos = param_pos(function, name);
 
-    pub fn array(t: Type, len: Option<usize>) -> Type {
-        Type::new(TypeNode::Array(Rc::new(t), len), TypeMode::Regular)
+    // not on the signature's line, so it can't be told apart
+    if pos.1 == signature_pos(function).1 {
+        return None;
     }
 
-    pub fn function(params: Vec<Type>, return_type: Type, is_method: bool) -> Self {
-        Type::new(
-            TypeNode::Func(params, Rc::new(return_type), None, is_method),
-            TypeMode::Regular,
-        )
+    let line = (pos.0).1.chars().collect::<Vec<_>>();
+
+    match line.iter().skip((pos.1).1).find(|c| !c.is_whitespace()) {
+        Some(':') => None,
+        _ => Some(pos),
     }
 }
 
-impl Display for Type {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{}{}", self.mode, self.node)
+// the declared return type in a function's signature, e.g. `str` in `fun(x: int) -> str`
+fn return_type_pos(function: &Pos) -> Option<Pos> {
+    let signature = signature_pos(function);
+
+    let line = (signature.0).1.chars().collect::<Vec<_>>();
+    let end = (signature.1).1.min(line.len());
+    let start = (signature.1).0.saturating_sub(1).min(end);
+
+    let arrow = (start..end.saturating_sub(1)).find(|&i| line[i] == '-' && line[i + 1] == '>')?;
+    let type_start = (arrow + 2..end).find(|&i| !line[i].is_whitespace())?;
+
+    Some(Pos(signature.0, (type_start + 1, end)))
+}
+
+// a parameter in a function's signature, e.g. `...rest` in `fun(a: int, ...rest: str)`
+fn param_pos(function: &Pos, name: &str) -> Pos {
+    let signature = signature_pos(function);
+
+    let line = (signature.0).1.chars().collect::<Vec<_>>();
+    let end = (signature.1).1.min(line.len());
+    let start = (signature.1).0.saturating_sub(1).min(end);
+
+    let name = name.chars().collect::<Vec<_>>();
+    let is_ident = |i: usize| line.get(i).is_some_and(|c| c.is_alphanumeric() || *c == '_');
+
+    let found = (start..end.saturating_sub(name.len() - 1)).find(|&i| {
+        line[i..i + name.len()] == name[..]
+            && (i == 0 || !is_ident(i - 1))
+            && !is_ident(i + name.len())
+    });
+
+    match found {
+        Some(i) => {
+            let from = if i >= 3 && line[i - 3..i] == ['.', '.', '.'] {
+                i - 3
+            } else {
+                i
+            };
+
+            Pos(signature.0, (from + 1, i + name.len()))
+        }
+
+        None => signature,
     }
 }
 
@@ -349,11 +371,25 @@ pub enum Inside {
     Calling(Pos),
     Splat(Option<usize>),
     Implement(Type),
-    ForeignModule(HashMap<String, Type>),
+    ForeignModule(Rc<HashMap<String, Type>>),
     Function,
     Nothing,
 }
 
+// what an imported file contributes to its importers
+#[derive(Debug, Clone)]
+pub struct ImportedModule {
+    pub content: HashMap<String, Type>,
+    pub implementations: HashMap<String, HashMap<String, Type>>,
+    pub files: Vec<(PathBuf, u64)>, // the module and all it imports, with the hash of their content
+}
+
+// the chain of imports being visited, outermost first, each with where the one before imports it
+pub type Loading = Rc<RefCell<Vec<(PathBuf, Option<Pos>)>>>;
+
+// the members of a struct, by name, e.g. those an initialization leaves out
+pub type Members = Vec<(String, Type)>;
+
 pub struct Visitor<'v> {
     pub symtab: SymTab,
 
@@ -361,20 +397,77 @@ pub struct Visitor<'v> {
     pub ast: &'v Vec<Statement>,
 
     pub flag: Option<FlagContext>,
-    pub inside: Vec<Inside>,
+    pub inside: Insides,
 
     pub method_calls: HashMap<Pos, bool>,
+    pub builtins: HashMap<Pos, Builtin>, // array and optional methods called, e.g. `xs len`
+    pub conversions: HashSet<Pos>, // concatenated values that need a `tostring`
+    pub floats: HashSet<Pos>,      // floats made strings, by concatenating or `as str`
+    pub nil_checks: HashSet<Pos>,  // `or` and `and` on optionals that can be `false`
+    pub strings: HashSet<Pos>,     // what `as str` leaves be, being a `str` already
+    pub inliner: Inliner,
+    pub purity: Purity,
+    pub escapes: Escapes,
+    pub defaults: HashMap<Pos, Vec<String>>, // trait default methods each implementation takes
+    pub componentwise: HashMap<Pos, Componentwise>, // arithmetic on value structs, e.g. `a + b`
+    pub resolution: Resolution,
+    pub lookups: HashSet<Pos>, // functions looked up in modules, e.g. `love graphics draw`
     pub module_content: HashMap<String, Type>,
     pub import_map: HashMap<Pos, (String, String)>,
+    pub import_expansions: HashMap<Pos, Vec<String>>, // names brought in by `import foo { * }`
+    pub import_paths: HashMap<Pos, String>, // `root/` import paths, from the importing file
+    pub imports: Rc<RefCell<HashMap<PathBuf, ImportedModule>>>, // shared by the whole compilation
+    pub loading: Loading,
+    pub files: Vec<(PathBuf, u64)>, // every module imported, directly or not, and its hash
+    pub depth: Depth,
+    pub types: Option<HashMap<Pos, Type>>, // what every expression was typed as, when asked for
+    pub initializations: Option<Vec<(Pos, Members)>>, // what each `new` still lacks
+    pub calls: Option<Vec<(Pos, Type)>>, // what each call calls, when asked for
+    pub poisoned: HashSet<String>, // names declared wrong, which what uses them isn't checked for
+    pub diagnostics: Vec<Diagnostic>, // what `check` found wrong, in the order it was found
+    pub pragmas: Pragmas,                  // the module's `#!strict` and `#!allow(..)`
+    pub left_out: HashMap<String, String>, // names of imports whose `cfg` is off, with the `cfg`
+    pub complexity: Complexity, // how big the functions being visited are, for `--max-lines` etc.
+    pub budget: Budget,         // what the whole compilation may spend, see `playground`
+    pub exports: Exports,       // what the project's modules define, for suggesting imports
 
     pub root: String,
     pub is_deep: bool,
 }
 
 impl<'v> Visitor<'v> {
+    // checks the module, then shows what's wrong with it
     pub fn visit(&mut self) -> Result<(), ()> {
+        let checked = self.check();
+
+        for diagnostic in self.diagnostics.iter() {
+            report(diagnostic.clone())
+        }
+
+        checked
+    }
+
+    // checks the module, keeping what's wrong with it in `diagnostics` instead of showing it
+    pub fn check(&mut self) -> Result<(), ()> {
+        let (checked, diagnostics) = collected(|| self.check_module());
+
+        self.diagnostics = diagnostics;
+
+        checked
+    }
+
+    fn check_module(&mut self) -> Result<(), ()> {
+        self.pragmas = Pragmas::read(self.source)?;
+
         self.visit_block(self.ast, false, true)?;
 
+        if internal_checks() {
+            self.check_module_content()
+        }
+
+        self.inliner.finish();
+        self.purity.finish();
+
         Ok(())
     }
 
@@ -386,11 +479,39 @@ impl<'v> Visitor<'v> {
             ast,
 
             flag: None,
-            inside: Vec::new(),
+            inside: Insides::default(),
 
             method_calls: HashMap::new(),
+            builtins: HashMap::new(),
+            conversions: HashSet::new(),
+            floats: HashSet::new(),
+            nil_checks: HashSet::new(),
+            strings: HashSet::new(),
+            inliner: Inliner::default(),
+            purity: Purity::default(),
+            escapes: Escapes::default(),
+            defaults: HashMap::new(),
+            componentwise: HashMap::new(),
+            resolution: Resolution::default(),
+            lookups: HashSet::new(),
             module_content: HashMap::new(),
             import_map: HashMap::new(),
+            import_expansions: HashMap::new(),
+            import_paths: HashMap::new(),
+            imports: Rc::new(RefCell::new(HashMap::new())),
+            loading: Rc::new(RefCell::new(Vec::new())),
+            files: Vec::new(),
+            depth: Depth::default(),
+            types: None,
+            initializations: None,
+            calls: None,
+            poisoned: HashSet::new(),
+            diagnostics: Vec::new(),
+            pragmas: Pragmas::default(),
+            complexity: Complexity::default(),
+            budget: Budget::default(),
+            exports: Exports::default(),
+            left_out: HashMap::new(),
 
             root,
             is_deep: false,
@@ -411,11 +532,39 @@ impl<'v> Visitor<'v> {
             ast,
 
             flag: None,
-            inside: Vec::new(),
+            inside: Insides::default(),
 
             method_calls: HashMap::new(),
+            builtins: HashMap::new(),
+            conversions: HashSet::new(),
+            floats: HashSet::new(),
+            nil_checks: HashSet::new(),
+            strings: HashSet::new(),
+            inliner: Inliner::default(),
+            purity: Purity::default(),
+            escapes: Escapes::default(),
+            defaults: HashMap::new(),
+            componentwise: HashMap::new(),
+            resolution: Resolution::default(),
+            lookups: HashSet::new(),
             module_content: HashMap::new(),
             import_map: HashMap::new(),
+            import_expansions: HashMap::new(),
+            import_paths: HashMap::new(),
+            imports: Rc::new(RefCell::new(HashMap::new())),
+            loading: Rc::new(RefCell::new(Vec::new())),
+            files: Vec::new(),
+            depth: Depth::default(),
+            types: None,
+            initializations: None,
+            calls: None,
+            poisoned: HashSet::new(),
+            diagnostics: Vec::new(),
+            pragmas: Pragmas::default(),
+            complexity: Complexity::default(),
+            budget: Budget::default(),
+            exports: Exports::default(),
+            left_out: HashMap::new(),
 
             root,
             is_deep: false,
@@ -423,12 +572,41 @@ impl<'v> Visitor<'v> {
     }
 
     fn visit_statement(&mut self, statement: &Statement) -> Result<(), ()> {
+        if !internal_checks() {
+            return self.visit_statement_node(statement);
+        }
+
+        let before = self.balance();
+        let visited = self.visit_statement_node(statement);
+
+        if visited.is_ok() {
+            self.check_balance(before, &statement.pos, "statement")
+        }
+
+        visited
+    }
+
+    fn visit_statement_node(&mut self, statement: &Statement) -> Result<(), ()> {
         use self::StatementNode::*;
 
+        let _processing = ice::processing(&self.source.file, &statement.pos);
+
+        self.complexity.see(&statement.pos, false);
+        self.spend(Resource::Time, 0, &statement.pos)?;
+
         match statement.node {
             Expression(ref expr) => self.visit_expression(expr),
             Variable(..) => self.visit_variable(&statement.node, &statement.pos, false),
+            Global(ref binding) => self.visit_statement(binding),
             SplatVariable(ref t, ref splats, ref right, ref public) => {
+                if let Some(content) = self.returned_tuple(right)? {
+                    let right = right.as_ref().unwrap();
+
+                    self.escapes.spread(right, &Type::tuple(content.clone()));
+
+                    return self.destructure(t, splats, &content, right, &statement.pos);
+                }
+
                 for splat in splats.iter() {
                     let fake_var =
                         StatementNode::Variable(t.to_owned(), splat.to_owned(), right.to_owned(), *public);
@@ -442,7 +620,12 @@ impl<'v> Visitor<'v> {
             Return(ref value) => {
                 if self.inside.contains(&Inside::Function) {
                     if let Some(ref expression) = *value {
-                        self.visit_expression(expression)
+                        self.visit_expression(expression)?;
+
+                        let t = self.type_expression(expression)?;
+                        self.escapes.spread(expression, &t);
+
+                        Ok(())
                     } else {
                         Ok(())
                     }
@@ -481,90 +664,76 @@ impl<'v> Visitor<'v> {
                 }
             }
 
-            Import(ref path, ref specifics, _public) => {
+            // left out along with the module, whose names can't be used then
+            Import(ref path, ref specifics, Some(ref cfg), _) if !cfg_enabled(cfg) => {
+                let names = match specifics.as_slice() {
+                    [] => vec![Self::module_name(path)],
+                    _ => specifics
+                        .iter()
+                        .map(|(name, alias)| alias.as_ref().unwrap_or(name).clone())
+                        .collect(),
+                };
+
+                for name in names {
+                    self.left_out.insert(name, cfg.clone());
+                }
+
+                Ok(())
+            }
+
+            Import(ref path, ref specifics, ..) => {
                 let local_root = Path::new(&self.source.file.0)
                     .parent()
                     .unwrap()
                     .display()
                     .to_string();
                 // &self.root.clone()
+                let path = &self.rooted_path(path, &local_root, statement)?;
                 let module = self.find_module(path, &local_root, &statement, self.is_deep)?;
 
-                let mut file = match File::open(&module) {
-                    Err(why) => panic!("failed to open {}: {}", module, why),
-                    Ok(file) => file,
-                };
-
-                let mut content = String::new();
+                let imported = self.import_module(module, statement)?;
+                let content_type = Rc::new(imported.content);
 
-                match file.read_to_string(&mut content) {
-                    Err(why) => panic!("failed to read {}: {}", module, why),
-                    Ok(_) => {
-                        let source = Source::new(module);
-                        let lexer = Lexer::default(content.chars().collect(), &source);
+                for &(ref name, ref alias) in specifics.iter() {
+                    if name == "*" {
+                        let mut names = content_type.keys().cloned().collect::<Vec<String>>();
 
-                        let mut tokens = Vec::new();
+                        names.sort();
 
-                        for token_result in lexer {
-                            if let Ok(token) = token_result {
-                                tokens.push(token)
-                            } else {
-                                panic!("weird unexpected lexer error")
-                            }
+                        for name in names.iter() {
+                            self.import_member(name, &content_type[name], &content_type)
                         }
 
-                        let parsed = Parser::new(tokens, &source).parse()?;
-
-                        let mut is_deep = false;
-
-                        let root = if let Some(other_path) = self.import_map.get(&statement.pos) {
-                            is_deep = true;
-                            Path::new(&other_path.0)
-                                .parent()
-                                .unwrap()
-                                .display()
-                                .to_string()
-                        } else {
-                            self.root.clone()
-                        };
-
-                        let mut visitor = Visitor::new(&parsed, &source, root);
-                        visitor.is_deep = is_deep;
+                        self.import_expansions.insert(statement.pos.clone(), names);
 
-                        visitor.visit()?;
-
-                        let content_type = visitor.module_content.clone();
+                        continue;
+                    }
 
-                        for name in specifics {
-                            if let Some(kind) = content_type.get(name) {
-                                self.symtab.import(name.clone(), content_type.clone());
-                                self.assign(name.clone(), kind.clone());
+                    if let Some(kind) = content_type.get(name) {
+                        let local = alias.as_ref().unwrap_or(name);
 
-                                if self.symtab.stack.len() == 1 {
-                                    self.module_content.insert(name.clone(), kind.clone());
-                                }
+                        self.import_member(local, kind, &content_type)
+                    } else {
+                        return Err(response!(
+                            Wrong(format!("no such member `{}`", name)),
+                            self.source.file,
+                            statement.pos
+                        ));
+                    }
+                }
 
-                            } else {
-                                return Err(response!(
-                                    Wrong(format!("no such member `{}`", name)),
-                                    self.source.file,
-                                    statement.pos
-                                ));
-                            }
-                        }
+                let module_type = Type::from(TypeNode::Module(content_type, true));
 
-                        let module_type = Type::from(TypeNode::Module(content_type, true));
+                // nice
+                self.symtab
+                    .implementations
+                    .extend(imported.implementations);
 
-                        // nice
-                        self.symtab
-                            .implementations
-                            .extend(visitor.symtab.implementations);
+                let name = Self::module_name(path);
 
-                        self.module_content
-                            .insert(path.clone(), module_type.clone());
-                        self.assign(path.clone(), module_type.clone())
-                    }
-                }
+                self.module_content
+                    .insert(name.clone(), module_type.clone());
+                self.assign(name, module_type);
 
                 Ok(())
             }
@@ -572,24 +741,26 @@ impl<'v> Visitor<'v> {
             Implement(ref struct_name, ref body, ref parent) => {
                 use self::ExpressionNode::*;
 
-                self.push_scope();
+                let mut scope = self.scope();
 
-                self.visit_expression(struct_name)?;
+                scope.visit_expression(struct_name)?;
 
                 let position = struct_name.pos.clone();
 
                 match struct_name.node {
                     Identifier(ref name) => {
-                        let kind = self.fetch(name, &position)?;
+                        scope.reached(name, &position)?;
+
+                        let kind = scope.fetch(name, &position)?;
 
-                        self.inside.push(Inside::Implement(kind.clone()));
+                        let implementing = scope.inside.enter(Inside::Implement(kind.clone()));
 
                         if let TypeNode::Struct(struct_name, content, id) = kind.node.clone() {
                             if kind.mode.strong_cmp(&TypeMode::Undeclared) {
                                 let new_content = content;
 
                                 if let ExpressionNode::Block(ref ast) = body.node {
-                                    self.visit_implement_block(
+                                    scope.visit_implement_block(
                                         ast,
                                         &struct_name,
                                         &new_content,
@@ -600,49 +771,31 @@ impl<'v> Visitor<'v> {
                                     )?;
                                 }
 
-                                self.inside.pop();
+                                drop(implementing);
 
                                 let new_struct_type =
-                                    self.fetch(&struct_name, &statement.pos)?.clone();
+                                    scope.fetch(&struct_name, &statement.pos)?.clone();
 
-                                self.pop_scope();
+                                drop(scope);
 
                                 self.assign(struct_name.clone(), new_struct_type); // here we go, out and into the world
 
                                 if let Some(ref expr) = parent {
                                     let trait_ty = self.type_expression(expr)?;
 
-                                    if let TypeNode::Struct(_, ref content, _) =
-                                        self.fetch(&struct_name, &position)?.node
-                                    {
-                                        if let TypeNode::Trait(ref _n, ref content_b) = trait_ty.node {
-                                            if let TypeNode::Struct(_, _, _) = trait_ty.node {
-                                                //TODO: isn't this impossible? Should this be
-                                                //nested differently?
-                                                return Err(response!(
-                                                    Wrong(format!("can't implement type `{}`", kind)),
-                                                    self.source.file,
-                                                    position
-                                                ))
-                                            }
+                                    let implemented = self.fetch(&struct_name, &position)?;
 
-                                            for (name, ty) in content_b.iter() {
-                                                if let Some(ty_b) = content.get(name) {
-                                                    if ty.node != ty_b.node {
-                                                        return Err(response!(
-                                                            Wrong(format!("expected implemented type `{}` for `{}`", ty, name)),
-                                                            self.source.file,
-                                                            position
-                                                        ));
-                                                    }
-                                                } else {
-                                                    return Err(response!(
-                                                        Wrong(format!("missing implementation of method `{}: {}`", name, ty)),
-                                                        self.source.file,
-                                                        position
-                                                    ));
-                                                }
-                                            }
+                                    if let TypeNode::Struct(_, ref content, _) = implemented.node {
+                                        if let TypeNode::Trait(_, ref members) = trait_ty.node {
+                                            let defaults =
+                                                self.defaults_taken(content, members, &position)?;
+
+                                            self.take_defaults(
+                                                &struct_name,
+                                                &implemented,
+                                                &defaults,
+                                                &statement.pos,
+                                            )
                                         } else {
                                             return Err(response!(
                                                 Wrong(format!("can't implement type `{}`", kind)),
@@ -658,7 +811,7 @@ impl<'v> Visitor<'v> {
 
                             Err(response!(
                                 Wrong(format!("can't implement type `{}`", kind)),
-                                self.source.file,
+                                scope.source.file,
                                 position
                             ))
                         } else {
@@ -668,8 +821,8 @@ impl<'v> Visitor<'v> {
 
                     Index(ref array, ref indexing, _) => {
                         if let Identifier(ref name) = array.node {
-                            self.fetch(name, &position)?;
-                            let array_type = self.type_expression(array)?;
+                            scope.fetch(name, &position)?;
+                            let array_type = scope.type_expression(array)?;
 
                             match array_type.node {
                                 TypeNode::Module(ref module_content, _is_foreign) => {
@@ -681,10 +834,14 @@ impl<'v> Visitor<'v> {
                                                 if kind.mode.strong_cmp(&TypeMode::Undeclared) {
                                                     let new_content = content;
 
+                                                    let implementing = scope
+                                                        .inside
+                                                        .enter(Inside::Implement((*kind).clone()));
+
                                                     if let ExpressionNode::Block(ref ast) =
                                                         body.node
                                                     {
-                                                        self.visit_implement_block(
+                                                        scope.visit_implement_block(
                                                             ast,
                                                             &name,
                                                             &new_content,
@@ -695,9 +852,9 @@ impl<'v> Visitor<'v> {
                                                         )?;
                                                     }
 
-                                                    self.inside.pop();
+                                                    drop(implementing);
 
-                                                    self.pop_scope();
+                                                    drop(scope);
 
                                                     if let Some(ref expr) = parent {
                                                         let trait_ty =
@@ -706,34 +863,20 @@ impl<'v> Visitor<'v> {
                                                         if let TypeNode::Struct(_, ref content, _) =
                                                             self.type_expression(&struct_name)?.node
                                                         {
-                                                            if let TypeNode::Trait(
-                                                                _,
-                                                                ref content_b,
-                                                            ) = trait_ty.node
+                                                            if let TypeNode::Trait(_, ref members) =
+                                                                trait_ty.node
                                                             {
-                                                                for (name, ty) in content_b.iter() {
-                                                                    if let Some(ty_b) =
-                                                                        content.get(name)
-                                                                    {
-                                                                        if ty.node != ty_b.node {
-                                                                            return Err(
-                                                                                response!(
-                                                                                Wrong(format!("expected implemented type `{}` for `{}`", ty, name)),
-                                                                                self.source.file,
-                                                                                position
-                                                                                )
-                                                                            );
-                                                                        }
-                                                                    } else {
-                                                                        return Err(
-                                                                            response!(
-                                                                                Wrong(format!("missing implementation of method `{}: {}`", name, ty)),
-                                                                                self.source.file,
-                                                                                position
-                                                                            )
-                                                                        );
-                                                                    }
-                                                                }
+                                                                let defaults = self.defaults_taken(
+                                                                    content, members, &position,
+                                                                )?;
+
+                                                                self.defaults.insert(
+                                                                    statement.pos.clone(),
+                                                                    defaults
+                                                                        .into_iter()
+                                                                        .map(|(name, _)| name)
+                                                                        .collect(),
+                                                                );
                                                             }
                                                         }
                                                     }
@@ -748,7 +891,7 @@ impl<'v> Visitor<'v> {
                                 ref kind => {
                                     return Err(response!(
                                         Wrong(format!("can't implement type `{}`", kind)),
-                                        self.source.file,
+                                        scope.source.file,
                                         position
                                     ))
                                 }
@@ -761,7 +904,7 @@ impl<'v> Visitor<'v> {
                     _ => {
                         return Err(response!(
                             Wrong("can't implement anything but structs"),
-                            self.source.file,
+                            scope.source.file,
                             position
                         ))
                     }
@@ -772,9 +915,22 @@ impl<'v> Visitor<'v> {
                 self.visit_expression(left)?;
                 self.visit_expression(right)?;
 
+                self.inliner.reassign(left);
+                self.purity.reassign(left);
+
                 let a = self.type_expression(left)?;
                 let b = self.type_expression(right)?;
 
+                self.escapes.keep(right, &b);
+
+                if a.mode.strong_cmp(&TypeMode::Immutable) {
+                    return Err(response!(
+                        Wrong("can't assign to a constant"),
+                        self.source.file,
+                        left.pos
+                    ));
+                }
+
                 self.assert_types(a, b, &left.pos)?;
 
                 Ok(())
@@ -783,10 +939,39 @@ impl<'v> Visitor<'v> {
             SplatAssignment(ref splats, ref right) => {
                 for splat in splats.iter() {
                     self.visit_expression(splat)?;
+                    self.inliner.reassign(splat);
+                    self.purity.reassign(splat);
                 }
 
                 self.visit_expression(right)?;
 
+                let right_type = self.type_expression(right)?;
+
+                self.escapes.spread(right, &right_type);
+
+                // `a, b = f()` takes the values of a tuple one by one
+                if let TypeNode::Tuple(ref content) = right_type.node {
+                    if content.len() != splats.len() {
+                        return Err(response!(
+                            Wrong(format!(
+                                "can't destructure `{}` into {} names",
+                                self.type_expression(right)?,
+                                splats.len()
+                            )),
+                            self.source.file,
+                            right.pos
+                        ));
+                    }
+
+                    for (splat, element) in splats.iter().zip(content.iter()) {
+                        let splat_t = self.type_expression(splat)?;
+
+                        self.assert_types(splat_t, element.clone(), &splat.pos)?;
+                    }
+
+                    return Ok(());
+                }
+
                 let a = self.type_expression(&splats[0])?;
 
                 for splat in splats.iter() {
@@ -818,22 +1003,40 @@ impl<'v> Visitor<'v> {
     }
 
     fn visit_expression(&mut self, expression: &Expression) -> Result<(), ()> {
+        if !internal_checks() {
+            return self.visit_expression_node(expression);
+        }
+
+        let before = self.balance();
+        let visited = self.visit_expression_node(expression);
+
+        if visited.is_ok() {
+            self.check_balance(before, &expression.pos, "expression")
+        }
+
+        visited
+    }
+
+    fn visit_expression_node(&mut self, expression: &Expression) -> Result<(), ()> {
         use self::ExpressionNode::*;
 
+        let _processing = ice::processing(&self.source.file, &expression.pos);
+
+        let nests = matches!(expression.node, If(..) | Match(..) | While(..) | For(..));
+        let _nested = self.complexity.see(&expression.pos, nests);
+
         match expression.node {
             Identifier(ref name) => {
-                if name == "Self" {
-                    for inside in self.inside.iter() {
-                        if let Inside::Implement(_) = inside {
-                            return Ok(());
-                        }
-                    }
+                if name == "Self" && self.implementing().is_some() {
+                    return Ok(());
                 }
 
-                if let Some(content) = self.symtab.get_foreign_module(name) {
-                    self.inside.push(Inside::ForeignModule(content.clone()))
-                }
+                let _foreign = self
+                    .symtab
+                    .get_foreign_module(name)
+                    .map(|content| self.inside.enter(Inside::ForeignModule(content.clone())));
 
+                self.reached(name, &expression.pos)?;
                 self.fetch(name, &expression.pos)?;
 
                 Ok(())
@@ -841,6 +1044,29 @@ impl<'v> Visitor<'v> {
 
             ExternExpression(ref expr) => self.visit_expression(expr),
 
+            Int(n) => {
+                if !has_wide_ints() && n.unsigned_abs() > EXACT_INT as u64 {
+                    let level = self.pragmas.level(Lint::InexactInts);
+
+                    if let Some(reported) =
+                        level.response(format!("`{}` isn't exact as an `int` before lua 5.3", n))
+                    {
+                        response!(reported, self.source.file, expression.pos);
+
+                        response!(Note(
+                            "ints are floats there, exact up to 2^53; \
+                             `--target 5.3` makes them 64 bit"
+                        ));
+                    }
+
+                    if level == LintLevel::Deny {
+                        return Err(());
+                    }
+                }
+
+                Ok(())
+            }
+
             Splat(ref splats) => {
                 for splat in splats.iter() {
                     self.visit_expression(&splat)?
@@ -882,7 +1108,7 @@ impl<'v> Visitor<'v> {
                 self.visit_expression(right)
             }
 
-            Module(ref content) => self.visit_expression(content),
+            Module(ref content, _) => self.visit_expression(content),
 
             UnwrapSplat(ref expression) => {
                 self.visit_expression(&**expression)?;
@@ -915,10 +1141,21 @@ impl<'v> Visitor<'v> {
             }
 
             Initialization(ref left, ref args) => {
+                if let Identifier(ref name) = left.node {
+                    self.reached(name, &left.pos)?
+                }
+
                 let struct_type = self.type_expression(&*left)?;
 
                 if let TypeNode::Struct(ref name, ref content, ref struct_id) = struct_type.node {
                     if struct_type.mode.strong_cmp(&TypeMode::Undeclared) {
+                        let unassigned = self.unassigned(content, struct_id, args);
+
+                        // before the members, so their failing leaves this one completable
+                        if let Some(ref mut initializations) = self.initializations {
+                            initializations.push((expression.pos.clone(), unassigned.clone()))
+                        }
+
                         let mut validation_map = HashMap::new();
 
                         for arg in args.iter() {
@@ -926,18 +1163,23 @@ impl<'v> Visitor<'v> {
 
                             let arg_type = self.type_expression(&arg.1)?;
 
+                            self.escapes.keep(&arg.1, &arg_type);
+
                             validation_map.insert(arg.0.clone(), arg_type.clone());
 
-                            if let Some(ref content_type) = content.get(&arg.0) {
+                            if let Some(content_type) = content.get(&arg.0) {
                                 if !content_type
                                     .node
                                     .check_expression(&Parser::fold_expression(&arg.1).node)
-                                    && arg_type != **content_type
+                                    && *content_type != arg_type
                                 {
+                                    let (expected, found) =
+                                        disambiguate(&content_type.node, &arg_type);
+
                                     return Err(response!(
                                         Wrong(format!(
                                             "mismatched types, expected `{}` got `{}`",
-                                            content_type, arg_type
+                                            expected, found
                                         )),
                                         self.source.file,
                                         expression.pos
@@ -955,30 +1197,25 @@ impl<'v> Visitor<'v> {
                             }
                         }
 
-                        for (key, kind) in content.iter() {
-                            match kind.node {
-                                TypeNode::Optional(_) => (),
-                                _ => {
-                                    if !validation_map.contains_key(key) {
-                                        if let Some(ref implementations) =
-                                            self.symtab.get_implementations(struct_id)
-                                        {
-                                            if implementations.contains_key(key) {
-                                                continue;
-                                            }
-                                        }
+                        if let Some((key, kind)) = unassigned.first() {
+                            response!(
+                                Wrong(format!(
+                                    "missing assignment of struct member `{}: {}`",
+                                    key, kind
+                                )),
+                                self.source.file,
+                                expression.pos
+                            );
 
-                                        return Err(response!(
-                                            Wrong(format!(
-                                                "missing assignment of struct member `{}: {}`",
-                                                key, kind
-                                            )),
-                                            self.source.file,
-                                            expression.pos
-                                        ));
-                                    }
-                                }
-                            }
+                            self.suggest_members(
+                                name,
+                                content,
+                                &validation_map,
+                                args,
+                                &expression.pos,
+                            );
+
+                            return Err(());
                         }
                     } else {
                         return Err(response!(
@@ -1004,20 +1241,12 @@ impl<'v> Visitor<'v> {
                 Ok(())
             }
 
-            Block(ref statements) => {
-                self.push_scope();
+            Block(ref statements) => self.scope().visit_block(statements, true, false),
 
-                self.visit_block(statements, true, false)?;
+            If(ref condition, ref body, ref elses) => {
+                self.visit_expression(&*condition)?;
 
-                self.pop_scope();
-
-                Ok(())
-            }
-
-            If(ref condition, ref body, ref elses) => {
-                self.visit_expression(&*condition)?;
-
-                let condition_type = self.type_expression(&*condition)?.node;
+                let condition_type = self.type_expression(&*condition)?.node;
 
                 if condition_type == TypeNode::Bool {
                     self.visit_expression(body)?;
@@ -1044,10 +1273,13 @@ impl<'v> Visitor<'v> {
                             let else_body_type = self.type_expression(body)?;
 
                             if body_type != else_body_type {
+                                let (expected, found) =
+                                    disambiguate(&body_type, &else_body_type);
+
                                 return Err(response!(
                                     Wrong(format!(
                                         "mismatched types, expected `{}` got `{}`",
-                                        body_type, else_body_type
+                                        expected, found
                                     )),
                                     self.source.file,
                                     body.pos
@@ -1072,19 +1304,16 @@ impl<'v> Visitor<'v> {
             For(ref condition, ref body) => {
                 let (expr, iterator) = &*condition;
 
-                if let Some(ref iterator) = iterator {
-                    self.visit_expression(&iterator)?;
+                if let Some(ref iterated) = iterator {
+                    self.visit_expression(iterated)?;
 
-                    let iterator = if let ExpressionNode::Call(ref called, ..) = iterator.node {
+                    let iterator = if let ExpressionNode::Call(ref called, ..) = iterated.node {
                         called
                     } else {
-                        iterator
+                        iterated
                     };
 
                     let iterator_t = self.type_expression(&iterator)?;
-                    let params_t = Type::new(TypeNode::Any, TypeMode::Splat(None));
-
-                    // TODO: ACTUALLY INFER ITERATOR TYPE
 
                     // allowed: fun(...) -> ...
 
@@ -1100,15 +1329,24 @@ impl<'v> Visitor<'v> {
                         )),
                     }
 
+                    let elements = self.elements(iterated, &iterator_t)?;
+
+                    // what isn't known of is left `any`
+                    let element = |i: usize| {
+                        elements
+                            .get(i)
+                            .cloned()
+                            .unwrap_or_else(|| Type::from(TypeNode::Any))
+                    };
+
                     match expr.node {
-                        ExpressionNode::Identifier(ref name) => self
-                            .symtab
-                            .assign((*name).clone(), Type::from(TypeNode::Any)),
+                        ExpressionNode::Identifier(ref name) => {
+                            self.symtab.assign((*name).clone(), element(0))
+                        }
                         ExpressionNode::Tuple(ref names) => {
-                            for name in names.iter() {
+                            for (i, name) in names.iter().enumerate() {
                                 if let ExpressionNode::Identifier(ref name) = name.node {
-                                    self.symtab
-                                        .assign((*name).clone(), Type::from(TypeNode::Any))
+                                    self.symtab.assign((*name).clone(), element(i))
                                 }
                             }
                         }
@@ -1125,7 +1363,7 @@ impl<'v> Visitor<'v> {
                 if (iterator.is_none() && self.type_expression(&expr)?.node == TypeNode::Int)
                     || iterator.is_some()
                 {
-                    self.inside.push(Inside::Loop);
+                    let _looping = self.inside.enter(Inside::Loop);
 
                     self.visit_expression(body)?;
 
@@ -1148,8 +1386,6 @@ impl<'v> Visitor<'v> {
                         ));
                     }
 
-                    self.inside.pop();
-
                     Ok(())
                 } else {
                     return Err(response!(
@@ -1169,7 +1405,7 @@ impl<'v> Visitor<'v> {
                 let condition_type = self.type_expression(&*condition)?.node;
 
                 if condition_type == TypeNode::Bool {
-                    self.inside.push(Inside::Loop);
+                    let _looping = self.inside.enter(Inside::Loop);
 
                     self.visit_expression(body)?;
 
@@ -1192,8 +1428,6 @@ impl<'v> Visitor<'v> {
                         ));
                     }
 
-                    self.inside.pop();
-
                     Ok(())
                 } else {
                     return Err(response!(
@@ -1209,7 +1443,10 @@ impl<'v> Visitor<'v> {
 
             Tuple(ref content) => {
                 for element in content.iter() {
-                    self.visit_expression(&element)?
+                    self.visit_expression(element)?;
+
+                    let t = self.type_expression(element)?;
+                    self.escapes.keep(element, &t)
                 }
 
                 Ok(())
@@ -1225,15 +1462,19 @@ impl<'v> Visitor<'v> {
                 for element in content {
                     let element_type = self.type_expression(element)?;
 
+                    self.escapes.keep(element, &element_type);
+
                     if !t
                         .node
                         .check_expression(&Parser::fold_expression(element).node)
                         && t.node != element_type.node
                     {
+                        let (expected, found) = disambiguate(&t, &element_type);
+
                         return Err(response!(
                             Wrong(format!(
                                 "mismatched types in array, expected `{}` got `{}`",
-                                t, element_type
+                                expected, found
                             )),
                             self.source.file,
                             element.pos
@@ -1262,7 +1503,125 @@ impl<'v> Visitor<'v> {
                 Ok(())
             }
 
-            Trait(_, ref params) => {
+            Enum(_, ref variants, _, flags) => {
+                let mut declared = Vec::new();
+
+                if flags && variants.len() > FLAGS {
+                    return Err(response!(
+                        Wrong(format!("a `@flags` enum holds at most {} variants", FLAGS)),
+                        self.source.file,
+                        expression.pos
+                    ));
+                }
+
+                for (variant, carried) in variants.iter() {
+                    if flags && !carried.is_empty() {
+                        return Err(response!(
+                            Wrong(format!(
+                                "variant `{}` of a `@flags` enum carries a value",
+                                variant
+                            )),
+                            self.source.file,
+                            expression.pos,
+                            Note("its variants are bits, which can't carry anything")
+                        ));
+                    }
+
+                    if declared.contains(&variant) {
+                        return Err(response!(
+                            Wrong(format!("variant `{}` defined more than once", variant)),
+                            self.source.file,
+                            expression.pos
+                        ));
+                    }
+
+                    declared.push(variant)
+                }
+
+                Ok(())
+            }
+
+            Match(ref subject, ref arms) => {
+                self.visit_expression(subject)?;
+
+                let (name, variants) = self.matched_enum(subject)?;
+
+                let mut matched = Vec::new();
+                let mut rest = false; // whether `_` took what's left
+                let mut arm_type: Option<Type> = None;
+
+                for (variant, names, body, pos) in arms.iter() {
+                    if rest {
+                        return Err(response!(
+                            Wrong("unreachable arm, `_` above matches everything left"),
+                            self.source.file,
+                            pos
+                        ));
+                    }
+
+                    match variant {
+                        Some(variant) if matched.contains(variant) => {
+                            return Err(response!(
+                                Wrong(format!("variant `{}` matched more than once", variant)),
+                                self.source.file,
+                                pos
+                            ))
+                        }
+
+                        Some(variant) => matched.push(variant.clone()),
+                        None => rest = true,
+                    }
+
+                    let bindings = self.arm_bindings(&name, &variants, variant, names, pos)?;
+
+                    let mut scope = self.scope_with(bindings);
+
+                    scope.visit_expression(body)?;
+                    let body_type = scope.type_expression(body)?;
+
+                    drop(scope);
+
+                    match arm_type {
+                        Some(ref expected) if *expected != body_type => {
+                            let (expected, found) = disambiguate(expected, &body_type);
+
+                            return Err(response!(
+                                Wrong(format!(
+                                    "mismatched types, expected `{}` got `{}`",
+                                    expected, found
+                                )),
+                                self.source.file,
+                                body.pos
+                            ));
+                        }
+
+                        Some(_) => (),
+                        None => arm_type = Some(body_type),
+                    }
+                }
+
+                let missing = variants
+                    .iter()
+                    .filter(|variant| !rest && !matched.contains(&variant.0))
+                    .map(|variant| format!("`{}`", variant.0))
+                    .collect::<Vec<_>>();
+
+                if !missing.is_empty() {
+                    return Err(response!(
+                        Wrong(format!("non-exhaustive match on `{}`", name)),
+                        self.source.file,
+                        expression.pos,
+                        Note(format!(
+                            "not covered: {}, add an arm for each or `_ =>` for the rest",
+                            missing.join(", ")
+                        ))
+                    ));
+                }
+
+                Ok(())
+            }
+
+            Trait(_, ref params, ref defaults) => {
                 let mut name_buffer = Vec::new();
 
                 for &(ref name, _) in params.iter() {
@@ -1277,18 +1636,46 @@ impl<'v> Visitor<'v> {
                     name_buffer.push(&name)
                 }
 
+                // a default method's `self` is whatever implements the trait
+                let kind = self.type_expression(expression)?;
+
+                for (_, function) in defaults.iter() {
+                    let mut scope = self.scope();
+                    let _implementing = scope.inside.enter(Inside::Implement(kind.clone()));
+
+                    scope.assign_str("self", kind.clone());
+                    scope.visit_expression(function)?
+                }
+
                 Ok(())
             }
 
             Call(ref expr, ref args) => {
+                if let Some(builtin) = self.builtin(expr)? {
+                    self.builtins.insert(expr.pos.clone(), builtin);
+                }
+
                 self.visit_expression(expr)?;
 
-                self.inside.push(Inside::Calling(expr.pos.clone()));
+                // the types of what's called from a foreign module are named as it names them
+                let _foreign = match expr.node {
+                    Identifier(ref name) => self
+                        .symtab
+                        .get_foreign_module(name)
+                        .map(|content| self.inside.enter(Inside::ForeignModule(content.clone()))),
+                    _ => None,
+                };
+
+                let calling = self.inside.enter(Inside::Calling(expr.pos.clone()));
 
                 let expression_type = self.type_expression(expr)?;
 
-                if let TypeNode::Func(ref params, _, ref _func, .., is_method) = expression_type.node
+                if let TypeNode::Func(ref params, _, ref func, .., is_method) = expression_type.node
                 {
+                    if let Some(ref mut calls) = self.calls {
+                        calls.push((expr.pos.clone(), expression_type.clone()))
+                    }
+
                     // // this is where we visit the func, nvm
                     // if let Some(func) = func {
                     //   self.visit_expression(
@@ -1310,6 +1697,11 @@ impl<'v> Visitor<'v> {
                         let param_type = self.deid(param_type.clone())?;
 
                         if args.len() <= i {
+                            // a splat parameter takes no arguments just fine
+                            if let TypeMode::Splat(_) = param_type.mode {
+                                break;
+                            }
+
                             let last_arg_pos = match args.last() {
                                 Some(arg) => {
                                     let arg_pos = arg.pos.clone();
@@ -1321,10 +1713,16 @@ impl<'v> Visitor<'v> {
                                 }
                             };
 
+                            let splat = matches!(
+                                params.last().map(|p| &p.mode),
+                                Some(TypeMode::Splat(_))
+                            );
+
                             return Err(response!(
                                 Wrong(format!(
-                                    "mismatched argument count, expected {} got {}",
-                                    params.len(),
+                                    "mismatched argument count, expected {}{} got {}",
+                                    if splat { "at least " } else { "" },
+                                    params.len() - splat as usize,
                                     args.len()
                                 )),
                                 self.source.file,
@@ -1332,25 +1730,91 @@ impl<'v> Visitor<'v> {
                             ));
                         }
 
+                        if let Some(spread) = self.forwarded(&args[i])? {
+                            if let TypeMode::Splat(_) = param_type.mode {
+                                self.check_forwarded(&param_type, &spread, &args[i])?;
+
+                                continue;
+                            }
+
+                            return Err(response!(
+                                Wrong(format!(
+                                    "can't forward `...{}` into a parameter of type `{}`, \
+                                     only into a splat parameter",
+                                    spread.node, param_type
+                                )),
+                                self.source.file,
+                                args[i].pos
+                            ));
+                        }
+
                         self.visit_expression(&args[i])?;
 
-                        let arg_type = self.type_expression(&args[i])?;
+                        let mut arg_type = self.type_expression(&args[i])?;
+
+                        // a splat parameter passed on as a whole is an array
+                        match (&arg_type.mode, &param_type.mode) {
+                            // the splat parameter itself is a table, its arguments need a `*`
+                            (TypeMode::Splat(_), TypeMode::Splat(_))
+                                if matches!(args[i].node, ExpressionNode::Identifier(_)) =>
+                            {
+                                let name = match args[i].node {
+                                    ExpressionNode::Identifier(ref name) => name,
+                                    _ => unreachable!(),
+                                };
+
+                                response!(
+                                    Wrong(format!(
+                                        "can't pass splat `...{}` as a single argument",
+                                        arg_type.node
+                                    )),
+                                    self.source.file,
+                                    args[i].pos
+                                );
+
+                                response!(Note(format!(
+                                    "forward its arguments with `*`, as in `*{}`",
+                                    name
+                                )));
+
+                                return Err(());
+                            }
+
+                            (TypeMode::Splat(_), TypeMode::Splat(_)) => (),
+                            (TypeMode::Splat(_), _) => {
+                                arg_type = Type::from(TypeNode::Array(
+                                    Rc::new(Type::from(arg_type.node.clone())),
+                                    None,
+                                ))
+                            }
+                            _ => (),
+                        }
+
+                        self.check_not_struct(&param_type.node, &arg_type, &args[i].pos)?;
 
                         if !param_type
                             .node
                             .check_expression(&Parser::fold_expression(&args[i]).node)
-                            && arg_type.node != param_type.node
+                            && param_type.node != arg_type.node
                         {
-                            return Err(response!(
+                            let (expected, found) = disambiguate(&param_type.node, &arg_type);
+
+                            response!(
                                 Wrong(format!(
                                     "mismatched types, expected type `{}` got `{}`",
-                                    param_type.node, arg_type
+                                    expected, found
                                 )),
                                 self.source.file,
                                 args[i].pos
-                            ));
+                            );
+
+                            self.note_missing_else(&args[i]);
+
+                            return Err(());
                         }
 
+                        self.escapes.keep(&args[i], &arg_type);
+
                         let arg_type = if i < args.len() {
                             self.visit_expression(&args[i])?;
                             self.type_expression(&args[i])?
@@ -1367,11 +1831,19 @@ impl<'v> Visitor<'v> {
                         }
                     }
 
+                    let mut splatting = None;
+
                     if actual_arg_len > params.len() {
                         let last = self.deid(params.last().unwrap().clone())?;
 
                         if let TypeMode::Splat(_) = last.mode {
                             for splat in &args[params.len()..] {
+                                if let Some(spread) = self.forwarded(splat)? {
+                                    self.check_forwarded(&last, &spread, splat)?;
+
+                                    continue;
+                                }
+
                                 self.visit_expression(&splat)?;
                                 let splat_type = self.type_expression(&splat)?;
 
@@ -1390,13 +1862,16 @@ impl<'v> Visitor<'v> {
                             }
                         }
 
-                        self.inside
-                            .push(Inside::Splat(Some(actual_arg_len - params.len())))
+                        splatting = Some(
+                            self.inside
+                                .enter(Inside::Splat(Some(actual_arg_len - params.len()))),
+                        )
                     }
 
                     self.visit_expression(expr)?;
 
-                    self.inside.pop();
+                    drop(splatting);
+                    drop(calling);
 
                     if actual_arg_len != params.len() {
                         match params.last().unwrap().mode {
@@ -1415,6 +1890,11 @@ impl<'v> Visitor<'v> {
                             }
                         }
                     }
+
+                    if let Some(ref func) = *func {
+                        self.inliner.offer(&expression.pos, expr, func, args);
+                        self.purity.call(&expression.pos, expr, func)
+                    }
                 }
 
                 Ok(())
@@ -1431,7 +1911,7 @@ impl<'v> Visitor<'v> {
                     let ident_type = self.type_expression(&ident)?;
 
                     match ident_type.node {
-                        TypeNode::Struct(..) | TypeNode::Trait(..) => {
+                        TypeNode::Struct(..) | TypeNode::Trait(..) | TypeNode::Enum(..) => {
                             return_type = Type::from(ident_type.node)
                         }
                         _ => {
@@ -1446,71 +1926,188 @@ impl<'v> Visitor<'v> {
 
                 return_type = Type::from(return_type.node.clone());
 
-                let mut found_splat = false;
+                for (i, param) in params.iter().enumerate() {
+                    // the splat takes every argument from its position on, so it goes last
+                    if matches!(param.1.mode, TypeMode::Splat(_)) && i + 1 < params.len() {
+                        let after = &params[i + 1..];
 
-                for param in params.iter() {
-                    if let TypeMode::Splat(_) = param.1.mode {
-                        if found_splat {
+                        if let Some(other) =
+                            after.iter().find(|p| matches!(p.1.mode, TypeMode::Splat(_)))
+                        {
                             return Err(response!(
                                 Wrong("can't have multiple splat parameters in function"),
                                 self.source.file,
-                                expression.pos
+                                param_pos(&expression.pos, &other.0)
                             ));
                         }
 
-                        found_splat = true
+                        response!(
+                            Wrong(format!(
+                                "splat parameter `{}` must be the last parameter",
+                                param.0
+                            )),
+                            self.source.file,
+                            param_pos(&expression.pos, &param.0)
+                        );
+
+                        response!(Note(format!(
+                            "it takes every argument from its position on, \
+                             so `{}` would never get one",
+                            after[0].0
+                        )));
+
+                        return Err(());
                     }
 
-                    frame_hash.insert(param.0.clone(), self.deid(param.1.clone())?);
-                }
+                    let level = self.pragmas.level(Lint::ImplicitAny);
+
+                    if matches!(param.1.node, TypeNode::Any) && level != LintLevel::Allow {
+                        if let Some(pos) = untyped_param(&expression.pos, &param.0) {
+                            let why = if self.pragmas.denies(Lint::ImplicitAny) {
+                                "this module is `#!strict`, so say"
+                            } else {
+                                "say"
+                            };
 
-                if *is_method {
-                    let mut found = false;
+                            if let Some(reported) = level
+                                .response(format!("parameter `{}` is implicitly `any`", param.0))
+                            {
+                                response!(
+                                    reported,
+                                    self.source.file,
+                                    pos,
+                                    Note(format!("{} what it is, e.g. `{}: any`", why, param.0))
+                                );
+                            }
 
-                    for inside in self.inside.iter().rev() {
-                        // ffs
-                        if let Inside::Implement(_) = inside {
-                            found = true;
+                            if level == LintLevel::Deny {
+                                return Err(());
+                            }
                         }
                     }
 
-                    if !found {
-                        return Err(response!(
-                            Wrong("can't define method outside implementation"),
-                            self.source.file,
-                            expression.pos
-                        ));
-                    }
+                    frame_hash.insert(param.0.clone(), self.deid(param.1.clone())?);
                 }
 
-                self.symtab.put_frame(Frame::from(frame_hash));
+                // lua takes no more, and the module level spills past them, but a function can't
+                let locals = params.len() + active_locals(body);
 
-                self.inside.push(Inside::Function);
+                if locals > LOCALS {
+                    response!(
+                        Wrong(format!("function has {} locals at once, over {}", locals, LOCALS)),
+                        self.source.file,
+                        expression.pos
+                    );
 
-                self.visit_expression(body)?;
+                    response!(Note(
+                        "lua allows 200 and wu needs a few of them, \
+                         keep some in a table or split the function up"
+                    ));
 
-                let body_type = self.type_expression(body)?;
+                    return Err(());
+                }
+
+                if *is_method && self.implementing().is_none() {
+                    return Err(response!(
+                        Wrong("can't define method outside implementation"),
+                        self.source.file,
+                        expression.pos
+                    ));
+                }
+
+                let body_type = {
+                    let mut scope = self.scope_with(frame_hash);
+                    let _function = scope.inside.enter(Inside::Function);
+
+                    scope.complexity.enter(&expression.pos);
 
-                self.inside.pop();
+                    scope.visit_expression(body)?;
 
-                self.pop_scope();
+                    if let Some(measure) = scope.complexity.leave() {
+                        scope.warn_complexity(&measure, params.len(), &expression.pos)?
+                    }
+
+                    scope.type_expression(body)?
+                };
+
+                // a block's value is its last expression, returned as well
+                match body.node {
+                    ExpressionNode::Block(ref statements) => {
+                        if let Some(StatementNode::Expression(ref last)) =
+                            statements.last().map(|last| &last.node)
+                        {
+                            self.escapes.spread(last, &body_type)
+                        }
+                    }
+                    _ => self.escapes.spread(body, &body_type),
+                }
 
                 if return_type.node != body_type.node {
-                    Err(response!(
+                    let (expected, found) = disambiguate(&return_type, &body_type);
+
+                    let returned = self.returned_pos(body, &return_type);
+                    let signature = signature_pos(&expression.pos);
+                    let declared = return_type_pos(&expression.pos);
+
+                    let spans = Spans::new(&returned, format!("this is `{}`", found));
+
+                    let spans = match declared {
+                        Some(ref declared) => spans
+                            .label(declared, format!("expected `{}` because of this", expected)),
+                        None => spans.label(
+                            &signature,
+                            "expected `nil`, as no return type is declared",
+                        ),
+                    };
+
+                    response!(
                         Wrong(format!(
                             "mismatched return type, expected `{}` got `{}`",
-                            return_type, body_type
+                            expected, found
                         )),
                         self.source.file,
-                        body.pos
-                    ))
+                        spans
+                    );
+
+                    if let ExpressionNode::Block(ref statements) = body.node {
+                        if let Some(StatementNode::Expression(ref last)) =
+                            statements.last().map(|last| &last.node)
+                        {
+                            self.note_missing_else(last)
+                        }
+                    }
+
+                    Err(())
                 } else {
                     Ok(())
                 }
             }
 
-            Index(ref left, ref index, _) => {
-                let mut left_type = self.type_expression(left)?;
+            Index(ref left, ref index, is_element) => {
+                if self.static_builtin(left, index, is_element).is_some() {
+                    if self.builtins.contains_key(&expression.pos) {
+                        return Ok(());
+                    }
+
+                    return Err(response!(
+                        Wrong(format!(
+                            "`{} {}` can only be called",
+                            left.pos.get_lexeme(),
+                            index.pos.get_lexeme()
+                        )),
+                        self.source.file,
+                        index.pos
+                    ));
+                }
+
+                // names are looked up below, anything else is checked like any expression, e.g.
+                // the call in `make() name`
+                if !matches!(left.node, Identifier(_)) {
+                    self.visit_expression(left)?
+                }
+
+                let left_type = self.type_expression(left)?;
+                let mut left_type = self.latest_struct(left_type);
 
                 if let TypeMode::Splat(_) = left_type.mode {
                     left_type = Type::from(TypeNode::Array(
@@ -1520,8 +2117,26 @@ impl<'v> Visitor<'v> {
                 }
 
                 match left_type.node {
+                    TypeNode::Array(..)
+                    | TypeNode::Optional(_)
+                    | TypeNode::Str
+                    | TypeNode::Tuple(_)
+                    | TypeNode::Int
+                    | TypeNode::Float
+                        if !is_element =>
+                    {
+                        self.called_builtin(expression, &left_type, index)?
+                    }
+
+                    // values of `@flags` enums have `contains`, the enum itself its variants
+                    TypeNode::Enum(.., true)
+                        if !is_element && !left_type.mode.strong_cmp(&TypeMode::Undeclared) =>
+                    {
+                        self.called_builtin(expression, &left_type, index)?
+                    }
+
                     TypeNode::Array(_, ref len) => {
-                        self.inside.push(Inside::Nothing);
+                        let _nothing = self.inside.enter(Inside::Nothing);
 
                         self.visit_expression(index)?;
 
@@ -1559,11 +2174,10 @@ impl<'v> Visitor<'v> {
                     }
 
                     TypeNode::Module(ref content, is_foreign) => {
-                        self.inside.push(Inside::Nothing);
+                        let _nothing = self.inside.enter(Inside::Nothing);
 
-                        if is_foreign {
-                            self.inside.push(Inside::ForeignModule(content.clone()))
-                        }
+                        let _foreign = is_foreign
+                            .then(|| self.inside.enter(Inside::ForeignModule(content.clone())));
 
                         if let Identifier(ref name) = index.node {
                             if !content.contains_key(name) {
@@ -1582,14 +2196,10 @@ impl<'v> Visitor<'v> {
                                 index.pos
                             ));
                         }
-
-                        if is_foreign {
-                            self.inside.pop();
-                        }
                     }
 
                     TypeNode::Struct(_, ref content, ref id) => {
-                        self.inside.push(Inside::Implement(left_type.clone()));
+                        let _implementing = self.inside.enter(Inside::Implement(left_type.clone()));
 
                         if let Identifier(ref name) = index.node {
                             if !content.contains_key(name) && !self.is_implemented(id, name) {
@@ -1630,6 +2240,10 @@ impl<'v> Visitor<'v> {
                         }
                     }
 
+                    TypeNode::Enum(..) => {
+                        self.type_expression(expression)?;
+                    }
+
                     TypeNode::Any => (),
 
                     _ => {
@@ -1644,6 +2258,37 @@ impl<'v> Visitor<'v> {
                 Ok(())
             }
 
+            Cast(ref value, ref t) => {
+                self.visit_expression(value)?;
+
+                if t.node.strong_cmp(&TypeNode::Str) {
+                    let value_t = self.type_expression(value)?;
+
+                    match value_t.node {
+                        TypeNode::Float => {
+                            self.floats.insert(value.pos.clone());
+                        }
+
+                        TypeNode::Str => {
+                            self.strings.insert(value.pos.clone());
+                        }
+
+                        // nothing to show but `nil` or an address, e.g. in `"{f}"`
+                        TypeNode::Func(..) | TypeNode::Nil => {
+                            return Err(response!(
+                                Wrong(format!("can't make `{}` a `str`", value_t)),
+                                self.source.file,
+                                value.pos
+                            ))
+                        }
+
+                        _ => (),
+                    }
+                }
+
+                Ok(())
+            }
+
             _ => Ok(()),
         }
     }
@@ -1674,7 +2319,9 @@ impl<'v> Visitor<'v> {
                     ident_type.mode = TypeMode::Regular
                 }
 
-                if let TypeNode::Struct(..) = ident_type.node {
+                if let TypeNode::Struct(..) | TypeNode::Trait(..) | TypeNode::Enum(..) =
+                    ident_type.node
+                {
                     variable_type = Type::from(ident_type.node)
                 } else {
                     return Err(response!(
@@ -1689,33 +2336,45 @@ impl<'v> Visitor<'v> {
 
             if let &Some(ref right) = right {
                 match right.node {
-                    Function(..) | Block(_) | If(..) | While(..) | For(..) => (),
-                    Struct(..) | Trait(..) => {
-                        self.assign(name.to_owned(), Type::from(TypeNode::Any)) // temp
-                    }
+                    // types are already declared ahead, as they are or as `any` in a cycle
+                    Function(..) | Block(_) | If(..) | While(..) | For(..) | Struct(..)
+                    | Trait(..) | Enum(..) => (),
                     _ => self.visit_expression(right)?,
                 }
 
                 let mut right_type = self.type_expression(&right)?;
 
-                if is_splat {
+                // a new binding is its own, even when it starts out as a constant
+                if is_splat || right_type.mode.strong_cmp(&TypeMode::Immutable) {
                     right_type.mode = TypeMode::Regular
                 }
 
+                if !is_splat {
+                    self.escapes.keep(right, &right_type)
+                }
+
                 if !variable_type.node.strong_cmp(&TypeNode::Nil) {
+                    self.check_not_struct(&variable_type.node, &right_type, &right.pos)?;
+
                     if !variable_type
                         .node
                         .check_expression(&Parser::fold_expression(right).node)
                         && variable_type.node != right_type.node
                     {
-                        return Err(response!(
+                        let (expected, found) = disambiguate(&variable_type.node, &right_type.node);
+
+                        response!(
                             Wrong(format!(
                                 "mismatched types, expected type `{}` got `{}`",
-                                variable_type.node, right_type.node
+                                expected, found
                             )),
                             self.source.file,
                             right.pos
-                        ));
+                        );
+
+                        self.note_missing_else(right);
+
+                        return Err(());
                     } else {
                         self.assign(name.to_owned(), variable_type.to_owned())
                     }
@@ -1724,23 +2383,193 @@ impl<'v> Visitor<'v> {
                 }
 
                 match right.node {
-                    Function(..) | Block(_) | If(..) | While(..) | For(..) | Struct(..) | Trait(..) => {
-                        self.visit_expression(right)?
-                    }
+                    Function(..) | Block(_) | If(..) | While(..) | For(..) | Struct(..) | Trait(..)
+                    | Enum(..) => self.visit_expression(right)?,
                     _ => (),
                 }
             } else {
                 self.assign(name.to_owned(), variable_type.to_owned())
             }
 
+            if let Some(ref mut types) = self.types {
+                let (line, start, end) = declared_span(pos, name);
+
+                if let Some(t) = self.symtab.fetch(name) {
+                    types.insert(Pos((line, (pos.0).1.clone()), (start, end)), t);
+                }
+            }
+
             Ok(())
         } else {
             unreachable!()
         }
     }
 
-    pub fn type_statement(&mut self, statement: &Statement) -> Result<Type, ()> {
-        use self::StatementNode::*;
+    // the values `a, b := right` takes apart, when `right` is a tuple
+    fn returned_tuple(&mut self, right: &Option<Expression>) -> Result<Option<Vec<Type>>, ()> {
+        use self::ExpressionNode::*;
+
+        let right = match *right {
+            Some(ref right) => right,
+            None => return Ok(None),
+        };
+
+        match right.node {
+            Function(..) | Block(_) | If(..) | While(..) | For(..) | Struct(..) | Trait(..)
+            | Enum(..) => return Ok(None),
+            _ => self.visit_expression(right)?,
+        }
+
+        let right_type = self.type_expression(right)?;
+
+        match (right_type.node, right_type.mode) {
+            (TypeNode::Tuple(content), TypeMode::Regular | TypeMode::Immutable) => Ok(Some(content)),
+            _ => Ok(None),
+        }
+    }
+
+    fn destructure(
+        &mut self,
+        declared: &Type,
+        names: &[String],
+        content: &[Type],
+        right: &Expression,
+        pos: &Pos,
+    ) -> Result<(), ()> {
+        if names.len() != content.len() {
+            return Err(response!(
+                Wrong(format!(
+                    "can't destructure `{}` into {} names",
+                    TypeNode::Tuple(content.to_vec()),
+                    names.len()
+                )),
+                self.source.file,
+                right.pos
+            ));
+        }
+
+        let declared = self.deid(declared.clone())?;
+
+        for (name, element) in names.iter().zip(content.iter()) {
+            let element = Type::from(element.node.clone());
+
+            let t = if declared.node.strong_cmp(&TypeNode::Nil) {
+                element
+            } else if declared.node != element.node {
+                let (expected, found) = disambiguate(&declared.node, &element.node);
+
+                return Err(response!(
+                    Wrong(format!(
+                        "mismatched types, expected type `{}` got `{}` for `{}`",
+                        expected, found, name
+                    )),
+                    self.source.file,
+                    right.pos
+                ));
+            } else {
+                Type::from(declared.node.clone())
+            };
+
+            self.assign(name.to_owned(), t);
+
+            if let Some(ref mut types) = self.types {
+                let (line, start, end) = declared_span(pos, name);
+
+                if let Some(t) = self.symtab.fetch(name) {
+                    types.insert(Pos((line, (pos.0).1.clone()), (start, end)), t);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // the type of the innermost expression or declaration around `line`:`column`
+    pub fn type_at(&self, line: usize, column: usize) -> Option<Type> {
+        self.types
+            .as_ref()?
+            .iter()
+            .filter(|(pos, _)| (pos.0).0 == line && (pos.1).0 <= column && column <= (pos.1).1)
+            .min_by_key(|(pos, _)| (pos.1).1 - (pos.1).0)
+            .map(|(_, t)| t.clone())
+    }
+
+    // the required members still unassigned in the `new` whose braces are around `line`:`column`
+    pub fn completions_at(
+        &self,
+        tokens: &[Token],
+        line: usize,
+        column: usize,
+    ) -> Option<Members> {
+        let at = (line, column);
+
+        self.initializations
+            .as_ref()?
+            .iter()
+            .filter_map(|(pos, unassigned)| {
+                let (open, close) = delimited_after(tokens, pos, ("{", "}"))?;
+
+                if open < at && at <= close {
+                    Some((open, unassigned))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(open, _)| *open)
+            .map(|(_, unassigned)| unassigned.clone())
+    }
+
+    // the function called around `line`:`column`, and how many arguments come before it
+    pub fn signature_at(
+        &self,
+        tokens: &[Token],
+        line: usize,
+        column: usize,
+    ) -> Option<(Type, usize)> {
+        let at = (line, column);
+
+        self.calls
+            .as_ref()?
+            .iter()
+            .filter_map(|(pos, called)| {
+                let (open, close) = delimited_after(tokens, pos, ("(", ")"))?;
+
+                if open < at && at <= close {
+                    Some((open, called))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(open, _)| *open)
+            .map(|(open, called)| (called.clone(), arguments_before(tokens, open, at)))
+    }
+
+    // `t` as users write it, with the members of structs, traits and modules listed below
+    pub fn describe(&mut self, t: &Type) -> String {
+        let members = match t.node {
+            TypeNode::Struct(_, ref content, _)
+            | TypeNode::Trait(_, ref content)
+            | TypeNode::Module(ref content, _) => content.clone(),
+            _ => return format!("{}", t),
+        };
+
+        let mut members = members.iter().collect::<Vec<_>>();
+        members.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut description = format!("{} {{\n", t);
+
+        for (name, member) in members {
+            let member = silently(|| self.deid(member.clone())).unwrap_or_else(|_| member.clone());
+
+            description.push_str(&format!("  {}: {}\n", name, member))
+        }
+
+        description.push('}');
+        description
+    }
+
+    pub fn type_statement(&mut self, statement: &Statement) -> Result<Type, ()> {
+        use self::StatementNode::*;
 
         let t = match statement.node {
             Expression(ref expression) => self.type_expression(expression)?,
@@ -1757,20 +2586,22 @@ impl<'v> Visitor<'v> {
         Ok(t)
     }
 
-    fn type_expression(&mut self, expression: &Expression) -> Result<Type, ()> {
+    pub fn type_expression(&mut self, expression: &Expression) -> Result<Type, ()> {
         use self::ExpressionNode::*;
 
+        let _processing = ice::processing(&self.source.file, &expression.pos);
+
+        let _level = match self.depth.enter() {
+            Some(level) => level,
+            None => return Err(too_deep(self.source, &expression.pos)),
+        };
+
+        self.spend(Resource::Time, 0, &expression.pos)?;
+
         let t = match expression.node {
             Identifier(ref name) => {
                 if name == "Self" {
-                    let mut maybe_res = None::<Type>;
-                    for inside in self.inside.iter() {
-                        if let Inside::Implement(ref s) = inside {
-                            maybe_res = Some(s.clone());
-                            break;
-                        }
-                    }
-                    if let Some(s) = maybe_res {
+                    if let Some(s) = self.implementing() {
                         return Ok(self.deid(s)?);
                     }
                 }
@@ -1845,7 +2676,16 @@ impl<'v> Visitor<'v> {
 
             Initialization(ref name, _) => Type::from(self.type_expression(name)?.node),
 
-            If(_, ref body, ..) => self.type_expression(body)?,
+            If(_, ref body, ref elses) => {
+                let body_type = self.type_expression(body)?;
+
+                // without an `else`, it's `nil` whenever no condition holds
+                match body_type.node {
+                    TypeNode::Nil | TypeNode::Any | TypeNode::Optional(_) => body_type,
+                    _ if has_else(elses) => body_type,
+                    _ => Type::from(TypeNode::Optional(Rc::new(body_type.node))),
+                }
+            }
 
             Struct(ref name, ref params, ref id) => {
                 let mut param_hash = HashMap::new();
@@ -1858,12 +2698,45 @@ impl<'v> Visitor<'v> {
                 }
 
                 Type::new(
-                    TypeNode::Struct(name.to_owned(), param_hash, id.to_string()),
+                    TypeNode::Struct(name.to_owned(), Rc::new(param_hash), id.to_string()),
+                    TypeMode::Undeclared,
+                )
+            }
+
+            Enum(ref name, ref variants, ref id, flags) => {
+                let mut declared = Vec::new();
+
+                for (variant, carried) in variants.iter() {
+                    let mut types = Vec::new();
+
+                    for t in carried.iter() {
+                        types.push(Type::from(self.deid(t.clone())?.node))
+                    }
+
+                    declared.push((variant.clone(), types))
+                }
+
+                Type::new(
+                    TypeNode::Enum(name.to_owned(), Rc::new(declared), id.to_string(), flags),
                     TypeMode::Undeclared,
                 )
             }
 
-            Trait(ref name, ref params) => {
+            Match(ref subject, ref arms) => {
+                let (name, variants) = self.matched_enum(subject)?;
+
+                match arms.first() {
+                    Some((variant, names, body, pos)) => {
+                        let bindings = self.arm_bindings(&name, &variants, variant, names, pos)?;
+
+                        self.scope_with(bindings).type_expression(body)?
+                    }
+
+                    None => Type::from(TypeNode::Nil),
+                }
+            }
+
+            Trait(ref name, ref params, _) => {
                 let mut param_hash = HashMap::new();
 
                 for param in params {
@@ -1873,11 +2746,24 @@ impl<'v> Visitor<'v> {
                     );
                 }
 
-                Type::from(TypeNode::Trait(name.to_owned(), param_hash))
+                Type::from(TypeNode::Trait(name.to_owned(), Rc::new(param_hash)))
             }
 
-            Index(ref array, ref index, _) => {
-                let mut kind = self.type_expression(array)?;
+            Index(ref array, ref index, is_element) => {
+                if let Some(builtin) = self.static_builtin(array, index, is_element) {
+                    return Ok(builtin.signature(&TypeNode::Nil));
+                }
+
+                let kind = self.type_expression(array)?;
+                let mut kind = self.latest_struct(kind);
+
+                if !is_element {
+                    if let Identifier(ref name) = index.node {
+                        if let Some(builtin) = Builtin::of(&kind, name) {
+                            return Ok(builtin.signature(&kind.node));
+                        }
+                    }
+                }
 
                 if let TypeMode::Splat(_) = kind.mode {
                     kind = Type::from(TypeNode::Array(
@@ -1893,6 +2779,10 @@ impl<'v> Visitor<'v> {
                     TypeNode::Module(ref content, _) => {
                         if let Identifier(ref name) = index.node {
                             if let Some(kind) = content.get(name) {
+                                if let TypeNode::Func(..) = kind.node {
+                                    self.lookups.insert(expression.pos.clone());
+                                }
+
                                 kind.clone()
                             } else {
                                 return Err(response!(
@@ -1906,6 +2796,44 @@ impl<'v> Visitor<'v> {
                         }
                     }
 
+                    TypeNode::Enum(ref enum_name, ref variants, ..) => {
+                        if let Identifier(ref name) = index.node {
+                            if !kind.mode.strong_cmp(&TypeMode::Undeclared) {
+                                return Err(response!(
+                                    Wrong(format!(
+                                        "can't access `{}` on a value of enum `{}`",
+                                        name, enum_name
+                                    )),
+                                    self.source.file,
+                                    index.pos,
+                                    Note("what a variant carries is taken out with `match`")
+                                ));
+                            }
+
+                            let value = Type::from(kind.node.clone());
+
+                            match variants.iter().find(|variant| variant.0 == *name) {
+                                Some((_, carried)) if carried.is_empty() => value,
+                                Some((_, carried)) => {
+                                    Type::function(carried.clone(), value, false)
+                                }
+
+                                None => {
+                                    return Err(response!(
+                                        Wrong(format!(
+                                            "no such variant `{}` in enum `{}`",
+                                            name, enum_name
+                                        )),
+                                        self.source.file,
+                                        index.pos
+                                    ))
+                                }
+                            }
+                        } else {
+                            unreachable!()
+                        }
+                    }
+
                     TypeNode::Trait(_, ref content) => {
                         if let Identifier(ref name) = index.node {
                             if let Some(kind) = content.get(name) {
@@ -1940,7 +2868,8 @@ impl<'v> Visitor<'v> {
                         response!(
                           Wrong(format!("can't access uninitialized value `{}` on undeclared `{}`", name, struct_name)),
                           self.source.file,
-                          index.pos
+                          index.pos,
+                          Note(self.value_of_struct(struct_name))
                         )
                       );
                                         }
@@ -1955,7 +2884,14 @@ impl<'v> Visitor<'v> {
                                     ));
                                 }
                             } else {
-                                self.symtab.get_implementation_force(struct_id, name)
+                                let member = self.symtab.get_implementation_force(struct_id, name);
+
+                                // constants are read as the values they are, but can't be changed
+                                if let TypeNode::Func(..) = member.node {
+                                    member
+                                } else {
+                                    Type::new(member.node, TypeMode::Immutable)
+                                }
                             }
                         } else {
                             unreachable!()
@@ -1976,9 +2912,9 @@ impl<'v> Visitor<'v> {
                 if let TypeNode::Func(_, ref return_type, ..) =
                     self.type_expression(expression)?.node
                 {
-                    (**return_type).clone()
+                    self.returned(expression, return_type)?
                 } else {
-                    panic!("BAM! (please submit an issue): called {:#?}", expression)
+                    panic!("BAM! (please submit an issue): called `{}`", expression.pos.get_lexeme())
                 }
             }
 
@@ -2006,27 +2942,27 @@ impl<'v> Visitor<'v> {
                     self.flag = Some(FlagContext::Block(None))
                 }
 
-                self.push_scope();
+                let mut scope = self.scope();
 
                 let block_type = if statements.len() > 0 {
                     for element in statements {
                         match element.node {
                             StatementNode::Expression(ref expression) => match expression.node {
                                 Function(..) | Block(_) | If(..) | While(..) | For(..) => {
-                                    self.type_expression(expression)?;
+                                    scope.type_expression(expression)?;
                                 }
 
                                 _ => (),
                             },
 
                             StatementNode::Return(ref return_type) => {
-                                let flag = self.flag.clone();
+                                let flag = scope.flag.clone();
 
                                 if let Some(ref flag) = flag {
                                     if let &FlagContext::Block(ref consistent) = flag {
                                         let return_type =
                                             if let Some(ref return_type) = *return_type {
-                                                self.type_expression(&return_type)?
+                                                scope.type_expression(return_type)?
                                             } else {
                                                 Type::from(TypeNode::Nil)
                                             };
@@ -2035,14 +2971,17 @@ impl<'v> Visitor<'v> {
                                             if return_type != *consistent {
                                                 return Err(
                           response!(
-                            Wrong(format!("mismatched types, expected `{}` found `{}`", consistent, return_type)),
-                            self.source.file,
+                            Wrong({
+                              let (expected, found) = disambiguate(consistent, &return_type);
+                              format!("mismatched types, expected `{}` found `{}`", expected, found)
+                            }),
+                            scope.source.file,
                             expression.pos
                           )
                         );
                                             }
                                         } else {
-                                            self.flag =
+                                            scope.flag =
                                                 Some(FlagContext::Block(Some(return_type.clone())))
                                         }
                                     }
@@ -2053,29 +2992,28 @@ impl<'v> Visitor<'v> {
                         }
                     }
 
-                    self.symtab.put_frame(self.symtab.last.clone());
-
                     let last = statements.last().unwrap();
-                    let implicit_type = self.type_statement(&last)?;
+                    let implicit_type = scope.reentered().type_statement(last)?;
 
-                    self.pop_scope();
+                    if let Some(FlagContext::Block(consistent)) = scope.flag.clone() {
+                        if let Some(ref consistent) = consistent {
+                            if implicit_type.node != consistent.node {
+                                return Err(response!(
+                                    Wrong({
+                                        let (expected, found) =
+                                            disambiguate(consistent, &implicit_type);
 
-                    if let Some(flag) = self.flag.clone() {
-                        if let FlagContext::Block(ref consistent) = flag {
-                            if let Some(ref consistent) = *consistent {
-                                if implicit_type.node != consistent.node {
-                                    return Err(response!(
-                                        Wrong(format!(
+                                        format!(
                                             "mismatched types, expected `{}` found `{}`",
-                                            consistent, implicit_type
-                                        )),
-                                        self.source.file,
-                                        last.pos
-                                    ));
-                                }
-                            } else {
-                                self.flag = Some(FlagContext::Block(Some(implicit_type.clone())))
+                                            expected, found
+                                        )
+                                    }),
+                                    scope.source.file,
+                                    last.pos
+                                ));
                             }
+                        } else {
+                            scope.flag = Some(FlagContext::Block(Some(implicit_type.clone())))
                         }
                     }
 
@@ -2084,7 +3022,7 @@ impl<'v> Visitor<'v> {
                     Type::from(TypeNode::Nil)
                 };
 
-                self.pop_scope();
+                drop(scope);
 
                 self.flag = flag_backup;
 
@@ -2102,6 +3040,15 @@ impl<'v> Visitor<'v> {
                     self.type_expression(right)?.node,
                 ) {
                     (ref a, ref op, ref b) => match **op {
+                        Add | Sub | Mul | Div if Componentwise::of(a, op, b).is_some() => {
+                            let (componentwise, made) = Componentwise::of(a, op, b).unwrap();
+
+                            self.componentwise
+                                .insert(expression.pos.clone(), componentwise);
+
+                            Type::from(made)
+                        }
+
                         Add | Sub | Mul | Div | Mod => {
                             if [a, b] != [&TypeNode::Nil, &TypeNode::Nil] {
                                 // real hack here
@@ -2154,6 +3101,23 @@ impl<'v> Visitor<'v> {
                             }
                         }
 
+                        // flags of one `@flags` enum, put together or picked out
+                        BitOr | BitAnd => match a {
+                            TypeNode::Enum(.., true) if a == b => Type::from(a.clone()),
+
+                            _ => {
+                                response!(
+                                    Wrong(format!("can't perform operation `{} {} {}`", a, op, b)),
+                                    self.source.file,
+                                    expression.pos
+                                );
+
+                                response!(Note("`|` and `&` combine flags of one `@flags` enum"));
+
+                                return Err(());
+                            }
+                        },
+
                         Pow => match a {
                             TypeNode::Float | TypeNode::Int => match b {
                                 TypeNode::Float | TypeNode::Int => Type::from(a.clone()),
@@ -2179,17 +3143,66 @@ impl<'v> Visitor<'v> {
                             }
                         },
 
-                        And | Or => {
-                            if a == b && *a == TypeNode::Bool {
+                        And | Or => match (a, &**op) {
+                            // an optional `bool` on the left falls back where it's `nil`, below
+                            _ if a == b && matches!(a, TypeNode::Bool) => {
                                 Type::from(TypeNode::Bool)
-                            } else {
-                                return Err(response!(
+                            }
+
+                            // `maybe or default`, what's left of it when it's `nil`
+                            (TypeNode::Optional(ref inner), Or) => {
+                                if can_be_false(inner) {
+                                    self.nil_checks.insert(expression.pos.clone());
+                                }
+
+                                match b {
+                                    TypeNode::Optional(_) | TypeNode::Nil if a == b => {
+                                        Type::from(a.clone())
+                                    }
+
+                                    _ if **inner == *b => Type::from((**inner).clone()),
+
+                                    _ => {
+                                        return Err(response!(
+                                            Wrong(format!(
+                                                "mismatched default for `{}`, \
+                                                 expected `{}` got `{}`",
+                                                a, inner, b
+                                            )),
+                                            self.source.file,
+                                            right.pos
+                                        ))
+                                    }
+                                }
+                            }
+
+                            // `maybe and value`, `nil` when `maybe` is
+                            (TypeNode::Optional(ref inner), And) => {
+                                if can_be_false(inner) {
+                                    self.nil_checks.insert(expression.pos.clone());
+                                }
+
+                                match b {
+                                    TypeNode::Optional(_) | TypeNode::Nil => Type::from(b.clone()),
+                                    _ => Type::from(TypeNode::Optional(Rc::new(b.clone()))),
+                                }
+                            }
+
+                            _ => {
+                                response!(
                                     Wrong(format!("can't perform operation `{} {} {}`", a, op, b)),
                                     self.source.file,
                                     expression.pos
+                                );
+
+                                response!(Note(
+                                    "`and` and `or` take `bool`s, or an optional on the left, \
+                                     as in `maybe or default`"
                                 ));
+
+                                return Err(());
                             }
-                        }
+                        },
 
                         PipeLeft => {
                             if let TypeNode::Func(_, ret, ..) = a {
@@ -2222,7 +3235,7 @@ impl<'v> Visitor<'v> {
                         }
 
                         Concat => {
-                            if *a == TypeNode::Str {
+                            if matches!(*a, TypeNode::Str | TypeNode::Char) {
                                 match *b {
                                     TypeNode::Func(..) | TypeNode::Array(..) => {
                                         return Err(response!(
@@ -2235,6 +3248,45 @@ impl<'v> Visitor<'v> {
                                         ))
                                     }
 
+                                    // lua only concatenates strings and numbers, and prints
+                                    // floats like `2.0` its own way, so these are converted first
+                                    TypeNode::Int | TypeNode::Float | TypeNode::Bool => {
+                                        let message =
+                                            format!("implicit conversion of `{}` to `str`", b);
+
+                                        match self.pragmas.level(Lint::Conversions) {
+                                            LintLevel::Allow => (),
+                                            LintLevel::Warn => response!(
+                                                Weird(message),
+                                                self.source.file,
+                                                right.pos
+                                            ),
+                                            LintLevel::Deny => {
+                                                let why = if self.pragmas.denies(Lint::Conversions)
+                                                {
+                                                    "this module is `#!strict`, so convert"
+                                                } else {
+                                                    "convert"
+                                                };
+
+                                                return Err(response!(
+                                                    Wrong(message),
+                                                    self.source.file,
+                                                    right.pos,
+                                                    Note(format!("{} it with `as str`", why))
+                                                ));
+                                            }
+                                        }
+
+                                        self.conversions.insert(right.pos.clone());
+
+                                        if *b == TypeNode::Float {
+                                            self.floats.insert(right.pos.clone());
+                                        }
+
+                                        Type::from(TypeNode::Str)
+                                    }
+
                                     _ => Type::from(TypeNode::Str),
                                 }
                             } else {
@@ -2261,15 +3313,57 @@ impl<'v> Visitor<'v> {
                 }
             }
 
-            Module(ref content) => {
+            Module(ref content, ref exports) => {
                 if let ExpressionNode::Block(ref ast) = content.node {
                     let mut visitor = Visitor::new(ast, self.source, self.root.clone());
+                    visitor.imports = self.imports.clone();
+                    visitor.loading = self.loading.clone();
+                    visitor.budget = self.budget.clone();
+                    visitor.exports = self.exports.clone();
+                    visitor.depth = self.depth.clone();
 
                     visitor.visit()?;
 
-                    let content_type = visitor.module_content.clone();
+                    self.conversions.extend(visitor.conversions.drain());
+                    self.floats.extend(visitor.floats.drain());
+                    self.nil_checks.extend(visitor.nil_checks.drain());
+                    self.componentwise.extend(visitor.componentwise.drain());
+                    self.strings.extend(visitor.strings.drain());
+                    self.inliner.extend(visitor.inliner);
+                    self.purity.extend(visitor.purity);
+                    self.escapes.extend(visitor.escapes);
+                    self.lookups.extend(visitor.lookups.drain());
+                    self.files.append(&mut visitor.files);
+
+                    let content_type = if let Some(ref exports) = *exports {
+                        let mut exported = HashMap::new();
+
+                        for name in exports.iter() {
+                            if exported.contains_key(name) {
+                                return Err(response!(
+                                    Wrong(format!("`{}` exported more than once", name)),
+                                    self.source.file,
+                                    expression.pos
+                                ));
+                            }
+
+                            if let Some(kind) = visitor.symtab.global(name) {
+                                exported.insert(name.clone(), kind);
+                            } else {
+                                return Err(response!(
+                                    Wrong(format!("can't export `{}`, no such binding in module", name)),
+                                    self.source.file,
+                                    expression.pos
+                                ));
+                            }
+                        }
+
+                        exported
+                    } else {
+                        visitor.module_content.clone()
+                    };
 
-                    Type::from(TypeNode::Module(content_type, false))
+                    Type::from(TypeNode::Module(Rc::new(content_type), false))
                 } else {
                     unreachable!()
                 }
@@ -2279,8 +3373,8 @@ impl<'v> Visitor<'v> {
                 let t = self.type_expression(&**expr)?;
 
                 if let TypeMode::Splat(_) = t.mode {
-                    if let Some(Inside::Splat(Some(ref len))) = self.inside.last() {
-                        Type::new(t.node, TypeMode::Unwrap(*len))
+                    if let Some(Inside::Splat(Some(len))) = self.inside.last() {
+                        Type::new(t.node, TypeMode::Unwrap(len))
                     } else {
                         Type::from(TypeNode::Any)
                     }
@@ -2305,91 +3399,461 @@ impl<'v> Visitor<'v> {
             _ => Type::from(TypeNode::Nil),
         };
 
-        self.deid(t)
+        let t = self.deid(t)?;
+
+        if let Some(ref mut types) = self.types {
+            types.insert(expression.pos.clone(), t.clone());
+        }
+
+        Ok(t)
+    }
+
+    // warns about a function that is longer, nests deeper or takes more parameters than allowed,
+    // failing once all are told where `complexity` is denied
+    fn warn_complexity(&self, measure: &Measure, params: usize, function: &Pos) -> Result<(), ()> {
+        let level = self.pragmas.level(Lint::Complexity);
+        let mut exceeded = false;
+
+        if let Some(max) = limit(Limit::Lines).filter(|max| measure.lines() > *max) {
+            exceeded = true;
+
+            if let Some(reported) =
+                level.response(format!("function is {} lines long", measure.lines()))
+            {
+                response!(
+                    reported,
+                    self.source.file,
+                    signature_pos(function),
+                    Note(format!("`{}` is {}", Limit::Lines.flag(), max))
+                );
+            }
+        }
+
+        if let Some((deepest, ref pos)) = measure.deepest {
+            if let Some(max) = limit(Limit::Nesting).filter(|max| deepest > *max) {
+                exceeded = true;
+
+                if let Some(reported) =
+                    level.response(format!("nested {} deep in its function", deepest))
+                {
+                    response!(
+                        reported,
+                        self.source.file,
+                        pos,
+                        Note(format!("`{}` is {}", Limit::Nesting.flag(), max))
+                    );
+                }
+            }
+        }
+
+        if let Some(max) = limit(Limit::Params).filter(|max| params > *max) {
+            exceeded = true;
+
+            if let Some(reported) =
+                level.response(format!("function takes {} parameters", params))
+            {
+                response!(
+                    reported,
+                    self.source.file,
+                    signature_pos(function),
+                    Note(format!("`{}` is {}", Limit::Params.flag(), max))
+                );
+            }
+        }
+
+        if exceeded && level == LintLevel::Deny {
+            Err(())
+        } else {
+            Ok(())
+        }
     }
 
     // `ensure_implicit` gets mad at wannabe implicit returns
     fn visit_block(
         &mut self,
-        content: &Vec<Statement>,
+        content: &[Statement],
         ensure_implicits: bool,
         module_level: bool,
     ) -> Result<(), ()> {
-        for (i, statement) in content.iter().enumerate() {
-            let mut statement = statement.clone();
+        let declared = declarations(content);
 
-            if let StatementNode::ExternBlock(ref s) = statement.node {
-                if let StatementNode::Variable(..) = s.node {
-                    statement.node = s.node.clone()
-                }
-            }
+        self.declare_ahead(&declared);
 
-            // ommiting functions, for that extra user-feel
-            if let StatementNode::Variable(ref kind, ref name, ref value, _) = statement.node {
-                if let Some(ref right) = *value {
-                    if let ExpressionNode::Function(ref params, ref retty, .., is_method) =
-                        right.node
-                    {
-                        let mut types = Vec::new();
+        self.resolution.enter(&declared);
+        let checked = self.visit_statements(content, ensure_implicits, module_level);
+        self.resolution.leave();
 
-                        for param in params.iter() {
-                            types.push(self.deid(param.1.clone())?)
-                        }
+        let mut failed = checked.is_err();
 
-                        let t = Type::from(TypeNode::Func(
-                            types,
-                            Rc::new(retty.clone()),
-                            Some(Rc::new(right.node.clone())),
-                            is_method,
-                        ));
+        for statement in content.iter() {
+            if let StatementNode::Variable(ref _t, ref name, Some(ref right), public) =
+                statement.binding().node
+            {
+                if let ExpressionNode::Function(..) = right.node {
+                    // its signature was wrong, which is told already
+                    if self.poisoned.contains(name) {
+                        failed = true;
+                        continue;
+                    }
 
-                        self.assign(name.to_owned(), t);
+                    let visited = self
+                        .visit_statement(statement)
+                        .and_then(|_| self.type_expression(right));
 
-                        continue;
-                    } else {
-                        self.visit_statement(&statement)?;
+                    match visited {
+                        Ok(t) if module_level && public => {
+                            self.module_content.insert(name.to_owned(), t);
+                        }
+
+                        Ok(_) => (),
 
-                        let t = self.type_expression(right)?;
+                        Err(()) => {
+                            self.recover(statement)?;
 
-                        if module_level {
-                            self.module_content.insert(name.clone(), t);
+                            failed = true
                         }
                     }
-                } else {
-                    if module_level {
-                        self.module_content.insert(name.clone(), kind.clone());
-                    }
                 }
             }
+        }
 
-            if ensure_implicits {
-                if i < content.len() - 1 {
-                    if let StatementNode::Expression(ref expression) = statement.node {
-                        self.ensure_no_implicit(expression)?
-                    }
-                }
-            }
+        if failed {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    // the resolution pass over a block, see `Resolution`
+    fn declare_ahead(&mut self, declared: &[Declaration]) {
+        let mut types = declared
+            .iter()
+            .filter(|declaration| declaration.kind == Declared::Type)
+            .collect::<Vec<_>>();
+
+        // a type goes in once the types its fields name are in. Whatever is left names itself,
+        // is part of a cycle, or needs an import, and sees those as `any` for now
+        loop {
+            let left = types.len();
+
+            types.retain(|declaration| {
+                match silently(|| self.type_expression(declaration.right)) {
+                    Ok(t) => {
+                        self.assign(declaration.name.clone(), t);
+                        false
+                    }
+
+                    Err(()) => true,
+                }
+            });
 
-            // at this point it's not a variable ...
-            self.visit_statement(&statement)?
+            if types.is_empty() || types.len() == left {
+                break;
+            }
         }
 
-        for statement in content.iter() {
-            if let StatementNode::Variable(ref _t, ref name, ref right, public) = statement.node {
-                if let Some(ref right) = *right {
-                    if let ExpressionNode::Function(..) = right.node {
-                        self.visit_statement(statement)?;
+        for declaration in types.iter() {
+            self.assign(declaration.name.clone(), Type::from(TypeNode::Any))
+        }
 
-                        let t = self.type_expression(right)?;
+        // typed twice more: the first take still sees the cycle as `any`, the second holds the
+        // first where the cycle is named, and `latest_struct` goes on from there
+        for _ in 0..2 {
+            for declaration in types.iter() {
+                if let Ok(t) = silently(|| self.type_expression(declaration.right)) {
+                    self.assign(declaration.name.clone(), t)
+                }
+            }
+        }
 
-                        if module_level && public {
-                            self.module_content.insert(name.to_owned(), t.clone());
-                        }
+        for declaration in declared {
+            if declaration.kind != Declared::Function {
+                continue;
+            }
+
+            if let Ok(t) = silently(|| self.signature(declaration.right)) {
+                self.assign(declaration.name.clone(), t);
+                self.inliner.declare(declaration.name, &declaration.right.node);
+            }
+        }
+    }
+
+    // the struct or enum `t` is, as it is declared now. Types spelled out before a struct was
+    // complete hold an earlier take on it, e.g. the fields of structs referring to each other
+    fn latest_struct(&self, t: Type) -> Type {
+        if let TypeNode::Struct(ref name, _, ref id) | TypeNode::Enum(ref name, _, ref id, _) =
+            t.node
+        {
+            if let Some(latest) = self.symtab.fetch(name) {
+                if matches!(
+                    latest.node,
+                    TypeNode::Struct(_, _, ref latest_id) | TypeNode::Enum(_, _, ref latest_id, _)
+                        if latest_id == id
+                ) {
+                    return Type::new(latest.node, t.mode);
+                }
+            }
+        }
+
+        t
+    }
+
+    // the enum `subject` is a value of, for a `match` on it
+    fn matched_enum(
+        &mut self,
+        subject: &Expression,
+    ) -> Result<(String, Rc<Vec<Variant>>), ()> {
+        let t = self.type_expression(subject)?;
+        let t = self.latest_struct(t);
+
+        match t.node {
+            TypeNode::Enum(ref name, _, _, true) => {
+                response!(
+                    Wrong(format!("can't match on the `@flags` enum `{}`", name)),
+                    self.source.file,
+                    subject.pos
+                );
+
+                response!(Note(
+                    "a value of it can hold several variants at once, check them with `contains`"
+                ));
+
+                Err(())
+            }
+
+            TypeNode::Enum(ref name, ref variants, ..)
+                if !t.mode.strong_cmp(&TypeMode::Undeclared) =>
+            {
+                Ok((name.clone(), variants.clone()))
+            }
+
+            TypeNode::Enum(ref name, ..) => Err(response!(
+                Wrong(format!("can't match on the enum `{0}` itself, only on values of it", name)),
+                self.source.file,
+                subject.pos
+            )),
+
+            _ => Err(response!(
+                Wrong(format!("can't match on `{}`, only on values of enums", t)),
+                self.source.file,
+                subject.pos
+            )),
+        }
+    }
+
+    // what an arm of a `match` on the enum `name` binds: the values its variant carries, by the
+    // names the arm gives them. `_` binds nothing, and an arm naming nothing ignores them
+    fn arm_bindings(
+        &self,
+        name: &str,
+        variants: &[Variant],
+        variant: &Option<String>,
+        names: &[String],
+        pos: &Pos,
+    ) -> Result<HashMap<String, Type>, ()> {
+        let mut bindings = HashMap::new();
+
+        let variant = match variant {
+            Some(variant) => variant,
+            None if names.is_empty() => return Ok(bindings),
+
+            None => {
+                return Err(response!(
+                    Wrong("`_` carries nothing to name"),
+                    self.source.file,
+                    pos
+                ))
+            }
+        };
+
+        let carried = match variants.iter().find(|declared| declared.0 == *variant) {
+            Some((_, carried)) => carried,
+
+            None => {
+                return Err(response!(
+                    Wrong(format!("no such variant `{}` in enum `{}`", variant, name)),
+                    self.source.file,
+                    pos
+                ))
+            }
+        };
+
+        if !names.is_empty() && names.len() != carried.len() {
+            return Err(response!(
+                Wrong(format!(
+                    "`{}` carries {} value{}, the arm names {}",
+                    variant,
+                    carried.len(),
+                    if carried.len() == 1 { "" } else { "s" },
+                    names.len()
+                )),
+                self.source.file,
+                pos
+            ));
+        }
+
+        for (name, t) in names.iter().zip(carried.iter()) {
+            if name == "_" {
+                continue;
+            }
+
+            if bindings.insert(name.clone(), t.clone()).is_some() {
+                return Err(response!(
+                    Wrong(format!("`{}` is bound more than once in the arm", name)),
+                    self.source.file,
+                    pos
+                ));
+            }
+        }
+
+        Ok(bindings)
+    }
+
+    // the type a function declaration gives its name, before its body is checked
+    fn signature(&mut self, function: &Expression) -> Result<Type, ()> {
+        if let ExpressionNode::Function(ref params, ref retty, .., is_method) = function.node {
+            let mut types = Vec::new();
+
+            for param in params.iter() {
+                types.push(self.deid(param.1.clone())?)
+            }
+
+            Ok(Type::from(TypeNode::Func(
+                types,
+                Rc::new(retty.clone()),
+                Some(Rc::new(function.node.clone())),
+                is_method,
+            )))
+        } else {
+            unreachable!()
+        }
+    }
+
+    // refuses `name` where it would be evaluated before the statement declaring it runs
+    fn reached(&self, name: &str, pos: &Pos) -> Result<(), ()> {
+        let declared = match self.resolution.ahead(name) {
+            Some(declared) => declared,
+            None => return Ok(()),
+        };
+
+        if self.inside.contains(&Inside::Function) {
+            return Ok(());
+        }
+
+        Err(response!(
+            Wrong(format!("`{}` is used before it's declared", name)),
+            self.source.file,
+            pos,
+            Note(format!("`{}` is declared on line {}", name, (declared.0).0))
+        ))
+    }
+
+    fn visit_statements(
+        &mut self,
+        content: &[Statement],
+        ensure_implicits: bool,
+        module_level: bool,
+    ) -> Result<(), ()> {
+        let mut failed = false;
+
+        for (i, statement) in content.iter().enumerate() {
+            let last = i == content.len() - 1;
+
+            // what uses a name declared wrong would only be wrong because of that
+            if used_by(statement).iter().any(|name| self.poisoned.contains(name)) {
+                self.recover(statement)?;
+
+                failed = true;
+                continue;
+            }
+
+            // the statements after a wrong one are still checked, showing every mistake at once
+            match self.visit_listed(statement, last, ensure_implicits, module_level) {
+                Ok(()) => {
+                    for name in declared_by(statement) {
+                        self.poisoned.remove(&name);
                     }
                 }
+
+                Err(()) => {
+                    self.recover(statement)?;
+
+                    failed = true
+                }
             }
         }
 
+        if failed {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn visit_listed(
+        &mut self,
+        statement: &Statement,
+        last: bool,
+        ensure_implicits: bool,
+        module_level: bool,
+    ) -> Result<(), ()> {
+        let mut statement = statement.binding().clone();
+
+        if let StatementNode::ExternBlock(ref s) = statement.node {
+            if let StatementNode::Variable(..) = s.node {
+                statement.node = s.node.clone()
+            }
+        }
+
+        // ommiting functions, for that extra user-feel
+        if let StatementNode::Variable(ref kind, ref name, ref value, _) = statement.node {
+            self.resolution.reach(name);
+
+            if let Some(ref right) = *value {
+                if let ExpressionNode::Function(..) = right.node {
+                    let t = self.signature(right)?;
+
+                    self.assign(name.to_owned(), t);
+                    self.inliner.declare(name, &right.node);
+
+                    return Ok(());
+                } else {
+                    self.visit_statement(&statement)?;
+
+                    let t = self.type_expression(right)?;
+
+                    if module_level {
+                        self.module_content.insert(name.clone(), t);
+                    }
+
+                    return Ok(());
+                }
+            } else {
+                if module_level {
+                    self.module_content.insert(name.clone(), kind.clone());
+                }
+            }
+        }
+
+        if ensure_implicits && !last {
+            if let StatementNode::Expression(ref expression) = statement.node {
+                self.ensure_no_implicit(expression)?
+            }
+        }
+
+        // at this point it's not a variable ...
+        self.visit_statement(&statement)
+    }
+
+    // after `statement` failed, leaves what it declares unchecked from here on; fails when
+    // checking on is pointless, past `--max-errors` or over the budget
+    fn recover(&mut self, statement: &Statement) -> Result<(), ()> {
+        if capped() || self.budget.exceeded().is_some() {
+            return Err(());
+        }
+
+        self.poisoned.extend(declared_by(statement));
+
         Ok(())
     }
 
@@ -2401,21 +3865,17 @@ impl<'v> Visitor<'v> {
         is_deep_run: bool,
     ) -> Result<String, ()> {
         let is_deep_run = is_deep_run || self.is_deep;
+        let is_relative = path.starts_with('.');
 
         let my_folder = Path::new(&root);
 
-        let mut file_path = format!("{}/{}.wu", my_folder.to_str().unwrap(), path);
+        // a file given without a folder imports from the working directory
+        let folder = if root.is_empty() { "." } else { my_folder.to_str().unwrap() };
 
-        // if !is_deep_run {
-        //     file_path = format!("./{}", file_path)
-        // }
-
-        if &file_path[0..1] == "/" && !is_deep_run {
-            file_path = format!(".{}", file_path)
-        }
+        let file_path = format!("{}/{}.wu", folder, path);
 
         let module = Path::new(&file_path);
-        let init_path = format!("{}/{}/init.wu", my_folder.to_str().unwrap(), path);
+        let init_path = format!("{}/{}/init.wu", folder, path);
 
         // if !is_deep_run {
         //     init_path = format!("./{}", init_path)
@@ -2424,8 +3884,32 @@ impl<'v> Visitor<'v> {
         let module = if !module.exists() {
             let module = Path::new(&init_path);
 
+            // what wu comes with, once there's no `$WU_HOME` left to look in
+            let home = env::var("WU_HOME").is_ok() && !is_hermetic() && !is_deep_run;
+            let embedded = stdlib::std_module(path).filter(|_| !is_relative && !home);
+
+            if let (false, Some(embedded)) = (module.exists(), embedded) {
+                log!(Verbose, "Resolved", "`{}` to what wu comes with", path);
+
+                self.import_map.insert(
+                    statement.pos.clone(),
+                    (embedded.clone(), format!("{}/", stdlib::ROOT)),
+                );
+
+                return Ok(embedded);
+            }
+
             if !module.exists() {
-                if is_deep_run {
+                if is_relative {
+                    return Err(response!(
+                        Wrong(format!(
+                            "no such module `{0}`, needed either `{0}.wu` or `{0}/init.wu`",
+                            path
+                        )),
+                        self.source.file,
+                        statement.pos
+                    ));
+                } else if is_deep_run {
                     return Err(response!(
                         Wrong(format!(
                             "no such module `{0}`, needed either `{0}.wu`, `{0}/init.wu` or in `$WU_HOME`",
@@ -2434,8 +3918,27 @@ impl<'v> Visitor<'v> {
                         self.source.file,
                         statement.pos
                     ));
+                } else if is_hermetic() {
+                    return Err(response!(
+                        Wrong(format!(
+                            "no such module `{0}`, needed either `{0}.wu` or `{0}/init.wu`",
+                            path
+                        )),
+                        self.source.file,
+                        statement.pos,
+                        Note("a hermetic build doesn't look in `$WU_HOME`")
+                    ));
                 } else {
                     if let Ok(root) = env::var("WU_HOME") {
+                        log!(
+                            Verbose,
+                            "Fallback",
+                            "`{}` isn't in `{}`, looking in `$WU_HOME` ({})",
+                            path,
+                            my_folder.display(),
+                            root
+                        );
+
                         // - 1 cause / is added in the next iteration
                         let new_path = self.find_module(
                             path,
@@ -2470,9 +3973,340 @@ impl<'v> Visitor<'v> {
             module
         };
 
+        if is_relative {
+            let project_root = self.project_root();
+
+            if !Self::absolute(module).starts_with(&project_root) {
+                return Err(response!(
+                    Wrong(format!("can't import `{}`, it's outside of the project", path)),
+                    self.source.file,
+                    statement.pos,
+                    Note(format!("project root is `{}`", project_root.display()))
+                ));
+            }
+        }
+
+        if is_hermetic() {
+            let root = hermetic_root(Path::new(&self.source.file.0));
+
+            if !absolute_path(module).starts_with(&root) {
+                return Err(response!(
+                    Wrong(format!(
+                        "can't import `{}` in a hermetic build, it's outside of the project",
+                        path
+                    )),
+                    self.source.file,
+                    statement.pos,
+                    Note(format!("project root is `{}`", root.display()))
+                ));
+            }
+        }
+
+        log!(Verbose, "Resolved", "`{}` to `{}`", path, module.display());
+
         Ok(module.display().to_string())
     }
 
+    // visits the module at `module` once per compilation, keyed by its canonical path,
+    // so every path leading to the same file yields the very same types. Later compilations,
+    // and with `--module-cache` later runs, reuse it for as long as none of the files it was
+    // visited from have changed
+    fn import_module(&mut self, module: String, statement: &Statement) -> Result<ImportedModule, ()> {
+        let canonical = fs::canonicalize(&module).unwrap_or_else(|_| PathBuf::from(&module));
+
+        let cached = self.imports.borrow().get(&canonical).cloned();
+
+        if let Some(imported) = cached {
+            log!(Verbose, "Cached", "{}", canonical.display());
+
+            self.files.extend(imported.files.iter().cloned());
+
+            return Ok(imported);
+        }
+
+        let mut is_deep = false;
+
+        let root = if let Some(other_path) = self.import_map.get(&statement.pos) {
+            is_deep = true;
+            Path::new(&other_path.0)
+                .parent()
+                .unwrap()
+                .display()
+                .to_string()
+        } else {
+            self.root.clone()
+        };
+
+        let key = (canonical.clone(), root.clone(), is_deep);
+
+        let cached = MODULES.with(|modules| modules.borrow().get(&key).cloned());
+        let cached = cached.filter(|imported| unchanged(&imported.files));
+
+        if cached.is_some() {
+            log!(Verbose, "Cached", "{} (unchanged)", canonical.display());
+        }
+
+        // or by earlier runs, see `--module-cache`
+        let cached = cached.or_else(|| {
+            let stored = load_module(&key).filter(|imported| unchanged(&imported.files))?;
+
+            log!(Verbose, "Cached", "{} (on disk)", canonical.display());
+
+            MODULES.with(|modules| modules.borrow_mut().insert(key.clone(), stored.clone()));
+
+            Some(stored)
+        });
+
+        if let Some(imported) = cached {
+            self.imports
+                .borrow_mut()
+                .insert(canonical, imported.clone());
+            self.files.extend(imported.files.iter().cloned());
+
+            return Ok(imported);
+        }
+
+        // a module can't be loaded while loading what it imports, and chains of imports
+        // recurse as deeply as they are long
+        if self.loading.borrow().is_empty() {
+            let file = &self.source.file.0;
+
+            self.loading
+                .borrow_mut()
+                .push((fs::canonicalize(file).unwrap_or_else(|_| PathBuf::from(file)), None))
+        }
+
+        let chain = self.loading.borrow().clone();
+
+        if let Some(start) = chain.iter().position(|(loading, _)| *loading == canonical) {
+            let cwd = env::current_dir().unwrap_or_default();
+            let shown = |module: &PathBuf| {
+                module
+                    .strip_prefix(&cwd)
+                    .unwrap_or(module)
+                    .display()
+                    .to_string()
+            };
+
+            let cycle = chain[start..]
+                .iter()
+                .map(|(module, _)| module)
+                .chain(Some(&canonical))
+                .map(shown)
+                .collect::<Vec<_>>();
+
+            // every import on the way back around, where it is
+            let mut links = String::new();
+
+            for pair in chain[start..].windows(2) {
+                let ((importer, _), (imported, ref pos)) = (&pair[0], &pair[1]);
+
+                if let Some(pos) = pos {
+                    links.push_str(&format!(
+                        "{}{}{}",
+                        Note(format!("`{}` imports `{}`", shown(importer), shown(imported))),
+                        FilePath(shown(importer)),
+                        pos
+                    ))
+                }
+            }
+
+            return Err(response!(
+                Wrong(format!("circular import: {}", cycle.join(" -> "))),
+                self.source.file,
+                statement.pos,
+                links
+            ));
+        }
+
+        if chain.len() >= max_depth() {
+            return Err(too_deep(self.source, &statement.pos));
+        }
+
+        self.spend(Resource::Imports, 1, &statement.pos)?;
+
+        log!(Verbose, "Loading", "{}", canonical.display());
+
+        let content = match read_module(&canonical) {
+            Err(why) => panic!("failed to read {}: {}", module, why),
+            Ok(content) => content,
+        };
+
+        self.spend(Resource::Bytes, content.len(), &statement.pos)?;
+
+        // read here, as std modules may be embedded rather than on disk
+        let source = Source::from(&module, content.lines().map(str::to_string).collect());
+        let lexer = Lexer::default(content.chars().collect(), &source);
+
+        let mut tokens = Vec::new();
+
+        for token_result in lexer {
+            tokens.push(token_result?)
+        }
+
+        let mut parser = Parser::new(tokens, &source);
+        parser.budget = self.budget.clone();
+
+        let parsed = parser.parse()?;
+
+        let mut visitor = Visitor::new(&parsed, &source, root);
+        visitor.is_deep = is_deep;
+        visitor.imports = self.imports.clone();
+        visitor.loading = self.loading.clone();
+        visitor.budget = self.budget.clone();
+        visitor.exports = self.exports.clone();
+
+        self.loading
+            .borrow_mut()
+            .push((canonical.clone(), Some(statement.pos.clone())));
+
+        let visited = visitor.visit();
+
+        self.loading.borrow_mut().pop();
+
+        visited?;
+
+        let mut files = visitor.files;
+
+        files.push((canonical.clone(), hash(&content)));
+        files.sort();
+        files.dedup();
+
+        let imported = ImportedModule {
+            content: visitor.module_content,
+            implementations: visitor.symtab.implementations,
+            files,
+        };
+
+        self.imports
+            .borrow_mut()
+            .insert(canonical, imported.clone());
+        self.files.extend(imported.files.iter().cloned());
+
+        store_module(&key, &imported);
+
+        MODULES.with(|modules| modules.borrow_mut().insert(key, imported.clone()));
+
+        Ok(imported)
+    }
+
+    // the types of the values a `for` gets from `iterated` every time around, as far as known:
+    // `ipairs` and `pairs` of an array give its indices and elements, and an iterator function,
+    // either `iterated` itself or what calling it returns, gives what it returns but `nil`
+    fn elements(&mut self, iterated: &Expression, iterator_t: &Type) -> Result<Vec<Type>, ()> {
+        let returned = match iterated.node {
+            ExpressionNode::Call(ref called, ref args) => {
+                if let ExpressionNode::Identifier(ref name) = called.node {
+                    let builtin = SymTab::prelude().fetch(name);
+
+                    if builtin.as_ref() == Some(iterator_t) && args.len() == 1 {
+                        return Ok(match self.type_expression(&args[0])?.node {
+                            TypeNode::Array(ref t, _) if name == "ipairs" || name == "pairs" => {
+                                vec![Type::from(TypeNode::Int), Type::from(t.node.clone())]
+                            }
+                            _ => Vec::new(),
+                        });
+                    }
+                }
+
+                match self.type_expression(iterated)?.node {
+                    TypeNode::Func(_, ref returned, ..) => (**returned).clone(),
+                    _ => return Ok(Vec::new()),
+                }
+            }
+
+            _ => match iterator_t.node {
+                TypeNode::Func(_, ref returned, ..) => (**returned).clone(),
+                _ => return Ok(Vec::new()),
+            },
+        };
+
+        if let TypeMode::Splat(_) = returned.mode {
+            return Ok(Vec::new());
+        }
+
+        // the loop ends on the first value being `nil`, so inside it's there
+        let first = |node: &TypeNode| match *node {
+            TypeNode::Optional(ref inner) => Type::from((**inner).clone()),
+            ref node => Type::from(node.clone()),
+        };
+
+        Ok(match returned.node {
+            TypeNode::Any | TypeNode::Nil => Vec::new(),
+
+            TypeNode::Tuple(ref values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, t)| if i == 0 { first(&t.node) } else { Type::from(t.node.clone()) })
+                .collect(),
+
+            ref node => vec![first(node)],
+        })
+    }
+
+    // spends `amount` of the compilation's budget, failing at `pos` once it's spent
+    fn spend(&self, resource: Resource, amount: usize, pos: &Pos) -> Result<(), ()> {
+        self.budget
+            .spend(resource, amount)
+            .map_err(|exceeded| too_large(self.source, pos, exceeded))
+    }
+
+    // the binding introduced by an import, i.e. the last segment of its path
+    fn module_name(path: &str) -> String {
+        path.rsplit('/').next().unwrap().to_string()
+    }
+
+    fn absolute(path: &Path) -> PathBuf {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            env::current_dir().unwrap_or_default().join(path)
+        };
+
+        normalize_path(&path)
+    }
+
+    // closest folder containing a `wu.toml`, falling back to the compilation root
+    fn project_root(&self) -> PathBuf {
+        let root = Self::absolute(Path::new(&self.root));
+
+        project_of(&root).unwrap_or(root)
+    }
+
+    // `root/a/b` as a path from the importing file's folder, `../../a/b` two folders down
+    fn rooted_path(
+        &mut self,
+        path: &str,
+        folder: &str,
+        statement: &Statement,
+    ) -> Result<String, ()> {
+        let rest = match path.strip_prefix("root/") {
+            Some(rest) => rest,
+            None => return Ok(path.to_string()),
+        };
+
+        let project_root = self.project_root();
+        let folder = Self::absolute(Path::new(if folder.is_empty() { "." } else { folder }));
+
+        let depth = match folder.strip_prefix(&project_root) {
+            Ok(inner) => inner.components().count(),
+            Err(_) => {
+                return Err(response!(
+                    Wrong(format!("can't import `{}` from outside of the project", path)),
+                    self.source.file,
+                    statement.pos,
+                    Note(format!("project root is `{}`", project_root.display()))
+                ))
+            }
+        };
+
+        let relative = format!("./{}{}", "../".repeat(depth), rest);
+
+        self.import_paths.insert(statement.pos.clone(), relative.clone());
+
+        Ok(relative)
+    }
+
     #[allow(dead_code)]
     pub fn visit_implement_block(
         &mut self,
@@ -2495,177 +4329,227 @@ impl<'v> Visitor<'v> {
                 continue
             }
 
-            if let StatementNode::Variable(_, ref name, ref right, _) = statement.node {
-                if let Some(ref right) = *right {
-                    if let ExpressionNode::Function(ref params, ref retty, .., is_method) =
-                        right.node
-                    {
-                        let mut types = Vec::new();
+            let (name, t) = match statement.binding().node {
+                StatementNode::Variable(ref declared, ref name, Some(ref right), _) => {
+                    match right.node {
+                        ExpressionNode::Function(ref params, ref retty, .., is_method) => {
+                            let mut types = Vec::new();
+
+                            for param in params.iter() {
+                                types.push(self.deid(param.1.clone())?)
+                            }
+
+                            let t = Type::from(TypeNode::Func(
+                                types,
+                                Rc::new(retty.clone()),
+                                Some(Rc::new(right.node.clone())),
+                                is_method,
+                            ));
 
-                        for param in params.iter() {
-                            types.push(self.deid(param.1.clone())?)
+                            (name, t)
                         }
 
-                        let t = Type::from(TypeNode::Func(
-                            types,
-                            Rc::new(retty.clone()),
-                            Some(Rc::new(right.node.clone())),
-                            is_method,
-                        ));
+                        ExpressionNode::Extern(ref t, _) => {
+                            if let TypeNode::Func(..) = t.node {
+                                (name, t.clone())
+                            } else {
+                                return Err(response!(
+                                    Wrong("expected function definition"),
+                                    self.source.file,
+                                    statement.pos
+                                ));
+                            }
+                        }
 
-                        // set type on struct, on the fucking fly wthf
+                        // an associated constant, e.g. `MAX_HP := 100`
+                        _ => (name, self.constant(declared, right)?),
+                    }
+                }
 
-                        new_content.insert(
-                            name.clone(),
-                            Type::new(t.node.clone(), TypeMode::Implemented),
-                        );
+                _ => {
+                    return Err(response!(
+                        Wrong("expected function or constant definition"),
+                        self.source.file,
+                        statement.pos
+                    ))
+                }
+            };
 
-                        let kind = Type::new(
-                            TypeNode::Struct(struct_name.clone(), new_content.clone(), id.clone()),
-                            kind.mode.clone(),
-                        );
+            // set type on struct, on the fucking fly wthf
 
-                        // we have strong computers in 2018
-                        self.inside.pop();
-                        self.inside.push(Inside::Implement(kind.clone()));
+            new_content.insert(
+                name.clone(),
+                Type::new(t.node.clone(), TypeMode::Implemented),
+            );
 
-                        self.assign_str("self", Type::from(kind.node.clone()));
+            let kind = Type::new(
+                TypeNode::Struct(
+                    struct_name.clone(),
+                    Rc::new(new_content.clone()),
+                    id.clone(),
+                ),
+                kind.mode.clone(),
+            );
 
-                        if is_index {
-                            self.symtab.implement(
-                                id,
-                                name.clone(),
-                                Type::new(t.node.clone(), TypeMode::Implemented),
-                            );
+            // we have strong computers in 2018
+            self.inside.replace_innermost(Inside::Implement(kind.clone()));
 
-                            let mut new_module_content = module_content.unwrap().clone();
+            self.assign_str("self", Type::from(kind.node.clone()));
 
-                            new_module_content.insert(name.to_string(), kind.clone());
+            self.symtab.implement(
+                id,
+                name.clone(),
+                Type::new(t.node.clone(), TypeMode::Implemented),
+            );
 
-                            self.assign(struct_name.to_owned(), kind.clone());
+            if is_index {
+                let mut new_module_content = module_content.unwrap().clone();
 
-                            if let Some(root) = self.symtab.stack[0].get(struct_name) {
-                                if root == original_kind {
-                                    self.module_content
-                                        .insert(struct_name.to_owned(), kind.clone());
-                                }
-                            }
-                        } else {
-                            self.symtab.implement(
-                                id,
-                                name.clone(),
-                                Type::new(t.node.clone(), TypeMode::Implemented),
-                            );
+                new_module_content.insert(name.to_string(), kind.clone());
+            }
 
-                            self.assign(struct_name.to_owned(), kind.clone());
+            self.assign(struct_name.to_owned(), kind.clone());
 
-                            if let Some(root) = self.symtab.stack[0].get(struct_name) {
-                                if root == original_kind {
-                                    self.module_content
-                                        .insert(struct_name.to_owned(), kind.clone());
-                                }
-                            }
-                        }
+            if let Some(root) = self.symtab.global(struct_name) {
+                if root == original_kind {
+                    self.module_content
+                        .insert(struct_name.to_owned(), kind.clone());
+                }
+            }
+        }
 
-                        self.assign(name.to_owned(), t);
+        for statement in ast {
+            if let StatementNode::Variable(.., Some(ref right), _) = statement.binding().node {
+                if let ExpressionNode::Function(..) = right.node {
+                    self.visit_statement(statement)?
+                }
+            }
+        }
 
-                        continue;
-                    } else if let ExpressionNode::Extern(ref t, _) = right.node {
-                        if let TypeNode::Func(..) = t.node {
-                            new_content.insert(
-                                name.clone(),
-                                Type::new(t.node.clone(), TypeMode::Implemented),
-                            );
+        Ok(())
+    }
 
-                            let kind = Type::new(
-                                TypeNode::Struct(
-                                    struct_name.clone(),
-                                    new_content.clone(),
-                                    id.clone(),
-                                ),
-                                kind.mode.clone(),
-                            );
+    // the default methods of a trait with `members` a struct with `content` takes, failing on
+    // members it lacks with no default, or implements as something else
+    fn defaults_taken(
+        &self,
+        content: &HashMap<String, Type>,
+        members: &HashMap<String, Type>,
+        position: &Pos,
+    ) -> Result<Vec<(String, Type)>, ()> {
+        let mut defaults = Vec::new();
+
+        for (name, ty) in members.iter() {
+            match content.get(name) {
+                Some(ty_b) if ty.node != ty_b.node => {
+                    return Err(response!(
+                        Wrong(format!("expected implemented type `{}` for `{}`", ty, name)),
+                        self.source.file,
+                        position
+                    ))
+                }
 
-                            // we have strong computers in 2018
-                            self.inside.pop();
-                            self.inside.push(Inside::Implement(kind.clone()));
+                Some(_) => (),
 
-                            self.assign_str("self", Type::from(kind.node.clone()));
+                None if matches!(ty.node, TypeNode::Func(_, _, Some(_), _)) => {
+                    defaults.push((name.clone(), ty.clone()))
+                }
 
-                            if is_index {
-                                self.symtab.implement(
-                                    id,
-                                    name.clone(),
-                                    Type::new(t.node.clone(), TypeMode::Implemented),
-                                );
+                None => {
+                    return Err(response!(
+                        Wrong(format!("missing implementation of method `{}: {}`", name, ty)),
+                        self.source.file,
+                        position
+                    ))
+                }
+            }
+        }
 
-                                let mut new_module_content = module_content.unwrap().clone();
+        defaults.sort_by(|a, b| a.0.cmp(&b.0));
 
-                                new_module_content.insert(name.to_string(), kind.clone());
+        Ok(defaults)
+    }
 
-                                self.assign(struct_name.to_owned(), kind.clone());
+    // the struct `implemented` with the trait's `defaults` as its methods, as if implemented by
+    // the implementation at `pos`
+    fn take_defaults(
+        &mut self,
+        struct_name: &String,
+        implemented: &Type,
+        defaults: &[(String, Type)],
+        pos: &Pos,
+    ) {
+        if defaults.is_empty() {
+            return;
+        }
 
-                                if let Some(root) = self.symtab.stack[0].get(struct_name) {
-                                    if root == original_kind {
-                                        self.module_content
-                                            .insert(struct_name.to_owned(), kind.clone());
-                                    }
-                                }
-                            } else {
-                                self.symtab.implement(
-                                    id,
-                                    name.clone(),
-                                    Type::new(t.node.clone(), TypeMode::Implemented),
-                                );
+        if let TypeNode::Struct(_, ref content, ref id) = implemented.node {
+            let mut content = (**content).clone();
 
-                                self.assign(struct_name.to_owned(), kind.clone());
+            for (name, t) in defaults.iter() {
+                let method = Type::new(t.node.clone(), TypeMode::Implemented);
 
-                                if let Some(root) = self.symtab.stack[0].get(struct_name) {
-                                    if root == original_kind {
-                                        self.module_content
-                                            .insert(struct_name.to_owned(), kind.clone());
-                                    }
-                                }
-                            }
+                content.insert(name.clone(), method.clone());
+                self.symtab.implement(id, name.clone(), method)
+            }
 
-                            self.assign(name.to_owned(), t.to_owned());
+            let kind = Type::new(
+                TypeNode::Struct(struct_name.clone(), Rc::new(content), id.clone()),
+                implemented.mode.clone(),
+            );
 
-                            continue;
-                        } else {
-                            return Err(response!(
-                                Wrong("expected function definition"),
-                                self.source.file,
-                                statement.pos
-                            ));
-                        }
-                    }
-                } else {
-                    return Err(response!(
-                        Wrong("expected function definition"),
-                        self.source.file,
-                        statement.pos
-                    ));
-                }
-            } else {
-                return Err(response!(
-                    Wrong("expected function definition"),
-                    self.source.file,
-                    statement.pos
-                ));
+            if self.module_content.contains_key(struct_name) {
+                self.module_content
+                    .insert(struct_name.clone(), kind.clone());
             }
+
+            self.assign(struct_name.clone(), kind);
         }
 
-        for statement in ast {
-            if let StatementNode::Variable(.., ref right, _) = statement.node {
-                if let Some(ref right) = *right {
-                    if let ExpressionNode::Function(..) = right.node {
-                        self.visit_statement(statement)?
-                    }
-                }
-            }
+        self.defaults.insert(
+            pos.clone(),
+            defaults.iter().map(|(name, _)| name.clone()).collect(),
+        );
+    }
+
+    // the type of a constant in an implementation, which is checked like a variable's value
+    fn constant(&mut self, declared: &Type, value: &Expression) -> Result<Type, ()> {
+        self.visit_expression(value)?;
+
+        let t = self.type_expression(value)?;
+
+        if declared.node.strong_cmp(&TypeNode::Nil) {
+            return Ok(Type::from(t.node));
         }
 
-        Ok(())
+        let declared = self.deid(declared.clone())?;
+
+        if !declared.node.check_expression(&Parser::fold_expression(value).node)
+            && declared.node != t.node
+        {
+            let (expected, found) = disambiguate(&declared.node, &t.node);
+
+            return Err(response!(
+                Wrong(format!(
+                    "mismatched types, expected type `{}` got `{}`",
+                    expected, found
+                )),
+                self.source.file,
+                value.pos
+            ));
+        }
+
+        Ok(Type::from(declared.node))
+    }
+
+    // binds an imported module member locally, re-exporting it at module level
+    fn import_member(&mut self, local: &str, kind: &Type, origin: &Rc<HashMap<String, Type>>) {
+        self.symtab.import(local.to_string(), origin.clone());
+        self.assign(local.to_string(), kind.clone());
+
+        if self.symtab.at_root() {
+            self.module_content.insert(local.to_string(), kind.clone());
+        }
     }
 
     fn ensure_no_implicit(&self, expression: &Expression) -> Result<(), ()> {
@@ -2719,10 +4603,157 @@ impl<'v> Visitor<'v> {
         Ok(())
     }
 
+    // the required members of a struct an initialization hasn't assigned, by name
+    fn unassigned(
+        &self,
+        content: &HashMap<String, Type>,
+        struct_id: &String,
+        args: &[(String, Expression)],
+    ) -> Members {
+        let implementations = self.symtab.get_implementations(struct_id);
+
+        let mut unassigned = content
+            .iter()
+            .filter(|(key, kind)| {
+                !matches!(kind.node, TypeNode::Optional(_))
+                    && !args.iter().any(|arg| arg.0 == **key)
+                    && !implementations.is_some_and(|found| found.contains_key(*key))
+            })
+            .map(|(key, kind)| (key.clone(), kind.clone()))
+            .collect::<Vec<_>>();
+
+        unassigned.sort_by(|a, b| a.0.cmp(&b.0));
+        unassigned
+    }
+
+    // fills the members an initialization leaves out with defaults, when they have one
+    fn suggest_members(
+        &self,
+        name: &str,
+        content: &HashMap<String, Type>,
+        given: &HashMap<String, Type>,
+        args: &[(String, Expression)],
+        pos: &Pos,
+    ) {
+        let mut missing = Vec::new();
+
+        for (key, kind) in content.iter() {
+            if given.contains_key(key) || matches!(kind.node, TypeNode::Optional(_)) {
+                continue;
+            }
+
+            match fix::default_value(kind) {
+                Some(value) => missing.push(format!("{}: {}", key, value)),
+                None => return,
+            }
+        }
+
+        missing.sort();
+
+        let members = missing.join(", ");
+
+        // right before the first member given, or else right inside the braces
+        let edit = match args.first() {
+            Some((key, value)) => {
+                let (line, column, _) = declared_span(&value.pos, key);
+
+                fix::insertion(line, column, format!("{}, ", members))
+            }
+
+            None => {
+                let line = (pos.0).1.chars().collect::<Vec<_>>();
+
+                let brace = match (pos.1 .0..line.len()).find(|&i| line[i] == '{') {
+                    Some(brace) => brace,
+                    None => return,
+                };
+
+                fix::insertion((pos.0).0, brace + 2, format!(" {} ", members))
+            }
+        };
+
+        fix::suggest(Fix {
+            title: format!("add the missing members of `{}`: `{}`", name, members),
+            file: self.source.file.0.clone(),
+            line: (pos.0).0,
+            edits: vec![edit],
+        })
+    }
+
+    // where a function body gets its value from: the last `return` at the top of the body that
+    // doesn't give `expected`, or else the last expression
+    fn returned_pos(&mut self, body: &Expression, expected: &Type) -> Pos {
+        let statements = match body.node {
+            ExpressionNode::Block(ref statements) => statements,
+            _ => return body.pos.clone(),
+        };
+
+        let returned = statements.iter().rev().find_map(|statement| match statement.node {
+            StatementNode::Return(Some(ref value)) => {
+                match silently(|| self.type_expression(value)) {
+                    Ok(ref t) if t.node == expected.node => None,
+                    _ => Some(value.pos.clone()),
+                }
+            }
+            _ => None,
+        });
+
+        match (returned, statements.last()) {
+            (Some(pos), _) => pos,
+            (None, Some(last)) => match last.node {
+                StatementNode::Expression(ref value) => value.pos.clone(),
+                _ => last.pos.clone(),
+            },
+            (None, None) => body.pos.clone(),
+        }
+    }
+
+    // explains the `T?` of an `if` without an `else` that was used as a value
+    fn note_missing_else(&self, value: &Expression) {
+        if let ExpressionNode::If(_, _, ref elses) = value.node {
+            if !has_else(elses) {
+                response!(Note(
+                    "an `if` without `else` is `nil` when no condition holds, add an `else`"
+                ))
+            }
+        }
+    }
+
+    // the splat behind `*args`, when an argument forwards one
+    fn forwarded(&mut self, arg: &Expression) -> Result<Option<Type>, ()> {
+        if let ExpressionNode::UnwrapSplat(ref splat) = arg.node {
+            self.visit_expression(splat)?;
+
+            Ok(Some(self.type_expression(splat)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn check_forwarded(&self, param: &Type, spread: &Type, arg: &Expression) -> Result<(), ()> {
+        if param.node != spread.node {
+            let (expected, found) = disambiguate(&param.node, &spread.node);
+
+            return Err(response!(
+                Wrong(format!(
+                    "mismatched forwarded arguments, expected `...{}` got `...{}`",
+                    expected, found
+                )),
+                self.source.file,
+                arg.pos
+            ));
+        }
+
+        Ok(())
+    }
+
     fn assert_types(&self, a: Type, b: Type, pos: &Pos) -> Result<bool, ()> {
         if a != b {
             Err(response!(
-                Wrong(format!("mismatched types, expected `{}` got `{}`", a, b)),
+                Wrong({
+                    let (expected, found) = disambiguate(&a, &b);
+                    format!("mismatched types, expected `{}` got `{}`", expected, found)
+                }),
                 self.source.file,
                 pos
             ))
@@ -2734,12 +4765,107 @@ impl<'v> Visitor<'v> {
     fn fetch(&self, name: &String, pos: &Pos) -> Result<Type, ()> {
         if let Some(t) = self.symtab.fetch(name) {
             Ok(t)
-        } else {
+        } else if let Some(cfg) = self.left_out.get(name) {
             Err(response!(
                 Wrong(format!("can't seem to find `{}`", name)),
                 self.source.file,
-                pos
+                pos,
+                Note(format!("`{}` is only imported with `--cfg {}`", name, cfg))
+            ))
+        } else if let Some((other, t)) = self.symtab.fetch_other_case(name) {
+            let note = if is_struct_itself(&t) {
+                format!(
+                    "names are case-sensitive, did you mean the struct `{0}`? Values of it are \
+                     made with `new {0} {{ .. }}`",
+                    other
+                )
+            } else {
+                format!("names are case-sensitive, did you mean `{}`?", other)
+            };
+
+            Err(response!(
+                Wrong(format!("can't seem to find `{}`", name)),
+                self.source.file,
+                pos,
+                Note(note)
             ))
+        } else {
+            response!(
+                Wrong(format!("can't seem to find `{}`", name)),
+                self.source.file,
+                pos
+            );
+
+            if !silenced() {
+                self.suggest_import(name, pos)
+            }
+
+            Err(())
+        }
+    }
+
+    // how to get at the fields of the struct `name`, which only its values have
+    fn value_of_struct(&self, name: &str) -> String {
+        match self.symtab.fetch_other_case(name) {
+            Some((value, ref t)) if !is_struct_itself(t) => {
+                format!("`{}` is the struct itself, did you mean `{}`?", name, value)
+            }
+
+            _ => format!(
+                "`{0}` is the struct itself, its fields belong to values made with \
+                 `new {0} {{ .. }}`",
+                name
+            ),
+        }
+    }
+
+    // `Player` where a value of it goes, e.g. `heal(Player)` rather than `heal(player)`
+    fn check_not_struct(&self, expected: &TypeNode, found: &Type, pos: &Pos) -> Result<(), ()> {
+        match (expected, &found.node) {
+            (TypeNode::Struct(_, _, ref wanted), TypeNode::Struct(ref name, _, ref id))
+                if wanted == id && is_struct_itself(found) =>
+            {
+                Err(response!(
+                    Wrong(format!("expected a value of `{0}`, got the struct `{0}` itself", name)),
+                    self.source.file,
+                    pos,
+                    Note(format!(
+                        "a struct is bound like a variable, but values of it are made with \
+                         `new {} {{ .. }}`",
+                        name
+                    ))
+                ))
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    // points at the modules of the project that could provide `name`
+    fn suggest_import(&self, name: &str, pos: &Pos) {
+        let exporters = self.exports.exporters(name, &self.root, &self.source.file.0);
+
+        match exporters.as_slice() {
+            [] => (),
+
+            [path] => fix::suggest(Fix {
+                title: format!("import `{}` from `{}`: `import {} {{ {} }}`", name, path, path, name),
+                file: self.source.file.0.clone(),
+                line: (pos.0).0,
+                edits: vec![fix::insertion(1, 1, format!("import {} {{ {} }}\n", path, name))],
+            }),
+
+            paths => {
+                response!(Note(format!(
+                    "`{}` is defined in {}",
+                    name,
+                    paths
+                        .iter()
+                        .map(|path| format!("`{}`", path))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
         }
     }
 
@@ -2751,37 +4877,49 @@ impl<'v> Visitor<'v> {
         self.symtab.assign(name, t)
     }
 
-    fn push_scope(&mut self) {
-        self.symtab.push()
-    }
+    pub fn deid(&mut self, t: Type) -> Result<Type, ()> {
+        // optionals are taken off and put back in a loop, however many there are
+        let mut optionals = 0;
+        let mut inner = t.node.clone();
 
-    fn pop_scope(&mut self) {
-        self.symtab.pop()
-    }
+        while let TypeNode::Optional(content) = inner {
+            inner = (*content).clone();
+            optionals += 1
+        }
 
-    pub fn deid(&mut self, t: Type) -> Result<Type, ()> {
-        if let TypeNode::Optional(ref content) = t.node {
-            return Ok(Type::new(
-                TypeNode::Optional(Rc::new(self.deid(Type::from((**content).clone()))?.node)),
-                t.mode,
-            ));
+        if optionals > 0 {
+            let mut node = self.deid(Type::from(inner))?.node;
+
+            for _ in 0..optionals {
+                node = TypeNode::Optional(Rc::new(node))
+            }
+
+            return Ok(Type::new(node, t.mode));
+        }
+
+        // outside an implementation `Self` stays as it is, a call says what it is
+        if t.is_self() && self.implementing().is_none() {
+            return Ok(t);
         }
 
         if let TypeNode::Id(ref expr) = t.node {
             let mut new_t;
 
-            for inside in self.inside.iter().rev() {
-                if let Inside::ForeignModule(ref content) = inside {
-                    let empty_ast = Vec::new();
-                    let mut visitor = Visitor::new(&empty_ast, &self.source, self.root.clone()); // TODO: fix source to refer to proper file
+            let foreign = self.inside.innermost(|inside| match inside {
+                Inside::ForeignModule(ref content) => Some(content.clone()),
+                _ => None,
+            });
 
-                    visitor.symtab = SymTab::from(content.clone());
+            if let Some(content) = foreign {
+                let empty_ast = Vec::new();
+                let mut visitor = Visitor::new(&empty_ast, self.source, self.root.clone()); // TODO: fix source to refer to proper file
 
-                    new_t = visitor.type_expression(expr)?;
-                    new_t.mode = t.mode.clone();
+                visitor.symtab = SymTab::from((*content).clone());
 
-                    return Ok(new_t);
-                }
+                new_t = visitor.type_expression(expr)?;
+                new_t.mode = t.mode.clone();
+
+                return Ok(new_t);
             }
 
             new_t = self.type_expression(expr)?;
@@ -2806,11 +4944,149 @@ impl<'v> Visitor<'v> {
                         )
                     )
                 }
+                TypeNode::This => match self.implementing() {
+                    Some(s) => Ok(Type::new(s.node, t.mode)),
+                    None => Ok(t),
+                },
+
                 _ => Ok(t)
             }
         }
     }
 
+    // the builtin `called` is, when it is a method of an array or optional
+    fn builtin(&mut self, called: &Expression) -> Result<Option<Builtin>, ()> {
+        if let ExpressionNode::Index(ref receiver, ref index, false) = called.node {
+            if let Some(builtin) = self.static_builtin(receiver, index, false) {
+                return Ok(Some(builtin));
+            }
+
+            if let ExpressionNode::Identifier(ref name) = index.node {
+                let receiver = self.type_expression(receiver)?;
+                let builtin = Builtin::of(&receiver, name);
+
+                if let Some(builtin) = builtin {
+                    if builtin.changes() && receiver.mode.strong_cmp(&TypeMode::Immutable) {
+                        return Err(response!(
+                            Wrong(format!("can't `{}` a constant `{}`", name, receiver.node)),
+                            self.source.file,
+                            index.pos
+                        ));
+                    }
+                }
+
+                // their length is part of their type
+                if let (Some(builtin), TypeNode::Array(ref element, Some(_))) =
+                    (builtin, &receiver.node)
+                {
+                    if builtin.changes() {
+                        response!(
+                            Wrong(format!(
+                                "can't `{}` a `{}`, its size is fixed",
+                                name, receiver
+                            )),
+                            self.source.file,
+                            index.pos
+                        );
+
+                        response!(Note(format!("declare it as `[{}]` to let it grow", element)));
+
+                        return Err(());
+                    }
+                }
+
+                return Ok(builtin);
+            }
+        }
+
+        Ok(None)
+    }
+
+    // `int parse` and the like, as long as nothing else goes by the type's name
+    fn static_builtin(
+        &self,
+        receiver: &Expression,
+        member: &Expression,
+        is_element: bool,
+    ) -> Option<Builtin> {
+        match (&receiver.node, &member.node) {
+            (ExpressionNode::Identifier(ref ty), ExpressionNode::Identifier(ref name))
+                if !is_element && self.symtab.fetch(ty).is_none() =>
+            {
+                Builtin::find_static(ty, name)
+            }
+
+            _ => None,
+        }
+    }
+
+    // the only members of arrays, strings, tuples and optionals are their builtins, and only to
+    // call
+    fn called_builtin(
+        &self,
+        index: &Expression,
+        receiver: &Type,
+        member: &Expression,
+    ) -> Result<(), ()> {
+        if self.builtins.contains_key(&index.pos) {
+            return Ok(());
+        }
+
+        let kind = Builtin::receiver_name(&receiver.node);
+
+        match member.node {
+            ExpressionNode::Identifier(ref name)
+                if Builtin::find(&receiver.node, name).is_some() =>
+            {
+                Err(response!(
+                    Wrong(format!("`{}` of a {} can only be called", name, kind)),
+                    self.source.file,
+                    member.pos
+                ))
+            }
+
+            _ => {
+                response!(
+                    Wrong(format!("no such {} method `{}`", kind, member.pos.get_lexeme())),
+                    self.source.file,
+                    member.pos
+                );
+
+                if kind == "optional" {
+                    response!(Note("unwrap it with `!` first to get at what it holds"));
+                }
+
+                Err(())
+            }
+        }
+    }
+
+    // the struct whose implementation is being checked, innermost first
+    fn implementing(&self) -> Option<Type> {
+        self.inside.innermost(|inside| match inside {
+            Inside::Implement(ref s) => Some(s.clone()),
+            _ => None,
+        })
+    }
+
+    // what calling `called` gives back. `Self` and `self` in a method's return type are the
+    // struct it is called on, so builders chain as that struct
+    fn returned(&mut self, called: &Expression, return_type: &Type) -> Result<Type, ()> {
+        if let ExpressionNode::Index(ref receiver, ..) = called.node {
+            if return_type.is_self() {
+                let receiver = self.type_expression(receiver)?;
+
+                if let TypeNode::Struct(..) = receiver.node {
+                    let _implementing = self.inside.enter(Inside::Implement(receiver));
+
+                    return self.deid(return_type.clone());
+                }
+            }
+        }
+
+        Ok(return_type.clone())
+    }
+
     pub fn is_implemented(&mut self, struct_id: &String, method_name: &String) -> bool {
         if let Some(ref content) = self.symtab.get_implementations(struct_id) {
             return content.contains_key(method_name);
@@ -0,0 +1,10 @@
+pub mod runtime;
+pub mod wumap;
+
+use super::build::*;
+use super::error::Response;
+use super::highlight::escape;
+use super::visitor::{from_root, is_hermetic};
+
+pub use self::runtime::*;
+pub use self::wumap::*;
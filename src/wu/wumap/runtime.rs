@@ -0,0 +1,135 @@
+// put ahead of every module by `--pretty-errors`: the modules keep their line maps in it, and the
+// first of them to run, the program's entry point, runs its body through `report`, which prints
+// an error nothing caught the way the compiler prints diagnostics, at the Wu lines of the traceback
+const HELPER: &str = r#"local __errors = package.loaded.__wu_errors or (function()
+  local errors = { maps = {} }
+
+  package.loaded.__wu_errors = errors
+
+  -- the Wu file and line of `line` of the lua file `file`, where its module has a map
+  local function locate(file, line)
+    local map = errors.maps[file]
+    local wu_line = map and map.lines[tonumber(line) - map.ahead]
+
+    if wu_line and wu_line > 0 then
+      return map.source, wu_line
+    end
+  end
+
+  local function excerpt(source, line)
+    local file = io.open(source)
+
+    if not file then
+      return nil
+    end
+
+    local n, found = 0, nil
+
+    for text in file:lines() do
+      n = n + 1
+
+      if n == line then
+        found = text
+        break
+      end
+    end
+
+    file:close()
+
+    return found
+  end
+
+  local function report(message)
+    message = tostring(message)
+
+    local frames = {}
+
+    for frame in debug.traceback("", 2):gmatch("\n\t([^\n]+)") do
+      local source, line = locate(frame:match("^(.-):(%d+):"))
+
+      if source then
+        frames[#frames + 1] = { source, line }
+      end
+    end
+
+    local file, line, rest = message:match("^(.-):(%d+): (.*)$")
+    local source, wu_line = locate(file, line)
+
+    if source then
+      message = rest
+    elseif frames[1] then
+      source, wu_line = frames[1][1], frames[1][2]
+    end
+
+    if frames[1] and frames[1][1] == source and frames[1][2] == wu_line then
+      table.remove(frames, 1)
+    end
+
+    local shown = { "", "wrong: " .. message }
+
+    if source then
+      shown[#shown + 1] = "     --> " .. source .. ":" .. wu_line
+
+      local text = excerpt(source, wu_line)
+
+      if text then
+        shown[#shown + 1] = "      │"
+        shown[#shown + 1] = string.format("%5d │ %s", wu_line, text)
+        shown[#shown + 1] = "      │"
+      end
+    end
+
+    for _, frame in ipairs(frames) do
+      shown[#shown + 1] = "note: called from " .. frame[1] .. ":" .. frame[2]
+    end
+
+    io.stderr:write(table.concat(shown, "\n") .. "\n")
+    os.exit(1)
+  end
+
+  errors.run = function(body)
+    if errors.running then
+      return body()
+    end
+
+    errors.running = true
+
+    local _, exports = xpcall(body, report)
+
+    errors.running = false
+
+    return exports
+  end
+
+  return errors
+end)()
+"#;
+
+// `lua`, a module generated from `source` with `lines` as its line map, with the helper of
+// `--pretty-errors` ahead of it, and the map moved down past that
+pub fn with_pretty_errors(source: &str, lua: &str, lines: &[usize]) -> (String, Vec<usize>) {
+    let ahead = HELPER.lines().count() + 1;
+
+    let map = lines
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let body = lua
+        .strip_prefix("return (function()")
+        .and_then(|body| body.strip_suffix("end)()"))
+        .unwrap_or(lua);
+
+    let lua = format!(
+        "{}__errors.maps[debug.getinfo(1, \"S\").short_src] = \
+         {{ source = {:?}, ahead = {}, lines = {{ {} }} }}\n\
+         return __errors.run(function(){}end)",
+        HELPER, source, ahead, map, body
+    );
+
+    let mut moved = vec![0; ahead];
+    moved.extend_from_slice(lines);
+
+    (lua, moved)
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use super::*;
+
+// generated statements are prefixed with the Wu line they came from, between these
+const MARK_START: char = '\u{1}';
+const MARK_END: char = '\u{2}';
+
+pub fn marker(line: usize) -> String {
+    format!("{}{}{}", MARK_START, line, MARK_END)
+}
+
+// takes the markers out of generated lua, noting the Wu line of every lua line
+pub fn strip(marked: &str) -> (String, Vec<usize>) {
+    let mut lua = String::with_capacity(marked.len());
+    let mut lines = Vec::new();
+
+    let mut current = 0;
+
+    for line in marked.split('\n') {
+        let mut rest = line;
+        let mut first = None;
+
+        let start_of_line = lua.len();
+
+        while let Some(start) = rest.find(MARK_START) {
+            let end = match rest[start..].find(MARK_END) {
+                Some(end) => start + end,
+                None => break,
+            };
+
+            if let Ok(wu_line) = rest[start + 1..end].parse() {
+                // of markers with no code between them, the last is what the code comes from
+                let no_code_yet =
+                    lua[start_of_line..].trim().is_empty() && rest[..start].trim().is_empty();
+
+                if first.is_none() || no_code_yet {
+                    first = Some(wu_line)
+                }
+
+                current = wu_line
+            }
+
+            lua.push_str(&rest[..start]);
+            rest = &rest[end + 1..]
+        }
+
+        lua.push_str(rest);
+        lua.push('\n');
+
+        lines.push(first.unwrap_or(current))
+    }
+
+    lua.pop();
+
+    (lua, lines)
+}
+
+// how a map names the module at `path`: from the project root in a hermetic build
+pub fn source_name(path: &str) -> String {
+    if is_hermetic() {
+        from_root(Path::new(path)).display().to_string()
+    } else {
+        path.replace("./", "")
+    }
+}
+
+// which Wu line every line of a compiled module came from, 0 where none did
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineMap {
+    pub source: String,
+    pub lines: Vec<usize>,
+}
+
+impl LineMap {
+    pub fn to_json(&self) -> String {
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            "{{\n  \"version\": 1,\n  \"source\": \"{}\",\n  \"lines\": [{}]\n}}\n",
+            escape(&self.source),
+            lines
+        )
+    }
+
+    // reads what `to_json` writes
+    pub fn from_json(json: &str) -> Option<Self> {
+        let field = |name: &str| {
+            let start = json.find(&format!("\"{}\":", name))? + name.len() + 3;
+
+            Some(json[start..].trim_start())
+        };
+
+        let source = field("source")?.strip_prefix('"')?;
+        let mut unescaped = String::new();
+        let mut chars = source.chars();
+
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => match chars.next()? {
+                    'n' => unescaped.push('\n'),
+                    't' => unescaped.push('\t'),
+                    c => unescaped.push(c),
+                },
+                c => unescaped.push(c),
+            }
+        }
+
+        let lines = field("lines")?.strip_prefix('[')?;
+        let lines = &lines[..lines.find(']')?];
+
+        let lines = lines
+            .split(',')
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<usize>, _>>()
+            .ok()?;
+
+        Some(LineMap {
+            source: unescaped,
+            lines,
+        })
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        Self::from_json(&fs::read_to_string(path).ok()?)
+    }
+
+    pub fn wu_line(&self, lua_line: usize) -> Option<usize> {
+        match self.lines.get(lua_line.checked_sub(1)?) {
+            Some(0) | None => None,
+            Some(&line) => Some(line),
+        }
+    }
+
+    // the first lua line of every run generated from `wu_line`, i.e. where breakpoints go
+    pub fn lua_lines(&self, wu_line: usize) -> Vec<usize> {
+        let mut lines = Vec::new();
+
+        for (i, &line) in self.lines.iter().enumerate() {
+            if line == wu_line && (i == 0 || self.lines[i - 1] != wu_line) {
+                lines.push(i + 1)
+            }
+        }
+
+        lines
+    }
+}
+
+// the map written next to a compiled module
+pub fn map_path(lua: &Path) -> PathBuf {
+    lua.with_extension("wumap")
+}
+
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || "/\\._-~".contains(c)
+}
+
+// turns every `foo.lua:12` with a `foo.wumap` beside it into `foo.wu:7`, e.g. in a traceback
+pub fn rewrite(text: &str) -> String {
+    let mut maps: HashMap<String, Option<LineMap>> = HashMap::new();
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(found) = rest.find(".lua:") {
+        let digits = rest[found + 5..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - found - 5);
+
+        let start = rest[..found]
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| is_path_char(c))
+            .last()
+            .map_or(found, |(i, _)| i);
+
+        let end = found + 5 + digits;
+
+        let lua = &rest[start..found + 4];
+
+        let map = maps
+            .entry(lua.to_string())
+            .or_insert_with(|| LineMap::load(&map_path(Path::new(lua))));
+
+        let mapped = match (map, rest[found + 5..end].parse()) {
+            (Some(map), Ok(lua_line)) => map
+                .wu_line(lua_line)
+                .map(|line| format!("{}:{}", map.source, line)),
+            _ => None,
+        };
+
+        match mapped {
+            Some(mapped) => {
+                result.push_str(&rest[..start]);
+                result.push_str(&mapped)
+            }
+
+            None => result.push_str(&rest[..end]),
+        }
+
+        rest = &rest[end..]
+    }
+
+    result.push_str(rest);
+
+    result
+}
+
+pub fn map_lines(location: Option<&String>, build: &mut Build) {
+    let location = match location {
+        Some(location) => location,
+        None => {
+            let mut traceback = String::new();
+
+            if io::stdin().read_to_string(&mut traceback).is_err() {
+                response!(Response::Wrong("failed to read stdin"));
+
+                return build.fail(Failure::Io);
+            }
+
+            return print!("{}", wumap::rewrite(&traceback));
+        }
+    };
+
+    let (file, line) = match location.rsplit_once(':').map(|(file, line)| (file, line.parse())) {
+        Some((file, Ok(line))) => (file, line),
+        _ => {
+            response!(Response::Wrong("expected `wu map <file>:<line>`"));
+
+            return build.fail(Failure::Usage);
+        }
+    };
+
+    let is_lua = file.ends_with(".lua");
+
+    let lua = if is_lua {
+        PathBuf::from(file)
+    } else {
+        output_path(file, build)
+    };
+
+    let map = match LineMap::load(&wumap::map_path(&lua)) {
+        Some(map) => map,
+        None => {
+            response!(
+                Response::Wrong(format!("no line map for `{}`", file)),
+                Response::Note("compile with `--wumap` to write one")
+            );
+
+            return build.fail(Failure::Io);
+        }
+    };
+
+    let found = if is_lua {
+        map.wu_line(line)
+            .map(|line| format!("{}:{}", map.source, line))
+            .into_iter()
+            .collect::<Vec<_>>()
+    } else {
+        map.lua_lines(line)
+            .iter()
+            .map(|line| format!("{}:{}", lua.display(), line))
+            .collect()
+    };
+
+    if found.is_empty() {
+        response!(Response::Wrong(format!("nothing maps to `{}`", location)));
+
+        return build.fail(Failure::Usage);
+    }
+
+    for location in found {
+        println!("{}", location)
+    }
+}
@@ -0,0 +1,62 @@
+// `wu api` reports, and the breaking changes `--diff` finds between two of them.
+
+use wu::wu::api::{Api, Change};
+
+fn api(entries: &[(&str, &str)]) -> Api {
+    Api {
+        module: "shapes.wu".to_string(),
+        entries: entries
+            .iter()
+            .map(|(name, t)| (name.to_string(), t.to_string()))
+            .collect(),
+    }
+}
+
+#[test]
+fn json_round_trips() {
+    let report = api(&[
+        ("Point", "struct"),
+        ("Point x", "float"),
+        ("say", "fun(str) -> \"str\""),
+    ]);
+
+    assert_eq!(Api::from_json(&report.to_json()), Some(report));
+}
+
+#[test]
+fn finds_breaking_changes() {
+    let old = api(&[
+        ("Point", "struct"),
+        ("Point x", "float"),
+        ("area", "fun(int) -> int"),
+        ("scale", "int"),
+    ]);
+    let new = api(&[
+        ("Point", "struct"),
+        ("Point x", "float"),
+        ("Point y", "float"),
+        ("area", "fun(float) -> float"),
+        ("origin", "fun() -> Point"),
+    ]);
+
+    let changes = new.changes_since(&old);
+
+    assert_eq!(
+        changes,
+        [
+            Change::Changed(
+                "area".into(),
+                "fun(int) -> int".into(),
+                "fun(float) -> float".into()
+            ),
+            Change::Removed("scale".into(), "int".into()),
+            Change::Gained("Point".into(), "y".into(), "float".into()),
+            Change::Added("origin".into(), "fun() -> Point".into()),
+        ]
+    );
+
+    assert_eq!(
+        changes.iter().filter(|change| change.is_breaking()).count(),
+        3
+    )
+}
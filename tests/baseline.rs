@@ -0,0 +1,88 @@
+// the warnings `wu lint --baseline` records, and how later runs leave them out.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use wu::wu::baseline::Baseline;
+
+// too many parameters for `--max-params 2`
+const LONG: &str = "fun(a: int, b: int, c: int) {}";
+
+fn project(name: &str) -> PathBuf {
+    common::project(
+        &format!("baseline-{}", name),
+        &[
+            ("a.wu", &format!("f: {}\ng: {}\n", LONG, LONG)),
+            ("b.wu", &format!("h: {}\n", LONG)),
+        ],
+    )
+}
+
+fn warnings(output: &str) -> usize {
+    output.matches("weird: function takes 3 parameters").count()
+}
+
+#[test]
+fn records_then_leaves_out() {
+    let root = project("records");
+
+    let lint = [
+        "--max-params",
+        "2",
+        "lint",
+        ".",
+        "--baseline",
+        "wu-baseline.json",
+    ];
+
+    let run = common::wu(&root, &lint);
+    let output = common::stdout(&run);
+
+    assert!(run.status.success());
+    assert_eq!(warnings(&output), 3);
+    assert!(output.contains("Baselined 3 warnings in `wu-baseline.json`"));
+    assert!(!root.join("a.lua").exists());
+
+    let json = fs::read_to_string(root.join("wu-baseline.json")).unwrap();
+    let baseline = Baseline::from_json(&json).unwrap();
+
+    assert_eq!(baseline.len(), 3);
+    assert_eq!(Baseline::from_json(&baseline.to_json()), Some(baseline));
+
+    let run = common::wu(&root, &lint);
+    let output = common::stdout(&run);
+
+    assert!(run.status.success());
+    assert_eq!(warnings(&output), 0);
+    assert!(output.contains("0 warnings (3 baselined)"));
+
+    // lines moving around don't matter, one more of the same does
+    let a = format!("\n\nk: {}\nf: {}\ng: {}\n", LONG, LONG, LONG);
+    fs::write(root.join("a.wu"), a).unwrap();
+
+    let run = common::wu(
+        &root,
+        &["--max-params", "2", "--baseline=wu-baseline.json", "."],
+    );
+    let output = common::stdout(&run);
+
+    assert!(run.status.success());
+    assert_eq!(warnings(&output), 1);
+    assert!(output.contains("1 warning (3 baselined)"));
+
+    common::remove(&root);
+}
+
+#[test]
+fn missing_baseline() {
+    let root = project("missing");
+
+    let run = common::wu(&root, &["--baseline", "wu-baseline.json", "."]);
+
+    assert!(!run.status.success());
+    assert!(common::stdout(&run).contains("there's no baseline `wu-baseline.json`"));
+
+    common::remove(&root);
+}
@@ -0,0 +1,49 @@
+// `wu codemod`, migrating every module of a folder without touching anything else.
+
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+#[test]
+fn typed_splats() {
+    let root = env::temp_dir().join(format!("wu-codemod-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("lib")).unwrap();
+
+    let io = "print: extern fun(...) = \"print\" # out\nread: extern fun(...?) -> ...\n";
+    let show = "show: fun(...xs: int) {\n  print(*xs)\n}\n";
+
+    fs::write(root.join("io.wu"), io).unwrap();
+    fs::write(root.join("lib/show.wu"), show).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .args(["--color", "never", "codemod", "typed-splats", "."])
+        .current_dir(&root)
+        .env("WU_HOME", env::temp_dir())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("2 edits in 1 module"));
+
+    assert_eq!(
+        fs::read_to_string(root.join("io.wu")).unwrap(),
+        "print: extern fun(...any) = \"print\" # out\nread: extern fun(...?) -> ...any\n"
+    );
+
+    assert_eq!(fs::read_to_string(root.join("lib/show.wu")).unwrap(), show);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn unknown_codemod() {
+    let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .args(["--color", "never", "codemod", "nope"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("known codemods: `typed-splats`"));
+}
@@ -0,0 +1,109 @@
+// what the integration tests share: projects written to folders of their own, each with an empty
+// `$WU_HOME` next to it, so tests running side by side never see each other's files, and the
+// `wu` binary run on them.
+
+// not every test uses all of it
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static PROJECTS: AtomicUsize = AtomicUsize::new(0);
+
+// writes `files` to a new project, giving its root
+pub fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let folder = env::temp_dir().join(format!(
+        "wu-{}-{}-{}",
+        name,
+        process::id(),
+        PROJECTS.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let root = folder.join("project");
+
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&root).unwrap();
+    fs::create_dir_all(folder.join("home")).unwrap();
+
+    for (path, content) in files.iter() {
+        let path = root.join(path);
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    root
+}
+
+// the folder the project at `root` is in, for files that are meant to be outside of it
+pub fn outside(root: &Path) -> PathBuf {
+    root.parent().unwrap().to_path_buf()
+}
+
+// the `$WU_HOME` of the project at `root`, as wu wants it, ending in a slash
+pub fn home(root: &Path) -> String {
+    format!("{}/", outside(root).join("home").display())
+}
+
+// removes the project at `root`, its `$WU_HOME` and anything else written next to it
+pub fn remove(root: &Path) {
+    fs::remove_dir_all(outside(root)).unwrap()
+}
+
+// runs wu with `args` in the project at `root`
+pub fn wu(root: &Path, args: &[&str]) -> Output {
+    command(root, root, args).output().unwrap()
+}
+
+// wu with `args`, to run in `folder` for the project at `root`: with its `$WU_HOME`, no colors
+// and lines long enough that nothing shown is wrapped
+pub fn command(folder: &Path, root: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_wu"));
+
+    command
+        .args(args)
+        .current_dir(folder)
+        .env("WU_HOME", home(root))
+        .env("NO_COLOR", "1")
+        .env("COLUMNS", "1000");
+
+    command
+}
+
+// what a run of wu printed to stdout
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+// what a run of wu printed, to stdout and then stderr
+pub fn shown(output: &Output) -> String {
+    stdout(output) + &String::from_utf8_lossy(&output.stderr)
+}
+
+// extra command line flags from a first line like `# flags: --inline`
+pub fn flags(fixture: &Path) -> Vec<String> {
+    let source = fs::read_to_string(fixture).unwrap();
+
+    source
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("# flags:"))
+        .map(|flags| flags.split_whitespace().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+// the fixtures in `folder`, in order
+pub fn fixtures(folder: &Path) -> Vec<PathBuf> {
+    let mut fixtures = fs::read_dir(folder)
+        .unwrap()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wu"))
+        .collect::<Vec<_>>();
+
+    fixtures.sort();
+    fixtures
+}
@@ -0,0 +1,75 @@
+// `wu complete`, the required members a `new` around a position still leaves out.
+
+use std::env;
+use std::fs;
+use std::process::{self, Command, Output};
+
+const PLAYERS: &str = "\
+Point: struct {
+  x: int
+  y: int
+  label: str?
+}
+
+Player: struct {
+  name: str
+  pos: Point
+  hp: int
+}
+
+p := new Player {
+  name: \"bob\"
+  pos: new Point {
+    x: 1
+  }
+
+}
+";
+
+fn complete(name: &str, at: &str) -> Output {
+    let root = env::temp_dir().join(format!("wu-complete-{}-{}", name, process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("main.wu"), PLAYERS).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .args(["--color", "never", "complete", &format!("main.wu:{}", at)])
+        .current_dir(&root)
+        .env("WU_HOME", env::temp_dir())
+        .output()
+        .unwrap();
+
+    fs::remove_dir_all(root).unwrap();
+
+    output
+}
+
+#[test]
+fn innermost() {
+    let output = complete("inner", "16:5");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "y: int\n");
+}
+
+#[test]
+fn after_failing_member() {
+    // the inner `new` lacking `y` fails the check, which the outer one is still known through
+    let output = complete("outer", "18:3");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hp: int\n");
+}
+
+#[test]
+fn outside() {
+    let output = complete("outside", "1:1");
+
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("no `new` to complete"),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
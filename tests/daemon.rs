@@ -0,0 +1,119 @@
+// `wu daemon`, building for `wu build --use-daemon` with the modules of earlier builds still
+// visited.
+#![cfg(unix)]
+
+mod common;
+
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::thread;
+use std::time::Duration;
+
+fn project(name: &str) -> PathBuf {
+    common::project(
+        &format!("daemon-{}", name),
+        &[
+            ("wu.toml", ""),
+            ("main.wu", "import shapes { area }\n\nr := area(2.0)\n"),
+            ("shapes.wu", "pub area: fun(r: float) -> float { r * r }\n"),
+        ],
+    )
+}
+
+// `wu daemon` started in the project at `root`, once it's listening
+fn start(root: &Path) -> Child {
+    let daemon = common::command(root, root, &["-v", "--color", "never", "daemon"])
+        .spawn()
+        .unwrap();
+
+    for _ in 0..100 {
+        if root.join(".wu-daemon.sock").exists() {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(50))
+    }
+
+    daemon
+}
+
+#[test]
+fn builds_with_modules_kept() {
+    let root = project("kept");
+
+    let mut daemon = start(&root);
+
+    let build = ["-v", "--color", "never", "build", "--use-daemon"];
+
+    let first = common::wu(&root, &build);
+    let first_shown = common::stdout(&first);
+
+    assert!(first.status.success(), "{}", first_shown);
+    assert!(first_shown.contains("Loading"), "{}", first_shown);
+    assert!(root.join("main.lua").is_file());
+
+    let second = common::wu(&root, &build);
+    let second_shown = common::stdout(&second);
+
+    assert!(second.status.success(), "{}", second_shown);
+    assert!(
+        second_shown.contains("shapes.wu (unchanged)"),
+        "{}",
+        second_shown
+    );
+
+    // the daemon's flags are set for all its builds
+    let other = common::wu(&root, &["--color", "never", "build", "--use-daemon"]);
+
+    assert_eq!(other.status.code(), Some(4));
+    assert!(common::stdout(&other)
+        .contains("the daemon builds with `-v --color never`, not `--color never`"));
+
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+
+    common::remove(&root);
+}
+
+#[test]
+fn builds_without_a_daemon() {
+    let root = project("none");
+
+    let output = common::wu(&root, &["build", "--use-daemon"]);
+    let shown = common::stdout(&output);
+
+    assert!(output.status.success(), "{}", shown);
+    assert!(shown.contains("no `wu daemon` to build with"), "{}", shown);
+    assert!(root.join("main.lua").is_file());
+
+    common::remove(&root);
+}
+
+#[test]
+fn stalled_clients_let_go() {
+    let root = project("stalled");
+
+    let mut daemon = start(&root);
+
+    // one that never finishes its request, and one sending more than a request could be
+    let silent = UnixStream::connect(root.join(".wu-daemon.sock")).unwrap();
+    let mut flood = UnixStream::connect(root.join(".wu-daemon.sock")).unwrap();
+
+    let _ = flood.write_all(&vec![b'x'; 4 << 20]);
+
+    let build = ["-v", "--color", "never", "build", "--use-daemon"];
+    let output = common::wu(&root, &build);
+    let shown = common::stdout(&output);
+
+    assert!(output.status.success(), "{}", shown);
+    assert!(!shown.contains("no `wu daemon` to build with"), "{}", shown);
+    assert!(root.join("main.lua").is_file());
+
+    drop(silent);
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+
+    common::remove(&root);
+}
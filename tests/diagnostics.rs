@@ -0,0 +1,47 @@
+// What `Visitor::check` hands back instead of showing: every mistake of a module, taken apart.
+
+use wu::wu::error::{captured, Severity};
+use wu::wu::lexer::Lexer;
+use wu::wu::parser::Parser;
+use wu::wu::source::Source;
+use wu::wu::visitor::{SymTab, Visitor};
+
+#[test]
+fn checking_collects() {
+    let content = "x: int = \"a\"\ny := x + 1\n\nw: str = 2\n";
+    let source = Source::from("collect.wu", content.lines().map(String::from).collect());
+
+    let tokens = Lexer::default(content.chars().collect(), &source)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let ast = Parser::new(tokens, &source).parse().unwrap();
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), ".".to_string());
+    let (checked, shown) = captured(|| visitor.check());
+
+    assert!(checked.is_err());
+    assert_eq!(shown, "");
+
+    // `y` only goes wrong because of `x`, so isn't told
+    let found = visitor
+        .diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let pos = diagnostic.pos.as_ref().unwrap();
+
+            (diagnostic.severity, diagnostic.file.as_deref(), (pos.0).0)
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        found,
+        vec![
+            (Severity::Wrong, Some("collect.wu"), 1),
+            (Severity::Wrong, Some("collect.wu"), 4)
+        ]
+    );
+
+    assert!(visitor.diagnostics[0].message.starts_with("mismatched types"));
+    assert!(visitor.diagnostics[0].shown.contains("--> collect.wu"))
+}
@@ -0,0 +1,58 @@
+// Loading Wu into an embedded Lua through `wu::load_into_lua`. Only built with the `mlua`
+// feature, and a Lua picked through mlua, e.g.
+// `cargo test --features mlua,mlua/lua54,mlua/vendored --test embed`.
+#![cfg(feature = "mlua")]
+
+use mlua::{Function, Lua, Table};
+
+#[test]
+fn loads_and_runs() {
+    let lua = Lua::new();
+
+    let source = "\
+double: fun(n: int) -> int {
+  n * 2
+}
+";
+
+    let chunk = wu::load_into_lua(&lua, "scripts/double.wu", source).unwrap();
+    let exports: Table = chunk.call(()).unwrap();
+    let double: Function = exports.get("double").unwrap();
+
+    assert_eq!(double.call::<_, i64>(21).unwrap(), 42);
+}
+
+#[test]
+fn tracebacks_name_the_script() {
+    let lua = Lua::new();
+
+    let source = "\
+fail: fun() {
+  error: extern fun(str) = \"error\"
+  error(\"boom\")
+}
+
+fail()
+";
+
+    let chunk = wu::load_into_lua(&lua, "scripts/fail.wu", source).unwrap();
+    let error = chunk.call::<_, ()>(()).unwrap_err().to_string();
+
+    assert!(error.contains("scripts/fail.wu"), "{}", error);
+}
+
+#[test]
+fn diagnostics_become_the_error() {
+    let lua = Lua::new();
+
+    let error = wu::load_into_lua(&lua, "scripts/wrong.wu", "x: int = \"nope\"\n").unwrap_err();
+
+    match error {
+        mlua::Error::SyntaxError { ref message, .. } => {
+            assert!(message.starts_with("scripts/wrong.wu: failed to compile"), "{}", message);
+            assert!(message.contains("mismatched"), "{}", message)
+        }
+
+        _ => panic!("expected a syntax error, got {}", error),
+    }
+}
@@ -0,0 +1,223 @@
+// Compiles every `tests/execution/*.wu`, runs the lua under `lua` (or $WU_LUA) and compares what it
+// prints with `name.stdout`, catching generated code that type checks but does the wrong thing.
+//
+// Fixtures compiled with `--target` run under $WU_LUA_<target> when it's set, e.g. `WU_LUA_5_1`
+// or `WU_LUA_JIT`, and under `lua` otherwise.
+//
+// Running them needs lua, so those tests are ignored unless asked for with
+// `cargo test --test execution -- --ignored`, and fail when there's no lua to run. Without it the
+// fixtures are still compiled. `UPDATE_SNAPSHOTS=1` rewrites the expected output from the current
+// run.
+
+mod common;
+
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+
+// the lua to run what was compiled with `flags` under, for its `--target` if one is set
+fn interpreter(flags: &[String], lua: &str) -> String {
+    let target = flags.iter().skip_while(|flag| *flag != "--target").nth(1);
+    let variable =
+        target.map(|target| format!("WU_LUA_{}", target.replace('.', "_").to_uppercase()));
+
+    variable
+        .and_then(|variable| env::var(variable).ok())
+        .unwrap_or_else(|| lua.to_string())
+}
+
+fn folder() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("execution")
+}
+
+// the lua compiled from `fixture`, or why it didn't compile
+fn compile(fixture: &Path) -> Result<Vec<u8>, String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .args(["-q", "--stdout", "--color", "never"])
+        .args(common::flags(fixture))
+        .arg(fixture.file_name().unwrap())
+        .current_dir(fixture.parent().unwrap())
+        .env("WU_HOME", env::temp_dir())
+        .output()
+        .unwrap();
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
+
+// runs `command`, failing the test when there's no `lua` to run
+fn run(command: &mut Command, lua: &str) -> Output {
+    match command.output() {
+        Ok(run) => run,
+        Err(why) if why.kind() == ErrorKind::NotFound => {
+            panic!("`{}` not found, set $WU_LUA to the lua to run", lua)
+        }
+        Err(why) => panic!("failed to run `{}`: {}", lua, why),
+    }
+}
+
+#[test]
+fn compiles() {
+    let failures = common::fixtures(&folder())
+        .into_iter()
+        .filter_map(|fixture| {
+            compile(&fixture)
+                .err()
+                .map(|why| format!("{}\n{}", fixture.display(), why))
+        })
+        .collect::<Vec<_>>();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+#[ignore = "needs lua, run with `--ignored` and $WU_LUA"]
+fn execution() {
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let lua = env::var("WU_LUA").unwrap_or_else(|_| "lua".to_string());
+
+    let mut failures = Vec::new();
+
+    for fixture in common::fixtures(&folder()) {
+        let name = fixture.file_name().unwrap().to_str().unwrap();
+        let flags = common::flags(&fixture);
+
+        let compiled = match compile(&fixture) {
+            Ok(compiled) => compiled,
+            Err(why) => {
+                failures.push(format!("{}: failed to compile\n{}", name, why));
+                continue;
+            }
+        };
+
+        let path = env::temp_dir().join(format!(
+            "wu-execution-{}-{}.lua",
+            process::id(),
+            name.trim_end_matches(".wu")
+        ));
+
+        fs::write(&path, &compiled).unwrap();
+
+        let interpreter = interpreter(&flags, &lua);
+        let run = run(Command::new(&interpreter).arg(&path), &interpreter);
+
+        let _ = fs::remove_file(&path);
+
+        let expected_path = fixture.with_extension("stdout");
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        let actual = String::from_utf8_lossy(&run.stdout);
+
+        if update {
+            fs::write(&expected_path, actual.as_bytes()).unwrap();
+            continue;
+        }
+
+        if !run.status.success() {
+            failures.push(format!(
+                "{}: lua exited with {}\n{}",
+                name,
+                run.status,
+                String::from_utf8_lossy(&run.stderr)
+            ))
+        } else if actual != expected {
+            failures.push(format!(
+                "{}: output differs\n--- expected\n{}\n--- actual\n{}",
+                name, expected, actual
+            ))
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "{} execution fixtures failed:\n\n{}",
+            failures.len(),
+            failures.join("\n\n")
+        )
+    }
+}
+
+// what the running program prints, reloads `lib.lua` through the hot driver, then prints again
+const HARNESS: &str = r#"
+local main = dofile("main.lua")
+print("before", main.get())
+
+local fresh = io.open("next/lib.lua"):read("a")
+local lib = io.open("lib.lua", "w")
+lib:write(fresh)
+lib:close()
+
+local signal = io.open(".wu-hot", "w")
+signal:write("1 lib.lua\n")
+signal:close()
+
+local start = os.clock()
+while main.get() == 1 and os.clock() - start < 5 do end
+
+print("after", main.get())
+"#;
+
+#[test]
+#[ignore = "needs lua, run with `--ignored` and $WU_LUA"]
+fn hot_reload_named_imports() {
+    let lua = env::var("WU_LUA").unwrap_or_else(|_| "lua".to_string());
+    let root = env::temp_dir().join(format!("wu-hot-reload-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("next")).unwrap();
+
+    fs::write(root.join("lib.wu"), "pub f: fun() -> int {\n  1\n}\n").unwrap();
+    fs::write(root.join("next/lib.wu"), "pub f: fun() -> int {\n  2\n}\n").unwrap();
+    fs::write(
+        root.join("main.wu"),
+        "import lib { f }\n\npub get: fun() -> int {\n  f()\n}\n",
+    )
+    .unwrap();
+    fs::write(root.join("harness.lua"), HARNESS).unwrap();
+
+    for (folder, file) in [
+        (&root, "lib.wu"),
+        (&root, "main.wu"),
+        (&root.join("next"), "lib.wu"),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+            .args(["-q", "--hot", file])
+            .current_dir(folder)
+            .env("WU_HOME", &root)
+            .output()
+            .unwrap();
+
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let run = run(
+        Command::new(&lua)
+            .args(["-e", wu::wu::hot::DRIVER, "harness.lua"])
+            .current_dir(&root),
+        &lua,
+    );
+
+    let _ = fs::remove_dir_all(&root);
+
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+
+    // `main` imported `f` by name, and calls the reloaded one
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "before\t1\nafter\t2\n"
+    );
+}
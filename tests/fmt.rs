@@ -0,0 +1,95 @@
+// `wu fmt`, `format_source` and `format_range`, laying modules out one way without losing their
+// comments.
+
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+use wu::{format_range, format_source};
+
+const MESSY: &str = "add: fun(a: int,b: int) -> int {\n    # the sum\n    return a+b # done\n}\n\
+                     \n\n\nx: int = add(1,2)\n";
+const TIDY: &str = "add: fun(a: int, b: int) -> int {\n  # the sum\n  return a + b # done\n}\n\
+                    \nx: int = add(1, 2)\n";
+
+#[test]
+fn keeps_comments() {
+    assert_eq!(format_source(MESSY), TIDY);
+}
+
+#[test]
+fn formats_once() {
+    assert_eq!(format_source(TIDY), TIDY);
+}
+
+#[test]
+fn keeps_bare_splats() {
+    let splats = "print: extern fun(...)\nlog:   extern fun(str, ...any) -> ...\n";
+
+    // spelling them out is what `wu codemod typed-splats` is for
+    assert_eq!(format_source(splats), splats);
+}
+
+#[test]
+fn leaves_what_doesnt_parse() {
+    assert_eq!(format_source("z := (\n"), "z := (\n");
+}
+
+#[test]
+fn formats_a_range() {
+    let x = MESSY.find("x: int").unwrap();
+
+    // only the statement touched, the function above stays as messy as it was
+    assert_eq!(
+        format_range(MESSY, x + 3, x + 5),
+        Some((7, 8, "x: int = add(1, 2)\n".to_string()))
+    );
+
+    // the whole function, with the blank lines after it
+    let sum = MESSY.find("a+b").unwrap();
+
+    assert_eq!(
+        format_range(MESSY, sum, sum),
+        Some((0, 7, TIDY[..TIDY.find("x: int").unwrap()].to_string()))
+    );
+
+    assert_eq!(format_range("z := (\n", 0, 1), None);
+}
+
+#[test]
+fn check_then_write() {
+    let root = env::temp_dir().join(format!("wu-fmt-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    fs::write(root.join("main.wu"), MESSY).unwrap();
+    fs::write(root.join("ok.wu"), "y := 1\n").unwrap();
+
+    let fmt = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_wu"))
+            .args(["--color", "never", "fmt"])
+            .args(args)
+            .current_dir(&root)
+            .env("WU_HOME", env::temp_dir())
+            .output()
+            .unwrap()
+    };
+
+    let output = fmt(&["--check"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("`main.wu` isn't formatted"));
+    assert_eq!(fs::read_to_string(root.join("main.wu")).unwrap(), MESSY);
+
+    let output = fmt(&["."]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 module of 2"));
+    assert_eq!(fs::read_to_string(root.join("main.wu")).unwrap(), TIDY);
+    assert_eq!(fs::read_to_string(root.join("ok.wu")).unwrap(), "y := 1\n");
+
+    assert!(fmt(&["--check"]).status.success());
+
+    fs::remove_dir_all(root).unwrap();
+}
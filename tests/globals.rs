@@ -0,0 +1,61 @@
+// The check that generated lua only puts the names marked `@global` in `_G`.
+
+use wu::wu::compiler::globals::assigned;
+
+#[test]
+fn finds_functions_and_assignments_to_undeclared_names() {
+    let lua = "\
+return (function()
+  function speed(world)
+    return world.x
+  end
+
+  count = 1
+end)()";
+
+    assert_eq!(
+        assigned(lua),
+        [(2, "speed".to_string()), (6, "count".to_string())]
+    );
+}
+
+#[test]
+fn leaves_out_locals_parameters_and_fields() {
+    let lua = "\
+return (function()
+  local a, b = 1, 2
+  local function f(x, y)
+    x = y
+    for i, v in ipairs(a) do
+      v = i
+    end
+    if x == y then
+      local c = 1
+    else
+      a = 3
+    end
+  end
+  local t = {
+    a = 1,
+    g = function(z) z = 2 end,
+  }
+  t.u = 1
+  function t.h() end
+  return {
+    f = f,
+  }
+end)()";
+
+    assert!(assigned(lua).is_empty(), "{:?}", assigned(lua));
+}
+
+#[test]
+fn locals_end_with_their_block() {
+    let lua = "\
+if true then
+  local c = 1
+end
+c = 2";
+
+    assert_eq!(assigned(lua), [(4, "c".to_string())]);
+}
@@ -0,0 +1,83 @@
+// Runs the compiler over every `tests/golden/*.wu` and compares what comes out with the snapshots
+// next to it: `name.lua` for the compiled lua, `name.diagnostics` for errors and warnings.
+//
+// `UPDATE_SNAPSHOTS=1 cargo test` rewrites the snapshots from the current output. A fixture can
+// pass flags to the compiler with a first line like `# flags: --inline`.
+
+mod common;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// compares `actual` with the snapshot at `path`, where no output means no snapshot
+fn check(path: &Path, actual: &str, update: bool) -> Option<String> {
+    let expected = fs::read_to_string(path).ok();
+
+    if expected.as_deref() == Some(actual) || (expected.is_none() && actual.is_empty()) {
+        return None;
+    }
+
+    if update {
+        if actual.is_empty() {
+            fs::remove_file(path).unwrap()
+        } else {
+            fs::write(path, actual).unwrap()
+        }
+
+        return None;
+    }
+
+    Some(match expected {
+        None => format!("{}: no snapshot, but got:\n{}", path.display(), actual),
+        Some(expected) => format!(
+            "{}: snapshot differs\n--- expected\n{}\n--- actual\n{}",
+            path.display(),
+            expected,
+            actual
+        ),
+    })
+}
+
+#[test]
+fn golden() {
+    let folder = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden");
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+
+    let mut failures = Vec::new();
+
+    for fixture in common::fixtures(&folder) {
+        let name = fixture.file_name().unwrap().to_str().unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+            .args(["-q", "--stdout", "--color", "never"])
+            .args(common::flags(&fixture))
+            .arg(name)
+            .current_dir(&folder)
+            .env("WU_HOME", env::temp_dir())
+            .env("COLUMNS", "100")
+            .output()
+            .unwrap();
+
+        let lua = String::from_utf8_lossy(&output.stdout);
+        let diagnostics = String::from_utf8_lossy(&output.stderr);
+
+        failures.extend(check(&fixture.with_extension("lua"), &lua, update));
+        failures.extend(check(
+            &fixture.with_extension("diagnostics"),
+            &diagnostics,
+            update,
+        ));
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "{} golden snapshots differ, rerun with UPDATE_SNAPSHOTS=1 to accept:\n\n{}",
+            failures.len(),
+            failures.join("\n\n")
+        )
+    }
+}
@@ -0,0 +1,5 @@
+return (function()
+  print("release")
+  return {
+  }
+end)()
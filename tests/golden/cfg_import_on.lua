@@ -0,0 +1,10 @@
+return (function()
+  local shapes = require('modules.shapes')
+  local area = shapes['area']
+  
+  
+  print(area(2, 3))
+  return {
+    area = area,
+  }
+end)()
@@ -0,0 +1,8 @@
+return (function()
+  local largest = 9223372036854775807
+  
+  print((function(a, b) local r = a + b return ((b >= 0) == (r >= a)) and r or nil end)(largest, 1), (function(a, b) local r = a - b return ((b >= 0) == (r <= a)) and r or nil end)(largest, 1), (function(a, b) local r = a * b return (a == 0 or (r // a == b and not (a == -1 and b == math.mininteger))) and r or nil end)(largest, 2))
+  return {
+    largest = largest,
+  }
+end)()
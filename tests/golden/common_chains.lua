@@ -0,0 +1,53 @@
+return (function()
+  local Vec = {}
+  Vec.__meta = {__index = Vec}
+  function Vec.__new(fields)
+    return setmetatable(fields, Vec.__meta)
+  end
+  
+  local Body = {}
+  Body.__meta = {__index = Body}
+  function Body.__new(fields)
+    return setmetatable(fields, Body.__meta)
+  end
+  
+  local World = {}
+  World.__meta = {__index = World}
+  function World.__new(fields)
+    return setmetatable(fields, World.__meta)
+  end
+  
+  local function speed(world)
+    local __world_player_velocity_x = world['player']['velocity']['x']
+    local __world_player_velocity_y = world['player']['velocity']['y']
+    return ((__world_player_velocity_x * __world_player_velocity_x) + (__world_player_velocity_y * __world_player_velocity_y))
+  end
+  
+  local function nudge(world, by)
+    world['player']['position']['x'] = (world['player']['position']['x'] + by)
+    world['player']['position']['y'] = (world['player']['position']['y'] + by)
+  end
+  
+  local function guarded(world, go)
+    if go then
+      return world['player']['position']['x']
+    end
+    local __world_player_position_x = world['player']['position']['x']
+    return (__world_player_position_x * __world_player_position_x)
+  end
+  
+  local function logged(world)
+    print(world['player']['velocity']['x'])
+    return world['player']['velocity']['x']
+  end
+  
+  return {
+    Vec = Vec,
+    Body = Body,
+    World = World,
+    speed = speed,
+    nudge = nudge,
+    guarded = guarded,
+    logged = logged,
+  }
+end)()
@@ -0,0 +1,38 @@
+return (function()
+  local function blend(a, b, c, d)
+    return (((a + b) + c) + d)
+  end
+  
+  local function walk(xs)
+    local total = 0
+    for x in ipairs(xs) do  
+      local __brk_0 = false
+      repeat
+      if (x > 0) then
+        while (total < x) do
+          repeat
+          total = (total + 1)
+          until true
+        end
+      end
+      
+      until true
+      if __brk_1 then break end
+    end
+    return total
+  end
+  
+  local function small(x)
+    if (x > 0) then
+      return x
+    else
+        return 0
+    end
+  end
+  
+  return {
+    blend = blend,
+    walk = walk,
+    small = small,
+  }
+end)()
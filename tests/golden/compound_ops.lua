@@ -0,0 +1,35 @@
+return (function()
+  local Foo = {}
+  Foo.__meta = {__index = Foo}
+  function Foo.__new(fields)
+    return setmetatable(fields, Foo.__meta)
+  end
+  
+  local grr = Foo.__new({
+    b = 100,
+  })
+  
+  grr['b'] = (grr['b'] + 10)
+  local a = 100
+  
+  a = (a * 100)
+  a = (a + 10)
+  a = (a / 10)
+  local c = "duggiduggiduk"
+  
+  c = (c .. "hey")
+  c = (c .. tostring(a))
+  local function lol(x)
+    return x
+  end
+  
+  print(lol(10))
+  print(lol(lol(lol(10))))
+  return {
+    Foo = Foo,
+    grr = grr,
+    a = a,
+    c = c,
+    lol = lol,
+  }
+end)()
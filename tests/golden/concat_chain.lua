@@ -0,0 +1,63 @@
+return (function()
+  local function join(xs)
+    local out = ""
+    local __buf_out = {out}
+    for i, x in ipairs(xs) do  
+      local __brk_0 = false
+      repeat
+      __buf_out[#__buf_out + 1] = x
+      __buf_out[#__buf_out + 1] = ", "
+      until true
+      if __brk_0 then break end
+    end
+    out = table.concat(__buf_out)
+    return out
+  end
+  
+  local function count(n)
+    local s = "<"
+    local i = 0
+    local __buf_s = {s}
+    while (i < n) do
+      repeat
+      __buf_s[#__buf_s + 1] = tostring(i)
+      i = (i + 1)
+      until true
+    end
+    s = table.concat(__buf_s)
+    return (s .. ">")
+  end
+  
+  local function seen(n)
+    local s = ""
+    for __iterator_0 = 1, n do
+      
+      local __brk_0 = false
+      repeat
+      s = (s .. "x")
+      if (s == "xxx") then
+        __brk_0 = true break
+      end
+      
+      until true
+      if __brk_0 then break end
+    end
+    return s
+  end
+  
+  local function line(a, b)
+    return table.concat({a, " ", b, " ", a})
+  end
+  
+  local function short(a)
+    return (a .. "!" .. tostring(1))
+  end
+  
+  return {
+    join = join,
+    count = count,
+    seen = seen,
+    line = line,
+    short = short,
+  }
+end)()
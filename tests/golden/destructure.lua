@@ -0,0 +1,19 @@
+return (function()
+  local function divmod(a, b)
+    return (a / b), (a % b)
+  end
+  
+  local function named()
+    return "answer", 42
+  end
+  
+  local q, r = divmod(7, 2)
+  
+  local name, value = named()
+  
+  q, r = divmod(q, 2)
+  return {
+    divmod = divmod,
+    named = named,
+  }
+end)()
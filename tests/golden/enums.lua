@@ -0,0 +1,50 @@
+return (function()
+  local Shape = {
+    ['Circle'] = function(__1) return {tag = "Circle", __1} end,
+    ['Rect'] = function(__1, __2) return {tag = "Rect", __1, __2} end,
+    ['Empty'] = {tag = "Empty"},
+  }
+  
+  local function area(s)
+      local __match_8_3 = s
+      if (__match_8_3['tag'] == "Circle") then
+        local r = __match_8_3[1]
+        return ((3 * r) * r)
+      elseif (__match_8_3['tag'] == "Rect") then
+          local w = __match_8_3[1]
+          local h = __match_8_3[2]
+          return (w * h)
+      else
+          return 0
+      end
+  end
+  
+  local function round(s)
+    local is = (function()
+      local __match_16_9 = s
+      if (__match_16_9['tag'] == "Circle") then
+        return true
+      else
+          return false
+      end
+    end)()
+    return is
+  end
+  
+  do
+    local __match_24_1 = Shape['Rect'](1, 2)
+    if (__match_24_1['tag'] == "Rect") then
+      local h = __match_24_1[2]
+      print(h)
+    else
+        print("not a rect")
+    end
+  end
+  
+  print(area(Shape['Circle'](2)), round(Shape['Empty']))
+  return {
+    Shape = Shape,
+    area = area,
+    round = round,
+  }
+end)()
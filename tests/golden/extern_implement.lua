@@ -0,0 +1,15 @@
+return (function()
+  local File = {}
+  File.__meta = {__index = File}
+  function File.__new(fields)
+    return setmetatable(fields, File.__meta)
+  end
+  
+  local file = File.__new({})
+  
+  file['close']()
+  return {
+    File = File,
+    file = file,
+  }
+end)()
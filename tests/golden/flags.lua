@@ -0,0 +1,17 @@
+return (function()
+  local Layer = {
+    ['Ground'] = 1,
+    ['Water'] = 2,
+    ['Air'] = 4,
+  }
+  
+  local reach = (function(a, b) local r, bit = 0, 1 while a > 0 or b > 0 do if a % 2 == 1 or b % 2 == 1 then r = r + bit end a, b, bit = math.floor(a / 2), math.floor(b / 2), bit * 2 end return r end)(Layer['Ground'], Layer['Air'])
+  
+  reach = (function(a, b) local r, bit = 0, 1 while a > 0 or b > 0 do if a % 2 == 1 or b % 2 == 1 then r = r + bit end a, b, bit = math.floor(a / 2), math.floor(b / 2), bit * 2 end return r end)(reach, Layer['Water'])
+  print((function(a, b) return (function(a, b) local r, bit = 0, 1 while a > 0 or b > 0 do if a % 2 == 1 and b % 2 == 1 then r = r + bit end a, b, bit = math.floor(a / 2), math.floor(b / 2), bit * 2 end return r end)(a, b) == b end)(reach, Layer['Water']))
+  print(((function(a, b) local r, bit = 0, 1 while a > 0 or b > 0 do if a % 2 == 1 and b % 2 == 1 then r = r + bit end a, b, bit = math.floor(a / 2), math.floor(b / 2), bit * 2 end return r end)(reach, Layer['Air']) == Layer['Air']))
+  return {
+    Layer = Layer,
+    reach = reach,
+  }
+end)()
@@ -0,0 +1,14 @@
+return (function()
+  local Layer = {
+    ['Ground'] = 1,
+    ['Water'] = 2,
+  }
+  
+  local reach = (Layer['Ground'] | Layer['Water'])
+  
+  print((function(a, b) return (a & b) == b end)(reach, Layer['Ground']))
+  return {
+    Layer = Layer,
+    reach = reach,
+  }
+end)()
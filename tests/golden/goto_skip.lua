@@ -0,0 +1,28 @@
+return (function()
+  local total = 0
+  
+  for i, word in ipairs({
+    [1] = "a",
+    [2] = "b",
+    [3] = "c"
+  }) do
+    if (word == "b") then
+      goto __continue_1
+    end
+    
+    total = (total + 1)
+    ::__continue_1::
+  end
+  
+  while (total > 0) do
+    total = (total - 1)
+    if (total == 2) then
+      break
+    end
+    
+  end
+  
+  return {
+    total = total,
+  }
+end)()
@@ -0,0 +1,21 @@
+return (function()
+  local function render(xs)
+    local __love_graphics_draw = love['graphics']['draw']
+    __love_graphics_draw(1)
+    __love_graphics_draw(2)
+    love['graphics']['print']("x")
+    local function inner()
+      return love['graphics']['draw'](3)
+    end
+  end
+  
+  local function shadow(love)
+    return print(love)
+  end
+  
+  return {
+    love = love,
+    render = render,
+    shadow = shadow,
+  }
+end)()
@@ -0,0 +1,63 @@
+return (function()
+  local Point = {}
+  Point.__meta = {__index = Point}
+  function Point.__new(fields)
+    return setmetatable(fields, Point.__meta)
+  end
+  
+  Point['show'] = function(self)
+    return print("point", self['x'])
+  end
+  
+  
+  
+  local count = 0
+  
+  local items = {
+  }
+  
+  local function bump()
+    count = (count + 1)
+    return count
+  end
+  
+  local __exports = {
+    Point = Point,
+    count = count,
+    items = items,
+    bump = bump,
+  }
+  
+  function __exports.__state()
+    return {
+      count = count,
+      items = items,
+    }
+  end
+  
+  function __exports.__reload(old)
+    local state = old.__state and old.__state() or {}
+    if state.count ~= nil then
+      count = state.count
+    end
+    if state.items ~= nil then
+      items = state.items
+    end
+    if old.Point then
+      for key, value in pairs(Point) do
+        if key ~= "__meta" then
+          old.Point[key] = value
+        end
+      end
+      Point = old.Point
+    end
+    __exports.count = count
+    __exports.items = items
+    __exports.Point = Point
+    for key, value in pairs(__exports) do
+      old[key] = value
+    end
+  end
+  
+  return __exports
+end)()
@@ -0,0 +1,10 @@
+return (function()
+  local sizes = require('modules.rooted.sizes')
+  local square = sizes['square']
+  
+  
+  print(square(3))
+  return {
+    square = square,
+  }
+end)()
@@ -0,0 +1,10 @@
+return (function()
+  local exact = 9007199254740992
+  
+  local past = 9007199254740993
+  
+  return {
+    exact = exact,
+    past = past,
+  }
+end)()
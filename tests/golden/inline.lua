@@ -0,0 +1,50 @@
+return (function()
+  local function square(x)
+    return (x * x)
+  end
+  
+  local function area(w, h)
+    return (w * h)
+  end
+  
+  local function label(n)
+    return ("n = " .. tostring(n))
+  end
+  
+  local function either(a, b)
+    return (a or b)
+  end
+  
+  local function noisy()
+    print("called")
+    return 1
+  end
+  
+  local function changing(x)
+    return (x + 1)
+  end
+  
+  changing = function(x)
+    return (x + 2)
+  end
+  
+  local y = 3
+  
+  print((y * y))
+  print(6)
+  print((noisy() * 3))
+  print(area(noisy(), noisy()))
+  print(("n = " .. tostring(area(1, 2))))
+  print(either(true, (noisy() == 1)))
+  print(changing(1))
+  area(noisy(), 1)
+  return {
+    square = square,
+    area = area,
+    label = label,
+    either = either,
+    noisy = noisy,
+    changing = changing,
+    y = y,
+  }
+end)()
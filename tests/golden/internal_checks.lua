@@ -0,0 +1,32 @@
+return (function()
+  local __unpack = table.unpack or unpack
+  
+  local shapes = require('modules.shapes')
+  local area = shapes['area']
+  
+  
+  local function show(...)
+    local xs = {...}
+    return print(__unpack(xs))
+  end
+  
+  local function forward(first, ...)
+    local ys = {...}
+    return show(first, __unpack(ys))
+  end
+  
+  local xs = {
+    [1] = 1,
+    [2] = 2,
+    [3] = 3
+  }
+  
+  forward(area(xs[1], 3), 2, 3)
+  show(area(2, 3))
+  return {
+    area = area,
+    show = show,
+    forward = forward,
+    xs = xs,
+  }
+end)()
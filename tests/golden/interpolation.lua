@@ -0,0 +1,19 @@
+return (function()
+  local name = "wu"
+  
+  local hits = 3
+  
+  local ratio = 2
+  
+  local greeting = table.concat({"hello ", name, ", ", tostring(hits), " hits at ", string.format("%.14g", ratio)})
+  
+  local only = name
+  
+  return {
+    name = name,
+    hits = hits,
+    ratio = ratio,
+    greeting = greeting,
+    only = only,
+  }
+end)()
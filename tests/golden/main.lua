@@ -0,0 +1,28 @@
+local __main = (function()
+  local x = 0
+  
+  local function load()
+  end
+  
+  local function update(dt)
+    x = (x + dt)
+  end
+  
+  local function draw()
+  end
+  
+  local function keypressed(key)
+  end
+  
+  return {
+    x = x,
+    load = load,
+    update = update,
+    draw = draw,
+    keypressed = keypressed,
+  }
+end)()
+
+love.load = __main.load
+love.update = __main.update
+love.draw = __main.draw
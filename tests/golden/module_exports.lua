@@ -0,0 +1,31 @@
+return (function()
+  local shapes = (function()
+    local Rect = {}
+    Rect.__meta = {__index = Rect}
+    function Rect.__new(fields)
+      return setmetatable(fields, Rect.__meta)
+    end
+    local scale = 2
+    local function area(r)
+      return ((r['w'] * r['h']) * scale)
+    end
+    
+    return {
+      area = area,
+      Rect = Rect,
+    }
+  end)()
+  
+  local r = shapes['Rect'].__new({
+    w = 10,
+    h = 5,
+  })
+  
+  local size = shapes['area'](r)
+  
+  return {
+    shapes = shapes,
+    r = r,
+    size = size,
+  }
+end)()
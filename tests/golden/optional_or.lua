@@ -0,0 +1,25 @@
+return (function()
+  local x = nil
+  
+  local a = (x or 3)
+  
+  local b = (x or nil)
+  
+  local c = (x and "set")
+  
+  local d = (true or false)
+  
+  local e = false
+  
+  local f = (function(__maybe) if __maybe == nil then return true end return __maybe end)(e)
+  
+  return {
+    x = x,
+    a = a,
+    b = b,
+    c = c,
+    d = d,
+    e = e,
+    f = f,
+  }
+end)()
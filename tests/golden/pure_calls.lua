@@ -0,0 +1,57 @@
+return (function()
+  local println = print
+  
+  local function square(x)
+    return (x * x)
+  end
+  
+  local function sum_squares(a, b)
+    return (square(a) + square(b))
+  end
+  
+  local function shout(s)
+    println(s)
+    return s
+  end
+  
+  local count = 0
+  
+  local function bump()
+    count = (count + 1)
+    return count
+  end
+  
+  local function fact(n)
+    if (n < 2) then
+      return 1
+    end
+    return (n * fact((n - 1)))
+  end
+  
+  square(bump())
+  shout("hi")
+  fact(5)
+  local x = sum_squares(1, 2)
+  
+  local function swapped(x)
+    return x
+  end
+  
+  swapped(1)
+  swapped = function(x)
+    shout("swapped")
+    return x
+  end
+  
+  return {
+    println = println,
+    square = square,
+    sum_squares = sum_squares,
+    shout = shout,
+    count = count,
+    bump = bump,
+    fact = fact,
+    x = x,
+    swapped = swapped,
+  }
+end)()
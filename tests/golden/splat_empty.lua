@@ -0,0 +1,12 @@
+return (function()
+  local function log(level, ...)
+    local parts = {...}
+    return print(level)
+  end
+  
+  log(1)
+  log(1, "a", "b")
+  return {
+    log = log,
+  }
+end)()
@@ -0,0 +1,14 @@
+return (function()
+  local function show(n)
+    return ("n is " .. tostring(n))
+  end
+  
+  local function same(x, y)
+    return y
+  end
+  
+  return {
+    show = show,
+    same = same,
+  }
+end)()
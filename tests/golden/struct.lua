@@ -0,0 +1,37 @@
+return (function()
+  local Foo = {}
+  Foo.__meta = {__index = Foo}
+  function Foo.__new(fields)
+    return setmetatable(fields, Foo.__meta)
+  end
+  
+  Foo['foobar'] = function(a)
+  end
+  
+  
+  
+  Foo['boo'] = function()
+    return Foo.__new({})
+  end
+  
+  
+  
+  Foo['foo'] = function()
+    return Foo.__new({})
+  end
+  
+  
+  Foo['bob'] = function(self, a)
+    return Foo.__new({})
+  end
+  
+  
+  
+  local fo = Foo['foo']()
+  
+  return {
+    Foo = Foo,
+    Bar = Bar,
+    fo = fo,
+  }
+end)()
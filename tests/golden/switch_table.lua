@@ -0,0 +1,84 @@
+return (function()
+  local len = #
+  
+  local Vm = {}
+  Vm.__meta = {__index = Vm}
+  function Vm.__new(fields)
+    return setmetatable(fields, Vm.__meta)
+  end
+  
+  local function run(vm, code)
+    local __switch_1 = {
+      [0] = function()
+        return print("nop")
+      end,
+      [1] = function()
+        vm['top'] = (vm['top'] + 1)
+        vm['stack'][vm['top']] = 1
+      end,
+      [2] = function()
+        return print("two", vm['top'])
+      end,
+      [3] = function()
+        return print("three")
+      end,
+    }
+    while (vm['pc'] <= len(code)) do
+      repeat
+      local op = code[vm['pc']]
+      
+      vm['pc'] = (vm['pc'] + 1)
+      do
+        local __case = __switch_1[op]
+        if __case then
+          __case()
+        end
+      end
+      
+      until true
+    end
+  end
+  
+  local __switch_2 = {
+    [1] = function()
+      return print("idle")
+    end,
+    [2] = function()
+      return print("run")
+    end,
+    [3] = function()
+      return print("jump")
+    end,
+    [4] = function()
+      return print("fall")
+    end,
+  }
+  local function kind(n)
+    do
+      local __case = __switch_2[n]
+      if __case then
+        return __case()
+      end
+    end
+  end
+  
+  local function sparse(n)
+      local __switch_tmp_32 = n
+      if (1 == __switch_tmp_32) then
+      print("one")elseif (20 == __switch_tmp_32) then
+        print("twenty")
+      elseif (300 == __switch_tmp_32) then
+        print("many")
+      elseif (4000 == __switch_tmp_32) then
+        print("more")
+      end
+  end
+  
+  return {
+    len = len,
+    Vm = Vm,
+    run = run,
+    kind = kind,
+    sparse = sparse,
+  }
+end)()
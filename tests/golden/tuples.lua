@@ -0,0 +1,19 @@
+return (function()
+  local __unpack = table.unpack or unpack
+  
+  local foo = { 1, 1 }
+  
+  local function bar()
+    return __unpack(foo)
+  end
+  
+  local function lol()
+    return 1, 2, 3
+  end
+  
+  return {
+    foo = foo,
+    bar = bar,
+    lol = lol,
+  }
+end)()
@@ -0,0 +1,67 @@
+// The call and module graphs `--emit callgraph` and `--emit modgraph` write for a whole build.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+fn project() -> PathBuf {
+    let files = [
+        (
+            "main.wu",
+            "import shapes { area, Point }\nimport lib/util\n\
+             tick: fun() {\n  p := new Point { x: 1.0 }\n  g := p grow()\n  \
+             d := util double(2)\n}\na := area(1.0)\ntick()\n",
+        ),
+        (
+            "shapes.wu",
+            "pub Point: struct { x: float }\nimplement Point {\n  \
+             len: fun(self) -> float { self x }\n  \
+             grow: fun(self) -> float { self len() * 2.0 }\n}\n\
+             pub area: fun(r: float) -> float { sq(r) }\nsq: fun(r: float) -> float { r * r }\n",
+        ),
+        ("lib/util.wu", "pub double: fun(x: int) -> int { x * 2 }\n"),
+    ];
+
+    common::project("graph", &files)
+}
+
+// what building the whole project with `flags` writes to stdout
+fn emit(root: &Path, flags: &[&str]) -> String {
+    let output = common::wu(root, &[&["-q", "--stdout"], flags, &["."]].concat());
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn graphs() {
+    let root = project();
+
+    let calls = emit(&root, &["--emit", "callgraph"]);
+
+    for edge in [
+        "\"main\" -> \"main.tick\";",
+        "\"main\" -> \"shapes.area\";",
+        "\"main.tick\" -> \"lib.util.double\";",
+        "\"main.tick\" -> \"shapes.Point.grow\";",
+        "\"shapes.Point.grow\" -> \"shapes.Point.len\";",
+        "\"shapes.area\" -> \"shapes.sq\";",
+    ] {
+        assert!(calls.contains(edge), "no `{}` in\n{}", edge, calls);
+    }
+
+    assert!(calls.starts_with("digraph callgraph {"));
+    assert_eq!(calls.matches("->").count(), 6, "{}", calls);
+
+    let modules = emit(&root, &["--emit=modgraph", "--json"]);
+
+    assert!(modules.contains("[\"main\", \"lib.util\"]"), "{}", modules);
+    assert!(modules.contains("[\"main\", \"shapes\"]"), "{}", modules);
+
+    common::remove(&root);
+}
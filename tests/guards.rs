@@ -0,0 +1,57 @@
+// The guards that take the visitor's `inside` entries and scopes off again, however visiting
+// what they were entered for ends.
+
+use wu::wu::error::silently;
+use wu::wu::lexer::Lexer;
+use wu::wu::parser::Parser;
+use wu::wu::source::Source;
+use wu::wu::visitor::{Inside, Insides, SymTab, Visitor};
+
+// whether checking `content` fails, with the visitor left as it started either way
+fn left_balanced(content: &str) -> bool {
+    let source = Source::from("guards.wu", content.lines().map(String::from).collect());
+
+    let tokens = Lexer::default(content.chars().collect(), &source)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let ast = Parser::new(tokens, &source).parse().unwrap();
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, SymTab::prelude(), ".".to_string());
+    let failed = silently(|| visitor.visit()).is_err();
+
+    assert!(visitor.inside.is_empty(), "{:?}", visitor.inside);
+    assert!(visitor.symtab.at_root());
+
+    failed
+}
+
+#[test]
+fn failing_deep_inside() {
+    let in_loop = "f: fun() {\n  while true {\n    x: int = \"nope\"\n  }\n}\n";
+    let in_method =
+        "P: struct {}\n\nimplement P {\n  m: fun(self) {\n    for 3 {\n      y()\n    }\n  }\n}\n";
+    let in_call = "f: fun(...xs: int) {}\n\nf(1, 2, \"nope\")\n";
+
+    assert!(left_balanced(in_loop));
+    assert!(left_balanced(in_method));
+    assert!(left_balanced(in_call));
+
+    assert!(!left_balanced(
+        "f: fun(a: int) -> int {\n  a * 2\n}\n\nf(f(1))\n"
+    ));
+}
+
+#[test]
+fn dropping_takes_off_what_was_entered_since() {
+    let insides = Insides::default();
+
+    let looping = insides.enter(Inside::Loop);
+    let _function = insides.enter(Inside::Function);
+
+    assert!(insides.contains(&Inside::Function));
+
+    drop(looping);
+
+    assert!(insides.is_empty());
+}
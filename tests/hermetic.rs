@@ -0,0 +1,86 @@
+// `--hermetic`, builds that depend on nothing outside of the project and come out the same
+// wherever they're made.
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn project(name: &str) -> PathBuf {
+    common::project(
+        &format!("hermetic-{}", name),
+        &[("wu.toml", ""), ("sub/x.wu", "pub a: int = 1\n")],
+    )
+}
+
+#[test]
+fn same_output_wherever_built() {
+    let root = project("same");
+    fs::write(root.join("main.wu"), "import sub/x { a }\n\nb := a\n").unwrap();
+
+    let main = root.join("main.wu").display().to_string();
+
+    let inside = common::wu(&root, &["-q", "--hermetic", "--stdout", "main.wu"]);
+    let outside = common::command(
+        &common::outside(&root),
+        &root,
+        &["-q", "--hermetic", "--stdout", &main],
+    )
+    .output()
+    .unwrap();
+
+    assert!(inside.status.success());
+    assert_eq!(common::stdout(&inside), common::stdout(&outside));
+    assert!(common::stdout(&inside).contains("require('sub.x')"));
+
+    common::remove(&root);
+}
+
+#[test]
+fn no_wu_home() {
+    let root = project("home");
+    let home = common::home(&root);
+
+    fs::write(Path::new(&home).join("extra.wu"), "pub e: int = 2\n").unwrap();
+    fs::write(root.join("main.wu"), "import extra { e }\n\nb := e\n").unwrap();
+
+    assert!(common::wu(&root, &["-q", "--stdout", "main.wu"])
+        .status
+        .success());
+
+    let hermetic = common::wu(&root, &["-q", "--hermetic", "--stdout", "main.wu"]);
+    let shown = common::shown(&hermetic);
+
+    assert!(!hermetic.status.success());
+    assert!(
+        shown.contains("a hermetic build doesn't look in `$WU_HOME`"),
+        "{}",
+        shown
+    );
+
+    common::remove(&root);
+}
+
+#[test]
+fn outside_the_project() {
+    let root = project("outside");
+
+    // next to the project, one folder up from it
+    fs::write(
+        common::outside(&root).join("elsewhere.wu"),
+        "pub o: int = 3\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("main.wu"),
+        "import sub/../../elsewhere { o }\n\nb := o\n",
+    )
+    .unwrap();
+
+    let hermetic = common::wu(&root, &["-q", "--hermetic", "main.wu"]);
+
+    assert!(!hermetic.status.success());
+    assert!(common::stdout(&hermetic).contains("in a hermetic build, it's outside of the project"));
+
+    common::remove(&root);
+}
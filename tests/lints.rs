@@ -0,0 +1,77 @@
+// lints set to be allowed, warned about or denied by name, from wu.toml's `[lints]` and the
+// `--allow`, `--warn` and `--deny` flags going over it.
+
+mod common;
+
+use std::path::PathBuf;
+
+fn project(name: &str, lints: &str) -> PathBuf {
+    common::project(
+        &format!("lints-{}", name),
+        &[
+            ("wu.toml", &format!("[lints]\n{}", lints)),
+            ("main.wu", "import shapes { area }\n\nr := area(2.0)\n"),
+            (
+                "shapes.wu",
+                "pub area: fun(r: float) -> float { r * r }\n\
+                 pub perimeter: fun(r: float) -> float { r }\n",
+            ),
+        ],
+    )
+}
+
+#[test]
+fn denied_in_the_manifest() {
+    let root = project("denied", "unused_exports = \"deny\"\n");
+
+    let denied = common::wu(&root, &["build"]);
+    let shown = common::stdout(&denied);
+
+    assert_eq!(denied.status.code(), Some(1), "{}", shown);
+    assert!(
+        shown.contains("wrong: `perimeter` is `pub`, but no other module imports it"),
+        "{}",
+        shown
+    );
+
+    // the flag goes over the manifest
+    let warned = common::wu(&root, &["build", "--warn", "unused-exports"]);
+    let shown = common::stdout(&warned);
+
+    assert!(warned.status.success(), "{}", shown);
+    assert!(
+        shown.contains("weird: `perimeter` is `pub`, but no other module imports it"),
+        "{}",
+        shown
+    );
+
+    let allowed = common::wu(&root, &["build", "--allow=unused_exports"]);
+    let shown = common::stdout(&allowed);
+
+    assert!(allowed.status.success(), "{}", shown);
+    assert!(!shown.contains("perimeter"), "{}", shown);
+
+    common::remove(&root);
+}
+
+#[test]
+fn no_such_lint() {
+    let root = project("unknown", "unused_imports = \"deny\"\n");
+
+    let output = common::wu(&root, &["build", "--deny", "everything"]);
+    let shown = common::stdout(&output);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(shown.contains("no such lint `everything`"), "{}", shown);
+
+    let output = common::wu(&root, &["build"]);
+    let shown = common::stdout(&output);
+
+    assert!(
+        shown.contains("no such lint `unused_imports` in `[lints]`"),
+        "{}",
+        shown
+    );
+
+    common::remove(&root);
+}
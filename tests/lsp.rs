@@ -0,0 +1,539 @@
+// `wu lsp`, the language server, driven through a whole session over stdin and stdout.
+
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+
+use wu::wu::lsp::Json;
+
+const MAIN: &str = "\
+import shapes { area }
+
+Point: struct {
+  x: int
+  y: int
+}
+
+add: fun(a: int, b: str) -> int { a }
+
+p := new Point {
+  x: 1
+}
+n := add(1, \"a\")
+r := area(2.0)
+";
+
+fn framed(message: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", message.len(), message)
+}
+
+fn request(id: usize, method: &str, uri: &str, line: usize, character: usize) -> String {
+    framed(&format!(
+        "{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"{}\",\"params\":{{\"textDocument\":\
+         {{\"uri\":\"{}\"}},\"position\":{{\"line\":{},\"character\":{}}}}}}}",
+        id, method, uri, line, character
+    ))
+}
+
+// the messages the server sent, in order
+fn session(root: &Path, input: &str) -> (Vec<Json>, bool) {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .arg("lsp")
+        .current_dir(root)
+        .env("WU_HOME", env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    server
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let output = server.wait_with_output().unwrap();
+
+    let mut messages = Vec::new();
+    let mut rest = String::from_utf8(output.stdout).unwrap();
+
+    while let Some((header, after)) = rest.split_once("\r\n\r\n") {
+        let length = header["Content-Length: ".len()..].parse::<usize>().unwrap();
+
+        messages.push(Json::parse(&after[..length]).unwrap());
+        rest = after[length..].to_string();
+    }
+
+    (messages, output.status.success())
+}
+
+// the next message the server sends
+fn next(output: &mut impl BufRead) -> Json {
+    let mut length = 0;
+
+    loop {
+        let mut header = String::new();
+        output.read_line(&mut header).unwrap();
+
+        match header.trim_end().strip_prefix("Content-Length: ") {
+            Some(value) => length = value.parse().unwrap(),
+            None if header.trim_end().is_empty() => break,
+            None => (),
+        }
+    }
+
+    let mut content = vec![0; length];
+    output.read_exact(&mut content).unwrap();
+
+    Json::parse(&String::from_utf8(content).unwrap()).unwrap()
+}
+
+fn opened(uri: &str, text: &str) -> String {
+    let open = Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("method", Json::string("textDocument/didOpen")),
+        (
+            "params",
+            Json::object(vec![(
+                "textDocument",
+                Json::object(vec![
+                    ("uri", Json::string(uri)),
+                    ("text", Json::string(text)),
+                ]),
+            )]),
+        ),
+    ]);
+
+    framed(&open.to_string())
+}
+
+fn response(messages: &[Json], id: usize) -> &Json {
+    messages
+        .iter()
+        .find(|message| message.get("id") == Some(&Json::Number(id as f64)))
+        .and_then(|message| message.get("result"))
+        .unwrap()
+}
+
+#[test]
+fn session_over_stdio() {
+    let root = env::temp_dir().join(format!("wu-lsp-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("main.wu"), MAIN).unwrap();
+    fs::write(
+        root.join("shapes.wu"),
+        "pub area: fun(r: float) -> float { r * r }\n",
+    )
+    .unwrap();
+
+    let main = root.join("main.wu").display().to_string();
+    let uri = format!("file://{}", main);
+
+    let input = [
+        framed("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\",\"params\":{}}"),
+        opened(&uri, MAIN),
+        request(2, "textDocument/hover", &uri, 12, 0),
+        request(3, "textDocument/definition", &uri, 13, 6),
+        request(4, "textDocument/completion", &uri, 11, 0),
+        request(5, "textDocument/signatureHelp", &uri, 12, 12),
+        framed("{\"jsonrpc\":\"2.0\",\"id\":6,\"method\":\"shutdown\"}"),
+        framed("{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}"),
+    ]
+    .concat();
+
+    let (messages, exited) = session(&root, &input);
+
+    assert!(exited);
+
+    let capabilities = response(&messages, 1).get("capabilities").unwrap();
+    assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+
+    // the `new` leaving out `y`
+    let published = messages
+        .iter()
+        .find(|message| {
+            message.get("method").and_then(Json::as_str) == Some("textDocument/publishDiagnostics")
+        })
+        .unwrap();
+
+    let diagnostics = match published.at(&["params", "diagnostics"]) {
+        Some(Json::Array(ref diagnostics)) => diagnostics,
+        _ => panic!("no diagnostics in {}", published),
+    };
+
+    assert_eq!(diagnostics.len(), 1, "{}", published);
+    assert_eq!(
+        diagnostics[0].at(&["range", "start", "line"]),
+        Some(&Json::Number(9.0))
+    );
+    assert!(diagnostics[0]
+        .get("message")
+        .and_then(Json::as_str)
+        .unwrap()
+        .starts_with("missing assignment of struct member `y: int`"));
+
+    assert_eq!(
+        response(&messages, 2)
+            .at(&["contents", "value"])
+            .and_then(Json::as_str),
+        Some("int")
+    );
+
+    // into the imported module
+    let definition = response(&messages, 3);
+    assert!(definition
+        .get("uri")
+        .and_then(Json::as_str)
+        .unwrap()
+        .ends_with("/shapes.wu"));
+    assert_eq!(
+        definition.at(&["range", "start", "character"]),
+        Some(&Json::Number(4.0))
+    );
+
+    assert_eq!(
+        response(&messages, 4).to_string(),
+        "[{\"label\":\"y\",\"kind\":5,\"detail\":\"int\",\"insertText\":\"y: \"}]"
+    );
+
+    assert_eq!(
+        response(&messages, 5).to_string(),
+        "{\"signatures\":[{\"label\":\"fun(a: int, b: str) -> int\",\"parameters\":\
+         [{\"label\":\"a: int\"},{\"label\":\"b: str\"}],\"activeParameter\":1}]}"
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn project_wide_diagnostics() {
+    let root = env::temp_dir().join(format!("wu-lsp-project-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("wu.toml"), "").unwrap();
+    fs::write(
+        root.join("src/shapes.wu"),
+        "pub area: fun(r: float) -> float { r * r }\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/main.wu"),
+        "import shapes { area }\n\nr := area(2.0)\n",
+    )
+    .unwrap();
+
+    let mut server = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .arg("lsp")
+        .current_dir(&root)
+        .env("WU_HOME", env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut input = server.stdin.take().unwrap();
+    let mut output = BufReader::new(server.stdout.take().unwrap());
+
+    // `area` changed, but not saved yet
+    let shapes = format!("file://{}", root.join("src/shapes.wu").display());
+
+    input
+        .write_all(opened(&shapes, "pub area: fun(r: str) -> str { r }\n").as_bytes())
+        .unwrap();
+
+    // `main`, which isn't open, is checked against the edited `shapes`
+    let published = loop {
+        let message = next(&mut output);
+
+        let about_main = message
+            .at(&["params", "uri"])
+            .and_then(Json::as_str)
+            .is_some_and(|uri| uri.ends_with("/src/main.wu"));
+
+        if about_main {
+            break message;
+        }
+    };
+
+    let diagnostics = match published.at(&["params", "diagnostics"]) {
+        Some(Json::Array(ref diagnostics)) => diagnostics.clone(),
+        _ => panic!("no diagnostics in {}", published),
+    };
+
+    assert_eq!(diagnostics.len(), 1, "{}", published);
+    assert_eq!(
+        diagnostics[0].at(&["range", "start", "line"]),
+        Some(&Json::Number(2.0))
+    );
+
+    input
+        .write_all(
+            [
+                framed("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"shutdown\"}"),
+                framed("{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}"),
+            ]
+            .concat()
+            .as_bytes(),
+        )
+        .unwrap();
+
+    drop(input);
+
+    assert!(server.wait().unwrap().success());
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn utf16_columns() {
+    let root = env::temp_dir().join(format!("wu-lsp-utf16-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    let uri = format!("file://{}", root.join("main.wu").display());
+
+    let input = [
+        framed("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\",\"params\":{}}"),
+        opened(&uri, "print(\"😀\", nope)\n"),
+        framed("{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"shutdown\"}"),
+        framed("{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}"),
+    ]
+    .concat();
+
+    let (messages, _) = session(&root, &input);
+
+    let published = messages
+        .iter()
+        .find(|message| {
+            message.get("method").and_then(Json::as_str) == Some("textDocument/publishDiagnostics")
+        })
+        .unwrap();
+
+    // the emoji is one char, but two UTF-16 code units
+    let range =
+        published
+            .at(&["params", "diagnostics"])
+            .and_then(|diagnostics| match diagnostics {
+                Json::Array(ref diagnostics) => diagnostics.first()?.get("range"),
+                _ => None,
+            });
+
+    assert_eq!(
+        range.map(Json::to_string).as_deref(),
+        Some("{\"start\":{\"line\":0,\"character\":12},\"end\":{\"line\":0,\"character\":16}}"),
+        "{}",
+        published
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn range_formatting() {
+    let root = env::temp_dir().join(format!("wu-lsp-range-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    let uri = format!("file://{}", root.join("main.wu").display());
+
+    // the selection is in `b` only, `a` stays as it is
+    let format = format!(
+        "{{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"textDocument/rangeFormatting\",\
+         \"params\":{{\"textDocument\":{{\"uri\":\"{}\"}},\"range\":{{\"start\":\
+         {{\"line\":1,\"character\":0}},\"end\":{{\"line\":1,\"character\":3}}}},\
+         \"options\":{{\"tabSize\":2}}}}}}",
+        uri
+    );
+
+    let input = [
+        framed("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\",\"params\":{}}"),
+        opened(&uri, "a:=1+2\nb:=3*4\n"),
+        framed(&format),
+        framed("{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"shutdown\"}"),
+        framed("{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}"),
+    ]
+    .concat();
+
+    let (messages, _) = session(&root, &input);
+
+    let capabilities = response(&messages, 1).get("capabilities").unwrap();
+    assert_eq!(
+        capabilities.get("documentRangeFormattingProvider"),
+        Some(&Json::Bool(true))
+    );
+
+    assert_eq!(
+        response(&messages, 2).to_string(),
+        "[{\"range\":{\"start\":{\"line\":1,\"character\":0},\"end\":{\"line\":2,\
+         \"character\":0}},\"newText\":\"b := 3 * 4\\n\"}]"
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn rename() {
+    let root = env::temp_dir().join(format!("wu-lsp-rename-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("wu.toml"), "").unwrap();
+    fs::write(
+        root.join("shapes.wu"),
+        "pub area: fun(r: float) -> float { r * r }\n",
+    )
+    .unwrap();
+
+    let main = root.join("main.wu");
+    let text = "import shapes { area }\n\nr := area(2.0)\n";
+    fs::write(&main, text).unwrap();
+
+    let uri = format!("file://{}", main.display());
+
+    let rename = |id: usize, name: &str| {
+        framed(&format!(
+            "{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"textDocument/rename\",\"params\":\
+             {{\"textDocument\":{{\"uri\":\"{}\"}},\"position\":{{\"line\":2,\"character\":6}},\
+             \"newName\":\"{}\"}}}}",
+            id, uri, name
+        ))
+    };
+
+    let input = [
+        framed("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\",\"params\":{}}"),
+        opened(&uri, text),
+        rename(2, "surface"),
+        rename(3, "not a name"),
+        framed("{\"jsonrpc\":\"2.0\",\"id\":4,\"method\":\"shutdown\"}"),
+        framed("{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}"),
+    ]
+    .concat();
+
+    let (messages, _) = session(&root, &input);
+
+    let capabilities = response(&messages, 1).get("capabilities").unwrap();
+    assert_eq!(capabilities.get("renameProvider"), Some(&Json::Bool(true)));
+
+    // the definition, and the import and use of it
+    let canonical = fs::canonicalize(&root).unwrap();
+    let edit = |line: usize, start: usize, end: usize| {
+        format!(
+            "{{\"range\":{{\"start\":{{\"line\":{0},\"character\":{1}}},\"end\":\
+             {{\"line\":{0},\"character\":{2}}}}},\"newText\":\"surface\"}}",
+            line, start, end
+        )
+    };
+
+    assert_eq!(
+        response(&messages, 2).to_string(),
+        format!(
+            "{{\"changes\":{{\"file://{0}/main.wu\":[{1},{2}],\"file://{0}/shapes.wu\":[{3}]}}}}",
+            canonical.display(),
+            edit(0, 16, 20),
+            edit(2, 5, 9),
+            edit(0, 4, 8)
+        )
+    );
+
+    let refused = messages
+        .iter()
+        .find(|message| message.get("id") == Some(&Json::Number(3.0)))
+        .and_then(|message| message.at(&["error", "message"]))
+        .and_then(Json::as_str);
+
+    assert_eq!(refused, Some("`not a name` isn't a valid name"));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn code_actions() {
+    let root = env::temp_dir().join(format!("wu-lsp-actions-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("shapes.wu"),
+        "pub area: fun(r: float) -> float { r * r }\n",
+    )
+    .unwrap();
+
+    let uri = format!("file://{}", root.join("main.wu").display());
+
+    let actions = |id: usize, line: usize| {
+        framed(&format!(
+            "{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"textDocument/codeAction\",\
+             \"params\":{{\"textDocument\":{{\"uri\":\"{}\"}},\"range\":{{\"start\":\
+             {{\"line\":{2},\"character\":0}},\"end\":{{\"line\":{2},\"character\":0}}}},\
+             \"context\":{{\"diagnostics\":[]}}}}}}",
+            id, uri, line
+        ))
+    };
+
+    let input = [
+        framed("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\",\"params\":{}}"),
+        opened(&uri, "x := 1\nr := area(2.0)\n"),
+        actions(2, 1),
+        actions(3, 0),
+        framed("{\"jsonrpc\":\"2.0\",\"id\":4,\"method\":\"shutdown\"}"),
+        framed("{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}"),
+    ]
+    .concat();
+
+    let (messages, _) = session(&root, &input);
+
+    let capabilities = response(&messages, 1).get("capabilities").unwrap();
+    assert_eq!(
+        capabilities.get("codeActionProvider"),
+        Some(&Json::Bool(true))
+    );
+
+    assert_eq!(
+        response(&messages, 2).to_string(),
+        format!(
+            "[{{\"title\":\"import `area` from `shapes`: `import shapes {{ area }}`\",\
+             \"kind\":\"quickfix\",\"edit\":{{\"changes\":{{\"{}\":[{{\"range\":\
+             {{\"start\":{{\"line\":0,\"character\":0}},\"end\":{{\"line\":0,\
+             \"character\":0}}}},\"newText\":\"import shapes {{ area }}\\n\"}}]}}}}}}]",
+            uri
+        )
+    );
+
+    // nothing wrong on the first line
+    assert_eq!(response(&messages, 3).to_string(), "[]");
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn exit_without_shutdown() {
+    let root = env::temp_dir();
+
+    let (messages, exited) = session(&root, &framed("{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}"));
+
+    assert!(messages.is_empty());
+    assert!(!exited);
+}
+
+#[test]
+fn json() {
+    let text = "{\"a\":[1,2.5,-3e2,true,null],\"b\":\"\\u00e9\\ud83d\\ude00\\n\"}";
+    let json = Json::parse(text).unwrap();
+
+    assert_eq!(json.at(&["b"]).and_then(Json::as_str), Some("é😀\n"));
+    assert_eq!(
+        json.to_string(),
+        "{\"a\":[1,2.5,-300,true,null],\"b\":\"é😀\\n\"}"
+    );
+
+    assert_eq!(Json::parse("{\"a\":1,}"), None);
+    assert_eq!(Json::parse("[1] 2"), None);
+}
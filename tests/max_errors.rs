@@ -0,0 +1,68 @@
+// how `--max-errors` and `--fail-fast` cut a failing build short.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+fn project(name: &str) -> PathBuf {
+    common::project(
+        &format!("max-errors-{}", name),
+        &[
+            ("a.wu", "x: int = \"a\"\n"),
+            ("b.wu", "x: int = \"b\"\n"),
+            ("c.wu", "x: int = \"c\"\n"),
+            ("d.wu", "x: int = \"d\"\n"),
+        ],
+    )
+}
+
+// what building the whole project with `flags` shows, failing as it does
+fn build(root: &Path, flags: &[&str]) -> String {
+    let output = common::wu(root, &[&["-q"], flags, &["."]].concat());
+
+    assert!(!output.status.success());
+
+    common::shown(&output)
+}
+
+fn errors(output: &str) -> usize {
+    output.matches("wrong: mismatched types").count()
+}
+
+#[test]
+fn everything_by_default() {
+    let root = project("all");
+    let output = build(&root, &[]);
+
+    assert_eq!(errors(&output), 4);
+    assert!(!output.contains("stopped early"));
+
+    common::remove(&root);
+}
+
+#[test]
+fn max_errors() {
+    let root = project("max");
+    let output = build(&root, &["--max-errors", "3"]);
+
+    assert_eq!(errors(&output), 3);
+    assert!(output.contains("stopped early for `--max-errors 3`, 1 module left unchecked"));
+
+    let output = build(&root, &["--max-errors=1"]);
+
+    assert_eq!(errors(&output), 1);
+    assert!(output.contains("3 modules left unchecked"));
+
+    common::remove(&root);
+}
+
+#[test]
+fn fail_fast() {
+    let root = project("fast");
+    let output = build(&root, &["--fail-fast"]);
+
+    assert_eq!(errors(&output), 1);
+    assert!(output.contains("stopped early for `--fail-fast`, 3 modules left unchecked"));
+
+    common::remove(&root);
+}
@@ -0,0 +1,112 @@
+// Imported modules visited once and reused by later compilations, and with `--module-cache` by
+// later runs, until any file they were visited from changes.
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use wu::wu::error::captured;
+use wu::wu::lexer::Lexer;
+use wu::wu::parser::Parser;
+use wu::wu::source::Source;
+use wu::wu::visitor::{SymTab, Visitor};
+
+fn project(name: &str) -> PathBuf {
+    common::project(
+        &format!("module-cache-{}", name),
+        &[
+            ("a.wu", "import shared { twice }\nx := twice(2)\n"),
+            ("b.wu", "import shared { twice }\ny := twice(3)\n"),
+            (
+                "shared.wu",
+                "import deep { one }\npub twice: fun(n: int) -> int { n * 2 + one }\n",
+            ),
+            ("deep.wu", "pub one: int = 1\n"),
+        ],
+    )
+}
+
+// the diagnostics of checking `file`, in this process
+fn check(root: &Path, file: &str) -> Result<(), String> {
+    let path = root.join(file).display().to_string();
+    let content = fs::read_to_string(&path).unwrap();
+    let source = Source::from(&path, content.lines().map(|x| x.into()).collect());
+
+    let (checked, diagnostics) = captured(|| {
+        let mut tokens = Vec::new();
+
+        for token in Lexer::default(content.chars().collect(), &source) {
+            tokens.push(token?)
+        }
+
+        let ast = Parser::new(tokens, &source).parse()?;
+
+        Visitor::from_symtab(&ast, &source, SymTab::prelude(), root.display().to_string()).visit()
+    });
+
+    checked.map_err(|_| diagnostics)
+}
+
+#[test]
+fn reused_by_other_files() {
+    let root = project("reused");
+
+    let output = common::wu(&root, &["-v", "."]);
+    let log = common::stdout(&output);
+
+    assert!(output.status.success(), "{}", log);
+    assert_eq!(log.matches("Loading").count(), 2, "{}", log);
+    assert!(log.contains("shared.wu (unchanged)"), "{}", log);
+
+    common::remove(&root);
+}
+
+#[test]
+fn rechecked_when_an_import_changes() {
+    let root = project("changed");
+
+    assert_eq!(check(&root, "a.wu"), Ok(()));
+
+    // `shared` itself is the same, what it imports isn't
+    fs::write(root.join("deep.wu"), "pub one: str = \"1\"\n").unwrap();
+
+    let diagnostics = check(&root, "b.wu").unwrap_err();
+    assert!(diagnostics.contains("shared.wu"), "{}", diagnostics);
+
+    fs::write(root.join("deep.wu"), "pub one: int = 1\n").unwrap();
+
+    assert_eq!(check(&root, "b.wu"), Ok(()));
+
+    common::remove(&root);
+}
+
+#[test]
+fn kept_on_disk_between_runs() {
+    let root = project("disk");
+
+    let run = || {
+        let output = common::wu(&root, &["-v", "--module-cache", "cache", "a.wu"]);
+        let log = common::stdout(&output);
+
+        assert!(output.status.success(), "{}", log);
+
+        (log, fs::read_to_string(root.join("a.lua")).unwrap())
+    };
+
+    let (first, compiled) = run();
+    assert_eq!(first.matches("Loading").count(), 2, "{}", first);
+
+    // a new process, which only has the cache to go by
+    let (second, again) = run();
+    assert_eq!(second.matches("Loading").count(), 0, "{}", second);
+    assert!(second.contains("shared.wu (on disk)"), "{}", second);
+    assert_eq!(compiled, again);
+
+    fs::write(root.join("deep.wu"), "pub one: int = 2\n").unwrap();
+
+    let (third, _) = run();
+    assert_eq!(third.matches("Loading").count(), 2, "{}", third);
+
+    common::remove(&root);
+}
@@ -0,0 +1,143 @@
+// `compile_limited`, giving up on programs too large to compile rather than compiling them.
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+use wu::wu::playground::{compile_limited, Rejected};
+use wu::wu::visitor::{Limits, Resource};
+
+#[test]
+fn within_limits() {
+    let lua = compile_limited("main.wu", "x := 1 + 2\n", &Limits::default()).unwrap();
+
+    assert!(lua.contains("local x = 3"), "{}", lua);
+}
+
+#[test]
+fn diagnostics() {
+    match compile_limited("main.wu", "x: int = \"nope\"\n", &Limits::default()) {
+        Err(Rejected::Diagnostics(diagnostics)) => {
+            assert!(diagnostics.contains("mismatched"), "{}", diagnostics)
+        }
+
+        other => panic!("expected diagnostics, got {:?}", other),
+    }
+}
+
+#[test]
+fn too_many_nodes() {
+    let limits = Limits {
+        nodes: 10,
+        ..Limits::default()
+    };
+
+    // a statement, the `+` and its operands on each line
+    let content = "a := 1 + 2\nb := 3 + 4\nc := 5 + 6\n";
+
+    match compile_limited("main.wu", content, &limits) {
+        Err(Rejected::TooLarge(exceeded)) => {
+            assert_eq!(exceeded.resource, Resource::Nodes);
+            assert_eq!((exceeded.limit, exceeded.found), (10, 12));
+        }
+
+        other => panic!("expected too many nodes, got {:?}", other),
+    }
+}
+
+#[test]
+fn too_many_imports() {
+    let root = env::temp_dir().join(format!("wu-playground-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    fs::write(root.join("a.wu"), "import b\n").unwrap();
+    fs::write(root.join("b.wu"), "import c\n").unwrap();
+    fs::write(root.join("c.wu"), "pub c := 1\n").unwrap();
+
+    let limits = Limits {
+        imports: 2,
+        ..Limits::default()
+    };
+
+    let main = root.join("main.wu").display().to_string();
+    let rejected = compile_limited(&main, "import a\n", &limits).unwrap_err();
+
+    match rejected {
+        Rejected::TooLarge(exceeded) => {
+            assert_eq!(exceeded.resource, Resource::Imports);
+            assert_eq!((exceeded.limit, exceeded.found), (2, 3));
+        }
+
+        other => panic!("expected too many imports, got {:?}", other),
+    }
+
+    assert_eq!(
+        rejected.to_string(),
+        "program too large: 3 imports over the limit of 2"
+    );
+
+    assert!(compile_limited(&main, "import b\n", &limits).is_ok());
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn out_of_time() {
+    let limits = Limits {
+        time: Duration::ZERO,
+        ..Limits::default()
+    };
+
+    let content = (0..5000)
+        .map(|i| format!("x{} := {} * 2\n", i, i))
+        .collect::<String>();
+
+    match compile_limited("main.wu", &content, &limits) {
+        Err(Rejected::TooLarge(exceeded)) => assert_eq!(exceeded.resource, Resource::Time),
+        other => panic!("expected to run out of time, got {:?}", other),
+    }
+}
+
+#[test]
+fn too_many_bytes() {
+    let limits = Limits {
+        bytes: 16,
+        ..Limits::default()
+    };
+
+    match compile_limited("main.wu", "a := 1\nb := 2\nc := 3\n", &limits) {
+        Err(Rejected::TooLarge(exceeded)) => {
+            assert_eq!(exceeded.resource, Resource::Bytes);
+            assert_eq!((exceeded.limit, exceeded.found), (16, 21));
+        }
+
+        other => panic!("expected too many bytes, got {:?}", other),
+    }
+}
+
+// parentheses `depth` deep around a `1`
+fn nested(depth: usize) -> String {
+    format!("x := {}1{}\n", "(".repeat(depth), ")".repeat(depth))
+}
+
+#[test]
+fn nested_within_depth() {
+    // as deep as `--max-depth` allows by default, more than a test thread's stack would take
+    let lua = compile_limited("main.wu", &nested(250), &Limits::default()).unwrap();
+
+    assert!(lua.contains("local x = 1"), "{}", lua);
+}
+
+#[test]
+fn nested_too_deep() {
+    match compile_limited("main.wu", &nested(2000), &Limits::default()) {
+        Err(Rejected::Diagnostics(diagnostics)) => {
+            assert!(diagnostics.contains("too deeply nested"), "{}", diagnostics)
+        }
+
+        other => panic!("expected nesting too deep, got {:?}", other),
+    }
+}
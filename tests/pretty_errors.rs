@@ -0,0 +1,101 @@
+// `--pretty-errors`, programs printing an error nothing caught at the Wu line it came from. Runs
+// the output under `lua` (or $WU_LUA) where there is one.
+
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::process::{self, Command};
+
+#[test]
+fn error_at_wu_lines() {
+    let root = env::temp_dir().join(format!("wu-pretty-errors-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    fs::write(
+        root.join("main.wu"),
+        "import shapes { area }\n\n\
+         print: extern fun(...any) = \"print\"\n\n\
+         print(area(0.5))\n\n\
+         r := area(2.0)\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("shapes.wu"),
+        "error: extern fun(str) = \"error\"\n\n\
+         pub area: fun(r: float) -> float {\n  \
+           if r > 1.0 {\n    \
+             error(\"too big\")\n  \
+           }\n  \
+           r * r\n\
+         }\n",
+    )
+    .unwrap();
+
+    let build = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .args(["-q", "--color", "never", "--pretty-errors", "--wumap", "."])
+        .current_dir(&root)
+        .env("WU_HOME", env::temp_dir())
+        .output()
+        .unwrap();
+
+    assert!(
+        build.status.success(),
+        "{}",
+        String::from_utf8_lossy(&build.stdout)
+    );
+
+    let main = fs::read_to_string(root.join("main.lua")).unwrap();
+
+    assert!(main.contains("return __errors.run(function()"), "{}", main);
+    assert!(
+        main.contains("{ source = \"main.wu\", ahead = "),
+        "{}",
+        main
+    );
+
+    // the .wumap counts the helper's lines too
+    let map = fs::read_to_string(root.join("main.wumap")).unwrap();
+    let ahead = main
+        .lines()
+        .position(|line| line.starts_with("return "))
+        .unwrap();
+
+    assert!(
+        map.contains(&format!("\"lines\": [{}", "0,".repeat(ahead))),
+        "{}",
+        map
+    );
+
+    let lua = env::var("WU_LUA").unwrap_or_else(|_| "lua".to_string());
+    let run = Command::new(&lua)
+        .arg("main.lua")
+        .current_dir(&root)
+        .output();
+
+    match run {
+        Ok(run) => {
+            let shown = String::from_utf8_lossy(&run.stderr).to_string();
+
+            assert_eq!(String::from_utf8_lossy(&run.stdout), "0.25\n");
+            assert_eq!(run.status.code(), Some(1), "{}", shown);
+            assert!(
+                shown.contains(
+                    "wrong: too big\n     --> shapes.wu:5\n      │\n    \
+                     5 │     error(\"too big\")\n      │\nnote: called from main.wu:7\n"
+                ),
+                "{}",
+                shown
+            );
+        }
+
+        Err(why) if why.kind() == ErrorKind::NotFound => {
+            eprintln!("`{}` not found, only compiling", lua)
+        }
+
+        Err(why) => panic!("failed to run `{}`: {}", lua, why),
+    }
+
+    fs::remove_dir_all(root).unwrap();
+}
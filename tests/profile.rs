@@ -0,0 +1,110 @@
+// `wu profile`, carrying the stacks sampled while the program ran over to the Wu functions and
+// lines they were compiled from.
+
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+use wu::wu::profile::{map_samples, read_stats};
+
+const MAIN: &str = "\
+fib: fun(n: int) -> int {
+  if n < 2 {
+    return n
+  }
+
+  return fib(n - 1) + fib(n - 2)
+}
+
+Box: struct {
+  size: int
+}
+
+implement Box {
+  grow: fun(self) -> int {
+    self size + 1
+  }
+}
+
+print(fib(10), (new Box { size: 1 }) grow())
+";
+
+// the lua line, counting from 1, of the first line of `lua` containing `needle`
+fn line_of(lua: &str, needle: &str) -> usize {
+    lua.lines().position(|line| line.contains(needle)).unwrap() + 1
+}
+
+#[test]
+fn samples_by_function() {
+    let root = env::temp_dir().join(format!("wu-profile-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    let main = root.join("main.wu");
+    fs::write(&main, MAIN).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .args(["-q", "--color", "never", "--wumap"])
+        .arg(&main)
+        .env("WU_HOME", env::temp_dir())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let lua = root.join("main.lua").display().to_string();
+    let code = fs::read_to_string(&lua).unwrap();
+
+    let fib = line_of(&code, "function fib");
+    let recurse = line_of(&code, "return (fib(");
+    let grow = line_of(&code, "Box['grow']");
+    let top = line_of(&code, "print(");
+
+    // fib calling itself from the module twice, the method once
+    let stats = format!(
+        "2\t{lua}:{fib}:{recurse}\t{lua}:{fib}:{recurse}\t{lua}:1:{top}\n\
+         1\t{lua}:{grow}:{}\t{lua}:1:{top}\n\
+         1\t{lua}:1:{top}\n",
+        grow + 1,
+    );
+
+    let profile = map_samples(&read_stats(&stats));
+
+    assert_eq!(profile.samples, 4);
+
+    let found = profile
+        .functions
+        .iter()
+        .map(|function| {
+            (
+                function.name.as_str(),
+                function.line,
+                function.own,
+                function.total,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        found,
+        [
+            ("fib", 1, 2, 2),
+            ("(module)", 0, 1, 4),
+            ("Box:grow", 14, 1, 1)
+        ]
+    );
+
+    let table = profile.table();
+
+    assert!(table.contains("50.0%  "), "{}", table);
+    assert!(table.contains("main.wu:1 fib @hot"), "{}", table);
+    assert!(table.contains("main.wu:14 Box:grow @hot"), "{}", table);
+
+    let lines = &profile.lines[&main.display().to_string()];
+
+    assert_eq!(lines.get(&6), Some(&2));
+    assert_eq!(lines.get(&19), Some(&1));
+
+    fs::remove_dir_all(root).unwrap();
+}
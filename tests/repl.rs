@@ -0,0 +1,62 @@
+// the REPL's `:type`, `:ast` and `:lua`, on a session of what was typed in before.
+
+use wu::wu::error::silently;
+use wu::wu::repl::Session;
+
+const ADD: &str = "add: fun(a: int, b: int) -> int {\n  a + b\n}";
+
+// declares `code` without running it, the way a REPL without lua still would
+fn session(declared: &[&str]) -> Session {
+    let mut session = Session::new();
+
+    for code in declared.iter() {
+        assert!(silently(|| session.eval(code, "wu-no-such-lua")).is_err());
+    }
+
+    session
+}
+
+#[test]
+fn types() {
+    let session = session(&[ADD, "P: struct { x: int }"]);
+
+    assert_eq!(session.declared.len(), 2);
+
+    assert_eq!(session.type_of("add").unwrap(), "fun(int, int) -> int");
+    assert_eq!(session.type_of("add(1, 2) + 1").unwrap(), "int");
+    assert_eq!(session.type_of("[add(1, 2), 3]").unwrap(), "[int; 2]");
+    assert_eq!(
+        session.type_of("new P { x: 1 }").unwrap(),
+        "P {\n  x: int\n}"
+    );
+
+    assert!(silently(|| session.type_of("y := 1")).is_err());
+    assert!(silently(|| session.type_of("nope")).is_err());
+}
+
+#[test]
+fn expressions_are_not_kept() {
+    let session = session(&[ADD, "add(1, 2)"]);
+
+    assert_eq!(session.declared, vec![ADD.to_string()]);
+}
+
+#[test]
+fn syntax_trees() {
+    let ast = Session::new().ast("1 + x").unwrap();
+
+    assert!(ast.starts_with("Expression(\n"));
+    assert!(ast.contains("node: Binary("));
+    assert!(ast.contains("Identifier(\n"));
+    assert!(!ast.contains("Pos("));
+}
+
+#[test]
+fn lua() {
+    let session = session(&[ADD]);
+
+    let lua = session.lua("x := add(1, 2) * 2").unwrap();
+
+    assert_eq!(lua, "local x = (add(1, 2) * 2)");
+    assert!(!lua.contains("function add"));
+}
@@ -0,0 +1,65 @@
+// `wu signature`, the parameters of the call around a position and which one is being given.
+
+use std::env;
+use std::fs;
+use std::process::{self, Command, Output};
+
+const CALLS: &str = "\
+add: fun(a: int, b: str, ...rest: float) -> int { a }
+pair: fun(x: int, y: int) -> int { x }
+
+z := add(1, \"a\", pair(2, 3), 4.0, 5.0)
+";
+
+fn signature(name: &str, at: &str) -> Output {
+    let root = env::temp_dir().join(format!("wu-signature-{}-{}", name, process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("main.wu"), CALLS).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .args(["--color", "never", "signature", &format!("main.wu:{}", at)])
+        .current_dir(&root)
+        .env("WU_HOME", env::temp_dir())
+        .output()
+        .unwrap();
+
+    fs::remove_dir_all(root).unwrap();
+
+    output
+}
+
+#[test]
+fn active_parameter() {
+    let output = signature("active", "4:12");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "fun(int, str, ...float) -> int\n  a: int\n> b: str\n  rest: ...float\n"
+    );
+}
+
+#[test]
+fn innermost() {
+    let output = signature("inner", "4:26");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "fun(int, int) -> int\n  x: int\n> y: int\n"
+    );
+}
+
+#[test]
+fn splat_takes_the_rest() {
+    let output = signature("splat", "4:38");
+
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).ends_with("> rest: ...float\n"),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
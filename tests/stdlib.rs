@@ -0,0 +1,100 @@
+// the modules wu comes with, imported as `std/<name>` and read from the wu binary, with or without
+// `$WU_HOME`.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn vec_from_wu_itself() {
+    let root = common::project(
+        "stdlib",
+        &[(
+            "main.wu",
+            "import std/vec { Vec2, vec2 }\n\na := vec2(1, 2)\nb := a + vec2(3, 4) * 2\n",
+        )],
+    );
+    let home = common::home(&root);
+
+    let output = common::wu(&root, &["-q", "--out-dir", "out", "main.wu"]);
+
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    // read from wu itself, nothing is written into `$WU_HOME`
+    assert!(!Path::new(&home).join("std").exists());
+    assert!(root.join("out/std/vec.lua").exists());
+
+    // the arithmetic is written out member by member, not left to metamethods
+    let main = fs::read_to_string(root.join("out/main.lua")).unwrap();
+
+    assert!(main.contains("(__operand1['x'] * 2)"), "{}", main);
+    assert!(!main.contains("__add"));
+
+    common::remove(&root);
+}
+
+#[test]
+fn files_in_wu_home_go_first() {
+    let root = common::project(
+        "stdlib-local",
+        &[(
+            "main.wu",
+            "import std/vec { vec2 }\n\nn: int = vec2(1, 2)\n",
+        )],
+    );
+    let home = common::home(&root);
+
+    let local = "pub vec2: fun(x: int, y: int) -> int { x * 10 + y }\n";
+
+    fs::create_dir_all(Path::new(&home).join("std")).unwrap();
+    fs::write(Path::new(&home).join("std/vec.wu"), local).unwrap();
+
+    let output = common::wu(&root, &["-q", "--out-dir", "out", "main.wu"]);
+
+    assert!(output.status.success(), "{}", common::stdout(&output));
+
+    // left as it was, and compiled rather than what wu comes with
+    assert_eq!(
+        fs::read_to_string(Path::new(&home).join("std/vec.wu")).unwrap(),
+        local
+    );
+
+    let vec = fs::read_to_string(root.join("out/std/vec.lua")).unwrap();
+
+    assert!(vec.contains("* 10"), "{}", vec);
+
+    common::remove(&root);
+}
+
+#[test]
+fn hermetic_without_wu_home() {
+    let root = common::project(
+        "stdlib-hermetic",
+        &[
+            ("wu.toml", ""),
+            (
+                "main.wu",
+                "import std/vec { vec2 }
+
+v := vec2(1, 2)
+",
+            ),
+        ],
+    );
+
+    // with no `$WU_HOME`, nor a home folder to find one in
+    let output = common::command(
+        &root,
+        &root,
+        &["-q", "--hermetic", "--out-dir", "out", "main.wu"],
+    )
+    .env_remove("WU_HOME")
+    .env("HOME", common::outside(&root))
+    .output()
+    .unwrap();
+
+    assert!(output.status.success(), "{}", common::shown(&output));
+    assert!(root.join("out/std/vec.lua").exists());
+
+    common::remove(&root);
+}
@@ -0,0 +1,77 @@
+// The scope tree behind the checker's symbol table, and the snapshots tools take of it to look
+// names up after checking is done.
+
+use wu::wu::visitor::{SymTab, Type, TypeNode};
+
+fn int() -> Type {
+    Type::from(TypeNode::Int)
+}
+
+fn str() -> Type {
+    Type::from(TypeNode::Str)
+}
+
+#[test]
+fn inner_scopes_shadow_and_leave() {
+    let mut symtab = SymTab::new();
+
+    symtab.assign_str("x", int());
+    symtab.push();
+    symtab.assign_str("x", str());
+    symtab.assign_str("y", int());
+
+    assert!(symtab.fetch(&"x".into()).unwrap().node.strong_cmp(&TypeNode::Str));
+
+    symtab.pop();
+
+    assert!(symtab.at_root());
+    assert!(symtab.fetch(&"x".into()).unwrap().node.strong_cmp(&TypeNode::Int));
+    assert!(symtab.fetch(&"y".into()).is_none());
+}
+
+#[test]
+fn reentering_sees_the_scope_left_last() {
+    let mut symtab = SymTab::new();
+
+    symtab.push();
+    symtab.assign_str("y", int());
+    symtab.pop();
+
+    symtab.push();
+    symtab.reenter();
+
+    assert!(symtab.fetch(&"y".into()).is_some());
+
+    symtab.pop();
+    symtab.pop();
+
+    assert!(symtab.at_root())
+}
+
+#[test]
+fn snapshots_outlive_their_scope() {
+    let mut symtab = SymTab::new();
+
+    symtab.assign_str("x", int());
+    symtab.push();
+    symtab.assign_str("y", str());
+
+    let inside = symtab.snapshot();
+
+    symtab.pop();
+    symtab.push();
+    symtab.assign_str("z", int());
+    symtab.pop();
+
+    assert!(symtab.fetch(&"y".into()).is_none());
+    assert!(symtab.fetch_at(inside, &"y".into()).is_some());
+    assert!(symtab.fetch_at(inside, &"z".into()).is_none());
+
+    let names = symtab
+        .visible_at(inside)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+
+    assert_eq!(names, ["x", "y"])
+}
@@ -0,0 +1,75 @@
+// `wu triage`, minimizing a program that crashes the compiler to what crashes it.
+
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+use wu::wu::triage::{crash, triage, Phase};
+
+// calling what isn't a function panics while checking, at the time of writing
+const CRASH: &str = "\
+# a crash
+f: fun(a: int) -> int {
+  a + 1
+}
+
+x := 1
+z := f(2)
+y := x()
+w := 3
+";
+
+#[test]
+fn minimizes() {
+    let triaged = triage("crash.wu", CRASH).unwrap();
+
+    assert_eq!(triaged.crash.phase, Phase::Checking);
+    assert!(
+        triaged.crash.message.contains("called `x`"),
+        "{}",
+        triaged.crash.message
+    );
+    assert_eq!(triaged.reproducer, "x := 1\ny := x()\n");
+
+    assert_eq!(crash("crash.wu", &triaged.reproducer), Some(triaged.crash));
+}
+
+#[test]
+fn no_crash() {
+    assert_eq!(crash("fine.wu", "x := 1\n"), None);
+    assert_eq!(crash("wrong.wu", "x: int = \"nope\"\n"), None);
+    assert!(triage("fine.wu", "x := 1\n").is_none());
+}
+
+#[test]
+fn command() {
+    let root = env::temp_dir().join(format!("wu-triage-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("crash.wu"), CRASH).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wu"))
+        .args(["--color", "never", "triage", "crash.wu"])
+        .current_dir(&root)
+        .env("WU_HOME", env::temp_dir())
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Crashed while checking: BAM!"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Minimized 9 lines to 2 lines"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.ends_with("x := 1\ny := x()\n"), "{}", stdout);
+
+    fs::remove_dir_all(root).unwrap();
+}
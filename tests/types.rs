@@ -0,0 +1,195 @@
+// Properties of the type relations. `==` is what the checker asks when a value of the right
+// type is handed to a place of the left type, so it is deliberately lopsided: `int?` takes an
+// `int`, `[int]` takes an `[int; 3]`, never the other way around. `strong_cmp` is the exact check.
+
+use std::rc::Rc;
+
+use proptest::prelude::*;
+
+use wu::wu::visitor::{intern, Type, TypeMode, TypeNode};
+
+fn leaf() -> impl Strategy<Value = TypeNode> {
+    prop_oneof![
+        Just(TypeNode::Int),
+        Just(TypeNode::Float),
+        Just(TypeNode::Bool),
+        Just(TypeNode::Str),
+        Just(TypeNode::Char),
+        Just(TypeNode::Nil),
+        Just(TypeNode::Any),
+        Just(TypeNode::This),
+    ]
+}
+
+// `Undeclared` is left out, as it is a marker that never equals anything, not even itself. Counted
+// splats are lopsided too, `...int` of 2 fits where up to 3 are taken
+fn mode_of(lopsided: bool) -> impl Strategy<Value = TypeMode> {
+    let count = if lopsided {
+        prop::option::of(0..4usize).boxed()
+    } else {
+        Just(None).boxed()
+    };
+
+    prop_oneof![
+        4 => Just(TypeMode::Regular),
+        1 => Just(TypeMode::Immutable),
+        1 => count.prop_map(TypeMode::Splat),
+    ]
+}
+
+// with `lopsided`, also the optionals, arrays and counted splats that make `==` depend on which
+// side is which
+fn node_of(lopsided: bool) -> impl Strategy<Value = TypeNode> {
+    leaf().prop_recursive(4, 24, 3, move |inner| {
+        let kind = (inner.clone(), mode_of(lopsided))
+            .prop_map(|(node, mode)| Type::new(node, mode))
+            .boxed();
+
+        let tuple = prop::collection::vec(kind.clone(), 0..3).prop_map(TypeNode::Tuple);
+        let func = (prop::collection::vec(kind.clone(), 0..3), kind.clone(), any::<bool>())
+            .prop_map(|(params, retty, is_method)| {
+                TypeNode::Func(params, Rc::new(retty), None, is_method)
+            });
+
+        if !lopsided {
+            return prop_oneof![tuple, func].boxed();
+        }
+
+        prop_oneof![
+            inner.prop_map(|t| TypeNode::Optional(Rc::new(t))),
+            (kind, prop::option::of(0..4usize))
+                .prop_map(|(t, len)| TypeNode::Array(Rc::new(t), len)),
+            tuple,
+            func,
+        ]
+        .boxed()
+    })
+}
+
+fn node() -> impl Strategy<Value = TypeNode> {
+    node_of(true)
+}
+
+fn optional(t: &TypeNode) -> TypeNode {
+    TypeNode::Optional(Rc::new(t.clone()))
+}
+
+fn contains(t: &TypeNode, found: &dyn Fn(&TypeNode) -> bool) -> bool {
+    use TypeNode::*;
+
+    found(t)
+        || match t {
+            Optional(inner) => contains(inner, found),
+            Array(inner, _) => contains(&inner.node, found),
+            Tuple(content) => content.iter().any(|t| contains(&t.node, found)),
+            Func(params, retty, ..) => {
+                params.iter().any(|t| contains(&t.node, found)) || contains(&retty.node, found)
+            }
+            _ => false,
+        }
+}
+
+proptest! {
+    #[test]
+    fn eq_is_reflexive(a in node()) {
+        prop_assert!(a == a, "{} != {}", a, a)
+    }
+
+    #[test]
+    fn strong_cmp_is_reflexive(a in node()) {
+        prop_assert!(a.strong_cmp(&a), "{} is not strongly {}", a, a)
+    }
+
+    #[test]
+    fn strong_cmp_implies_eq(a in node(), b in node()) {
+        if a.strong_cmp(&b) {
+            prop_assert!(a == b, "{} is strongly {} but not equal", a, b)
+        }
+    }
+
+    #[test]
+    fn eq_is_symmetric_without_optionals_or_arrays(a in node_of(false), b in node_of(false)) {
+        prop_assert_eq!(a == b, b == a, "{} and {}", a, b)
+    }
+
+    #[test]
+    fn strong_cmp_is_symmetric_on_leaves(a in leaf(), b in leaf()) {
+        prop_assert_eq!(a.strong_cmp(&b), b.strong_cmp(&a), "{} and {}", a, b)
+    }
+
+    #[test]
+    fn strong_cmp_ignores_any(a in node()) {
+        prop_assume!(!matches!(a, TypeNode::Any));
+
+        prop_assert!(!a.strong_cmp(&TypeNode::Any));
+        prop_assert!(!TypeNode::Any.strong_cmp(&a))
+    }
+
+    #[test]
+    fn any_equals_everything(a in node()) {
+        prop_assert!(a == TypeNode::Any);
+        prop_assert!(TypeNode::Any == a);
+
+        prop_assert!(a == optional(&TypeNode::Any));
+        prop_assert!(optional(&TypeNode::Any) == a)
+    }
+
+    #[test]
+    fn optional_takes_nil_both_ways(a in node()) {
+        prop_assert!(optional(&a) == TypeNode::Nil);
+        prop_assert!(TypeNode::Nil == optional(&a))
+    }
+
+    #[test]
+    fn optional_takes_its_inner_type(a in node()) {
+        prop_assert!(optional(&a) == a, "{}? does not take {}", a, a);
+        prop_assert!(optional(&a) == optional(&a))
+    }
+
+    #[test]
+    fn inner_type_does_not_take_optional(a in node(), b in node()) {
+        prop_assume!(!contains(&a, &|t| matches!(t, TypeNode::Any)));
+        prop_assume!(!matches!(a, TypeNode::Nil | TypeNode::Optional(_)));
+        prop_assume!(!matches!(b, TypeNode::Any));
+
+        prop_assert!(a != optional(&b), "{} takes {}?", a, b)
+    }
+
+    #[test]
+    fn strong_cmp_keeps_optionals_apart(a in node()) {
+        prop_assert!(optional(&a).strong_cmp(&optional(&a)));
+        prop_assert!(!optional(&a).strong_cmp(&a) || matches!(a, TypeNode::Optional(_)))
+    }
+
+    #[test]
+    fn unsized_array_takes_sized(a in node(), len in 0..4usize) {
+        let unsized_array = TypeNode::Array(Rc::new(Type::from(a.clone())), None);
+        let sized_array = TypeNode::Array(Rc::new(Type::from(a.clone())), Some(len));
+
+        prop_assert!(unsized_array == sized_array);
+        prop_assert!(unsized_array.strong_cmp(&sized_array));
+
+        // only `[any; n]` takes an array of any length
+        if !matches!(a, TypeNode::Any) {
+            prop_assert!(sized_array != unsized_array, "[{}; {}] takes [{}]", a, len, a)
+        }
+    }
+
+    #[test]
+    fn interning_shares_identical_types(a in node(), mode in mode_of(true)) {
+        let t = Type::new(a, mode);
+
+        let first = intern(t.clone());
+        let second = intern(t.clone());
+
+        prop_assert!(Rc::ptr_eq(&first, &second), "{} was interned twice", t);
+        prop_assert!(first.node.strong_cmp(&t.node), "{} was interned as {}", t, first)
+    }
+
+    #[test]
+    fn interning_keeps_eq(a in node(), b in node()) {
+        let (a, b) = (Type::from(a), Type::from(b));
+
+        prop_assert_eq!(*intern(a.clone()) == *intern(b.clone()), a == b, "{} and {}", a, b)
+    }
+}
@@ -0,0 +1,53 @@
+// the `pub` definitions `wu build` warns no other module of a program imports.
+
+mod common;
+
+use wu::wu::references::warn_unused_exports;
+
+#[test]
+fn counts_exports_nothing_imports() {
+    let root = common::project(
+        "unused-members",
+        &[
+            (
+                "main.wu",
+                "import shapes { area }\nimport lib/util\n\
+                 pub tick: fun() {}\na := area(1.0)\nb := util double(2)\n",
+            ),
+            (
+                "shapes.wu",
+                "pub area: fun(r: float) -> float { r * r }\n\
+                 pub perimeter: fun(r: float) -> float { r }\nhidden := 1\n",
+            ),
+            (
+                "lib/util.wu",
+                "pub double: fun(x: int) -> int { x * 2 }\n\
+                 pub triple: fun(x: int) -> int { x * 3 }\n",
+            ),
+        ],
+    );
+
+    // `perimeter` and `triple`, but not `tick`, which is the entry point's
+    assert_eq!(warn_unused_exports(&root), Ok(2));
+
+    common::remove(&root);
+}
+
+#[test]
+fn wildcards_use_everything() {
+    let root = common::project(
+        "unused-wildcard",
+        &[
+            ("main.wu", "import shapes { * }\na := area(1.0)\n"),
+            (
+                "shapes.wu",
+                "pub area: fun(r: float) -> float { r * r }\n\
+                 pub perimeter: fun(r: float) -> float { r }\n",
+            ),
+        ],
+    );
+
+    assert_eq!(warn_unused_exports(&root), Ok(0));
+
+    common::remove(&root);
+}
@@ -0,0 +1,167 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use super::error::Response::{Note, Wrong};
+use super::lexer::Pos;
+use super::source::Source;
+
+// what compiling a program may take before it's given up on, e.g. by a playground compiling
+// whatever anyone sends it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub bytes: usize,   // source read, in the program and what it imports
+    pub imports: usize, // modules loaded, counting what imports import
+    pub nodes: usize,   // statements and expressions, in the program and what it imports
+    pub time: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            bytes: 1024 * 1024,
+            imports: 64,
+            nodes: 100_000,
+            time: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resource {
+    Bytes,
+    Imports,
+    Nodes,
+    Time, // in milliseconds
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Resource::Bytes => write!(f, "bytes"),
+            Resource::Imports => write!(f, "imports"),
+            Resource::Nodes => write!(f, "syntax nodes"),
+            Resource::Time => write!(f, "milliseconds"),
+        }
+    }
+}
+
+// the limit a program went over, and how far it got when that was noticed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exceeded {
+    pub resource: Resource,
+    pub limit: usize,
+    pub found: usize,
+}
+
+impl fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "program too large: {} {} over the limit of {}",
+            self.found, self.resource, self.limit
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Spent {
+    bytes: usize,
+    imports: usize,
+    nodes: usize,
+    exceeded: Option<Exceeded>,
+}
+
+// what a compilation has spent of its `Limits`, shared by the parsers and visitors of it and its
+// imports; the default has no limits
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    limits: Option<(Limits, Instant)>,
+    spent: Rc<RefCell<Spent>>,
+}
+
+impl Budget {
+    // `limits`, with the clock starting now
+    pub fn new(limits: Limits) -> Self {
+        Budget {
+            limits: Some((limits, Instant::now())),
+            spent: Rc::default(),
+        }
+    }
+
+    // the limit gone over, once one is
+    pub fn exceeded(&self) -> Option<Exceeded> {
+        self.spent.borrow().exceeded
+    }
+
+    // spends `amount` more of `resource`, or for `Time` checks the clock; fails once over a
+    // limit, saying which the first time
+    pub fn spend(&self, resource: Resource, amount: usize) -> Result<(), Option<Exceeded>> {
+        let (limits, start) = match self.limits {
+            Some(limits) => limits,
+            None => return Ok(()),
+        };
+
+        let mut spent = self.spent.borrow_mut();
+
+        if spent.exceeded.is_some() {
+            return Err(None);
+        }
+
+        let (found, limit) = match resource {
+            Resource::Bytes => {
+                spent.bytes += amount;
+                (spent.bytes, limits.bytes)
+            }
+
+            Resource::Imports => {
+                spent.imports += amount;
+                (spent.imports, limits.imports)
+            }
+
+            Resource::Nodes => {
+                spent.nodes += amount;
+                (spent.nodes, limits.nodes)
+            }
+
+            Resource::Time => (
+                start.elapsed().as_millis() as usize,
+                limits.time.as_millis() as usize,
+            ),
+        };
+
+        if found > limit {
+            let exceeded = Exceeded {
+                resource,
+                limit,
+                found,
+            };
+
+            spent.exceeded = Some(exceeded);
+
+            return Err(Some(exceeded));
+        }
+
+        Ok(())
+    }
+}
+
+// the diagnostic for a compilation that went over its budget at `pos`, given by the first
+// `Budget::spend` to fail
+pub fn too_large(source: &Source, pos: &Pos, exceeded: Option<Exceeded>) {
+    let exceeded = match exceeded {
+        Some(exceeded) => exceeded,
+        None => return,
+    };
+
+    response!(
+        Wrong("program too large"),
+        source.file,
+        pos,
+        Note(format!(
+            "{} {} over the limit of {}",
+            exceeded.found, exceeded.resource, exceeded.limit
+        ))
+    )
+}
@@ -0,0 +1,109 @@
+use std::ops::Range;
+
+use super::*;
+
+// new text for the tokens in `range`, indexes into `SyntaxTokens::tokens`; the trivia before the
+// first of them stays, the trivia between them goes with them. An empty range inserts before a
+// token, after its trivia
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl Patch {
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Patch {
+            range,
+            text: text.into(),
+        }
+    }
+
+    // just the token at `at`
+    pub fn replace(at: usize, text: impl Into<String>) -> Self {
+        Patch::new(at..at + 1, text)
+    }
+
+    pub fn insert(at: usize, text: impl Into<String>) -> Self {
+        Patch::new(at..at, text)
+    }
+}
+
+// a mechanical migration, e.g. from syntax the language moved away from: shown each token of a
+// file in turn, with the whole file around it, it patches what needs to change
+pub trait Codemod {
+    // what `wu codemod <name>` runs it by
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    fn visit(&mut self, tokens: &SyntaxTokens, at: usize) -> Vec<Patch>;
+}
+
+// the file `tokens` lexed from with everything `codemod` patches changed, and how many patches
+// that took; a patch overlapping an earlier one is left out
+pub fn rewrite(tokens: &SyntaxTokens, codemod: &mut dyn Codemod) -> (String, usize) {
+    let mut patches: Vec<Patch> = Vec::new();
+
+    for at in 0..tokens.tokens.len() {
+        for patch in codemod.visit(tokens, at) {
+            let overlaps = patches.iter().any(|other| {
+                patch.range.start < other.range.end.max(other.range.start + 1)
+                    && other.range.start < patch.range.end.max(patch.range.start + 1)
+            });
+
+            if !overlaps && patch.range.end <= tokens.tokens.len() {
+                patches.push(patch)
+            }
+        }
+    }
+
+    patches.sort_by_key(|patch| (patch.range.start, patch.range.end));
+
+    (apply(tokens, &patches), patches.len())
+}
+
+// the text of `tokens` with `patches` applied, which are in order and don't overlap
+pub fn apply(tokens: &SyntaxTokens, patches: &[Patch]) -> String {
+    let mut text = String::new();
+    let mut patches = patches.iter().peekable();
+    let mut at = 0;
+
+    while at <= tokens.tokens.len() {
+        let leading = match tokens.tokens.get(at) {
+            Some(token) => &token.leading,
+            None => &tokens.trailing,
+        };
+
+        for trivia in leading.iter() {
+            text.push_str(trivia.text())
+        }
+
+        match patches.next_if(|patch| patch.range.start == at) {
+            Some(patch) => {
+                text.push_str(&patch.text);
+
+                if patch.range.is_empty() {
+                    if let Some(token) = tokens.tokens.get(at) {
+                        text.push_str(&token.text)
+                    }
+
+                    at += 1
+                } else {
+                    // the trivia after the patched tokens is the next one's, written with it
+                    at = patch.range.end
+                }
+            }
+
+            None => {
+                if let Some(token) = tokens.tokens.get(at) {
+                    text.push_str(&token.text)
+                }
+
+                at += 1
+            }
+        }
+    }
+
+    text
+}
@@ -0,0 +1,5 @@
+pub mod codemod;
+
+use super::lexer::*;
+
+pub use self::codemod::*;
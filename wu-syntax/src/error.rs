@@ -0,0 +1,525 @@
+use colored::Colorize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::lexer::{Pos, Spans};
+use super::source::FilePath;
+
+pub enum Response<T: fmt::Display> {
+    Wrong(T),
+    Weird(T),
+    Note(T),
+}
+
+use self::Response::*;
+
+static ERRORS: AtomicUsize = AtomicUsize::new(0);
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+static VERBOSITY: AtomicUsize = AtomicUsize::new(Verbosity::Normal as usize);
+static STDOUT_TAKEN: AtomicBool = AtomicBool::new(false);
+static SILENCED: AtomicUsize = AtomicUsize::new(0);
+static MAX_ERRORS: AtomicUsize = AtomicUsize::new(0);
+static BASELINED: AtomicUsize = AtomicUsize::new(0);
+
+// the warnings a baseline file lets pass silently, as (file, message) with how often, and
+// whether to record every warning for writing a new one
+static BASELINE: Mutex<Option<HashMap<(String, String), usize>>> = Mutex::new(None);
+static RECORDED: Mutex<Option<Vec<(String, String)>>> = Mutex::new(None);
+
+thread_local! {
+    static CAPTURED: RefCell<Option<String>> = const { RefCell::new(None) };
+    static COLLECTED: RefCell<Option<Vec<Diagnostic>>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Wrong,
+    Weird,
+    Note,
+}
+
+// a reported diagnostic, both as it's shown and taken apart for tools such as `wu lsp`
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub notes: Vec<String>,
+    pub file: Option<String>, // the first file it names, later ones go with the notes
+    pub pos: Option<Pos>,     // the first span it points at
+    pub shown: String,
+}
+
+// what the parts following the first one of a `response!` say about the diagnostic
+pub trait Part {
+    fn describe(&self, diagnostic: &mut Diagnostic);
+}
+
+impl<P: Part + ?Sized> Part for &P {
+    fn describe(&self, diagnostic: &mut Diagnostic) {
+        (**self).describe(diagnostic)
+    }
+}
+
+impl Part for FilePath {
+    fn describe(&self, diagnostic: &mut Diagnostic) {
+        diagnostic.file.get_or_insert_with(|| self.0.clone());
+    }
+}
+
+impl Part for Pos {
+    fn describe(&self, diagnostic: &mut Diagnostic) {
+        diagnostic.pos.get_or_insert_with(|| self.clone());
+    }
+}
+
+impl Part for Spans<'_> {
+    fn describe(&self, diagnostic: &mut Diagnostic) {
+        self.primary().describe(diagnostic)
+    }
+}
+
+// parts put together beforehand only show
+impl Part for String {
+    fn describe(&self, _: &mut Diagnostic) {}
+}
+
+impl<T: fmt::Display> Part for Response<T> {
+    fn describe(&self, diagnostic: &mut Diagnostic) {
+        if let Note(ref note) = *self {
+            diagnostic.notes.push(note.to_string())
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! response {
+  ( $first:expr $( , $r:expr )* ) => {{
+    let first = $first;
+
+    #[allow(unused_mut)]
+    let mut rest = String::new();
+    #[allow(unused_mut)]
+    let mut diagnostic = first.diagnostic();
+
+    if !$crate::error::silenced() {
+        $(
+            let part = &$r;
+
+            rest.push_str(&format!("{}", part));
+            $crate::error::Part::describe(part, &mut diagnostic);
+        )*
+    }
+
+    let baselined = first.baselined(&rest);
+    let capped = $crate::error::capped();
+
+    if !baselined {
+        first.tally();
+    }
+
+    if !$crate::error::silenced() && !capped && !baselined {
+        diagnostic.shown = format!("{}{}", first, rest);
+
+        $crate::error::report(diagnostic)
+    }
+  }};
+}
+
+// progress and tracing, shown depending on verbosity, e.g.
+// `log!(Verbose, "Resolved", "`{}` to `{}`", path, file)`
+#[macro_export]
+macro_rules! log {
+  ( $level:ident, $label:expr, $( $arg:tt )+ ) => {{
+    use $crate::error::Verbosity;
+
+    if Verbosity::$level.enabled() {
+        $crate::error::emit_log(Verbosity::$level, $label, &format!($( $arg )+))
+    }
+  }};
+}
+
+// every diagnostic ends up here
+pub fn emit(message: &str) {
+    if silenced() {
+        return;
+    }
+
+    let captured = CAPTURED.with(|captured| match *captured.borrow_mut() {
+        Some(ref mut captured) => {
+            captured.push_str(message);
+            captured.push('\n');
+
+            true
+        }
+
+        None => false,
+    });
+
+    if captured {
+        return;
+    }
+
+    if STDOUT_TAKEN.load(Ordering::Relaxed) {
+        let _ = writeln!(io::stderr().lock(), "{}", message);
+    } else {
+        let _ = writeln!(io::stdout().lock(), "{}", message);
+    }
+}
+
+// shows `diagnostic`, or keeps it for whoever is collecting them. A note about nothing in
+// particular goes with the diagnostic before it
+pub fn report(diagnostic: Diagnostic) {
+    let diagnostic = COLLECTED.with(|collected| match *collected.borrow_mut() {
+        Some(ref mut collected) => {
+            let about_nothing = diagnostic.severity == Severity::Note
+                && diagnostic.file.is_none()
+                && diagnostic.pos.is_none();
+
+            match collected.last_mut() {
+                Some(last) if about_nothing => {
+                    last.notes.push(diagnostic.message);
+                    last.shown.push('\n');
+                    last.shown.push_str(&diagnostic.shown)
+                }
+
+                _ => collected.push(diagnostic),
+            }
+
+            None
+        }
+
+        None => Some(diagnostic),
+    });
+
+    if let Some(diagnostic) = diagnostic {
+        emit(&diagnostic.shown)
+    }
+}
+
+// runs `f`, keeping the diagnostics it reports rather than showing them
+pub fn collected<T>(f: impl FnOnce() -> T) -> (T, Vec<Diagnostic>) {
+    let outer = COLLECTED.with(|collected| collected.replace(Some(Vec::new())));
+    let mut restore = Restore(outer);
+    let result = f();
+    let inner = COLLECTED.with(|collected| collected.replace(restore.0.take()));
+
+    (result, inner.unwrap_or_default())
+}
+
+// puts back what was collected before, also when `f` panics, so the panic itself is shown
+struct Restore(Option<Vec<Diagnostic>>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            let outer = self.0.take();
+            let _ = COLLECTED.try_with(|collected| collected.replace(outer));
+        }
+    }
+}
+
+// moves diagnostics over to stderr, when stdout carries the compiled lua
+pub fn take_stdout() {
+    STDOUT_TAKEN.store(true, Ordering::Relaxed)
+}
+
+// runs `f` without reporting or counting its diagnostics, e.g. when peeking at other files
+pub fn silently<T>(f: impl FnOnce() -> T) -> T {
+    SILENCED.fetch_add(1, Ordering::Relaxed);
+    let result = f();
+    SILENCED.fetch_sub(1, Ordering::Relaxed);
+
+    result
+}
+
+// runs `f`, collecting its diagnostics instead of printing them, e.g. for a host embedding wu
+pub fn captured<T>(f: impl FnOnce() -> T) -> (T, String) {
+    let outer = CAPTURED.with(|captured| captured.replace(Some(String::new())));
+    let result = f();
+    let inner = CAPTURED.with(|captured| captured.replace(outer));
+
+    (result, inner.unwrap_or_default())
+}
+
+pub fn silenced() -> bool {
+    SILENCED.load(Ordering::Relaxed) > 0
+}
+
+pub fn emit_log(level: Verbosity, label: &str, message: &str) {
+    let label = format!("{:>10}", label);
+
+    let label = match level {
+        Verbosity::Normal => label.green().bold(),
+        _ => label.blue().bold(),
+    };
+
+    emit(&format!("{} {}", label, message))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,   // only diagnostics
+    Normal,  // progress
+    Verbose, // module resolution and caching, `-v`
+    Trace,   // compiler phases, `-vv`
+}
+
+impl Verbosity {
+    pub fn apply(self) {
+        VERBOSITY.store(self as usize, Ordering::Relaxed)
+    }
+
+    pub fn enabled(self) -> bool {
+        VERBOSITY.load(Ordering::Relaxed) >= self as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    Always,
+    Auto,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from(choice: &str) -> Option<Self> {
+        match choice {
+            "always" => Some(ColorChoice::Always),
+            "auto" => Some(ColorChoice::Auto),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    // `auto` colors only terminals, and respects NO_COLOR (https://no-color.org)
+    pub fn apply(self) {
+        let enabled = match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let is_terminal = if STDOUT_TAKEN.load(Ordering::Relaxed) {
+                    io::stderr().is_terminal()
+                } else {
+                    io::stdout().is_terminal()
+                };
+
+                env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && is_terminal
+            }
+        };
+
+        colored::control::set_override(enabled)
+    }
+}
+
+impl<T: fmt::Display> Response<T> {
+    // the diagnostic this starts, for `response!` to fill in
+    pub fn diagnostic(&self) -> Diagnostic {
+        let (severity, message) = match *self {
+            Wrong(ref m) => (Severity::Wrong, m),
+            Weird(ref m) => (Severity::Weird, m),
+            Note(ref m) => (Severity::Note, m),
+        };
+
+        Diagnostic {
+            severity,
+            message: message.to_string(),
+            notes: Vec::new(),
+            file: None,
+            pos: None,
+            shown: String::new(),
+        }
+    }
+
+    pub fn tally(&self) {
+        if silenced() {
+            return;
+        }
+
+        match *self {
+            Wrong(_) => ERRORS.fetch_add(1, Ordering::Relaxed),
+            Weird(_) => WARNINGS.fetch_add(1, Ordering::Relaxed),
+            Note(_) => 0,
+        };
+    }
+}
+
+// (errors, warnings) reported so far
+pub fn tallies() -> (usize, usize) {
+    (
+        ERRORS.load(Ordering::Relaxed),
+        WARNINGS.load(Ordering::Relaxed),
+    )
+}
+
+// counts errors and warnings from none again, e.g. for every build of `wu daemon`
+pub fn reset_tallies() {
+    ERRORS.store(0, Ordering::Relaxed);
+    WARNINGS.store(0, Ordering::Relaxed);
+    BASELINED.store(0, Ordering::Relaxed)
+}
+
+// how many errors are shown before anything else is left out, see `--max-errors`
+pub fn set_max_errors(max: usize) {
+    MAX_ERRORS.store(max, Ordering::Relaxed)
+}
+
+// whether as many errors as `--max-errors` allows were reported already
+pub fn capped() -> bool {
+    let max = MAX_ERRORS.load(Ordering::Relaxed);
+
+    max > 0 && ERRORS.load(Ordering::Relaxed) >= max
+}
+
+// leaves out the warnings in `known` from here on, each as often as it's counted there
+pub fn set_baseline(known: HashMap<(String, String), usize>) {
+    *BASELINE.lock().unwrap() = Some(known)
+}
+
+// starts keeping every warning reported, for `recorded`
+pub fn record_warnings() {
+    *RECORDED.lock().unwrap() = Some(Vec::new())
+}
+
+// the (file, message) of every warning since `record_warnings`
+pub fn recorded() -> Vec<(String, String)> {
+    RECORDED.lock().unwrap().clone().unwrap_or_default()
+}
+
+// how many warnings the baseline left out so far
+pub fn baselined() -> usize {
+    BASELINED.load(Ordering::Relaxed)
+}
+
+// `text` without its colors
+fn plain(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(c)
+        }
+    }
+
+    plain
+}
+
+impl<T: fmt::Display> Response<T> {
+    // whether this is a warning the baseline knows, recording it when asked to; `rest` is what
+    // follows it, where the `-->` line tells the file
+    pub fn baselined(&self, rest: &str) -> bool {
+        let message = match *self {
+            Weird(ref message) if !silenced() => message.to_string(),
+            _ => return false,
+        };
+
+        let mut recorded = RECORDED.lock().unwrap();
+        let mut baseline = BASELINE.lock().unwrap();
+
+        if recorded.is_none() && baseline.is_none() {
+            return false;
+        }
+
+        let file = plain(rest)
+            .lines()
+            .find_map(|line| line.trim_start().strip_prefix("--> "))
+            .map_or(String::new(), |file| file.trim_start_matches("./").to_string());
+
+        let key = (file, message);
+
+        if let Some(ref mut recorded) = *recorded {
+            recorded.push(key.clone())
+        }
+
+        match baseline.as_mut().and_then(|baseline| baseline.get_mut(&key)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                BASELINED.fetch_add(1, Ordering::Relaxed);
+
+                true
+            }
+
+            _ => false,
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Response<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (color, message_type, message) = match *self {
+            Wrong(ref m) => ("red", "wrong", m),
+            Weird(ref m) => ("yellow", "weird", m),
+            Note(ref m) => ("cyan", "note", m),
+        };
+
+        let indent = message_type.len() + 2;
+
+        let message_type = format!("\n{}", message_type).color(color).bold();
+        let message = wrap(&format!("{}", message), width(), indent);
+
+        let message = format!("{}: {}", message_type, message);
+
+        write!(f, "{}", message)
+    }
+}
+
+// how wide diagnostics get, from $COLUMNS where that is set
+pub fn width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns| columns >= 40)
+        .unwrap_or(100)
+}
+
+// breaks the lines of `text` that don't fit in `width` at spaces, continuing them `indent` in;
+// the first line starts out `indent` in, after the `wrong: `
+pub fn wrap(text: &str, width: usize, indent: usize) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            result.push('\n')
+        }
+
+        let mut column = if i == 0 { indent } else { 0 };
+
+        if column + line.chars().count() <= width {
+            result.push_str(line);
+            continue;
+        }
+
+        let words = line.trim_start();
+        let leading = &line[..line.len() - words.len()];
+
+        result.push_str(leading);
+        column += leading.chars().count();
+
+        for (j, word) in words.split(' ').enumerate() {
+            let length = word.chars().count();
+
+            if j > 0 {
+                if column + 1 + length > width {
+                    result.push('\n');
+                    result.push_str(&" ".repeat(indent));
+                    column = indent
+                } else {
+                    result.push(' ');
+                    column += 1
+                }
+            }
+
+            result.push_str(word);
+            column += length
+        }
+    }
+
+    result
+}
@@ -0,0 +1,1586 @@
+use std::collections::HashMap;
+use std::iter;
+
+use super::super::error::silently;
+use super::super::error::Response::{Note, Wrong};
+use super::*;
+
+// how deep every block is indented
+const INDENT: &str = "  ";
+
+// how long a line may get by keeping what the source has on one line there, e.g. `{ r * r }`
+const WIDTH: usize = 100;
+
+// a comment of the file, written before the code after it or after the code on its line
+struct Comment {
+    text: String,
+    line: usize,
+    trailing: bool, // whether code comes before it on its line
+}
+
+// where the items between brackets are in the source: the line of each, and of the brackets
+struct Layout {
+    lines: Vec<usize>,
+    open: usize,
+    close: usize,
+}
+
+// writes a parsed file back out as canonical source, see `format_file`
+struct Formatter<'f> {
+    source: &'f Source,
+    tokens: &'f SyntaxTokens,
+
+    starts: HashMap<(usize, usize), usize>, // the token at each line and column
+    depths: Vec<usize>,                     // how many brackets are open around each token
+
+    comments: Vec<Comment>,
+    written: usize, // how many of them are out
+
+    splats: Vec<bool>, // whether each `...` taking `any` is written bare, rather than as `...any`
+    splatted: usize,   // how many of them are out
+
+    out: String,
+    indent: usize,
+
+    // every operation in parentheses, nothing taken from the tokens and no comments: two trees
+    // written like this are the same tree
+    shape: bool,
+}
+
+impl<'f> Formatter<'f> {
+    fn new(source: &'f Source, tokens: &'f SyntaxTokens) -> Self {
+        let mut starts = HashMap::new();
+        let mut depths = Vec::new();
+        let mut comments = Vec::new();
+        let mut splats = Vec::new();
+
+        let mut depth = 0;
+        let mut previous = None;
+
+        for (i, token) in tokens.tokens.iter().enumerate() {
+            comments_of(&token.leading, previous, &mut comments);
+
+            if is_symbol(token, &[")", "]", "}"]) {
+                depth = usize::saturating_sub(depth, 1)
+            }
+
+            depths.push(depth);
+
+            if is_symbol(token, &["(", "[", "{"]) {
+                depth += 1
+            }
+
+            starts
+                .entry((token.token.line.0, token.token.slice.0))
+                .or_insert(i);
+
+            if is_symbol(token, &["..."]) {
+                if let Some(bare) = splat_of_any(&tokens.tokens[i + 1..]) {
+                    splats.push(bare)
+                }
+            }
+
+            previous = Some(&token.token)
+        }
+
+        comments_of(&tokens.trailing, previous, &mut comments);
+
+        Formatter {
+            source,
+            tokens,
+
+            starts,
+            depths,
+
+            comments,
+            written: 0,
+
+            splats,
+            splatted: 0,
+
+            out: String::new(),
+            indent: 0,
+
+            shape: false,
+        }
+    }
+
+    fn shaped(source: &'f Source, tokens: &'f SyntaxTokens) -> Self {
+        let mut formatter = Formatter::new(source, tokens);
+
+        formatter.shape = true;
+        formatter.comments.clear();
+
+        formatter
+    }
+
+    fn format(mut self, ast: &[Statement]) -> String {
+        self.statements(ast, usize::MAX);
+
+        if !self.out.is_empty() {
+            self.out.push('\n')
+        }
+
+        self.out
+    }
+
+    fn write(&mut self, text: &str) {
+        self.out.push_str(text)
+    }
+
+    // starts a line at the current indentation
+    fn line(&mut self) {
+        if !self.out.is_empty() {
+            self.out.push('\n')
+        }
+
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT)
+        }
+    }
+
+    // whether the source has a blank line right above `line`, which stays, one at most
+    fn blank_before(&self, line: usize) -> bool {
+        !self.shape
+            && line >= 2
+            && self
+                .source
+                .lines
+                .get(line - 2)
+                .is_some_and(|text| text.trim().is_empty())
+    }
+
+    // writes with `write` if that keeps to one line that fits and takes no comment along, or
+    // leaves everything as it was
+    fn inline(&mut self, write: impl FnOnce(&mut Self)) -> bool {
+        let (length, written) = (self.out.len(), self.written);
+
+        write(self);
+
+        let start = self.out.rfind('\n').map_or(0, |newline| newline + 1);
+
+        if self.written == written
+            && !self.out[length..].contains('\n')
+            && self.out[start..].chars().count() <= WIDTH
+        {
+            return true;
+        }
+
+        self.out.truncate(length);
+        self.written = written;
+
+        false
+    }
+
+    // the comments before `line` on lines of their own, e.g. those above the statement on it
+    fn comments_before(&mut self, line: usize, first: &mut bool) {
+        while let Some(comment) = self.comments.get(self.written) {
+            if comment.line >= line {
+                break;
+            }
+
+            let (text, line) = (comment.text.clone(), comment.line);
+
+            if !*first && self.blank_before(line) {
+                self.out.push('\n')
+            }
+
+            self.line();
+            self.write(&text);
+
+            self.written += 1;
+            *first = false
+        }
+    }
+
+    // the comment after the code on its line, if that's before `line`
+    fn trailing(&mut self, line: usize) {
+        if let Some(comment) = self.comments.get(self.written) {
+            if comment.trailing && comment.line < line {
+                let text = format!(" {}", comment.text);
+
+                self.write(&text);
+                self.written += 1
+            }
+        }
+    }
+
+    // the token an expression or statement at `pos` starts with
+    fn token_at(&self, pos: &Pos) -> Option<usize> {
+        if self.shape {
+            return None;
+        }
+
+        self.starts.get(&((pos.0).0, (pos.1).0)).cloned()
+    }
+
+    // the line of the bracket closing the one the token at `pos` is in
+    fn end_line(&self, pos: &Pos) -> Option<usize> {
+        let start = self.token_at(pos)?;
+        let depth = self.depths[start];
+
+        (start..self.tokens.tokens.len())
+            .find(|&i| self.depths[i] < depth)
+            .map(|i| self.tokens.tokens[i].token.line.0)
+    }
+
+    // the layout of items starting at `positions`, e.g. the arms of a match
+    fn layout(&self, positions: &[&Pos]) -> Option<Layout> {
+        let first = self.token_at(positions.first()?)?;
+
+        let open = self.tokens.tokens[..first]
+            .iter()
+            .rev()
+            .find(|token| token.token.token_type != TokenType::EOL)?;
+
+        Some(Layout {
+            lines: positions.iter().map(|pos| (pos.0).0).collect(),
+            open: open.token.line.0,
+            close: self.end_line(positions[0])?,
+        })
+    }
+
+    // the layout of the items between the braces after `pos`, which have no positions of their
+    // own, e.g. the fields of a struct
+    fn scanned(&self, pos: &Pos) -> Option<Layout> {
+        let tokens = &self.tokens.tokens;
+
+        let start = self.token_at(pos)?;
+        let open = (start..tokens.len()).find(|&i| is_symbol(&tokens[i], &["{"]))?;
+        let depth = self.depths[open] + 1;
+
+        let mut lines = Vec::new();
+        let mut starts_item = true;
+
+        for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+            if self.depths[i] < depth {
+                return Some(Layout {
+                    lines,
+                    open: tokens[open].token.line.0,
+                    close: token.token.line.0,
+                });
+            }
+
+            if self.depths[i] > depth {
+                continue;
+            }
+
+            if token.token.token_type == TokenType::EOL || is_symbol(token, &[","]) {
+                starts_item = true
+            } else if starts_item {
+                lines.push(token.token.line.0);
+                starts_item = false
+            }
+        }
+
+        None
+    }
+
+    fn statements(&mut self, statements: &[Statement], end: usize) {
+        // a file ending in a comment ends in an `EOF`, which is nothing to write
+        let statements = statements
+            .iter()
+            .filter(|statement| match statement.node {
+                StatementNode::Expression(ref expression) => expression.node != ExpressionNode::EOF,
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+
+        // bindings on consecutive lines line up what follows their names
+        let labels = statements
+            .iter()
+            .map(|statement| self.label(statement))
+            .collect::<Vec<_>>();
+
+        let mut widths = vec![None; statements.len()];
+        let mut start = 0;
+
+        while start < statements.len() {
+            let mut end = start + 1;
+
+            while end < statements.len()
+                && labels[start].is_some()
+                && labels[end].is_some()
+                && (statements[end].pos.0).0 == (statements[end - 1].pos.0).0 + 1
+            {
+                end += 1
+            }
+
+            if end - start > 1 {
+                let width = labels[start..end]
+                    .iter()
+                    .flatten()
+                    .map(|label| label.chars().count())
+                    .max();
+
+                for width_of in widths[start..end].iter_mut() {
+                    *width_of = width
+                }
+            }
+
+            start = end
+        }
+
+        let mut first = true;
+
+        for (i, statement) in statements.iter().enumerate() {
+            let line = (statement.pos.0).0;
+
+            self.comments_before(line, &mut first);
+
+            if !first && self.blank_before(line) {
+                self.out.push('\n')
+            }
+
+            self.line();
+
+            let start = self.out.len();
+
+            self.statement(statement);
+
+            if let (Some(width), Some(label)) = (widths[i], &labels[i]) {
+                let padding = " ".repeat(width - label.chars().count());
+
+                self.out.insert_str(start + label.len(), &padding)
+            }
+
+            self.trailing(statements.get(i + 1).map_or(end, |next| (next.pos.0).0));
+
+            first = false
+        }
+
+        self.comments_before(end, &mut first)
+    }
+
+    // the `name:` a binding starts with where a type follows it, which lines up with those of
+    // the bindings on the lines around it: `x: int` or `print: extern fun(str)`
+    fn label(&self, statement: &Statement) -> Option<String> {
+        match statement.node {
+            StatementNode::Variable(ref kind, ref name, ref right, public) if !self.shape => {
+                let typed = match *right {
+                    Some(ref right) => {
+                        !is_nil(kind) || matches!(right.node, ExpressionNode::Extern(..))
+                    }
+
+                    None => true,
+                };
+
+                let public = if public { "pub " } else { "" };
+
+                Some(format!("{}{}:", public, name)).filter(|_| typed)
+            }
+
+            _ => None,
+        }
+    }
+
+    // `{ ... }`, on one line if it's a statement the source has on the line the block starts on
+    fn block(&mut self, pos: &Pos, statements: &[Statement]) {
+        let (first, last) = match (statements.first(), statements.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return self.write("{}"),
+        };
+
+        // `return` with nothing after it reads on to the `}` on its line
+        let inline = statements.len() == 1
+            && !self.shape
+            && (first.pos.0).0 == (pos.0).0
+            && first.node != StatementNode::Return(None);
+
+        if inline
+            && self.inline(|f| {
+                f.write("{ ");
+                f.statement(first);
+                f.write(" }")
+            })
+        {
+            return;
+        }
+
+        let end = self.end_line(&last.pos).unwrap_or(0);
+
+        self.write("{");
+        self.trailing((first.pos.0).0);
+
+        self.indent += 1;
+        self.statements(statements, end);
+        self.indent -= 1;
+
+        self.line();
+        self.write("}")
+    }
+
+    // items on lines of their own between `brackets`, each followed by `separator`
+    fn lines<T>(
+        &mut self,
+        brackets: (&str, &str),
+        layout: Option<&Layout>,
+        items: &[T],
+        separator: &str,
+        write: impl Fn(&mut Self, &T, bool),
+    ) {
+        let layout = layout.filter(|layout| layout.lines.len() == items.len());
+
+        self.write(brackets.0);
+
+        if let Some(layout) = layout {
+            self.trailing(layout.lines[0])
+        }
+
+        self.indent += 1;
+
+        let mut first = true;
+
+        for (i, item) in items.iter().enumerate() {
+            if let Some(layout) = layout {
+                let line = layout.lines[i];
+
+                self.comments_before(line, &mut first);
+
+                if !first && self.blank_before(line) {
+                    self.out.push('\n')
+                }
+            }
+
+            self.line();
+
+            write(self, item, true);
+            self.write(separator);
+
+            if let Some(layout) = layout {
+                self.trailing(layout.lines.get(i + 1).cloned().unwrap_or(layout.close))
+            }
+
+            first = false
+        }
+
+        if let Some(layout) = layout {
+            self.comments_before(layout.close, &mut first)
+        }
+
+        self.indent -= 1;
+
+        self.line();
+        self.write(brackets.1)
+    }
+
+    // the items of a struct, enum and the like in braces, each on a line of its own; or on one
+    // line where the source has them so, they fit and `inline` allows it
+    fn items<T>(
+        &mut self,
+        layout: Option<Layout>,
+        items: &[T],
+        inline: bool,
+        write: impl Fn(&mut Self, &T, bool),
+    ) {
+        if items.is_empty() {
+            return self.write("{}");
+        }
+
+        let on_one_line = layout
+            .as_ref()
+            .is_some_and(|layout| layout.open == layout.close);
+
+        if inline
+            && on_one_line
+            && self.inline(|f| {
+                f.write("{ ");
+
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write(", ")
+                    }
+
+                    write(f, item, false)
+                }
+
+                f.write(" }")
+            })
+        {
+            return;
+        }
+
+        self.lines(("{", "}"), layout.as_ref(), items, "", write)
+    }
+
+    // the elements of an array, tuple or call; each on a line of its own where the source has
+    // them on other lines than the bracket opening them
+    fn list(&mut self, brackets: (&str, &str), content: &[Expression]) {
+        let layout = self.layout(&content.iter().map(|e| &e.pos).collect::<Vec<_>>());
+
+        if let Some(ref layout) = layout {
+            if layout.lines.iter().any(|&line| line != layout.open) {
+                let close = brackets.1.trim_start_matches(',');
+
+                return self.lines(
+                    (brackets.0, close),
+                    Some(layout),
+                    content,
+                    ",",
+                    |f, e, _| f.expression(e),
+                );
+            }
+        }
+
+        self.write(brackets.0);
+
+        for (i, element) in content.iter().enumerate() {
+            if i > 0 {
+                self.write(", ")
+            }
+
+            self.expression(element)
+        }
+
+        self.write(brackets.1)
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        use self::StatementNode::*;
+
+        match statement.node {
+            Expression(ref expression) => self.expression(expression),
+
+            Variable(ref kind, ref name, ref right, public) => {
+                if let Some(ExpressionNode::Enum(.., true)) = right.as_ref().map(|it| &it.node) {
+                    self.write("@flags ")
+                }
+
+                if public {
+                    self.write("pub ")
+                }
+
+                self.write(name);
+                self.binding(kind, right.as_ref())
+            }
+
+            SplatVariable(ref kind, ref names, ref right, public) => {
+                if public {
+                    self.write("pub ")
+                }
+
+                self.write(&names.join(", "));
+                self.binding(kind, right.as_ref())
+            }
+
+            Assignment(ref left, ref right) => {
+                self.expression(left);
+
+                match right.node {
+                    ExpressionNode::Binary(_, ref operator, ref value) if self.compound(right) => {
+                        self.write(&format!(" {}= ", operator));
+                        self.expression(value)
+                    }
+
+                    _ => {
+                        self.write(" = ");
+                        self.expression(right)
+                    }
+                }
+            }
+
+            SplatAssignment(ref lefts, ref right) => {
+                for (i, left) in lefts.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ")
+                    }
+
+                    self.expression(left)
+                }
+
+                self.write(" = ");
+                self.expression(right)
+            }
+
+            Return(ref value) => {
+                self.write("return");
+
+                if let Some(ref value) = *value {
+                    if value.node != ExpressionNode::EOF {
+                        self.write(" ");
+                        self.expression(value)
+                    }
+                }
+            }
+
+            Implement(ref name, ref body, ref parent) => {
+                self.write("implement ");
+                self.expression(name);
+
+                if let Some(ref parent) = *parent {
+                    self.write(": ");
+                    self.expression(parent)
+                }
+
+                self.write(" ");
+                self.expression(body)
+            }
+
+            Import(ref path, ref names, ref cfg, public) => {
+                if public {
+                    self.write("pub ")
+                }
+
+                self.write("import ");
+                self.write(path);
+
+                if !names.is_empty() {
+                    let names = names
+                        .iter()
+                        .map(|(name, alias)| match *alias {
+                            Some(ref alias) => format!("{} as {}", name, alias),
+                            None => name.clone(),
+                        })
+                        .collect::<Vec<_>>();
+
+                    self.write(&format!(" {{ {} }}", names.join(", ")))
+                }
+
+                if let Some(ref cfg) = *cfg {
+                    self.write(&format!(" if cfg({})", cfg))
+                }
+            }
+
+            ExternBlock(ref statement) => {
+                self.write("extern ");
+                self.statement(statement)
+            }
+
+            Global(ref binding) => {
+                self.write("@global ");
+                self.statement(binding)
+            }
+
+            Skip => self.write("skip"),
+            Break => self.write("break"),
+        }
+    }
+
+    // what follows the names of a binding: `: T`, `: T = value`, `:= value` or `: <declaration>`
+    fn binding(&mut self, kind: &Type, right: Option<&Expression>) {
+        let inferred = is_nil(kind);
+
+        match right {
+            None => {
+                self.write(": ");
+                self.kind(kind)
+            }
+
+            Some(right) if inferred && declares(right) => {
+                self.write(": ");
+                self.expression(right)
+            }
+
+            Some(right) if inferred => {
+                self.write(" := ");
+                self.expression(right)
+            }
+
+            Some(right) => {
+                self.write(": ");
+                self.kind(kind);
+                self.write(" = ");
+                self.expression(right)
+            }
+        }
+    }
+
+    // whether `value`, assigned, was written `a += b`, which reads as `a = a + b`
+    fn compound(&self, value: &Expression) -> bool {
+        match value.node {
+            ExpressionNode::Binary(_, ref operator, _)
+                if Operator::is_compoundable(operator.as_str()) =>
+            {
+                self.token_at(&value.pos)
+                    .is_some_and(|i| is_symbol(&self.tokens.tokens[i], &["="]))
+            }
+
+            _ => false,
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        self.expression_in(expression, true)
+    }
+
+    // `open` where nothing follows the expression, so it may read on to the end, as `-x` does
+    fn expression_in(&mut self, expression: &Expression, open: bool) {
+        use self::ExpressionNode::*;
+
+        match expression.node {
+            Int(n) => self.literal(expression, TokenType::Int, n.to_string()),
+            Float(n) => self.literal(expression, TokenType::Float, format!("{:?}", n)),
+            Str(ref text) => self.literal(expression, TokenType::Str, quoted(text, '"')),
+            Char(c) => self.literal(expression, TokenType::Char, quoted(&c.to_string(), '\'')),
+            Bool(b) => self.write(if b { "true" } else { "false" }),
+            Identifier(ref name) => self.write(name),
+            Empty => self.write("nil"),
+            EOF => (),
+
+            UnwrapSplat(ref inner) => self.prefix("*", inner),
+            Neg(ref inner) => self.prefix("-", inner),
+            Not(ref inner) => self.prefix("not ", inner),
+
+            Unwrap(ref inner) => {
+                self.target(inner);
+                self.write("!")
+            }
+
+            Tuple(ref content) if content.len() == 1 => self.list(("(", ",)"), content),
+            Tuple(ref content) => self.list(("(", ")"), content),
+            Array(ref content) => self.list(("[", "]"), content),
+
+            Splat(ref content) => {
+                for (i, element) in content.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ")
+                    }
+
+                    // the first would take in the rest, e.g. `-a, b` is `-(a, b)`
+                    if i == 0 && !self.postfixed(element) {
+                        self.parenthesized(element)
+                    } else {
+                        self.expression(element)
+                    }
+                }
+            }
+
+            Binary(ref left, ref operator, ref right) => {
+                if let Some(text) = self.interpolation(expression) {
+                    return self.write(&text);
+                }
+
+                self.operand(left, operator, false, false);
+                self.write(&format!(" {} ", operator));
+                self.operand(right, operator, true, open)
+            }
+
+            Call(ref called, ref args) => {
+                self.target(called);
+                self.list(("(", ")"), args)
+            }
+
+            Index(ref indexed, ref index, true) => {
+                self.target(indexed);
+                self.write("[");
+                self.expression(index);
+                self.write("]")
+            }
+
+            Index(ref indexed, ref index, false) => {
+                self.target(indexed);
+                self.write(" ");
+                self.expression(index)
+            }
+
+            Cast(ref inner, ref kind) => {
+                if let Some(text) = self.interpolation(expression) {
+                    return self.write(&text);
+                }
+
+                if self.postfixed(inner) {
+                    self.expression(inner)
+                } else {
+                    self.parenthesized(inner)
+                }
+
+                self.write(" as ");
+                self.kind(kind)
+            }
+
+            Block(ref statements) => match switch(statements) {
+                Some((subject, arms)) => {
+                    self.write("switch ");
+                    self.expression(subject);
+                    self.write(" ");
+
+                    let layout =
+                        self.layout(&arms.iter().map(|arm| &arm.0.pos).collect::<Vec<_>>());
+
+                    self.items(layout, &arms, false, |f, &(value, body), _| {
+                        f.expression(value);
+                        f.write(" => ");
+                        f.expression(body)
+                    })
+                }
+
+                None => self.block(&expression.pos, statements),
+            },
+
+            Function(ref params, ref returns, ref body, method) => {
+                let untyped = self.untyped(&expression.pos);
+
+                self.write("fun(");
+
+                if method {
+                    self.write("self")
+                }
+
+                for (i, (name, kind)) in params.iter().enumerate() {
+                    if i > 0 || method {
+                        self.write(", ")
+                    }
+
+                    match untyped.get(name) {
+                        Some(written) if matches!(kind.node, TypeNode::Any) => self.write(written),
+
+                        _ if matches!(kind.mode, TypeMode::Splat(_)) => {
+                            self.write(&format!("...{}: ", name));
+                            self.kind_node(&kind.node)
+                        }
+
+                        _ => {
+                            self.write(&format!("{}: ", name));
+                            self.kind(kind)
+                        }
+                    }
+                }
+
+                self.write(")");
+
+                if !is_nil(returns) {
+                    self.write(" -> ");
+                    self.kind(returns)
+                }
+
+                self.write(" ");
+                self.expression(body)
+            }
+
+            If(ref condition, ref body, ref elses) => {
+                self.write("if ");
+                self.expression(condition);
+                self.write(" ");
+                self.expression(body);
+
+                for (condition, body, _) in elses.iter().flatten() {
+                    match *condition {
+                        Some(ref condition) => {
+                            self.write(" elif ");
+                            self.expression(condition);
+                            self.write(" ")
+                        }
+
+                        None => self.write(" else "),
+                    }
+
+                    self.expression(body)
+                }
+            }
+
+            For((ref binding, ref iterator), ref body) => {
+                self.write("for ");
+                self.expression(binding);
+
+                if let Some(ref iterator) = *iterator {
+                    self.write(" in ");
+                    self.expression(iterator)
+                }
+
+                self.write(" ");
+                self.expression(body)
+            }
+
+            While(ref condition, ref body) => {
+                self.write("while ");
+                self.expression(condition);
+                self.write(" ");
+                self.expression(body)
+            }
+
+            Module(ref body, ref exports) => {
+                self.write("module ");
+
+                if let Some(ref exports) = *exports {
+                    self.write(&format!("export {{ {} }} ", exports.join(", ")))
+                }
+
+                self.expression(body)
+            }
+
+            Extern(ref kind, ref lua) => {
+                self.write("extern ");
+                self.kind(kind);
+
+                if let Some(ref lua) = *lua {
+                    let written = self.token_at(&expression.pos).and_then(|start| {
+                        self.tokens.tokens[start..]
+                            .iter()
+                            .find(|token| token.token.token_type == TokenType::Str)
+                            .map(|token| token.text.clone())
+                    });
+
+                    self.write(" = ");
+                    self.write(&written.unwrap_or_else(|| quoted(lua, '"')))
+                }
+            }
+
+            ExternExpression(ref inner) => {
+                self.write("extern ");
+                self.expression(inner)
+            }
+
+            Struct(_, ref fields, _) | Trait(_, ref fields, _) => {
+                let (keyword, defaults) = match expression.node {
+                    Trait(_, _, ref defaults) => ("trait ", defaults.as_slice()),
+                    _ => ("struct ", &[][..]),
+                };
+
+                self.write(keyword);
+
+                // the types line up, unless default methods come between them
+                let width = fields
+                    .iter()
+                    .map(|field| field.0.chars().count() + 1)
+                    .max()
+                    .filter(|_| defaults.is_empty());
+                let layout = self.scanned(&expression.pos);
+
+                self.items(layout, fields, true, |f, (name, kind), aligned| {
+                    let label = format!("{}:", name);
+
+                    match width {
+                        Some(width) if aligned => f.write(&format!("{:width$} ", label)),
+                        _ => f.write(&format!("{} ", label)),
+                    }
+
+                    // a default method is written whole
+                    match defaults.iter().find(|default| &default.0 == name) {
+                        Some(default) => f.expression(&default.1),
+                        None => f.kind(kind),
+                    }
+                })
+            }
+
+            Enum(_, ref variants, ..) => {
+                self.write("enum ");
+
+                let layout = self.scanned(&expression.pos);
+
+                self.items(layout, variants, true, |f, (name, carried), _| {
+                    f.write(name);
+
+                    if !carried.is_empty() {
+                        f.kinds(carried)
+                    }
+                })
+            }
+
+            Match(ref subject, ref arms) => {
+                self.write("match ");
+                self.expression(subject);
+                self.write(" ");
+
+                let layout = self.layout(&arms.iter().map(|arm| &arm.3).collect::<Vec<_>>());
+
+                self.items(layout, arms, false, |f, (variant, names, body, _), _| {
+                    f.write(variant.as_deref().unwrap_or("_"));
+
+                    if !names.is_empty() {
+                        f.write(&format!("({})", names.join(", ")))
+                    }
+
+                    f.write(" => ");
+                    f.expression(body)
+                })
+            }
+
+            Initialization(ref name, ref fields) => {
+                self.write("new ");
+                self.expression(name);
+                self.write(" ");
+
+                let layout =
+                    self.layout(&fields.iter().map(|field| &field.1.pos).collect::<Vec<_>>());
+
+                // the values line up
+                let width = fields.iter().map(|field| field.0.chars().count() + 1).max();
+
+                self.items(layout, fields, true, |f, (name, value), aligned| {
+                    let name = format!("{}:", name);
+
+                    match width {
+                        Some(width) if aligned => f.write(&format!("{:width$} ", name)),
+                        _ => f.write(&format!("{} ", name)),
+                    }
+
+                    // the values have the positions of their names, the value comes after the `:`
+                    match f
+                        .token_at(&value.pos)
+                        .and_then(|i| f.tokens.tokens.get(i + 2))
+                    {
+                        Some(token) => f.expression(&Expression::new(
+                            value.node.clone(),
+                            Pos(token.token.line.clone(), token.token.slice),
+                        )),
+
+                        None => f.expression(value),
+                    }
+                })
+            }
+        }
+    }
+
+    // a literal as it's written, e.g. `r"\d+"`, or as `written` otherwise
+    fn literal(&mut self, expression: &Expression, kind: TokenType, written: String) {
+        let token = self
+            .token_at(&expression.pos)
+            .map(|i| &self.tokens.tokens[i])
+            .filter(|token| token.token.token_type == kind)
+            .map(|token| token.text.clone());
+
+        self.write(&token.unwrap_or(written))
+    }
+
+    // an interpolated string as it's written, which reads as its parts joined with `++`
+    fn interpolation(&self, expression: &Expression) -> Option<String> {
+        let tokens = &self.tokens.tokens;
+
+        let start = match expression.node {
+            ExpressionNode::Binary(_, Operator::Concat, _) => self.token_at(&expression.pos)?,
+
+            // `"{x}"`, with no text around the value, is only the value as a `str`
+            ExpressionNode::Cast(_, ref kind) if matches!(kind.node, TypeNode::Str) => {
+                self.token_at(&expression.pos)?.checked_sub(1)?
+            }
+
+            _ => return None,
+        };
+
+        if tokens[start].token.token_type != TokenType::Interpolation
+            || tokens[start].text.starts_with('}')
+        {
+            return None;
+        }
+
+        // the parts after values start with the `}` ending them, a string ends with such a `Str`
+        let mut open = 0;
+
+        let end = (start..tokens.len()).find(|&i| {
+            let resumed = tokens[i].text.starts_with('}');
+
+            match tokens[i].token.token_type {
+                TokenType::Interpolation if !resumed => open += 1,
+                TokenType::Str if resumed => open -= 1,
+                _ => (),
+            }
+
+            open == 0
+        })?;
+
+        let whole = match expression.node {
+            ExpressionNode::Cast(..) => tokens[start].text == "\"{" && tokens[end].text == "}\"",
+            _ => tokens[end].token.slice.1 == (expression.pos.1).1,
+        };
+
+        if !whole {
+            return None;
+        }
+
+        let mut text = tokens[start].text.clone();
+
+        for token in tokens[start + 1..=end].iter() {
+            for trivia in token.leading.iter() {
+                text.push_str(trivia.text())
+            }
+
+            text.push_str(&token.text)
+        }
+
+        Some(text)
+    }
+
+    // the parameters of the function at `pos` written without a type, by name, as they're
+    // written, e.g. `...rest`
+    fn untyped(&self, pos: &Pos) -> HashMap<String, String> {
+        let tokens = &self.tokens.tokens;
+        let mut untyped = HashMap::new();
+
+        let open = match self.token_at(pos) {
+            Some(at)
+                if tokens
+                    .get(at + 1)
+                    .is_some_and(|token| is_symbol(token, &["("])) =>
+            {
+                at + 1
+            }
+            _ => return untyped,
+        };
+
+        let depth = self.depths[open] + 1;
+        let mut param: Vec<&SyntaxToken> = Vec::new();
+
+        for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+            let closed = self.depths[i] < depth;
+
+            if closed || (self.depths[i] == depth && is_symbol(token, &[","])) {
+                if !param.iter().any(|token| is_symbol(token, &[":"])) {
+                    let name = param
+                        .iter()
+                        .find(|token| token.token.token_type == TokenType::Identifier);
+
+                    if let Some(name) = name {
+                        let written = param.iter().map(|token| token.text.as_str()).collect();
+
+                        untyped.insert(name.text.clone(), written);
+                    }
+                }
+
+                if closed {
+                    break;
+                }
+
+                param.clear()
+            } else if token.token.token_type != TokenType::EOL {
+                param.push(token)
+            }
+        }
+
+        untyped
+    }
+
+    // `-x`, `not x` or `*x`, which take in everything after them: `-a + b` is `-(a + b)`
+    fn prefix(&mut self, operator: &str, inner: &Expression) {
+        self.write(operator);
+
+        match inner.node {
+            ExpressionNode::Binary(..) if self.interpolation(inner).is_none() => {
+                self.parenthesized(inner)
+            }
+
+            _ => self.expression(inner),
+        }
+    }
+
+    // what's called, indexed or unwrapped
+    fn target(&mut self, target: &Expression) {
+        if self.atomic(target) {
+            self.expression(target)
+        } else {
+            self.parenthesized(target)
+        }
+    }
+
+    // a side of `operator`, in parentheses where it would read differently without them
+    fn operand(&mut self, operand: &Expression, operator: &Operator, right: bool, open: bool) {
+        use self::ExpressionNode::*;
+
+        let parenthesized = match operand.node {
+            _ if self.atomic(operand) => false,
+            _ if self.shape => true,
+
+            Binary(_, ref inner, _) => {
+                let (outer, inner) = (precedence(operator), precedence(inner));
+
+                inner < outer || (inner == outer && right != operator.is_right_ass())
+            }
+
+            // the rest of it, `a + 1, b` is `a + (1, b)`
+            Splat(_) => false,
+
+            Neg(_) | Not(_) | UnwrapSplat(_) => !(right && open),
+            Cast(_, ref kind) => reads_on(kind) && !(right && open),
+
+            _ => true,
+        };
+
+        if parenthesized {
+            self.parenthesized(operand)
+        } else {
+            self.expression_in(operand, right && open)
+        }
+    }
+
+    fn parenthesized(&mut self, expression: &Expression) {
+        self.write("(");
+        self.expression(expression);
+        self.write(")")
+    }
+
+    // whether `expression` ends where it does wherever it is, so it may be called, indexed or
+    // unwrapped as it is
+    fn atomic(&self, expression: &Expression) -> bool {
+        use self::ExpressionNode::*;
+
+        match expression.node {
+            Int(_) | Float(_) | Str(_) | Char(_) | Bool(_) | Identifier(_) | Empty => true,
+            Tuple(_) | Array(_) | Call(..) | Index(..) | Unwrap(_) => true,
+            Binary(..) | Cast(..) => self.interpolation(expression).is_some(),
+            _ => false,
+        }
+    }
+
+    // the same, for what goes before `as` or starts a splat, which a cast may too
+    fn postfixed(&self, expression: &Expression) -> bool {
+        match expression.node {
+            ExpressionNode::Cast(_, ref kind) => !reads_on(kind),
+            _ => self.atomic(expression),
+        }
+    }
+
+    fn kind(&mut self, kind: &Type) {
+        if let TypeMode::Splat(_) = kind.mode {
+            self.write("...");
+
+            // a bare `...` stays so, spelling it out is left to `wu codemod typed-splats`
+            if matches!(kind.node, TypeNode::Any) && !self.shape {
+                self.splatted += 1;
+
+                if self.splats.get(self.splatted - 1) == Some(&true) {
+                    return;
+                }
+            }
+        }
+
+        self.kind_node(&kind.node)
+    }
+
+    fn kind_node(&mut self, node: &TypeNode) {
+        use self::TypeNode::*;
+
+        match *node {
+            Int => self.write("int"),
+            Float => self.write("float"),
+            Bool => self.write("bool"),
+            Str => self.write("str"),
+            Any => self.write("any"),
+            Char => self.write("char"),
+            Nil => self.write("nil"),
+            This => self.write("self"),
+
+            Id(ref expression) => self.expression(expression),
+
+            Array(ref inner, len) => {
+                self.write("[");
+                self.kind(inner);
+
+                if let Some(len) = len {
+                    self.write(&format!("; {}", len))
+                }
+
+                self.write("]")
+            }
+
+            Func(ref params, ref returns, _, method) => {
+                self.write("fun");
+
+                if method {
+                    let mut params = params.clone();
+                    params.insert(0, Type::from(This));
+
+                    self.kinds(&params)
+                } else {
+                    self.kinds(params)
+                }
+
+                if !is_nil(returns) {
+                    self.write(" -> ");
+                    self.kind(returns)
+                }
+            }
+
+            Optional(ref inner) => {
+                self.kind_node(inner);
+                self.write("?")
+            }
+
+            Tuple(ref content) => self.kinds(content),
+
+            Struct(ref name, ..) | Trait(ref name, ..) | Enum(ref name, ..) => self.write(name),
+            Module(..) => self.write("module"),
+        }
+    }
+
+    // `(A, B)`
+    fn kinds(&mut self, kinds: &[Type]) {
+        self.write("(");
+
+        for (i, kind) in kinds.iter().enumerate() {
+            if i > 0 {
+                self.write(", ")
+            }
+
+            self.kind(kind)
+        }
+
+        self.write(")")
+    }
+}
+
+fn comments_of(trivia: &[Trivia], previous: Option<&Token>, comments: &mut Vec<Comment>) {
+    for trivia in trivia.iter() {
+        if let Trivia::Comment(ref text, ref token) = *trivia {
+            let trailing = previous.is_some_and(|previous| {
+                previous.token_type != TokenType::EOL && previous.line.0 == token.line.0
+            });
+
+            comments.push(Comment {
+                text: text.trim_end().to_string(),
+                line: token.line.0,
+                trailing,
+            })
+        }
+    }
+}
+
+fn is_symbol(token: &SyntaxToken, symbols: &[&str]) -> bool {
+    token.token.token_type == TokenType::Symbol && symbols.contains(&token.text.as_str())
+}
+
+// for the tokens after a `...` splatting `any`, whether it's written bare, as parsing a type takes
+// it; `None` for any other splat
+fn splat_of_any(after: &[SyntaxToken]) -> Option<bool> {
+    let next = match after.first() {
+        Some(next) => next,
+        None => return Some(true),
+    };
+
+    if next.token.token_type == TokenType::EOL || is_symbol(next, &[")", "=", "?", "{"]) {
+        return Some(true);
+    }
+
+    // not a parameter called `any`, e.g. `...any: int`
+    let typed = after.get(1).is_some_and(|token| is_symbol(token, &[":"]));
+
+    (next.text == "any" && !typed).then_some(false)
+}
+
+fn is_nil(kind: &Type) -> bool {
+    matches!((&kind.node, &kind.mode), (TypeNode::Nil, TypeMode::Regular))
+}
+
+fn precedence(operator: &Operator) -> u8 {
+    Operator::from_str(operator.as_str()).map_or(0, |(_, precedence)| precedence)
+}
+
+// whether a type at the end of a cast takes in what follows it, as a name does: `x as T + 1`
+// casts to `T + 1`
+fn reads_on(kind: &Type) -> bool {
+    match kind.node {
+        TypeNode::Id(_) => true,
+        TypeNode::Func(_, ref returns, ..) => !is_nil(returns) && reads_on(returns),
+        _ => false,
+    }
+}
+
+// what a binding declares, rather than gives a value: `name: fun() {}`
+fn declares(expression: &Expression) -> bool {
+    use self::ExpressionNode::*;
+
+    matches!(
+        expression.node,
+        Function(..)
+            | Struct(..)
+            | Enum(..)
+            | Trait(..)
+            | Module(..)
+            | Extern(..)
+            | ExternExpression(_)
+    )
+}
+
+// `text` as a literal between `delimiter`s
+fn quoted(text: &str, delimiter: char) -> String {
+    let mut quoted = delimiter.to_string();
+
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+
+            '{' | '}' if delimiter == '"' => {
+                quoted.push('\\');
+                quoted.push(c)
+            }
+
+            c if c == delimiter => {
+                quoted.push('\\');
+                quoted.push(c)
+            }
+
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push(delimiter);
+    quoted
+}
+
+// the subject and arms of a `switch`, which parses as a block declaring the subject and an `if`
+// comparing each arm with it, see `Parser::parse_bare_atom`
+fn switch(statements: &[Statement]) -> Option<(&Expression, Vec<(&Expression, &Expression)>)> {
+    let (name, subject) = match statements {
+        [declaration, _] => match declaration.node {
+            StatementNode::Variable(_, ref name, Some(ref subject), false)
+                if name.starts_with("__switch_tmp_") =>
+            {
+                (name, subject)
+            }
+
+            _ => return None,
+        },
+
+        _ => return None,
+    };
+
+    let (condition, body, elses) = match statements[1].node {
+        StatementNode::Expression(Expression {
+            node: ExpressionNode::If(ref condition, ref body, ref elses),
+            ..
+        }) => (condition, body, elses),
+
+        _ => return None,
+    };
+
+    let branches = iter::once((Some(&**condition), &**body)).chain(
+        elses
+            .iter()
+            .flatten()
+            .map(|(condition, body, _)| (condition.as_ref(), body)),
+    );
+
+    let mut arms = Vec::new();
+
+    for (condition, body) in branches {
+        match condition.map(|condition| &condition.node) {
+            Some(ExpressionNode::Binary(value, Operator::Eq, compared))
+                if compared.node == ExpressionNode::Identifier(name.clone()) =>
+            {
+                arms.push((&**value, body))
+            }
+
+            _ => return None,
+        }
+    }
+
+    Some((subject, arms))
+}
+
+fn comment_texts(tokens: &SyntaxTokens) -> Vec<String> {
+    let mut comments = Vec::new();
+
+    for token in tokens.tokens.iter() {
+        comments_of(&token.leading, None, &mut comments)
+    }
+
+    comments_of(&tokens.trailing, None, &mut comments);
+
+    comments.into_iter().map(|comment| comment.text).collect()
+}
+
+// `content`, the file `source` was read from, formatted the way `wu fmt` writes it: indented by
+// two spaces, one statement a line, with the comments and single blank lines where they were
+pub fn format_file(content: &str, source: &Source) -> Result<String, ()> {
+    let tokens = SyntaxTokens::lex(content.chars().collect(), source)?;
+    let ast = Parser::new(tokens.parser_tokens(), source).parse()?;
+
+    let formatted = Formatter::new(source, &tokens).format(&ast);
+
+    // read back, it has to be the same tree with the same comments, or formatting got it wrong
+    let reread = Source::from(
+        &source.file.0,
+        formatted.lines().map(|x| x.into()).collect(),
+    );
+
+    let same = silently(|| {
+        let after = SyntaxTokens::lex(formatted.chars().collect(), &reread)?;
+        let ast_after = Parser::new(after.parser_tokens(), &reread).parse()?;
+
+        let shape = Formatter::shaped(source, &tokens).format(&ast);
+        let shape_after = Formatter::shaped(&reread, &after).format(&ast_after);
+
+        Ok::<_, ()>(shape == shape_after && comment_texts(&tokens) == comment_texts(&after))
+    });
+
+    if same != Ok(true) {
+        return Err(response!(
+            Wrong(format!(
+                "can't format `{}` without changing what it means",
+                source.file.0
+            )),
+            Note("this is a bug of `wu fmt`, which leaves the file as it is")
+        ));
+    }
+
+    Ok(formatted)
+}
+
+// `content` formatted the way `wu fmt` writes it, or as it is where it doesn't parse
+pub fn format_source(content: &str) -> String {
+    let source = Source::from("<source>", content.lines().map(|x| x.into()).collect());
+
+    silently(|| format_file(content, &source)).unwrap_or_else(|_| content.to_string())
+}
+
+// the line every top-level statement of `content` starts on, from 0, the first taking the file's
+// start along: the comments and blank lines above a statement go with the one before it
+fn statement_lines(content: &str, source: &Source) -> Result<Vec<usize>, ()> {
+    let tokens = SyntaxTokens::lex(content.chars().collect(), source)?;
+    let ast = Parser::new(tokens.parser_tokens(), source).parse()?;
+
+    let mut lines = ast
+        .iter()
+        .filter(|statement| match statement.node {
+            StatementNode::Expression(ref expression) => expression.node != ExpressionNode::EOF,
+            _ => true,
+        })
+        .map(|statement| (statement.pos.0).0.saturating_sub(1))
+        .collect::<Vec<_>>();
+
+    match lines.first_mut() {
+        Some(first) => *first = 0,
+        None => lines.push(0),
+    }
+
+    Ok(lines)
+}
+
+// the top-level statements of `content` that the bytes `start..end` touch, formatted the way
+// `wu fmt` writes them while everything around them stays as it is. Gives the lines they take,
+// from the first to past the last counting from 0, and what to write there instead; nothing
+// where `content` doesn't parse
+pub fn format_range(content: &str, start: usize, end: usize) -> Option<(usize, usize, String)> {
+    let source = Source::from("<source>", content.lines().map(|x| x.into()).collect());
+    let formatted = silently(|| format_file(content, &source)).ok()?;
+
+    let reread = Source::from("<source>", formatted.lines().map(|x| x.into()).collect());
+
+    let (before, after) = silently(|| {
+        Ok::<_, ()>((
+            statement_lines(content, &source)?,
+            statement_lines(&formatted, &reread)?,
+        ))
+    })
+    .ok()?;
+
+    if before.len() != after.len() {
+        return None;
+    }
+
+    // a span ending with a line break ends on the line it breaks
+    let bytes = content.as_bytes();
+    let end = end.max(start).min(bytes.len());
+    let end = if end > start && bytes[end - 1] == b'\n' { end - 1 } else { end };
+
+    let line_of = |byte: usize| bytes[..byte.min(end)].iter().filter(|&&b| b == b'\n').count();
+
+    let first = before.iter().rposition(|&line| line <= line_of(start))?;
+    let last = before.iter().rposition(|&line| line <= line_of(end))?;
+
+    let written = formatted.lines().collect::<Vec<_>>();
+    let until = after.get(last + 1).copied().unwrap_or(written.len());
+
+    let mut text = written[after[first]..until].join("\n");
+
+    if !text.is_empty() {
+        text.push('\n')
+    }
+
+    Some((
+        before[first],
+        before.get(last + 1).copied().unwrap_or(content.lines().count()),
+        text,
+    ))
+}
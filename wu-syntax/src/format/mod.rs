@@ -0,0 +1,8 @@
+pub mod format;
+
+use super::lexer::*;
+use super::parser::*;
+use super::source::*;
+use super::types::*;
+
+pub use self::format::*;
@@ -4,6 +4,43 @@ use super::super::error::Response::Wrong;
 
 use std::rc::Rc;
 
+pub const KEYWORDS: &[&str] = &[
+    "fun",
+    "->",
+    "=>",
+    "return",
+    "as",
+    "if",
+    "elif",
+    "else",
+    "switch",
+    "while",
+    "skip",
+    "break",
+    "module",
+    "extern",
+    "struct",
+    "new",
+    "implement",
+    "import",
+    "export",
+    "interface",
+    "trait",
+    "enum",
+    "match",
+    "nil",
+    "switch",
+    "for",
+    "in",
+    "pub",
+];
+
+pub const OPERATORS: &[&str] = &[
+    "|>", "<|", "^", "++", "+", "-", "*", "/", "%", "==", "!=", "<=", ">=", "<", ">", "|", "&",
+];
+
+pub const WORD_OPERATORS: &[&str] = &["or", "and", "not"];
+
 pub struct Lexer<'l> {
     tokenizer: Tokenizer<'l>,
     matchers: Vec<Rc<dyn Matcher<'l>>>,
@@ -30,36 +67,9 @@ impl<'l> Lexer<'l> {
         lexer.matchers.push(Rc::new(EOLMatcher));
         lexer.matchers.push(Rc::new(StringLiteralMatcher));
 
-        lexer.matchers.push(Rc::new(KeyMatcher::new(
-            Keyword,
-            &[
-                "fun",
-                "->",
-                "=>",
-                "return",
-                "as",
-                "if",
-                "elif",
-                "else",
-                "switch",
-                "while",
-                "skip",
-                "break",
-                "module",
-                "extern",
-                "struct",
-                "new",
-                "implement",
-                "import",
-                "interface",
-                "trait",
-                "nil",
-                "switch",
-                "for",
-                "in",
-                "pub"
-            ],
-        )));
+        lexer
+            .matchers
+            .push(Rc::new(KeyMatcher::new(Keyword, KEYWORDS)));
 
         lexer
             .matchers
@@ -72,23 +82,20 @@ impl<'l> Lexer<'l> {
         lexer.matchers.push(Rc::new(NumberLiteralMatcher));
         lexer.matchers.push(Rc::new(WhitespaceMatcher));
 
-        lexer.matchers.push(Rc::new(ConstantStringMatcher::new(
-            Operator,
-            &[
-                "|>", "<|", "^", "++", "+", "-", "*", "/", "%", "==", "!=", "<=", ">=", "<", ">",
-            ],
-        )));
+        lexer
+            .matchers
+            .push(Rc::new(ConstantStringMatcher::new(Operator, OPERATORS)));
 
         lexer
             .matchers
-            .push(Rc::new(KeyMatcher::new(Operator, &["or", "and", "not"])));
+            .push(Rc::new(KeyMatcher::new(Operator, WORD_OPERATORS)));
 
         lexer.matchers.push(Rc::new(IdentifierMatcher));
 
         lexer.matchers.push(Rc::new(ConstantCharMatcher::new(
             Symbol,
             &[
-                '?', '!', '(', ')', '[', ']', '{', '}', ',', ':', ';', '=', '.', '|',
+                '?', '!', '(', ')', '[', ']', '{', '}', ',', ':', ';', '=', '.', '|', '@',
             ],
         )));
 
@@ -105,6 +112,28 @@ impl<'l> Lexer<'l> {
 
         Ok(None)
     }
+
+    // how many chars of the source are lexed so far
+    pub fn offset(&self) -> usize {
+        self.tokenizer.index
+    }
+
+    // reports the char no matcher takes
+    pub fn weird_character(&self) {
+        let pos = self.tokenizer.pos;
+
+        response!(
+            Wrong("bumped into weird character"),
+            self.source.file,
+            Pos(
+                (
+                    pos.0,
+                    self.source.line(pos.0)
+                ),
+                (pos.1 + 1, pos.1 + 1),
+            )
+        )
+    }
 }
 
 impl<'l> Iterator for Lexer<'l> {
@@ -114,25 +143,7 @@ impl<'l> Iterator for Lexer<'l> {
         let token = match self.match_token() {
             Ok(hmm) => match hmm {
                 Some(n) => n,
-                None => {
-                    let pos = self.tokenizer.pos;
-
-                    return Some(Err(response!(
-                        Wrong("bumped into weird character"),
-                        self.source.file,
-                        Pos(
-                            (
-                                pos.0,
-                                self.source
-                                    .lines
-                                    .get(pos.0.saturating_sub(1))
-                                    .unwrap_or(self.source.lines.last().unwrap_or(&String::new()))
-                                    .to_string()
-                            ),
-                            (pos.1 + 1, pos.1 + 1),
-                        )
-                    )));
-                }
+                None => return Some(Err(self.weird_character())),
             },
 
             Err(_) => return Some(Err(())),
@@ -0,0 +1,116 @@
+use super::*;
+
+use std::fmt;
+
+// what the parser skips: the text between tokens
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    Comment(String, Token), // with the end of line the parser reads in its place
+}
+
+impl Trivia {
+    pub fn text(&self) -> &str {
+        match *self {
+            Trivia::Whitespace(ref text) | Trivia::Comment(ref text, _) => text,
+        }
+    }
+}
+
+// a token as it's written, with the trivia before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub leading: Vec<Trivia>,
+    pub token: Token,
+    pub text: String, // e.g. a string literal with its quotes and escapes
+}
+
+// a file as tokens and trivia, which put back together are the file byte for byte; the tokens
+// the parser reads are derived from it, so edits to it keep everything they don't touch
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SyntaxTokens {
+    pub tokens: Vec<SyntaxToken>,
+    pub trailing: Vec<Trivia>, // after the last token
+}
+
+impl SyntaxTokens {
+    // lexes `data` keeping everything, the way `Lexer::default` lexes it for the parser
+    pub fn lex(data: Vec<char>, source: &Source) -> Result<Self, ()> {
+        let mut lexer = Lexer::default(data.clone(), source);
+
+        let mut tokens = Vec::new();
+        let mut leading = Vec::new();
+
+        loop {
+            let start = lexer.offset();
+
+            let token = match lexer.match_token()? {
+                Some(token) => token,
+                None => return Err(lexer.weird_character()),
+            };
+
+            let text = data[start..lexer.offset().min(data.len())]
+                .iter()
+                .collect::<String>();
+
+            match token.token_type {
+                TokenType::EOF => break,
+                TokenType::Whitespace => leading.push(Trivia::Whitespace(text)),
+                TokenType::EOL if text.starts_with('#') => {
+                    leading.push(Trivia::Comment(text, token))
+                }
+
+                _ => tokens.push(SyntaxToken {
+                    leading: std::mem::take(&mut leading),
+                    token,
+                    text,
+                }),
+            }
+        }
+
+        Ok(SyntaxTokens {
+            tokens,
+            trailing: leading,
+        })
+    }
+
+    // what `Lexer::default` gives the parser
+    pub fn parser_tokens(&self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+
+        let comments = |trivia: &[Trivia], tokens: &mut Vec<Token>| {
+            for trivia in trivia.iter() {
+                if let Trivia::Comment(_, ref token) = *trivia {
+                    tokens.push(token.clone())
+                }
+            }
+        };
+
+        for token in self.tokens.iter() {
+            comments(&token.leading, &mut tokens);
+            tokens.push(token.token.clone())
+        }
+
+        comments(&self.trailing, &mut tokens);
+
+        tokens
+    }
+}
+
+impl fmt::Display for SyntaxTokens {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in self.tokens.iter() {
+            for trivia in token.leading.iter() {
+                write!(f, "{}", trivia.text())?
+            }
+
+            write!(f, "{}", token.text)?
+        }
+
+        for trivia in self.trailing.iter() {
+            write!(f, "{}", trivia.text())?
+        }
+
+        Ok(())
+    }
+}
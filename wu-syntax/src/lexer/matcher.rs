@@ -6,20 +6,18 @@ macro_rules! token {
         token!($tokenizer, TokenType::$token_type, $accum)
     }};
     ($tokenizer:expr, $token_type:expr, $accum:expr) => {{
-        let tokenizer = $tokenizer as &$crate::wu::lexer::tokenizer::Tokenizer<'t>;
-        let token_type = $token_type as $crate::wu::lexer::token::TokenType;
+        let tokenizer = $tokenizer as &$crate::lexer::tokenizer::Tokenizer<'t>;
+        let token_type = $token_type as $crate::lexer::token::TokenType;
 
         let accum: String = $accum;
         let pos = tokenizer.last_position();
 
-        let line = tokenizer
-            .source
-            .lines
-            .get(pos.0.saturating_sub(1))
-            .unwrap_or(tokenizer.source.lines.last().unwrap())
-            .to_string();
+        let line = tokenizer.source.line(pos.0);
 
-        if TokenType::Str == token_type || TokenType::Char == token_type {
+        if TokenType::Str == token_type
+            || TokenType::Interpolation == token_type
+            || TokenType::Char == token_type
+        {
             Token::new(
                 token_type,
                 (pos.0, line),
@@ -142,7 +140,31 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 
         let mut pos = tokenizer.pos;
 
+        // inside of an interpolation, the braces its value opens are counted, so the one closing
+        // it goes on with the string
+        let next = tokenizer.peek();
+
+        let resumed = match (tokenizer.interpolations.last_mut(), next) {
+            (Some(open), Some('{')) => {
+                *open += 1;
+                return Ok(None);
+            }
+
+            (Some(open), Some('}')) if *open > 0 => {
+                *open -= 1;
+                return Ok(None);
+            }
+
+            (Some(_), Some('}')) => {
+                tokenizer.interpolations.pop();
+                true
+            }
+
+            _ => false,
+        };
+
         let delimeter = match tokenizer.peek().unwrap() {
+            _ if resumed => '"',
             '"' => '"',
             '\'' => '\'',
             'r' => {
@@ -160,12 +182,7 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
                         Pos(
                             (
                                 pos.0,
-                                tokenizer
-                                    .source
-                                    .lines
-                                    .get(pos.0.saturating_sub(1))
-                                    .unwrap_or(tokenizer.source.lines.last().unwrap())
-                                    .to_string()
+                                tokenizer.source.line(pos.0)
                             ),
                             (pos.1 - 1, pos.1),
                         )
@@ -181,6 +198,7 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 
         let mut string = String::new();
         let mut found_escape = false;
+        let mut interpolating = false;
 
         loop {
             if tokenizer.end() {
@@ -190,12 +208,7 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
                     Pos(
                         (
                             pos.0 + 1,
-                            tokenizer
-                                .source
-                                .lines
-                                .get(pos.0.saturating_sub(1))
-                                .unwrap_or(tokenizer.source.lines.last().unwrap())
-                                .to_string()
+                            tokenizer.source.line(pos.0)
                         ),
                         (pos.1.saturating_sub(1), pos.1 + 1),
                     )
@@ -210,7 +223,7 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
                 string.push(tokenizer.next().unwrap())
             } else if found_escape {
                 string.push(match tokenizer.next().unwrap() {
-                    c @ '\\' | c @ '\'' | c @ '"' => c,
+                    c @ '\\' | c @ '\'' | c @ '"' | c @ '{' | c @ '}' => c,
                     'n' => '\n',
                     'r' => '\r',
                     't' => '\t',
@@ -221,12 +234,7 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
                             Pos(
                                 (
                                     tokenizer.pos.0,
-                                    tokenizer
-                                        .source
-                                        .lines
-                                        .get(pos.0.saturating_sub(1))
-                                        .unwrap_or(tokenizer.source.lines.last().unwrap())
-                                        .to_string()
+                                    tokenizer.source.line(pos.0)
                                 ),
                                 (tokenizer.pos.1 - 1, tokenizer.pos.1),
                             )
@@ -242,6 +250,33 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
                         found_escape = true
                     }
 
+                    // e.g. `"hello {name}"`, where `\{` is a brace
+                    '{' if delimeter == '"' => {
+                        tokenizer.next();
+
+                        if tokenizer.peek() == Some('}') {
+                            let line = tokenizer.pos.0;
+
+                            return Err(response!(
+                                Wrong("nothing to interpolate between `{}`"),
+                                tokenizer.source.file,
+                                Pos(
+                                    (
+                                        line,
+                                        tokenizer.source.line(line)
+                                    ),
+                                    (tokenizer.pos.1, tokenizer.pos.1 + 1),
+                                ),
+                                Note("a brace of the string itself is written `\\{`")
+                            ));
+                        }
+
+                        tokenizer.interpolations.push(0);
+
+                        interpolating = true;
+                        break;
+                    }
+
                     // check for valid closing delimeter and alternative
                     c => {
                         if c == delimeter {
@@ -258,6 +293,10 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
             }
         }
 
+        if interpolating {
+            return Ok(Some(token!(tokenizer, Interpolation, string)));
+        }
+
         tokenizer.advance();
 
         if delimeter == '"' {
@@ -278,12 +317,7 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
                     Pos(
                         (
                             pos.0,
-                            tokenizer
-                                .source
-                                .lines
-                                .get(pos.0.saturating_sub(1))
-                                .unwrap_or(tokenizer.source.lines.last().unwrap())
-                                .to_string()
+                            tokenizer.source.line(pos.0)
                         ),
                         (
                             pos.1 + 2,
@@ -347,12 +381,7 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
                         Pos(
                             (
                                 pos.0,
-                                tokenizer
-                                    .source
-                                    .lines
-                                    .get(pos.0.saturating_sub(1))
-                                    .unwrap_or(tokenizer.source.lines.last().unwrap())
-                                    .to_string()
+                                tokenizer.source.line(pos.0)
                             ),
                             (pos.1 + 1, pos.1 + 1),
                         )
@@ -375,9 +404,11 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
 
                 Ok(Some(token!(tokenizer, Float, literal)))
             } else {
-                let literal: String = match accum.parse::<f64>() {
+                // read as a float, ints past 2^53 would be rounded; the parser reports those
+                // too big for an `int` at all
+                let literal: String = match accum.parse::<i64>() {
                     Ok(result) => result.to_string(),
-                    Err(error) => panic!("unable to parse int `{}`: {}", accum, error),
+                    Err(_) => accum,
                 };
 
                 Ok(Some(token!(tokenizer, Int, literal)))
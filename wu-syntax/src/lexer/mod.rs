@@ -1,4 +1,5 @@
 pub mod lexer;
+pub mod lossless;
 pub mod matcher;
 pub mod token;
 pub mod tokenizer;
@@ -6,6 +7,7 @@ pub mod tokenizer;
 use super::source::Source;
 
 pub use self::lexer::*;
+pub use self::lossless::*;
 pub use self::matcher::*;
 pub use self::token::*;
 pub use self::tokenizer::*;
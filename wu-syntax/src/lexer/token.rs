@@ -0,0 +1,209 @@
+use colored::Colorize;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenType {
+    Identifier,
+    Int,
+    Float,
+    Keyword,
+    Str,
+    Interpolation, // the part of a string before a `{`, which the value it interpolates follows
+    Char,
+    Symbol,
+    Operator,
+    Bool,
+    Whitespace,
+    EOL,
+    EOF,
+}
+
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::TokenType::*;
+
+        match *self {
+            Identifier => write!(f, "Identifier"),
+            Int => write!(f, "Int"),
+            Float => write!(f, "Float"),
+            Str => write!(f, "Str"),
+            Interpolation => write!(f, "Interpolation"),
+            Char => write!(f, "Char"),
+            Keyword => write!(f, "Keyword"),
+            Bool => write!(f, "Bool"),
+            Symbol => write!(f, "Symbol"),
+            Operator => write!(f, "Operator"),
+            Whitespace => write!(f, "Whitespace"),
+            EOL => write!(f, "EOL"),
+            EOF => write!(f, "EOF"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pos(pub (usize, Arc<str>), pub (usize, usize));
+
+impl Pos {
+    pub fn get_lexeme(&self) -> String {
+        (self.0).1[(self.1).0 - if (self.1).0 > 0 { 1 } else { 0 }..(self.1).1].to_string()
+    }
+}
+
+impl Pos {
+    // the numbered source line, with the spanned part highlighted if `highlight`
+    fn source_line(&self, highlight: bool) -> String {
+        let lineno = format!("{:5} │ ", (self.0).0).blue().bold();
+        // spans can point past the end of their line, e.g. at the end of the file
+        let line = (self.0).1.chars().collect::<Vec<_>>();
+        let end = (self.1).1.min(line.len());
+        let start = (self.1).0.saturating_sub(1).min(end);
+
+        let mut mark = line[start..end].iter().collect::<String>();
+
+        if highlight {
+            if mark.split_whitespace().count() == 0 {
+                mark = format!("{:─>count$}", ">".red().bold(), count = mark.len());
+            } else {
+                mark = format!("{}", mark.red().bold());
+            }
+        }
+
+        format!(
+            "{}{}{}{}",
+            lineno,
+            line[..start].iter().collect::<String>(),
+            mark,
+            line[end..].iter().collect::<String>()
+        )
+    }
+
+    // `mark` under every spanned column, lined up with `source_line`
+    fn underline(&self, mark: char) -> String {
+        let mut marks = format!("{: <count$}", " ", count = (self.1).0);
+
+        for _ in 0..((self.1).1 + 1).saturating_sub((self.1).0).max(1) {
+            marks.push(mark)
+        }
+
+        marks
+    }
+}
+
+fn linepad() -> colored::ColoredString {
+    format!("{:5} │", " ").blue().bold()
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\n{}\n{}\n{}{}",
+            linepad(),
+            self.source_line(true),
+            linepad(),
+            self.underline('^').red().bold()
+        )
+    }
+}
+
+// a diagnostic pointing at more than one place: the primary span, marked `^`, and secondary ones
+// marked `-`, each with a label saying what it has to do with the error, shown in line order
+pub struct Spans<'p> {
+    primary: (&'p Pos, String),
+    secondary: Vec<(&'p Pos, String)>,
+}
+
+impl<'p> Spans<'p> {
+    pub fn new(primary: &'p Pos, label: impl fmt::Display) -> Self {
+        Spans {
+            primary: (primary, label.to_string()),
+            secondary: Vec::new(),
+        }
+    }
+
+    pub fn label(mut self, pos: &'p Pos, label: impl fmt::Display) -> Self {
+        self.secondary.push((pos, label.to_string()));
+        self
+    }
+
+    pub fn primary(&self) -> &'p Pos {
+        self.primary.0
+    }
+}
+
+impl fmt::Display for Spans<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut spans = vec![(self.primary.0, &self.primary.1, true)];
+        spans.extend(
+            self.secondary
+                .iter()
+                .map(|(pos, label)| (*pos, label, false)),
+        );
+
+        spans.sort_by_key(|(pos, _, is_primary)| ((pos.0).0, !is_primary, (pos.1).0));
+
+        let mut line = None;
+
+        for (pos, label, is_primary) in spans {
+            // the primary comes first on its line, so only it gets highlighted
+            if line != Some((pos.0).0) {
+                write!(f, "\n{}\n{}", linepad(), pos.source_line(is_primary))?;
+
+                line = Some((pos.0).0)
+            }
+
+            let mut marks = pos.underline(if is_primary { '^' } else { '-' });
+
+            if !label.is_empty() {
+                marks.push(' ');
+                marks.push_str(label)
+            }
+
+            if is_primary {
+                write!(f, "\n{}{}", linepad(), marks.red().bold())?
+            } else {
+                write!(f, "\n{}{}", linepad(), marks.blue().bold())?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub line: (usize, Arc<str>),
+    pub slice: (usize, usize),
+    pub lexeme: String,
+}
+
+impl Token {
+    pub fn new(
+        token_type: TokenType,
+        line: (usize, Arc<str>),
+        slice: (usize, usize),
+        lexeme: &str,
+    ) -> Self {
+        Token {
+            token_type,
+            line,
+            slice,
+            lexeme: lexeme.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            Pos(
+                (self.line.0, self.line.1.clone()),
+                (self.slice.0, self.slice.1)
+            )
+        )
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use super::token::*;
 use super::{Matcher, Source};
 
@@ -19,6 +21,7 @@ pub struct Tokenizer<'t> {
     pub items: Vec<char>,
     pub source: &'t Source,
     pub snapshots: Vec<Snapshot>,
+    pub interpolations: Vec<usize>, // the braces open in each `{..}` of a string, innermost last
 }
 
 impl<'t> Tokenizer<'t> {
@@ -30,6 +33,7 @@ impl<'t> Tokenizer<'t> {
             source,
             index: 0,
             snapshots: Vec::new(),
+            interpolations: Vec::new(),
         }
     }
 
@@ -95,15 +99,12 @@ impl<'t> Tokenizer<'t> {
                 TokenType::EOF,
                 (
                     self.pos.0,
-                    if self.source.lines.len() > 0 {
-                        self.source
-                            .lines
-                            .get(self.pos.0)
-                            .unwrap_or(self.source.lines.first().unwrap())
-                            .to_string()
-                    } else {
-                        String::new()
-                    },
+                    self.source
+                        .shared
+                        .get(self.pos.0)
+                        .or(self.source.shared.first())
+                        .cloned()
+                        .unwrap_or_else(|| Arc::from("")),
                 ),
                 (self.pos.1, 0),
                 "",
@@ -0,0 +1,13 @@
+// reading Wu: tokens, the syntax tree and its types, and the diagnostics reporting what's wrong.
+// Formatters, linters and codemods can depend on this without the checker and code generator
+extern crate colored;
+
+#[macro_use]
+pub mod error;
+pub mod budget;
+pub mod codemod;
+pub mod format;
+pub mod lexer;
+pub mod parser;
+pub mod source;
+pub mod types;
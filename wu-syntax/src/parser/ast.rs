@@ -0,0 +1,273 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementNode {
+    Expression(Expression),
+    Variable(Type, String, Option<Expression>, bool), // pub: bool
+    SplatVariable(Type, Vec<String>, Option<Expression>, bool), // pub: u guessed it
+    Assignment(Expression, Expression),
+    SplatAssignment(Vec<Expression>, Expression),
+    Return(Option<Rc<Expression>>),
+    Implement(Expression, Expression, Option<Expression>),
+    // (name or `*`, alias), the `name` of `if cfg(name)`, pub: bool
+    Import(String, Vec<(String, Option<String>)>, Option<String>, bool),
+    //TODO: Never instantiated?
+    ExternBlock(Rc<Statement>),
+    Global(Rc<Statement>), // a binding marked `@global`, which lua keeps in `_G`
+    Skip,
+    Break,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub node: StatementNode,
+    pub pos: Pos,
+}
+
+impl Statement {
+    pub fn new(node: StatementNode, pos: Pos) -> Self {
+        Statement { node, pos }
+    }
+
+    // the binding a `@global` marks, or the statement itself
+    pub fn binding(&self) -> &Statement {
+        match self.node {
+            StatementNode::Global(ref binding) => binding,
+            _ => self,
+        }
+    }
+
+    // how many statements and expressions this is made of, itself included
+    pub fn size(&self) -> usize {
+        use self::StatementNode::*;
+
+        let inner = match self.node {
+            Expression(ref expression) => expression.size(),
+            Variable(_, _, ref right, _) | SplatVariable(_, _, ref right, _) => {
+                right.iter().map(|right| right.size()).sum()
+            }
+            Assignment(ref left, ref right) => left.size() + right.size(),
+            SplatAssignment(ref splats, ref right) => {
+                splats.iter().map(|splat| splat.size()).sum::<usize>() + right.size()
+            }
+            Return(ref value) => value.iter().map(|value| value.size()).sum(),
+            Implement(ref name, ref body, ref parent) => {
+                name.size() + body.size() + parent.iter().map(|parent| parent.size()).sum::<usize>()
+            }
+            ExternBlock(ref statement) | Global(ref statement) => statement.size(),
+            Import(..) | Skip | Break => 0,
+        };
+
+        inner + 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionNode {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Char(char),
+    Bool(bool),
+    UnwrapSplat(Rc<Expression>),
+    Unwrap(Rc<Expression>),
+
+    Tuple(Vec<Expression>),
+
+    Neg(Rc<Expression>),
+    Not(Rc<Expression>),
+
+    Identifier(String),
+    Binary(Rc<Expression>, Operator, Rc<Expression>),
+    Array(Vec<Expression>),
+
+    Call(Rc<Expression>, Vec<Expression>),
+    Index(Rc<Expression>, Rc<Expression>, bool), // whether_index_is_an_array_index: bool
+
+    Cast(Rc<Expression>, Type),
+    Block(Vec<Statement>),
+
+    Function(Vec<(String, Type)>, Type, Rc<Expression>, bool), // is_method: bool
+    If(
+        Rc<Expression>,
+        Rc<Expression>,
+        Option<Vec<(Option<Expression>, Expression, Pos)>>,
+    ),
+    For((Rc<Expression>, Option<Rc<Expression>>), Rc<Expression>),
+    Splat(Vec<Expression>),
+
+    While(Rc<Expression>, Rc<Expression>),
+    Module(Rc<Expression>, Option<Vec<String>>), // exports: only these names are visible outside
+    Extern(Type, Option<String>),
+    ExternExpression(Rc<Expression>),
+    Struct(String, Vec<(String, Type)>, String),
+    Trait(String, Vec<(String, Type)>, Vec<(String, Expression)>), // with the default methods
+    // each variant with what it carries, and whether it's `@flags`: variants that combine as bits
+    Enum(String, Vec<(String, Vec<Type>)>, String, bool),
+    // the arms: the variant matched or `_` for the rest, the names of what it carries, the value
+    Match(Rc<Expression>, Vec<(Option<String>, Vec<String>, Expression, Pos)>),
+    Initialization(Rc<Expression>, Vec<(String, Expression)>),
+
+    Empty,
+    EOF,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub node: ExpressionNode,
+    pub pos: Pos,
+}
+
+impl Expression {
+    pub fn new(node: ExpressionNode, pos: Pos) -> Self {
+        Expression { node, pos }
+    }
+
+    // how many expressions and statements this is made of, itself included
+    pub fn size(&self) -> usize {
+        use self::ExpressionNode::*;
+
+        let all = |content: &[Expression]| content.iter().map(|e| e.size()).sum::<usize>();
+
+        let inner = match self.node {
+            UnwrapSplat(ref inner)
+            | Unwrap(ref inner)
+            | Neg(ref inner)
+            | Not(ref inner)
+            | Cast(ref inner, _)
+            | Module(ref inner, _)
+            | ExternExpression(ref inner)
+            | Function(_, _, ref inner, _) => inner.size(),
+
+            Binary(ref left, _, ref right)
+            | Index(ref left, ref right, _)
+            | While(ref left, ref right) => left.size() + right.size(),
+
+            Tuple(ref content) | Array(ref content) | Splat(ref content) => all(content),
+            Call(ref called, ref args) => called.size() + all(args),
+            Block(ref content) => content.iter().map(|statement| statement.size()).sum(),
+
+            If(ref condition, ref body, ref elses) => {
+                let elses = elses.iter().flatten().map(|(condition, body, _)| {
+                    condition.iter().map(|c| c.size()).sum::<usize>() + body.size()
+                });
+
+                condition.size() + body.size() + elses.sum::<usize>()
+            }
+
+            For((ref iterator, ref second), ref body) => {
+                iterator.size() + second.iter().map(|s| s.size()).sum::<usize>() + body.size()
+            }
+
+            Match(ref subject, ref arms) => {
+                subject.size() + arms.iter().map(|arm| arm.2.size()).sum::<usize>()
+            }
+
+            Initialization(ref name, ref fields) => {
+                name.size() + fields.iter().map(|field| field.1.size()).sum::<usize>()
+            }
+
+            Int(_) | Float(_) | Str(_) | Char(_) | Bool(_) | Identifier(_) | Extern(..)
+            | Struct(..) | Trait(..) | Enum(..) | Empty | EOF => 0,
+        };
+
+        inner + 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Concat,
+    Eq,
+    Lt,
+    Gt,
+    NEq,
+    LtEq,
+    GtEq,
+    Or,
+    And,
+    PipeLeft,
+    PipeRight,
+    BitOr,
+    BitAnd,
+}
+
+impl Operator {
+    pub fn is_right_ass(&self) -> bool {
+        &Operator::Pow == self
+    }
+
+    pub fn from_str(operator: &str) -> Option<(Operator, u8)> {
+        use self::Operator::*;
+
+        let op_prec = match operator {
+            "or" => (Or, 0),
+            "and" => (And, 0),
+            "<|" => (PipeLeft, 0),
+            "|>" => (PipeRight, 0),
+            "==" => (Eq, 1),
+            "<" => (Lt, 1),
+            ">" => (Gt, 1),
+            "!=" => (NEq, 1),
+            "<=" => (LtEq, 1),
+            ">=" => (GtEq, 1),
+            "|" => (BitOr, 2),
+            "&" => (BitAnd, 3),
+            "+" => (Add, 4),
+            "-" => (Sub, 4),
+            "++" => (Concat, 4),
+            "*" => (Mul, 5),
+            "/" => (Div, 5),
+            "%" => (Mod, 5),
+            "^" => (Pow, 6),
+            _ => return None,
+        };
+
+        Some(op_prec)
+    }
+
+    pub fn as_str(&self) -> &str {
+        use self::Operator::*;
+
+        match *self {
+            Add => "+",
+            Sub => "-",
+            Concat => "++",
+            Pow => "^",
+            Mul => "*",
+            Div => "/",
+            Mod => "%",
+            Eq => "==",
+            Lt => "<",
+            Gt => ">",
+            NEq => "!=",
+            LtEq => "<=",
+            GtEq => ">=",
+            Or => "or",
+            And => "and",
+            PipeLeft => "<|",
+            PipeRight => "|>",
+            BitOr => "|",
+            BitAnd => "&",
+        }
+    }
+
+    pub fn is_compoundable(operator: &str) -> bool {
+        ["+", "-", "*", "/", "++", "%", "^", "|", "&", "not", "or", "and"].contains(&operator)
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,71 @@
+use super::super::error::Response::{Note, Wrong};
+use super::*;
+
+const PAIRS: [(&str, &str); 3] = [("(", ")"), ("[", "]"), ("{", "}")];
+
+// pairs up the brackets of a whole file before it's parsed, so one left open is reported where
+// it was opened, rather than where the parser runs out of tokens at the end of the file.
+// Closers without an opener are left for the parser to report where they are
+pub fn check_brackets(tokens: &[Token], source: &Source) -> Result<(), ()> {
+    let mut open: Vec<(&str, Pos)> = Vec::new();
+
+    for token in tokens
+        .iter()
+        .filter(|token| token.token_type == TokenType::Symbol)
+    {
+        let pos = || Pos(token.line.clone(), token.slice);
+
+        if let Some((opener, _)) = PAIRS.iter().find(|(opener, _)| *opener == token.lexeme) {
+            open.push((opener, pos()));
+
+            continue;
+        }
+
+        let closing = match PAIRS.iter().find(|(_, closer)| *closer == token.lexeme) {
+            Some((opener, _)) => *opener,
+            None => continue,
+        };
+
+        match open.pop() {
+            Some((opener, _)) if opener == closing => (),
+
+            Some((opener, ref opened)) => {
+                let closer = closer(opener);
+                let found = pos();
+
+                return Err(response!(
+                    Wrong(format!(
+                        "mismatched `{}`, expected `{}`",
+                        token.lexeme, closer
+                    )),
+                    source.file,
+                    Spans::new(&found, format!("expected `{}`", closer))
+                        .label(opened, format!("to close the `{}` opened here", opener))
+                ));
+            }
+
+            None => return Ok(()),
+        }
+    }
+
+    match open.pop() {
+        Some((opener, ref opened)) => Err(response!(
+            Wrong(format!("unclosed `{}`", opener)),
+            source.file,
+            Spans::new(opened, "opened here"),
+            Note(format!(
+                "the file ends before a `{}` closes it",
+                closer(opener)
+            ))
+        )),
+
+        None => Ok(()),
+    }
+}
+
+fn closer(opener: &str) -> &'static str {
+    PAIRS
+        .iter()
+        .find(|(pair, _)| *pair == opener)
+        .map_or(")", |(_, closer)| closer)
+}
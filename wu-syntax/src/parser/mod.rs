@@ -0,0 +1,14 @@
+pub mod ast;
+pub mod brackets;
+pub mod parser;
+pub mod pragma;
+
+use super::budget::*;
+use super::lexer::*;
+use super::source::*;
+use super::types::*;
+
+pub use self::ast::*;
+pub use self::brackets::*;
+pub use self::parser::*;
+pub use self::pragma::*;
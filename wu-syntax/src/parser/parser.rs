@@ -1,32 +1,142 @@
-use super::super::error::Response::Wrong;
+use super::super::error::Response::{Note, Wrong};
 use super::*;
 
+use std::cell::Cell;
+use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// how deeply expressions, types and imports may nest before compiling them would risk running
+// out of stack, see `--max-depth`
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(256);
+
+pub fn set_max_depth(depth: usize) {
+    MAX_DEPTH.store(depth, Ordering::Relaxed)
+}
+
+pub fn max_depth() -> usize {
+    MAX_DEPTH.load(Ordering::Relaxed)
+}
+
+// the stack to compile on: walking a program nested as deeply as the default `max_depth` allows
+// takes more than a main thread's usual 8MB in debug builds
+pub const STACK: usize = 64 * 1024 * 1024;
+
+// how deeply nested the tree being walked is where the walk is, shared by the parsers of
+// nested blocks
+#[derive(Debug, Clone, Default)]
+pub struct Depth(Rc<Cell<usize>>);
+
+// a level of `Depth`, given back when dropped
+pub struct Level(Rc<Cell<usize>>);
+
+impl Depth {
+    // a level deeper, unless that is deeper than `max_depth`
+    pub fn enter(&self) -> Option<Level> {
+        let depth = self.0.get() + 1;
+
+        if depth > max_depth() {
+            return None;
+        }
+
+        self.0.set(depth);
+
+        Some(Level(self.0.clone()))
+    }
+
+    // whether a level deeper would be deeper than `max_depth`
+    pub fn full(&self) -> bool {
+        self.0.get() >= max_depth()
+    }
+}
+
+impl Drop for Level {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1)
+    }
+}
+
+// the diagnostic for a program nested deeper than `max_depth`, at `pos`
+pub fn too_deep(source: &Source, pos: &Pos) {
+    response!(
+        Wrong("program too deeply nested"),
+        source.file,
+        pos,
+        Note(format!(
+            "nesting is limited to {} levels, raise it with `--max-depth`",
+            max_depth()
+        ))
+    )
+}
+
+// the tokens a parser is given, a window into those of the whole file, so that the parsers of
+// nested blocks share them rather than each copying its own
+#[derive(Debug, Clone)]
+struct Tokens {
+    all: Rc<[Token]>,
+    start: usize,
+    end: usize,
+}
+
+impl Tokens {
+    // the tokens from `start` to `end` of this window, as a window of their own
+    fn window(&self, start: usize, end: usize) -> Self {
+        Tokens {
+            all: self.all.clone(),
+            start: self.start + start,
+            end: self.start + end,
+        }
+    }
+}
+
+impl Deref for Tokens {
+    type Target = [Token];
+
+    fn deref(&self) -> &[Token] {
+        &self.all[self.start..self.end]
+    }
+}
 
 pub struct Parser<'p> {
     index: usize,
-    tokens: Vec<Token>,
+    tokens: Tokens,
     source: &'p Source,
     in_sequence: bool,
     tmp_sequence: bool,
+    depth: Depth,
+    pub budget: Budget, // what the whole compilation may spend, see `Limits`
 }
 
 impl<'p> Parser<'p> {
     pub fn new(tokens: Vec<Token>, source: &'p Source) -> Self {
+        let end = tokens.len();
+
         Parser {
-            tokens,
+            tokens: Tokens {
+                all: tokens.into(),
+                start: 0,
+                end,
+            },
             source,
             index: 0,
             in_sequence: false,
             tmp_sequence: false,
+            depth: Depth::default(),
+            budget: Budget::default(),
         }
     }
 
     pub fn parse(&mut self) -> Result<Vec<Statement>, ()> {
+        check_brackets(&self.tokens, self.source)?;
+
         let mut ast = Vec::new();
 
         while self.remaining() > 0 {
-            ast.push(self.parse_statement()?)
+            let statement = self.parse_statement()?;
+
+            self.spend(Resource::Nodes, statement.size(), &statement.pos)?;
+
+            ast.push(statement)
         }
 
         Ok(ast)
@@ -41,6 +151,14 @@ impl<'p> Parser<'p> {
 
         let position = self.current_position();
 
+        self.spend(Resource::Time, 0, &position)?;
+
+        let declared = self.tokens.get(self.index + 1).is_some_and(|next| next.lexeme == ":");
+
+        if self.at_word_keyword() && declared {
+            return Err(self.keyword_as_name());
+        }
+
         let statement = match self.current_type() {
             Identifier => {
                 let backup_index = self.index;
@@ -256,16 +374,25 @@ impl<'p> Parser<'p> {
                 "import" => {
                     self.next()?;
 
-                    let path = self.eat_type(&Identifier)?;
+                    let path = self.parse_import_path()?;
 
                     let specifics = if self.current_lexeme() == "{" {
-                        self.parse_block_of(("{", "}"), &Self::_parse_name_comma)?
+                        self.parse_block_of(("{", "}"), &Self::_parse_import_comma)?
                     } else {
                         Vec::new()
                     };
 
+                    // `import profiler if cfg(debug)`, only imported with `--cfg debug`
+                    let cfg = if self.remaining() > 0 && self.current_lexeme() == "if" {
+                        self.next()?;
+
+                        Some(self.parse_cfg()?)
+                    } else {
+                        None
+                    };
+
                     Statement::new(
-                        StatementNode::Import(path, specifics, false),
+                        StatementNode::Import(path, specifics, cfg, false),
                         self.span_from(position),
                     )
                 }
@@ -288,8 +415,7 @@ impl<'p> Parser<'p> {
 
                     let end = self.index;
 
-                    let mut name_parser =
-                        Parser::new(self.tokens[start..end].to_vec(), self.source);
+                    let mut name_parser = self.within(start, end);
 
                     let name = name_parser.parse_expression()?;
 
@@ -321,6 +447,68 @@ impl<'p> Parser<'p> {
                 }
             },
 
+            // `@global name: ...`, for the few names other lua looks up in `_G`
+            // `@flags name: enum { ... }`, for variants that combine with `|` and `&`
+            Symbol if self.current_lexeme() == "@" => {
+                self.next()?;
+
+                let attribute = self.current_position();
+
+                let flags = match self.eat_type(&Identifier)?.as_str() {
+                    "global" => false,
+                    "flags" => true,
+                    _ => {
+                        return Err(response!(
+                            Wrong(format!("no such attribute `@{}`", attribute.get_lexeme())),
+                            self.source.file,
+                            attribute
+                        ))
+                    }
+                };
+
+                let thing = self.parse_statement()?;
+
+                if flags {
+                    if let StatementNode::Variable(ref kind, ref name, Some(ref right), public) =
+                        thing.node
+                    {
+                        if let ExpressionNode::Enum(ref of, ref variants, ref id, _) = right.node {
+                            let variants = variants.clone();
+                            let node = ExpressionNode::Enum(of.clone(), variants, id.clone(), true);
+
+                            return Ok(Statement::new(
+                                StatementNode::Variable(
+                                    kind.clone(),
+                                    name.clone(),
+                                    Some(Expression::new(node, right.pos.clone())),
+                                    public,
+                                ),
+                                thing.pos.clone(),
+                            ));
+                        }
+                    }
+
+                    return Err(response!(
+                        Wrong("expected enum after `@flags`"),
+                        self.source.file,
+                        thing.pos
+                    ));
+                }
+
+                if let StatementNode::Variable(..) = thing.node {
+                    return Ok(Statement::new(
+                        StatementNode::Global(Rc::new(thing)),
+                        position,
+                    ));
+                }
+
+                return Err(response!(
+                    Wrong("expected binding after `@global`"),
+                    self.source.file,
+                    thing.pos
+                ));
+            }
+
             _ => {
                 let mut expression = self.parse_expression()?;
                 expression = self.maybe_splat(expression)?;
@@ -349,6 +537,81 @@ impl<'p> Parser<'p> {
         Ok(statement)
     }
 
+    // `foo`, `foo/bar`, `./foo` or `../shared/foo`, where `super` is an alias for `..`
+    // the `name` of `cfg(name)`
+    fn parse_cfg(&mut self) -> Result<String, ()> {
+        if self.current_lexeme() != "cfg" {
+            return Err(response!(
+                Wrong(format!(
+                    "expected `cfg(name)` after `if`, found `{}`",
+                    self.current_lexeme()
+                )),
+                self.source.file,
+                self.current_position()
+            ));
+        }
+
+        self.next()?;
+        self.eat_lexeme("(")?;
+
+        let name = self.eat_type(&TokenType::Identifier)?;
+
+        self.eat_lexeme(")")?;
+
+        Ok(name)
+    }
+
+    fn parse_import_path(&mut self) -> Result<String, ()> {
+        let mut segments = Vec::new();
+
+        loop {
+            let segment = match (self.current_type(), self.current_lexeme().as_str()) {
+                (TokenType::Symbol, "..") | (TokenType::Identifier, "super") => "..".to_string(),
+                (TokenType::Symbol, ".") => ".".to_string(),
+
+                // the project root, resolved by the visitor
+                (TokenType::Identifier, "root") if segments.is_empty() => "root".to_string(),
+                (TokenType::Identifier, "root") => {
+                    return Err(response!(
+                        Wrong("`root` can only start an import path"),
+                        self.source.file,
+                        self.current_position()
+                    ))
+                }
+
+                (TokenType::Identifier, name) => name.to_string(),
+
+                (_, lexeme) => {
+                    return Err(response!(
+                        Wrong(format!("expected module path, found `{}`", lexeme)),
+                        self.source.file,
+                        self.current_position()
+                    ))
+                }
+            };
+
+            segments.push(segment);
+
+            self.next()?;
+
+            if self.remaining() > 0 && self.current_lexeme() == "/" {
+                self.next()?
+            } else {
+                break;
+            }
+        }
+
+        if [".", "..", "root"].contains(&segments.last().unwrap().as_str()) {
+            return Err(response!(
+                Wrong("expected module name at the end of import path"),
+                self.source.file,
+                self.current_position()
+            ));
+        }
+
+        Ok(segments.join("/"))
+    }
+
     fn maybe_splat(&mut self, expr: Expression) -> Result<Expression, ()> {
         match self.current_lexeme().as_str() {
             "," => {
@@ -480,7 +743,30 @@ impl<'p> Parser<'p> {
                     ExpressionNode::Struct(
                         name,
                         params,
-                        format!("{}{}", self.source.file, position),
+                        format!("{}:{}:{}", self.source.file.0, (position.0).0, (position.1).0),
+                    ),
+                    position,
+                ))
+            },
+
+            "enum" => {
+                let mut position = self.current_position();
+
+                self.next()?;
+                self.next_newline()?;
+
+                position = self.span_from(position);
+
+                self.expect_lexeme("{")?;
+
+                let variants = self.parse_block_of(("{", "}"), &Self::_parse_variant_comma)?;
+
+                Some(Expression::new(
+                    ExpressionNode::Enum(
+                        name,
+                        variants,
+                        format!("{}:{}:{}", self.source.file.0, (position.0).0, (position.1).0),
+                        false,
                     ),
                     position,
                 ))
@@ -491,9 +777,20 @@ impl<'p> Parser<'p> {
 
                 self.next()?;
 
-                let body = self.parse_block_of(("{", "}"), &Self::_parse_param_comma)?;
+                let body = self.parse_block_of(("{", "}"), &Self::_parse_member_comma)?;
 
-                Some(Expression::new(ExpressionNode::Trait(name, body), position))
+                let mut members = Vec::new();
+                let mut defaults = Vec::new();
+
+                for (member, kind, default) in body {
+                    if let Some(default) = default {
+                        defaults.push((member.clone(), default))
+                    }
+
+                    members.push((member, kind))
+                }
+
+                Some(Expression::new(ExpressionNode::Trait(name, members, defaults), position))
             },
 
             "module" => {
@@ -502,12 +799,21 @@ impl<'p> Parser<'p> {
                 self.next()?;
                 self.next_newline()?;
 
+                let exports = if self.current_lexeme() == "export" {
+                    self.next()?;
+                    self.next_newline()?;
+
+                    Some(self.parse_block_of(("{", "}"), &Self::_parse_name_comma)?)
+                } else {
+                    None
+                };
+
                 self.next_newline()?;
 
                 self.expect_lexeme("{")?;
 
                 Some(Expression::new(
-                    ExpressionNode::Module(Rc::new(self.parse_expression()?)),
+                    ExpressionNode::Module(Rc::new(self.parse_expression()?), exports),
                     position,
                 ))
             },
@@ -570,7 +876,80 @@ impl<'p> Parser<'p> {
         }
     }
 
+    // `"a {b} c"` as `"a " ++ (b as str) ++ " c"`, leaving out the empty parts
+    fn parse_interpolation(&mut self, position: Pos) -> Result<Expression, ()> {
+        let mut parts = Vec::new();
+
+        let end = loop {
+            let part_position = self.current_position();
+            let end = part_position.1 .1;
+            let last = self.current_type() == TokenType::Str;
+
+            let text = self.eat()?;
+
+            if !text.is_empty() {
+                parts.push(Expression::new(ExpressionNode::Str(text), part_position))
+            }
+
+            if last {
+                break end;
+            }
+
+            // a `,` ends the value, rather than making it a splat
+            let in_sequence = self.in_sequence;
+            self.in_sequence = true;
+
+            let value = self.parse_expression();
+            self.in_sequence = in_sequence;
+
+            let value = value?;
+
+            // up to the `}`, so it isn't mistaken for the value itself
+            let pos = Pos(value.pos.0.clone(), (value.pos.1 .0, value.pos.1 .1 + 1));
+
+            parts.push(Expression::new(
+                ExpressionNode::Cast(Rc::new(value), Type::from(TypeNode::Str)),
+                pos,
+            ));
+
+            if !matches!(
+                self.current_type(),
+                TokenType::Interpolation | TokenType::Str
+            ) {
+                return Err(response!(
+                    Wrong(format!(
+                        "expected `}}` closing the interpolation, found `{}`",
+                        self.current_lexeme()
+                    )),
+                    self.source.file,
+                    self.current_position()
+                ));
+            }
+        };
+
+        let pos = Pos(position.0.clone(), (position.1 .0, end));
+        let mut parts = parts.into_iter();
+
+        // at least the value, there's no `{}`
+        let first = parts.next().unwrap();
+
+        Ok(parts.fold(first, |left, right| {
+            Expression::new(
+                ExpressionNode::Binary(Rc::new(left), Operator::Concat, Rc::new(right)),
+                pos.clone(),
+            )
+        }))
+    }
+
     fn parse_atom(&mut self) -> Result<Expression, ()> {
+        let _level = self.descend()?;
+
+        self.spend(Resource::Time, 0, &self.current_position())?;
+
+        self.parse_bare_atom()
+    }
+
+    fn parse_bare_atom(&mut self) -> Result<Expression, ()> {
         use self::TokenType::*;
 
         if self.remaining() == 0 {
@@ -583,10 +962,25 @@ impl<'p> Parser<'p> {
             let position = self.current_position();
 
             let expression = match token_type {
-                Int => Expression::new(
-                    ExpressionNode::Int(self.eat()?.parse::<i64>().unwrap()),
-                    position,
-                ),
+                Int => {
+                    let lexeme = self.eat()?;
+
+                    match lexeme.parse::<i64>() {
+                        Ok(n) => Expression::new(ExpressionNode::Int(n), position),
+
+                        Err(_) => {
+                            return Err(response!(
+                                Wrong(format!(
+                                    "`{}` is too big for an `int`, which goes up to {}",
+                                    lexeme,
+                                    i64::MAX
+                                )),
+                                self.source.file,
+                                position
+                            ))
+                        }
+                    }
+                }
 
                 Float => Expression::new(
                     ExpressionNode::Float(self.eat()?.parse::<f64>().unwrap()),
@@ -600,6 +994,8 @@ impl<'p> Parser<'p> {
 
                 Str => Expression::new(ExpressionNode::Str(self.eat()?), position),
 
+                Interpolation => self.parse_interpolation(position)?,
+
                 Identifier => Expression::new(ExpressionNode::Identifier(self.eat()?), position),
 
                 Bool => Expression::new(ExpressionNode::Bool(self.eat()? == "true"), position),
@@ -669,11 +1065,22 @@ impl<'p> Parser<'p> {
 
                         self.enter_sequence();
 
-                        let possible_content = self.parse_block_of(("(", ")"), &Self::_parse_expression_comma)?;
+                        let mut possible_content = self.parse_block_of(("(", ")"), &Self::_parse_expression_comma)?;
 
                         self.exit_sequence();
 
-                        if possible_content.len() > 1 {
+                        // a single expression is already parsed, unless a trailing comma
+                        // makes it what `(1,)` is; parsing it again doubles the work at every
+                        // level of nesting
+                        let trailing_comma = self.tokens[..self.index - 1]
+                            .iter()
+                            .rev()
+                            .find(|token| token.lexeme != "\n")
+                            .is_some_and(|token| token.lexeme == "," && token.token_type == TokenType::Symbol);
+
+                        if possible_content.len() == 1 && !trailing_comma {
+                            possible_content.pop().unwrap()
+                        } else if possible_content.len() > 1 {
                             self.enter_sequence();
 
                             let expr = Expression::new(
@@ -811,6 +1218,58 @@ impl<'p> Parser<'p> {
                             Expression::new(ExpressionNode::Block(block_scope), position)
                         }
 
+                        "match" => {
+                            self.next()?;
+                            self.next_newline()?;
+
+                            let subject = Rc::new(self.parse_expression()?);
+
+                            let end = (self.tokens[self.index - 1].slice).1;
+                            let position = Pos(position.0, ((position.1).0, end));
+
+                            self.next_newline()?;
+
+                            self.eat_lexeme("{")?;
+
+                            self.next_newline()?;
+
+                            let mut arms = Vec::new();
+
+                            while self.current_lexeme() != "}" && self.remaining() > 0 {
+                                let arm_position = self.current_position();
+
+                                let variant = match self.eat_type(&Identifier)?.as_str() {
+                                    "_" => None,
+                                    name => Some(name.to_string()),
+                                };
+
+                                let names = if self.current_lexeme() == "(" {
+                                    self.parse_block_of(("(", ")"), &Self::_parse_name_comma)?
+                                } else {
+                                    Vec::new()
+                                };
+
+                                // the pattern, up to the `)` closing its names
+                                let end = (self.tokens[self.index - 1].slice).1;
+                                let arm_position =
+                                    Pos(arm_position.0, ((arm_position.1).0, end));
+
+                                self.eat_lexeme("=>")?;
+
+                                self.next_newline()?;
+
+                                let body = self.parse_expression()?;
+
+                                arms.push((variant, names, body, arm_position));
+
+                                self.next_newline()?;
+                            }
+
+                            self.eat_lexeme("}")?;
+
+                            Expression::new(ExpressionNode::Match(subject, arms), position)
+                        }
+
                         "for" => {
                             self.next()?;
                             self.next_newline()?;
@@ -976,6 +1435,12 @@ impl<'p> Parser<'p> {
     }
 
     fn parse_postfix(&mut self, expression: Expression) -> Result<Expression, ()> {
+        let _level = self.descend()?;
+
+        self.parse_bare_postfix(expression)
+    }
+
+    fn parse_bare_postfix(&mut self, expression: Expression) -> Result<Expression, ()> {
         if self.remaining() == 0 {
             return Ok(expression);
         }
@@ -1100,6 +1565,9 @@ impl<'p> Parser<'p> {
         let mut left = left;
         let left_position = left.pos.clone();
 
+        // every operator applied nests what came before it one deeper
+        let mut levels = Vec::new();
+
         while self.current_type() == TokenType::Operator {
             let index_backup = self.index;
             let operator = Operator::from_str(self.eat()?.as_str()).unwrap();
@@ -1115,6 +1583,8 @@ impl<'p> Parser<'p> {
                 operator.1
             };
 
+            levels.push(self.descend()?);
+
             let mut right = self.parse_atom()?;
             right = self.parse_binary(right, prec as usize)?;
 
@@ -1128,6 +1598,12 @@ impl<'p> Parser<'p> {
     }
 
     fn parse_type(&mut self) -> Result<Type, ()> {
+        let _level = self.descend()?;
+
+        self.parse_bare_type()
+    }
+
+    fn parse_bare_type(&mut self) -> Result<Type, ()> {
         use self::TokenType::*;
 
         let mut t = match self.current_type() {
@@ -1219,6 +1695,12 @@ impl<'p> Parser<'p> {
 
                     while self.current_lexeme() == "," {
                         self.eat()?;
+                        self.next_newline()?;
+
+                        // a trailing comma, e.g. at the end of a line
+                        if self.current_lexeme() == ")" {
+                            break;
+                        }
 
                         content.push(self.parse_type()?);
 
@@ -1364,6 +1846,33 @@ impl<'p> Parser<'p> {
         self.tokens.len().saturating_sub(self.index)
     }
 
+    // a parser of the tokens from `start` to `end`, as deep as this one
+    fn within(&self, start: usize, end: usize) -> Parser<'p> {
+        Parser {
+            tokens: self.tokens.window(start, end),
+            source: self.source,
+            index: 0,
+            in_sequence: false,
+            tmp_sequence: false,
+            depth: self.depth.clone(),
+            budget: self.budget.clone(),
+        }
+    }
+
+    // spends `amount` of the compilation's budget, failing at `pos` once it's spent
+    fn spend(&self, resource: Resource, amount: usize, pos: &Pos) -> Result<(), ()> {
+        self.budget
+            .spend(resource, amount)
+            .map_err(|exceeded| too_large(self.source, pos, exceeded))
+    }
+
+    fn descend(&mut self) -> Result<Level, ()> {
+        match self.depth.enter() {
+            Some(level) => Ok(level),
+            None => Err(too_deep(self.source, &self.current_position())),
+        }
+    }
+
     fn current_position(&self) -> Pos {
         let current = self.current();
 
@@ -1422,6 +1931,10 @@ impl<'p> Parser<'p> {
     }
 
     fn eat_type(&mut self, token_type: &TokenType) -> Result<String, ()> {
+        if *token_type == TokenType::Identifier && self.at_word_keyword() {
+            return Err(self.keyword_as_name());
+        }
+
         if self.current_type() == *token_type {
             let lexeme = self.current().lexeme.clone();
             self.next()?;
@@ -1440,6 +1953,54 @@ impl<'p> Parser<'p> {
         }
     }
 
+    // whether the current token is a keyword that looks like a name, unlike `->`
+    fn at_word_keyword(&self) -> bool {
+        self.current_type() == TokenType::Keyword
+            && self.current().lexeme.chars().all(char::is_alphabetic)
+    }
+
+    // a keyword where a name goes, e.g. `return := 1` or `fun(if: int)`
+    fn keyword_as_name(&self) {
+        let keyword = self.current_lexeme();
+
+        response!(
+            Wrong(format!("`{}` is a keyword", keyword)),
+            self.source.file,
+            self.current_position(),
+            Note(format!("it can't be used as a name, try e.g. `{}_`", keyword))
+        )
+    }
+
+    // whether the next token past any newlines is a `,`
+    fn comma_ahead(&self) -> bool {
+        self.tokens
+            .iter()
+            .skip(self.index)
+            .find(|token| token.lexeme != "\n")
+            .is_some_and(|token| token.lexeme == ",")
+    }
+
+    // whether the member starting here has a body, a `{` before the `,` or newline ending it
+    fn body_ahead(&self) -> bool {
+        let mut depth = 0;
+
+        for token in self.tokens.iter().skip(self.index) {
+            if token.token_type != TokenType::Symbol && token.lexeme != "\n" {
+                continue;
+            }
+
+            match token.lexeme.as_str() {
+                "(" | "[" => depth += 1,
+                ")" | "]" => depth -= 1,
+                "{" if depth == 0 => return true,
+                "," | "\n" if depth == 0 => return false,
+                _ => (),
+            }
+        }
+
+        false
+    }
+
     fn current_lexeme(&self) -> String {
         self.current().lexeme.clone()
     }
@@ -1492,11 +2053,19 @@ impl<'p> Parser<'p> {
 
         if self.current_lexeme() == delimeters.1 {
             self.next()?;
+            self.exit_sequence();
 
             return Ok(Vec::new());
         }
 
-        let mut block_tokens = Vec::new();
+        // the block is a level deeper, so don't bother gathering it if that is too deep
+        if self.depth.full() {
+            too_deep(self.source, &self.current_position());
+
+            return Err(());
+        }
+
+        let start = self.index;
         let mut nest_count = 1;
 
         while nest_count > 0 {
@@ -1511,17 +2080,16 @@ impl<'p> Parser<'p> {
             if nest_count == 0 {
                 break;
             } else {
-                block_tokens.push(self.current());
-
                 self.next()?;
             }
         }
 
-        self.eat_lexeme(delimeters.1)?;
+        let end = self.index;
 
+        self.eat_lexeme(delimeters.1)?;
 
-        if !block_tokens.is_empty() {
-            let mut parser = Parser::new(block_tokens, self.source);
+        if end > start {
+            let mut parser = self.within(start, end);
             parser.in_sequence = self.in_sequence;
             let mut block = Vec::new();
 
@@ -1589,6 +2157,64 @@ impl<'p> Parser<'p> {
         }
     }
 
+    // Parses `name`, `name as alias` or `*` in an import list
+    fn _parse_import_comma(self: &mut Self) -> Result<Option<(String, Option<String>)>, ()> {
+        if self.remaining() > 0 && self.current_lexeme() == "\n" {
+            self.next()?
+        }
+
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+
+        let position = self.current_position();
+
+        let name = if self.current_lexeme() == "*" {
+            self.next()?;
+
+            "*".to_string()
+        } else {
+            self.eat_type(&TokenType::Identifier)?
+        };
+
+        let alias = if self.current_lexeme() == "as" && self.remaining() > 0 {
+            if name == "*" {
+                return Err(response!(
+                    Wrong("can't alias a wildcard import"),
+                    self.source.file,
+                    position
+                ));
+            }
+
+            self.next()?;
+
+            Some(self.eat_type(&TokenType::Identifier)?)
+        } else {
+            None
+        };
+
+        if self.remaining() > 0 {
+            if ![",", "\n"].contains(&self.current_lexeme().as_str()) {
+                return Err(response!(
+                    Wrong(format!(
+                        "expected `,` or newline, found `{}`",
+                        self.current_lexeme()
+                    )),
+                    self.source.file,
+                    self.current_position()
+                ));
+            } else {
+                self.next()?;
+            }
+
+            if self.remaining() > 0 && self.current_lexeme() == "\n" {
+                self.next()?
+            }
+        }
+
+        Ok(Some((name, alias)))
+    }
+
     // Static method for parsing sequence `expr* ,* \n*` - for things like [1, 2, 3, 4,]
     fn _parse_expression_comma(self: &mut Self) -> Result<Option<Expression>, ()> {
         if self.remaining() > 0 && self.current_lexeme() == "\n" {
@@ -1596,7 +2222,7 @@ impl<'p> Parser<'p> {
             self.next_newline()?
         }
 
-        let expression = Self::_parse_expression(self);
+        let expression = Self::_parse_expression(self)?;
 
         if self.remaining() > 0 && self.current_lexeme() == "\n" {
             self.next()?;
@@ -1612,7 +2238,7 @@ impl<'p> Parser<'p> {
             }
         }
 
-        expression
+        Ok(expression)
     }
 
     fn _parse_param_comma(self: &mut Self) -> Result<Option<(String, Type)>, ()> {
@@ -1680,7 +2306,10 @@ impl<'p> Parser<'p> {
 
         let param = Some((name, kind));
 
-        self.next_newline()?;
+        // a newline may come before the `,`, otherwise it is what separates the parameters
+        if self.comma_ahead() {
+            self.next_newline()?;
+        }
 
         if self.remaining() > 0 {
             if ![",", "\n"].contains(&self.current_lexeme().as_str()) {
@@ -1704,6 +2333,67 @@ impl<'p> Parser<'p> {
         Ok(param)
     }
 
+    // a trait member, `name: fun(self) -> T`, or with a body after it a default method
+    fn _parse_member_comma(
+        self: &mut Self,
+    ) -> Result<Option<(String, Type, Option<Expression>)>, ()> {
+        if self.remaining() > 0 && self.current_lexeme() == "\n" {
+            self.next()?
+        }
+
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+
+        if !self.body_ahead() {
+            return Ok(self
+                ._parse_param_comma()?
+                .map(|(name, kind)| (name, kind, None)));
+        }
+
+        let name = self.eat_type(&TokenType::Identifier)?;
+
+        self.eat_lexeme(":")?;
+        self.expect_lexeme("fun")?;
+
+        let function = self.parse_function()?;
+
+        let kind = match function.node {
+            ExpressionNode::Function(ref params, ref retty, _, is_method) => {
+                Type::from(TypeNode::Func(
+                    params.iter().map(|param| param.1.clone()).collect(),
+                    Rc::new(retty.clone()),
+                    Some(Rc::new(function.node.clone())),
+                    is_method,
+                ))
+            }
+            _ => unreachable!(),
+        };
+
+        let member = Some((name, kind, Some(function)));
+
+        if self.remaining() > 0 {
+            if ![",", "\n"].contains(&self.current_lexeme().as_str()) {
+                return Err(response!(
+                    Wrong(format!(
+                        "expected `,` or newline, found `{}`",
+                        self.current_lexeme()
+                    )),
+                    self.source.file,
+                    self.current_position()
+                ));
+            } else {
+                self.next()?;
+            }
+
+            if self.remaining() > 0 && self.current_lexeme() == "\n" {
+                self.next()?
+            }
+        }
+
+        Ok(member)
+    }
+
     fn _parse_definition_comma(self: &mut Self) -> Result<Option<(String, Expression)>, ()> {
         if self.remaining() > 0 && self.current_lexeme() == "\n" {
             self.next()?
@@ -1786,17 +2476,70 @@ impl<'p> Parser<'p> {
         Ok(param)
     }
 
+    // a variant of an enum, with the types of what it carries, e.g. `Rect(float, float)`
+    fn _parse_variant_comma(self: &mut Self) -> Result<Option<(String, Vec<Type>)>, ()> {
+        if self.remaining() > 0 && self.current_lexeme() == "\n" {
+            self.next()?;
+            self.next_newline()?;
+        }
+
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+
+        let name = self.eat_type(&TokenType::Identifier)?;
+
+        let carried = if self.remaining() > 0 && self.current_lexeme() == "(" {
+            self.parse_block_of(("(", ")"), &Self::_parse_type_comma)?
+        } else {
+            Vec::new()
+        };
+
+        if self.remaining() > 0 {
+            if ![",", "\n"].contains(&self.current_lexeme().as_str()) {
+                return Err(response!(
+                    Wrong(format!(
+                        "expected `,` or newline, found `{}`",
+                        self.current_lexeme()
+                    )),
+                    self.source.file,
+                    self.current_position()
+                ));
+            } else {
+                self.next()?;
+            }
+
+            if self.remaining() > 0 && self.current_lexeme() == "\n" {
+                self.next()?
+            }
+        }
+
+        Ok(Some((name, carried)))
+    }
+
+    // the same for types, e.g. the parameters of `fun(int, str,)`
     fn _parse_type_comma(self: &mut Self) -> Result<Option<Type>, ()> {
+        if self.remaining() > 0 && self.current_lexeme() == "\n" {
+            self.next()?;
+            self.next_newline()?
+        }
+
         if self.remaining() == 0 {
             Ok(None)
         } else {
             let t = self.parse_type()?;
 
+            if self.remaining() > 0 && self.current_lexeme() == "\n" {
+                self.next()?;
+                self.next_newline()?
+            }
+
             if self.remaining() > 0 {
                 self.eat_lexeme(",")?;
 
                 if self.remaining() > 0 && self.current_lexeme() == "\n" {
-                    self.next()?
+                    self.next()?;
+                    self.next_newline()?
                 }
             }
 
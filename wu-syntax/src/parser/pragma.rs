@@ -0,0 +1,214 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use super::super::error::Response::{self, Note, Weird, Wrong};
+use super::*;
+
+// what is warned about, or what a module may ask to be checked more strictly for, or let off from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lint {
+    ImplicitAny,      // bindings typed `any` without saying so, e.g. `x` in `fun(x)`
+    Conversions,      // `++` turning an `int`, `float` or `bool` into a `str`
+    InexactInts,      // `int` literals past 2^53, before lua 5.3
+    Complexity,       // functions past `--max-lines`, `--max-nesting` or `--max-params`
+    UnusedExports,    // `pub` definitions no module of a program imports
+    PrivateCallbacks, // LÖVE callbacks `main` doesn't make `pub`
+    Edition,          // code a later edition reads differently
+}
+
+// how a lint is reported: not at all, as a warning, or as an error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+// the levels set for the whole build, by `--allow`, `--warn` and `--deny` or wu.toml's `[lints]`
+static LEVELS: Mutex<Vec<(Lint, LintLevel)>> = Mutex::new(Vec::new());
+
+// reports `lint` at `level` from here on, over what was set for it before
+pub fn set_level(lint: Lint, level: LintLevel) {
+    let mut levels = LEVELS.lock().unwrap();
+
+    levels.retain(|(set, _)| *set != lint);
+    levels.push((lint, level))
+}
+
+impl Lint {
+    pub const ALL: [Lint; 7] = [
+        Lint::ImplicitAny,
+        Lint::Conversions,
+        Lint::InexactInts,
+        Lint::Complexity,
+        Lint::UnusedExports,
+        Lint::PrivateCallbacks,
+        Lint::Edition,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Lint::ImplicitAny => "implicit_any",
+            Lint::Conversions => "conversions",
+            Lint::InexactInts => "inexact_ints",
+            Lint::Complexity => "complexity",
+            Lint::UnusedExports => "unused_exports",
+            Lint::PrivateCallbacks => "private_callbacks",
+            Lint::Edition => "edition",
+        }
+    }
+
+    // the lint called `name`, which may be written with dashes, e.g. `unused-exports`
+    pub fn named(name: &str) -> Option<Lint> {
+        let name = name.replace('-', "_");
+
+        Lint::ALL.iter().copied().find(|lint| lint.name() == name)
+    }
+
+    // e.g. "`implicit_any`, `conversions` and `edition`", for notes
+    pub fn names() -> String {
+        let names = Lint::ALL
+            .iter()
+            .map(|lint| format!("`{}`", lint.name()))
+            .collect::<Vec<_>>();
+
+        format!(
+            "{} and {}",
+            names[..names.len() - 1].join(", "),
+            names[names.len() - 1]
+        )
+    }
+
+    // the stricter checks are left to `--warn`, `--deny` or `#!strict`
+    fn default_level(&self) -> LintLevel {
+        match *self {
+            Lint::ImplicitAny | Lint::Conversions => LintLevel::Allow,
+            _ => LintLevel::Warn,
+        }
+    }
+
+    // how the build reports this lint
+    pub fn level(&self) -> LintLevel {
+        LEVELS
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(set, _)| set == self)
+            .map_or(self.default_level(), |(_, level)| *level)
+    }
+}
+
+impl LintLevel {
+    // `allow`, `warn` or `deny`, e.g. from wu.toml
+    pub fn from(name: &str) -> Option<LintLevel> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+
+    // `message` as reported at this level, if it is at all
+    pub fn response<T: fmt::Display>(&self, message: T) -> Option<Response<T>> {
+        match *self {
+            LintLevel::Allow => None,
+            LintLevel::Warn => Some(Weird(message)),
+            LintLevel::Deny => Some(Wrong(message)),
+        }
+    }
+}
+
+// the `#!strict` and `#!allow(lint, ...)` lines heading a module, which make its checking
+// stricter than the rest of the codebase's, so it can migrate a module at a time
+#[derive(Debug, Clone, Default)]
+pub struct Pragmas {
+    pub strict: bool,
+    pub allowed: Vec<Lint>,
+}
+
+impl Pragmas {
+    // whether `lint` is an error in this module, `#!strict` denying those allowed by default
+    pub fn denies(&self, lint: Lint) -> bool {
+        self.strict && lint.default_level() == LintLevel::Allow && !self.allows(lint)
+    }
+
+    pub fn allows(&self, lint: Lint) -> bool {
+        self.allowed.contains(&lint)
+    }
+
+    // how `lint` is reported in this module, which its pragmas may change from the build's level
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        if self.allows(lint) {
+            LintLevel::Allow
+        } else if self.denies(lint) {
+            LintLevel::Deny
+        } else {
+            lint.level()
+        }
+    }
+
+    // the pragmas before the first statement; comments and blank lines may come between them
+    pub fn read(source: &Source) -> Result<Self, ()> {
+        let mut pragmas = Pragmas::default();
+        let mut heading = true;
+
+        for (i, line) in source.lines.iter().enumerate() {
+            let trimmed = line.trim();
+
+            let pragma = match trimmed.strip_prefix("#!") {
+                // a shebang, e.g. `#!/usr/bin/env wu`
+                Some(pragma) if i == 0 && pragma.starts_with('/') => continue,
+                Some(pragma) => pragma.trim(),
+
+                None => {
+                    if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                        heading = false
+                    }
+
+                    continue;
+                }
+            };
+
+            let pos = Pos((i + 1, source.line(i + 1)), (1, line.chars().count()));
+
+            if !heading {
+                return Err(response!(
+                    Wrong("pragmas go before the first statement"),
+                    source.file,
+                    pos
+                ));
+            }
+
+            if pragma == "strict" {
+                pragmas.strict = true
+            } else if let Some(lints) = pragma
+                .strip_prefix("allow(")
+                .and_then(|lints| lints.strip_suffix(')'))
+            {
+                for name in lints.split(',').map(str::trim) {
+                    match Lint::named(name) {
+                        Some(lint) => pragmas.allowed.push(lint),
+                        None => {
+                            return Err(response!(
+                                Wrong(format!("no such lint `{}`", name)),
+                                source.file,
+                                pos,
+                                Note(format!("the lints are {}", Lint::names()))
+                            ));
+                        }
+                    }
+                }
+            } else {
+                return Err(response!(
+                    Wrong(format!("no such pragma `#!{}`", pragma)),
+                    source.file,
+                    pos,
+                    Note("expected `#!strict` or `#!allow(lint, ...)`")
+                ));
+            }
+        }
+
+        Ok(pragmas)
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use std::fmt;
+
+use colored::Colorize;
+
+#[derive(Debug)]
+pub struct FilePath(pub String);
+
+impl fmt::Display for FilePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n{:>8} {}", "-->".blue().bold(), self.0)
+    }
+}
+
+#[derive(Debug)]
+pub struct Source {
+    pub file: FilePath,
+    pub lines: Vec<String>,
+    // the same lines, shared by every token and position on them rather than copied into each
+    pub shared: Vec<Arc<str>>,
+}
+
+impl Source {
+    pub fn new(path: String) -> Self {
+        let mut source = File::open(path.as_str()).unwrap();
+        let mut content = String::new();
+
+        source.read_to_string(&mut content).unwrap();
+
+        Source::from(&path, content.lines().map(|x| x.to_string()).collect())
+    }
+
+    pub fn from(path: &str, lines: Vec<String>) -> Self {
+        Source {
+            file: FilePath(path.into()),
+            shared: lines.iter().map(|line| Arc::from(line.as_str())).collect(),
+            lines,
+        }
+    }
+
+    // the shared copy of line `number`, counting from one; past the end gives the last line
+    pub fn line(&self, number: usize) -> Arc<str> {
+        self.shared
+            .get(number.saturating_sub(1))
+            .or_else(|| self.shared.last())
+            .cloned()
+            .unwrap_or_else(|| Arc::from(""))
+    }
+}
+
+// lexically resolves `.` and `..` components, without touching the file system
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match result.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    result.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => (),
+                _ => result.push(".."),
+            },
+            component => result.push(component.as_os_str()),
+        }
+    }
+
+    result
+}
+
+// all .wu files below `path`, in a stable order
+pub fn sources(path: &str, found: &mut Vec<String>) {
+    let path = Path::new(path);
+
+    if path.is_dir() {
+        let mut entries = match fs::read_dir(path) {
+            Ok(entries) => entries.flatten().map(|entry| entry.path()).collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+
+        entries.sort();
+
+        for entry in entries {
+            sources(&entry.display().to_string(), found)
+        }
+    } else if path.extension().is_some_and(|extension| extension == "wu") {
+        found.push(path.display().to_string().replace("./", ""))
+    }
+}
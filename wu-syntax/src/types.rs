@@ -0,0 +1,674 @@
+// the types the parser reads from annotations and the checker infers, with how diagnostics show them
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::mem::discriminant;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::parser::{Expression, ExpressionNode};
+
+// a variant of an enum, with the types of what it carries
+pub type Variant = (String, Vec<Type>);
+
+#[derive(Debug, Clone)]
+pub enum TypeNode {
+    Int,
+    Float,
+    Bool,
+    Str,
+    Any,
+    Char,
+    Nil,
+    Id(Rc<Expression>),
+    Array(Rc<Type>, Option<usize>),
+    Func(Vec<Type>, Rc<Type>, Option<Rc<ExpressionNode>>, bool),
+    Module(Rc<HashMap<String, Type>>, bool), // is_foreign
+    Struct(String, Rc<HashMap<String, Type>>, String),
+    Trait(String, Rc<HashMap<String, Type>>),
+    Enum(String, Rc<Vec<Variant>>, String, bool), // the variants in the order declared, `@flags`
+    Optional(Rc<TypeNode>),
+    Tuple(Vec<Type>),
+    This,
+}
+
+impl TypeNode {
+    pub fn check_expression(&self, other: &ExpressionNode) -> bool {
+        use self::TypeNode::*;
+
+        match *other {
+            ExpressionNode::Int(_) => matches!(*self, Int | Float),
+
+            ExpressionNode::Array(ref content) => {
+                let array_content = if let &Array(ref array_content, ref len) = self {
+                    if let Some(len) = len {
+                        if *len != content.len() {
+                            return false;
+                        }
+                    }
+
+                    array_content
+                } else {
+                    return false;
+                };
+
+                for element in content {
+                    if !array_content.node.check_expression(&element.node) {
+                        return false;
+                    }
+                }
+
+                true
+            }
+
+            _ => false,
+        }
+    }
+
+    pub fn strong_cmp(&self, other: &TypeNode) -> bool {
+        use self::TypeNode::*;
+
+        match (self, other) {
+            (&Int, &Int) => true,
+            (&Float, &Float) => true,
+            (&Bool, &Bool) => true,
+            (&Str, &Str) => true,
+            (&Any, &Any) => true,
+            (&Char, &Char) => true,
+            (&This, &This) => true,
+            (&Nil, &Nil) => true,
+            (&Tuple(ref a), &Tuple(ref b)) => a == b,
+            (&Optional(ref a), &Optional(ref b)) => a == b,
+            (&Id(ref a), &Id(ref b)) => a == b,
+            (&Array(ref a, ref la), &Array(ref b, ref lb)) => a == b && (la.is_none() || la == lb),
+            (&Func(ref a_params, ref a_retty, .., a), &Func(ref b_params, ref b_retty, .., b)) => {
+                a_params == b_params && a_retty == b_retty && a == b
+            }
+            (&Struct(ref name, _, ref content), &Struct(ref name_b, _, ref content_b)) => {
+                name == name_b && content == content_b
+            }
+            (&Trait(ref name, ref content), &Trait(ref name_b, ref content_b)) => {
+                name == name_b && content == content_b
+            }
+            (&Enum(ref name, _, ref id, _), &Enum(ref name_b, _, ref id_b, _)) => {
+                name == name_b && id == id_b
+            }
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for TypeNode {
+    fn eq(&self, other: &Self) -> bool {
+        use self::TypeNode::*;
+
+        match (self, other) {
+            (&Any, _) => true,
+            (_, &Any) => true,
+            (&Optional(ref a), _) if matches!(**a, Any) => true,
+            (_, &Optional(ref b)) if matches!(**b, Any) => true,
+
+            (&Int, &Int) => true,
+            (&Str, &Str) => true,
+            (&Float, &Float) => true,
+            (&Char, &Char) => true,
+            (&Bool, &Bool) => true,
+            (&Nil, &Nil) => true,
+            (&This, &This) => true,
+            (&Tuple(ref a), &Tuple(ref b)) => a == b,
+            (&Array(ref a, ref la), &Array(ref b, ref lb)) => {
+                (Rc::ptr_eq(a, b) || a == b)
+                    && (la.is_none() || (matches!(a.node, Any) && lb.is_none()) || la == lb)
+            }
+            (&Id(ref a), &Id(ref b)) => a == b,
+            (&Func(ref a_params, ref a_retty, .., a), &Func(ref b_params, ref b_retty, .., b)) => {
+                a_params == b_params
+                    && (Rc::ptr_eq(a_retty, b_retty) || a_retty == b_retty)
+                    && a == b
+            }
+
+            (&Struct(ref name, _, ref content), &Struct(ref name_b, _, ref content_b)) => {
+                name == name_b && content == content_b
+            }
+            (&Trait(_, ref content), &Trait(_, ref content_b)) => {
+                Rc::ptr_eq(content, content_b) || content == content_b
+            }
+            (&Trait(_, ref content), &Struct(_, ref content_b, _)) => {
+                for (name, ty) in content.iter() {
+                    if let Some(ty_b) = content_b.get(name) {
+                        if ty.node != ty_b.node {
+                            return false;
+                        }
+                    } else {
+                        return false;
+                    }
+                }
+
+                true
+            }
+
+            (&Optional(_), &Nil) => true,
+            (&Nil, &Optional(_)) => true,
+            (&Optional(ref a), &Optional(ref b)) => Rc::ptr_eq(a, b) || a == b,
+            (&Optional(ref a), b) => **a == *b,
+            (_, Optional(_)) => false,
+
+            (&Struct(..), &Trait(..)) => other == self,
+
+            (&Enum(ref name, _, ref id, _), &Enum(ref name_b, _, ref id_b, _)) => {
+                name == name_b && id == id_b
+            }
+
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TypeMode {
+    Undeclared,
+    Immutable, // constants of an implementation
+    #[allow(dead_code)]
+    Optional,
+    Implemented,
+    Regular,
+    Splat(Option<usize>),
+    Unwrap(usize),
+}
+
+impl TypeMode {
+    pub fn strong_cmp(&self, other: &TypeMode) -> bool {
+        use self::TypeMode::*;
+
+        match (self, other) {
+            (&Regular, &Regular) => true,
+            (&Immutable, &Immutable) => true,
+            (&Optional, &Optional) => true,
+            (&Implemented, &Implemented) => true,
+            (&Undeclared, &Undeclared) => true,
+            (&Splat(a), &Splat(b)) => a == b,
+            (&Unwrap(_), &Unwrap(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl TypeNode {
+    // like `Display`, but the structs named in `qualify` say where they were defined,
+    // and types nested deeper than `depth` are left out as `...`
+    fn fmt_qualified(
+        &self,
+        f: &mut Formatter,
+        qualify: &HashSet<String>,
+        depth: usize,
+    ) -> fmt::Result {
+        use self::TypeNode::*;
+
+        let nested = |ty| Qualified {
+            ty,
+            qualify,
+            depth: depth.saturating_sub(1),
+        };
+
+        match *self {
+            Tuple(..) | Array(..) | Func(..) if depth == 0 => write!(f, "..."),
+
+            Int => write!(f, "int"),
+            Float => write!(f, "float"),
+            Bool => write!(f, "bool"),
+            Str => write!(f, "str"),
+            Char => write!(f, "char"),
+            Nil => write!(f, "nil"),
+            This => write!(f, "self"),
+            Any => write!(f, "any"),
+
+            Tuple(ref content) => {
+                write!(f, "(")?;
+
+                for (i, t) in content.iter().enumerate() {
+                    write!(f, "{}", nested(t))?;
+
+                    if i != content.len() - 1 {
+                        write!(f, ", ")?;
+                    }
+                }
+
+                write!(f, ")")?;
+                Ok(())
+            }
+
+            Trait(ref name, _) => write!(f, "{}", name),
+
+            Array(ref n, l) => {
+                if let Some(len) = l {
+                    write!(f, "[{}; {}]", nested(&**n), len)?;
+                } else {
+                    write!(f, "[{}]", nested(&**n))?;
+                }
+                Ok(())
+            }
+
+            Id(ref n) => write!(f, "deid({})", n.pos.get_lexeme()),
+
+            Module(..) => write!(f, "module"),
+            Struct(ref name, _, ref id) if qualify.contains(name) => {
+                write!(f, "{} ({})", name, id.trim_start_matches("./"))
+            }
+            Struct(ref name, ..) => write!(f, "{}", name),
+            Enum(ref name, _, ref id, _) if qualify.contains(name) => {
+                write!(f, "{} ({})", name, id.trim_start_matches("./"))
+            }
+            Enum(ref name, ..) => write!(f, "{}", name),
+
+            Func(ref params, ref return_type, ..) => {
+                write!(f, "fun(")?;
+
+                for (index, element) in params.iter().enumerate() {
+                    if index < params.len() - 1 {
+                        write!(f, "{}, ", nested(element))?;
+                    } else {
+                        write!(f, "{}", nested(element))?;
+                    }
+                }
+
+                write!(f, ") -> {}", nested(&**return_type))?;
+                Ok(())
+            }
+
+            Optional(ref inner) => {
+                inner.fmt_qualified(f, qualify, depth)?;
+                write!(f, "?")
+            }
+        }
+    }
+}
+
+impl Display for TypeNode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.fmt_qualified(f, &HashSet::new(), usize::MAX)
+    }
+}
+
+// diagnostics leave out types nested deeper than this, unless `--verbose-types`
+const TYPE_DEPTH: usize = 3;
+
+static FULL_TYPES: AtomicBool = AtomicBool::new(false);
+
+pub fn show_full_types() {
+    FULL_TYPES.store(true, Ordering::Relaxed)
+}
+
+// a type for a diagnostic, where some struct names need telling apart
+pub struct Qualified<'t, T> {
+    ty: &'t T,
+    qualify: &'t HashSet<String>,
+    depth: usize,
+}
+
+impl<'t, T> Qualified<'t, T> {
+    pub fn new(ty: &'t T, qualify: &'t HashSet<String>) -> Self {
+        let depth = if FULL_TYPES.load(Ordering::Relaxed) {
+            usize::MAX
+        } else {
+            TYPE_DEPTH
+        };
+
+        Qualified { ty, qualify, depth }
+    }
+}
+
+impl Display for Qualified<'_, TypeNode> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.ty.fmt_qualified(f, self.qualify, self.depth)
+    }
+}
+
+impl Display for Qualified<'_, Type> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.ty.mode)?;
+        self.ty.node.fmt_qualified(f, self.qualify, self.depth)
+    }
+}
+
+impl AsRef<TypeNode> for TypeNode {
+    fn as_ref(&self) -> &TypeNode {
+        self
+    }
+}
+
+impl AsRef<TypeNode> for Type {
+    fn as_ref(&self) -> &TypeNode {
+        &self.node
+    }
+}
+
+fn struct_ids(node: &TypeNode, ids: &mut HashMap<String, HashSet<String>>) {
+    use self::TypeNode::*;
+
+    match *node {
+        Struct(ref name, _, ref id) | Enum(ref name, _, ref id, _) => {
+            ids.entry(name.clone()).or_default().insert(id.clone());
+        }
+
+        Optional(ref inner) => struct_ids(inner, ids),
+        Array(ref inner, _) => struct_ids(&inner.node, ids),
+        Tuple(ref content) => content.iter().for_each(|t| struct_ids(&t.node, ids)),
+        Func(ref params, ref return_type, ..) => {
+            params.iter().for_each(|t| struct_ids(&t.node, ids));
+            struct_ids(&return_type.node, ids)
+        }
+
+        _ => (),
+    }
+}
+
+// renders two types for a message comparing them, saying where a struct was defined when two
+// distinct ones share a name, e.g. `expected `Enemy (enemy.wu:1:8)` got `Enemy (boss.wu:3:8)``
+pub fn disambiguate<A, B>(a: &A, b: &B) -> (String, String)
+where
+    A: AsRef<TypeNode>,
+    B: AsRef<TypeNode>,
+    for<'t> Qualified<'t, A>: Display,
+    for<'t> Qualified<'t, B>: Display,
+{
+    let mut ids = HashMap::new();
+
+    struct_ids(a.as_ref(), &mut ids);
+    struct_ids(b.as_ref(), &mut ids);
+
+    let qualify = ids
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(name, _)| name)
+        .collect();
+
+    (
+        Qualified::new(a, &qualify).to_string(),
+        Qualified::new(b, &qualify).to_string(),
+    )
+}
+
+impl PartialEq for TypeMode {
+    fn eq(&self, other: &TypeMode) -> bool {
+        use self::TypeMode::*;
+
+        match (self, other) {
+            (&Regular, &Regular) => true,
+            (&Regular, &Immutable) => true,
+            (&Immutable, &Immutable) => true,
+            (&Immutable, &Regular) => true,
+            (_, &Optional) => true,
+            (&Optional, _) => true,
+            (&Undeclared, _) => false,
+            (_, &Undeclared) => false,
+            (&Splat(a), &Splat(b)) => (a.is_none() || b.is_none()) || a <= b,
+            (&Unwrap(_), _) => true,
+            (_, &Unwrap(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Display for TypeMode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        use self::TypeMode::*;
+
+        match *self {
+            Regular => Ok(()),
+            Immutable => write!(f, "constant "),
+            Undeclared => write!(f, "undeclared "),
+            Optional => write!(f, "optional? "),
+            Implemented => Ok(()),
+            Splat(_count) => write!(f, "..."),
+            Unwrap(_) => write!(f, "*"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type {
+    pub node: TypeNode,
+    pub mode: TypeMode,
+}
+
+impl Type {
+    pub fn new(node: TypeNode, mode: TypeMode) -> Self {
+        Self { node, mode }
+    }
+
+    pub fn is_method(&self) -> bool {
+        if let TypeNode::Func(.., is_method) = self.node {
+            return is_method;
+        }
+
+        false
+    }
+
+    // whether this is `self` or `Self`, optional or not, which only an implementation can tell
+    pub fn is_self(&self) -> bool {
+        let mut node = &self.node;
+
+        while let TypeNode::Optional(ref inner) = *node {
+            node = inner
+        }
+
+        match *node {
+            TypeNode::This => true,
+            TypeNode::Id(ref id) => {
+                matches!(id.node, ExpressionNode::Identifier(ref name) if name == "Self")
+            }
+            _ => false,
+        }
+    }
+
+    pub fn id(id: Rc<Expression>) -> Self {
+        Type::new(TypeNode::Id(id), TypeMode::Regular)
+    }
+
+    pub fn from(node: TypeNode) -> Type {
+        Type::new(node, TypeMode::Regular)
+    }
+
+    pub fn tuple(t: Vec<Type>) -> Type {
+        Type::new(TypeNode::Tuple(t), TypeMode::Regular)
+    }
+
+    pub fn array(t: Type, len: Option<usize>) -> Type {
+        Type::new(TypeNode::Array(Rc::new(t), len), TypeMode::Regular)
+    }
+
+    pub fn function(params: Vec<Type>, return_type: Type, is_method: bool) -> Self {
+        Type::new(
+            TypeNode::Func(params, Rc::new(return_type), None, is_method),
+            TypeMode::Regular,
+        )
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.mode, self.node)
+    }
+}
+
+// hash-consing for resolved types: structurally identical types come back as the same shared
+// handle, and the types inside them are shared too, so comparing them stops at a pointer check.
+// Only weak handles are kept, a type nobody holds anymore is dropped from the table
+#[derive(Default)]
+pub struct Interner {
+    types: HashMap<u64, Vec<Weak<Type>>>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, t: Type) -> Rc<Type> {
+        let t = self.share(t);
+
+        let mut hasher = DefaultHasher::new();
+        shape(&t, &mut hasher);
+
+        let bucket = self.types.entry(hasher.finish()).or_default();
+        bucket.retain(|handle| handle.strong_count() > 0);
+
+        for handle in bucket.iter() {
+            if let Some(handle) = handle.upgrade() {
+                if identical(&handle, &t) {
+                    return handle;
+                }
+            }
+        }
+
+        let handle = Rc::new(t);
+        bucket.push(Rc::downgrade(&handle));
+
+        handle
+    }
+
+    // interns the types `t` is made of
+    fn share(&mut self, t: Type) -> Type {
+        let Type { node, mode } = t;
+
+        Type::new(self.share_node(node), mode)
+    }
+
+    fn share_node(&mut self, node: TypeNode) -> TypeNode {
+        use self::TypeNode::*;
+
+        match node {
+            Array(content, len) => Array(self.intern(unshare(content)), len),
+
+            Func(params, return_type, body, is_method) => Func(
+                params.into_iter().map(|param| self.share(param)).collect(),
+                self.intern(unshare(return_type)),
+                body,
+                is_method,
+            ),
+
+            Optional(inner) => {
+                let inner = Rc::try_unwrap(inner).unwrap_or_else(|inner| (*inner).clone());
+
+                Optional(Rc::new(self.share_node(inner)))
+            }
+
+            Tuple(content) => Tuple(content.into_iter().map(|t| self.share(t)).collect()),
+
+            node => node,
+        }
+    }
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+// the shared handle for `t`, see `Interner`
+pub fn intern(t: Type) -> Rc<Type> {
+    INTERNER.with(|interner| interner.borrow_mut().intern(t))
+}
+
+fn unshare(t: Rc<Type>) -> Type {
+    Rc::try_unwrap(t).unwrap_or_else(|t| (*t).clone())
+}
+
+// hashes what `identical` compares; names, members and expressions count by who they are
+fn shape<H: Hasher>(t: &Type, state: &mut H) {
+    use self::TypeMode::*;
+
+    discriminant(&t.mode).hash(state);
+
+    match t.mode {
+        Splat(count) => count.hash(state),
+        Unwrap(count) => count.hash(state),
+        _ => (),
+    }
+
+    shape_node(&t.node, state)
+}
+
+fn shape_node<H: Hasher>(node: &TypeNode, state: &mut H) {
+    use self::TypeNode::*;
+
+    discriminant(node).hash(state);
+
+    match *node {
+        Id(ref expression) => Rc::as_ptr(expression).hash(state),
+        Array(ref content, len) => {
+            shape(content, state);
+            len.hash(state)
+        }
+        Func(ref params, ref return_type, ref body, is_method) => {
+            params.iter().for_each(|param| shape(param, state));
+            shape(return_type, state);
+            body.as_ref().map(Rc::as_ptr).hash(state);
+            is_method.hash(state)
+        }
+        Module(ref content, is_foreign) => {
+            Rc::as_ptr(content).hash(state);
+            is_foreign.hash(state)
+        }
+        Struct(ref name, ref content, ref id) => {
+            name.hash(state);
+            Rc::as_ptr(content).hash(state);
+            id.hash(state)
+        }
+        Trait(ref name, ref content) => {
+            name.hash(state);
+            Rc::as_ptr(content).hash(state)
+        }
+        Enum(ref name, _, ref id, _) => {
+            name.hash(state);
+            id.hash(state)
+        }
+        Optional(ref inner) => shape_node(inner, state),
+        Tuple(ref content) => content.iter().for_each(|t| shape(t, state)),
+        _ => (),
+    }
+}
+
+// exact sameness, unlike `==` which asks whether one type fits where another is expected
+fn identical(a: &Type, b: &Type) -> bool {
+    use self::TypeMode::*;
+
+    let same_mode = match (&a.mode, &b.mode) {
+        (Splat(a), Splat(b)) => a == b,
+        (Unwrap(a), Unwrap(b)) => a == b,
+        (a, b) => discriminant(a) == discriminant(b),
+    };
+
+    same_mode && identical_node(&a.node, &b.node)
+}
+
+fn identical_node(a: &TypeNode, b: &TypeNode) -> bool {
+    use self::TypeNode::*;
+
+    let all = |a: &[Type], b: &[Type]| {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| identical(a, b))
+    };
+
+    match (a, b) {
+        (Id(a), Id(b)) => Rc::ptr_eq(a, b),
+        (Array(a, la), Array(b, lb)) => (Rc::ptr_eq(a, b) || identical(a, b)) && la == lb,
+        (Func(a_params, a_retty, a_body, a), Func(b_params, b_retty, b_body, b)) => {
+            all(a_params, b_params)
+                && (Rc::ptr_eq(a_retty, b_retty) || identical(a_retty, b_retty))
+                && match (a_body, b_body) {
+                    (Some(a_body), Some(b_body)) => Rc::ptr_eq(a_body, b_body),
+                    (None, None) => true,
+                    _ => false,
+                }
+                && a == b
+        }
+        (Module(a, a_foreign), Module(b, b_foreign)) => Rc::ptr_eq(a, b) && a_foreign == b_foreign,
+        (Struct(a_name, a, a_id), Struct(b_name, b, b_id)) => {
+            a_name == b_name && Rc::ptr_eq(a, b) && a_id == b_id
+        }
+        (Trait(a_name, a), Trait(b_name, b)) => a_name == b_name && Rc::ptr_eq(a, b),
+        (Enum(a_name, a, a_id, _), Enum(b_name, b, b_id, _)) => {
+            a_name == b_name && Rc::ptr_eq(a, b) && a_id == b_id
+        }
+        (Optional(a), Optional(b)) => identical_node(a, b),
+        (Tuple(a), Tuple(b)) => all(a, b),
+        (a, b) => discriminant(a) == discriminant(b),
+    }
+}
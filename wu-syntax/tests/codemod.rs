@@ -0,0 +1,94 @@
+// rewriting files through patches over their lossless tokens, keeping everything else as it was.
+
+use wu_syntax::codemod::{apply, rewrite, Codemod, Patch};
+use wu_syntax::lexer::{SyntaxTokens, TokenType};
+use wu_syntax::source::Source;
+
+fn lex(content: &str) -> SyntaxTokens {
+    let source = Source::from("test.wu", content.lines().map(String::from).collect());
+
+    SyntaxTokens::lex(content.chars().collect(), &source).unwrap()
+}
+
+// `a` to `b`, wherever it's a name
+struct Rename;
+
+impl Codemod for Rename {
+    fn name(&self) -> &str {
+        "rename"
+    }
+
+    fn description(&self) -> &str {
+        "renames `a` to `b`"
+    }
+
+    fn visit(&mut self, tokens: &SyntaxTokens, at: usize) -> Vec<Patch> {
+        let token = &tokens.tokens[at].token;
+
+        if token.token_type == TokenType::Identifier && token.lexeme == "a" {
+            vec![Patch::replace(at, "b")]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[test]
+fn keeps_trivia() {
+    let content = "# first\na := 1  # one\n\nc := a   + a\n";
+    let (text, patches) = rewrite(&lex(content), &mut Rename);
+
+    assert_eq!(patches, 3);
+    assert_eq!(text, "# first\nb := 1  # one\n\nc := b   + b\n");
+}
+
+#[test]
+fn ranges_and_insertions() {
+    let content = "x := f(1,  2) # call\n";
+    let tokens = lex(content);
+
+    let at = |text: &str| {
+        tokens
+            .tokens
+            .iter()
+            .position(|token| token.text == text)
+            .unwrap()
+    };
+
+    // `(1,  2)` as one, then a name before `f`
+    let patches = [
+        Patch::insert(at("f"), "g |> "),
+        Patch::new(at("(")..at(")") + 1, "(2, 1)"),
+    ];
+
+    assert_eq!(apply(&tokens, &patches), "x := g |> f(2, 1) # call\n");
+    assert_eq!(apply(&tokens, &[]), content);
+}
+
+#[test]
+fn overlapping_patches() {
+    struct Twice;
+
+    impl Codemod for Twice {
+        fn name(&self) -> &str {
+            "twice"
+        }
+
+        fn description(&self) -> &str {
+            "patches `x` twice"
+        }
+
+        fn visit(&mut self, tokens: &SyntaxTokens, at: usize) -> Vec<Patch> {
+            if tokens.tokens[at].text == "x" {
+                vec![Patch::replace(at, "y"), Patch::replace(at, "z")]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    let (text, patches) = rewrite(&lex("x := 1\n"), &mut Twice);
+
+    assert_eq!(patches, 1);
+    assert_eq!(text, "y := 1\n");
+}
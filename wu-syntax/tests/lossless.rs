@@ -0,0 +1,84 @@
+// lexing that keeps whitespace and comments, so a file can be put back together byte for byte.
+
+use std::fs;
+use std::path::Path;
+
+use wu_syntax::error::silently;
+use wu_syntax::lexer::{Lexer, SyntaxTokens, Trivia};
+use wu_syntax::source::Source;
+
+fn lex(content: &str) -> Result<SyntaxTokens, ()> {
+    let source = Source::from("test.wu", content.lines().map(String::from).collect());
+
+    SyntaxTokens::lex(content.chars().collect(), &source)
+}
+
+#[test]
+fn trivia() {
+    let content = "# adds\nadd := fun(a: int,  b: int) -> int {\n\ta + b # sum\n}\n";
+    let tokens = lex(content).unwrap();
+
+    assert_eq!(tokens.to_string(), content);
+
+    let b = tokens
+        .tokens
+        .iter()
+        .find(|token| token.text == "b")
+        .unwrap();
+
+    assert_eq!(b.leading, vec![Trivia::Whitespace("  ".into())]);
+
+    let comments = tokens
+        .tokens
+        .iter()
+        .flat_map(|token| token.leading.iter())
+        .filter(|trivia| matches!(trivia, Trivia::Comment(..)))
+        .map(Trivia::text)
+        .collect::<Vec<_>>();
+
+    assert_eq!(comments, vec!["# adds", "# sum"]);
+}
+
+#[test]
+fn literals_as_written() {
+    let content = "s := \"a\\tb \\\\ c\"\nc := 'x'\nf := 1.50\n";
+    let tokens = lex(content).unwrap();
+
+    assert_eq!(tokens.to_string(), content);
+    assert!(tokens
+        .tokens
+        .iter()
+        .any(|token| token.text == "\"a\\tb \\\\ c\""));
+}
+
+// every fixture lexes back to itself, and to what the parser reads
+#[test]
+fn golden_round_trip() {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/golden");
+
+    for entry in fs::read_dir(golden).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension().is_none_or(|extension| extension != "wu") {
+            continue;
+        }
+
+        let content = fs::read_to_string(&path).unwrap();
+        let source = Source::from("test.wu", content.lines().map(String::from).collect());
+
+        let expected = silently(|| {
+            Lexer::default(content.chars().collect(), &source).collect::<Result<Vec<_>, _>>()
+        });
+
+        let tokens = match silently(|| lex(&content)) {
+            Ok(tokens) => tokens,
+            Err(()) => {
+                assert!(expected.is_err(), "{}", path.display());
+                continue;
+            }
+        };
+
+        assert_eq!(tokens.to_string(), content, "{}", path.display());
+        assert_eq!(Ok(tokens.parser_tokens()), expected, "{}", path.display());
+    }
+}
@@ -0,0 +1,141 @@
+// The syntax crate on its own, as a formatter or linter would use it: source text in, syntax
+// tree out, nothing checked or compiled.
+
+use wu_syntax::error::silently;
+use wu_syntax::lexer::Lexer;
+use wu_syntax::parser::{ExpressionNode, Lint, Parser, Pragmas, Statement, StatementNode};
+use wu_syntax::source::Source;
+use wu_syntax::types::TypeNode;
+
+fn parse(content: &str) -> Result<Vec<Statement>, ()> {
+    let source = Source::from("test.wu", content.lines().map(String::from).collect());
+
+    let tokens =
+        Lexer::default(content.chars().collect(), &source).collect::<Result<Vec<_>, _>>()?;
+
+    Parser::new(tokens, &source).parse()
+}
+
+#[test]
+fn declarations() {
+    let ast = parse("add: fun(a: int, b: int) -> int {\n  a + b\n}\n\nx: float = 1.5\n").unwrap();
+
+    assert_eq!(ast.len(), 2);
+
+    match ast[0].node {
+        StatementNode::Variable(_, ref name, Some(ref right), _) => {
+            assert_eq!(name, "add");
+            assert!(matches!(right.node, ExpressionNode::Function(..)))
+        }
+
+        ref other => panic!("expected a function, got {:?}", other),
+    }
+
+    match ast[1].node {
+        StatementNode::Variable(ref ty, ref name, _, _) => {
+            assert_eq!(name, "x");
+            assert!(matches!(ty.node, TypeNode::Float))
+        }
+
+        ref other => panic!("expected a variable, got {:?}", other),
+    }
+}
+
+#[test]
+fn syntax_errors() {
+    assert!(silently(|| parse("x := (1 +\n")).is_err())
+}
+
+#[test]
+fn trait_members_by_line() {
+    let ast =
+        parse("Shape: trait {\n  area: fun(self) -> float\n  name: fun(self) -> str\n}\n").unwrap();
+
+    match ast[0].node {
+        StatementNode::Variable(_, _, Some(ref right), _) => match right.node {
+            ExpressionNode::Trait(_, ref members, _) => {
+                let names = members.iter().map(|member| &member.0).collect::<Vec<_>>();
+
+                assert_eq!(names, ["area", "name"])
+            }
+
+            ref other => panic!("expected a trait, got {:?}", other),
+        },
+
+        ref other => panic!("expected a variable, got {:?}", other),
+    }
+}
+
+#[test]
+fn trait_default_methods() {
+    let ast = parse(
+        "Shape: trait {\n  area: fun(self) -> float\n  name: fun(self) -> str { \"shape\" }\n}\n",
+    )
+    .unwrap();
+
+    match ast[0].node {
+        StatementNode::Variable(_, _, Some(ref right), _) => match right.node {
+            ExpressionNode::Trait(_, ref members, ref defaults) => {
+                let names = members.iter().map(|member| &member.0).collect::<Vec<_>>();
+
+                assert_eq!(names, ["area", "name"]);
+                assert_eq!(defaults.len(), 1);
+                assert_eq!(defaults[0].0, "name");
+            }
+
+            ref other => panic!("expected a trait, got {:?}", other),
+        },
+
+        ref other => panic!("expected a variable, got {:?}", other),
+    }
+}
+
+#[test]
+fn pragmas_head_the_module() {
+    let source = Source::from(
+        "test.wu",
+        vec![
+            "#!strict".into(),
+            "# comment".into(),
+            "#!allow(conversions)".into(),
+            "x := 1".into(),
+        ],
+    );
+
+    let pragmas = Pragmas::read(&source).unwrap();
+
+    assert!(pragmas.strict);
+    assert!(pragmas.denies(Lint::ImplicitAny));
+    assert!(!pragmas.denies(Lint::Conversions));
+
+    let late = Source::from("test.wu", vec!["x := 1".into(), "#!strict".into()]);
+
+    assert!(silently(|| Pragmas::read(&late)).is_err())
+}
+
+#[test]
+fn trailing_commas() {
+    let sources = [
+        "xs := [\n  1,\n  2,\n]\n",
+        "t := (\n  1,\n  2,\n)\n",
+        "t: (int, str,) = (1, \"a\")\n",
+        "t: (\n  int,\n  str,\n) = (1, \"a\")\n",
+        "f: fun(a: int, b: int,) -> int { a + b }\ny := f(\n  1,\n  2,\n)\n",
+        "f: fun(g: fun(\n  int,\n  int,\n) -> int) -> int { g(1, 2) }\n",
+        "P: struct {\n  x: int,\n  y: int,\n}\np := new P {\n  x: 1,\n  y: 2,\n}\n",
+        "import shapes {\n  area,\n  Point,\n}\n",
+    ];
+
+    for source in sources.iter() {
+        assert!(parse(source).is_ok(), "failed to parse:\n{}", source)
+    }
+
+    match parse("t: (int, str,) = (1, \"a\")\n").unwrap()[0].node {
+        StatementNode::Variable(ref ty, ..) => match ty.node {
+            TypeNode::Tuple(ref content) => assert_eq!(content.len(), 2),
+            ref other => panic!("expected a tuple type, got {:?}", other),
+        },
+
+        ref other => panic!("expected a variable, got {:?}", other),
+    }
+}